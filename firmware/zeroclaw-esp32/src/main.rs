@@ -89,10 +89,35 @@ fn handle_request(
 
     let result = match req.cmd.as_str() {
         "capabilities" => {
-            // Phase C: report GPIO pins and LED pin (matches Arduino protocol)
+            // Phase C: report GPIO pins and LED pin (matches Arduino protocol).
+            // `commands` lets the host build tools without per-board code.
             let caps = serde_json::json!({
                 "gpio": [0, 1, 2, 3, 4, 5, 12, 13, 14, 15, 16, 17, 18, 19],
-                "led_pin": 2
+                "led_pin": 2,
+                "commands": [
+                    {
+                        "name": "gpio_read",
+                        "description": "Read the value (0 or 1) of an ESP32 GPIO pin",
+                        "read_only": true,
+                        "parameters": {
+                            "type": "object",
+                            "properties": { "pin": { "type": "integer" } },
+                            "required": ["pin"]
+                        }
+                    },
+                    {
+                        "name": "gpio_write",
+                        "description": "Set an ESP32 GPIO pin high (1) or low (0); pin 2 is the LED",
+                        "parameters": {
+                            "type": "object",
+                            "properties": {
+                                "pin": { "type": "integer" },
+                                "value": { "type": "integer", "enum": [0, 1] }
+                            },
+                            "required": ["pin", "value"]
+                        }
+                    }
+                ]
             });
             Ok(caps.to_string())
         }
@@ -126,7 +151,10 @@ fn handle_request(
     }
 }
 
-fn gpio_read(_peripherals: &esp_idf_svc::hal::peripherals::Peripherals, _pin: i32) -> anyhow::Result<u8> {
+fn gpio_read(
+    _peripherals: &esp_idf_svc::hal::peripherals::Peripherals,
+    _pin: i32,
+) -> anyhow::Result<u8> {
     // TODO: implement input pin read — requires storing InputPin drivers per pin
     Ok(0)
 }
@@ -1,3 +1,4 @@
+use crate::agent::context::{self, ContextBudget};
 use crate::agent::dispatcher::{
     NativeToolDispatcher, ParsedToolCall, ToolDispatcher, ToolExecutionResult, XmlToolDispatcher,
 };
@@ -26,12 +27,14 @@ pub struct Agent {
     tool_dispatcher: Box<dyn ToolDispatcher>,
     memory_loader: Box<dyn MemoryLoader>,
     config: crate::config::AgentConfig,
+    provider_name: String,
     model_name: String,
     temperature: f64,
     workspace_dir: std::path::PathBuf,
     identity_config: crate::config::IdentityConfig,
     skills: Vec<crate::skills::Skill>,
     auto_save: bool,
+    context_budget: ContextBudget,
     history: Vec<ConversationMessage>,
 }
 
@@ -44,6 +47,7 @@ pub struct AgentBuilder {
     tool_dispatcher: Option<Box<dyn ToolDispatcher>>,
     memory_loader: Option<Box<dyn MemoryLoader>>,
     config: Option<crate::config::AgentConfig>,
+    provider_name: Option<String>,
     model_name: Option<String>,
     temperature: Option<f64>,
     workspace_dir: Option<std::path::PathBuf>,
@@ -63,6 +67,7 @@ impl AgentBuilder {
             tool_dispatcher: None,
             memory_loader: None,
             config: None,
+            provider_name: None,
             model_name: None,
             temperature: None,
             workspace_dir: None,
@@ -112,6 +117,11 @@ impl AgentBuilder {
         self
     }
 
+    pub fn provider_name(mut self, provider_name: String) -> Self {
+        self.provider_name = Some(provider_name);
+        self
+    }
+
     pub fn model_name(mut self, model_name: String) -> Self {
         self.model_name = Some(model_name);
         self
@@ -147,6 +157,16 @@ impl AgentBuilder {
             .tools
             .ok_or_else(|| anyhow::anyhow!("tools are required"))?;
         let tool_specs = tools.iter().map(|tool| tool.spec()).collect();
+        let config = self.config.unwrap_or_default();
+        let provider_name = self.provider_name.unwrap_or_else(|| "unknown".into());
+        let model_name = self
+            .model_name
+            .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
+        let workspace_dir = self
+            .workspace_dir
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        let context_budget =
+            ContextBudget::resolve(&config, &workspace_dir, &provider_name, &model_name);
 
         Ok(Agent {
             provider: self
@@ -169,17 +189,15 @@ impl AgentBuilder {
             memory_loader: self
                 .memory_loader
                 .unwrap_or_else(|| Box::new(DefaultMemoryLoader::default())),
-            config: self.config.unwrap_or_default(),
-            model_name: self
-                .model_name
-                .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into()),
+            config,
+            provider_name,
+            model_name,
             temperature: self.temperature.unwrap_or(0.7),
-            workspace_dir: self
-                .workspace_dir
-                .unwrap_or_else(|| std::path::PathBuf::from(".")),
+            workspace_dir,
             identity_config: self.identity_config.unwrap_or_default(),
             skills: self.skills.unwrap_or_default(),
             auto_save: self.auto_save.unwrap_or(false),
+            context_budget,
             history: Vec::new(),
         })
     }
@@ -200,7 +218,7 @@ impl Agent {
 
     pub fn from_config(config: &Config) -> Result<Self> {
         let observer: Arc<dyn Observer> =
-            Arc::from(observability::create_observer(&config.observability));
+            observability::create_shared_observer(&config.observability);
         let runtime: Arc<dyn runtime::RuntimeAdapter> =
             Arc::from(runtime::create_runtime(&config.runtime)?);
         let security = Arc::new(SecurityPolicy::from_config(
@@ -247,12 +265,11 @@ impl Agent {
             .unwrap_or("anthropic/claude-sonnet-4-20250514")
             .to_string();
 
-        let provider: Box<dyn Provider> = providers::create_routed_provider(
+        let provider: Box<dyn Provider> = providers::create_auto_routed_provider(
+            config,
             provider_name,
-            config.api_key.as_deref(),
-            &config.reliability,
-            &config.model_routes,
             &model_name,
+            observer.clone(),
         )?;
 
         let dispatcher_choice = config.agent.tool_dispatcher.as_str();
@@ -272,6 +289,7 @@ impl Agent {
             .memory_loader(Box::new(DefaultMemoryLoader::default()))
             .prompt_builder(SystemPromptBuilder::with_defaults())
             .config(config.agent.clone())
+            .provider_name(provider_name.to_string())
             .model_name(model_name)
             .temperature(config.default_temperature)
             .workspace_dir(config.workspace_dir.clone())
@@ -322,10 +340,13 @@ impl Agent {
     }
 
     async fn execute_tool_call(&self, call: &ParsedToolCall) -> ToolExecutionResult {
+        self.observer.record_event(&ObserverEvent::ToolCallStart {
+            tool: call.name.clone(),
+        });
         let start = Instant::now();
 
         let result = if let Some(tool) = self.tools.iter().find(|t| t.name() == call.name) {
-            match tool.execute(call.arguments.clone()).await {
+            match tools::execute_validated(tool.as_ref(), call.arguments.clone()).await {
                 Ok(r) => {
                     self.observer.record_event(&ObserverEvent::ToolCall {
                         tool: call.name.clone(),
@@ -350,6 +371,14 @@ impl Agent {
         } else {
             format!("Unknown tool: {}", call.name)
         };
+        let result = context::condense_tool_output(
+            self.provider.as_ref(),
+            &self.model_name,
+            &call.name,
+            &result,
+            &self.context_budget,
+        )
+        .await;
 
         ToolExecutionResult {
             name: call.name.clone(),
@@ -397,6 +426,7 @@ impl Agent {
             .await
             .unwrap_or_default();
 
+        let context = self.context_budget.fit_memory_context(context);
         let enriched = if context.is_empty() {
             user_message.to_string()
         } else {
@@ -407,7 +437,14 @@ impl Agent {
             .push(ConversationMessage::Chat(ChatMessage::user(enriched)));
 
         for _ in 0..self.config.max_tool_iterations {
-            let messages = self.tool_dispatcher.to_provider_messages(&self.history);
+            let mut messages = self.tool_dispatcher.to_provider_messages(&self.history);
+            context::fit_history(&mut messages, &self.context_budget);
+            self.observer.record_event(&ObserverEvent::LlmRequest {
+                provider: self.provider_name.clone(),
+                model: self.model_name.clone(),
+                messages_count: messages.len(),
+            });
+            let llm_started_at = Instant::now();
             let response = match self
                 .provider
                 .chat(
@@ -424,8 +461,26 @@ impl Agent {
                 )
                 .await
             {
-                Ok(resp) => resp,
-                Err(err) => return Err(err),
+                Ok(resp) => {
+                    self.observer.record_event(&ObserverEvent::LlmResponse {
+                        provider: self.provider_name.clone(),
+                        model: self.model_name.clone(),
+                        duration: llm_started_at.elapsed(),
+                        success: true,
+                        error_message: None,
+                    });
+                    resp
+                }
+                Err(err) => {
+                    self.observer.record_event(&ObserverEvent::LlmResponse {
+                        provider: self.provider_name.clone(),
+                        model: self.model_name.clone(),
+                        duration: llm_started_at.elapsed(),
+                        success: false,
+                        error_message: Some(providers::sanitize_api_error(&err.to_string())),
+                    });
+                    return Err(err);
+                }
             };
 
             let (text, calls) = self.tool_dispatcher.parse_response(&response);
@@ -450,6 +505,7 @@ impl Agent {
                         .await;
                 }
 
+                self.observer.record_event(&ObserverEvent::TurnComplete);
                 return Ok(final_text);
             }
 
@@ -594,6 +650,7 @@ mod tests {
                 return Ok(crate::providers::ChatResponse {
                     text: Some("done".into()),
                     tool_calls: vec![],
+                    usage: None,
                 });
             }
             Ok(guard.remove(0))
@@ -631,6 +688,7 @@ mod tests {
             responses: Mutex::new(vec![crate::providers::ChatResponse {
                 text: Some("hello".into()),
                 tool_calls: vec![],
+                usage: None,
             }]),
         });
 
@@ -668,10 +726,12 @@ mod tests {
                         name: "echo".into(),
                         arguments: "{}".into(),
                     }],
+                    usage: None,
                 },
                 crate::providers::ChatResponse {
                     text: Some("done".into()),
                     tool_calls: vec![],
+                    usage: None,
                 },
             ]),
         });
@@ -0,0 +1,378 @@
+//! Token-aware context management: a cheap token estimator, per-model
+//! context windows and the budget that keeps a request inside them.
+//!
+//! A [`ContextBudget`] is carved out of the model's context window in this
+//! order: a reserve for the reply, the system prompt (never trimmed), capped
+//! memory/RAG context, capped individual tool results, and whatever is left
+//! for conversation history.
+
+use crate::config::AgentConfig;
+use crate::providers::{ChatMessage, Provider};
+use std::path::Path;
+
+/// Context window assumed for models nobody told us about.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 32_768;
+
+const SUMMARIZER_SYSTEM_PROMPT: &str =
+    "You compress tool output for an AI agent. Reply with the condensed output only.";
+
+/// Fixed per-message cost for role markers and separators.
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Known context windows by model-name fragment. First match wins, so more
+/// specific fragments come before their prefixes.
+const KNOWN_WINDOWS: &[(&str, usize)] = &[
+    ("claude", 200_000),
+    ("gemini", 1_048_576),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-5", 400_000),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("grok", 131_072),
+    ("deepseek", 64_000),
+    ("minimax", 204_800),
+    ("llama-3.1", 128_000),
+    ("llama-3.2", 128_000),
+    ("llama-3.3", 128_000),
+    ("llama-3", 8_192),
+    ("llama3.1", 128_000),
+    ("llama3.2", 128_000),
+    ("llama3.3", 128_000),
+    ("llama3", 8_192),
+    ("qwen", 32_768),
+    ("mixtral", 32_768),
+    ("mistral", 32_768),
+];
+
+/// Rough token count for `text`: about four bytes per token, which holds
+/// for English and code and over-counts (safely) for most other scripts.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Estimated tokens for one chat message, including framing overhead.
+pub fn message_tokens(message: &ChatMessage) -> usize {
+    estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Estimated tokens for a whole request.
+pub fn estimate_messages(messages: &[ChatMessage]) -> usize {
+    messages.iter().map(message_tokens).sum()
+}
+
+/// Built-in context window for `model`, ignoring any `vendor/` prefix. The
+/// longest matching entry wins, so `llama3.1` is not read as `llama3`.
+pub fn default_context_window(model: &str) -> usize {
+    let model = model.to_ascii_lowercase();
+    let name = model
+        .rsplit_once('/')
+        .map_or(model.as_str(), |(_, name)| name);
+    KNOWN_WINDOWS
+        .iter()
+        .filter(|(fragment, _)| {
+            name.starts_with(fragment) || name.contains(&format!("-{fragment}"))
+        })
+        .max_by_key(|(fragment, _)| fragment.len())
+        .map_or(DEFAULT_CONTEXT_WINDOW, |(_, window)| *window)
+}
+
+/// Context window for `model`: `[agent] context_window` if set, else the
+/// window recorded by `zeroclaw models refresh`, else the built-in table.
+pub fn context_window(
+    agent: &AgentConfig,
+    workspace_dir: &Path,
+    provider_name: &str,
+    model: &str,
+) -> usize {
+    agent
+        .context_window
+        .or_else(|| crate::onboard::cached_context_window(workspace_dir, provider_name, model))
+        .unwrap_or_else(|| default_context_window(model))
+}
+
+/// Token allowances for one model, derived from its context window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextBudget {
+    /// Total context window of the model, in tokens.
+    pub window: usize,
+    /// Held back for the model's reply.
+    pub output_reserve: usize,
+    /// Cap for recalled memory and RAG context prepended to a user message.
+    pub memory: usize,
+    /// Cap for a single tool result before it is condensed.
+    pub tool_result: usize,
+    /// Summarize oversized tool results with the model instead of truncating.
+    pub summarize_tool_output: bool,
+}
+
+impl ContextBudget {
+    pub fn for_window(window: usize) -> Self {
+        let window = window.max(1_024);
+        let output_reserve = (window / 4).min(8_192);
+        let input = window - output_reserve;
+        Self {
+            window,
+            output_reserve,
+            memory: (input / 10).min(4_000),
+            tool_result: (input / 4).min(16_000),
+            summarize_tool_output: false,
+        }
+    }
+
+    /// Budget for `model`, with the window from [`context_window`].
+    pub fn resolve(
+        agent: &AgentConfig,
+        workspace_dir: &Path,
+        provider_name: &str,
+        model: &str,
+    ) -> Self {
+        let window = context_window(agent, workspace_dir, provider_name, model);
+        Self {
+            summarize_tool_output: agent.summarize_tool_output,
+            ..Self::for_window(window)
+        }
+    }
+
+    /// Tokens available for the request itself (everything but the reply).
+    pub fn input(&self) -> usize {
+        self.window - self.output_reserve
+    }
+
+    /// `context` cut down to the memory allowance.
+    pub fn fit_memory_context(&self, context: String) -> String {
+        if estimate_tokens(&context) <= self.memory {
+            return context;
+        }
+        let mut fitted = fit_text(&context, self.memory);
+        fitted.push_str("\n\n");
+        fitted
+    }
+}
+
+/// `text` cut to roughly `max_tokens`, keeping the head and the tail (where
+/// errors and summaries usually are) around an omission marker.
+pub fn fit_text(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    let max_bytes = max_tokens.saturating_mul(4).saturating_sub(80);
+    let head_end = floor_char_boundary(text, max_bytes * 2 / 3);
+    let tail_start = ceil_char_boundary(text, text.len() - (max_bytes - max_bytes * 2 / 3));
+    let omitted = text[head_end..tail_start].chars().count();
+    format!(
+        "{}\n\n[... {omitted} characters omitted to fit the context window ...]\n\n{}",
+        &text[..head_end],
+        &text[tail_start..]
+    )
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// Drop the oldest non-system messages until `history` fits the input
+/// budget. The system prompt and the newest message are always kept.
+/// Returns how many messages were dropped.
+pub fn fit_history(history: &mut Vec<ChatMessage>, budget: &ContextBudget) -> usize {
+    let total = estimate_messages(history);
+    if total <= budget.input() {
+        return 0;
+    }
+
+    let start = usize::from(history.first().is_some_and(|m| m.role == "system"));
+    let mut excess = total - budget.input();
+    let mut end = start;
+    while excess > 0 && end + 1 < history.len() {
+        excess = excess.saturating_sub(message_tokens(&history[end]));
+        end += 1;
+    }
+    // Tool results whose call was dropped would be orphans.
+    while end + 1 < history.len() && history[end].role == "tool" {
+        end += 1;
+    }
+    history.drain(start..end);
+    if excess > 0 {
+        tracing::warn!(
+            window = budget.window,
+            over_by = excess,
+            "Request still exceeds the context window after dropping history"
+        );
+    }
+    end - start
+}
+
+/// Bring one tool result within `budget.tool_result`: summarized by the
+/// model when `summarize_tool_output` is on, otherwise (or if that fails)
+/// truncated around the middle.
+pub async fn condense_tool_output(
+    provider: &dyn Provider,
+    model: &str,
+    tool_name: &str,
+    output: &str,
+    budget: &ContextBudget,
+) -> String {
+    let tokens = estimate_tokens(output);
+    if tokens <= budget.tool_result {
+        return output.to_string();
+    }
+
+    if budget.summarize_tool_output {
+        let source = fit_text(output, budget.input() / 2);
+        let prompt = format!(
+            "Condense this output of the `{tool_name}` tool to what matters for the task. \
+             Keep exact values, identifiers, paths, numbers and error messages; \
+             drop repetition.\n\n{source}"
+        );
+        match provider
+            .chat_with_system(Some(SUMMARIZER_SYSTEM_PROMPT), &prompt, model, 0.0)
+            .await
+        {
+            Ok(summary) if !summary.trim().is_empty() => {
+                return format!(
+                    "[Summarized from ~{tokens} tokens of output]\n{}",
+                    fit_text(summary.trim(), budget.tool_result)
+                );
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(tool = tool_name, "Tool output summarization failed: {e}"),
+        }
+    }
+
+    fit_text(output, budget.tool_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    #[test]
+    fn default_windows_follow_the_model_family() {
+        assert_eq!(default_context_window("anthropic/claude-sonnet-4"), 200_000);
+        assert_eq!(default_context_window("gpt-4o-mini"), 128_000);
+        assert_eq!(default_context_window("gpt-4"), 8_192);
+        assert_eq!(default_context_window("openai/o3-mini"), 200_000);
+        assert_eq!(default_context_window("meta-llama/llama-3.3-70b"), 128_000);
+        assert_eq!(default_context_window("llama3.1:8b"), 128_000);
+        assert_eq!(default_context_window("llama3.2"), 128_000);
+        assert_eq!(default_context_window("llama3:8b"), 8_192);
+        assert_eq!(
+            default_context_window("meta-llama/llama-3-70b-instruct"),
+            8_192
+        );
+        assert_eq!(
+            default_context_window("some-local-model"),
+            DEFAULT_CONTEXT_WINDOW
+        );
+    }
+
+    #[test]
+    fn budget_is_carved_from_the_window() {
+        let small = ContextBudget::for_window(8_192);
+        assert_eq!(small.output_reserve, 2_048);
+        assert_eq!(small.input(), 6_144);
+        assert_eq!(small.memory, 614);
+        assert_eq!(small.tool_result, 1_536);
+
+        let large = ContextBudget::for_window(200_000);
+        assert_eq!(large.output_reserve, 8_192);
+        assert_eq!(large.memory, 4_000);
+        assert_eq!(large.tool_result, 16_000);
+    }
+
+    #[test]
+    fn resolve_prefers_the_configured_window() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let agent = AgentConfig {
+            context_window: Some(16_384),
+            summarize_tool_output: true,
+            ..AgentConfig::default()
+        };
+        let budget = ContextBudget::resolve(&agent, tmp.path(), "openrouter", "claude-sonnet-4");
+        assert_eq!(budget.window, 16_384);
+        assert!(budget.summarize_tool_output);
+
+        let budget = ContextBudget::resolve(
+            &AgentConfig::default(),
+            tmp.path(),
+            "openrouter",
+            "claude-sonnet-4",
+        );
+        assert_eq!(budget.window, 200_000);
+    }
+
+    #[test]
+    fn fit_text_keeps_head_and_tail() {
+        let text = format!("START {} END ✓", "é".repeat(5_000));
+        let fitted = fit_text(&text, 500);
+        assert!(estimate_tokens(&fitted) <= 500);
+        assert!(fitted.starts_with("START "));
+        assert!(fitted.ends_with(" END ✓"));
+        assert!(fitted.contains("characters omitted to fit the context window"));
+        assert_eq!(fit_text("short", 500), "short");
+    }
+
+    #[test]
+    fn fit_history_drops_oldest_turns_first() {
+        let budget = ContextBudget::for_window(1_024);
+        let mut history = vec![ChatMessage::system("system prompt")];
+        for i in 0..20 {
+            history.push(ChatMessage::user(format!("{i} {}", "x".repeat(400))));
+        }
+        let dropped = fit_history(&mut history, &budget);
+        assert!(dropped > 0);
+        assert_eq!(history.len(), 21 - dropped);
+        assert_eq!(history[0].role, "system");
+        assert!(history.last().unwrap().content.starts_with("19 "));
+        assert!(estimate_messages(&history) <= budget.input());
+    }
+
+    struct Summarizer;
+
+    #[async_trait]
+    impl Provider for Summarizer {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            assert!(message.contains("`shell`"));
+            Ok("3 files changed".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_tool_output_is_condensed() {
+        let mut budget = ContextBudget::for_window(8_192);
+        let output = "line\n".repeat(10_000);
+
+        let truncated = condense_tool_output(&Summarizer, "m", "shell", &output, &budget).await;
+        assert!(estimate_tokens(&truncated) <= budget.tool_result);
+        assert!(truncated.contains("characters omitted"));
+
+        budget.summarize_tool_output = true;
+        let summary = condense_tool_output(&Summarizer, "m", "shell", &output, &budget).await;
+        assert!(summary.starts_with("[Summarized from ~12500 tokens of output]\n"));
+        assert!(summary.ends_with("3 files changed"));
+
+        let small = condense_tool_output(&Summarizer, "m", "shell", "ok", &budget).await;
+        assert_eq!(small, "ok");
+    }
+}
@@ -237,6 +237,7 @@ mod tests {
                     .into(),
             ),
             tool_calls: vec![],
+            usage: None,
         };
         let dispatcher = XmlToolDispatcher;
         let (_, calls) = dispatcher.parse_response(&response);
@@ -253,6 +254,7 @@ mod tests {
                 name: "file_read".into(),
                 arguments: "{\"path\":\"a.txt\"}".into(),
             }],
+            usage: None,
         };
         let dispatcher = NativeToolDispatcher;
         let (_, calls) = dispatcher.parse_response(&response);
@@ -1,7 +1,9 @@
+use super::context::{self, ContextBudget};
 use crate::config::Config;
+use crate::cost::CostTracker;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::observability::{self, Observer, ObserverEvent};
-use crate::providers::{self, ChatMessage, Provider, ToolCall};
+use crate::providers::{self, ChatMessage, ChatRequest, DeltaSink, Provider, ToolCall};
 use crate::runtime;
 use crate::security::SecurityPolicy;
 use crate::tools::{self, Tool};
@@ -75,10 +77,18 @@ fn apply_compaction_summary(
     history.splice(start..compact_end, std::iter::once(summary_msg));
 }
 
+/// Structured reply of the compaction summarizer.
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+struct CompactionSummary {
+    /// Short facts to keep, most important first
+    bullets: Vec<String>,
+}
+
 async fn auto_compact_history(
     history: &mut Vec<ChatMessage>,
     provider: &dyn Provider,
     model: &str,
+    budget: &ContextBudget,
 ) -> Result<bool> {
     let has_system = history.first().map_or(false, |m| m.role == "system");
     let non_system_count = if has_system {
@@ -87,7 +97,11 @@ async fn auto_compact_history(
         history.len()
     };
 
-    if non_system_count <= MAX_HISTORY_MESSAGES {
+    // Compact on message count, or once history fills three quarters of
+    // the input budget.
+    if non_system_count <= MAX_HISTORY_MESSAGES
+        && context::estimate_messages(history) <= budget.input() / 4 * 3
+    {
         return Ok(false);
     }
 
@@ -102,20 +116,33 @@ async fn auto_compact_history(
     let to_compact: Vec<ChatMessage> = history[start..compact_end].to_vec();
     let transcript = build_compaction_transcript(&to_compact);
 
-    let summarizer_system = "You are a conversation compaction engine. Summarize older chat history into concise context for future turns. Preserve: user preferences, commitments, decisions, unresolved tasks, key facts. Omit: filler, repeated chit-chat, verbose tool logs. Output plain text bullet points only.";
+    let summarizer_system = "You are a conversation compaction engine. Summarize older chat history into concise context for future turns. Preserve: user preferences, commitments, decisions, unresolved tasks, key facts. Omit: filler, repeated chit-chat, verbose tool logs.";
 
     let summarizer_user = format!(
         "Summarize the following conversation history for context preservation. Keep it short (max 12 bullet points).\n\n{}",
         transcript
     );
 
-    let summary_raw = provider
-        .chat_with_system(Some(summarizer_system), &summarizer_user, model, 0.2)
-        .await
-        .unwrap_or_else(|_| {
-            // Fallback to deterministic local truncation when summarization fails.
-            truncate_with_ellipsis(&transcript, COMPACTION_MAX_SUMMARY_CHARS)
-        });
+    let summary_raw = match providers::structured::chat_as::<CompactionSummary>(
+        provider,
+        &[
+            ChatMessage::system(summarizer_system),
+            ChatMessage::user(summarizer_user),
+        ],
+        model,
+        0.2,
+    )
+    .await
+    {
+        Ok(summary) if !summary.bullets.is_empty() => summary
+            .bullets
+            .iter()
+            .map(|bullet| format!("- {}", bullet.trim()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        // Fallback to deterministic local truncation when summarization fails.
+        _ => truncate_with_ellipsis(&transcript, COMPACTION_MAX_SUMMARY_CHARS),
+    };
 
     let summary = truncate_with_ellipsis(&summary_raw, COMPACTION_MAX_SUMMARY_CHARS);
     apply_compaction_summary(history, start, compact_end, &summary);
@@ -419,6 +446,7 @@ struct ParsedToolCall {
 /// Execute a single turn of the agent loop: send messages, parse tool calls,
 /// execute tools, and loop until the LLM produces a final text response.
 /// When `silent` is true, suppresses stdout (for channel use).
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn agent_turn(
     provider: &dyn Provider,
     history: &mut Vec<ChatMessage>,
@@ -428,6 +456,8 @@ pub(crate) async fn agent_turn(
     model: &str,
     temperature: f64,
     silent: bool,
+    budget: &ContextBudget,
+    cost: Option<&CostTracker>,
 ) -> Result<String> {
     run_tool_call_loop(
         provider,
@@ -438,12 +468,56 @@ pub(crate) async fn agent_turn(
         model,
         temperature,
         silent,
+        budget,
+        cost,
+        None,
     )
     .await
 }
 
+/// Forwards streamed reply text up to the first `<tool_call>` tag, so
+/// tool-call markup never reaches the reader. A trailing fragment that could
+/// start the tag is held back until the next delta settles it.
+struct VisibleDeltas<'a> {
+    sink: &'a DeltaSink<'a>,
+    /// Text seen so far and how many bytes of it were forwarded.
+    state: parking_lot::Mutex<(String, usize)>,
+}
+
+impl<'a> VisibleDeltas<'a> {
+    const TAG: &'static str = "<tool_call";
+
+    fn new(sink: &'a DeltaSink<'a>) -> Self {
+        Self {
+            sink,
+            state: parking_lot::Mutex::new((String::new(), 0)),
+        }
+    }
+
+    fn push(&self, delta: &str) {
+        let mut state = self.state.lock();
+        let (seen, sent) = &mut *state;
+        seen.push_str(delta);
+        let visible = seen.find(Self::TAG).unwrap_or_else(|| {
+            let held = (1..Self::TAG.len())
+                .rev()
+                .find(|&len| seen.ends_with(&Self::TAG[..len]))
+                .unwrap_or(0);
+            seen.len() - held
+        });
+        if visible > *sent {
+            (self.sink)(&seen[*sent..visible]);
+            *sent = visible;
+        }
+    }
+}
+
 /// Execute a single turn of the agent loop: send messages, parse tool calls,
 /// execute tools, and loop until the LLM produces a final text response.
+/// Tool results and history are kept within `budget`; reported token usage
+/// goes to `cost`. With `on_delta`, reply text is streamed to it as the
+/// provider produces it, up to the first tool call.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_tool_call_loop(
     provider: &dyn Provider,
     history: &mut Vec<ChatMessage>,
@@ -453,8 +527,20 @@ pub(crate) async fn run_tool_call_loop(
     model: &str,
     temperature: f64,
     silent: bool,
+    budget: &ContextBudget,
+    cost: Option<&CostTracker>,
+    on_delta: Option<&DeltaSink<'_>>,
 ) -> Result<String> {
     for _iteration in 0..MAX_TOOL_ITERATIONS {
+        let dropped = context::fit_history(history, budget);
+        if dropped > 0 {
+            tracing::debug!(
+                dropped,
+                window = budget.window,
+                "Dropped oldest messages to fit the context window"
+            );
+        }
+
         observer.record_event(&ObserverEvent::LlmRequest {
             provider: provider_name.to_string(),
             model: model.to_string(),
@@ -462,10 +548,20 @@ pub(crate) async fn run_tool_call_loop(
         });
 
         let llm_started_at = Instant::now();
-        let response = match provider
-            .chat_with_history(history, model, temperature)
-            .await
-        {
+        let request = ChatRequest {
+            messages: history,
+            tools: None,
+        };
+        let result = match on_delta {
+            Some(sink) => {
+                let visible = VisibleDeltas::new(sink);
+                provider
+                    .chat_streaming(request, model, temperature, &|delta| visible.push(delta))
+                    .await
+            }
+            None => provider.chat(request, model, temperature).await,
+        };
+        let response = match result {
             Ok(resp) => {
                 observer.record_event(&ObserverEvent::LlmResponse {
                     provider: provider_name.to_string(),
@@ -488,11 +584,23 @@ pub(crate) async fn run_tool_call_loop(
             }
         };
 
-        let response_text = response;
+        if let (Some(cost), Some(usage)) = (cost, response.usage.as_ref()) {
+            if let Err(e) = cost.record_response(model, usage) {
+                tracing::warn!("Failed to record token usage: {e:#}");
+            }
+        }
+
+        let response_text = response.text_or_empty().to_string();
         let mut assistant_history_content = response_text.clone();
-        let (parsed_text, tool_calls) = parse_tool_calls(&response_text);
-        let mut parsed_text = parsed_text;
-        let mut tool_calls = tool_calls;
+        let (mut parsed_text, mut tool_calls) = parse_tool_calls(&response_text);
+        // Providers that parse tool calls themselves (OpenAI-style JSON
+        // replies) hand them back structured instead of in the text.
+        if tool_calls.is_empty() && !response.tool_calls.is_empty() {
+            parsed_text = response_text.trim().to_string();
+            tool_calls = parse_structured_tool_calls(&response.tool_calls);
+            assistant_history_content =
+                build_assistant_history_with_tool_calls(&response_text, &response.tool_calls);
+        }
 
         if tool_calls.is_empty() {
             // No tool calls — this is the final response
@@ -518,7 +626,7 @@ pub(crate) async fn run_tool_call_loop(
             });
             let start = Instant::now();
             let result = if let Some(tool) = find_tool(tools_registry, &call.name) {
-                match tool.execute(call.arguments.clone()).await {
+                match tools::execute_validated(tool, call.arguments.clone()).await {
                     Ok(r) => {
                         observer.record_event(&ObserverEvent::ToolCall {
                             tool: call.name.clone(),
@@ -543,6 +651,8 @@ pub(crate) async fn run_tool_call_loop(
             } else {
                 format!("Unknown tool: {}", call.name)
             };
+            let result =
+                context::condense_tool_output(provider, model, &call.name, &result, budget).await;
 
             let _ = writeln!(
                 tool_results,
@@ -597,6 +707,7 @@ pub async fn run(
     model_override: Option<String>,
     temperature: f64,
     peripheral_overrides: Vec<String>,
+    record: Option<String>,
 ) -> Result<()> {
     // ── Wire up agnostic subsystems ──────────────────────────────
     let base_observer = observability::create_observer(&config.observability);
@@ -653,6 +764,8 @@ pub async fn run(
         tracing::info!(count = peripheral_tools.len(), "Peripheral tools added");
         tools_registry.extend(peripheral_tools);
     }
+    tools_registry
+        .extend(crate::mcp::create_mcp_tools(&config.mcp, &security, &config.workspace_dir).await);
 
     // ── Resolve provider ─────────────────────────────────────────
     let provider_name = provider_override
@@ -665,18 +778,32 @@ pub async fn run(
         .or(config.default_model.as_deref())
         .unwrap_or("anthropic/claude-sonnet-4");
 
-    let provider: Box<dyn Provider> = providers::create_routed_provider(
+    let mut provider: Box<dyn Provider> = providers::create_auto_routed_provider(
+        &config,
         provider_name,
-        config.api_key.as_deref(),
-        &config.reliability,
-        &config.model_routes,
         model_name,
+        observer.clone(),
     )?;
+    if let Some(cassette) = record.as_deref() {
+        let path = std::path::PathBuf::from(shellexpand::tilde(cassette).into_owned());
+        println!("⏺  Recording provider traffic to {}", path.display());
+        provider = Box::new(
+            providers::replay::RecordingProvider::new(provider, path)
+                .with_alias(&config.workspace_dir.display().to_string(), "{workspace}"),
+        );
+    }
 
     observer.record_event(&ObserverEvent::AgentStart {
         provider: provider_name.to_string(),
         model: model_name.to_string(),
     });
+    let budget = ContextBudget::resolve(
+        &config.agent,
+        &config.workspace_dir,
+        provider_name,
+        model_name,
+    );
+    let cost = CostTracker::from_config(&config);
 
     // ── Hardware RAG (datasheet retrieval when peripherals + datasheet_dir) ──
     let hardware_rag: Option<crate::rag::HardwareRag> = config
@@ -823,7 +950,7 @@ pub async fn run(
             .as_ref()
             .map(|r| build_hardware_context(r, &msg, &board_names, rag_limit))
             .unwrap_or_default();
-        let context = format!("{mem_context}{hw_context}");
+        let context = budget.fit_memory_context(format!("{mem_context}{hw_context}"));
         let enriched = if context.is_empty() {
             msg.clone()
         } else {
@@ -844,6 +971,9 @@ pub async fn run(
             model_name,
             temperature,
             false,
+            &budget,
+            cost.as_deref(),
+            None,
         )
         .await?;
         println!("{response}");
@@ -888,7 +1018,7 @@ pub async fn run(
                 .as_ref()
                 .map(|r| build_hardware_context(r, &msg.content, &board_names, rag_limit))
                 .unwrap_or_default();
-            let context = format!("{mem_context}{hw_context}");
+            let context = budget.fit_memory_context(format!("{mem_context}{hw_context}"));
             let enriched = if context.is_empty() {
                 msg.content.clone()
             } else {
@@ -906,6 +1036,9 @@ pub async fn run(
                 model_name,
                 temperature,
                 false,
+                &budget,
+                cost.as_deref(),
+                None,
             )
             .await
             {
@@ -920,7 +1053,7 @@ pub async fn run(
 
             // Auto-compaction before hard trimming to preserve long-context signal.
             if let Ok(compacted) =
-                auto_compact_history(&mut history, provider.as_ref(), model_name).await
+                auto_compact_history(&mut history, provider.as_ref(), model_name, &budget).await
             {
                 if compacted {
                     println!("🧹 Auto-compaction complete");
@@ -954,8 +1087,7 @@ pub async fn run(
 /// Process a single message through the full agent (with tools, peripherals, memory).
 /// Used by channels (Telegram, Discord, etc.) to enable hardware and tool use.
 pub async fn process_message(config: Config, message: &str) -> Result<String> {
-    let observer: Arc<dyn Observer> =
-        Arc::from(observability::create_observer(&config.observability));
+    let observer: Arc<dyn Observer> = observability::create_shared_observer(&config.observability);
     let runtime: Arc<dyn runtime::RuntimeAdapter> =
         Arc::from(runtime::create_runtime(&config.runtime)?);
     let security = Arc::new(SecurityPolicy::from_config(
@@ -992,19 +1124,27 @@ pub async fn process_message(config: Config, message: &str) -> Result<String> {
     let peripheral_tools: Vec<Box<dyn Tool>> =
         crate::peripherals::create_peripheral_tools(&config.peripherals).await?;
     tools_registry.extend(peripheral_tools);
+    tools_registry
+        .extend(crate::mcp::create_mcp_tools(&config.mcp, &security, &config.workspace_dir).await);
 
     let provider_name = config.default_provider.as_deref().unwrap_or("openrouter");
     let model_name = config
         .default_model
         .clone()
         .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
-    let provider: Box<dyn Provider> = providers::create_routed_provider(
+    let provider: Box<dyn Provider> = providers::create_auto_routed_provider(
+        &config,
         provider_name,
-        config.api_key.as_deref(),
-        &config.reliability,
-        &config.model_routes,
         &model_name,
+        observer.clone(),
     )?;
+    let budget = ContextBudget::resolve(
+        &config.agent,
+        &config.workspace_dir,
+        provider_name,
+        &model_name,
+    );
+    let cost = CostTracker::from_config(&config);
 
     let hardware_rag: Option<crate::rag::HardwareRag> = config
         .peripherals
@@ -1087,7 +1227,7 @@ pub async fn process_message(config: Config, message: &str) -> Result<String> {
         .as_ref()
         .map(|r| build_hardware_context(r, message, &board_names, rag_limit))
         .unwrap_or_default();
-    let context = format!("{mem_context}{hw_context}");
+    let context = budget.fit_memory_context(format!("{mem_context}{hw_context}"));
     let enriched = if context.is_empty() {
         message.to_string()
     } else {
@@ -1108,6 +1248,8 @@ pub async fn process_message(config: Config, message: &str) -> Result<String> {
         &model_name,
         config.default_temperature,
         true,
+        &budget,
+        cost.as_deref(),
     )
     .await
 }
@@ -1118,6 +1260,23 @@ mod tests {
     use crate::memory::{Memory, MemoryCategory, SqliteMemory};
     use tempfile::TempDir;
 
+    #[test]
+    fn visible_deltas_stop_at_tool_call_markup() {
+        let out = parking_lot::Mutex::new(Vec::new());
+        let sink = |text: &str| out.lock().push(text.to_string());
+        let visible = VisibleDeltas::new(&sink);
+        for delta in [
+            "Let me check",
+            " <",
+            "b>that</b> <tool",
+            "_call>{\"name\"",
+            " more",
+        ] {
+            visible.push(delta);
+        }
+        assert_eq!(*out.lock(), ["Let me check", " ", "<b>that</b> "]);
+    }
+
     #[test]
     fn parse_tool_calls_extracts_single_call() {
         let response = r#"Let me check that.
@@ -1346,6 +1505,43 @@ I will now call the tool with this payload:
         assert!(history[3].content.contains("recent 2"));
     }
 
+    struct CompactionSummarizer;
+
+    #[async_trait::async_trait]
+    impl Provider for CompactionSummarizer {
+        async fn chat_with_system(
+            &self,
+            system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            assert!(system_prompt.unwrap().contains("JSON Schema"));
+            assert!(message.contains("USER: turn 0"));
+            Ok(r#"{"bullets": ["user prefers Rust", " wants short replies "]}"#.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn auto_compact_history_stores_structured_summary() {
+        let mut history = vec![ChatMessage::system("sys")];
+        for i in 0..MAX_HISTORY_MESSAGES + 5 {
+            history.push(ChatMessage::user(format!("turn {i}")));
+        }
+        let budget = ContextBudget::for_window(1_000_000);
+
+        let compacted = auto_compact_history(&mut history, &CompactionSummarizer, "m", &budget)
+            .await
+            .unwrap();
+
+        assert!(compacted);
+        assert_eq!(history.len(), 2 + COMPACTION_KEEP_RECENT_MESSAGES);
+        assert_eq!(
+            history[1].content,
+            "[Compaction summary]\n- user prefers Rust\n- wants short replies"
+        );
+    }
+
     #[test]
     fn autosave_memory_key_has_prefix_and_uniqueness() {
         let key1 = autosave_memory_key("user_msg");
@@ -1583,4 +1779,74 @@ Done."#;
         let result = parse_tool_calls_from_json_value(&value);
         assert_eq!(result.len(), 2);
     }
+
+    #[tokio::test]
+    async fn cached_anthropic_usage_lands_in_cost_tracker() {
+        use crate::config::schema::{CostConfig, ModelPricing};
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/v1/messages",
+            post(|| async {
+                Json(serde_json::json!({
+                    "content": [{"type": "text", "text": "cached answer"}],
+                    "usage": {
+                        "input_tokens": 100,
+                        "output_tokens": 20,
+                        "cache_creation_input_tokens": 0,
+                        "cache_read_input_tokens": 4000
+                    }
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let tmp = TempDir::new().unwrap();
+        let mut cost_config = CostConfig {
+            enabled: true,
+            ..CostConfig::default()
+        };
+        cost_config.prices.insert(
+            "claude-sonnet-4".into(),
+            ModelPricing {
+                input: 3.0,
+                output: 15.0,
+                cache_read: Some(0.3),
+                cache_write: None,
+            },
+        );
+        let tracker = CostTracker::new(cost_config, tmp.path()).unwrap();
+        let provider = crate::providers::anthropic::AnthropicProvider::with_base_url(
+            Some("sk-ant-test"),
+            Some(&format!("http://{addr}")),
+        );
+        let mut history = vec![ChatMessage::system("sys"), ChatMessage::user("hi")];
+
+        let reply = run_tool_call_loop(
+            &provider,
+            &mut history,
+            &[],
+            &crate::observability::NoopObserver,
+            "anthropic",
+            "claude-sonnet-4",
+            0.0,
+            true,
+            &ContextBudget::for_window(32_768),
+            Some(&tracker),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply, "cached answer");
+
+        let summary = tracker.get_summary().unwrap();
+        assert_eq!(summary.request_count, 1);
+        assert_eq!(summary.total_tokens, 4120);
+        // 100 input at $3, 20 output at $15 and 4000 cache reads at $0.30 per 1M.
+        assert!((summary.session_cost_usd - 0.0018).abs() < 1e-9);
+    }
 }
@@ -1,5 +1,6 @@
 #[allow(clippy::module_inception)]
 pub mod agent;
+pub mod context;
 pub mod dispatcher;
 pub mod loop_;
 pub mod memory_loader;
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
@@ -12,7 +12,7 @@ use uuid::Uuid;
 pub struct DingTalkChannel {
     client_id: String,
     client_secret: String,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
     client: reqwest::Client,
     /// Per-chat session webhooks for sending replies (chatID -> webhook URL).
     /// DingTalk provides a unique webhook URL with each incoming message.
@@ -31,14 +31,17 @@ impl DingTalkChannel {
         Self {
             client_id,
             client_secret,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
             client: reqwest::Client::new(),
             session_webhooks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     fn is_user_allowed(&self, user_id: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == user_id)
+        self.allowed_users
+            .read()
+            .iter()
+            .any(|u| u == "*" || u == user_id)
     }
 
     /// Register a connection with DingTalk's gateway to get a WebSocket endpoint.
@@ -72,6 +75,10 @@ impl Channel for DingTalkChannel {
         "dingtalk"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let webhooks = self.session_webhooks.read().await;
         let webhook_url = webhooks.get(recipient).ok_or_else(|| {
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
@@ -9,7 +9,7 @@ use uuid::Uuid;
 pub struct DiscordChannel {
     bot_token: String,
     guild_id: Option<String>,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
     listen_to_bots: bool,
     client: reqwest::Client,
     typing_handle: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
@@ -25,7 +25,7 @@ impl DiscordChannel {
         Self {
             bot_token,
             guild_id,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
             listen_to_bots,
             client: reqwest::Client::new(),
             typing_handle: std::sync::Mutex::new(None),
@@ -36,7 +36,10 @@ impl DiscordChannel {
     /// Empty list means deny everyone until explicitly configured.
     /// `"*"` means allow everyone.
     fn is_user_allowed(&self, user_id: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == user_id)
+        self.allowed_users
+            .read()
+            .iter()
+            .any(|u| u == "*" || u == user_id)
     }
 
     fn bot_user_id_from_token(token: &str) -> Option<String> {
@@ -145,6 +148,10 @@ impl Channel for DiscordChannel {
         "discord"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
+
     async fn send(&self, message: &str, channel_id: &str) -> anyhow::Result<()> {
         let chunks = split_message_for_discord(message);
 
@@ -8,29 +8,45 @@
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::unnecessary_map_or)]
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
-use mail_parser::{MessageParser, MimeHeaders};
+use mail_parser::decoders::html::html_to_text;
+use mail_parser::{MessageParser, MimeHeaders, PartType};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
-use std::io::Write as IoWrite;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write as IoWrite};
 use std::net::TcpStream;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Maximum number of seen message IDs to retain before evicting the oldest.
 const SEEN_MESSAGES_CAPACITY: usize = 100_000;
+/// Maximum number of threads whose reply headers are remembered.
+const MAX_TRACKED_THREADS: usize = 1024;
+/// Message-IDs kept in `References`: the thread root plus the most recent ones.
+const MAX_REFERENCES: usize = 20;
+/// Attachments larger than this are mentioned but not saved.
+const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+/// Re-issue IDLE before the 29-minute server timeout (RFC 2177).
+const IDLE_RENEW: Duration = Duration::from_secs(25 * 60);
+/// How often an idling session checks whether the channel was stopped.
+const IDLE_TICK: Duration = Duration::from_secs(5);
+/// Read timeout for ordinary IMAP commands.
+const IMAP_IO_TIMEOUT: Duration = Duration::from_secs(30);
+/// Delay before reconnecting after an IMAP error.
+const IMAP_RETRY: Duration = Duration::from_secs(10);
 use tokio::sync::mpsc;
-use tokio::time::{interval, sleep};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 
 /// Email channel configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct EmailConfig {
     /// IMAP server hostname
     pub imap_host: String,
@@ -54,9 +70,12 @@ pub struct EmailConfig {
     pub password: String,
     /// From address for outgoing emails
     pub from_address: String,
-    /// Poll interval in seconds (default: 60)
+    /// Poll interval in seconds (default: 60); used when IDLE is off or unsupported
     #[serde(default = "default_poll_interval")]
     pub poll_interval_secs: u64,
+    /// Use IMAP IDLE for push delivery when the server supports it (default: true)
+    #[serde(default = "default_true")]
+    pub idle: bool,
     /// Allowed sender addresses/domains (empty = deny all, ["*"] = allow all)
     #[serde(default)]
     pub allowed_senders: Vec<String>,
@@ -91,6 +110,7 @@ impl Default for EmailConfig {
             password: String::new(),
             from_address: String::new(),
             poll_interval_secs: default_poll_interval(),
+            idle: true,
             allowed_senders: Vec::new(),
         }
     }
@@ -135,30 +155,474 @@ impl BoundedSeenSet {
     }
 }
 
-/// Email channel — IMAP polling for inbound, SMTP for outbound
+/// Reply headers for one email thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct EmailThread {
+    /// Subject without `Re:`/`Fwd:` prefixes
+    subject: String,
+    /// Message-IDs (without angle brackets), oldest first
+    references: Vec<String>,
+}
+
+impl EmailThread {
+    /// Append a message to the chain, keeping the root and the newest IDs.
+    fn push(&mut self, message_id: &str) {
+        if self.references.iter().any(|id| id == message_id) {
+            return;
+        }
+        self.references.push(message_id.to_string());
+        if self.references.len() > MAX_REFERENCES {
+            self.references.remove(1);
+        }
+    }
+}
+
+/// Bounded map of thread key → reply headers, evicting the oldest thread.
+#[derive(Default)]
+struct ThreadBook {
+    threads: HashMap<String, EmailThread>,
+    order: VecDeque<String>,
+}
+
+impl ThreadBook {
+    fn get(&self, key: &str) -> Option<EmailThread> {
+        self.threads.get(key).cloned()
+    }
+
+    fn update(&mut self, key: &str, f: impl FnOnce(&mut EmailThread)) {
+        if !self.threads.contains_key(key) {
+            if self.order.len() >= MAX_TRACKED_THREADS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.threads.remove(&oldest);
+                }
+            }
+            self.order.push_back(key.to_string());
+        }
+        f(self.threads.entry(key.to_string()).or_default());
+    }
+}
+
+/// A file attached to an inbound email.
+struct EmailAttachment {
+    name: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+/// The parts of an inbound email the agent cares about.
+struct InboundEmail {
+    message_id: String,
+    sender: String,
+    subject: String,
+    /// Readable body with quoted replies removed
+    body: String,
+    /// `References` followed by `In-Reply-To`, oldest first
+    references: Vec<String>,
+    timestamp: u64,
+    attachments: Vec<EmailAttachment>,
+}
+
+impl InboundEmail {
+    /// Stable key for the thread: a hash of its root Message-ID.
+    fn thread_key(&self) -> String {
+        let root = self.references.first().unwrap_or(&self.message_id);
+        short_hash(root)
+    }
+}
+
+fn short_hash(value: &str) -> String {
+    hex::encode(&Sha256::digest(value.as_bytes())[..8])
+}
+
+/// Split a reply target into the address and an optional thread key.
+/// Targets look like `user@example.com` or `user@example.com#<thread key>`.
+fn parse_target(target: &str) -> (&str, Option<&str>) {
+    match target.rsplit_once('#') {
+        Some((address, key)) if key.len() == 16 && key.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            (address, Some(key))
+        }
+        _ => (target, None),
+    }
+}
+
+/// Drop quoted text from a reply: `>` lines, the "On … wrote:" attribution
+/// and everything after an Outlook-style original-message separator or a
+/// `-- ` signature delimiter.
+fn strip_quoted_reply(text: &str) -> String {
+    let mut kept: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.ends_with(" wrote:") {
+            // Attributions are often wrapped: "On Mon, … Alice <\nalice@x> wrote:"
+            if !trimmed.starts_with("On ")
+                && kept
+                    .last()
+                    .is_some_and(|l| l.trim_start().starts_with("On "))
+            {
+                kept.pop();
+            }
+            break;
+        }
+        if trimmed == "--"
+            || trimmed.starts_with("-----Original Message-----")
+            || trimmed.starts_with("________________________________")
+        {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            kept.push(line.trim_end());
+        }
+    }
+    kept.join("\n").trim().to_string()
+}
+
+/// Remove `<blockquote>` sections (quoted replies in HTML mail).
+fn strip_html_quotes(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::with_capacity(html.len());
+    let mut depth = 0_usize;
+    let mut pos = 0;
+    while pos < html.len() {
+        let open = lower[pos..].find("<blockquote").map(|i| i + pos);
+        let close = lower[pos..].find("</blockquote").map(|i| i + pos);
+        let next = match (open, close) {
+            (Some(o), Some(c)) => o.min(c),
+            (Some(o), None) => o,
+            (None, Some(c)) => c,
+            (None, None) => {
+                if depth == 0 {
+                    out.push_str(&html[pos..]);
+                }
+                break;
+            }
+        };
+        if depth == 0 {
+            out.push_str(&html[pos..next]);
+        }
+        if Some(next) == open {
+            depth += 1;
+        } else {
+            depth = depth.saturating_sub(1);
+        }
+        pos = lower[next..].find('>').map_or(html.len(), |i| next + i + 1);
+    }
+    out
+}
+
+/// Keep attachment names to a single safe path component.
+fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.');
+    if cleaned.is_empty() {
+        "attachment".into()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+// ── IMAP ─────────────────────────────────────────────────────────
+
+/// A byte stream an IMAP session can run over (TLS in production).
+trait ImapStream: Read + IoWrite {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()>;
+}
+
+impl ImapStream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+impl ImapStream
+    for tokio_rustls::rustls::StreamOwned<tokio_rustls::rustls::ClientConnection, TcpStream>
+{
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.sock.set_read_timeout(timeout)
+    }
+}
+
+/// One server response line with any `{n}` literals pulled out.
+struct ImapResponse {
+    line: String,
+    literals: Vec<Vec<u8>>,
+}
+
+/// Minimal blocking IMAP4rev1 client: just what the channel needs.
+struct ImapSession<S: ImapStream> {
+    stream: BufReader<S>,
+    /// Bytes of a line interrupted by a read timeout
+    pending: Vec<u8>,
+    next_tag: u32,
+    idle_tick: Duration,
+}
+
+impl
+    ImapSession<
+        tokio_rustls::rustls::StreamOwned<tokio_rustls::rustls::ClientConnection, TcpStream>,
+    >
+{
+    fn connect(config: &EmailConfig) -> Result<Self> {
+        use rustls_pki_types::ServerName;
+        use tokio_rustls::rustls;
+
+        let tcp = TcpStream::connect((&*config.imap_host, config.imap_port))?;
+        let tls_config = EmailChannel::build_imap_tls_config()?;
+        let server_name: ServerName<'_> = ServerName::try_from(config.imap_host.clone())?;
+        let conn = rustls::ClientConnection::new(tls_config, server_name)?;
+        Self::new(rustls::StreamOwned::new(conn, tcp))
+    }
+}
+
+impl<S: ImapStream> ImapSession<S> {
+    /// Wrap a connected stream and consume the server greeting.
+    fn new(stream: S) -> Result<Self> {
+        stream.set_read_timeout(Some(IMAP_IO_TIMEOUT))?;
+        let mut session = Self {
+            stream: BufReader::new(stream),
+            pending: Vec::new(),
+            next_tag: 1,
+            idle_tick: IDLE_TICK,
+        };
+        let greeting = session.read_response()?;
+        if !greeting.line.starts_with("* OK") && !greeting.line.starts_with("* PREAUTH") {
+            bail!("IMAP server refused connection: {}", greeting.line);
+        }
+        Ok(session)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        self.stream.read_until(b'\n', &mut self.pending)?;
+        if !self.pending.ends_with(b"\n") {
+            bail!("IMAP connection closed");
+        }
+        let line = String::from_utf8_lossy(&self.pending)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        self.pending.clear();
+        Ok(line)
+    }
+
+    fn read_response(&mut self) -> Result<ImapResponse> {
+        let mut response = ImapResponse {
+            line: String::new(),
+            literals: Vec::new(),
+        };
+        loop {
+            let line = self.read_line()?;
+            let literal = line
+                .strip_suffix('}')
+                .and_then(|rest| rest.rsplit_once('{'))
+                .and_then(|(before, len)| Some((before, len.parse::<usize>().ok()?)));
+            let Some((before, len)) = literal else {
+                response.line.push_str(&line);
+                return Ok(response);
+            };
+            response.line.push_str(before);
+            let mut data = vec![0; len];
+            self.stream.read_exact(&mut data)?;
+            response.literals.push(data);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    fn tag(&mut self) -> String {
+        let tag = format!("A{}", self.next_tag);
+        self.next_tag += 1;
+        tag
+    }
+
+    /// Read until the tagged completion and fail unless it is `OK`.
+    fn complete(&mut self, tag: &str, verb: &str) -> Result<Vec<ImapResponse>> {
+        let prefix = format!("{tag} ");
+        let mut responses = Vec::new();
+        loop {
+            let response = self.read_response()?;
+            if let Some(status) = response.line.strip_prefix(&prefix) {
+                if status.starts_with("OK") {
+                    return Ok(responses);
+                }
+                bail!("IMAP {verb} failed: {status}");
+            }
+            responses.push(response);
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Result<Vec<ImapResponse>> {
+        let tag = self.tag();
+        self.write_line(&format!("{tag} {command}"))?;
+        let verb = command.split(' ').next().unwrap_or(command);
+        self.complete(&tag, verb)
+    }
+
+    fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        self.command(&format!("LOGIN {} {}", quote(username), quote(password)))
+            .map(|_| ())
+    }
+
+    fn select(&mut self, folder: &str) -> Result<()> {
+        self.command(&format!("SELECT {}", quote(folder)))
+            .map(|_| ())
+    }
+
+    fn supports_idle(&mut self) -> Result<bool> {
+        Ok(self.command("CAPABILITY")?.iter().any(|r| {
+            r.line.starts_with("* CAPABILITY")
+                && r.line
+                    .split_whitespace()
+                    .any(|cap| cap.eq_ignore_ascii_case("IDLE"))
+        }))
+    }
+
+    /// Fetch every unseen message and flag it `\Seen`.
+    fn fetch_unseen(&mut self) -> Result<Vec<Vec<u8>>> {
+        let uids: Vec<String> = self
+            .command("UID SEARCH UNSEEN")?
+            .iter()
+            .filter_map(|r| r.line.strip_prefix("* SEARCH"))
+            .flat_map(|rest| rest.split_whitespace().map(str::to_string))
+            .collect();
+
+        let mut messages = Vec::new();
+        for uid in uids {
+            let fetched = self.command(&format!("UID FETCH {uid} BODY.PEEK[]"))?;
+            if let Some(raw) = fetched
+                .into_iter()
+                .filter(|r| r.line.contains("FETCH"))
+                .find_map(|r| r.literals.into_iter().next())
+            {
+                messages.push(raw);
+            }
+            self.command(&format!("UID STORE {uid} +FLAGS.SILENT (\\Seen)"))?;
+        }
+        Ok(messages)
+    }
+
+    /// IDLE until the mailbox reports new mail, `renew` elapses or `stop()`
+    /// returns true. Returns whether new mail arrived.
+    fn idle(&mut self, renew: Duration, stop: &dyn Fn() -> bool) -> Result<bool> {
+        let tag = self.tag();
+        self.write_line(&format!("{tag} IDLE"))?;
+        let ready = self.read_response()?;
+        if !ready.line.starts_with('+') {
+            bail!("IMAP IDLE rejected: {}", ready.line);
+        }
+
+        self.stream
+            .get_ref()
+            .set_read_timeout(Some(self.idle_tick))?;
+        let deadline = Instant::now() + renew;
+        let mut new_mail = false;
+        while !new_mail && Instant::now() < deadline && !stop() {
+            match self.read_response() {
+                Ok(response) => {
+                    new_mail = response.line.starts_with("* ")
+                        && response.line.to_ascii_uppercase().ends_with(" EXISTS");
+                }
+                Err(e) if is_timeout(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.stream
+            .get_ref()
+            .set_read_timeout(Some(IMAP_IO_TIMEOUT))?;
+
+        self.write_line("DONE")?;
+        self.complete(&tag, "IDLE")?;
+        Ok(new_mail)
+    }
+
+    fn logout(&mut self) {
+        let _ = self.command("LOGOUT");
+    }
+}
+
+/// Quote an IMAP string argument.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn is_timeout(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<std::io::Error>().is_some_and(|e| {
+        matches!(
+            e.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        )
+    })
+}
+
+/// Sleep for `duration`, waking early once the receiver is gone.
+/// Returns true if the channel stopped.
+fn wait_or_stop(raw_tx: &mpsc::Sender<Vec<u8>>, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !raw_tx.is_closed() {
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_secs(1)));
+    }
+    true
+}
+
+/// Email channel — IMAP IDLE (or polling) for inbound, SMTP for outbound.
+///
+/// Each inbound email is answered on its own thread: the reply target is
+/// `address#<thread key>` and replies carry `In-Reply-To`/`References`.
 pub struct EmailChannel {
     pub config: EmailConfig,
+    /// Live copy of `config.allowed_senders`, swapped on reload
+    allowed_senders: Allowlist,
     seen_messages: Mutex<BoundedSeenSet>,
+    threads: Mutex<ThreadBook>,
+    /// Where inbound attachments are saved (`<workspace>/email_attachments`)
+    attachments_dir: Option<PathBuf>,
 }
 
 impl EmailChannel {
     pub fn new(config: EmailConfig) -> Self {
         Self {
+            allowed_senders: Allowlist::new(config.allowed_senders.clone()),
             config,
             seen_messages: Mutex::new(BoundedSeenSet::new(SEEN_MESSAGES_CAPACITY)),
+            threads: Mutex::new(ThreadBook::default()),
+            attachments_dir: None,
         }
     }
 
+    /// Build from config. Inbound attachments are saved under `workspace_dir`.
+    pub fn from_config(config: &EmailConfig, workspace_dir: &Path) -> Self {
+        let mut channel = Self::new(config.clone());
+        channel.attachments_dir = Some(workspace_dir.join("email_attachments"));
+        channel
+    }
     /// Check if a sender email is in the allowlist
     pub fn is_sender_allowed(&self, email: &str) -> bool {
-        if self.config.allowed_senders.is_empty() {
+        let allowed_senders = self.allowed_senders.read();
+        if allowed_senders.is_empty() {
             return false; // Empty = deny all
         }
-        if self.config.allowed_senders.iter().any(|a| a == "*") {
+        if allowed_senders.iter().any(|a| a == "*") {
             return true; // Wildcard = allow all
         }
         let email_lower = email.to_lowercase();
-        self.config.allowed_senders.iter().any(|allowed| {
+        allowed_senders.iter().any(|allowed| {
             if allowed.starts_with('@') {
                 // Domain match with @ prefix: "@example.com"
                 email_lower.ends_with(&allowed.to_lowercase())
@@ -172,19 +636,12 @@ impl EmailChannel {
         })
     }
 
-    /// Strip HTML tags from content (basic)
+    /// Convert HTML to plain text, collapsing whitespace
     pub fn strip_html(html: &str) -> String {
-        let mut result = String::new();
-        let mut in_tag = false;
-        for ch in html.chars() {
-            match ch {
-                '<' => in_tag = true,
-                '>' => in_tag = false,
-                _ if !in_tag => result.push(ch),
-                _ => {}
-            }
-        }
-        result.split_whitespace().collect::<Vec<_>>().join(" ")
+        html_to_text(html)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     /// Extract the sender address from a parsed email
@@ -197,26 +654,199 @@ impl EmailChannel {
             .unwrap_or_else(|| "unknown".into())
     }
 
-    /// Extract readable text from a parsed email
+    /// Extract readable text from a parsed email, without quoted replies
     fn extract_text(parsed: &mail_parser::Message) -> String {
-        if let Some(text) = parsed.body_text(0) {
-            return text.to_string();
-        }
-        if let Some(html) = parsed.body_html(0) {
-            return Self::strip_html(html.as_ref());
-        }
-        for part in parsed.attachments() {
-            let part: &mail_parser::MessagePart = part;
-            if let Some(ct) = MimeHeaders::content_type(part) {
-                if ct.ctype() == "text" {
-                    if let Ok(text) = std::str::from_utf8(part.contents()) {
-                        let name = MimeHeaders::attachment_name(part).unwrap_or("file");
-                        return format!("[Attachment: {}]\n{}", name, text);
+        let text = match parsed.text_part(0).map(|part| &part.body) {
+            Some(PartType::Text(text)) => text.to_string(),
+            _ => parsed
+                .body_html(0)
+                .map(|html| html_to_text(&strip_html_quotes(&html)))
+                .unwrap_or_default(),
+        };
+        let text = strip_quoted_reply(&text);
+        if text.is_empty() {
+            "(no readable content)".to_string()
+        } else {
+            text
+        }
+    }
+
+    /// Parse a raw RFC 5322 message.
+    fn parse_email(raw: &[u8]) -> Option<InboundEmail> {
+        let parsed = MessageParser::default().parse(raw)?;
+
+        let mut references: Vec<String> = parsed
+            .references()
+            .as_text_list()
+            .unwrap_or_default()
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        if let Some(parent) = parsed.in_reply_to().as_text() {
+            if !references.iter().any(|id| id == parent) {
+                references.push(parent.to_string());
+            }
+        }
+
+        let attachments = parsed
+            .attachments()
+            .enumerate()
+            .map(|(i, part)| EmailAttachment {
+                name: part
+                    .attachment_name()
+                    .map_or_else(|| format!("attachment-{}", i + 1), sanitize_file_name),
+                content_type: part
+                    .content_type()
+                    .map(|ct| match ct.subtype() {
+                        Some(sub) => format!("{}/{}", ct.ctype(), sub),
+                        None => ct.ctype().to_string(),
+                    })
+                    .unwrap_or_else(|| "application/octet-stream".into()),
+                data: part.contents().to_vec(),
+            })
+            .collect();
+
+        Some(InboundEmail {
+            message_id: parsed
+                .message_id()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("gen-{}", Uuid::new_v4())),
+            sender: Self::extract_sender(&parsed),
+            subject: parsed.subject().unwrap_or("(no subject)").to_string(),
+            body: Self::extract_text(&parsed),
+            references,
+            timestamp: parsed
+                .date()
+                .and_then(|d| u64::try_from(d.to_timestamp()).ok())
+                .unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0)
+                }),
+            attachments,
+        })
+    }
+
+    /// Save attachments under `<attachments_dir>/<message hash>/` and
+    /// describe them for the agent.
+    fn save_attachments(&self, email: &InboundEmail) -> Vec<String> {
+        let dir = self
+            .attachments_dir
+            .as_ref()
+            .map(|root| root.join(short_hash(&email.message_id)));
+        email
+            .attachments
+            .iter()
+            .map(|attachment| {
+                let label = format!("{} ({})", attachment.name, attachment.content_type);
+                if attachment.data.len() > MAX_ATTACHMENT_BYTES {
+                    return format!("[Attachment: {label}, too large to save]");
+                }
+                let Some(dir) = dir.as_ref() else {
+                    return format!("[Attachment: {label}]");
+                };
+                let path = dir.join(&attachment.name);
+                match std::fs::create_dir_all(dir)
+                    .and_then(|()| std::fs::write(&path, &attachment.data))
+                {
+                    Ok(()) => format!("[Attachment: {label} at {}]", path.display()),
+                    Err(e) => {
+                        warn!("Failed to save email attachment {}: {e}", path.display());
+                        format!("[Attachment: {label}, could not be saved]")
                     }
                 }
+            })
+            .collect()
+    }
+
+    /// Turn a raw email into a channel message, remembering its thread.
+    /// Returns `None` for duplicates, unparseable mail and blocked senders.
+    fn handle_raw(&self, raw: &[u8]) -> Option<ChannelMessage> {
+        let email = Self::parse_email(raw)?;
+        {
+            let mut seen = self.seen_messages.lock().unwrap();
+            if seen.contains(&email.message_id) {
+                return None;
+            }
+            if !self.is_sender_allowed(&email.sender) {
+                warn!("Blocked email from {}", email.sender);
+                return None;
             }
+            seen.insert(email.message_id.clone());
         }
-        "(no readable content)".to_string()
+
+        let thread_key = email.thread_key();
+        self.threads.lock().unwrap().update(&thread_key, |thread| {
+            thread.subject =
+                mail_parser::parsers::fields::thread::thread_name(&email.subject).to_string();
+            for id in &email.references {
+                thread.push(id);
+            }
+            thread.push(&email.message_id);
+        });
+
+        let mut content = format!("Subject: {}\n\n{}", email.subject, email.body);
+        for line in self.save_attachments(&email) {
+            content.push('\n');
+            content.push_str(&line);
+        }
+
+        Some(ChannelMessage {
+            id: email.message_id,
+            sender: format!("{}#{thread_key}", email.sender),
+            content,
+            channel: "email".to_string(),
+            timestamp: email.timestamp,
+        })
+    }
+
+    /// Build an outgoing email, threading it under `thread` when known.
+    /// Returns the message and its Message-ID (without angle brackets).
+    fn build_message(
+        &self,
+        message: &str,
+        address: &str,
+        thread: Option<&EmailThread>,
+    ) -> Result<(Message, String)> {
+        let explicit = message
+            .strip_prefix("Subject: ")
+            .and_then(|rest| rest.split_once('\n'));
+        let (subject, body) = match (explicit, thread) {
+            (Some((subject, body)), _) => (subject.trim().to_string(), body.trim()),
+            (None, Some(thread)) if !thread.subject.is_empty() => {
+                (format!("Re: {}", thread.subject), message)
+            }
+            _ => ("ZeroClaw Message".to_string(), message),
+        };
+
+        let domain = self
+            .config
+            .from_address
+            .rsplit_once('@')
+            .map_or("zeroclaw.local", |(_, domain)| domain.trim_end_matches('>'));
+        let message_id = format!("{}@{domain}", Uuid::new_v4());
+
+        let mut builder = Message::builder()
+            .from(self.config.from_address.parse()?)
+            .to(address.parse()?)
+            .subject(subject)
+            .message_id(Some(format!("<{message_id}>")));
+        if let Some(parent) = thread.and_then(|t| t.references.last()) {
+            let references = thread
+                .map(|t| {
+                    t.references
+                        .iter()
+                        .map(|id| format!("<{id}>"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .unwrap_or_default();
+            builder = builder
+                .in_reply_to(format!("<{parent}>"))
+                .references(references);
+        }
+        Ok((builder.body(body.to_string())?, message_id))
     }
 
     fn build_imap_tls_config() -> Result<std::sync::Arc<tokio_rustls::rustls::ClientConfig>> {
@@ -239,158 +869,60 @@ impl EmailChannel {
         Ok(Arc::new(tls_config))
     }
 
-    /// Fetch unseen emails via IMAP (blocking, run in spawn_blocking)
-    fn fetch_unseen_imap(config: &EmailConfig) -> Result<Vec<(String, String, String, u64)>> {
-        use rustls_pki_types::ServerName;
-        use tokio_rustls::rustls;
-
-        // Connect TCP
-        let tcp = TcpStream::connect((&*config.imap_host, config.imap_port))?;
-        tcp.set_read_timeout(Some(Duration::from_secs(30)))?;
-
-        // TLS
-        let tls_config = Self::build_imap_tls_config()?;
-        let server_name: ServerName<'_> = ServerName::try_from(config.imap_host.clone())?;
-        let conn = rustls::ClientConnection::new(tls_config, server_name)?;
-        let mut tls = rustls::StreamOwned::new(conn, tcp);
-
-        let read_line =
-            |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>| -> Result<String> {
-                let mut buf = Vec::new();
-                loop {
-                    let mut byte = [0u8; 1];
-                    match std::io::Read::read(tls, &mut byte) {
-                        Ok(0) => return Err(anyhow!("IMAP connection closed")),
-                        Ok(_) => {
-                            buf.push(byte[0]);
-                            if buf.ends_with(b"\r\n") {
-                                return Ok(String::from_utf8_lossy(&buf).to_string());
-                            }
-                        }
-                        Err(e) => return Err(e.into()),
-                    }
-                }
-            };
-
-        let send_cmd = |tls: &mut rustls::StreamOwned<rustls::ClientConnection, TcpStream>,
-                        tag: &str,
-                        cmd: &str|
-         -> Result<Vec<String>> {
-            let full = format!("{} {}\r\n", tag, cmd);
-            IoWrite::write_all(tls, full.as_bytes())?;
-            IoWrite::flush(tls)?;
-            let mut lines = Vec::new();
-            loop {
-                let line = read_line(tls)?;
-                let done = line.starts_with(tag);
-                lines.push(line);
-                if done {
-                    break;
-                }
+    /// Blocking IMAP loop: forward raw messages until the receiver is dropped,
+    /// reconnecting after errors.
+    fn run_imap_worker(config: &EmailConfig, raw_tx: &mpsc::Sender<Vec<u8>>) {
+        loop {
+            let result = ImapSession::connect(config)
+                .and_then(|mut session| Self::run_imap_session(&mut session, config, raw_tx));
+            match result {
+                Ok(()) => return,
+                Err(e) => error!("Email IMAP session failed: {e}"),
             }
-            Ok(lines)
-        };
-
-        // Read greeting
-        let _greeting = read_line(&mut tls)?;
-
-        // Login
-        let login_resp = send_cmd(
-            &mut tls,
-            "A1",
-            &format!("LOGIN \"{}\" \"{}\"", config.username, config.password),
-        )?;
-        if !login_resp.last().map_or(false, |l| l.contains("OK")) {
-            return Err(anyhow!("IMAP login failed"));
-        }
-
-        // Select folder
-        let _select = send_cmd(
-            &mut tls,
-            "A2",
-            &format!("SELECT \"{}\"", config.imap_folder),
-        )?;
-
-        // Search unseen
-        let search_resp = send_cmd(&mut tls, "A3", "SEARCH UNSEEN")?;
-        let mut uids: Vec<&str> = Vec::new();
-        for line in &search_resp {
-            if line.starts_with("* SEARCH") {
-                let parts: Vec<&str> = line.trim().split_whitespace().collect();
-                if parts.len() > 2 {
-                    uids.extend_from_slice(&parts[2..]);
-                }
+            if wait_or_stop(raw_tx, IMAP_RETRY) {
+                return;
             }
         }
+    }
 
-        let mut results = Vec::new();
-        let mut tag_counter = 4_u32; // Start after A1, A2, A3
-
-        for uid in &uids {
-            // Fetch RFC822 with unique tag
-            let fetch_tag = format!("A{}", tag_counter);
-            tag_counter += 1;
-            let fetch_resp = send_cmd(&mut tls, &fetch_tag, &format!("FETCH {} RFC822", uid))?;
-            // Reconstruct the raw email from the response (skip first and last lines)
-            let raw: String = fetch_resp
-                .iter()
-                .skip(1)
-                .take(fetch_resp.len().saturating_sub(2))
-                .cloned()
-                .collect();
-
-            if let Some(parsed) = MessageParser::default().parse(raw.as_bytes()) {
-                let sender = Self::extract_sender(&parsed);
-                let subject = parsed.subject().unwrap_or("(no subject)").to_string();
-                let body = Self::extract_text(&parsed);
-                let content = format!("Subject: {}\n\n{}", subject, body);
-                let msg_id = parsed
-                    .message_id()
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| format!("gen-{}", Uuid::new_v4()));
-                #[allow(clippy::cast_sign_loss)]
-                let ts = parsed
-                    .date()
-                    .map(|d| {
-                        let naive = chrono::NaiveDate::from_ymd_opt(
-                            d.year as i32,
-                            u32::from(d.month),
-                            u32::from(d.day),
-                        )
-                        .and_then(|date| {
-                            date.and_hms_opt(
-                                u32::from(d.hour),
-                                u32::from(d.minute),
-                                u32::from(d.second),
-                            )
-                        });
-                        naive.map_or(0, |n| n.and_utc().timestamp() as u64)
-                    })
-                    .unwrap_or_else(|| {
-                        SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .map(|d| d.as_secs())
-                            .unwrap_or(0)
-                    });
-
-                results.push((msg_id, sender, content, ts));
-            }
-
-            // Mark as seen with unique tag
-            let store_tag = format!("A{tag_counter}");
-            tag_counter += 1;
-            let _ = send_cmd(
-                &mut tls,
-                &store_tag,
-                &format!("STORE {uid} +FLAGS (\\Seen)"),
+    /// Log in, then alternate between fetching unseen mail and waiting
+    /// (IDLE, or sleeping `poll_interval_secs`). Returns once the receiver
+    /// is dropped.
+    fn run_imap_session<S: ImapStream>(
+        session: &mut ImapSession<S>,
+        config: &EmailConfig,
+        raw_tx: &mpsc::Sender<Vec<u8>>,
+    ) -> Result<()> {
+        session.login(&config.username, &config.password)?;
+        session.select(&config.imap_folder)?;
+        let idle = config.idle && session.supports_idle()?;
+        if idle {
+            info!("Email: IMAP IDLE on {}", config.imap_folder);
+        } else {
+            info!(
+                "Email polling every {}s on {}",
+                config.poll_interval_secs, config.imap_folder
             );
         }
 
-        // Logout with unique tag
-        let logout_tag = format!("A{tag_counter}");
-        let _ = send_cmd(&mut tls, &logout_tag, "LOGOUT");
-
-        Ok(results)
+        loop {
+            for raw in session.fetch_unseen()? {
+                if raw_tx.blocking_send(raw).is_err() {
+                    session.logout();
+                    return Ok(());
+                }
+            }
+            let stopped = if idle {
+                session.idle(IDLE_RENEW, &|| raw_tx.is_closed())?;
+                raw_tx.is_closed()
+            } else {
+                wait_or_stop(raw_tx, Duration::from_secs(config.poll_interval_secs))
+            };
+            if stopped {
+                session.logout();
+                return Ok(());
+            }
+        }
     }
 
     fn create_smtp_transport(&self) -> Result<SmtpTransport> {
@@ -416,76 +948,43 @@ impl Channel for EmailChannel {
         "email"
     }
 
-    async fn send(&self, message: &str, recipient: &str) -> Result<()> {
-        let (subject, body) = if message.starts_with("Subject: ") {
-            if let Some(pos) = message.find('\n') {
-                (&message[9..pos], message[pos + 1..].trim())
-            } else {
-                ("ZeroClaw Message", message)
-            }
-        } else {
-            ("ZeroClaw Message", message)
-        };
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_senders").then_some(&self.allowed_senders)
+    }
 
-        let email = Message::builder()
-            .from(self.config.from_address.parse()?)
-            .to(recipient.parse()?)
-            .subject(subject)
-            .body(body.to_string())?;
+    async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+        let (address, thread_key) = parse_target(recipient);
+        let thread = thread_key.and_then(|key| self.threads.lock().unwrap().get(key));
+        let (email, message_id) = self.build_message(message, address, thread.as_ref())?;
 
         let transport = self.create_smtp_transport()?;
         transport.send(&email)?;
-        info!("Email sent to {}", recipient);
+        if let Some(key) = thread_key {
+            self.threads
+                .lock()
+                .unwrap()
+                .update(key, |thread| thread.push(&message_id));
+        }
+        info!("Email sent to {}", address);
         Ok(())
     }
 
     async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
-        info!(
-            "Email polling every {}s on {}",
-            self.config.poll_interval_secs, self.config.imap_folder
-        );
-        let mut tick = interval(Duration::from_secs(self.config.poll_interval_secs));
+        let (raw_tx, mut raw_rx) = mpsc::channel::<Vec<u8>>(32);
         let config = self.config.clone();
+        let worker = tokio::task::spawn_blocking(move || Self::run_imap_worker(&config, &raw_tx));
 
-        loop {
-            tick.tick().await;
-            let cfg = config.clone();
-            match tokio::task::spawn_blocking(move || Self::fetch_unseen_imap(&cfg)).await {
-                Ok(Ok(messages)) => {
-                    for (id, sender, content, ts) in messages {
-                        {
-                            let mut seen = self.seen_messages.lock().unwrap();
-                            if seen.contains(&id) {
-                                continue;
-                            }
-                            if !self.is_sender_allowed(&sender) {
-                                warn!("Blocked email from {}", sender);
-                                continue;
-                            }
-                            seen.insert(id.clone());
-                        } // MutexGuard dropped before await
-                        let msg = ChannelMessage {
-                            id,
-                            sender,
-                            content,
-                            channel: "email".to_string(),
-                            timestamp: ts,
-                        };
-                        if tx.send(msg).await.is_err() {
-                            return Ok(());
-                        }
-                    }
-                }
-                Ok(Err(e)) => {
-                    error!("Email poll failed: {}", e);
-                    sleep(Duration::from_secs(10)).await;
-                }
-                Err(e) => {
-                    error!("Email poll task panicked: {}", e);
-                    sleep(Duration::from_secs(10)).await;
+        while let Some(raw) = raw_rx.recv().await {
+            if let Some(msg) = self.handle_raw(&raw) {
+                if tx.send(msg).await.is_err() {
+                    break;
                 }
             }
         }
+        drop(raw_rx);
+        worker
+            .await
+            .map_err(|e| anyhow!("Email IMAP worker panicked: {e}"))
     }
 
     async fn health_check(&self) -> bool {
@@ -501,7 +1000,8 @@ impl Channel for EmailChannel {
 
 #[cfg(test)]
 mod tests {
-    use super::{BoundedSeenSet, EmailChannel};
+    use super::*;
+    use tempfile::TempDir;
 
     #[test]
     fn build_imap_tls_config_succeeds() {
@@ -570,4 +1070,298 @@ mod tests {
         assert!(set.contains("b"));
         assert_eq!(set.len(), 1);
     }
+
+    const ROOT: &str = "Message-ID: <root@example.com>\r\n\
+        From: Alice <alice@example.com>\r\n\
+        To: bot@zeroclaw.dev\r\n\
+        Subject: Quarterly report\r\n\
+        Date: Mon, 6 Jan 2025 10:00:00 +0000\r\n\
+        \r\n\
+        Can you summarise the numbers?\r\n";
+
+    const REPLY: &str = "Message-ID: <reply@example.com>\r\n\
+        In-Reply-To: <bot-1@zeroclaw.dev>\r\n\
+        References: <root@example.com> <bot-1@zeroclaw.dev>\r\n\
+        From: alice@example.com\r\n\
+        Subject: Re: Quarterly report\r\n\
+        \r\n\
+        Thanks, and Q2?\r\n\
+        \r\n\
+        On Mon, 6 Jan 2025 at 10:05, ZeroClaw <bot@zeroclaw.dev> wrote:\r\n\
+        > Revenue grew 12%.\r\n";
+
+    fn channel(allowed: &[&str]) -> EmailChannel {
+        EmailChannel::new(EmailConfig {
+            from_address: "bot@zeroclaw.dev".into(),
+            allowed_senders: allowed.iter().map(|s| s.to_string()).collect(),
+            ..EmailConfig::default()
+        })
+    }
+
+    #[test]
+    fn targets_split_into_address_and_thread() {
+        assert_eq!(parse_target("a@b.com"), ("a@b.com", None));
+        assert_eq!(
+            parse_target("a@b.com#0123456789abcdef"),
+            ("a@b.com", Some("0123456789abcdef"))
+        );
+        // A '#' in the local part is not a thread key.
+        assert_eq!(parse_target("a#b@c.com"), ("a#b@c.com", None));
+    }
+
+    #[test]
+    fn quoted_replies_and_signatures_are_stripped() {
+        assert_eq!(
+            strip_quoted_reply(
+                "Sure.\n\nOn Mon, Jan 6, 2025 at 10:05 AM Bot <\nbot@x.dev> wrote:\n> old"
+            ),
+            "Sure."
+        );
+        assert_eq!(
+            strip_quoted_reply("Yes\n-----Original Message-----\nFrom: bot"),
+            "Yes"
+        );
+        assert_eq!(
+            strip_quoted_reply("Inline\n> quoted\nanswer\n-- \nAlice"),
+            "Inline\nanswer"
+        );
+    }
+
+    #[test]
+    fn html_mail_is_converted_without_quotes() {
+        let raw = "Message-ID: <html@example.com>\r\n\
+            From: alice@example.com\r\n\
+            Subject: Hi\r\n\
+            Content-Type: text/html\r\n\
+            \r\n\
+            <p>Sounds <b>good</b></p><BLOCKQUOTE type=cite><p>old <blockquote>older</blockquote> stuff</p></BLOCKQUOTE><p>Bye</p>\r\n";
+        let email = EmailChannel::parse_email(raw.as_bytes()).unwrap();
+        assert!(email.body.contains("Sounds good"), "{}", email.body);
+        assert!(email.body.contains("Bye"));
+        assert!(!email.body.contains("old"));
+        assert_eq!(EmailChannel::strip_html("<p>a</p>\n<p>b</p>"), "a b");
+    }
+
+    #[test]
+    fn replies_share_the_thread_of_the_original() {
+        let ch = channel(&["example.com"]);
+        let first = ch.handle_raw(ROOT.as_bytes()).unwrap();
+        let (address, key) = parse_target(&first.sender);
+        assert_eq!(address, "alice@example.com");
+        let key = key.unwrap().to_string();
+        assert_eq!(
+            first.content,
+            "Subject: Quarterly report\n\nCan you summarise the numbers?"
+        );
+        assert_eq!(first.timestamp, 1_736_157_600);
+
+        let second = ch.handle_raw(REPLY.as_bytes()).unwrap();
+        assert_eq!(second.sender, first.sender);
+        assert_eq!(
+            second.content,
+            "Subject: Re: Quarterly report\n\nThanks, and Q2?"
+        );
+
+        let thread = ch.threads.lock().unwrap().get(&key).unwrap();
+        assert_eq!(thread.subject, "Quarterly report");
+        assert_eq!(
+            thread.references,
+            vec![
+                "root@example.com",
+                "bot-1@zeroclaw.dev",
+                "reply@example.com"
+            ]
+        );
+
+        // Duplicates and blocked senders are dropped.
+        assert!(ch.handle_raw(REPLY.as_bytes()).is_none());
+        assert!(channel(&["other.com"])
+            .handle_raw(ROOT.as_bytes())
+            .is_none());
+    }
+
+    #[test]
+    fn replies_carry_threading_headers() {
+        let ch = channel(&["*"]);
+        let thread = EmailThread {
+            subject: "Quarterly report".into(),
+            references: vec!["root@example.com".into(), "reply@example.com".into()],
+        };
+
+        let (email, id) = ch
+            .build_message("Q2 was flat.", "alice@example.com", Some(&thread))
+            .unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Subject: Re: Quarterly report"));
+        assert!(formatted.contains("In-Reply-To: <reply@example.com>"));
+        assert!(formatted.contains("References: <root@example.com> <reply@example.com>"));
+        assert!(formatted.contains(&format!("Message-ID: <{id}>")));
+        assert!(id.ends_with("@zeroclaw.dev"));
+
+        let (email, _) = ch
+            .build_message(
+                "Subject: Weekly digest\nAll quiet.",
+                "alice@example.com",
+                None,
+            )
+            .unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Subject: Weekly digest"));
+        assert!(!formatted.contains("In-Reply-To"));
+    }
+
+    #[test]
+    fn thread_references_keep_root_and_newest() {
+        let mut thread = EmailThread::default();
+        for i in 0..30 {
+            thread.push(&format!("m{i}@x"));
+        }
+        thread.push("m29@x");
+        assert_eq!(thread.references.len(), MAX_REFERENCES);
+        assert_eq!(thread.references[0], "m0@x");
+        assert_eq!(thread.references[1], "m11@x");
+        assert_eq!(thread.references.last().unwrap(), "m29@x");
+    }
+
+    #[test]
+    fn attachments_are_saved_to_the_workspace() {
+        let tmp = TempDir::new().unwrap();
+        let ch = EmailChannel::from_config(
+            &EmailConfig {
+                allowed_senders: vec!["*".into()],
+                ..EmailConfig::default()
+            },
+            tmp.path(),
+        );
+        let raw = "Message-ID: <att@example.com>\r\n\
+            From: alice@example.com\r\n\
+            Subject: Logs\r\n\
+            MIME-Version: 1.0\r\n\
+            Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+            \r\n\
+            --b1\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            See attached.\r\n\
+            --b1\r\n\
+            Content-Type: text/plain; name=\"app.log\"\r\n\
+            Content-Disposition: attachment; filename=\"../../etc/app.log\"\r\n\
+            \r\n\
+            line one\r\n\
+            --b1--\r\n";
+
+        let msg = ch.handle_raw(raw.as_bytes()).unwrap();
+        let path = tmp
+            .path()
+            .join("email_attachments")
+            .join(short_hash("att@example.com"))
+            .join("app.log");
+        assert!(msg.content.starts_with("Subject: Logs\n\nSee attached.\n"));
+        assert!(msg.content.contains(&format!(
+            "[Attachment: app.log (text/plain) at {}]",
+            path.display()
+        )));
+        assert_eq!(std::fs::read_to_string(path).unwrap().trim(), "line one");
+    }
+
+    /// Scripted IMAP server: answers commands by verb and records them.
+    fn spawn_imap_server(
+        messages: Vec<(u32, String)>,
+    ) -> (TcpStream, std::sync::Arc<Mutex<Vec<String>>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let log = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let server_log = std::sync::Arc::clone(&log);
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut out = stream;
+            let mut pending = messages.into_iter().collect::<VecDeque<_>>();
+            let mut searches = 0;
+            let mut idles = 0;
+            out.write_all(b"* OK fake IMAP ready\r\n").unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                let command = line.trim_end().to_string();
+                line.clear();
+                server_log.lock().unwrap().push(command.clone());
+                let (tag, rest) = command.split_once(' ').unwrap_or((&command, ""));
+                let reply = if rest.starts_with("CAPABILITY") {
+                    format!("* CAPABILITY IMAP4rev1 IDLE\r\n{tag} OK\r\n")
+                } else if rest.starts_with("UID SEARCH") {
+                    searches += 1;
+                    // The first message is there at login, the second arrives during IDLE.
+                    let uids = match searches {
+                        1 => pending.front().map(|(uid, _)| uid.to_string()),
+                        2 => pending.back().map(|(uid, _)| uid.to_string()),
+                        _ => None,
+                    };
+                    format!("* SEARCH {}\r\n{tag} OK\r\n", uids.unwrap_or_default())
+                } else if let Some(fetch) = rest.strip_prefix("UID FETCH ") {
+                    let uid: u32 = fetch.split(' ').next().unwrap().parse().unwrap();
+                    let idx = pending.iter().position(|(u, _)| *u == uid).unwrap();
+                    let (_, raw) = pending.remove(idx).unwrap();
+                    format!(
+                        "* 1 FETCH (UID {uid} BODY[] {{{}}}\r\n{raw})\r\n{tag} OK\r\n",
+                        raw.len()
+                    )
+                } else if rest == "IDLE" {
+                    idles += 1;
+                    out.write_all(b"+ idling\r\n").unwrap();
+                    if idles == 1 {
+                        out.write_all(b"* 2 EXISTS\r\n").unwrap();
+                    }
+                    reader.read_line(&mut line).unwrap();
+                    server_log.lock().unwrap().push(line.trim_end().to_string());
+                    line.clear();
+                    format!("{tag} OK IDLE terminated\r\n")
+                } else if rest == "LOGOUT" {
+                    out.write_all(format!("* BYE\r\n{tag} OK\r\n").as_bytes())
+                        .unwrap();
+                    break;
+                } else {
+                    format!("{tag} OK\r\n")
+                };
+                out.write_all(reply.as_bytes()).unwrap();
+            }
+        });
+        (TcpStream::connect(addr).unwrap(), log)
+    }
+
+    #[test]
+    fn imap_session_fetches_then_idles_for_new_mail() {
+        let (stream, log) = spawn_imap_server(vec![
+            (7, ROOT.replace("\r\n", "\n")),
+            (8, REPLY.to_string()),
+        ]);
+        let mut session = ImapSession::new(stream).unwrap();
+        session.idle_tick = Duration::from_millis(20);
+        let config = EmailConfig {
+            username: "bot".into(),
+            password: "p\"w".into(),
+            ..EmailConfig::default()
+        };
+        let (raw_tx, mut raw_rx) = mpsc::channel(8);
+        let worker = std::thread::spawn(move || {
+            EmailChannel::run_imap_session(&mut session, &config, &raw_tx)
+        });
+
+        let first = raw_rx.blocking_recv().unwrap();
+        assert_eq!(first, ROOT.replace("\r\n", "\n").into_bytes());
+        let second = raw_rx.blocking_recv().unwrap();
+        assert_eq!(second, REPLY.as_bytes());
+        drop(raw_rx);
+        worker.join().unwrap().unwrap();
+
+        let log = log.lock().unwrap().clone();
+        assert_eq!(log[0], r#"A1 LOGIN "bot" "p\"w""#);
+        assert_eq!(log[1], r#"A2 SELECT "INBOX""#);
+        assert!(log.iter().any(|c| c.ends_with("UID FETCH 7 BODY.PEEK[]")));
+        assert!(log
+            .iter()
+            .any(|c| c.ends_with("UID STORE 7 +FLAGS.SILENT (\\Seen)")));
+        assert!(log.iter().any(|c| c.ends_with(" IDLE")));
+        assert!(log.iter().any(|c| c == "DONE"));
+        assert!(log.last().unwrap().ends_with("LOGOUT"));
+    }
 }
@@ -0,0 +1,372 @@
+use super::traits::{Channel, ChannelMessage};
+use crate::config::{HomeAssistantConfig, HomeAssistantTrigger};
+use crate::tools::home_assistant::HomeAssistantClient;
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Notification id used for agent replies, so repeated replies about the same
+/// entity replace each other instead of piling up.
+const NOTIFICATION_PREFIX: &str = "zeroclaw_";
+
+/// Home Assistant channel — turns `state_changed` events from the HA
+/// WebSocket API into agent prompts.
+///
+/// Each configured trigger watches an entity (or a whole `domain.*`); when
+/// its state value changes, the trigger prompt is sent to the agent with the
+/// entity id as sender. Replies are posted back as persistent notifications.
+pub struct HomeAssistantChannel {
+    client: HomeAssistantClient,
+    triggers: Vec<HomeAssistantTrigger>,
+}
+
+impl HomeAssistantChannel {
+    pub fn new(client: HomeAssistantClient, triggers: Vec<HomeAssistantTrigger>) -> Self {
+        Self { client, triggers }
+    }
+
+    pub fn from_config(config: &HomeAssistantConfig) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            HomeAssistantClient::from_config(config)?,
+            config.triggers.clone(),
+        ))
+    }
+
+    /// Build the agent prompt for a `state_changed` event payload, if any
+    /// trigger matches it. Attribute-only updates are ignored.
+    fn prompt_for_event(&self, data: &Value) -> Option<(String, String)> {
+        let entity_id = data.get("entity_id")?.as_str()?;
+        let new_state = data.get("new_state")?;
+        let to = new_state.get("state")?.as_str()?;
+        let from = data
+            .get("old_state")
+            .and_then(|s| s.get("state"))
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        if from == to {
+            return None;
+        }
+
+        let trigger = self.triggers.iter().find(|t| {
+            entity_matches(&t.entity_id, entity_id) && t.to.as_deref().is_none_or(|want| want == to)
+        })?;
+
+        let name = new_state
+            .get("attributes")
+            .and_then(|a| a.get("friendly_name"))
+            .and_then(Value::as_str)
+            .unwrap_or(entity_id);
+        let template = trigger
+            .prompt
+            .as_deref()
+            .unwrap_or("Home Assistant: {name} ({entity_id}) changed from {from} to {to}.");
+        let prompt = template
+            .replace("{entity_id}", entity_id)
+            .replace("{name}", name)
+            .replace("{from}", from)
+            .replace("{to}", to);
+        Some((entity_id.to_string(), prompt))
+    }
+}
+
+/// Match an entity id against a trigger pattern (exact or `domain.*`).
+fn entity_matches(pattern: &str, entity_id: &str) -> bool {
+    let pattern = pattern.trim();
+    match pattern.strip_suffix(".*") {
+        Some(domain) => entity_id.split_once('.').is_some_and(|(d, _)| d == domain),
+        None => pattern == entity_id,
+    }
+}
+
+#[async_trait]
+impl Channel for HomeAssistantChannel {
+    fn name(&self) -> &str {
+        "home_assistant"
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+        let notification_id: String = recipient
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        self.client
+            .call_service(
+                "persistent_notification",
+                "create",
+                &json!({
+                    "title": "ZeroClaw",
+                    "message": message,
+                    "notification_id": format!("{NOTIFICATION_PREFIX}{notification_id}"),
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        let url = self.client.websocket_url();
+        let (ws_stream, _) = tokio_tungstenite::connect_async(&url).await?;
+        let (mut write, mut read) = ws_stream.split();
+
+        // Handshake: auth_required → auth → auth_ok
+        let mut authenticated = false;
+        while let Some(msg) = read.next().await {
+            let Message::Text(text) = msg? else {
+                continue;
+            };
+            let frame: Value = serde_json::from_str(&text)?;
+            match frame.get("type").and_then(Value::as_str) {
+                Some("auth_required") => {
+                    let auth = json!({"type": "auth", "access_token": self.client.token()});
+                    write.send(Message::Text(auth.to_string())).await?;
+                }
+                Some("auth_ok") => {
+                    authenticated = true;
+                    break;
+                }
+                Some("auth_invalid") => {
+                    let reason = frame
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("invalid token");
+                    anyhow::bail!("Home Assistant rejected the access token: {reason}");
+                }
+                _ => {}
+            }
+        }
+        if !authenticated {
+            anyhow::bail!("Home Assistant closed the WebSocket during authentication");
+        }
+
+        let subscribe = json!({"id": 1, "type": "subscribe_events", "event_type": "state_changed"});
+        write.send(Message::Text(subscribe.to_string())).await?;
+        tracing::info!("Home Assistant: subscribed to state changes");
+
+        while let Some(msg) = read.next().await {
+            let text = match msg? {
+                Message::Text(text) => text,
+                Message::Ping(payload) => {
+                    write.send(Message::Pong(payload)).await?;
+                    continue;
+                }
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(frame) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            match frame.get("type").and_then(Value::as_str) {
+                Some("result") if frame.get("success") == Some(&Value::Bool(false)) => {
+                    anyhow::bail!("Home Assistant subscription failed: {}", frame["error"]);
+                }
+                Some("event") => {}
+                _ => continue,
+            }
+            let Some(data) = frame.get("event").and_then(|e| e.get("data")) else {
+                continue;
+            };
+            let Some((entity_id, content)) = self.prompt_for_event(data) else {
+                continue;
+            };
+
+            let msg = ChannelMessage {
+                id: Uuid::new_v4().to_string(),
+                sender: entity_id,
+                content,
+                channel: "home_assistant".to_string(),
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            };
+            if tx.send(msg).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        anyhow::bail!("Home Assistant WebSocket closed")
+    }
+
+    async fn health_check(&self) -> bool {
+        self.client.ping().await.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+    use tokio::net::TcpListener;
+
+    fn trigger(entity_id: &str, to: Option<&str>, prompt: Option<&str>) -> HomeAssistantTrigger {
+        HomeAssistantTrigger {
+            entity_id: entity_id.into(),
+            to: to.map(Into::into),
+            prompt: prompt.map(Into::into),
+        }
+    }
+
+    fn channel(base: &str, triggers: Vec<HomeAssistantTrigger>) -> HomeAssistantChannel {
+        HomeAssistantChannel::new(HomeAssistantClient::new(base, "test-token", 5), triggers)
+    }
+
+    fn state_changed(entity_id: &str, from: &str, to: &str) -> Value {
+        json!({
+            "entity_id": entity_id,
+            "old_state": {"entity_id": entity_id, "state": from},
+            "new_state": {"entity_id": entity_id, "state": to, "attributes": {"friendly_name": "Front Door"}}
+        })
+    }
+
+    #[test]
+    fn entity_patterns_match_exact_and_domain() {
+        assert!(entity_matches(
+            "binary_sensor.front_door",
+            "binary_sensor.front_door"
+        ));
+        assert!(entity_matches("binary_sensor.*", "binary_sensor.back_door"));
+        assert!(!entity_matches("binary_sensor.*", "sensor.temperature"));
+        assert!(!entity_matches(
+            "binary_sensor.front",
+            "binary_sensor.front_door"
+        ));
+    }
+
+    #[test]
+    fn prompt_uses_template_and_filters() {
+        let ch = channel(
+            "http://ha.local",
+            vec![trigger(
+                "binary_sensor.*",
+                Some("on"),
+                Some("{name} opened ({from} → {to}); check the cameras"),
+            )],
+        );
+
+        let (sender, prompt) = ch
+            .prompt_for_event(&state_changed("binary_sensor.front_door", "off", "on"))
+            .unwrap();
+        assert_eq!(sender, "binary_sensor.front_door");
+        assert_eq!(prompt, "Front Door opened (off → on); check the cameras");
+
+        // Wrong target state, attribute-only update, unwatched entity
+        assert!(ch
+            .prompt_for_event(&state_changed("binary_sensor.front_door", "on", "off"))
+            .is_none());
+        assert!(ch
+            .prompt_for_event(&state_changed("binary_sensor.front_door", "on", "on"))
+            .is_none());
+        assert!(ch
+            .prompt_for_event(&state_changed("light.kitchen", "off", "on"))
+            .is_none());
+    }
+
+    #[test]
+    fn default_prompt_describes_change() {
+        let ch = channel(
+            "http://ha.local",
+            vec![trigger("lock.front_door", None, None)],
+        );
+        let (_, prompt) = ch
+            .prompt_for_event(&state_changed("lock.front_door", "locked", "unlocked"))
+            .unwrap();
+        assert_eq!(
+            prompt,
+            "Home Assistant: Front Door (lock.front_door) changed from locked to unlocked."
+        );
+    }
+
+    async fn fake_websocket(mut socket: WebSocket) {
+        let send = |v: Value| WsMessage::Text(v.to_string().into());
+        socket
+            .send(send(
+                json!({"type": "auth_required", "ha_version": "2024.1.0"}),
+            ))
+            .await
+            .unwrap();
+        let Some(Ok(WsMessage::Text(auth))) = socket.recv().await else {
+            return;
+        };
+        let auth: Value = serde_json::from_str(&auth).unwrap();
+        if auth["access_token"] != "test-token" {
+            let _ = socket
+                .send(send(
+                    json!({"type": "auth_invalid", "message": "Invalid access token"}),
+                ))
+                .await;
+            return;
+        }
+        socket.send(send(json!({"type": "auth_ok"}))).await.unwrap();
+
+        let Some(Ok(WsMessage::Text(sub))) = socket.recv().await else {
+            return;
+        };
+        let sub: Value = serde_json::from_str(&sub).unwrap();
+        assert_eq!(sub["type"], "subscribe_events");
+        assert_eq!(sub["event_type"], "state_changed");
+        socket
+            .send(send(
+                json!({"id": 1, "type": "result", "success": true, "result": null}),
+            ))
+            .await
+            .unwrap();
+
+        for data in [
+            state_changed("light.kitchen", "off", "on"),
+            state_changed("binary_sensor.front_door", "off", "on"),
+        ] {
+            let event = json!({"id": 1, "type": "event", "event": {"event_type": "state_changed", "data": data}});
+            socket.send(send(event)).await.unwrap();
+        }
+        // Keep the socket open until the client hangs up.
+        while socket.recv().await.is_some() {}
+    }
+
+    async fn spawn_fake_websocket() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route(
+            "/api/websocket",
+            get(
+                |ws: WebSocketUpgrade| async move { ws.on_upgrade(fake_websocket).into_response() },
+            ),
+        );
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn listen_authenticates_and_emits_matching_events() {
+        let base = spawn_fake_websocket().await;
+        let ch = channel(&base, vec![trigger("binary_sensor.front_door", None, None)]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+
+        let listener = tokio::spawn(async move { ch.listen(tx).await });
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.channel, "home_assistant");
+        assert_eq!(msg.sender, "binary_sensor.front_door");
+        assert!(msg.content.contains("changed from off to on"));
+        listener.abort();
+    }
+
+    #[tokio::test]
+    async fn listen_fails_on_invalid_token() {
+        let base = spawn_fake_websocket().await;
+        let ch = HomeAssistantChannel::new(HomeAssistantClient::new(&base, "wrong", 5), vec![]);
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        let err = ch.listen(tx).await.unwrap_err();
+        assert!(err.to_string().contains("rejected the access token"));
+    }
+}
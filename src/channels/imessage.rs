@@ -1,4 +1,4 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use directories::UserDirs;
 use rusqlite::{Connection, OpenFlags};
@@ -9,23 +9,24 @@ use tokio::sync::mpsc;
 /// Polls the Messages database for new messages and sends replies via `osascript`.
 #[derive(Clone)]
 pub struct IMessageChannel {
-    allowed_contacts: Vec<String>,
+    allowed_contacts: Allowlist,
     poll_interval_secs: u64,
 }
 
 impl IMessageChannel {
     pub fn new(allowed_contacts: Vec<String>) -> Self {
         Self {
-            allowed_contacts,
+            allowed_contacts: Allowlist::new(allowed_contacts),
             poll_interval_secs: 3,
         }
     }
 
     fn is_contact_allowed(&self, sender: &str) -> bool {
-        if self.allowed_contacts.iter().any(|u| u == "*") {
+        if self.allowed_contacts.read().iter().any(|u| u == "*") {
             return true;
         }
         self.allowed_contacts
+            .read()
             .iter()
             .any(|u| u.eq_ignore_ascii_case(sender))
     }
@@ -95,6 +96,10 @@ impl Channel for IMessageChannel {
         "imessage"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_contacts").then_some(&self.allowed_contacts)
+    }
+
     async fn send(&self, message: &str, target: &str) -> anyhow::Result<()> {
         // Defense-in-depth: validate target format before any interpolation
         if !is_valid_imessage_target(target) {
@@ -266,14 +271,14 @@ mod tests {
     #[test]
     fn creates_with_contacts() {
         let ch = IMessageChannel::new(vec!["+1234567890".into()]);
-        assert_eq!(ch.allowed_contacts.len(), 1);
+        assert_eq!(ch.allowed_contacts.read().len(), 1);
         assert_eq!(ch.poll_interval_secs, 3);
     }
 
     #[test]
     fn creates_with_empty_contacts() {
         let ch = IMessageChannel::new(vec![]);
-        assert!(ch.allowed_contacts.is_empty());
+        assert!(ch.allowed_contacts.read().is_empty());
     }
 
     #[test]
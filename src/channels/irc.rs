@@ -1,4 +1,4 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -26,7 +26,7 @@ pub struct IrcChannel {
     nickname: String,
     username: String,
     channels: Vec<String>,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
     server_password: Option<String>,
     nickserv_password: Option<String>,
     sasl_password: Option<String>,
@@ -241,7 +241,7 @@ impl IrcChannel {
             nickname,
             username,
             channels,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
             server_password,
             nickserv_password,
             sasl_password,
@@ -251,10 +251,11 @@ impl IrcChannel {
     }
 
     fn is_user_allowed(&self, nick: &str) -> bool {
-        if self.allowed_users.iter().any(|u| u == "*") {
+        if self.allowed_users.read().iter().any(|u| u == "*") {
             return true;
         }
         self.allowed_users
+            .read()
             .iter()
             .any(|u| u.eq_ignore_ascii_case(nick))
     }
@@ -343,6 +344,10 @@ impl Channel for IrcChannel {
         "irc"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let mut guard = self.writer.lock().await;
         let writer = guard
@@ -913,7 +918,7 @@ mod tests {
         assert_eq!(ch.nickname, "zcbot");
         assert_eq!(ch.username, "zeroclaw");
         assert_eq!(ch.channels, vec!["#test"]);
-        assert_eq!(ch.allowed_users, vec!["alice"]);
+        assert_eq!(*ch.allowed_users.read(), ["alice"]);
         assert_eq!(ch.server_password.as_deref(), Some("serverpass"));
         assert_eq!(ch.nickserv_password.as_deref(), Some("nspass"));
         assert_eq!(ch.sasl_password.as_deref(), Some("saslpass"));
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -6,13 +6,16 @@ use uuid::Uuid;
 
 const FEISHU_BASE_URL: &str = "https://open.feishu.cn/open-apis";
 
+/// Port the event callback server listens on.
+pub const CALLBACK_PORT: u16 = 9898;
+
 /// Lark/Feishu channel — receives events via HTTP callback, sends via Open API
 pub struct LarkChannel {
     app_id: String,
     app_secret: String,
     verification_token: String,
     port: u16,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
     client: reqwest::Client,
     /// Cached tenant access token
     tenant_token: Arc<RwLock<Option<String>>>,
@@ -31,7 +34,7 @@ impl LarkChannel {
             app_secret,
             verification_token,
             port,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
             client: reqwest::Client::new(),
             tenant_token: Arc::new(RwLock::new(None)),
         }
@@ -39,7 +42,10 @@ impl LarkChannel {
 
     /// Check if a user open_id is allowed
     fn is_user_allowed(&self, open_id: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == open_id)
+        self.allowed_users
+            .read()
+            .iter()
+            .any(|u| u == "*" || u == open_id)
     }
 
     /// Get or refresh tenant access token
@@ -189,6 +195,10 @@ impl Channel for LarkChannel {
         "lark"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let token = self.get_tenant_access_token().await?;
         let url = format!("{FEISHU_BASE_URL}/im/v1/messages?receive_id_type=chat_id");
@@ -284,13 +294,17 @@ impl Channel for LarkChannel {
 
         let state = AppState {
             verification_token: self.verification_token.clone(),
-            channel: Arc::new(LarkChannel::new(
-                self.app_id.clone(),
-                self.app_secret.clone(),
-                self.verification_token.clone(),
-                self.port,
-                self.allowed_users.clone(),
-            )),
+            channel: Arc::new(LarkChannel {
+                // Shared, so reloaded allowlists reach the callback handler.
+                allowed_users: self.allowed_users.clone(),
+                ..LarkChannel::new(
+                    self.app_id.clone(),
+                    self.app_secret.clone(),
+                    self.verification_token.clone(),
+                    self.port,
+                    Vec::new(),
+                )
+            }),
             tx,
         };
 
@@ -1,39 +1,111 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+#[cfg(feature = "matrix-e2ee")]
+use crate::channels::matrix_e2ee::{Device, MatrixCrypto};
+use crate::channels::traits::{Allowlist, Channel, ChannelMessage};
+use crate::security::SecretStore;
+use anyhow::Context;
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, Method};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
+/// Separates room and thread root in reply targets; event IDs start with `$`.
+const THREAD_SEPARATOR: &str = "/$";
+/// Long-poll timeout for `/sync`.
+const SYNC_TIMEOUT_MS: u64 = 30_000;
+/// Pause before retrying a failed `/sync`.
+const SYNC_RETRY_SECS: u64 = 5;
+/// Threads remembered per room for reply fallbacks.
+const MAX_TRACKED_THREADS: usize = 256;
+/// Rooms joined through invites, kept in the store directory.
+const JOINED_ROOMS_FILE: &str = "joined_rooms.json";
+
 /// Matrix channel using the Client-Server API (no SDK needed).
 /// Connects to any Matrix homeserver (Element, Synapse, etc.).
+///
+/// Listens in `room_id`, the configured `rooms` and any room an allowed user
+/// invites the bot to. Reply targets are `<room id>` or, for threaded
+/// replies, `<room id>/<thread root event id>`. With the `matrix-e2ee`
+/// feature and `e2ee = true`, encrypted rooms are read and written too.
 #[derive(Clone)]
 pub struct MatrixChannel {
     homeserver: String,
     access_token: String,
     room_id: String,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
+    rooms: Vec<String>,
+    auto_join: bool,
+    thread_replies: bool,
+    store_dir: Option<PathBuf>,
+    sessions: Arc<parking_lot::Mutex<RoomSessions>>,
+    #[cfg(feature = "matrix-e2ee")]
+    e2ee: Option<Arc<E2ee>>,
     client: Client,
 }
 
+/// Crypto machine, created on first use because it needs the device ID.
+#[cfg(feature = "matrix-e2ee")]
+struct E2ee {
+    secrets: SecretStore,
+    store_dir: PathBuf,
+    crypto: tokio::sync::OnceCell<MatrixCrypto>,
+}
+
+/// Per-room state shared by the listener and `send`.
+#[derive(Debug, Default)]
+struct RoomSessions {
+    /// Rooms joined by accepting an invite
+    joined: HashSet<String>,
+    rooms: HashMap<String, RoomSession>,
+}
+
+#[derive(Debug, Default)]
+struct RoomSession {
+    /// `None` until the room's `m.room.encryption` state is known
+    encrypted: Option<bool>,
+    /// Thread root → latest event seen in that thread
+    threads: HashMap<String, String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct SyncResponse {
     next_batch: String,
     #[serde(default)]
     rooms: Rooms,
+    #[serde(default)]
+    to_device: ToDevice,
+    #[serde(default)]
+    device_one_time_keys_count: HashMap<String, u64>,
 }
 
 #[derive(Debug, Deserialize, Default)]
 struct Rooms {
     #[serde(default)]
-    join: std::collections::HashMap<String, JoinedRoom>,
+    join: HashMap<String, JoinedRoom>,
+    #[serde(default)]
+    invite: HashMap<String, InvitedRoom>,
+    #[serde(default)]
+    leave: HashMap<String, Value>,
 }
 
 #[derive(Debug, Deserialize)]
 struct JoinedRoom {
+    #[serde(default)]
+    state: Timeline,
     #[serde(default)]
     timeline: Timeline,
 }
 
+#[derive(Debug, Deserialize, Default)]
+struct InvitedRoom {
+    #[serde(default)]
+    invite_state: Timeline,
+}
+
 #[derive(Debug, Deserialize, Default)]
 struct Timeline {
     #[serde(default)]
@@ -46,6 +118,10 @@ struct TimelineEvent {
     event_type: String,
     sender: String,
     #[serde(default)]
+    event_id: String,
+    #[serde(default)]
+    state_key: Option<String>,
+    #[serde(default)]
     content: EventContent,
 }
 
@@ -55,11 +131,91 @@ struct EventContent {
     body: Option<String>,
     #[serde(default)]
     msgtype: Option<String>,
+    #[serde(default, rename = "m.relates_to")]
+    relates_to: Option<RelatesTo>,
+    /// `m.room.member`
+    #[serde(default)]
+    membership: Option<String>,
+    /// `m.room.encrypted`
+    #[serde(default)]
+    algorithm: Option<String>,
+    #[serde(default)]
+    ciphertext: Option<Value>,
+    #[serde(default)]
+    session_id: Option<String>,
+    #[serde(default)]
+    sender_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RelatesTo {
+    #[serde(default)]
+    rel_type: Option<String>,
+    #[serde(default)]
+    event_id: Option<String>,
+    #[serde(default, rename = "m.in_reply_to")]
+    in_reply_to: Option<Value>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ToDevice {
+    #[serde(default)]
+    events: Vec<ToDeviceEvent>,
+}
+
+/// A to-device event from `/sync` (room keys arrive this way).
+#[derive(Debug, Deserialize)]
+pub struct ToDeviceEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub sender: String,
+    #[serde(default)]
+    pub content: Value,
 }
 
 #[derive(Debug, Deserialize)]
 struct WhoAmIResponse {
     user_id: String,
+    #[serde(default)]
+    device_id: Option<String>,
+}
+
+#[cfg(feature = "matrix-e2ee")]
+#[derive(Debug, Deserialize)]
+struct JoinedMembers {
+    #[serde(default)]
+    joined: HashMap<String, Value>,
+}
+
+/// Split a reply target into room and optional thread root; an empty
+/// target means the default room.
+fn parse_target<'a>(target: &'a str, default_room: &'a str) -> (&'a str, Option<&'a str>) {
+    let target = target.trim();
+    if target.is_empty() {
+        return (default_room, None);
+    }
+    match target.find(THREAD_SEPARATOR) {
+        Some(i) => (&target[..i], Some(&target[i + 1..])),
+        None => (target, None),
+    }
+}
+
+/// Drop the `> quoted` lines Matrix clients prepend to reply bodies.
+fn strip_reply_fallback(body: &str) -> String {
+    if !body.starts_with("> ") {
+        return body.to_string();
+    }
+    let rest = body.lines().skip_while(|line| line.starts_with('>'));
+    rest.skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn load_joined_rooms(store_dir: &Path) -> HashSet<String> {
+    std::fs::read_to_string(store_dir.join(JOINED_ROOMS_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
 }
 
 impl MatrixChannel {
@@ -78,187 +234,674 @@ impl MatrixChannel {
             homeserver,
             access_token,
             room_id,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
+            rooms: Vec::new(),
+            auto_join: true,
+            thread_replies: true,
+            store_dir: None,
+            sessions: Arc::default(),
+            #[cfg(feature = "matrix-e2ee")]
+            e2ee: None,
             client: Client::new(),
         }
     }
 
+    /// Build from config. Joined rooms and the crypto store live in
+    /// `store_dir` (default `<zeroclaw_dir>/matrix`); the crypto store is
+    /// encrypted with `secrets`.
+    #[cfg_attr(not(feature = "matrix-e2ee"), allow(unused_variables))]
+    pub fn from_config(
+        config: &crate::config::schema::MatrixConfig,
+        zeroclaw_dir: &Path,
+        secrets: &SecretStore,
+    ) -> anyhow::Result<Self> {
+        let mut channel = Self::new(
+            config.homeserver.clone(),
+            config.access_token.clone(),
+            config.room_id.clone(),
+            config.allowed_users.clone(),
+        );
+        channel.rooms.clone_from(&config.rooms);
+        channel.auto_join = config.auto_join;
+        channel.thread_replies = config.thread_replies;
+
+        let store_dir = config.store_dir.as_deref().map_or_else(
+            || zeroclaw_dir.join("matrix"),
+            |dir| PathBuf::from(shellexpand::tilde(dir).into_owned()),
+        );
+        channel.sessions.lock().joined = load_joined_rooms(&store_dir);
+
+        if config.e2ee {
+            #[cfg(not(feature = "matrix-e2ee"))]
+            anyhow::bail!(
+                "Matrix e2ee = true needs a build with the matrix-e2ee feature \
+                 (cargo build --features matrix-e2ee)"
+            );
+            #[cfg(feature = "matrix-e2ee")]
+            {
+                channel.e2ee = Some(Arc::new(E2ee {
+                    secrets: secrets.clone(),
+                    store_dir: store_dir.clone(),
+                    crypto: tokio::sync::OnceCell::new(),
+                }));
+            }
+        }
+        channel.store_dir = Some(store_dir);
+        Ok(channel)
+    }
+
     fn is_user_allowed(&self, sender: &str) -> bool {
-        if self.allowed_users.iter().any(|u| u == "*") {
+        if self.allowed_users.read().iter().any(|u| u == "*") {
             return true;
         }
         self.allowed_users
+            .read()
             .iter()
             .any(|u| u.eq_ignore_ascii_case(sender))
     }
 
-    async fn get_my_user_id(&self) -> anyhow::Result<String> {
-        let url = format!("{}/_matrix/client/v3/account/whoami", self.homeserver);
+    /// Whether messages in `room_id` are handled.
+    fn is_listening(&self, room_id: &str) -> bool {
+        room_id == self.room_id
+            || self.rooms.iter().any(|r| r == "*" || r == room_id)
+            || self.sessions.lock().joined.contains(room_id)
+    }
+
+    /// Client-Server API URL; segments are percent-encoded.
+    fn api_url(&self, segments: &[&str]) -> anyhow::Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.homeserver)
+            .with_context(|| format!("Invalid Matrix homeserver URL: {}", self.homeserver))?;
+        url.path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("Invalid Matrix homeserver URL: {}", self.homeserver))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3"])
+            .extend(segments);
+        Ok(url)
+    }
+
+    async fn api<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        segments: &[&str],
+        body: Option<&Value>,
+    ) -> anyhow::Result<T> {
+        let mut request = self
+            .client
+            .request(method, self.api_url(segments)?)
+            .header("Authorization", format!("Bearer {}", self.access_token));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Matrix {} failed ({status}): {err}", segments.join("/"));
+        }
+        Ok(resp.json().await?)
+    }
+
+    async fn whoami(&self) -> anyhow::Result<WhoAmIResponse> {
+        self.api(Method::GET, &["account", "whoami"], None).await
+    }
+
+    async fn sync(&self, since: Option<&str>, timeout_ms: u64) -> anyhow::Result<SyncResponse> {
+        let mut url = self.api_url(&["sync"])?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("timeout", &timeout_ms.to_string());
+            match since {
+                Some(since) => {
+                    query.append_pair("since", since);
+                }
+                // Only the latest event per room, so history is not replayed.
+                None => {
+                    query.append_pair("filter", r#"{"room":{"timeline":{"limit":1}}}"#);
+                }
+            }
+        }
         let resp = self
             .client
-            .get(&url)
+            .get(url)
             .header("Authorization", format!("Bearer {}", self.access_token))
             .send()
             .await?;
-
         if !resp.status().is_success() {
-            let err = resp.text().await?;
-            anyhow::bail!("Matrix whoami failed: {err}");
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Matrix sync failed ({status}): {err}");
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Apply one `/sync` response. Messages are forwarded only when `tx` is
+    /// set; returns `false` once the receiver is gone.
+    async fn handle_sync(
+        &self,
+        sync: &SyncResponse,
+        my_user_id: &str,
+        tx: Option<&mpsc::Sender<ChannelMessage>>,
+    ) -> bool {
+        #[cfg(feature = "matrix-e2ee")]
+        self.handle_crypto_sync(sync).await;
+
+        for (room_id, invited) in &sync.rooms.invite {
+            self.handle_invite(room_id, invited, my_user_id).await;
+        }
+        for room_id in sync.rooms.leave.keys() {
+            self.forget_room(room_id);
         }
 
-        let who: WhoAmIResponse = resp.json().await?;
-        Ok(who.user_id)
+        for (room_id, room) in &sync.rooms.join {
+            if !self.is_listening(room_id) {
+                continue;
+            }
+            if room
+                .state
+                .events
+                .iter()
+                .chain(&room.timeline.events)
+                .any(|event| event.event_type == "m.room.encryption")
+            {
+                self.sessions
+                    .lock()
+                    .rooms
+                    .entry(room_id.clone())
+                    .or_default()
+                    .encrypted = Some(true);
+            }
+
+            let Some(tx) = tx else {
+                continue;
+            };
+            for event in &room.timeline.events {
+                let Some(msg) = self.to_channel_message(room_id, event, my_user_id) else {
+                    continue;
+                };
+                if tx.send(msg).await.is_err() {
+                    return false;
+                }
+            }
+        }
+        true
     }
-}
 
-#[async_trait]
-impl Channel for MatrixChannel {
-    fn name(&self) -> &str {
-        "matrix"
+    /// Turn a timeline event into a message for the agent, or `None` when it
+    /// is not an allowed user's text message. `sender` is the reply target
+    /// (room or thread), so the author's user ID is prefixed onto the content
+    /// to keep speakers apart in shared rooms.
+    fn to_channel_message(
+        &self,
+        room_id: &str,
+        event: &TimelineEvent,
+        my_user_id: &str,
+    ) -> Option<ChannelMessage> {
+        // Skip our own messages
+        if event.sender == my_user_id {
+            return None;
+        }
+
+        let decrypted;
+        let event = if event.event_type == "m.room.encrypted" {
+            decrypted = self.decrypt_event(room_id, event)?;
+            &decrypted
+        } else {
+            event
+        };
+
+        // Only process text messages
+        if event.event_type != "m.room.message"
+            || event.content.msgtype.as_deref() != Some("m.text")
+        {
+            return None;
+        }
+        let body = event.content.body.as_deref()?;
+        if !self.is_user_allowed(&event.sender) {
+            return None;
+        }
+
+        let relation = event.content.relates_to.as_ref();
+        let thread_root = relation
+            .filter(|r| r.rel_type.as_deref() == Some("m.thread"))
+            .and_then(|r| r.event_id.clone())
+            .or_else(|| (!event.event_id.is_empty()).then(|| event.event_id.clone()));
+        let target = match thread_root {
+            Some(root) if self.thread_replies => {
+                let mut sessions = self.sessions.lock();
+                let threads = &mut sessions
+                    .rooms
+                    .entry(room_id.to_string())
+                    .or_default()
+                    .threads;
+                if threads.len() >= MAX_TRACKED_THREADS && !threads.contains_key(&root) {
+                    threads.clear();
+                }
+                threads.insert(root.clone(), event.event_id.clone());
+                format!("{room_id}/{root}")
+            }
+            _ => room_id.to_string(),
+        };
+        let text = if relation.is_some_and(|r| r.in_reply_to.is_some()) {
+            strip_reply_fallback(body)
+        } else {
+            body.to_string()
+        };
+        let content = format!("{}: {text}", event.sender);
+
+        Some(ChannelMessage {
+            id: if event.event_id.is_empty() {
+                format!("mx_{}", chrono::Utc::now().timestamp_millis())
+            } else {
+                event.event_id.clone()
+            },
+            sender: target,
+            content,
+            channel: "matrix".to_string(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        })
     }
 
-    async fn send(&self, message: &str, _target: &str) -> anyhow::Result<()> {
-        let txn_id = format!("zc_{}", chrono::Utc::now().timestamp_millis());
-        let url = format!(
-            "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
-            self.homeserver, self.room_id, txn_id
+    #[cfg(not(feature = "matrix-e2ee"))]
+    #[allow(clippy::unused_self)]
+    fn decrypt_event(&self, room_id: &str, event: &TimelineEvent) -> Option<TimelineEvent> {
+        tracing::warn!(
+            "Matrix: skipping encrypted message {} in {room_id}; E2EE needs the matrix-e2ee build feature",
+            event.event_id
         );
+        None
+    }
 
-        let body = serde_json::json!({
-            "msgtype": "m.text",
-            "body": message
-        });
+    /// Join rooms that an allowed user invites us to.
+    async fn handle_invite(&self, room_id: &str, invited: &InvitedRoom, my_user_id: &str) {
+        let Some(inviter) = invited
+            .invite_state
+            .events
+            .iter()
+            .find(|e| {
+                e.event_type == "m.room.member"
+                    && e.state_key.as_deref() == Some(my_user_id)
+                    && e.content.membership.as_deref() == Some("invite")
+            })
+            .map(|e| e.sender.as_str())
+        else {
+            return;
+        };
+        if !self.auto_join || !self.is_user_allowed(inviter) {
+            tracing::info!("Matrix: ignoring invite to {room_id} from {inviter}");
+            return;
+        }
 
-        let resp = self
-            .client
-            .put(&url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
-            .json(&body)
-            .send()
-            .await?;
+        match self
+            .api::<Value>(Method::POST, &["join", room_id], Some(&json!({})))
+            .await
+        {
+            Ok(_) => {
+                tracing::info!("Matrix: joined {room_id} (invited by {inviter})");
+                let mut sessions = self.sessions.lock();
+                sessions.joined.insert(room_id.to_string());
+                self.save_joined_rooms(&sessions.joined);
+            }
+            Err(e) => tracing::warn!("Matrix: failed to join {room_id}: {e}"),
+        }
+    }
 
-        if !resp.status().is_success() {
-            let err = resp.text().await?;
-            anyhow::bail!("Matrix send failed: {err}");
+    /// Drop state for a room we left or were removed from.
+    fn forget_room(&self, room_id: &str) {
+        let mut sessions = self.sessions.lock();
+        sessions.rooms.remove(room_id);
+        if sessions.joined.remove(room_id) {
+            tracing::info!("Matrix: left {room_id}");
+            self.save_joined_rooms(&sessions.joined);
         }
+    }
 
-        Ok(())
+    fn save_joined_rooms(&self, joined: &HashSet<String>) {
+        let Some(dir) = self.store_dir.as_ref() else {
+            return;
+        };
+        let mut rooms: Vec<&String> = joined.iter().collect();
+        rooms.sort();
+        let result = std::fs::create_dir_all(dir).and_then(|()| {
+            std::fs::write(
+                dir.join(JOINED_ROOMS_FILE),
+                serde_json::to_vec_pretty(&rooms).unwrap_or_default(),
+            )
+        });
+        if let Err(e) = result {
+            tracing::warn!("Matrix: failed to save joined rooms: {e}");
+        }
     }
+}
 
-    async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
-        tracing::info!("Matrix channel listening on room {}...", self.room_id);
+#[cfg(feature = "matrix-e2ee")]
+impl MatrixChannel {
+    /// The crypto machine, set up on first use: loads or creates this
+    /// device's identity and publishes its keys. `None` when E2EE is off.
+    async fn crypto(&self) -> anyhow::Result<Option<&MatrixCrypto>> {
+        let Some(e2ee) = self.e2ee.as_ref() else {
+            return Ok(None);
+        };
+        let crypto = e2ee
+            .crypto
+            .get_or_try_init(|| async {
+                let me = self.whoami().await?;
+                let device_id = me.device_id.context(
+                    "Matrix whoami returned no device_id; E2EE needs a device-bound access token",
+                )?;
+                let file_name: String = format!("crypto_{}_{device_id}.json", me.user_id)
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() || c == '.' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                let crypto = MatrixCrypto::load_or_create(
+                    e2ee.store_dir.join(file_name),
+                    e2ee.secrets.clone(),
+                    &me.user_id,
+                    &device_id,
+                )?;
+                self.upload_keys(&crypto, None).await?;
+                tracing::info!(
+                    "Matrix E2EE ready for {} ({device_id}), identity key {}",
+                    me.user_id,
+                    crypto.ed25519_key().to_base64()
+                );
+                Ok::<_, anyhow::Error>(crypto)
+            })
+            .await?;
+        Ok(Some(crypto))
+    }
 
-        let my_user_id = self.get_my_user_id().await?;
+    async fn upload_keys(
+        &self,
+        crypto: &MatrixCrypto,
+        server_count: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let Some(body) = crypto.keys_upload_body(server_count) else {
+            return Ok(());
+        };
+        let _: Value = self
+            .api(Method::POST, &["keys", "upload"], Some(&body))
+            .await?;
+        crypto.mark_keys_published()
+    }
 
-        // Initial sync to get the since token
-        let url = format!(
-            "{}/_matrix/client/v3/sync?timeout=30000&filter={{\"room\":{{\"timeline\":{{\"limit\":1}}}}}}",
-            self.homeserver
-        );
+    /// Take in room keys and keep the server stocked with one-time keys.
+    async fn handle_crypto_sync(&self, sync: &SyncResponse) {
+        let crypto = match self.crypto().await {
+            Ok(Some(crypto)) => crypto,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Matrix E2EE unavailable: {e}");
+                return;
+            }
+        };
+        let devices = match self.sender_devices(crypto, &sync.to_device.events).await {
+            Ok(devices) => devices,
+            Err(e) => {
+                tracing::warn!("Matrix: cannot look up to-device senders' keys: {e}");
+                Vec::new()
+            }
+        };
+        crypto.receive_to_device(&sync.to_device.events, &devices);
+        if !sync.device_one_time_keys_count.is_empty() {
+            let count = sync
+                .device_one_time_keys_count
+                .get("signed_curve25519")
+                .copied()
+                .unwrap_or(0);
+            if let Err(e) = self.upload_keys(crypto, Some(count)).await {
+                tracing::warn!("Matrix: failed to upload one-time keys: {e}");
+            }
+        }
+    }
+
+    /// Signature-verified devices of everyone who sent us an encrypted
+    /// to-device message, so room keys can be tied to a real device.
+    async fn sender_devices(
+        &self,
+        crypto: &MatrixCrypto,
+        events: &[ToDeviceEvent],
+    ) -> anyhow::Result<Vec<Device>> {
+        let users: serde_json::Map<String, Value> = events
+            .iter()
+            .filter(|event| event.event_type == "m.room.encrypted")
+            .map(|event| (event.sender.clone(), json!([])))
+            .collect();
+        if users.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Value = self
+            .api(
+                Method::POST,
+                &["keys", "query"],
+                Some(&json!({ "device_keys": users })),
+            )
+            .await?;
+        Ok(crypto.verified_devices(&keys))
+    }
+
+    fn decrypt_event(&self, room_id: &str, event: &TimelineEvent) -> Option<TimelineEvent> {
+        let Some(crypto) = self.e2ee.as_ref().and_then(|e2ee| e2ee.crypto.get()) else {
+            tracing::warn!(
+                "Matrix: skipping encrypted message {} in {room_id}; set e2ee = true to read it",
+                event.event_id
+            );
+            return None;
+        };
+        let content = json!({
+            "algorithm": event.content.algorithm,
+            "ciphertext": event.content.ciphertext,
+            "session_id": event.content.session_id,
+            "sender_key": event.content.sender_key,
+        });
+        match crypto.decrypt_room_event(room_id, &event.event_id, &content) {
+            Ok(plain) => Some(TimelineEvent {
+                event_type: plain["type"].as_str().unwrap_or_default().to_string(),
+                sender: event.sender.clone(),
+                event_id: event.event_id.clone(),
+                state_key: None,
+                content: serde_json::from_value(plain["content"].clone()).unwrap_or_default(),
+            }),
+            Err(e) => {
+                tracing::warn!(
+                    "Matrix: cannot decrypt {} in {room_id}: {e}",
+                    event.event_id
+                );
+                None
+            }
+        }
+    }
 
+    /// Whether `room_id` is encrypted, asking the server the first time.
+    async fn room_encrypted(&self, room_id: &str) -> anyhow::Result<bool> {
+        if let Some(known) = self
+            .sessions
+            .lock()
+            .rooms
+            .get(room_id)
+            .and_then(|room| room.encrypted)
+        {
+            return Ok(known);
+        }
         let resp = self
             .client
-            .get(&url)
+            .get(self.api_url(&["rooms", room_id, "state", "m.room.encryption", ""])?)
             .header("Authorization", format!("Bearer {}", self.access_token))
             .send()
             .await?;
-
-        if !resp.status().is_success() {
-            let err = resp.text().await?;
-            anyhow::bail!("Matrix initial sync failed: {err}");
+        let encrypted = resp.status().is_success();
+        if !encrypted && resp.status() != reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!(
+                "Matrix encryption state lookup for {room_id} failed ({})",
+                resp.status()
+            );
         }
+        self.sessions
+            .lock()
+            .rooms
+            .entry(room_id.to_string())
+            .or_default()
+            .encrypted = Some(encrypted);
+        Ok(encrypted)
+    }
 
-        let sync: SyncResponse = resp.json().await?;
-        let mut since = sync.next_batch;
+    /// Make sure every device in the room holds our current room key.
+    async fn share_room_key(&self, crypto: &MatrixCrypto, room_id: &str) -> anyhow::Result<()> {
+        let members: JoinedMembers = self
+            .api(Method::GET, &["rooms", room_id, "joined_members"], None)
+            .await?;
+        let users: serde_json::Map<String, Value> = members
+            .joined
+            .into_keys()
+            .map(|user| (user, json!([])))
+            .collect();
+        let keys: Value = self
+            .api(
+                Method::POST,
+                &["keys", "query"],
+                Some(&json!({ "device_keys": users })),
+            )
+            .await?;
+        let devices = crypto.verified_devices(&keys);
 
-        // Long-poll loop
-        loop {
-            let url = format!(
-                "{}/_matrix/client/v3/sync?since={}&timeout=30000",
-                self.homeserver, since
-            );
+        let missing = crypto.devices_without_session(&devices);
+        if !missing.is_empty() {
+            let mut claim = serde_json::Map::new();
+            for device in &missing {
+                claim
+                    .entry(device.user_id.clone())
+                    .or_insert_with(|| json!({}))[device.device_id.as_str()] =
+                    json!("signed_curve25519");
+            }
+            let claimed: Value = self
+                .api(
+                    Method::POST,
+                    &["keys", "claim"],
+                    Some(&json!({ "one_time_keys": claim })),
+                )
+                .await?;
+            crypto.create_outbound_sessions(&missing, &claimed)?;
+        }
 
-            let resp = self
-                .client
-                .get(&url)
-                .header("Authorization", format!("Bearer {}", self.access_token))
-                .send()
+        if let Some(messages) = crypto.share_room_key(room_id, &devices)? {
+            let txn_id = format!("zc_{}", uuid::Uuid::new_v4().simple());
+            let sent: anyhow::Result<Value> = self
+                .api(
+                    Method::PUT,
+                    &["sendToDevice", "m.room.encrypted", &txn_id],
+                    Some(&json!({ "messages": messages })),
+                )
                 .await;
+            if let Err(e) = sent {
+                // Devices were marked as having the key; start over next time.
+                crypto.discard_outbound(room_id)?;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
 
-            let resp = match resp {
-                Ok(r) => r,
-                Err(e) => {
-                    tracing::warn!("Matrix sync error: {e}, retrying...");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                    continue;
-                }
-            };
-
-            if !resp.status().is_success() {
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-                continue;
+    /// Encrypt outgoing content when the room is encrypted and E2EE is on.
+    async fn seal(&self, room_id: &str, content: Value) -> anyhow::Result<(&'static str, Value)> {
+        if let Some(crypto) = self.crypto().await? {
+            if self.room_encrypted(room_id).await? {
+                self.share_room_key(crypto, room_id).await?;
+                let encrypted = crypto.encrypt_room_event(room_id, "m.room.message", &content)?;
+                return Ok(("m.room.encrypted", encrypted));
             }
+        }
+        Ok(("m.room.message", content))
+    }
+}
 
-            let sync: SyncResponse = resp.json().await?;
-            since = sync.next_batch;
+#[async_trait]
+impl Channel for MatrixChannel {
+    fn name(&self) -> &str {
+        "matrix"
+    }
 
-            // Process events from our room
-            if let Some(room) = sync.rooms.join.get(&self.room_id) {
-                for event in &room.timeline.events {
-                    // Skip our own messages
-                    if event.sender == my_user_id {
-                        continue;
-                    }
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
 
-                    // Only process text messages
-                    if event.event_type != "m.room.message" {
-                        continue;
-                    }
+    async fn send(&self, message: &str, target: &str) -> anyhow::Result<()> {
+        let (room_id, thread_root) = parse_target(target, &self.room_id);
 
-                    if event.content.msgtype.as_deref() != Some("m.text") {
-                        continue;
-                    }
+        let mut content = json!({
+            "msgtype": "m.text",
+            "body": message
+        });
+        if let Some(root) = thread_root.filter(|_| self.thread_replies) {
+            let latest = self
+                .sessions
+                .lock()
+                .rooms
+                .get(room_id)
+                .and_then(|room| room.threads.get(root).cloned())
+                .unwrap_or_else(|| root.to_string());
+            content["m.relates_to"] = json!({
+                "rel_type": "m.thread",
+                "event_id": root,
+                "is_falling_back": true,
+                "m.in_reply_to": { "event_id": latest }
+            });
+        }
 
-                    let Some(ref body) = event.content.body else {
-                        continue;
-                    };
+        #[cfg(feature = "matrix-e2ee")]
+        let (event_type, content) = self.seal(room_id, content).await?;
+        #[cfg(not(feature = "matrix-e2ee"))]
+        let event_type = "m.room.message";
 
-                    if !self.is_user_allowed(&event.sender) {
-                        continue;
-                    }
+        let txn_id = format!("zc_{}", uuid::Uuid::new_v4().simple());
+        let _: Value = self
+            .api(
+                Method::PUT,
+                &["rooms", room_id, "send", event_type, &txn_id],
+                Some(&content),
+            )
+            .await?;
+        Ok(())
+    }
 
-                    let msg = ChannelMessage {
-                        id: format!("mx_{}", chrono::Utc::now().timestamp_millis()),
-                        sender: event.sender.clone(),
-                        content: body.clone(),
-                        channel: "matrix".to_string(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                    };
-
-                    if tx.send(msg).await.is_err() {
-                        return Ok(());
-                    }
+    async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        tracing::info!("Matrix channel listening on room {}...", self.room_id);
+
+        let my_user_id = self.whoami().await?.user_id;
+
+        // Initial sync: pick up invites, encryption state and room keys, but
+        // do not answer messages from before startup.
+        let initial = self.sync(None, 0).await?;
+        self.handle_sync(&initial, &my_user_id, None).await;
+        let mut since = initial.next_batch;
+
+        // Long-poll loop
+        loop {
+            let sync = match self.sync(Some(&since), SYNC_TIMEOUT_MS).await {
+                Ok(sync) => sync,
+                Err(e) => {
+                    tracing::warn!("Matrix sync error: {e}, retrying...");
+                    tokio::time::sleep(tokio::time::Duration::from_secs(SYNC_RETRY_SECS)).await;
+                    continue;
                 }
+            };
+            since.clone_from(&sync.next_batch);
+
+            if !self.handle_sync(&sync, &my_user_id, Some(&tx)).await {
+                return Ok(());
             }
         }
     }
 
     async fn health_check(&self) -> bool {
-        let url = format!("{}/_matrix/client/v3/account/whoami", self.homeserver);
-        let Ok(resp) = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
-            .send()
-            .await
-        else {
-            return false;
-        };
-
-        resp.status().is_success()
+        self.whoami().await.is_ok()
     }
 }
 
@@ -281,7 +924,7 @@ mod tests {
         assert_eq!(ch.homeserver, "https://matrix.org");
         assert_eq!(ch.access_token, "syt_test_token");
         assert_eq!(ch.room_id, "!room:matrix.org");
-        assert_eq!(ch.allowed_users.len(), 1);
+        assert_eq!(ch.allowed_users.read().len(), 1);
     }
 
     #[test]
@@ -464,4 +1107,493 @@ mod tests {
         let resp: SyncResponse = serde_json::from_str(json).unwrap();
         assert!(resp.rooms.join.is_empty());
     }
+
+    #[test]
+    fn targets_split_into_room_and_thread() {
+        assert_eq!(parse_target("", "!main:m"), ("!main:m", None));
+        assert_eq!(parse_target("!r:m", "!main:m"), ("!r:m", None));
+        assert_eq!(
+            parse_target("!r:m/$root_event", "!main:m"),
+            ("!r:m", Some("$root_event"))
+        );
+    }
+
+    #[test]
+    fn reply_fallback_is_stripped() {
+        assert_eq!(
+            strip_reply_fallback("> <@bot:m> earlier answer\n> more\n\nthanks!"),
+            "thanks!"
+        );
+        assert_eq!(strip_reply_fallback("no quote"), "no quote");
+    }
+
+    #[test]
+    fn sync_response_deserializes_invites_and_relations() {
+        let json = r#"{
+            "next_batch": "s1",
+            "rooms": {
+                "invite": {
+                    "!new:m": {"invite_state": {"events": [
+                        {"type": "m.room.member", "sender": "@user:m", "state_key": "@bot:m",
+                         "content": {"membership": "invite"}}
+                    ]}}
+                },
+                "join": {
+                    "!room:m": {"timeline": {"events": [
+                        {"type": "m.room.message", "sender": "@user:m", "event_id": "$2",
+                         "content": {"msgtype": "m.text", "body": "hi",
+                                     "m.relates_to": {"rel_type": "m.thread", "event_id": "$1"}}}
+                    ]}}
+                }
+            },
+            "to_device": {"events": [{"type": "m.room.encrypted", "sender": "@user:m", "content": {}}]}
+        }"#;
+        let resp: SyncResponse = serde_json::from_str(json).unwrap();
+        let invite = &resp.rooms.invite["!new:m"].invite_state.events[0];
+        assert_eq!(invite.state_key.as_deref(), Some("@bot:m"));
+        assert_eq!(invite.content.membership.as_deref(), Some("invite"));
+        let event = &resp.rooms.join["!room:m"].timeline.events[0];
+        let relation = event.content.relates_to.as_ref().unwrap();
+        assert_eq!(relation.rel_type.as_deref(), Some("m.thread"));
+        assert_eq!(relation.event_id.as_deref(), Some("$1"));
+        assert_eq!(resp.to_device.events.len(), 1);
+    }
+
+    #[test]
+    fn messages_from_different_users_keep_their_author() {
+        let ch = MatrixChannel::new(
+            "https://matrix.org".to_string(),
+            "syt_test_token".to_string(),
+            "!room:m".to_string(),
+            vec!["*".to_string()],
+        );
+        let event = |sender: &str, id: &str, body: &str| -> TimelineEvent {
+            serde_json::from_value(serde_json::json!({
+                "type": "m.room.message", "sender": sender, "event_id": id,
+                "content": {"msgtype": "m.text", "body": body}
+            }))
+            .unwrap()
+        };
+
+        let alice = ch
+            .to_channel_message("!room:m", &event("@alice:m", "$1", "deploy it"), "@bot:m")
+            .unwrap();
+        let bob = ch
+            .to_channel_message("!room:m", &event("@bob:m", "$2", "don't deploy"), "@bot:m")
+            .unwrap();
+
+        assert_eq!(alice.content, "@alice:m: deploy it");
+        assert_eq!(bob.content, "@bob:m: don't deploy");
+        assert_eq!(alice.sender.split('/').next(), Some("!room:m"));
+        assert_eq!(bob.sender.split('/').next(), Some("!room:m"));
+    }
+
+    // ── Fake homeserver ──────────────────────────────────────────
+
+    use crate::config::schema::MatrixConfig;
+    use axum::{extract::State, Json, Router};
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+
+    #[derive(Default)]
+    struct Homeserver {
+        /// Canned `/sync` bodies, served in order
+        syncs: std::collections::VecDeque<Value>,
+        /// `(method, path below /_matrix/client/v3, body)` of other requests
+        requests: Vec<(String, String, Value)>,
+        encrypted_rooms: Vec<String>,
+        keys_query: Value,
+        keys_claim: Value,
+    }
+
+    type SharedHomeserver = Arc<parking_lot::Mutex<Homeserver>>;
+
+    async fn homeserver_handler(
+        State(hs): State<SharedHomeserver>,
+        method: axum::http::Method,
+        uri: axum::http::Uri,
+        body: axum::body::Bytes,
+    ) -> axum::response::Response {
+        use axum::response::IntoResponse;
+
+        let path = uri
+            .path()
+            .trim_start_matches("/_matrix/client/v3")
+            .to_string();
+        if path == "/sync" {
+            let next = hs.lock().syncs.pop_front();
+            if let Some(sync) = next {
+                return Json(sync).into_response();
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            return Json(json!({"next_batch": "idle"})).into_response();
+        }
+
+        let mut hs = hs.lock();
+        let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        hs.requests.push((method.to_string(), path.clone(), body));
+        let reply = if path == "/account/whoami" {
+            json!({"user_id": "@bot:local", "device_id": "BOTDEV"})
+        } else if let Some(room) = path.strip_prefix("/join/") {
+            json!({"room_id": room})
+        } else if path.contains("/send/") {
+            json!({"event_id": "$sent"})
+        } else if path.ends_with("/state/m.room.encryption/") {
+            let room = path.split('/').nth(2).unwrap_or_default();
+            if !hs.encrypted_rooms.iter().any(|r| r == room) {
+                return (
+                    axum::http::StatusCode::NOT_FOUND,
+                    Json(json!({"errcode": "M_NOT_FOUND"})),
+                )
+                    .into_response();
+            }
+            json!({"algorithm": "m.megolm.v1.aes-sha2"})
+        } else if path.ends_with("/joined_members") {
+            json!({"joined": {"@bot:local": {}, "@alice:local": {}}})
+        } else if path == "/keys/upload" {
+            json!({"one_time_key_counts": {"signed_curve25519": 50}})
+        } else if path == "/keys/query" {
+            hs.keys_query.clone()
+        } else if path == "/keys/claim" {
+            hs.keys_claim.clone()
+        } else {
+            json!({})
+        };
+        Json(reply).into_response()
+    }
+
+    async fn spawn_homeserver(syncs: Vec<Value>) -> (String, SharedHomeserver) {
+        let hs: SharedHomeserver = Arc::default();
+        hs.lock().syncs = syncs.into();
+        let app = Router::new()
+            .fallback(homeserver_handler)
+            .with_state(Arc::clone(&hs));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}"), hs)
+    }
+
+    fn config(base: &str, dir: &TempDir) -> MatrixConfig {
+        MatrixConfig {
+            homeserver: base.to_string(),
+            access_token: "syt_test".into(),
+            room_id: "!main:local".into(),
+            allowed_users: vec!["@alice:local".into()],
+            rooms: vec![],
+            auto_join: true,
+            thread_replies: true,
+            e2ee: false,
+            store_dir: Some(dir.path().join("matrix").display().to_string()),
+        }
+    }
+
+    fn from_config(config: &MatrixConfig, dir: &TempDir) -> anyhow::Result<MatrixChannel> {
+        MatrixChannel::from_config(config, dir.path(), &SecretStore::new(dir.path(), true))
+    }
+
+    fn text_event(event_id: &str, sender: &str, body: &str) -> Value {
+        json!({
+            "type": "m.room.message",
+            "event_id": event_id,
+            "sender": sender,
+            "content": {"msgtype": "m.text", "body": body}
+        })
+    }
+
+    fn invite_from(inviter: &str) -> Value {
+        json!({"invite_state": {"events": [{
+            "type": "m.room.member",
+            "sender": inviter,
+            "state_key": "@bot:local",
+            "content": {"membership": "invite"}
+        }]}})
+    }
+
+    async fn next_message(rx: &mut mpsc::Receiver<ChannelMessage>) -> ChannelMessage {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("message within 5s")
+            .expect("channel open")
+    }
+
+    #[tokio::test]
+    async fn listen_joins_invited_rooms_and_replies_per_thread() {
+        let (base, hs) = spawn_homeserver(vec![
+            // Initial sync: history is not answered.
+            json!({"next_batch": "s0", "rooms": {"join": {"!main:local": {"timeline": {"events": [
+                text_event("$old", "@alice:local", "before startup")
+            ]}}}}}),
+            json!({"next_batch": "s1", "rooms": {
+                "invite": {
+                    "!team:local": invite_from("@alice:local"),
+                    "!spam:local": invite_from("@mallory:local")
+                },
+                "join": {
+                    "!main:local": {"timeline": {"events": [
+                        text_event("$e1", "@alice:local", "hello"),
+                        text_event("$e2", "@bot:local", "my own reply"),
+                        text_event("$e3", "@mallory:local", "let me in")
+                    ]}},
+                    "!other:local": {"timeline": {"events": [
+                        text_event("$e4", "@alice:local", "not listening here")
+                    ]}}
+                }
+            }}),
+            json!({"next_batch": "s2", "rooms": {"join": {"!team:local": {"timeline": {"events": [{
+                "type": "m.room.message",
+                "event_id": "$e5",
+                "sender": "@alice:local",
+                "content": {
+                    "msgtype": "m.text",
+                    "body": "> <@bot:local> earlier answer\n\nfollow-up in the thread",
+                    "m.relates_to": {
+                        "rel_type": "m.thread",
+                        "event_id": "$root",
+                        "m.in_reply_to": {"event_id": "$prev"}
+                    }
+                }
+            }]}}}}}),
+        ])
+        .await;
+        let dir = TempDir::new().unwrap();
+        let ch = from_config(&config(&base, &dir), &dir).unwrap();
+        let (tx, mut rx) = mpsc::channel(8);
+        let listener = tokio::spawn({
+            let ch = ch.clone();
+            async move { ch.listen(tx).await }
+        });
+
+        let first = next_message(&mut rx).await;
+        assert_eq!(first.id, "$e1");
+        assert_eq!(first.content, "@alice:local: hello");
+        assert_eq!(first.sender, "!main:local/$e1");
+        let second = next_message(&mut rx).await;
+        assert_eq!(second.content, "@alice:local: follow-up in the thread");
+        assert_eq!(second.sender, "!team:local/$root");
+        listener.abort();
+
+        let requests = hs.lock().requests.clone();
+        assert!(requests
+            .iter()
+            .any(|(method, path, _)| method == "POST" && path == "/join/!team:local"));
+        assert!(!requests.iter().any(|(_, path, _)| path.contains("spam")));
+
+        // Joined rooms are remembered across restarts; unrelated rooms are not.
+        let restarted = from_config(&config(&base, &dir), &dir).unwrap();
+        assert!(restarted.is_listening("!team:local"));
+        assert!(!restarted.is_listening("!other:local"));
+        assert_eq!(
+            ch.sessions.lock().rooms["!team:local"].threads["$root"],
+            "$e5"
+        );
+    }
+
+    #[tokio::test]
+    async fn send_uses_thread_relations_and_default_room() {
+        let (base, hs) = spawn_homeserver(vec![]).await;
+        let dir = TempDir::new().unwrap();
+        let mut ch = from_config(&config(&base, &dir), &dir).unwrap();
+        ch.sessions
+            .lock()
+            .rooms
+            .entry("!team:local".into())
+            .or_default()
+            .threads
+            .insert("$root".into(), "$latest".into());
+
+        ch.send("threaded", "!team:local/$root").await.unwrap();
+        ch.send("plain", "").await.unwrap();
+        ch.thread_replies = false;
+        ch.send("unthreaded", "!team:local/$root").await.unwrap();
+
+        let requests = hs.lock().requests.clone();
+        let (method, path, body) = &requests[0];
+        assert_eq!(method, "PUT");
+        assert!(path.starts_with("/rooms/!team:local/send/m.room.message/zc_"));
+        assert_eq!(body["body"], "threaded");
+        assert_eq!(body["m.relates_to"]["rel_type"], "m.thread");
+        assert_eq!(body["m.relates_to"]["event_id"], "$root");
+        assert_eq!(body["m.relates_to"]["m.in_reply_to"]["event_id"], "$latest");
+
+        let (_, path, body) = &requests[1];
+        assert!(path.starts_with("/rooms/!main:local/send/m.room.message/"));
+        assert!(body.get("m.relates_to").is_none());
+
+        let (_, path, body) = &requests[2];
+        assert!(path.starts_with("/rooms/!team:local/send/"));
+        assert!(body.get("m.relates_to").is_none());
+    }
+
+    #[tokio::test]
+    async fn invites_are_ignored_without_auto_join() {
+        let (base, hs) = spawn_homeserver(vec![]).await;
+        let dir = TempDir::new().unwrap();
+        let mut cfg = config(&base, &dir);
+        cfg.auto_join = false;
+        let ch = from_config(&cfg, &dir).unwrap();
+        let invite: InvitedRoom = serde_json::from_value(invite_from("@alice:local")).unwrap();
+
+        ch.handle_invite("!team:local", &invite, "@bot:local").await;
+        assert!(hs.lock().requests.is_empty());
+        assert!(!ch.is_listening("!team:local"));
+    }
+
+    #[cfg(not(feature = "matrix-e2ee"))]
+    #[test]
+    fn e2ee_needs_the_build_feature() {
+        let dir = TempDir::new().unwrap();
+        let mut cfg = config("http://127.0.0.1:9", &dir);
+        cfg.e2ee = true;
+        let err = from_config(&cfg, &dir).err().unwrap();
+        assert!(err.to_string().contains("matrix-e2ee"));
+    }
+
+    #[cfg(feature = "matrix-e2ee")]
+    #[tokio::test]
+    async fn encrypted_rooms_round_trip() {
+        use crate::channels::matrix_e2ee::canonical_json;
+        use vodozemac::megolm::{
+            GroupSession, InboundGroupSession, MegolmMessage, SessionConfig as MegolmConfig,
+            SessionKey,
+        };
+        use vodozemac::olm::{Account, OlmMessage};
+
+        // Alice's phone: signed device keys and one signed one-time key.
+        let mut alice = Account::new();
+        alice.generate_one_time_keys(1);
+        let (key_id, otk) = alice.one_time_keys().into_iter().next().unwrap();
+        let sign = |mut object: Value| {
+            let signature = alice.sign(canonical_json(&object));
+            object["signatures"] =
+                json!({"@alice:local": {"ed25519:PHONE": signature.to_base64()}});
+            object
+        };
+        let device_keys = sign(json!({
+            "user_id": "@alice:local",
+            "device_id": "PHONE",
+            "algorithms": ["m.olm.v1.curve25519-aes-sha2", "m.megolm.v1.aes-sha2"],
+            "keys": {
+                "curve25519:PHONE": alice.curve25519_key().to_base64(),
+                "ed25519:PHONE": alice.ed25519_key().to_base64()
+            }
+        }));
+        let signed_otk = sign(json!({"key": otk.to_base64()}));
+
+        let (base, hs) = spawn_homeserver(vec![]).await;
+        {
+            let mut hs = hs.lock();
+            hs.encrypted_rooms.push("!main:local".into());
+            hs.keys_query = json!({"device_keys": {"@alice:local": {"PHONE": device_keys}}});
+            hs.keys_claim = json!({"one_time_keys": {"@alice:local": {"PHONE": {
+                format!("signed_curve25519:{}", key_id.to_base64()): signed_otk
+            }}}});
+        }
+        let dir = TempDir::new().unwrap();
+        let mut cfg = config(&base, &dir);
+        cfg.e2ee = true;
+        let ch = from_config(&cfg, &dir).unwrap();
+
+        // Sending into the encrypted room shares a room key, then encrypts.
+        ch.send("top secret", "").await.unwrap();
+        let requests = hs.lock().requests.clone();
+        let upload = &requests
+            .iter()
+            .find(|(_, p, _)| p == "/keys/upload")
+            .unwrap()
+            .2;
+        assert!(upload["device_keys"]["keys"]["ed25519:BOTDEV"].is_string());
+        let to_device = &requests
+            .iter()
+            .find(|(_, p, _)| p.starts_with("/sendToDevice/m.room.encrypted/"))
+            .unwrap()
+            .2["messages"]["@alice:local"]["PHONE"];
+        let (_, path, sent) = requests.last().unwrap();
+        assert!(path.starts_with("/rooms/!main:local/send/m.room.encrypted/"));
+        assert!(sent.get("body").is_none());
+
+        let bot_curve =
+            vodozemac::Curve25519PublicKey::from_base64(to_device["sender_key"].as_str().unwrap())
+                .unwrap();
+        let olm: OlmMessage = serde_json::from_value(
+            to_device["ciphertext"][alice.curve25519_key().to_base64()].clone(),
+        )
+        .unwrap();
+        let OlmMessage::PreKey(pre_key) = olm else {
+            panic!("expected a pre-key message");
+        };
+        let mut opened = alice.create_inbound_session(bot_curve, &pre_key).unwrap();
+        let room_key: Value = serde_json::from_slice(&opened.plaintext).unwrap();
+        let key =
+            SessionKey::from_base64(room_key["content"]["session_key"].as_str().unwrap()).unwrap();
+        let mut inbound = InboundGroupSession::new(&key, MegolmConfig::version_1());
+        let message = MegolmMessage::from_base64(sent["ciphertext"].as_str().unwrap()).unwrap();
+        let event: Value =
+            serde_json::from_slice(&inbound.decrypt(&message).unwrap().plaintext).unwrap();
+        assert_eq!(event["content"]["body"], "top secret");
+
+        // Alice answers: room key over the Olm session, message over Megolm.
+        let mut group = GroupSession::new(MegolmConfig::version_1());
+        let alice_key = json!({
+            "type": "m.room_key",
+            "content": {
+                "algorithm": "m.megolm.v1.aes-sha2",
+                "room_id": "!main:local",
+                "session_id": group.session_id(),
+                "session_key": group.session_key().to_base64()
+            },
+            "sender": "@alice:local",
+            "sender_device": "PHONE",
+            "keys": {"ed25519": alice.ed25519_key().to_base64()},
+            "recipient": "@bot:local",
+            "recipient_keys": {"ed25519": upload["device_keys"]["keys"]["ed25519:BOTDEV"]}
+        });
+        let olm_reply = opened.session.encrypt(alice_key.to_string());
+        let plaintext = json!({
+            "type": "m.room.message",
+            "content": {"msgtype": "m.text", "body": "got it"},
+            "room_id": "!main:local"
+        });
+        hs.lock().syncs = vec![
+            json!({"next_batch": "s0"}),
+            json!({
+                "next_batch": "s1",
+                "to_device": {"events": [{
+                    "type": "m.room.encrypted",
+                    "sender": "@alice:local",
+                    "content": {
+                        "algorithm": "m.olm.v1.curve25519-aes-sha2",
+                        "sender_key": alice.curve25519_key().to_base64(),
+                        "ciphertext": {bot_curve.to_base64(): olm_reply}
+                    }
+                }]},
+                "rooms": {"join": {"!main:local": {"timeline": {"events": [{
+                    "type": "m.room.encrypted",
+                    "event_id": "$enc",
+                    "sender": "@alice:local",
+                    "content": {
+                        "algorithm": "m.megolm.v1.aes-sha2",
+                        "sender_key": alice.curve25519_key().to_base64(),
+                        "ciphertext": group.encrypt(plaintext.to_string()).to_base64(),
+                        "session_id": group.session_id(),
+                        "device_id": "PHONE"
+                    }
+                }]}}}}
+            }),
+        ]
+        .into();
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let listener = tokio::spawn({
+            let ch = ch.clone();
+            async move { ch.listen(tx).await }
+        });
+        let msg = next_message(&mut rx).await;
+        listener.abort();
+        assert_eq!(msg.content, "@alice:local: got it");
+        assert_eq!(msg.sender, "!main:local/$enc");
+    }
 }
@@ -0,0 +1,1075 @@
+//! End-to-end encryption for the Matrix channel (`matrix-e2ee` feature).
+//!
+//! Implements the parts of the Matrix E2EE spec a bot needs, on top of
+//! vodozemac's Olm/Megolm primitives:
+//! - publishing device and one-time keys (`/keys/upload`)
+//! - receiving room keys over Olm-encrypted to-device messages
+//! - decrypting `m.megolm.v1.aes-sha2` room events
+//! - sharing an outbound Megolm session with every device in a room before
+//!   sending into it
+//!
+//! All HTTP is left to [`super::matrix::MatrixChannel`]; this module only
+//! turns server responses into request bodies. The account, Olm sessions
+//! and Megolm sessions are persisted as one JSON document encrypted with the
+//! `SecretStore` key, so a restart keeps the device identity and every room
+//! key received so far.
+
+use super::matrix::ToDeviceEvent;
+use crate::security::SecretStore;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use vodozemac::megolm::{
+    GroupSession, GroupSessionPickle, InboundGroupSession, InboundGroupSessionPickle,
+    MegolmMessage, SessionConfig as MegolmConfig, SessionKey,
+};
+use vodozemac::olm::{Account, AccountPickle, OlmMessage, Session, SessionConfig, SessionPickle};
+use vodozemac::{Curve25519PublicKey, Ed25519PublicKey, Ed25519Signature};
+
+pub const OLM_ALGORITHM: &str = "m.olm.v1.curve25519-aes-sha2";
+pub const MEGOLM_ALGORITHM: &str = "m.megolm.v1.aes-sha2";
+/// Default `m.room.encryption` rotation limits.
+const ROTATION_MESSAGES: u32 = 100;
+const ROTATION_PERIOD_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// A remote device whose signed keys checked out.
+#[derive(Debug, Clone)]
+pub struct Device {
+    pub user_id: String,
+    pub device_id: String,
+    pub curve25519: Curve25519PublicKey,
+    pub ed25519: Ed25519PublicKey,
+}
+
+impl Device {
+    fn key(&self) -> String {
+        format!("{}|{}", self.user_id, self.device_id)
+    }
+}
+
+struct InboundEntry {
+    session: InboundGroupSession,
+    /// Curve25519 key of the device that sent us the room key
+    sender_key: String,
+}
+
+struct OutboundEntry {
+    session: GroupSession,
+    /// `user|device` pairs that already hold this session's key
+    shared_with: HashSet<String>,
+    created_at_ms: i64,
+    messages: u32,
+}
+
+impl OutboundEntry {
+    fn new() -> Self {
+        Self {
+            session: GroupSession::new(MegolmConfig::version_1()),
+            shared_with: HashSet::new(),
+            created_at_ms: chrono::Utc::now().timestamp_millis(),
+            messages: 0,
+        }
+    }
+
+    fn expired(&self) -> bool {
+        self.messages >= ROTATION_MESSAGES
+            || chrono::Utc::now().timestamp_millis() - self.created_at_ms >= ROTATION_PERIOD_MS
+    }
+}
+
+struct CryptoState {
+    account: Account,
+    device_keys_published: bool,
+    /// Olm sessions keyed by the other device's Curve25519 key
+    olm_sessions: HashMap<String, Vec<Session>>,
+    /// Inbound Megolm sessions keyed by `room_id|session_id`
+    inbound: HashMap<String, InboundEntry>,
+    /// Outbound Megolm session per room
+    outbound: HashMap<String, OutboundEntry>,
+    /// `(session_id, message_index)` → event ID, to catch replayed ciphertexts
+    seen: HashMap<(String, u32), String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredInbound {
+    session: InboundGroupSessionPickle,
+    sender_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredOutbound {
+    session: GroupSessionPickle,
+    shared_with: HashSet<String>,
+    created_at_ms: i64,
+    messages: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredCrypto {
+    user_id: String,
+    device_id: String,
+    account: AccountPickle,
+    device_keys_published: bool,
+    #[serde(default)]
+    olm_sessions: HashMap<String, Vec<SessionPickle>>,
+    #[serde(default)]
+    inbound: HashMap<String, StoredInbound>,
+    #[serde(default)]
+    outbound: HashMap<String, StoredOutbound>,
+}
+
+/// Olm account plus Olm/Megolm sessions for one Matrix device.
+pub struct MatrixCrypto {
+    path: PathBuf,
+    secrets: SecretStore,
+    user_id: String,
+    device_id: String,
+    state: parking_lot::Mutex<CryptoState>,
+}
+
+impl MatrixCrypto {
+    /// Load the crypto store at `path`, or create a new device identity.
+    /// A store written for another user or device is refused rather than
+    /// overwritten, since that would throw away its room keys.
+    pub fn load_or_create(
+        path: PathBuf,
+        secrets: SecretStore,
+        user_id: &str,
+        device_id: &str,
+    ) -> Result<Self> {
+        let state = if path.exists() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let json = secrets
+                .decrypt(raw.trim())
+                .context("Failed to decrypt the Matrix crypto store")?;
+            let stored: StoredCrypto = serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            if stored.user_id != user_id || stored.device_id != device_id {
+                anyhow::bail!(
+                    "Matrix crypto store {} belongs to {} ({}), not {user_id} ({device_id})",
+                    path.display(),
+                    stored.user_id,
+                    stored.device_id
+                );
+            }
+            CryptoState::from_stored(stored)
+        } else {
+            CryptoState {
+                account: Account::new(),
+                device_keys_published: false,
+                olm_sessions: HashMap::new(),
+                inbound: HashMap::new(),
+                outbound: HashMap::new(),
+                seen: HashMap::new(),
+            }
+        };
+
+        let crypto = Self {
+            path,
+            secrets,
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            state: parking_lot::Mutex::new(state),
+        };
+        crypto.save(&crypto.state.lock())?;
+        Ok(crypto)
+    }
+
+    pub fn curve25519_key(&self) -> Curve25519PublicKey {
+        self.state.lock().account.curve25519_key()
+    }
+
+    pub fn ed25519_key(&self) -> Ed25519PublicKey {
+        self.state.lock().account.ed25519_key()
+    }
+
+    /// Body for `/keys/upload`, or `None` when the server holds enough keys.
+    /// `server_otk_count` is the `signed_curve25519` count the server last
+    /// reported (`None` = unknown, assume zero).
+    pub fn keys_upload_body(&self, server_otk_count: Option<u64>) -> Option<Value> {
+        let mut state = self.state.lock();
+        let mut body = serde_json::Map::new();
+
+        if !state.device_keys_published {
+            let identity = state.account.identity_keys();
+            let mut device_keys = json!({
+                "user_id": self.user_id,
+                "device_id": self.device_id,
+                "algorithms": [OLM_ALGORITHM, MEGOLM_ALGORITHM],
+                "keys": {
+                    format!("curve25519:{}", self.device_id): identity.curve25519.to_base64(),
+                    format!("ed25519:{}", self.device_id): identity.ed25519.to_base64(),
+                },
+            });
+            self.sign(&state.account, &mut device_keys);
+            body.insert("device_keys".into(), device_keys);
+        }
+
+        let target = u64::try_from(state.account.max_number_of_one_time_keys() / 2).unwrap_or(50);
+        let on_server = server_otk_count.unwrap_or(0);
+        if on_server < target {
+            let unpublished = u64::try_from(state.account.one_time_keys().len()).unwrap_or(0);
+            let missing = target.saturating_sub(on_server + unpublished);
+            if missing > 0 {
+                state
+                    .account
+                    .generate_one_time_keys(usize::try_from(missing).unwrap_or(0));
+            }
+            let mut keys = serde_json::Map::new();
+            for (key_id, key) in state.account.one_time_keys() {
+                let mut signed = json!({ "key": key.to_base64() });
+                self.sign(&state.account, &mut signed);
+                keys.insert(format!("signed_curve25519:{}", key_id.to_base64()), signed);
+            }
+            if !keys.is_empty() {
+                body.insert("one_time_keys".into(), Value::Object(keys));
+            }
+        }
+
+        (!body.is_empty()).then_some(Value::Object(body))
+    }
+
+    /// Record a successful `/keys/upload` of the body last returned by
+    /// [`keys_upload_body`](Self::keys_upload_body).
+    pub fn mark_keys_published(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        state.account.mark_keys_as_published();
+        state.device_keys_published = true;
+        self.save(&state)
+    }
+
+    /// Handle the to-device events of a sync. Olm-encrypted `m.room_key`
+    /// events install inbound Megolm sessions; everything else is ignored.
+    /// A key is only taken from a sender whose Olm and signing keys belong
+    /// to one of `devices`, the signature-verified devices of the senders.
+    /// Returns how many room keys were added.
+    pub fn receive_to_device(&self, events: &[ToDeviceEvent], devices: &[Device]) -> usize {
+        let mut state = self.state.lock();
+        let mut added = 0;
+        for event in events {
+            if event.event_type != "m.room.encrypted" {
+                continue;
+            }
+            match self.receive_olm(&mut state, &event.sender, &event.content, devices) {
+                Ok(true) => added += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!(
+                    "Matrix: dropping to-device message from {}: {e}",
+                    event.sender
+                ),
+            }
+        }
+        if let Err(e) = self.save(&state) {
+            tracing::warn!("Matrix: failed to save crypto store: {e}");
+        }
+        added
+    }
+
+    fn receive_olm(
+        &self,
+        state: &mut CryptoState,
+        sender: &str,
+        content: &Value,
+        devices: &[Device],
+    ) -> Result<bool> {
+        if content["algorithm"] != OLM_ALGORITHM {
+            anyhow::bail!("unsupported algorithm {}", content["algorithm"]);
+        }
+        let sender_key = content["sender_key"]
+            .as_str()
+            .context("missing sender_key")?;
+        let our_key = state.account.curve25519_key().to_base64();
+        let Some(ciphertext) = content["ciphertext"].get(&our_key) else {
+            // Addressed to another of our user's devices.
+            return Ok(false);
+        };
+        let device = devices
+            .iter()
+            .find(|d| d.user_id == sender && d.curve25519.to_base64() == sender_key)
+            .with_context(|| format!("{sender_key} is not a verified device of {sender}"))?;
+        let message: OlmMessage =
+            serde_json::from_value(ciphertext.clone()).context("malformed Olm message")?;
+
+        let plaintext = decrypt_olm(state, sender_key, &message)?;
+        let payload: Value =
+            serde_json::from_slice(&plaintext).context("Olm payload is not JSON")?;
+
+        if payload["sender"] != sender {
+            anyhow::bail!("Olm payload sender does not match the event sender");
+        }
+        if payload["sender_device"] != device.device_id.as_str()
+            || payload["keys"]["ed25519"] != device.ed25519.to_base64()
+        {
+            anyhow::bail!("Olm payload keys do not match the sending device");
+        }
+        if payload["recipient"] != self.user_id.as_str()
+            || payload["recipient_keys"]["ed25519"] != state.account.ed25519_key().to_base64()
+        {
+            anyhow::bail!("Olm payload is addressed to another device");
+        }
+        if payload["type"] != "m.room_key" {
+            return Ok(false);
+        }
+
+        let room_key = &payload["content"];
+        if room_key["algorithm"] != MEGOLM_ALGORITHM {
+            anyhow::bail!("unsupported room key algorithm {}", room_key["algorithm"]);
+        }
+        let room_id = room_key["room_id"]
+            .as_str()
+            .context("room key without room_id")?;
+        let session_id = room_key["session_id"]
+            .as_str()
+            .context("room key without session_id")?;
+        let session_key = SessionKey::from_base64(
+            room_key["session_key"]
+                .as_str()
+                .context("room key without session_key")?,
+        )?;
+        let session = InboundGroupSession::new(&session_key, MegolmConfig::version_1());
+        if session.session_id() != session_id {
+            anyhow::bail!("room key session_id does not match its key");
+        }
+
+        let key = format!("{room_id}|{session_id}");
+        if state.inbound.contains_key(&key) {
+            return Ok(false);
+        }
+        tracing::info!("Matrix: received room key for {room_id} from {sender}");
+        state.inbound.insert(
+            key,
+            InboundEntry {
+                session,
+                sender_key: sender_key.to_string(),
+            },
+        );
+        Ok(true)
+    }
+
+    /// Decrypt the content of an `m.room.encrypted` room event into the
+    /// original `{"type": …, "content": …}` event.
+    pub fn decrypt_room_event(
+        &self,
+        room_id: &str,
+        event_id: &str,
+        content: &Value,
+    ) -> Result<Value> {
+        if content["algorithm"] != MEGOLM_ALGORITHM {
+            anyhow::bail!("unsupported algorithm {}", content["algorithm"]);
+        }
+        let session_id = content["session_id"]
+            .as_str()
+            .context("missing session_id")?;
+        let ciphertext = content["ciphertext"]
+            .as_str()
+            .context("missing ciphertext")?;
+
+        let mut state = self.state.lock();
+        let entry = state
+            .inbound
+            .get_mut(&format!("{room_id}|{session_id}"))
+            .with_context(|| format!("no room key for session {session_id} (yet)"))?;
+        if let Some(sender_key) = content["sender_key"].as_str() {
+            if sender_key != entry.sender_key {
+                anyhow::bail!("event sender_key does not match the room key's sender");
+            }
+        }
+        let decrypted = entry
+            .session
+            .decrypt(&MegolmMessage::from_base64(ciphertext)?)
+            .map_err(|e| anyhow::anyhow!("Megolm decryption failed: {e}"))?;
+
+        match state
+            .seen
+            .entry((session_id.to_string(), decrypted.message_index))
+        {
+            std::collections::hash_map::Entry::Occupied(seen) if seen.get() != event_id => {
+                anyhow::bail!("message index {} was replayed", decrypted.message_index);
+            }
+            std::collections::hash_map::Entry::Occupied(_) => {}
+            std::collections::hash_map::Entry::Vacant(slot) => {
+                slot.insert(event_id.to_string());
+            }
+        }
+        drop(state);
+
+        let event: Value =
+            serde_json::from_slice(&decrypted.plaintext).context("decrypted event is not JSON")?;
+        if event["room_id"] != room_id {
+            anyhow::bail!("decrypted event belongs to another room");
+        }
+        Ok(event)
+    }
+
+    /// Devices from a `/keys/query` response whose self-signature verifies,
+    /// excluding this device.
+    pub fn verified_devices(&self, query_response: &Value) -> Vec<Device> {
+        let mut devices = Vec::new();
+        let Some(users) = query_response["device_keys"].as_object() else {
+            return devices;
+        };
+        for (user_id, user_devices) in users {
+            let Some(user_devices) = user_devices.as_object() else {
+                continue;
+            };
+            for (device_id, keys) in user_devices {
+                if *user_id == self.user_id && *device_id == self.device_id {
+                    continue;
+                }
+                match parse_device(user_id, device_id, keys) {
+                    Ok(device) => devices.push(device),
+                    Err(e) => tracing::warn!("Matrix: ignoring device {user_id}/{device_id}: {e}"),
+                }
+            }
+        }
+        devices
+    }
+
+    /// Devices that still need an Olm session before a room key can be
+    /// shared with them.
+    pub fn devices_without_session<'a>(&self, devices: &'a [Device]) -> Vec<&'a Device> {
+        let state = self.state.lock();
+        devices
+            .iter()
+            .filter(|d| !state.olm_sessions.contains_key(&d.curve25519.to_base64()))
+            .collect()
+    }
+
+    /// Start Olm sessions from a `/keys/claim` response. One-time keys whose
+    /// signature does not verify against the device key are skipped.
+    pub fn create_outbound_sessions(
+        &self,
+        devices: &[&Device],
+        claim_response: &Value,
+    ) -> Result<()> {
+        let mut state = self.state.lock();
+        for device in devices {
+            let Some(keys) =
+                claim_response["one_time_keys"][&device.user_id][&device.device_id].as_object()
+            else {
+                tracing::warn!(
+                    "Matrix: no one-time key for {}/{}; it will not get room keys",
+                    device.user_id,
+                    device.device_id
+                );
+                continue;
+            };
+            for (key_id, signed) in keys {
+                if !key_id.starts_with("signed_curve25519:") {
+                    continue;
+                }
+                if let Err(e) =
+                    verify_signed(signed, &device.user_id, &device.device_id, &device.ed25519)
+                {
+                    tracing::warn!("Matrix: bad one-time key from {}: {e}", device.device_id);
+                    continue;
+                }
+                let Some(otk) = signed["key"].as_str() else {
+                    continue;
+                };
+                let session = state.account.create_outbound_session(
+                    SessionConfig::version_1(),
+                    device.curve25519,
+                    Curve25519PublicKey::from_base64(otk)?,
+                );
+                state
+                    .olm_sessions
+                    .entry(device.curve25519.to_base64())
+                    .or_default()
+                    .push(session);
+                break;
+            }
+        }
+        self.save(&state)
+    }
+
+    /// The room's outbound Megolm key, Olm-encrypted for each device that
+    /// does not have it yet, as the `messages` map of a `/sendToDevice`
+    /// request. Starts a new session when the current one is due for
+    /// rotation. Returns `None` when every device already has the key.
+    pub fn share_room_key(&self, room_id: &str, devices: &[Device]) -> Result<Option<Value>> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        if state
+            .outbound
+            .get(room_id)
+            .is_none_or(OutboundEntry::expired)
+        {
+            state
+                .outbound
+                .insert(room_id.to_string(), OutboundEntry::new());
+        }
+        let outbound = state.outbound.get_mut(room_id).expect("just inserted");
+
+        let room_key = json!({
+            "algorithm": MEGOLM_ALGORITHM,
+            "room_id": room_id,
+            "session_id": outbound.session.session_id(),
+            "session_key": outbound.session.session_key().to_base64(),
+        });
+        let our_ed25519 = state.account.ed25519_key().to_base64();
+        let our_curve25519 = state.account.curve25519_key().to_base64();
+
+        let mut messages = serde_json::Map::new();
+        for device in devices {
+            if outbound.shared_with.contains(&device.key()) {
+                continue;
+            }
+            let their_curve = device.curve25519.to_base64();
+            let Some(session) = state
+                .olm_sessions
+                .get_mut(&their_curve)
+                .and_then(|sessions| sessions.last_mut())
+            else {
+                continue;
+            };
+            let payload = json!({
+                "type": "m.room_key",
+                "content": room_key,
+                "sender": self.user_id,
+                "sender_device": self.device_id,
+                "keys": { "ed25519": our_ed25519 },
+                "recipient": device.user_id,
+                "recipient_keys": { "ed25519": device.ed25519.to_base64() },
+            });
+            let encrypted = session.encrypt(payload.to_string());
+            let content = json!({
+                "algorithm": OLM_ALGORITHM,
+                "sender_key": our_curve25519,
+                "ciphertext": { their_curve: encrypted },
+            });
+            messages
+                .entry(device.user_id.clone())
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .expect("object")
+                .insert(device.device_id.clone(), content);
+            outbound.shared_with.insert(device.key());
+        }
+
+        self.save(&guard)?;
+        Ok((!messages.is_empty()).then_some(Value::Object(messages)))
+    }
+
+    /// Forget the room's outbound session, e.g. when sharing its key failed.
+    pub fn discard_outbound(&self, room_id: &str) -> Result<()> {
+        let mut state = self.state.lock();
+        state.outbound.remove(room_id);
+        self.save(&state)
+    }
+
+    /// Megolm-encrypt a room event with the room's outbound session (call
+    /// [`share_room_key`](Self::share_room_key) first). Returns the
+    /// `m.room.encrypted` content.
+    pub fn encrypt_room_event(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: &Value,
+    ) -> Result<Value> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let our_curve25519 = state.account.curve25519_key().to_base64();
+        let outbound = state
+            .outbound
+            .get_mut(room_id)
+            .context("no outbound session for this room; share the room key first")?;
+        let plaintext = json!({
+            "type": event_type,
+            "content": content,
+            "room_id": room_id,
+        });
+        let ciphertext = outbound.session.encrypt(plaintext.to_string());
+        outbound.messages += 1;
+        let encrypted = json!({
+            "algorithm": MEGOLM_ALGORITHM,
+            "sender_key": our_curve25519,
+            "ciphertext": ciphertext.to_base64(),
+            "session_id": outbound.session.session_id(),
+            "device_id": self.device_id,
+        });
+        self.save(&guard)?;
+        Ok(encrypted)
+    }
+
+    /// Add `signatures.<user>.ed25519:<device>` to a JSON object.
+    fn sign(&self, account: &Account, object: &mut Value) {
+        let signature = account.sign(canonical_json(&unsigned_view(object)));
+        object["signatures"] = json!({
+            self.user_id.clone(): {
+                format!("ed25519:{}", self.device_id): signature.to_base64()
+            }
+        });
+    }
+
+    fn save(&self, state: &CryptoState) -> Result<()> {
+        let stored = StoredCrypto {
+            user_id: self.user_id.clone(),
+            device_id: self.device_id.clone(),
+            account: state.account.pickle(),
+            device_keys_published: state.device_keys_published,
+            olm_sessions: state
+                .olm_sessions
+                .iter()
+                .map(|(key, sessions)| {
+                    (key.clone(), sessions.iter().map(Session::pickle).collect())
+                })
+                .collect(),
+            inbound: state
+                .inbound
+                .iter()
+                .map(|(key, entry)| {
+                    (
+                        key.clone(),
+                        StoredInbound {
+                            session: entry.session.pickle(),
+                            sender_key: entry.sender_key.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            outbound: state
+                .outbound
+                .iter()
+                .map(|(room, entry)| {
+                    (
+                        room.clone(),
+                        StoredOutbound {
+                            session: entry.session.pickle(),
+                            shared_with: entry.shared_with.clone(),
+                            created_at_ms: entry.created_at_ms,
+                            messages: entry.messages,
+                        },
+                    )
+                })
+                .collect(),
+        };
+        let data = self.secrets.encrypt(&serde_json::to_string(&stored)?)?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, data)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl CryptoState {
+    fn from_stored(stored: StoredCrypto) -> Self {
+        Self {
+            account: Account::from_pickle(stored.account),
+            device_keys_published: stored.device_keys_published,
+            olm_sessions: stored
+                .olm_sessions
+                .into_iter()
+                .map(|(key, sessions)| {
+                    (
+                        key,
+                        sessions.into_iter().map(Session::from_pickle).collect(),
+                    )
+                })
+                .collect(),
+            inbound: stored
+                .inbound
+                .into_iter()
+                .map(|(key, entry)| {
+                    (
+                        key,
+                        InboundEntry {
+                            session: InboundGroupSession::from_pickle(entry.session),
+                            sender_key: entry.sender_key,
+                        },
+                    )
+                })
+                .collect(),
+            outbound: stored
+                .outbound
+                .into_iter()
+                .map(|(room, entry)| {
+                    (
+                        room,
+                        OutboundEntry {
+                            session: GroupSession::from_pickle(entry.session),
+                            shared_with: entry.shared_with,
+                            created_at_ms: entry.created_at_ms,
+                            messages: entry.messages,
+                        },
+                    )
+                })
+                .collect(),
+            seen: HashMap::new(),
+        }
+    }
+}
+
+/// Decrypt with an existing Olm session from `sender_key`, or start an
+/// inbound session when this is a pre-key message for a new one.
+fn decrypt_olm(state: &mut CryptoState, sender_key: &str, message: &OlmMessage) -> Result<Vec<u8>> {
+    if let Some(sessions) = state.olm_sessions.get_mut(sender_key) {
+        for session in sessions.iter_mut() {
+            if let Ok(plaintext) = session.decrypt(message) {
+                return Ok(plaintext);
+            }
+        }
+    }
+    let OlmMessage::PreKey(pre_key) = message else {
+        anyhow::bail!("no Olm session with {sender_key} can decrypt this message");
+    };
+    let result = state
+        .account
+        .create_inbound_session(Curve25519PublicKey::from_base64(sender_key)?, pre_key)?;
+    state
+        .olm_sessions
+        .entry(sender_key.to_string())
+        .or_default()
+        .push(result.session);
+    Ok(result.plaintext)
+}
+
+/// Parse and verify one device from a `/keys/query` response.
+fn parse_device(user_id: &str, device_id: &str, keys: &Value) -> Result<Device> {
+    if keys["user_id"] != user_id || keys["device_id"] != device_id {
+        anyhow::bail!("device keys are for another device");
+    }
+    let curve25519 = keys["keys"][format!("curve25519:{device_id}")]
+        .as_str()
+        .context("no curve25519 key")?;
+    let ed25519 = keys["keys"][format!("ed25519:{device_id}")]
+        .as_str()
+        .context("no ed25519 key")?;
+    let ed25519 = Ed25519PublicKey::from_base64(ed25519)?;
+    verify_signed(keys, user_id, device_id, &ed25519)?;
+    Ok(Device {
+        user_id: user_id.to_string(),
+        device_id: device_id.to_string(),
+        curve25519: Curve25519PublicKey::from_base64(curve25519)?,
+        ed25519,
+    })
+}
+
+/// Check the `signatures.<user>.ed25519:<device>` signature of a JSON object.
+fn verify_signed(
+    object: &Value,
+    user_id: &str,
+    device_id: &str,
+    key: &Ed25519PublicKey,
+) -> Result<()> {
+    let signature = object["signatures"][user_id][format!("ed25519:{device_id}")]
+        .as_str()
+        .context("missing signature")?;
+    key.verify(
+        canonical_json(&unsigned_view(object)).as_bytes(),
+        &Ed25519Signature::from_base64(signature)?,
+    )
+    .map_err(|e| anyhow::anyhow!("signature does not verify: {e}"))
+}
+
+/// The object without `signatures` and `unsigned`, as covered by signatures.
+fn unsigned_view(object: &Value) -> Value {
+    let mut object = object.clone();
+    if let Some(map) = object.as_object_mut() {
+        map.remove("signatures");
+        map.remove("unsigned");
+    }
+    object
+}
+
+/// Matrix canonical JSON: sorted keys, no insignificant whitespace.
+pub(crate) fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let body: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::from(key.as_str()), canonical_json(value))
+                })
+                .collect();
+            format!("{{{}}}", body.join(","))
+        }
+        Value::Array(items) => {
+            let body: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", body.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const BOT: &str = "@bot:local";
+    const ALICE: &str = "@alice:local";
+    const ROOM: &str = "!room:local";
+
+    fn bot(dir: &TempDir) -> MatrixCrypto {
+        MatrixCrypto::load_or_create(
+            dir.path().join("crypto.json"),
+            SecretStore::new(dir.path(), true),
+            BOT,
+            "BOTDEV",
+        )
+        .unwrap()
+    }
+
+    /// Alice's device keys as `/keys/query` returns them, signed by her account.
+    fn alice_device_keys(alice: &Account) -> Value {
+        let mut keys = json!({
+            "user_id": ALICE,
+            "device_id": "ALICEDEV",
+            "algorithms": [OLM_ALGORITHM, MEGOLM_ALGORITHM],
+            "keys": {
+                "curve25519:ALICEDEV": alice.curve25519_key().to_base64(),
+                "ed25519:ALICEDEV": alice.ed25519_key().to_base64(),
+            },
+            "unsigned": { "device_display_name": "phone" },
+        });
+        let signature = alice.sign(canonical_json(&unsigned_view(&keys)));
+        keys["signatures"] = json!({ ALICE: { "ed25519:ALICEDEV": signature.to_base64() } });
+        keys
+    }
+
+    /// Alice's Olm-encrypted to-device message carrying `group`'s room key.
+    fn alice_room_key(bot: &MatrixCrypto, alice: &Account, group: &GroupSession) -> ToDeviceEvent {
+        let body = bot.keys_upload_body(None).unwrap();
+        let (_, otk) = body["one_time_keys"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .next()
+            .unwrap();
+        let otk = Curve25519PublicKey::from_base64(otk["key"].as_str().unwrap()).unwrap();
+        let mut olm =
+            alice.create_outbound_session(SessionConfig::version_1(), bot.curve25519_key(), otk);
+
+        let payload = json!({
+            "type": "m.room_key",
+            "content": {
+                "algorithm": MEGOLM_ALGORITHM,
+                "room_id": ROOM,
+                "session_id": group.session_id(),
+                "session_key": group.session_key().to_base64(),
+            },
+            "sender": ALICE,
+            "sender_device": "ALICEDEV",
+            "keys": { "ed25519": alice.ed25519_key().to_base64() },
+            "recipient": BOT,
+            "recipient_keys": { "ed25519": bot.ed25519_key().to_base64() },
+        });
+        ToDeviceEvent {
+            event_type: "m.room.encrypted".into(),
+            sender: ALICE.into(),
+            content: json!({
+                "algorithm": OLM_ALGORITHM,
+                "sender_key": alice.curve25519_key().to_base64(),
+                "ciphertext": {
+                    bot.curve25519_key().to_base64(): olm.encrypt(payload.to_string())
+                },
+            }),
+        }
+    }
+
+    /// What `/keys/query` reports for Alice when `account` holds her device.
+    fn alice_devices(bot: &MatrixCrypto, account: &Account) -> Vec<Device> {
+        bot.verified_devices(&json!({
+            "device_keys": { ALICE: { "ALICEDEV": alice_device_keys(account) } }
+        }))
+    }
+
+    /// Alice sends the bot a room key and a message encrypted with it.
+    fn alice_sends(bot: &MatrixCrypto, alice: &Account, text: &str) -> Value {
+        let mut group = GroupSession::new(MegolmConfig::version_1());
+        let event = alice_room_key(bot, alice, &group);
+        assert_eq!(
+            bot.receive_to_device(&[event], &alice_devices(bot, alice)),
+            1
+        );
+
+        let event = json!({
+            "type": "m.room.message",
+            "content": { "msgtype": "m.text", "body": text },
+            "room_id": ROOM,
+        });
+        json!({
+            "algorithm": MEGOLM_ALGORITHM,
+            "sender_key": alice.curve25519_key().to_base64(),
+            "ciphertext": group.encrypt(event.to_string()).to_base64(),
+            "session_id": group.session_id(),
+            "device_id": "ALICEDEV",
+        })
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys_without_whitespace() {
+        let value = json!({"b": 1, "a": {"d": [true, null], "c": "x"}});
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":{"c":"x","d":[true,null]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn upload_body_is_signed_and_published_once() {
+        let dir = TempDir::new().unwrap();
+        let bot = bot(&dir);
+
+        let body = bot.keys_upload_body(Some(0)).unwrap();
+        let device = parse_device(BOT, "BOTDEV", &body["device_keys"]).unwrap();
+        assert_eq!(device.curve25519, bot.curve25519_key());
+        let otks = body["one_time_keys"].as_object().unwrap();
+        assert!(!otks.is_empty());
+        for signed in otks.values() {
+            verify_signed(signed, BOT, "BOTDEV", &bot.ed25519_key()).unwrap();
+        }
+
+        bot.mark_keys_published().unwrap();
+        let count = u64::try_from(otks.len()).unwrap();
+        assert!(bot.keys_upload_body(Some(count)).is_none());
+    }
+
+    #[test]
+    fn decrypts_room_messages_after_receiving_the_key_and_after_restart() {
+        let dir = TempDir::new().unwrap();
+        let alice = Account::new();
+        let encrypted = {
+            let bot = bot(&dir);
+            let encrypted = alice_sends(&bot, &alice, "secret hello");
+            let event = bot.decrypt_room_event(ROOM, "$1", &encrypted).unwrap();
+            assert_eq!(event["content"]["body"], "secret hello");
+            encrypted
+        };
+
+        // The store on disk is encrypted and survives a restart.
+        let raw = std::fs::read_to_string(dir.path().join("crypto.json")).unwrap();
+        assert!(raw.starts_with("enc2:"));
+        let bot = bot(&dir);
+        let event = bot.decrypt_room_event(ROOM, "$1", &encrypted).unwrap();
+        assert_eq!(event["content"]["body"], "secret hello");
+
+        // The same ciphertext under another event ID is a replay.
+        assert!(bot.decrypt_room_event(ROOM, "$2", &encrypted).is_err());
+        // And it only decrypts in the room the key was sent for.
+        assert!(bot
+            .decrypt_room_event("!other:local", "$1", &encrypted)
+            .is_err());
+    }
+
+    #[test]
+    fn room_keys_need_a_verified_sending_device() {
+        let dir = TempDir::new().unwrap();
+        let bot = bot(&dir);
+        let alice = Account::new();
+        let group = GroupSession::new(MegolmConfig::version_1());
+
+        // No device list for Alice, or one whose keys belong to someone else.
+        assert_eq!(
+            bot.receive_to_device(&[alice_room_key(&bot, &alice, &group)], &[]),
+            0
+        );
+        let impostor = alice_devices(&bot, &Account::new());
+        assert_eq!(impostor.len(), 1);
+        assert_eq!(
+            bot.receive_to_device(&[alice_room_key(&bot, &alice, &group)], &impostor),
+            0
+        );
+        assert!(bot.state.lock().inbound.is_empty());
+    }
+
+    #[test]
+    fn store_for_another_device_is_refused() {
+        let dir = TempDir::new().unwrap();
+        drop(bot(&dir));
+        let err = MatrixCrypto::load_or_create(
+            dir.path().join("crypto.json"),
+            SecretStore::new(dir.path(), true),
+            BOT,
+            "NEWDEV",
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("BOTDEV"));
+    }
+
+    #[test]
+    fn shares_room_key_that_recipient_can_use() {
+        let dir = TempDir::new().unwrap();
+        let bot = bot(&dir);
+        let mut alice = Account::new();
+        alice.generate_one_time_keys(1);
+        let (key_id, otk) = alice.one_time_keys().into_iter().next().unwrap();
+        let mut signed_otk = json!({ "key": otk.to_base64() });
+        let signature = alice.sign(canonical_json(&signed_otk));
+        signed_otk["signatures"] = json!({ ALICE: { "ed25519:ALICEDEV": signature.to_base64() } });
+
+        let query = json!({ "device_keys": { ALICE: { "ALICEDEV": alice_device_keys(&alice) } } });
+        let devices = bot.verified_devices(&query);
+        assert_eq!(devices.len(), 1);
+        let missing = bot.devices_without_session(&devices);
+        assert_eq!(missing.len(), 1);
+
+        let claim = json!({ "one_time_keys": { ALICE: { "ALICEDEV": {
+            format!("signed_curve25519:{}", key_id.to_base64()): signed_otk
+        } } } });
+        bot.create_outbound_sessions(&missing, &claim).unwrap();
+        assert!(bot.devices_without_session(&devices).is_empty());
+
+        let messages = bot.share_room_key(ROOM, &devices).unwrap().unwrap();
+        // Already shared: nothing more to send until the session rotates.
+        assert!(bot.share_room_key(ROOM, &devices).unwrap().is_none());
+        let encrypted = bot
+            .encrypt_room_event(
+                ROOM,
+                "m.room.message",
+                &json!({"msgtype": "m.text", "body": "hi alice"}),
+            )
+            .unwrap();
+
+        // Alice's side: open the Olm pre-key message, then the Megolm event.
+        let content = &messages[ALICE]["ALICEDEV"];
+        let olm: OlmMessage = serde_json::from_value(
+            content["ciphertext"][alice.curve25519_key().to_base64()].clone(),
+        )
+        .unwrap();
+        let OlmMessage::PreKey(pre_key) = olm else {
+            panic!("first message must be a pre-key message");
+        };
+        let opened = alice
+            .create_inbound_session(bot.curve25519_key(), &pre_key)
+            .unwrap();
+        let payload: Value = serde_json::from_slice(&opened.plaintext).unwrap();
+        assert_eq!(payload["recipient"], ALICE);
+        let key =
+            SessionKey::from_base64(payload["content"]["session_key"].as_str().unwrap()).unwrap();
+        let mut inbound = InboundGroupSession::new(&key, MegolmConfig::version_1());
+        let message =
+            MegolmMessage::from_base64(encrypted["ciphertext"].as_str().unwrap()).unwrap();
+        let event: Value =
+            serde_json::from_slice(&inbound.decrypt(&message).unwrap().plaintext).unwrap();
+        assert_eq!(event["content"]["body"], "hi alice");
+        assert_eq!(event["room_id"], ROOM);
+    }
+
+    #[test]
+    fn devices_with_bad_signatures_are_dropped() {
+        let dir = TempDir::new().unwrap();
+        let bot = bot(&dir);
+        let alice = Account::new();
+        let mut keys = alice_device_keys(&alice);
+        keys["keys"]["curve25519:ALICEDEV"] = json!(Account::new().curve25519_key().to_base64());
+        let query = json!({ "device_keys": { ALICE: { "ALICEDEV": keys } } });
+        assert!(bot.verified_devices(&query).is_empty());
+    }
+}
@@ -2,11 +2,16 @@ pub mod cli;
 pub mod dingtalk;
 pub mod discord;
 pub mod email_channel;
+pub mod home_assistant;
 pub mod imessage;
 pub mod irc;
 pub mod lark;
 pub mod matrix;
+#[cfg(feature = "matrix-e2ee")]
+pub mod matrix_e2ee;
+pub mod signal;
 pub mod slack;
+pub mod teams;
 pub mod telegram;
 pub mod traits;
 pub mod whatsapp;
@@ -15,17 +20,23 @@ pub use cli::CliChannel;
 pub use dingtalk::DingTalkChannel;
 pub use discord::DiscordChannel;
 pub use email_channel::EmailChannel;
+pub use home_assistant::HomeAssistantChannel;
 pub use imessage::IMessageChannel;
 pub use irc::IrcChannel;
 pub use lark::LarkChannel;
 pub use matrix::MatrixChannel;
+pub use signal::SignalChannel;
 pub use slack::SlackChannel;
+pub use teams::TeamsChannel;
 pub use telegram::TelegramChannel;
-pub use traits::Channel;
+pub use traits::{Allowlist, Channel};
 pub use whatsapp::WhatsAppChannel;
 
+use crate::agent::context::ContextBudget;
 use crate::agent::loop_::{build_tool_instructions, run_tool_call_loop};
+use crate::config::reload::LiveConfig;
 use crate::config::Config;
+use crate::cost::CostTracker;
 use crate::identity;
 use crate::memory::{self, Memory};
 use crate::orchestrator::Orchestrator;
@@ -35,11 +46,12 @@ use crate::runtime;
 use crate::security::SecurityPolicy;
 use crate::tools::{self, Tool};
 use crate::util::truncate_with_ellipsis;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
 /// Maximum characters per injected workspace file (matches `OpenClaw` default).
 const BOOTSTRAP_MAX_CHARS: usize = 20_000;
@@ -65,13 +77,15 @@ struct ChannelRuntimeContext {
     temperature: f64,
     auto_save_memory: bool,
     orchestrator: Option<Orchestrator>,
+    context_budget: ContextBudget,
+    cost: Option<Arc<CostTracker>>,
 }
 
 fn conversation_memory_key(msg: &traits::ChannelMessage) -> String {
     format!("{}_{}_{}", msg.channel, msg.sender, msg.id)
 }
 
-async fn build_memory_context(mem: &dyn Memory, user_msg: &str) -> String {
+pub(crate) async fn build_memory_context(mem: &dyn Memory, user_msg: &str) -> String {
     let mut context = String::new();
 
     if let Ok(entries) = mem.recall(user_msg, 5).await {
@@ -150,7 +164,9 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
         truncate_with_ellipsis(&msg.content, 80)
     );
 
-    let memory_context = build_memory_context(ctx.memory.as_ref(), &msg.content).await;
+    let memory_context = ctx
+        .context_budget
+        .fit_memory_context(build_memory_context(ctx.memory.as_ref(), &msg.content).await);
 
     if ctx.auto_save_memory {
         let autosave_key = conversation_memory_key(&msg);
@@ -218,6 +234,9 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
             ctx.model.as_str(),
             ctx.temperature,
             true, // silent — channels don't write to stdout
+            &ctx.context_budget,
+            ctx.cost.as_deref(),
+            None,
         ),
     )
     .await;
@@ -273,20 +292,51 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
 }
 
 async fn run_message_dispatch_loop(
-    mut rx: tokio::sync::mpsc::Receiver<traits::ChannelMessage>,
+    rx: tokio::sync::mpsc::Receiver<traits::ChannelMessage>,
     ctx: Arc<ChannelRuntimeContext>,
     max_in_flight_messages: usize,
+) {
+    let (_ctx_tx, ctx_rx) = tokio::sync::watch::channel(ctx);
+    run_live_dispatch_loop(rx, ctx_rx, max_in_flight_messages, CancellationToken::new()).await;
+}
+
+/// Dispatch messages to workers; each message uses whichever runtime context
+/// is current when it arrives, so reloads never affect in-flight replies.
+/// Once `shutdown` is cancelled no new message is started, and the loop
+/// returns when the replies already in flight are done.
+async fn run_live_dispatch_loop(
+    mut rx: tokio::sync::mpsc::Receiver<traits::ChannelMessage>,
+    ctx: tokio::sync::watch::Receiver<Arc<ChannelRuntimeContext>>,
+    max_in_flight_messages: usize,
+    shutdown: CancellationToken,
 ) {
     let semaphore = Arc::new(tokio::sync::Semaphore::new(max_in_flight_messages));
     let mut workers = tokio::task::JoinSet::new();
 
-    while let Some(msg) = rx.recv().await {
-        let permit = match Arc::clone(&semaphore).acquire_owned().await {
-            Ok(permit) => permit,
-            Err(_) => break,
+    loop {
+        let msg = tokio::select! {
+            msg = rx.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            () = shutdown.cancelled() => break,
+        };
+        let permit = tokio::select! {
+            permit = Arc::clone(&semaphore).acquire_owned() => match permit {
+                Ok(permit) => permit,
+                Err(_) => break,
+            },
+            () = shutdown.cancelled() => {
+                tracing::warn!(
+                    "Shutting down: dropped queued message from {} on {}",
+                    msg.sender,
+                    msg.channel
+                );
+                break;
+            }
         };
 
-        let worker_ctx = Arc::clone(&ctx);
+        let worker_ctx = Arc::clone(&ctx.borrow());
         workers.spawn(async move {
             let _permit = permit;
             process_channel_message(worker_ctx, msg).await;
@@ -297,6 +347,9 @@ async fn run_message_dispatch_loop(
         }
     }
 
+    if !workers.is_empty() {
+        tracing::info!("Waiting for {} in-flight channel replies", workers.len());
+    }
     while let Some(result) = workers.join_next().await {
         log_worker_join_result(result);
     }
@@ -581,6 +634,9 @@ pub fn handle_command(command: crate::ChannelCommands, config: &Config) -> Resul
                 ("IRC", config.channels_config.irc.is_some()),
                 ("Lark", config.channels_config.lark.is_some()),
                 ("DingTalk", config.channels_config.dingtalk.is_some()),
+                ("Signal", config.channels_config.signal.is_some()),
+                ("Teams", config.channels_config.teams.is_some()),
+                ("Home Assistant", config.home_assistant.triggers_enabled()),
             ] {
                 println!("  {} {name}", if configured { "✅" } else { "❌" });
             }
@@ -665,15 +721,10 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
     }
 
     if let Some(ref mx) = config.channels_config.matrix {
-        channels.push((
-            "Matrix",
-            Arc::new(MatrixChannel::new(
-                mx.homeserver.clone(),
-                mx.access_token.clone(),
-                mx.room_id.clone(),
-                mx.allowed_users.clone(),
-            )),
-        ));
+        match matrix_channel(&config, mx) {
+            Ok(channel) => channels.push(("Matrix", Arc::new(channel))),
+            Err(e) => println!("  ❌ Matrix    invalid config: {e}"),
+        }
     }
 
     if let Some(ref wa) = config.channels_config.whatsapp {
@@ -689,7 +740,10 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
     }
 
     if let Some(ref email_cfg) = config.channels_config.email {
-        channels.push(("Email", Arc::new(EmailChannel::new(email_cfg.clone()))));
+        channels.push((
+            "Email",
+            Arc::new(EmailChannel::from_config(email_cfg, &config.workspace_dir)),
+        ));
     }
 
     if let Some(ref irc) = config.channels_config.irc {
@@ -717,7 +771,7 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
                 lk.app_id.clone(),
                 lk.app_secret.clone(),
                 lk.verification_token.clone().unwrap_or_default(),
-                9898,
+                lark::CALLBACK_PORT,
                 lk.allowed_users.clone(),
             )),
         ));
@@ -734,6 +788,27 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
         ));
     }
 
+    if let Some(ref sg) = config.channels_config.signal {
+        match SignalChannel::from_config(sg, &config.workspace_dir) {
+            Ok(channel) => channels.push(("Signal", Arc::new(channel))),
+            Err(e) => println!("  ❌ Signal    invalid config: {e}"),
+        }
+    }
+
+    if let Some(ref tm) = config.channels_config.teams {
+        channels.push((
+            "Teams",
+            Arc::new(TeamsChannel::from_config(tm, &config.workspace_dir)),
+        ));
+    }
+
+    if config.home_assistant.triggers_enabled() {
+        match HomeAssistantChannel::from_config(&config.home_assistant) {
+            Ok(channel) => channels.push(("Home Assistant", Arc::new(channel))),
+            Err(e) => println!("  ❌ Home Assistant invalid config: {e}"),
+        }
+    }
+
     if channels.is_empty() {
         println!("No real-time channels configured. Run `zeroclaw onboard` first.");
         return Ok(());
@@ -775,70 +850,15 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
     Ok(())
 }
 
-/// Start all configured channels and route messages to the agent
-#[allow(clippy::too_many_lines)]
-pub async fn start_channels(config: Config) -> Result<()> {
-    let provider_name = config
-        .default_provider
-        .clone()
-        .unwrap_or_else(|| "openrouter".into());
-    let provider: Arc<dyn Provider> = Arc::from(providers::create_resilient_provider(
-        &provider_name,
-        config.api_key.as_deref(),
-        &config.reliability,
-    )?);
-
-    // Warm up the provider connection pool (TLS handshake, DNS, HTTP/2 setup)
-    // so the first real message doesn't hit a cold-start timeout.
-    if let Err(e) = provider.warmup().await {
-        tracing::warn!("Provider warmup failed (non-fatal): {e}");
-    }
-
-    let observer: Arc<dyn Observer> =
-        Arc::from(observability::create_observer(&config.observability));
-    let runtime: Arc<dyn runtime::RuntimeAdapter> =
-        Arc::from(runtime::create_runtime(&config.runtime)?);
-    let security = Arc::new(SecurityPolicy::from_config(
-        &config.autonomy,
-        &config.workspace_dir,
-    ));
-    let model = config
-        .default_model
-        .clone()
-        .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
-    let temperature = config.default_temperature;
-    let mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
-        &config.memory,
-        &config.workspace_dir,
-        config.api_key.as_deref(),
-    )?);
-    let (composio_key, composio_entity_id) = if config.composio.enabled {
-        (
-            config.composio.api_key.as_deref(),
-            Some(config.composio.entity_id.as_str()),
-        )
-    } else {
-        (None, None)
-    };
-    // Build system prompt from workspace identity files + skills
-    let workspace = config.workspace_dir.clone();
-    let tools_registry = Arc::new(tools::all_tools_with_runtime(
-        &security,
-        runtime,
-        Arc::clone(&mem),
-        composio_key,
-        composio_entity_id,
-        &config.browser,
-        &config.http_request,
-        &workspace,
-        &config.agents,
-        config.api_key.as_deref(),
-        &config,
-    ));
-
-    let skills = crate::skills::load_skills_for_run(&workspace);
-    let _skills_env = crate::skills::apply_env_overrides_for_run(&skills);
-
+/// System prompt for runtimes that drive `run_tool_call_loop` directly
+/// (channel server, gateway WebChat): identity files, skills, tool
+/// descriptions and the tool-call protocol.
+pub(crate) fn build_runtime_system_prompt(
+    config: &Config,
+    model: &str,
+    tools_registry: &[Box<dyn Tool>],
+    skills: &[crate::skills::Skill],
+) -> String {
     // Collect tool descriptions for the prompt
     let mut tool_descs: Vec<(&str, &str)> = vec![
         (
@@ -896,110 +916,358 @@ pub async fn start_channels(config: Config) -> Result<()> {
         None
     };
     let mut system_prompt = build_system_prompt(
-        &workspace,
-        &model,
+        &config.workspace_dir,
+        model,
         &tool_descs,
-        &skills,
+        skills,
         Some(&config.identity),
         bootstrap_max_chars,
     );
-    system_prompt.push_str(&build_tool_instructions(tools_registry.as_ref()));
+    system_prompt.push_str(&build_tool_instructions(tools_registry));
+    system_prompt
+}
 
-    if !skills.is_empty() {
-        println!(
-            "  🧩 Skills:   {}",
-            skills
-                .iter()
-                .map(|s| s.name.as_str())
-                .collect::<Vec<_>>()
-                .join(", ")
-        );
+/// Start all configured channels and route messages to the agent
+#[allow(clippy::too_many_lines)]
+pub async fn start_channels(config: Config) -> Result<()> {
+    start_channels_live(
+        crate::config::reload::fixed(config),
+        CancellationToken::new(),
+    )
+    .await
+}
+
+/// A running channel listener and the config it was built from.
+struct ChannelListener {
+    channel: Arc<dyn Channel>,
+    fingerprint: String,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+/// Config keys holding sender allowlists. They are left out of a channel's
+/// fingerprint and swapped into the running channel on reload instead.
+const ALLOWLIST_KEYS: &[&str] = &[
+    "allowed_users",
+    "allowed_groups",
+    "allowed_contacts",
+    "allowed_numbers",
+    "allowed_senders",
+];
+
+type MakeChannel<'a> = Box<dyn FnOnce() -> Result<Arc<dyn Channel>> + 'a>;
+
+/// A configured channel that has not been instantiated yet.
+struct ChannelSpec<'a> {
+    name: &'static str,
+    /// Serialized section without its allowlists; a change means reconnecting.
+    fingerprint: String,
+    /// Allowlists from the section, by config key.
+    allowlists: Vec<(&'static str, Vec<String>)>,
+    make: MakeChannel<'a>,
+}
+
+impl<'a> ChannelSpec<'a> {
+    fn new<T: serde::Serialize>(
+        name: &'static str,
+        section: &T,
+        make: impl FnOnce() -> Result<Arc<dyn Channel>> + 'a,
+    ) -> Self {
+        let mut value = serde_json::to_value(section).unwrap_or_default();
+        let mut allowlists = Vec::new();
+        if let Some(fields) = value.as_object_mut() {
+            for key in ALLOWLIST_KEYS {
+                if let Some(list) = fields.remove(*key) {
+                    allowlists.push((*key, serde_json::from_value(list).unwrap_or_default()));
+                }
+            }
+        }
+        Self {
+            name,
+            fingerprint: value.to_string(),
+            allowlists,
+            make: Box::new(make),
+        }
     }
 
-    // Collect active channels
-    let mut channels: Vec<Arc<dyn Channel>> = Vec::new();
+    /// Instantiate the channel. A bad section only takes its own channel
+    /// down, so failures are logged rather than returned.
+    fn build(self) -> Option<Arc<dyn Channel>> {
+        let name = self.name;
+        (self.make)()
+            .map_err(|e| tracing::error!("Channel {name} not started: {e:#}"))
+            .ok()
+    }
+}
+
+/// Matrix keeps joined rooms and its crypto store next to the config.
+fn matrix_channel(config: &Config, mx: &crate::config::MatrixConfig) -> Result<MatrixChannel> {
+    let zeroclaw_dir = config
+        .config_path
+        .parent()
+        .unwrap_or_else(|| std::path::Path::new("."));
+    let secrets = crate::security::SecretStore::new(zeroclaw_dir, config.secrets.encrypt);
+    MatrixChannel::from_config(mx, zeroclaw_dir, &secrets)
+}
+
+/// Every configured channel, ready to be instantiated on demand.
+fn build_channels(config: &Config) -> Vec<ChannelSpec<'_>> {
+    let mut channels = Vec::new();
 
     if let Some(ref tg) = config.channels_config.telegram {
-        channels.push(Arc::new(TelegramChannel::new(
-            tg.bot_token.clone(),
-            tg.allowed_users.clone(),
-        )));
+        channels.push(ChannelSpec::new("telegram", tg, || {
+            Ok(Arc::new(TelegramChannel::new(
+                tg.bot_token.clone(),
+                tg.allowed_users.clone(),
+            )))
+        }));
     }
 
     if let Some(ref dc) = config.channels_config.discord {
-        channels.push(Arc::new(DiscordChannel::new(
-            dc.bot_token.clone(),
-            dc.guild_id.clone(),
-            dc.allowed_users.clone(),
-            dc.listen_to_bots,
-        )));
+        channels.push(ChannelSpec::new("discord", dc, || {
+            Ok(Arc::new(DiscordChannel::new(
+                dc.bot_token.clone(),
+                dc.guild_id.clone(),
+                dc.allowed_users.clone(),
+                dc.listen_to_bots,
+            )))
+        }));
     }
 
     if let Some(ref sl) = config.channels_config.slack {
-        channels.push(Arc::new(SlackChannel::new(
-            sl.bot_token.clone(),
-            sl.channel_id.clone(),
-            sl.allowed_users.clone(),
-        )));
+        channels.push(ChannelSpec::new("slack", sl, || {
+            Ok(Arc::new(SlackChannel::new(
+                sl.bot_token.clone(),
+                sl.channel_id.clone(),
+                sl.allowed_users.clone(),
+            )))
+        }));
     }
 
     if let Some(ref im) = config.channels_config.imessage {
-        channels.push(Arc::new(IMessageChannel::new(im.allowed_contacts.clone())));
+        channels.push(ChannelSpec::new("imessage", im, || {
+            Ok(Arc::new(IMessageChannel::new(im.allowed_contacts.clone())))
+        }));
     }
 
     if let Some(ref mx) = config.channels_config.matrix {
-        channels.push(Arc::new(MatrixChannel::new(
-            mx.homeserver.clone(),
-            mx.access_token.clone(),
-            mx.room_id.clone(),
-            mx.allowed_users.clone(),
-        )));
+        channels.push(ChannelSpec::new("matrix", mx, || {
+            Ok(Arc::new(matrix_channel(config, mx)?))
+        }));
     }
 
     if let Some(ref wa) = config.channels_config.whatsapp {
-        channels.push(Arc::new(WhatsAppChannel::new(
-            wa.access_token.clone(),
-            wa.phone_number_id.clone(),
-            wa.verify_token.clone(),
-            wa.allowed_numbers.clone(),
-        )));
+        channels.push(ChannelSpec::new("whatsapp", wa, || {
+            Ok(Arc::new(WhatsAppChannel::new(
+                wa.access_token.clone(),
+                wa.phone_number_id.clone(),
+                wa.verify_token.clone(),
+                wa.allowed_numbers.clone(),
+            )))
+        }));
     }
 
     if let Some(ref email_cfg) = config.channels_config.email {
-        channels.push(Arc::new(EmailChannel::new(email_cfg.clone())));
+        channels.push(ChannelSpec::new("email", email_cfg, || {
+            Ok(Arc::new(EmailChannel::from_config(
+                email_cfg,
+                &config.workspace_dir,
+            )))
+        }));
     }
 
     if let Some(ref irc) = config.channels_config.irc {
-        channels.push(Arc::new(IrcChannel::new(
-            irc.server.clone(),
-            irc.port,
-            irc.nickname.clone(),
-            irc.username.clone(),
-            irc.channels.clone(),
-            irc.allowed_users.clone(),
-            irc.server_password.clone(),
-            irc.nickserv_password.clone(),
-            irc.sasl_password.clone(),
-            irc.verify_tls.unwrap_or(true),
-        )));
+        channels.push(ChannelSpec::new("irc", irc, || {
+            Ok(Arc::new(IrcChannel::new(
+                irc.server.clone(),
+                irc.port,
+                irc.nickname.clone(),
+                irc.username.clone(),
+                irc.channels.clone(),
+                irc.allowed_users.clone(),
+                irc.server_password.clone(),
+                irc.nickserv_password.clone(),
+                irc.sasl_password.clone(),
+                irc.verify_tls.unwrap_or(true),
+            )))
+        }));
     }
 
     if let Some(ref lk) = config.channels_config.lark {
-        channels.push(Arc::new(LarkChannel::new(
-            lk.app_id.clone(),
-            lk.app_secret.clone(),
-            lk.verification_token.clone().unwrap_or_default(),
-            9898,
-            lk.allowed_users.clone(),
-        )));
+        channels.push(ChannelSpec::new("lark", lk, || {
+            Ok(Arc::new(LarkChannel::new(
+                lk.app_id.clone(),
+                lk.app_secret.clone(),
+                lk.verification_token.clone().unwrap_or_default(),
+                lark::CALLBACK_PORT,
+                lk.allowed_users.clone(),
+            )))
+        }));
     }
 
     if let Some(ref dt) = config.channels_config.dingtalk {
-        channels.push(Arc::new(DingTalkChannel::new(
-            dt.client_id.clone(),
-            dt.client_secret.clone(),
-            dt.allowed_users.clone(),
-        )));
+        channels.push(ChannelSpec::new("dingtalk", dt, || {
+            Ok(Arc::new(DingTalkChannel::new(
+                dt.client_id.clone(),
+                dt.client_secret.clone(),
+                dt.allowed_users.clone(),
+            )))
+        }));
+    }
+
+    if let Some(ref sg) = config.channels_config.signal {
+        channels.push(ChannelSpec::new("signal", sg, || {
+            let channel =
+                SignalChannel::from_config(sg, &config.workspace_dir).context("invalid config")?;
+            Ok(Arc::new(channel))
+        }));
+    }
+
+    if let Some(ref tm) = config.channels_config.teams {
+        channels.push(ChannelSpec::new("teams", tm, || {
+            Ok(Arc::new(TeamsChannel::from_config(
+                tm,
+                &config.workspace_dir,
+            )))
+        }));
+    }
+
+    if config.home_assistant.triggers_enabled() {
+        let ha = &config.home_assistant;
+        channels.push(ChannelSpec::new("home_assistant", ha, || {
+            let channel = HomeAssistantChannel::from_config(ha).context("invalid config")?;
+            Ok(Arc::new(channel))
+        }));
+    }
+
+    channels
+}
+
+/// Build everything a message worker needs from one config snapshot:
+/// provider, memory, tools under the configured autonomy, and the prompt.
+async fn build_runtime_context(
+    config: &Config,
+    skills: &[crate::skills::Skill],
+    channels_by_name: Arc<HashMap<String, Arc<dyn Channel>>>,
+) -> Result<ChannelRuntimeContext> {
+    let provider_name = config
+        .default_provider
+        .clone()
+        .unwrap_or_else(|| "openrouter".into());
+    let provider: Arc<dyn Provider> = Arc::from(providers::create_resilient_provider(
+        &provider_name,
+        config.api_key.as_deref(),
+        &config.reliability,
+    )?);
+
+    // Warm up the provider connection pool (TLS handshake, DNS, HTTP/2 setup)
+    // so the first real message doesn't hit a cold-start timeout.
+    if let Err(e) = provider.warmup().await {
+        tracing::warn!("Provider warmup failed (non-fatal): {e}");
+    }
+
+    let observer: Arc<dyn Observer> = observability::create_shared_observer(&config.observability);
+    let runtime: Arc<dyn runtime::RuntimeAdapter> =
+        Arc::from(runtime::create_runtime(&config.runtime)?);
+    let security = Arc::new(SecurityPolicy::from_config(
+        &config.autonomy,
+        &config.workspace_dir,
+    ));
+    let model = config
+        .default_model
+        .clone()
+        .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
+    let mem: Arc<dyn Memory> = Arc::from(memory::create_memory(
+        &config.memory,
+        &config.workspace_dir,
+        config.api_key.as_deref(),
+    )?);
+    let (composio_key, composio_entity_id) = if config.composio.enabled {
+        (
+            config.composio.api_key.as_deref(),
+            Some(config.composio.entity_id.as_str()),
+        )
+    } else {
+        (None, None)
+    };
+    // Build system prompt from workspace identity files + skills
+    let workspace = config.workspace_dir.clone();
+    let mut tools_registry = tools::all_tools_with_runtime(
+        &security,
+        runtime,
+        Arc::clone(&mem),
+        composio_key,
+        composio_entity_id,
+        &config.browser,
+        &config.http_request,
+        &workspace,
+        &config.agents,
+        config.api_key.as_deref(),
+        config,
+    );
+    tools_registry.extend(crate::mcp::create_mcp_tools(&config.mcp, &security, &workspace).await);
+    let tools_registry = Arc::new(tools_registry);
+
+    let system_prompt =
+        build_runtime_system_prompt(config, &model, tools_registry.as_ref(), skills);
+
+    let context_budget =
+        ContextBudget::resolve(&config.agent, &config.workspace_dir, &provider_name, &model);
+
+    Ok(ChannelRuntimeContext {
+        channels_by_name,
+        provider,
+        memory: mem,
+        tools_registry,
+        observer,
+        system_prompt: Arc::new(system_prompt),
+        model: Arc::new(model),
+        temperature: config.default_temperature,
+        auto_save_memory: config.memory.auto_save,
+        orchestrator: config
+            .orchestrator
+            .enabled
+            .then(|| Orchestrator::from_config(config.orchestrator.clone())),
+        context_budget,
+        cost: CostTracker::from_config(config),
+    })
+}
+
+/// Run all configured channels against a live config. On reload, agent
+/// settings (provider, model, autonomy, routes, prompt) are swapped in for
+/// new messages, allowlists are updated in place, and only listeners whose
+/// own channel section otherwise changed are restarted — the other
+/// connections stay up. Once `shutdown` is
+/// cancelled the listeners stop and in-flight replies are allowed to finish.
+pub async fn start_channels_live(mut live: LiveConfig, shutdown: CancellationToken) -> Result<()> {
+    let config = live.borrow_and_update().as_ref().clone();
+    let workspace = config.workspace_dir.clone();
+    let skills = crate::skills::load_skills_for_run(&workspace);
+    let _skills_env = crate::skills::apply_env_overrides_for_run(&skills);
+
+    let channels: Vec<(Arc<dyn Channel>, String)> = build_channels(&config)
+        .into_iter()
+        .filter_map(|spec| {
+            let fingerprint = spec.fingerprint.clone();
+            spec.build().map(|ch| (ch, fingerprint))
+        })
+        .collect();
+    let channels_by_name = Arc::new(
+        channels
+            .iter()
+            .map(|(ch, _)| (ch.name().to_string(), Arc::clone(ch)))
+            .collect::<HashMap<_, _>>(),
+    );
+    let runtime_ctx = build_runtime_context(&config, &skills, channels_by_name).await?;
+
+    if !skills.is_empty() {
+        println!(
+            "  🧩 Skills:   {}",
+            skills
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
     }
 
     if channels.is_empty() {
@@ -1008,7 +1276,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
     }
 
     println!("🦀 ZeroClaw Channel Server");
-    println!("  🤖 Model:    {model}");
+    println!("  🤖 Model:    {}", runtime_ctx.model);
     println!(
         "  🧠 Memory:   {} (auto-save: {})",
         config.memory.backend,
@@ -1018,7 +1286,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
         "  📡 Channels: {}",
         channels
             .iter()
-            .map(|c| c.name())
+            .map(|(c, _)| c.name())
             .collect::<Vec<_>>()
             .join(", ")
     );
@@ -1041,50 +1309,231 @@ pub async fn start_channels(config: Config) -> Result<()> {
     let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(100);
 
     // Spawn a listener for each channel
-    let mut handles = Vec::new();
-    for ch in &channels {
-        handles.push(spawn_supervised_listener(
-            ch.clone(),
-            tx.clone(),
-            initial_backoff_secs,
-            max_backoff_secs,
-        ));
+    let mut listeners = HashMap::new();
+    for (ch, fingerprint) in &channels {
+        listeners.insert(
+            ch.name().to_string(),
+            ChannelListener {
+                channel: Arc::clone(ch),
+                fingerprint: fingerprint.clone(),
+                handle: spawn_supervised_listener(
+                    ch.clone(),
+                    tx.clone(),
+                    initial_backoff_secs,
+                    max_backoff_secs,
+                ),
+            },
+        );
     }
-    drop(tx); // Drop our copy so rx closes when all channels stop
 
-    let channels_by_name = Arc::new(
-        channels
-            .iter()
-            .map(|ch| (ch.name().to_string(), Arc::clone(ch)))
-            .collect::<HashMap<_, _>>(),
-    );
     let max_in_flight_messages = compute_max_in_flight_messages(channels.len());
 
     println!("  🚦 In-flight message limit: {max_in_flight_messages}");
 
-    let runtime_ctx = Arc::new(ChannelRuntimeContext {
-        channels_by_name,
-        provider: Arc::clone(&provider),
-        memory: Arc::clone(&mem),
-        tools_registry: Arc::clone(&tools_registry),
-        observer,
-        system_prompt: Arc::new(system_prompt),
-        model: Arc::new(model.clone()),
-        temperature,
-        auto_save_memory: config.memory.auto_save,
-        orchestrator: config.orchestrator.enabled.then(|| Orchestrator::from_config(config.orchestrator.clone())),
-    });
+    let (ctx_tx, ctx_rx) = tokio::sync::watch::channel(Arc::new(runtime_ctx));
+    let mut dispatch = tokio::spawn(run_live_dispatch_loop(
+        rx,
+        ctx_rx,
+        max_in_flight_messages,
+        shutdown.clone(),
+    ));
 
-    run_message_dispatch_loop(rx, runtime_ctx, max_in_flight_messages).await;
+    // Keep a sender only while the config can still change, so the bus closes
+    // once listeners stop when it cannot.
+    let mut tx = Some(tx);
+    let mut stopping = false;
+    loop {
+        tokio::select! {
+            result = &mut dispatch => {
+                log_worker_join_result(result);
+                break;
+            }
+            () = shutdown.cancelled(), if !stopping => {
+                // Stop taking messages; dispatch finishes the replies in flight.
+                stopping = true;
+                tx = None;
+                for listener in listeners.values() {
+                    listener.handle.abort();
+                }
+            }
+            changed = live.changed(), if tx.is_some() => {
+                if changed.is_err() {
+                    tx = None;
+                    continue;
+                }
+                let next = live.borrow_and_update().as_ref().clone();
+                let Some(sender) = tx.as_ref() else { continue };
+                match reload_channels(
+                    &next,
+                    &skills,
+                    &mut listeners,
+                    sender,
+                    &ctx_tx,
+                    (initial_backoff_secs, max_backoff_secs),
+                )
+                .await
+                {
+                    Ok(restarted) if restarted.is_empty() => {
+                        crate::health::record_component_reload("channels", "applied live");
+                    }
+                    Ok(restarted) => {
+                        crate::health::record_component_reload(
+                            "channels",
+                            format!("applied live; restarted listeners: {}", restarted.join(", ")),
+                        );
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Channel config reload failed, keeping previous settings: {e:#}"
+                        );
+                        crate::health::record_component_reload(
+                            "channels",
+                            format!("reload failed, kept previous settings: {e:#}"),
+                        );
+                    }
+                }
+            }
+        }
+    }
 
     // Wait for all channel tasks
-    for h in handles {
-        let _ = h.await;
+    for listener in listeners.into_values() {
+        let _ = listener.handle.await;
     }
 
     Ok(())
 }
 
+/// How a reload changes the running channels.
+#[derive(Default)]
+struct ReloadPlan {
+    /// Every channel that serves messages after the reload
+    channels_by_name: HashMap<String, Arc<dyn Channel>>,
+    /// New or reconfigured channels whose listeners need (re)starting
+    to_start: Vec<(String, Arc<dyn Channel>, String)>,
+    /// Allowlists to swap into channels that keep running
+    allowlists: Vec<(Arc<dyn Channel>, &'static str, Vec<String>)>,
+}
+
+/// Compare configured channels against the running listeners. Only
+/// channels that are new, or whose section changed beyond its allowlists,
+/// are instantiated; the rest keep running and get their allowlists
+/// swapped in place.
+fn plan_reload(
+    specs: Vec<ChannelSpec<'_>>,
+    listeners: &HashMap<String, ChannelListener>,
+) -> ReloadPlan {
+    let mut plan = ReloadPlan::default();
+    for spec in specs {
+        let name = spec.name.to_string();
+        let running = listeners.get(&name);
+        if let Some(running) = running.filter(|r| r.fingerprint == spec.fingerprint) {
+            let swappable = spec
+                .allowlists
+                .iter()
+                .all(|(key, _)| running.channel.allowlist(key).is_some());
+            if swappable {
+                for (key, list) in spec.allowlists {
+                    plan.allowlists
+                        .push((Arc::clone(&running.channel), key, list));
+                }
+                plan.channels_by_name
+                    .insert(name, Arc::clone(&running.channel));
+                continue;
+            }
+        }
+        let fingerprint = spec.fingerprint.clone();
+        match spec.build() {
+            Some(ch) => {
+                plan.channels_by_name.insert(name.clone(), Arc::clone(&ch));
+                plan.to_start.push((name, ch, fingerprint));
+            }
+            None => {
+                if let Some(running) = running {
+                    tracing::warn!("Channel {name} keeps running with its previous config");
+                    plan.channels_by_name
+                        .insert(name, Arc::clone(&running.channel));
+                }
+            }
+        }
+    }
+    plan
+}
+
+/// Apply a new config to running channels: build the new runtime context
+/// first (so a bad config leaves everything untouched), publish it, then
+/// restart only listeners that were added or whose section changed, and
+/// swap allowlists into the others. Returns the names of restarted listeners.
+async fn reload_channels(
+    config: &Config,
+    skills: &[crate::skills::Skill],
+    listeners: &mut HashMap<String, ChannelListener>,
+    tx: &tokio::sync::mpsc::Sender<traits::ChannelMessage>,
+    ctx_tx: &tokio::sync::watch::Sender<Arc<ChannelRuntimeContext>>,
+    (initial_backoff_secs, max_backoff_secs): (u64, u64),
+) -> Result<Vec<String>> {
+    let plan = plan_reload(build_channels(config), listeners);
+
+    let ctx =
+        build_runtime_context(config, skills, Arc::new(plan.channels_by_name.clone())).await?;
+    ctx_tx.send_replace(Arc::new(ctx));
+
+    for (ch, key, list) in plan.allowlists {
+        let Some(allowlist) = ch.allowlist(key) else {
+            continue;
+        };
+        if *allowlist.read() != list {
+            allowlist.replace(list);
+            let name = ch.name();
+            tracing::info!("Channel {name}: {key} updated without restarting");
+            crate::health::record_component_reload(
+                &format!("channel:{name}"),
+                format!("{key} updated in place"),
+            );
+        }
+    }
+
+    let removed: Vec<String> = listeners
+        .keys()
+        .filter(|name| !plan.channels_by_name.contains_key(*name))
+        .cloned()
+        .collect();
+    for name in removed {
+        if let Some(listener) = listeners.remove(&name) {
+            listener.handle.abort();
+            tracing::info!("Channel {name} removed from config; listener stopped");
+        }
+    }
+
+    let mut restarted = Vec::new();
+    for (name, ch, fingerprint) in plan.to_start {
+        if let Some(old) = listeners.remove(&name) {
+            old.handle.abort();
+        }
+        crate::health::record_component_reload(
+            &format!("channel:{name}"),
+            "restarted to apply config changes",
+        );
+        let handle = spawn_supervised_listener(
+            Arc::clone(&ch),
+            tx.clone(),
+            initial_backoff_secs,
+            max_backoff_secs,
+        );
+        listeners.insert(
+            name.clone(),
+            ChannelListener {
+                channel: ch,
+                fingerprint,
+                handle,
+            },
+        );
+        restarted.push(name);
+    }
+
+    Ok(restarted)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1260,6 +1709,8 @@ mod tests {
             temperature: 0.0,
             auto_save_memory: false,
             orchestrator: None,
+            context_budget: ContextBudget::for_window(32_768),
+            cost: None,
         });
 
         process_channel_message(
@@ -1351,6 +1802,8 @@ mod tests {
             temperature: 0.0,
             auto_save_memory: false,
             orchestrator: None,
+            context_budget: ContextBudget::for_window(32_768),
+            cost: None,
         });
 
         let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(4);
@@ -1921,4 +2374,144 @@ mod tests {
             .contains("listen boom"));
         assert!(calls.load(Ordering::SeqCst) >= 1);
     }
+
+    fn started_channels(config: &Config) -> Vec<String> {
+        build_channels(config)
+            .into_iter()
+            .filter_map(ChannelSpec::build)
+            .map(|ch| ch.name().to_string())
+            .collect()
+    }
+
+    fn telegram_config(allowed_users: &[&str]) -> Config {
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "token".into(),
+            allowed_users: allowed_users.iter().map(|u| u.to_string()).collect(),
+        });
+        config
+    }
+
+    #[test]
+    fn build_channels_fingerprint_leaves_out_allowlists() {
+        let fingerprint = |config: &Config| build_channels(config)[0].fingerprint.clone();
+        let mut config = telegram_config(&["alice"]);
+        let spec = build_channels(&config).pop().unwrap();
+        assert_eq!(spec.name, "telegram");
+        assert_eq!(
+            spec.allowlists,
+            [("allowed_users", vec!["alice".to_string()])]
+        );
+        let before = spec.fingerprint.clone();
+        drop(spec);
+
+        config.default_model = Some("other-model".into());
+        config
+            .channels_config
+            .telegram
+            .as_mut()
+            .unwrap()
+            .allowed_users
+            .push("bob".into());
+        assert_eq!(before, fingerprint(&config));
+
+        config.channels_config.telegram.as_mut().unwrap().bot_token = "rotated".into();
+        assert_ne!(before, fingerprint(&config));
+    }
+
+    #[tokio::test]
+    async fn reload_swaps_allowlists_without_rebuilding_the_channel() {
+        let config = telegram_config(&["alice"]);
+        let spec = build_channels(&config).pop().unwrap();
+        let fingerprint = spec.fingerprint.clone();
+        let channel = spec.build().unwrap();
+        let mut listeners = HashMap::new();
+        listeners.insert(
+            "telegram".to_string(),
+            ChannelListener {
+                channel: Arc::clone(&channel),
+                fingerprint,
+                handle: tokio::spawn(async {}),
+            },
+        );
+
+        let plan = plan_reload(build_channels(&telegram_config(&["bob"])), &listeners);
+        assert!(plan.to_start.is_empty());
+        assert!(Arc::ptr_eq(&plan.channels_by_name["telegram"], &channel));
+        let (target, key, list) = &plan.allowlists[0];
+        assert!(Arc::ptr_eq(target, &channel));
+        assert_eq!(
+            (*key, list.as_slice()),
+            ("allowed_users", ["bob".to_string()].as_slice())
+        );
+
+        let mut rotated = telegram_config(&["bob"]);
+        rotated.channels_config.telegram.as_mut().unwrap().bot_token = "rotated".into();
+        let plan = plan_reload(build_channels(&rotated), &listeners);
+        assert_eq!(plan.to_start.len(), 1);
+        assert!(!Arc::ptr_eq(&plan.to_start[0].1, &channel));
+        assert!(plan.allowlists.is_empty());
+    }
+
+    #[test]
+    fn build_channels_skips_invalid_home_assistant_config() {
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "token".into(),
+            allowed_users: vec!["alice".into()],
+        });
+        config.home_assistant.enabled = true;
+        config.home_assistant.triggers = vec![crate::config::schema::HomeAssistantTrigger {
+            entity_id: "binary_sensor.front_door".into(),
+            to: Some("on".into()),
+            prompt: None,
+        }];
+
+        assert_eq!(started_channels(&config), ["telegram"]);
+    }
+
+    #[cfg(not(feature = "matrix-e2ee"))]
+    #[test]
+    fn build_channels_skips_matrix_that_cannot_start() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "token".into(),
+            allowed_users: vec!["alice".into()],
+        });
+        config.channels_config.matrix = Some(crate::config::MatrixConfig {
+            homeserver: "https://matrix.org".into(),
+            access_token: "syt_test".into(),
+            room_id: "!room:matrix.org".into(),
+            allowed_users: vec!["*".into()],
+            rooms: Vec::new(),
+            auto_join: true,
+            thread_replies: true,
+            e2ee: true,
+            store_dir: Some(tmp.path().join("matrix").display().to_string()),
+        });
+
+        assert_eq!(started_channels(&config), ["telegram"]);
+    }
+
+    #[test]
+    fn build_channels_skips_invalid_signal_config() {
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "token".into(),
+            allowed_users: vec!["alice".into()],
+        });
+        config.channels_config.signal = Some(crate::config::schema::SignalConfig {
+            endpoint: "tcp://".into(),
+            account: None,
+            allowed_users: vec!["*".into()],
+            allowed_groups: Vec::new(),
+            attachments_dir: None,
+        });
+
+        assert_eq!(started_channels(&config), ["telegram"]);
+    }
 }
@@ -0,0 +1,995 @@
+use super::traits::{Allowlist, Channel, ChannelMessage};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+/// Prefix used for group reply targets (`group:<base64 group id>`).
+const GROUP_PREFIX: &str = "group:";
+/// Signal clients hide the typing indicator after ~15s, so refresh before that.
+const TYPING_REFRESH_SECS: u64 = 10;
+/// Upper bound for a single JSON-RPC round-trip.
+const RPC_TIMEOUT_SECS: u64 = 30;
+
+/// Signal channel — talks to a local `signal-cli` daemon over JSON-RPC.
+///
+/// Supports both daemon modes:
+/// - `signal-cli daemon --http 127.0.0.1:8080` → `endpoint = "http://127.0.0.1:8080"`
+///   (requests via `POST /api/v1/rpc`, messages via the `/api/v1/events` SSE stream)
+/// - `signal-cli daemon --tcp 127.0.0.1:7583` → `endpoint = "tcp://127.0.0.1:7583"`
+///   (newline-delimited JSON-RPC, `receive` notifications pushed on the socket)
+///
+/// Reply targets are the sender's number/UUID for 1:1 chats and
+/// `group:<group id>` for group chats.
+#[derive(Clone)]
+pub struct SignalChannel {
+    transport: SignalTransport,
+    account: Option<String>,
+    allowed_users: Allowlist,
+    allowed_groups: Allowlist,
+    attachments_dir: Option<PathBuf>,
+    outbound_root: Option<PathBuf>,
+    client: reqwest::Client,
+    typing_handles: Arc<parking_lot::Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SignalTransport {
+    /// Base URL of the HTTP daemon (no trailing slash)
+    Http(String),
+    /// `host:port` of the TCP socket daemon
+    Tcp(String),
+}
+
+impl SignalTransport {
+    fn parse(endpoint: &str) -> anyhow::Result<Self> {
+        let endpoint = endpoint.trim().trim_end_matches('/');
+        if let Some(addr) = endpoint.strip_prefix("tcp://") {
+            if addr.is_empty() {
+                anyhow::bail!("Signal endpoint 'tcp://' is missing host:port");
+            }
+            return Ok(Self::Tcp(addr.to_string()));
+        }
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            return Ok(Self::Http(endpoint.to_string()));
+        }
+        anyhow::bail!(
+            "Unsupported Signal endpoint '{endpoint}' — use http://host:port (signal-cli --http) \
+             or tcp://host:port (signal-cli --tcp)"
+        )
+    }
+}
+
+/// Target of an outgoing Signal message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SignalTarget {
+    Direct(String),
+    Group(String),
+}
+
+impl SignalTarget {
+    fn parse(recipient: &str) -> Self {
+        match recipient.strip_prefix(GROUP_PREFIX) {
+            Some(group_id) => Self::Group(group_id.to_string()),
+            None => Self::Direct(recipient.to_string()),
+        }
+    }
+
+    fn apply(&self, params: &mut serde_json::Map<String, serde_json::Value>) {
+        match self {
+            Self::Direct(number) => {
+                params.insert("recipient".into(), serde_json::json!([number]));
+            }
+            Self::Group(group_id) => {
+                params.insert("groupId".into(), serde_json::json!(group_id));
+            }
+        }
+    }
+}
+
+impl SignalChannel {
+    pub fn new(
+        endpoint: &str,
+        account: Option<String>,
+        allowed_users: Vec<String>,
+        allowed_groups: Vec<String>,
+        attachments_dir: Option<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            transport: SignalTransport::parse(endpoint)?,
+            account: account.filter(|a| !a.trim().is_empty()),
+            allowed_users: Allowlist::new(allowed_users),
+            allowed_groups: Allowlist::new(allowed_groups),
+            attachments_dir,
+            outbound_root: None,
+            client: reqwest::Client::new(),
+            typing_handles: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Build from config. Outgoing attachments are restricted to `workspace_dir`.
+    pub fn from_config(
+        config: &crate::config::schema::SignalConfig,
+        workspace_dir: &Path,
+    ) -> anyhow::Result<Self> {
+        let attachments_dir = config
+            .attachments_dir
+            .as_deref()
+            .map(|dir| PathBuf::from(shellexpand::tilde(dir).into_owned()))
+            .or_else(default_attachments_dir);
+        let mut channel = Self::new(
+            &config.endpoint,
+            config.account.clone(),
+            config.allowed_users.clone(),
+            config.allowed_groups.clone(),
+            attachments_dir,
+        )?;
+        channel.outbound_root = Some(workspace_dir.to_path_buf());
+        Ok(channel)
+    }
+
+    /// Check a sender (E.164 number or ACI UUID) against the allowlist.
+    /// Empty allowlist = deny all, "*" = allow all.
+    fn is_user_allowed(&self, number: Option<&str>, uuid: Option<&str>) -> bool {
+        self.allowed_users.read().iter().any(|u| {
+            u == "*"
+                || number.is_some_and(|n| n == u)
+                || uuid.is_some_and(|id| id.eq_ignore_ascii_case(u))
+        })
+    }
+
+    /// Group messages are only handled for allowlisted group IDs ("*" = any group).
+    fn is_group_allowed(&self, group_id: &str) -> bool {
+        self.allowed_groups
+            .read()
+            .iter()
+            .any(|g| g == "*" || g == group_id)
+    }
+
+    fn rpc_request(&self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let mut params = match params {
+            serde_json::Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        if let Some(ref account) = self.account {
+            params
+                .entry("account")
+                .or_insert_with(|| serde_json::json!(account));
+        }
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Perform a JSON-RPC call and return its `result`.
+    async fn rpc(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        let request = self.rpc_request(method, params);
+        let response = tokio::time::timeout(
+            Duration::from_secs(RPC_TIMEOUT_SECS),
+            self.rpc_roundtrip(&request),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("signal-cli '{method}' timed out"))??;
+
+        if let Some(error) = response.get("error") {
+            let message = error
+                .get("message")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("unknown error");
+            anyhow::bail!("signal-cli '{method}' failed: {message}");
+        }
+        Ok(response
+            .get("result")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null))
+    }
+
+    async fn rpc_roundtrip(
+        &self,
+        request: &serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        match &self.transport {
+            SignalTransport::Http(base) => {
+                let resp = self
+                    .client
+                    .post(format!("{base}/api/v1/rpc"))
+                    .json(request)
+                    .send()
+                    .await?;
+                if !resp.status().is_success() {
+                    let status = resp.status();
+                    let err = resp.text().await.unwrap_or_default();
+                    anyhow::bail!("signal-cli HTTP error ({status}): {err}");
+                }
+                Ok(resp.json().await?)
+            }
+            SignalTransport::Tcp(addr) => {
+                let stream = TcpStream::connect(addr).await?;
+                let (read_half, mut write_half) = stream.into_split();
+                let mut line = serde_json::to_string(request)?;
+                line.push('\n');
+                write_half.write_all(line.as_bytes()).await?;
+
+                let id = request.get("id").cloned().unwrap_or_default();
+                let mut lines = BufReader::new(read_half).lines();
+                while let Some(line) = lines.next_line().await? {
+                    let Ok(value) = serde_json::from_str::<serde_json::Value>(&line) else {
+                        continue;
+                    };
+                    // Skip pushed notifications until our response arrives
+                    if value.get("id") == Some(&id) {
+                        return Ok(value);
+                    }
+                }
+                anyhow::bail!("signal-cli closed the socket before responding")
+            }
+        }
+    }
+
+    /// Split `[ATTACHMENT:/path]` markers out of a reply.
+    /// Only files inside the workspace are sent; others are left in the text.
+    fn extract_attachments(&self, message: &str) -> (String, Vec<String>) {
+        let mut text = String::with_capacity(message.len());
+        let mut attachments = Vec::new();
+        let mut rest = message;
+
+        while let Some(start) = rest.find("[ATTACHMENT:") {
+            let Some(len) = rest[start..].find(']') else {
+                break;
+            };
+            let marker = &rest[start..=start + len];
+            let raw_path = marker["[ATTACHMENT:".len()..marker.len() - 1].trim();
+
+            text.push_str(&rest[..start]);
+            match self.resolve_outbound_path(raw_path) {
+                Some(path) => attachments.push(path),
+                None => text.push_str(marker),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        text.push_str(rest);
+
+        (text.trim().to_string(), attachments)
+    }
+
+    fn resolve_outbound_path(&self, raw_path: &str) -> Option<String> {
+        let root = self.outbound_root.as_ref()?.canonicalize().ok()?;
+        let candidate = Path::new(raw_path);
+        let candidate = if candidate.is_absolute() {
+            candidate.to_path_buf()
+        } else {
+            root.join(candidate)
+        };
+        let resolved = candidate.canonicalize().ok()?;
+        if resolved.starts_with(&root) && resolved.is_file() {
+            Some(resolved.display().to_string())
+        } else {
+            tracing::warn!("Signal: refusing attachment outside workspace: {raw_path}");
+            None
+        }
+    }
+
+    /// Turn a `receive` payload (`{"envelope": …}`) into a channel message.
+    /// Returns `None` for receipts, sync/typing events and unauthorized senders.
+    fn parse_receive(&self, payload: &serde_json::Value) -> Option<ChannelMessage> {
+        let envelope = payload.get("envelope")?;
+        let data = envelope.get("dataMessage")?;
+
+        let number = envelope
+            .get("sourceNumber")
+            .or_else(|| envelope.get("source"))
+            .and_then(serde_json::Value::as_str)
+            .filter(|s| !s.is_empty());
+        let uuid = envelope
+            .get("sourceUuid")
+            .and_then(serde_json::Value::as_str)
+            .filter(|s| !s.is_empty());
+        let sender_id = number.or(uuid)?;
+
+        if !self.is_user_allowed(number, uuid) {
+            tracing::warn!(
+                "Signal: ignoring message from unauthorized sender: {sender_id}. \
+                Add to channels.signal.allowed_users in config.toml."
+            );
+            return None;
+        }
+
+        let group_id = data
+            .get("groupInfo")
+            .and_then(|g| g.get("groupId"))
+            .and_then(serde_json::Value::as_str);
+        if let Some(group_id) = group_id {
+            if !self.is_group_allowed(group_id) {
+                tracing::debug!("Signal: ignoring message in non-allowlisted group {group_id}");
+                return None;
+            }
+        }
+
+        let mut content = data
+            .get("message")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        if let Some(attachments) = data.get("attachments").and_then(|a| a.as_array()) {
+            for attachment in attachments {
+                let line = self.describe_attachment(attachment);
+                if !content.is_empty() {
+                    content.push('\n');
+                }
+                content.push_str(&line);
+            }
+        }
+
+        if content.is_empty() {
+            return None;
+        }
+
+        let sender = match group_id {
+            Some(group_id) => format!("{GROUP_PREFIX}{group_id}"),
+            None => sender_id.to_string(),
+        };
+        let timestamp_ms = data
+            .get("timestamp")
+            .or_else(|| envelope.get("timestamp"))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or_default();
+
+        Some(ChannelMessage {
+            id: format!("{sender_id}_{timestamp_ms}"),
+            sender,
+            content,
+            channel: "signal".to_string(),
+            timestamp: if timestamp_ms > 0 {
+                timestamp_ms / 1000
+            } else {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            },
+        })
+    }
+
+    fn describe_attachment(&self, attachment: &serde_json::Value) -> String {
+        let content_type = attachment
+            .get("contentType")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("application/octet-stream");
+        let name = attachment
+            .get("filename")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unnamed");
+        let path = attachment
+            .get("id")
+            .and_then(serde_json::Value::as_str)
+            .filter(|id| is_plain_file_name(id))
+            .zip(self.attachments_dir.as_ref())
+            .map(|(id, dir)| dir.join(id).display().to_string());
+
+        match path {
+            Some(path) => format!("[Attachment: {name} ({content_type}) at {path}]"),
+            None => format!("[Attachment: {name} ({content_type})]"),
+        }
+    }
+
+    async fn send_typing(&self, target: &SignalTarget, stop: bool) -> anyhow::Result<()> {
+        let mut params = serde_json::Map::new();
+        target.apply(&mut params);
+        if stop {
+            params.insert("stop".into(), serde_json::json!(true));
+        }
+        self.rpc("sendTyping", serde_json::Value::Object(params))
+            .await
+            .map(|_| ())
+    }
+
+    /// Forward a `receive` payload to the agent. Returns false once the receiver is gone.
+    async fn dispatch(
+        &self,
+        payload: &serde_json::Value,
+        tx: &tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> bool {
+        match self.parse_receive(payload) {
+            Some(msg) => tx.send(msg).await.is_ok(),
+            None => true,
+        }
+    }
+
+    async fn listen_http(
+        &self,
+        base: &str,
+        tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<()> {
+        let mut request = self.client.get(format!("{base}/api/v1/events"));
+        if let Some(ref account) = self.account {
+            request = request.query(&[("account", account)]);
+        }
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("signal-cli event stream failed ({status}): {err}");
+        }
+
+        tracing::info!("Signal: connected to signal-cli event stream");
+
+        let mut stream = resp.bytes_stream();
+        let mut buffer = String::new();
+        while let Some(chunk) = stream.next().await {
+            buffer.push_str(&String::from_utf8_lossy(&chunk?));
+            while let Some(pos) = buffer.find("\n\n") {
+                let event: String = buffer.drain(..pos + 2).collect();
+                let Some(payload) = parse_sse_event(&event) else {
+                    continue;
+                };
+                if !self.dispatch(&payload, &tx).await {
+                    return Ok(());
+                }
+            }
+        }
+
+        anyhow::bail!("signal-cli event stream ended")
+    }
+
+    async fn listen_tcp(
+        &self,
+        addr: &str,
+        tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<()> {
+        let stream = TcpStream::connect(addr).await?;
+        tracing::info!("Signal: connected to signal-cli socket at {addr}");
+
+        let mut lines = BufReader::new(stream).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Ok(value) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            if value.get("method").and_then(serde_json::Value::as_str) != Some("receive") {
+                continue;
+            }
+            let Some(payload) = value.get("params") else {
+                continue;
+            };
+            if !self.dispatch(payload, &tx).await {
+                return Ok(());
+            }
+        }
+
+        anyhow::bail!("signal-cli socket closed")
+    }
+}
+
+/// Extract the JSON payload from one SSE event block (`data:` lines joined).
+/// Accepts both a bare `{"envelope": …}` payload and a full JSON-RPC notification.
+fn parse_sse_event(event: &str) -> Option<serde_json::Value> {
+    let data: Vec<&str> = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+    if data.is_empty() {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(&data.join("\n")).ok()?;
+    match value.get("params") {
+        Some(params) if value.get("method").is_some() => Some(params.clone()),
+        _ => Some(value),
+    }
+}
+
+/// Where `signal-cli` stores received attachments by default.
+/// The attachment id comes from the sender, so only a single plain file name
+/// may be joined onto the attachments directory.
+fn is_plain_file_name(id: &str) -> bool {
+    !id.contains(['/', '\\'])
+        && matches!(
+            Path::new(id).components().collect::<Vec<_>>().as_slice(),
+            [std::path::Component::Normal(_)]
+        )
+}
+
+fn default_attachments_dir() -> Option<PathBuf> {
+    directories::BaseDirs::new().map(|dirs| {
+        dirs.home_dir()
+            .join(".local")
+            .join("share")
+            .join("signal-cli")
+            .join("attachments")
+    })
+}
+
+#[async_trait]
+impl Channel for SignalChannel {
+    fn name(&self) -> &str {
+        "signal"
+    }
+
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        match key {
+            "allowed_users" => Some(&self.allowed_users),
+            "allowed_groups" => Some(&self.allowed_groups),
+            _ => None,
+        }
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+        let target = SignalTarget::parse(recipient);
+        let (text, attachments) = self.extract_attachments(message);
+
+        let mut params = serde_json::Map::new();
+        target.apply(&mut params);
+        params.insert("message".into(), serde_json::json!(text));
+        if !attachments.is_empty() {
+            params.insert("attachments".into(), serde_json::json!(attachments));
+        }
+
+        self.rpc("send", serde_json::Value::Object(params)).await?;
+        Ok(())
+    }
+
+    async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        match &self.transport {
+            SignalTransport::Http(base) => self.listen_http(base, tx).await,
+            SignalTransport::Tcp(addr) => self.listen_tcp(addr, tx).await,
+        }
+    }
+
+    async fn health_check(&self) -> bool {
+        match &self.transport {
+            SignalTransport::Http(base) => {
+                let check = self.client.get(format!("{base}/api/v1/check")).send();
+                matches!(
+                    tokio::time::timeout(Duration::from_secs(5), check).await,
+                    Ok(Ok(resp)) if resp.status().is_success()
+                )
+            }
+            SignalTransport::Tcp(_) => tokio::time::timeout(
+                Duration::from_secs(5),
+                self.rpc("version", serde_json::json!({})),
+            )
+            .await
+            .is_ok_and(|r| r.is_ok()),
+        }
+    }
+
+    async fn start_typing(&self, recipient: &str) -> anyhow::Result<()> {
+        self.stop_typing_task(recipient);
+
+        let target = SignalTarget::parse(recipient);
+        let channel = self.clone();
+
+        let handle = tokio::spawn(async move {
+            loop {
+                if let Err(e) = channel.send_typing(&target, false).await {
+                    tracing::debug!("Signal: typing indicator failed: {e}");
+                }
+                tokio::time::sleep(Duration::from_secs(TYPING_REFRESH_SECS)).await;
+            }
+        });
+
+        self.typing_handles
+            .lock()
+            .insert(recipient.to_string(), handle);
+        Ok(())
+    }
+
+    async fn stop_typing(&self, recipient: &str) -> anyhow::Result<()> {
+        if self.stop_typing_task(recipient) {
+            self.send_typing(&SignalTarget::parse(recipient), true)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl SignalChannel {
+    /// Abort the refresh task for `recipient`; returns true if one was running.
+    fn stop_typing_task(&self, recipient: &str) -> bool {
+        match self.typing_handles.lock().remove(recipient) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::get, routing::post, Json, Router};
+    use tokio::net::TcpListener;
+
+    fn channel(endpoint: &str, users: &[&str], groups: &[&str]) -> SignalChannel {
+        SignalChannel::new(
+            endpoint,
+            Some("+15550000000".into()),
+            users.iter().map(|s| (*s).to_string()).collect(),
+            groups.iter().map(|s| (*s).to_string()).collect(),
+            Some(PathBuf::from("/var/lib/signal/attachments")),
+        )
+        .unwrap()
+    }
+
+    fn direct_payload(source: &str, text: &str) -> serde_json::Value {
+        serde_json::json!({
+            "account": "+15550000000",
+            "envelope": {
+                "source": source,
+                "sourceNumber": source,
+                "sourceUuid": "a1b2c3d4-0000-0000-0000-000000000001",
+                "sourceName": "Alice",
+                "timestamp": 1_700_000_000_000_u64,
+                "dataMessage": {
+                    "timestamp": 1_700_000_000_000_u64,
+                    "message": text
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn name_is_signal() {
+        let ch = channel("http://127.0.0.1:8080", &[], &[]);
+        assert_eq!(ch.name(), "signal");
+    }
+
+    #[test]
+    fn transport_parsing() {
+        assert_eq!(
+            SignalTransport::parse("http://127.0.0.1:8080/").unwrap(),
+            SignalTransport::Http("http://127.0.0.1:8080".into())
+        );
+        assert_eq!(
+            SignalTransport::parse("tcp://127.0.0.1:7583").unwrap(),
+            SignalTransport::Tcp("127.0.0.1:7583".into())
+        );
+        assert!(SignalTransport::parse("tcp://").is_err());
+        assert!(SignalTransport::parse("/var/run/signal-cli.sock").is_err());
+    }
+
+    #[test]
+    fn target_parsing() {
+        assert_eq!(
+            SignalTarget::parse("+15551234567"),
+            SignalTarget::Direct("+15551234567".into())
+        );
+        assert_eq!(
+            SignalTarget::parse("group:abc=="),
+            SignalTarget::Group("abc==".into())
+        );
+    }
+
+    #[test]
+    fn allowlist_empty_denies_all() {
+        let ch = channel("http://localhost", &[], &[]);
+        assert!(!ch.is_user_allowed(Some("+15551234567"), None));
+    }
+
+    #[test]
+    fn allowlist_wildcard_and_specific() {
+        let ch = channel("http://localhost", &["*"], &[]);
+        assert!(ch.is_user_allowed(Some("+1"), None));
+
+        let ch = channel(
+            "http://localhost",
+            &["+15551234567", "A1B2C3D4-0000-0000-0000-000000000001"],
+            &[],
+        );
+        assert!(ch.is_user_allowed(Some("+15551234567"), None));
+        assert!(ch.is_user_allowed(None, Some("a1b2c3d4-0000-0000-0000-000000000001")));
+        assert!(!ch.is_user_allowed(Some("+15559999999"), None));
+    }
+
+    #[test]
+    fn parse_direct_message() {
+        let ch = channel("http://localhost", &["+15551234567"], &[]);
+        let msg = ch
+            .parse_receive(&direct_payload("+15551234567", "hello"))
+            .unwrap();
+        assert_eq!(msg.sender, "+15551234567");
+        assert_eq!(msg.content, "hello");
+        assert_eq!(msg.channel, "signal");
+        assert_eq!(msg.timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn parse_rejects_unauthorized_sender() {
+        let ch = channel("http://localhost", &["+15551234567"], &[]);
+        assert!(ch
+            .parse_receive(&direct_payload("+15559999999", "hello"))
+            .is_none());
+    }
+
+    #[test]
+    fn parse_ignores_receipts_and_typing() {
+        let ch = channel("http://localhost", &["*"], &[]);
+        let receipt = serde_json::json!({
+            "envelope": {
+                "sourceNumber": "+15551234567",
+                "receiptMessage": {"isDelivery": true, "timestamps": [1]}
+            }
+        });
+        assert!(ch.parse_receive(&receipt).is_none());
+    }
+
+    #[test]
+    fn parse_group_message_requires_group_allowlist() {
+        let mut payload = direct_payload("+15551234567", "hi group");
+        payload["envelope"]["dataMessage"]["groupInfo"] =
+            serde_json::json!({"groupId": "grp==", "type": "DELIVER"});
+
+        let ch = channel("http://localhost", &["*"], &[]);
+        assert!(ch.parse_receive(&payload).is_none());
+
+        let ch = channel("http://localhost", &["*"], &["grp=="]);
+        let msg = ch.parse_receive(&payload).unwrap();
+        assert_eq!(msg.sender, "group:grp==");
+        assert_eq!(msg.content, "hi group");
+    }
+
+    #[test]
+    fn parse_attachment_only_message() {
+        let mut payload = direct_payload("+15551234567", "");
+        payload["envelope"]["dataMessage"]["attachments"] = serde_json::json!([{
+            "contentType": "image/jpeg",
+            "filename": "photo.jpg",
+            "id": "abc123.jpg",
+            "size": 1024
+        }]);
+
+        let ch = channel("http://localhost", &["*"], &[]);
+        let msg = ch.parse_receive(&payload).unwrap();
+        assert_eq!(
+            msg.content,
+            "[Attachment: photo.jpg (image/jpeg) at /var/lib/signal/attachments/abc123.jpg]"
+        );
+    }
+
+    #[test]
+    fn attachment_ids_cannot_escape_the_attachments_dir() {
+        let ch = channel("http://localhost", &["*"], &[]);
+        for id in [
+            "../../etc/passwd",
+            "..",
+            "/etc/passwd",
+            "a/b.jpg",
+            "..\\secret",
+            "",
+        ] {
+            let attachment = serde_json::json!({"contentType": "image/jpeg", "id": id});
+            assert_eq!(
+                ch.describe_attachment(&attachment),
+                "[Attachment: unnamed (image/jpeg)]",
+                "{id}"
+            );
+        }
+    }
+
+    #[test]
+    fn sse_event_parsing() {
+        let event = "event:receive\ndata:{\"envelope\":{\"source\":\"+1\"}}\n\n";
+        let payload = parse_sse_event(event).unwrap();
+        assert_eq!(payload["envelope"]["source"], "+1");
+
+        let wrapped =
+            "data: {\"jsonrpc\":\"2.0\",\"method\":\"receive\",\"params\":{\"envelope\":{}}}\n\n";
+        assert!(parse_sse_event(wrapped).unwrap().get("envelope").is_some());
+
+        assert!(parse_sse_event(":keepalive\n\n").is_none());
+    }
+
+    #[test]
+    fn rpc_request_includes_account() {
+        let ch = channel("http://localhost", &[], &[]);
+        let req = ch.rpc_request("send", serde_json::json!({"message": "x"}));
+        assert_eq!(req["jsonrpc"], "2.0");
+        assert_eq!(req["method"], "send");
+        assert_eq!(req["params"]["account"], "+15550000000");
+        assert!(req["id"].is_string());
+    }
+
+    #[test]
+    fn extract_attachments_only_from_workspace() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("report.pdf"), b"%PDF").unwrap();
+
+        let mut ch = channel("http://localhost", &[], &[]);
+        ch.outbound_root = Some(tmp.path().to_path_buf());
+
+        let (text, files) =
+            ch.extract_attachments("Here you go [ATTACHMENT:report.pdf] [ATTACHMENT:/etc/passwd]");
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("report.pdf"));
+        assert_eq!(text, "Here you go  [ATTACHMENT:/etc/passwd]");
+    }
+
+    // ── Fake signal-cli daemons ──────────────────────────────────
+
+    type Calls = Arc<parking_lot::Mutex<Vec<serde_json::Value>>>;
+
+    async fn rpc_handler(
+        State(calls): State<Calls>,
+        Json(req): Json<serde_json::Value>,
+    ) -> Json<serde_json::Value> {
+        let id = req["id"].clone();
+        let method = req["method"].as_str().unwrap_or_default().to_string();
+        calls.lock().push(req);
+        if method == "send" {
+            Json(serde_json::json!({"jsonrpc": "2.0", "id": id, "result": {"timestamp": 1}}))
+        } else if method == "sendTyping" {
+            Json(serde_json::json!({"jsonrpc": "2.0", "id": id, "result": {}}))
+        } else {
+            Json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32601, "message": "Method not implemented"}
+            }))
+        }
+    }
+
+    async fn events_handler() -> ([(axum::http::header::HeaderName, &'static str); 1], String) {
+        let payload = direct_payload("+15551234567", "ping over sse");
+        (
+            [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+            format!(":keepalive\n\nevent:receive\ndata:{payload}\n\n"),
+        )
+    }
+
+    async fn spawn_fake_http_daemon() -> (String, Calls) {
+        let calls: Calls = Arc::default();
+        let app = Router::new()
+            .route("/api/v1/rpc", post(rpc_handler))
+            .route("/api/v1/events", get(events_handler))
+            .route("/api/v1/check", get(|| async { "" }))
+            .with_state(Arc::clone(&calls));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}"), calls)
+    }
+
+    #[tokio::test]
+    async fn http_send_direct_and_group() {
+        let (endpoint, calls) = spawn_fake_http_daemon().await;
+        let ch = channel(&endpoint, &["*"], &[]);
+
+        ch.send("hello", "+15551234567").await.unwrap();
+        ch.send("hi all", "group:grp==").await.unwrap();
+
+        let calls = calls.lock();
+        assert_eq!(calls[0]["method"], "send");
+        assert_eq!(calls[0]["params"]["recipient"][0], "+15551234567");
+        assert_eq!(calls[0]["params"]["message"], "hello");
+        assert_eq!(calls[0]["params"]["account"], "+15550000000");
+        assert_eq!(calls[1]["params"]["groupId"], "grp==");
+        assert!(calls[1]["params"].get("recipient").is_none());
+    }
+
+    #[tokio::test]
+    async fn http_rpc_error_is_surfaced() {
+        let (endpoint, _calls) = spawn_fake_http_daemon().await;
+        let ch = channel(&endpoint, &["*"], &[]);
+        let err = ch
+            .rpc("listGroups", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Method not implemented"));
+    }
+
+    #[tokio::test]
+    async fn http_typing_start_and_stop() {
+        let (endpoint, calls) = spawn_fake_http_daemon().await;
+        let ch = channel(&endpoint, &["*"], &[]);
+
+        ch.start_typing("+15551234567").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        ch.stop_typing("+15551234567").await.unwrap();
+        assert!(ch.typing_handles.lock().is_empty());
+
+        let calls = calls.lock();
+        let typing: Vec<_> = calls
+            .iter()
+            .filter(|c| c["method"] == "sendTyping")
+            .collect();
+        assert!(typing.len() >= 2);
+        assert_eq!(typing.last().unwrap()["params"]["stop"], true);
+    }
+
+    #[tokio::test]
+    async fn http_listen_forwards_sse_messages() {
+        let (endpoint, _calls) = spawn_fake_http_daemon().await;
+        let ch = channel(&endpoint, &["+15551234567"], &[]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+
+        // The fake stream ends after one event, so listen returns an error afterwards.
+        let result = ch.listen(tx).await;
+        assert!(result.is_err());
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.content, "ping over sse");
+        assert_eq!(msg.sender, "+15551234567");
+    }
+
+    #[tokio::test]
+    async fn http_health_check() {
+        let (endpoint, _calls) = spawn_fake_http_daemon().await;
+        assert!(channel(&endpoint, &[], &[]).health_check().await);
+        assert!(!channel("http://127.0.0.1:9", &[], &[]).health_check().await);
+    }
+
+    #[tokio::test]
+    async fn tcp_listen_and_send() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // First connection: listener — push one notification, then close.
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let note = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "receive",
+                "params": direct_payload("+15551234567", "ping over tcp"),
+            });
+            sock.write_all(format!("{note}\n").as_bytes())
+                .await
+                .unwrap();
+            drop(sock);
+
+            // Second connection: a `send` request — reply after a stray notification.
+            let (sock, _) = listener.accept().await.unwrap();
+            let (read_half, mut write_half) = sock.into_split();
+            let mut lines = BufReader::new(read_half).lines();
+            let req: serde_json::Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            assert_eq!(req["method"], "send");
+            write_half
+                .write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"receive\",\"params\":{}}\n")
+                .await
+                .unwrap();
+            let resp = serde_json::json!({"jsonrpc": "2.0", "id": req["id"], "result": {}});
+            write_half
+                .write_all(format!("{resp}\n").as_bytes())
+                .await
+                .unwrap();
+        });
+
+        let ch = channel(&format!("tcp://{addr}"), &["*"], &[]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        assert!(ch.listen(tx).await.is_err());
+        assert_eq!(rx.recv().await.unwrap().content, "ping over tcp");
+
+        ch.send("pong", "+15551234567").await.unwrap();
+    }
+
+    #[test]
+    fn config_serde() {
+        let toml_str = r#"
+endpoint = "http://127.0.0.1:8080"
+account = "+15550000000"
+allowed_users = ["+15551234567"]
+allowed_groups = ["*"]
+"#;
+        let config: crate::config::schema::SignalConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.endpoint, "http://127.0.0.1:8080");
+        assert_eq!(config.account.as_deref(), Some("+15550000000"));
+        assert_eq!(config.allowed_groups, vec!["*"]);
+        assert!(config.attachments_dir.is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use uuid::Uuid;
 
@@ -6,7 +6,7 @@ use uuid::Uuid;
 pub struct SlackChannel {
     bot_token: String,
     channel_id: Option<String>,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
     client: reqwest::Client,
 }
 
@@ -15,7 +15,7 @@ impl SlackChannel {
         Self {
             bot_token,
             channel_id,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
             client: reqwest::Client::new(),
         }
     }
@@ -24,7 +24,10 @@ impl SlackChannel {
     /// Empty list means deny everyone until explicitly configured.
     /// `"*"` means allow everyone.
     fn is_user_allowed(&self, user_id: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == user_id)
+        self.allowed_users
+            .read()
+            .iter()
+            .any(|u| u == "*" || u == user_id)
     }
 
     /// Get the bot's own user ID so we can ignore our own messages
@@ -52,6 +55,10 @@ impl Channel for SlackChannel {
         "slack"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
+
     async fn send(&self, message: &str, channel: &str) -> anyhow::Result<()> {
         let body = serde_json::json!({
             "channel": channel,
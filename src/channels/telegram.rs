@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use reqwest::multipart::{Form, Part};
 use std::path::Path;
@@ -55,7 +55,7 @@ fn split_message_for_telegram(message: &str) -> Vec<String> {
 /// Telegram channel — long-polls the Bot API for updates
 pub struct TelegramChannel {
     bot_token: String,
-    allowed_users: Vec<String>,
+    allowed_users: Allowlist,
     client: reqwest::Client,
 }
 
@@ -63,7 +63,7 @@ impl TelegramChannel {
     pub fn new(bot_token: String, allowed_users: Vec<String>) -> Self {
         Self {
             bot_token,
-            allowed_users,
+            allowed_users: Allowlist::new(allowed_users),
             client: reqwest::Client::new(),
         }
     }
@@ -73,7 +73,10 @@ impl TelegramChannel {
     }
 
     fn is_user_allowed(&self, username: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == username)
+        self.allowed_users
+            .read()
+            .iter()
+            .any(|u| u == "*" || u == username)
     }
 
     fn is_any_user_allowed<'a, I>(&self, identities: I) -> bool
@@ -417,6 +420,10 @@ impl Channel for TelegramChannel {
         "telegram"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_users").then_some(&self.allowed_users)
+    }
+
     async fn send(&self, message: &str, chat_id: &str) -> anyhow::Result<()> {
         // Split message if it exceeds Telegram's 4096 character limit
         let chunks = split_message_for_telegram(message);
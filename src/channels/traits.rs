@@ -1,4 +1,6 @@
 use async_trait::async_trait;
+use parking_lot::{RwLock, RwLockReadGuard};
+use std::sync::Arc;
 
 /// A message received from or sent to a channel
 #[derive(Debug, Clone)]
@@ -10,6 +12,25 @@ pub struct ChannelMessage {
     pub timestamp: u64,
 }
 
+/// Senders a channel accepts. Clones share the same list, so a config
+/// reload can swap entries under a running listener without reconnecting.
+#[derive(Debug, Clone, Default)]
+pub struct Allowlist(Arc<RwLock<Vec<String>>>);
+
+impl Allowlist {
+    pub fn new(entries: Vec<String>) -> Self {
+        Self(Arc::new(RwLock::new(entries)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Vec<String>> {
+        self.0.read()
+    }
+
+    pub fn replace(&self, entries: Vec<String>) {
+        *self.0.write() = entries;
+    }
+}
+
 /// Core channel trait — implement for any messaging platform
 #[async_trait]
 pub trait Channel: Send + Sync {
@@ -37,6 +58,12 @@ pub trait Channel: Send + Sync {
     async fn stop_typing(&self, _recipient: &str) -> anyhow::Result<()> {
         Ok(())
     }
+
+    /// The allowlist read from config key `key` (e.g. `allowed_users`), if
+    /// this channel can swap it in place on reload.
+    fn allowlist(&self, _key: &str) -> Option<&Allowlist> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -97,6 +124,15 @@ mod tests {
         assert!(channel.start_typing("bob").await.is_ok());
         assert!(channel.stop_typing("bob").await.is_ok());
         assert!(channel.send("hello", "bob").await.is_ok());
+        assert!(channel.allowlist("allowed_users").is_none());
+    }
+
+    #[test]
+    fn allowlist_clones_share_replacements() {
+        let list = Allowlist::new(vec!["alice".into()]);
+        let shared = list.clone();
+        list.replace(vec!["bob".into()]);
+        assert_eq!(*shared.read(), ["bob"]);
     }
 
     #[tokio::test]
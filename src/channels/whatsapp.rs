@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{Allowlist, Channel, ChannelMessage};
 use async_trait::async_trait;
 use uuid::Uuid;
 
@@ -12,7 +12,7 @@ pub struct WhatsAppChannel {
     access_token: String,
     phone_number_id: String,
     verify_token: String,
-    allowed_numbers: Vec<String>,
+    allowed_numbers: Allowlist,
     client: reqwest::Client,
 }
 
@@ -27,14 +27,17 @@ impl WhatsAppChannel {
             access_token,
             phone_number_id,
             verify_token,
-            allowed_numbers,
+            allowed_numbers: Allowlist::new(allowed_numbers),
             client: reqwest::Client::new(),
         }
     }
 
     /// Check if a phone number is allowed (E.164 format: +1234567890)
     fn is_number_allowed(&self, phone: &str) -> bool {
-        self.allowed_numbers.iter().any(|n| n == "*" || n == phone)
+        self.allowed_numbers
+            .read()
+            .iter()
+            .any(|n| n == "*" || n == phone)
     }
 
     /// Get the verify token for webhook verification
@@ -138,6 +141,10 @@ impl Channel for WhatsAppChannel {
         "whatsapp"
     }
 
+    fn allowlist(&self, key: &str) -> Option<&Allowlist> {
+        (key == "allowed_numbers").then_some(&self.allowed_numbers)
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         // WhatsApp Cloud API: POST to /v18.0/{phone_number_id}/messages
         let url = format!(
@@ -0,0 +1,478 @@
+//! `zeroclaw config` — validate, inspect and edit `config.toml`.
+//!
+//! Like `zeroclaw secrets`, this reads the file directly instead of going
+//! through `Config::load_or_init`, so a config the daemon rejects can still
+//! be inspected and fixed from the command line.
+
+use super::schema::write_config_file;
+use super::validate::{validate, ConfigIssue};
+use super::Config;
+use crate::security::secret_sources::{for_each_secret, get_path, is_reference, is_secret_key};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, TableLike};
+
+const REDACTED: &str = "***";
+
+pub fn handle_command(cmd: crate::ConfigCommands) -> Result<()> {
+    run(cmd, &Config::default_dir()?)
+}
+
+fn run(cmd: crate::ConfigCommands, config_dir: &Path) -> Result<()> {
+    let config_path = config_dir.join("config.toml");
+    match cmd {
+        crate::ConfigCommands::Validate => {
+            let config = load(&config_path, config_dir)?;
+            let issues = validate(&config);
+            println!("Config: {}", config_path.display());
+            print_issues(&issues);
+            let errors = issues.iter().filter(|issue| issue.is_error()).count();
+            if errors > 0 {
+                bail!("{errors} error(s) in {}", config_path.display());
+            }
+            println!("✅ Config is valid ({} warning(s))", issues.len());
+        }
+        crate::ConfigCommands::Show => {
+            let table = effective_table(&config_path)?;
+            print!(
+                "{}",
+                toml::to_string_pretty(&table).context("Failed to serialize config")?
+            );
+        }
+        crate::ConfigCommands::Get { key } => {
+            let table = effective_table(&config_path)?;
+            let value = get_path(&table, &key)
+                .with_context(|| format!("`{key}` is not set in {}", config_path.display()))?;
+            match value {
+                toml::Value::String(s) => println!("{s}"),
+                toml::Value::Table(t) => print!("{}", toml::to_string_pretty(t)?),
+                other => println!("{other}"),
+            }
+        }
+        crate::ConfigCommands::Set { key, value } => {
+            set(&config_path, config_dir, &key, &value)?;
+            println!("✅ Set {key} = {value}");
+        }
+        crate::ConfigCommands::Schema => {
+            let schema = schemars::schema_for!(Config);
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+    }
+    Ok(())
+}
+
+fn print_issues(issues: &[ConfigIssue]) {
+    for issue in issues {
+        let icon = if issue.is_error() { "❌" } else { "⚠️ " };
+        println!("  {icon} {issue}");
+    }
+}
+
+fn read_contents(config_path: &Path) -> Result<String> {
+    if !config_path.exists() {
+        bail!(
+            "No config at {} — run `zeroclaw onboard` first",
+            config_path.display()
+        );
+    }
+    fs::read_to_string(config_path).context("Failed to read config file")
+}
+
+/// Parse exactly like daemon startup: schema, secret references, env overrides.
+fn load(config_path: &Path, config_dir: &Path) -> Result<Config> {
+    let mut config = Config::from_toml_str(&read_contents(config_path)?, config_dir)?;
+    config.config_path = config_path.to_path_buf();
+    config.workspace_dir = config_dir.join("workspace");
+    config.apply_env_overrides();
+    Ok(config)
+}
+
+/// The config with defaults and env overrides filled in and every secret
+/// redacted. Secret references (`env:`, `file:`, `cmd:`) are not secret
+/// themselves and are shown as written.
+fn effective_table(config_path: &Path) -> Result<toml::Table> {
+    let mut config: Config =
+        toml::from_str(&read_contents(config_path)?).context("Failed to parse config file")?;
+    config.apply_env_overrides();
+    let mut table = toml::Table::try_from(&config).context("Failed to serialize config")?;
+    for_each_secret(&mut table, &mut |_, value| {
+        if !value.is_empty() && !is_reference(value) {
+            *value = REDACTED.to_string();
+        }
+        Ok(())
+    })?;
+    Ok(table)
+}
+
+fn set(config_path: &Path, config_dir: &Path, key: &str, raw: &str) -> Result<()> {
+    let field = key
+        .rsplit('.')
+        .find(|segment| segment.parse::<usize>().is_err())
+        .unwrap_or_default();
+    if is_secret_key(field) {
+        bail!("`{key}` is a secret; use `zeroclaw secrets set {key}` so it is stored encrypted");
+    }
+
+    let schema = serde_json::to_value(schemars::schema_for!(Config))?;
+    let node = schema_at(&schema, key).with_context(|| format!("`{key}` is not a config key"))?;
+    let value =
+        coerce(raw, &schema_types(node)).with_context(|| format!("Invalid value for `{key}`"))?;
+
+    let original = read_contents(config_path)?;
+    let mut document: DocumentMut = original.parse().context("Failed to parse config file")?;
+    set_in_document(&mut document, key, &value)?;
+    let contents = document.to_string();
+
+    let candidate = Config::from_toml_str(&contents, config_dir)
+        .context("Refusing to write a config that no longer parses")?;
+    // Only errors this edit introduces block it, so a broken config can be
+    // fixed one key at a time.
+    let existing = Config::from_toml_str(&original, config_dir)
+        .map(|config| validate(&config))
+        .unwrap_or_default();
+    let introduced: Vec<ConfigIssue> = validate(&candidate)
+        .into_iter()
+        .filter(|issue| issue.is_error() && !existing.contains(issue))
+        .collect();
+    if !introduced.is_empty() {
+        print_issues(&introduced);
+        bail!(
+            "Refusing to write an invalid config; {} left unchanged",
+            config_path.display()
+        );
+    }
+    write_config_file(config_path, &contents)
+}
+
+/// Set a dotted key in place, keeping comments, key order and formatting.
+/// Missing tables are created; a replaced value keeps its comments.
+fn set_in_document(document: &mut DocumentMut, key: &str, value: &toml::Value) -> Result<()> {
+    let segments: Vec<&str> = key.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        bail!("invalid config key `{key}`");
+    }
+    let (last, parents) = segments.split_last().expect("split yields one segment");
+    let mut new_value: toml_edit::Value = value
+        .to_string()
+        .parse()
+        .with_context(|| format!("Invalid value for `{key}`"))?;
+
+    let mut current: &mut dyn TableLike = document.as_table_mut();
+    for segment in parents {
+        let entry = current.entry(segment).or_insert_with(|| {
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        });
+        current = match entry {
+            Item::Table(t) => t,
+            Item::Value(toml_edit::Value::InlineTable(t)) => t,
+            Item::ArrayOfTables(_) | Item::Value(toml_edit::Value::Array(_)) => {
+                bail!("`{key}`: setting array entries is not supported")
+            }
+            _ => bail!("`{key}`: `{segment}` is not a table"),
+        };
+    }
+    match current.get_mut(last) {
+        Some(Item::Value(old)) => {
+            *new_value.decor_mut() = old.decor().clone();
+            *old = new_value;
+        }
+        _ => {
+            current.insert(last, Item::Value(new_value));
+        }
+    }
+    Ok(())
+}
+
+/// Walk the JSON Schema along a dotted key, following `$ref`s, optional
+/// (`anyOf [T, null]`) wrappers, map values and array items.
+fn schema_at<'a>(root: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    let mut node = resolve(root, root)?;
+    for segment in key.split('.') {
+        node = if let Some(property) = node.get("properties").and_then(|p| p.get(segment)) {
+            property
+        } else if segment.parse::<usize>().is_ok() {
+            node.get("items")?
+        } else {
+            node.get("additionalProperties").filter(|v| v.is_object())?
+        };
+        node = resolve(root, node)?;
+    }
+    Some(node)
+}
+
+fn resolve<'a>(
+    root: &'a serde_json::Value,
+    mut node: &'a serde_json::Value,
+) -> Option<&'a serde_json::Value> {
+    loop {
+        if let Some(reference) = node.get("$ref").and_then(|r| r.as_str()) {
+            node = root.pointer(reference.strip_prefix('#')?)?;
+        } else if let Some(variants) = node
+            .get("anyOf")
+            .or_else(|| node.get("oneOf"))
+            .and_then(|v| v.as_array())
+        {
+            node = variants
+                .iter()
+                .find(|v| v.get("type").and_then(|t| t.as_str()) != Some("null"))?;
+        } else {
+            return Some(node);
+        }
+    }
+}
+
+/// JSON Schema types a node accepts (empty = anything).
+fn schema_types(node: &serde_json::Value) -> Vec<String> {
+    match node.get("type") {
+        Some(serde_json::Value::String(t)) => vec![t.clone()],
+        Some(serde_json::Value::Array(types)) => types
+            .iter()
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect(),
+        _ if node.get("enum").is_some() || node.get("const").is_some() => vec!["string".into()],
+        _ => Vec::new(),
+    }
+}
+
+/// Interpret `raw` as a TOML literal (`true`, `8080`, `["a", "b"]`) when
+/// that matches the schema, falling back to a bare string.
+fn coerce(raw: &str, types: &[String]) -> Result<toml::Value> {
+    let accepts = |t: &str| types.is_empty() || types.iter().any(|allowed| allowed == t);
+    let literal = format!("value = {raw}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut t| t.remove("value"));
+
+    if let Some(value) = literal {
+        let matches = match &value {
+            toml::Value::String(_) | toml::Value::Datetime(_) => accepts("string"),
+            toml::Value::Integer(_) => accepts("integer") || accepts("number"),
+            toml::Value::Float(_) => accepts("number"),
+            toml::Value::Boolean(_) => accepts("boolean"),
+            toml::Value::Array(_) => accepts("array"),
+            toml::Value::Table(_) => accepts("object"),
+        };
+        if matches {
+            return Ok(value);
+        }
+    }
+    if accepts("string") {
+        return Ok(toml::Value::String(raw.to_string()));
+    }
+    bail!("expected {}, got `{raw}`", types.join(" or "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::secret_sources::set_path;
+    use crate::ConfigCommands;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.api_key = Some("sk-plain".into());
+        config.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "env:ZEROCLAW_TEST_CONFIG_CLI_TOKEN".into(),
+            allowed_users: vec!["alice".into()],
+        });
+        fs::write(
+            dir.path().join("config.toml"),
+            toml::to_string_pretty(&config).unwrap(),
+        )
+        .unwrap();
+        dir
+    }
+
+    fn raw_table(dir: &Path) -> toml::Table {
+        fs::read_to_string(dir.join("config.toml"))
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn show_redacts_secrets_but_keeps_references() {
+        let dir = setup();
+        let table = effective_table(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(
+            get_path(&table, "api_key").unwrap().as_str(),
+            Some(REDACTED)
+        );
+        assert_eq!(
+            get_path(&table, "channels_config.telegram.bot_token")
+                .unwrap()
+                .as_str(),
+            Some("env:ZEROCLAW_TEST_CONFIG_CLI_TOKEN")
+        );
+        // Defaults for sections missing from the file are filled in.
+        assert!(get_path(&table, "gateway.port").is_some());
+    }
+
+    #[test]
+    fn set_coerces_by_schema_type() {
+        let dir = setup();
+        std::env::set_var("ZEROCLAW_TEST_CONFIG_CLI_TOKEN", "token");
+        for (key, value) in [
+            ("gateway.port", "4000"),
+            ("gateway.require_pairing", "false"),
+            ("default_temperature", "1"),
+            ("default_model", "some-model"),
+            ("autonomy.level", "full"),
+            ("reliability.fallback_providers", r#"["openai", "ollama"]"#),
+        ] {
+            run(
+                ConfigCommands::Set {
+                    key: key.into(),
+                    value: value.into(),
+                },
+                dir.path(),
+            )
+            .unwrap();
+        }
+
+        let table = raw_table(dir.path());
+        assert_eq!(
+            get_path(&table, "gateway.port").unwrap().as_integer(),
+            Some(4000)
+        );
+        assert_eq!(
+            get_path(&table, "gateway.require_pairing")
+                .unwrap()
+                .as_bool(),
+            Some(false)
+        );
+        assert_eq!(
+            get_path(&table, "default_temperature")
+                .unwrap()
+                .as_integer(),
+            Some(1)
+        );
+        assert_eq!(
+            get_path(&table, "autonomy.level").unwrap().as_str(),
+            Some("full")
+        );
+        assert_eq!(
+            get_path(&table, "reliability.fallback_providers.1")
+                .unwrap()
+                .as_str(),
+            Some("ollama")
+        );
+    }
+
+    #[test]
+    fn set_keeps_comments_and_key_order() {
+        let dir = TempDir::new().unwrap();
+        let original = "# my zeroclaw setup\n\
+default_model = \"m\" # picked by hand\n\
+default_temperature = 0.7\n\
+\n\
+[gateway]\n\
+# local only\n\
+port = 3000\n\
+host = \"127.0.0.1\"\n";
+        fs::write(dir.path().join("config.toml"), original).unwrap();
+        for (key, value) in [
+            ("default_model", "other"),
+            ("gateway.port", "4000"),
+            ("reliability.provider_retries", "5"),
+        ] {
+            run(
+                ConfigCommands::Set {
+                    key: key.into(),
+                    value: value.into(),
+                },
+                dir.path(),
+            )
+            .unwrap();
+        }
+
+        let written = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+        assert_eq!(
+            written,
+            "# my zeroclaw setup\n\
+default_model = \"other\" # picked by hand\n\
+default_temperature = 0.7\n\
+\n\
+[gateway]\n\
+# local only\n\
+port = 4000\n\
+host = \"127.0.0.1\"\n\
+\n\
+[reliability]\n\
+provider_retries = 5\n"
+        );
+    }
+
+    #[test]
+    fn set_rejects_unknown_keys_bad_types_secrets_and_invalid_results() {
+        let dir = setup();
+        std::env::set_var("ZEROCLAW_TEST_CONFIG_CLI_TOKEN", "token");
+        let set = |key: &str, value: &str| {
+            run(
+                ConfigCommands::Set {
+                    key: key.into(),
+                    value: value.into(),
+                },
+                dir.path(),
+            )
+        };
+        let before = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+
+        assert!(set("gateway.nonexistent", "1").is_err());
+        assert!(set("gateway.port", "not-a-port").is_err());
+        assert!(set("api_key", "sk-new").is_err());
+        assert!(set("autonomy.level", "reckless").is_err());
+        assert!(set("default_provider", "nonexistent").is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.toml")).unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn validate_fails_on_hard_errors() {
+        let dir = setup();
+        std::env::set_var("ZEROCLAW_TEST_CONFIG_CLI_TOKEN", "token");
+        assert!(run(ConfigCommands::Validate, dir.path()).is_ok());
+
+        let mut table = raw_table(dir.path());
+        set_path(&mut table, "gateway.host", "0.0.0.0".into()).unwrap();
+        fs::write(
+            dir.path().join("config.toml"),
+            toml::to_string_pretty(&table).unwrap(),
+        )
+        .unwrap();
+        assert!(run(ConfigCommands::Validate, dir.path()).is_err());
+
+        // Existing errors do not block unrelated edits or the fix itself.
+        for (key, value) in [("gateway.port", "4000"), ("gateway.host", "127.0.0.1")] {
+            run(
+                ConfigCommands::Set {
+                    key: key.into(),
+                    value: value.into(),
+                },
+                dir.path(),
+            )
+            .unwrap();
+        }
+        assert!(run(ConfigCommands::Validate, dir.path()).is_ok());
+    }
+
+    #[test]
+    fn schema_walk_follows_refs_options_and_maps() {
+        let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap();
+        assert_eq!(
+            schema_types(schema_at(&schema, "gateway.port").unwrap()),
+            vec!["integer"]
+        );
+        assert!(schema_at(&schema, "channels_config.telegram.allowed_users").is_some());
+        assert!(schema_at(&schema, "agents.researcher.model").is_some());
+        assert!(schema_at(&schema, "model_routes.0.hint").is_some());
+        assert!(schema_at(&schema, "gateway.bogus").is_none());
+    }
+}
@@ -1,15 +1,19 @@
+pub mod cli;
+pub mod reload;
 pub mod schema;
+pub mod validate;
 
 #[allow(unused_imports)]
 pub use schema::{
     AgentConfig, AuditConfig, AutonomyConfig, BrowserComputerUseConfig, BrowserConfig,
     ChannelsConfig, ComposioConfig, Config, CostConfig, DelegateAgentConfig, DiscordConfig,
     DockerRuntimeConfig, GatewayConfig, HardwareConfig, HardwareTransport, HeartbeatConfig,
-    HttpRequestConfig, IMessageConfig, IdentityConfig, LarkConfig, MatrixConfig, MemoryConfig,
-    ModelRouteConfig, ObservabilityConfig, OrchestratorConfig, PeripheralBoardConfig, PeripheralsConfig,
-    ReliabilityConfig, ResourceLimitsConfig, RuntimeConfig, SandboxBackend, SandboxConfig,
-    SchedulerConfig, SecretsConfig, SecurityConfig, SlackConfig, TelegramConfig, TunnelConfig,
-    WebhookConfig,
+    HomeAssistantConfig, HomeAssistantTrigger, HttpRequestConfig, IMessageConfig, IdentityConfig,
+    LarkConfig, MatrixConfig, McpConfig, McpServerConfig, MemoryConfig, ModelPricing,
+    ModelRouteConfig, ObservabilityConfig, OrchestratorConfig, PeripheralBoardConfig,
+    PeripheralsConfig, ReliabilityConfig, ResourceLimitsConfig, RoutingConfig, RoutingMode,
+    RuntimeConfig, SandboxBackend, SandboxConfig, SchedulerConfig, SecretsConfig, SecurityConfig,
+    SlackConfig, TelegramConfig, TunnelConfig, WebhookConfig,
 };
 
 #[cfg(test)]
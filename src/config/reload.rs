@@ -0,0 +1,115 @@
+//! Live config for long-running processes.
+//!
+//! The daemon publishes each validated config through a `watch` channel;
+//! components holding a [`LiveConfig`] read the latest value whenever they
+//! start new work, so swaps are atomic per message, task or job.
+
+use super::Config;
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Receiving end of the daemon's config channel.
+pub type LiveConfig = tokio::sync::watch::Receiver<Arc<Config>>;
+
+/// A [`LiveConfig`] that never changes (for one-shot commands and tests).
+pub fn fixed(config: Config) -> LiveConfig {
+    tokio::sync::watch::channel(Arc::new(config)).1
+}
+
+/// Re-read `current.config_path` and fully validate it the same way startup
+/// does (TOML, schema, secret references, env overrides, semantic checks).
+pub fn load_candidate(current: &Config) -> Result<Config> {
+    let contents = std::fs::read_to_string(&current.config_path)
+        .with_context(|| format!("Failed to read {}", current.config_path.display()))?;
+    let config_dir = current
+        .config_path
+        .parent()
+        .context("Config path must have a parent directory")?;
+    let mut config = Config::from_toml_str(&contents, config_dir)?;
+    config.config_path = current.config_path.clone();
+    config.workspace_dir = config_dir.join("workspace");
+    config.apply_env_overrides();
+    super::validate::ensure_valid(&config)?;
+    Ok(config)
+}
+
+/// Config sections that differ between two configs. Top-level keys are
+/// reported as-is (`autonomy`, `default_model`); `channels_config` is split
+/// per channel (`channels_config.telegram`) so one channel's edit does not
+/// look like a change to all of them.
+pub fn changed_sections(old: &Config, new: &Config) -> BTreeSet<String> {
+    let old = section_values(old);
+    let new = section_values(new);
+    old.keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect()
+}
+
+fn section_values(config: &Config) -> std::collections::BTreeMap<String, toml::Value> {
+    let mut sections = std::collections::BTreeMap::new();
+    let Ok(table) = toml::Table::try_from(config) else {
+        return sections;
+    };
+    for (key, value) in table {
+        match value {
+            toml::Value::Table(channels) if key == "channels_config" => {
+                for (name, channel) in channels {
+                    sections.insert(format!("channels_config.{name}"), channel);
+                }
+            }
+            value => {
+                sections.insert(key, value);
+            }
+        }
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn changed_sections_reports_top_level_and_per_channel_keys() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert!(changed_sections(&old, &new).is_empty());
+
+        new.default_model = Some("other-model".into());
+        new.autonomy.max_actions_per_hour += 1;
+        new.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "token".into(),
+            allowed_users: vec!["alice".into()],
+        });
+
+        let changed = changed_sections(&old, &new);
+        assert_eq!(
+            changed.into_iter().collect::<Vec<_>>(),
+            vec!["autonomy", "channels_config.telegram", "default_model"]
+        );
+    }
+
+    #[test]
+    fn load_candidate_rereads_and_validates_file() {
+        let tmp = TempDir::new().unwrap();
+        let mut current = Config::default();
+        current.config_path = tmp.path().join("config.toml");
+        current.workspace_dir = tmp.path().join("workspace");
+        current.save().unwrap();
+
+        let contents = std::fs::read_to_string(&current.config_path)
+            .unwrap()
+            .replace("default_temperature = 0.7", "default_temperature = 0.2");
+        std::fs::write(&current.config_path, contents).unwrap();
+        let reloaded = load_candidate(&current).unwrap();
+        assert!((reloaded.default_temperature - 0.2).abs() < f64::EPSILON);
+        assert_eq!(reloaded.config_path, current.config_path);
+
+        std::fs::write(&current.config_path, "default_temperature = \"hot\"").unwrap();
+        assert!(load_candidate(&current).is_err());
+    }
+}
@@ -1,6 +1,8 @@
+use crate::security::secret_sources::SecretReferences;
 use crate::security::AutonomyLevel;
 use anyhow::{Context, Result};
 use directories::UserDirs;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
@@ -9,7 +11,7 @@ use std::path::{Path, PathBuf};
 
 // ── Top-level config ──────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     /// Workspace directory - computed from home, not serialized
     #[serde(skip)]
@@ -17,6 +19,9 @@ pub struct Config {
     /// Path to config.toml - computed from home, not serialized
     #[serde(skip)]
     pub config_path: PathBuf,
+    /// Original `env:`/`file:`/`cmd:`/`enc2:` secret values - restored on save
+    #[serde(skip)]
+    pub secret_refs: SecretReferences,
     pub api_key: Option<String>,
     pub default_provider: Option<String>,
     pub default_model: Option<String>,
@@ -28,6 +33,10 @@ pub struct Config {
     #[serde(default)]
     pub autonomy: AutonomyConfig,
 
+    /// Sandboxing, resource limits and audit logging (`[security]`).
+    #[serde(default)]
+    pub security: SecurityConfig,
+
     #[serde(default)]
     pub runtime: RuntimeConfig,
 
@@ -44,6 +53,10 @@ pub struct Config {
     #[serde(default)]
     pub model_routes: Vec<ModelRouteConfig>,
 
+    /// Automatic selection among `model_routes`.
+    #[serde(default)]
+    pub routing: RoutingConfig,
+
     #[serde(default)]
     pub heartbeat: HeartbeatConfig,
 
@@ -74,6 +87,13 @@ pub struct Config {
     #[serde(default)]
     pub http_request: HttpRequestConfig,
 
+    #[serde(default)]
+    pub home_assistant: HomeAssistantConfig,
+
+    /// External Model Context Protocol servers whose tools the agent can call.
+    #[serde(default)]
+    pub mcp: McpConfig,
+
     #[serde(default)]
     pub identity: IdentityConfig,
 
@@ -95,7 +115,7 @@ pub struct Config {
 // ── Delegate Agents ──────────────────────────────────────────────
 
 /// Configuration for a delegate sub-agent used by the `delegate` tool.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DelegateAgentConfig {
     /// Provider name (e.g. "ollama", "openrouter", "anthropic")
     pub provider: String,
@@ -122,12 +142,13 @@ fn default_max_depth() -> u32 {
 // ── Hardware Config (wizard-driven) ─────────────────────────────
 
 /// Hardware transport mode.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum HardwareTransport {
     None,
     Native,
     Serial,
     Probe,
+    Network,
 }
 
 impl Default for HardwareTransport {
@@ -143,12 +164,13 @@ impl std::fmt::Display for HardwareTransport {
             Self::Native => write!(f, "native"),
             Self::Serial => write!(f, "serial"),
             Self::Probe => write!(f, "probe"),
+            Self::Network => write!(f, "network"),
         }
     }
 }
 
 /// Wizard-driven hardware configuration for physical world interaction.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HardwareConfig {
     /// Whether hardware access is enabled
     #[serde(default)]
@@ -165,6 +187,9 @@ pub struct HardwareConfig {
     /// Probe target chip (e.g. "STM32F401RE")
     #[serde(default)]
     pub probe_target: Option<String>,
+    /// Board URL for the network transport (e.g. "tcp://192.168.1.50:7777")
+    #[serde(default)]
+    pub network_url: Option<String>,
     /// Enable workspace datasheet RAG (index PDF schematics for AI pin lookups)
     #[serde(default)]
     pub workspace_datasheets: bool,
@@ -189,12 +214,13 @@ impl Default for HardwareConfig {
             serial_port: None,
             baud_rate: default_baud_rate(),
             probe_target: None,
+            network_url: None,
             workspace_datasheets: false,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AgentConfig {
     /// When true: bootstrap_max_chars=6000, rag_chunk_limit=2. Use for 13B or smaller models.
     #[serde(default)]
@@ -207,6 +233,13 @@ pub struct AgentConfig {
     pub parallel_tools: bool,
     #[serde(default = "default_agent_tool_dispatcher")]
     pub tool_dispatcher: String,
+    /// Context window in tokens. Overrides the model catalog cache and the
+    /// built-in per-model defaults.
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    /// Summarize oversized tool output with the model instead of truncating it.
+    #[serde(default)]
+    pub summarize_tool_output: bool,
 }
 
 fn default_agent_max_tool_iterations() -> usize {
@@ -229,13 +262,15 @@ impl Default for AgentConfig {
             max_history_messages: default_agent_max_history_messages(),
             parallel_tools: false,
             tool_dispatcher: default_agent_tool_dispatcher(),
+            context_window: None,
+            summarize_tool_output: false,
         }
     }
 }
 
 // ── Identity (AIEOS / OpenClaw format) ──────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IdentityConfig {
     /// Identity format: "openclaw" (default) or "aieos"
     #[serde(default = "default_identity_format")]
@@ -264,7 +299,7 @@ impl Default for IdentityConfig {
 
 // ── Cost tracking and budget enforcement ───────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CostConfig {
     /// Enable cost tracking (default: false)
     #[serde(default)]
@@ -291,7 +326,7 @@ pub struct CostConfig {
     pub prices: std::collections::HashMap<String, ModelPricing>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelPricing {
     /// Input price per 1M tokens
     #[serde(default)]
@@ -300,6 +335,56 @@ pub struct ModelPricing {
     /// Output price per 1M tokens
     #[serde(default)]
     pub output: f64,
+
+    /// Price per 1M prompt tokens read from the provider's prompt cache
+    /// (default: the vendor's discount on `input`, e.g. 10% for Claude;
+    /// full `input` for unknown vendors)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+
+    /// Price per 1M prompt tokens written to the provider's prompt cache
+    /// (default: 125% of `input` for Claude, `input` otherwise)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Cache-read price per 1M tokens for `model`, defaulting to its vendor's
+    /// rate.
+    pub fn cache_read_price(&self, model: &str) -> f64 {
+        self.cache_read
+            .unwrap_or_else(|| self.input * cache_multipliers(model).0)
+    }
+
+    /// Cache-write price per 1M tokens for `model`, defaulting to its
+    /// vendor's rate.
+    pub fn cache_write_price(&self, model: &str) -> f64 {
+        self.cache_write
+            .unwrap_or_else(|| self.input * cache_multipliers(model).1)
+    }
+}
+
+/// Default cache read and write prices for `model` as multiples of its input
+/// price. Unknown vendors get no discount, so spend is never under-counted.
+fn cache_multipliers(model: &str) -> (f64, f64) {
+    let model = model.to_ascii_lowercase();
+    let name = model
+        .rsplit_once('/')
+        .map_or(model.as_str(), |(_, name)| name);
+    if name.starts_with("claude") {
+        (0.1, 1.25)
+    } else if ["gpt-", "o1", "o3", "o4"]
+        .iter()
+        .any(|p| name.starts_with(p))
+    {
+        (0.5, 1.0)
+    } else if name.starts_with("gemini") {
+        (0.25, 1.0)
+    } else if name.starts_with("deepseek") {
+        (0.1, 1.0)
+    } else {
+        (1.0, 1.0)
+    }
 }
 
 fn default_daily_limit() -> f64 {
@@ -337,6 +422,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 3.0,
             output: 15.0,
+            cache_read: None,
+            cache_write: None,
         },
     );
     prices.insert(
@@ -344,6 +431,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 15.0,
             output: 75.0,
+            cache_read: None,
+            cache_write: None,
         },
     );
     prices.insert(
@@ -351,6 +440,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 3.0,
             output: 15.0,
+            cache_read: None,
+            cache_write: None,
         },
     );
     prices.insert(
@@ -358,6 +449,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 0.25,
             output: 1.25,
+            cache_read: None,
+            cache_write: None,
         },
     );
 
@@ -367,6 +460,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 5.0,
             output: 15.0,
+            cache_read: None,
+            cache_write: None,
         },
     );
     prices.insert(
@@ -374,6 +469,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 0.15,
             output: 0.60,
+            cache_read: None,
+            cache_write: None,
         },
     );
     prices.insert(
@@ -381,6 +478,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 15.0,
             output: 60.0,
+            cache_read: None,
+            cache_write: None,
         },
     );
 
@@ -390,6 +489,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 0.10,
             output: 0.40,
+            cache_read: None,
+            cache_write: None,
         },
     );
     prices.insert(
@@ -397,6 +498,8 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
         ModelPricing {
             input: 1.25,
             output: 5.0,
+            cache_read: None,
+            cache_write: None,
         },
     );
 
@@ -405,7 +508,7 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
 
 // ── Peripherals (hardware: STM32, RPi GPIO, etc.) ────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PeripheralsConfig {
     /// Enable peripheral support (boards become agent tools)
     #[serde(default)]
@@ -419,19 +522,28 @@ pub struct PeripheralsConfig {
     pub datasheet_dir: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PeripheralBoardConfig {
     /// Board type: "nucleo-f401re", "rpi-gpio", "esp32", etc.
     pub board: String,
-    /// Transport: "serial", "native", "websocket"
+    /// Transport: "serial", "native", "network", "simulator"
     #[serde(default = "default_peripheral_transport")]
     pub transport: String,
-    /// Path for serial: "/dev/ttyACM0", "/dev/ttyUSB0"
+    /// Path for serial: "/dev/ttyACM0", "/dev/ttyUSB0". For "simulator", the
+    /// pty printed by `zeroclaw peripheral simulate`, or unset to run in-process.
+    /// For "network": "tcp://host:port", "ws(s)://host:port/path",
+    /// "mqtt://broker:port/topic" or "mqtts://[user:pass@]broker:port/topic"
     #[serde(default)]
     pub path: Option<String>,
     /// Baud rate for serial (default: 115200)
     #[serde(default = "default_peripheral_baud")]
     pub baud: u32,
+    /// Simulator script (TOML) with pin levels and sensor values; in-process simulator only
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Shared secret for the network handshake (supports env:/file:/cmd: refs)
+    #[serde(default)]
+    pub secret: Option<String>,
 }
 
 fn default_peripheral_transport() -> String {
@@ -459,13 +571,15 @@ impl Default for PeripheralBoardConfig {
             transport: default_peripheral_transport(),
             path: None,
             baud: default_peripheral_baud(),
+            script: None,
+            secret: None,
         }
     }
 }
 
 // ── Gateway security ─────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GatewayConfig {
     /// Gateway port (default: 8080)
     #[serde(default = "default_gateway_port")]
@@ -479,7 +593,8 @@ pub struct GatewayConfig {
     /// Allow binding to non-localhost without a tunnel (default: false)
     #[serde(default)]
     pub allow_public_bind: bool,
-    /// Paired bearer tokens (managed automatically, not user-edited)
+    /// Legacy bearer tokens. Imported once into `gateway_tokens.json` as
+    /// admin-scoped tokens; manage tokens with `zeroclaw gateway tokens`.
     #[serde(default)]
     pub paired_tokens: Vec<String>,
 
@@ -494,6 +609,10 @@ pub struct GatewayConfig {
     /// TTL for webhook idempotency keys.
     #[serde(default = "default_idempotency_ttl_secs")]
     pub idempotency_ttl_secs: u64,
+
+    /// Serve the built-in WebChat UI at `/chat` (default: true)
+    #[serde(default = "default_true")]
+    pub webchat_enabled: bool,
 }
 
 fn default_gateway_port() -> u16 {
@@ -531,13 +650,14 @@ impl Default for GatewayConfig {
             pair_rate_limit_per_minute: default_pair_rate_limit(),
             webhook_rate_limit_per_minute: default_webhook_rate_limit(),
             idempotency_ttl_secs: default_idempotency_ttl_secs(),
+            webchat_enabled: true,
         }
     }
 }
 
 // ── Composio (managed tool surface) ─────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ComposioConfig {
     /// Enable Composio integration for 1000+ OAuth tools
     #[serde(default)]
@@ -566,7 +686,7 @@ impl Default for ComposioConfig {
 
 // ── Secrets (encrypted credential store) ────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SecretsConfig {
     /// Enable encryption for API keys and tokens in config.toml
     #[serde(default = "default_true")]
@@ -581,7 +701,7 @@ impl Default for SecretsConfig {
 
 // ── Browser (friendly-service browsing only) ───────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BrowserComputerUseConfig {
     /// Sidecar endpoint for computer-use actions (OS-level mouse/keyboard/screenshot)
     #[serde(default = "default_browser_computer_use_endpoint")]
@@ -628,7 +748,7 @@ impl Default for BrowserComputerUseConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BrowserConfig {
     /// Enable `browser_open` tool (opens URLs in Brave without scraping)
     #[serde(default)]
@@ -681,7 +801,7 @@ impl Default for BrowserConfig {
 
 // ── HTTP request tool ───────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct HttpRequestConfig {
     /// Enable `http_request` tool for API interactions
     #[serde(default)]
@@ -705,9 +825,152 @@ fn default_http_timeout_secs() -> u64 {
     30
 }
 
+// ── Home Assistant ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HomeAssistantConfig {
+    /// Enable the `ha_*` tools
+    #[serde(default)]
+    pub enabled: bool,
+    /// Base URL of the Home Assistant instance
+    #[serde(default = "default_home_assistant_url")]
+    pub url: String,
+    /// Long-lived access token (Profile → Security in Home Assistant)
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Services `ha_call_service` may invoke, as `domain.service` or `domain.*`
+    #[serde(default)]
+    pub allowed_services: Vec<String>,
+    /// State changes that should prompt the agent
+    #[serde(default)]
+    pub triggers: Vec<HomeAssistantTrigger>,
+    /// REST request timeout in seconds (default: 15)
+    #[serde(default = "default_home_assistant_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// A state change that turns into an agent prompt.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HomeAssistantTrigger {
+    /// Entity to watch, exact (`binary_sensor.front_door`) or `domain.*`
+    pub entity_id: String,
+    /// Only fire when the new state equals this value
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Prompt template; `{entity_id}`, `{name}`, `{from}` and `{to}` are substituted
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+fn default_home_assistant_url() -> String {
+    "http://homeassistant.local:8123".into()
+}
+
+fn default_home_assistant_timeout_secs() -> u64 {
+    15
+}
+
+impl Default for HomeAssistantConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: default_home_assistant_url(),
+            token: None,
+            allowed_services: Vec::new(),
+            triggers: Vec::new(),
+            timeout_secs: default_home_assistant_timeout_secs(),
+        }
+    }
+}
+
+impl HomeAssistantConfig {
+    /// Whether state-change triggers should be subscribed to.
+    pub fn triggers_enabled(&self) -> bool {
+        self.enabled && !self.triggers.is_empty()
+    }
+}
+
+// ── MCP ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpConfig {
+    /// Servers to connect to (`[[mcp.servers]]`)
+    #[serde(default)]
+    pub servers: Vec<McpServerConfig>,
+    /// Port for `zeroclaw mcp serve --transport http` (default: 3001)
+    #[serde(default = "default_mcp_serve_port")]
+    pub serve_port: u16,
+}
+
+fn default_mcp_serve_port() -> u16 {
+    3001
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            serve_port: default_mcp_serve_port(),
+        }
+    }
+}
+
+impl McpConfig {
+    /// Servers that should be connected.
+    pub fn enabled_servers(&self) -> impl Iterator<Item = &McpServerConfig> {
+        self.servers.iter().filter(|server| server.enabled)
+    }
+
+    pub fn has_enabled_servers(&self) -> bool {
+        self.enabled_servers().next().is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct McpServerConfig {
+    /// Short name; tools are registered as `mcp_<name>_<tool>`
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// "stdio" (spawn `command`) or "http" (streamable HTTP at `url`)
+    #[serde(default = "default_mcp_transport")]
+    pub transport: String,
+    /// Program to spawn for the stdio transport
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment for the subprocess; values may be env:/file:/cmd: references
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Endpoint for the http transport, e.g. `https://mcp.example.com/mcp`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Extra request headers for the http transport; values may be references
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Sent as `Authorization: Bearer <token>` by the http transport
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    /// Only import these tools (server-side names); empty imports all of them
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Connect and per-call timeout in seconds (default: 60)
+    #[serde(default = "default_mcp_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_mcp_transport() -> String {
+    "stdio".into()
+}
+
+fn default_mcp_timeout_secs() -> u64 {
+    60
+}
+
 // ── Memory ───────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MemoryConfig {
     /// "sqlite" | "lucid" | "markdown" | "none" (`none` = explicit no-op memory)
     pub backend: String,
@@ -725,7 +988,8 @@ pub struct MemoryConfig {
     /// For sqlite backend: prune conversation rows older than this many days
     #[serde(default = "default_conversation_retention_days")]
     pub conversation_retention_days: u32,
-    /// Embedding provider: "none" | "openai" | "custom:URL"
+    /// Embedding provider: "none" | "openai" | "custom:URL" | "ollama" | "ollama:URL" | "local"
+    /// (`local` runs a small CPU model in-process; requires the `embeddings-local` feature)
     #[serde(default = "default_embedding_provider")]
     pub embedding_provider: String,
     /// Embedding model name (e.g. "text-embedding-3-small")
@@ -838,7 +1102,7 @@ impl Default for MemoryConfig {
 
 // ── Observability ─────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ObservabilityConfig {
     /// "none" | "log" | "prometheus" | "otel"
     pub backend: String,
@@ -864,7 +1128,7 @@ impl Default for ObservabilityConfig {
 
 // ── Autonomy / Security ──────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AutonomyConfig {
     pub level: AutonomyLevel,
     pub workspace_only: bool,
@@ -931,7 +1195,7 @@ impl Default for AutonomyConfig {
 
 // ── Runtime ──────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RuntimeConfig {
     /// Runtime kind (`native` | `docker`).
     #[serde(default = "default_runtime_kind")]
@@ -942,7 +1206,7 @@ pub struct RuntimeConfig {
     pub docker: DockerRuntimeConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DockerRuntimeConfig {
     /// Runtime image used to execute shell commands.
     #[serde(default = "default_docker_image")]
@@ -1018,7 +1282,7 @@ impl Default for RuntimeConfig {
 
 // ── Reliability / supervision ────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReliabilityConfig {
     /// Retries per provider before failing over.
     #[serde(default = "default_provider_retries")]
@@ -1037,6 +1301,15 @@ pub struct ReliabilityConfig {
     /// Example: `{ "claude-opus-4-20250514" = ["claude-sonnet-4-20250514", "gpt-4o"] }`
     #[serde(default)]
     pub model_fallbacks: std::collections::HashMap<String, Vec<String>>,
+    /// Consecutive failures that open a provider/model circuit breaker (0 disables).
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub circuit_failure_threshold: u32,
+    /// Seconds an open circuit skips its provider/model before a trial call.
+    #[serde(default = "default_circuit_open_secs")]
+    pub circuit_open_secs: u64,
+    /// Daemon health probe cadence for providers with a `warmup` check (0 disables).
+    #[serde(default = "default_health_probe_interval_secs")]
+    pub health_probe_interval_secs: u64,
     /// Initial backoff for channel/daemon restarts.
     #[serde(default = "default_channel_backoff_secs")]
     pub channel_initial_backoff_secs: u64,
@@ -1059,6 +1332,18 @@ fn default_provider_backoff_ms() -> u64 {
     500
 }
 
+fn default_circuit_failure_threshold() -> u32 {
+    5
+}
+
+fn default_circuit_open_secs() -> u64 {
+    30
+}
+
+fn default_health_probe_interval_secs() -> u64 {
+    60
+}
+
 fn default_channel_backoff_secs() -> u64 {
     2
 }
@@ -1083,6 +1368,9 @@ impl Default for ReliabilityConfig {
             fallback_providers: Vec::new(),
             api_keys: Vec::new(),
             model_fallbacks: std::collections::HashMap::new(),
+            circuit_failure_threshold: default_circuit_failure_threshold(),
+            circuit_open_secs: default_circuit_open_secs(),
+            health_probe_interval_secs: default_health_probe_interval_secs(),
             channel_initial_backoff_secs: default_channel_backoff_secs(),
             channel_max_backoff_secs: default_channel_backoff_max_secs(),
             scheduler_poll_secs: default_scheduler_poll_secs(),
@@ -1093,7 +1381,7 @@ impl Default for ReliabilityConfig {
 
 // ── Scheduler ────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SchedulerConfig {
     /// Enable the built-in scheduler loop.
     #[serde(default = "default_scheduler_enabled")]
@@ -1144,8 +1432,9 @@ impl Default for SchedulerConfig {
 /// model = "llama-3.3-70b-versatile"
 /// ```
 ///
-/// Usage: pass `hint:reasoning` as the model parameter to route the request.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Usage: pass `hint:reasoning` as the model parameter to route the request,
+/// or set `[routing] mode = "auto"` to have requests classified and routed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelRouteConfig {
     /// Task hint name (e.g. "reasoning", "fast", "code", "summarize")
     pub hint: String,
@@ -1156,11 +1445,50 @@ pub struct ModelRouteConfig {
     /// Optional API key override for this route's provider
     #[serde(default)]
     pub api_key: Option<String>,
+    /// What the route can handle in auto mode: "tools", "vision", plus any
+    /// request categories beyond its hint ("code", "reasoning", …).
+    /// Empty means a general-purpose route that supports tools and vision.
+    /// Tool requests also need a provider with native tool calling.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Stop auto-routing to this route once today's spend on its model, as
+    /// recorded in the cost log, reaches this many USD. Needs `[cost]`
+    /// enabled.
+    #[serde(default)]
+    pub daily_budget_usd: Option<f64>,
+}
+
+/// How `RouterProvider` picks a route.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RoutingMode {
+    /// Only explicit `hint:<name>` models are routed (default)
+    #[default]
+    Hint,
+    /// Requests for the default model are classified and sent to the best route
+    Auto,
+}
+
+/// Automatic model routing.
+///
+/// ```toml
+/// [routing]
+/// mode = "auto"
+/// classifier_hint = "fast"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RoutingConfig {
+    #[serde(default)]
+    pub mode: RoutingMode,
+    /// Route whose model classifies requests that keywords alone cannot
+    /// place. Unset: unmatched requests stay on the default model.
+    #[serde(default)]
+    pub classifier_hint: Option<String>,
 }
 
 // ── Heartbeat ────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HeartbeatConfig {
     pub enabled: bool,
     pub interval_minutes: u32,
@@ -1177,7 +1505,7 @@ impl Default for HeartbeatConfig {
 
 // ── Tunnel ──────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TunnelConfig {
     /// "none", "cloudflare", "tailscale", "ngrok", "custom"
     pub provider: String,
@@ -1207,13 +1535,13 @@ impl Default for TunnelConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CloudflareTunnelConfig {
     /// Cloudflare Tunnel token (from Zero Trust dashboard)
     pub token: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TailscaleTunnelConfig {
     /// Use Tailscale Funnel (public internet) vs Serve (tailnet only)
     #[serde(default)]
@@ -1222,7 +1550,7 @@ pub struct TailscaleTunnelConfig {
     pub hostname: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NgrokTunnelConfig {
     /// ngrok auth token
     pub auth_token: String,
@@ -1230,7 +1558,7 @@ pub struct NgrokTunnelConfig {
     pub domain: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CustomTunnelConfig {
     /// Command template to start the tunnel. Use {port} and {host} placeholders.
     /// Example: "bore local {port} --to bore.pub"
@@ -1243,7 +1571,7 @@ pub struct CustomTunnelConfig {
 
 // ── Channels ─────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChannelsConfig {
     pub cli: bool,
     pub telegram: Option<TelegramConfig>,
@@ -1257,6 +1585,8 @@ pub struct ChannelsConfig {
     pub irc: Option<IrcConfig>,
     pub lark: Option<LarkConfig>,
     pub dingtalk: Option<DingTalkConfig>,
+    pub signal: Option<SignalConfig>,
+    pub teams: Option<TeamsConfig>,
 }
 
 impl Default for ChannelsConfig {
@@ -1274,17 +1604,19 @@ impl Default for ChannelsConfig {
             irc: None,
             lark: None,
             dingtalk: None,
+            signal: None,
+            teams: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TelegramConfig {
     pub bot_token: String,
     pub allowed_users: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiscordConfig {
     pub bot_token: String,
     pub guild_id: Option<String>,
@@ -1296,7 +1628,7 @@ pub struct DiscordConfig {
     pub listen_to_bots: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SlackConfig {
     pub bot_token: String,
     pub app_token: Option<String>,
@@ -1305,26 +1637,42 @@ pub struct SlackConfig {
     pub allowed_users: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WebhookConfig {
     pub port: u16,
     pub secret: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IMessageConfig {
     pub allowed_contacts: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MatrixConfig {
     pub homeserver: String,
     pub access_token: String,
+    /// Default room: listened to, and used for sends without a room target
     pub room_id: String,
     pub allowed_users: Vec<String>,
+    /// Further room IDs to listen in. "*" = every joined room
+    #[serde(default)]
+    pub rooms: Vec<String>,
+    /// Join rooms that an allowed user invites the bot to (and listen there)
+    #[serde(default = "default_true")]
+    pub auto_join: bool,
+    /// Reply in an `m.thread` rooted at the message being answered
+    #[serde(default = "default_true")]
+    pub thread_replies: bool,
+    /// End-to-end encryption for encrypted rooms (needs the `matrix-e2ee` build feature)
+    #[serde(default)]
+    pub e2ee: bool,
+    /// Where joined rooms and the E2EE crypto store are kept. Default: `~/.zeroclaw/matrix`
+    #[serde(default)]
+    pub store_dir: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WhatsAppConfig {
     /// Access token from Meta Business Suite
     pub access_token: String,
@@ -1341,7 +1689,7 @@ pub struct WhatsAppConfig {
     pub allowed_numbers: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IrcConfig {
     /// IRC server hostname
     pub server: String,
@@ -1374,7 +1722,7 @@ fn default_irc_port() -> u16 {
 
 /// Lark/Feishu configuration for messaging integration
 /// Lark is the international version, Feishu is the Chinese version
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LarkConfig {
     /// App ID from Lark/Feishu developer console
     pub app_id: String,
@@ -1397,7 +1745,7 @@ pub struct LarkConfig {
 // ── Security Config ─────────────────────────────────────────────────
 
 /// Security configuration for sandboxing, resource limits, and audit logging
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct SecurityConfig {
     /// Sandbox configuration
     #[serde(default)]
@@ -1413,7 +1761,7 @@ pub struct SecurityConfig {
 }
 
 /// Sandbox configuration for OS-level isolation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SandboxConfig {
     /// Enable sandboxing (None = auto-detect, Some = explicit)
     #[serde(default)]
@@ -1439,7 +1787,7 @@ impl Default for SandboxConfig {
 }
 
 /// Sandbox backend selection
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SandboxBackend {
     /// Auto-detect best available (default)
@@ -1458,7 +1806,7 @@ pub enum SandboxBackend {
 }
 
 /// Resource limits for command execution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ResourceLimitsConfig {
     /// Maximum memory in MB per command
     #[serde(default = "default_max_memory_mb")]
@@ -1505,7 +1853,7 @@ impl Default for ResourceLimitsConfig {
 }
 
 /// Audit logging configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AuditConfig {
     /// Enable audit logging
     #[serde(default = "default_audit_enabled")]
@@ -1548,7 +1896,7 @@ impl Default for AuditConfig {
 }
 
 /// DingTalk (钉钉) configuration for Stream Mode messaging
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DingTalkConfig {
     /// Client ID (AppKey) from DingTalk developer console
     pub client_id: String,
@@ -1559,7 +1907,43 @@ pub struct DingTalkConfig {
     pub allowed_users: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Signal configuration — talks to a local `signal-cli` daemon over JSON-RPC
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SignalConfig {
+    /// signal-cli daemon endpoint: `http://127.0.0.1:8080` (`--http`) or
+    /// `tcp://127.0.0.1:7583` (`--tcp`)
+    pub endpoint: String,
+    /// Registered account number (E.164). Required when the daemon serves multiple accounts
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Allowed sender numbers (E.164) or ACI UUIDs. Empty = deny all, "*" = allow all
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+    /// Group IDs the bot responds in. Empty = ignore group messages, "*" = all groups
+    #[serde(default)]
+    pub allowed_groups: Vec<String>,
+    /// Directory where signal-cli stores received attachments
+    /// (default: `~/.local/share/signal-cli/attachments`)
+    #[serde(default)]
+    pub attachments_dir: Option<String>,
+}
+
+/// Microsoft Teams configuration — a Bot Framework bot registered in Azure
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TeamsConfig {
+    /// Microsoft App ID of the Azure Bot registration
+    pub app_id: String,
+    /// Client secret for the App ID
+    pub app_password: String,
+    /// Entra ID tenant for single-tenant bots (default: multi-tenant `botframework.com`)
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Allowed users by AAD object ID. Empty = deny all, "*" = allow all
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct OrchestratorConfig {
     #[serde(default)]
     pub enabled: bool,
@@ -1601,17 +1985,20 @@ impl Default for Config {
         Self {
             workspace_dir: zeroclaw_dir.join("workspace"),
             config_path: zeroclaw_dir.join("config.toml"),
+            secret_refs: SecretReferences::default(),
             api_key: None,
             default_provider: Some("openrouter".to_string()),
             default_model: Some("anthropic/claude-sonnet-4".to_string()),
             default_temperature: 0.7,
             observability: ObservabilityConfig::default(),
             autonomy: AutonomyConfig::default(),
+            security: SecurityConfig::default(),
             runtime: RuntimeConfig::default(),
             reliability: ReliabilityConfig::default(),
             scheduler: SchedulerConfig::default(),
             agent: AgentConfig::default(),
             model_routes: Vec::new(),
+            routing: RoutingConfig::default(),
             heartbeat: HeartbeatConfig::default(),
             channels_config: ChannelsConfig::default(),
             orchestrator: OrchestratorConfig::default(),
@@ -1622,6 +2009,8 @@ impl Default for Config {
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             http_request: HttpRequestConfig::default(),
+            home_assistant: HomeAssistantConfig::default(),
+            mcp: McpConfig::default(),
             identity: IdentityConfig::default(),
             cost: CostConfig::default(),
             peripherals: PeripheralsConfig::default(),
@@ -1632,11 +2021,16 @@ impl Default for Config {
 }
 
 impl Config {
-    pub fn load_or_init() -> Result<Self> {
+    /// `~/.zeroclaw`, which holds `config.toml`, the secret key and the workspace.
+    pub fn default_dir() -> Result<PathBuf> {
         let home = UserDirs::new()
             .map(|u| u.home_dir().to_path_buf())
             .context("Could not find home directory")?;
-        let zeroclaw_dir = home.join(".zeroclaw");
+        Ok(home.join(".zeroclaw"))
+    }
+
+    pub fn load_or_init() -> Result<Self> {
+        let zeroclaw_dir = Self::default_dir()?;
         let config_path = zeroclaw_dir.join("config.toml");
 
         if !zeroclaw_dir.exists() {
@@ -1648,8 +2042,7 @@ impl Config {
         if config_path.exists() {
             let contents =
                 fs::read_to_string(&config_path).context("Failed to read config file")?;
-            let mut config: Config =
-                toml::from_str(&contents).context("Failed to parse config file")?;
+            let mut config = Self::from_toml_str(&contents, &zeroclaw_dir)?;
             // Set computed paths that are skipped during serialization
             config.config_path = config_path.clone();
             config.workspace_dir = zeroclaw_dir.join("workspace");
@@ -1665,6 +2058,26 @@ impl Config {
         }
     }
 
+    /// Parse config TOML and resolve secret fields: `env:`, `file:` and `cmd:`
+    /// references are read from their source and `enc2:`/`enc:` values are
+    /// decrypted. The original values are kept in `secret_refs` for `save`.
+    pub fn from_toml_str(contents: &str, config_dir: &Path) -> Result<Self> {
+        // Typed parse first so schema errors keep their line/column context.
+        let config: Config = toml::from_str(contents).context("Failed to parse config file")?;
+        let mut table: toml::Table = contents.parse().context("Failed to parse config file")?;
+        let store = crate::security::SecretStore::new(config_dir, config.secrets.encrypt);
+        let secret_refs = SecretReferences::resolve(&mut table, &store, config_dir)?;
+        if secret_refs.is_empty() {
+            return Ok(config);
+        }
+
+        let mut config: Config = toml::Value::Table(table)
+            .try_into()
+            .context("Failed to parse config file")?;
+        config.secret_refs = secret_refs;
+        Ok(config)
+    }
+
     /// Apply environment variable overrides to config
     pub fn apply_env_overrides(&mut self) {
         // API Key: ZEROCLAW_API_KEY or API_KEY (generic)
@@ -1747,9 +2160,15 @@ impl Config {
             .parent()
             .context("Config path must have a parent directory")?;
         let store = crate::security::SecretStore::new(zeroclaw_dir, self.secrets.encrypt);
-        for agent in config_to_save.agents.values_mut() {
+        for (name, agent) in &mut config_to_save.agents {
             if let Some(ref plaintext_key) = agent.api_key {
-                if !crate::security::SecretStore::is_encrypted(plaintext_key) {
+                let path = format!("agents.{name}.api_key");
+                if !crate::security::SecretStore::is_encrypted(plaintext_key)
+                    && self
+                        .secret_refs
+                        .raw_if_unchanged(&path, plaintext_key)
+                        .is_none()
+                {
                     agent.api_key = Some(
                         store
                             .encrypt(plaintext_key)
@@ -1759,72 +2178,83 @@ impl Config {
             }
         }
 
-        let toml_str =
-            toml::to_string_pretty(&config_to_save).context("Failed to serialize config")?;
+        let toml_str = if self.secret_refs.is_empty() {
+            toml::to_string_pretty(&config_to_save).context("Failed to serialize config")?
+        } else {
+            // Write references and ciphertext back rather than resolved plaintext
+            let mut table =
+                toml::Table::try_from(&config_to_save).context("Failed to serialize config")?;
+            self.secret_refs.restore(&mut table);
+            toml::to_string_pretty(&table).context("Failed to serialize config")?
+        };
 
-        let parent_dir = self
-            .config_path
-            .parent()
-            .context("Config path must have a parent directory")?;
-        fs::create_dir_all(parent_dir).with_context(|| {
+        write_config_file(&self.config_path, &toml_str)
+    }
+}
+
+/// Atomically replace the config file with `toml_str` (temp file, fsync,
+/// rename), keeping a backup until the swap succeeds.
+pub(crate) fn write_config_file(config_path: &Path, toml_str: &str) -> Result<()> {
+    let parent_dir = config_path
+        .parent()
+        .context("Config path must have a parent directory")?;
+    fs::create_dir_all(parent_dir).with_context(|| {
+        format!(
+            "Failed to create config directory: {}",
+            parent_dir.display()
+        )
+    })?;
+
+    let file_name = config_path
+        .file_name()
+        .and_then(|v| v.to_str())
+        .unwrap_or("config.toml");
+    let temp_path = parent_dir.join(format!(".{file_name}.tmp-{}", uuid::Uuid::new_v4()));
+    let backup_path = parent_dir.join(format!("{file_name}.bak"));
+
+    let mut temp_file = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(&temp_path)
+        .with_context(|| {
             format!(
-                "Failed to create config directory: {}",
-                parent_dir.display()
+                "Failed to create temporary config file: {}",
+                temp_path.display()
             )
         })?;
+    temp_file
+        .write_all(toml_str.as_bytes())
+        .context("Failed to write temporary config contents")?;
+    temp_file
+        .sync_all()
+        .context("Failed to fsync temporary config file")?;
+    drop(temp_file);
+
+    let had_existing_config = config_path.exists();
+    if had_existing_config {
+        fs::copy(config_path, &backup_path).with_context(|| {
+            format!(
+                "Failed to create config backup before atomic replace: {}",
+                backup_path.display()
+            )
+        })?;
+    }
 
-        let file_name = self
-            .config_path
-            .file_name()
-            .and_then(|v| v.to_str())
-            .unwrap_or("config.toml");
-        let temp_path = parent_dir.join(format!(".{file_name}.tmp-{}", uuid::Uuid::new_v4()));
-        let backup_path = parent_dir.join(format!("{file_name}.bak"));
-
-        let mut temp_file = OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(&temp_path)
-            .with_context(|| {
-                format!(
-                    "Failed to create temporary config file: {}",
-                    temp_path.display()
-                )
-            })?;
-        temp_file
-            .write_all(toml_str.as_bytes())
-            .context("Failed to write temporary config contents")?;
-        temp_file
-            .sync_all()
-            .context("Failed to fsync temporary config file")?;
-        drop(temp_file);
-
-        let had_existing_config = self.config_path.exists();
-        if had_existing_config {
-            fs::copy(&self.config_path, &backup_path).with_context(|| {
-                format!(
-                    "Failed to create config backup before atomic replace: {}",
-                    backup_path.display()
-                )
-            })?;
-        }
-
-        if let Err(e) = fs::rename(&temp_path, &self.config_path) {
-            let _ = fs::remove_file(&temp_path);
-            if had_existing_config && backup_path.exists() {
-                let _ = fs::copy(&backup_path, &self.config_path);
-            }
-            anyhow::bail!("Failed to atomically replace config file: {e}");
+    if let Err(e) = fs::rename(&temp_path, config_path) {
+        let _ = fs::remove_file(&temp_path);
+        if had_existing_config && backup_path.exists() {
+            let _ = fs::copy(&backup_path, config_path);
         }
+        anyhow::bail!("Failed to atomically replace config file: {e}");
+    }
 
-        sync_directory(parent_dir)?;
-
-        if had_existing_config {
-            let _ = fs::remove_file(&backup_path);
-        }
+    sync_directory(parent_dir)?;
 
-        Ok(())
+    if had_existing_config {
+        let _ = fs::remove_file(&backup_path);
     }
+
+    Ok(())
 }
 
 #[cfg(unix)]
@@ -1925,6 +2355,7 @@ mod tests {
         let config = Config {
             workspace_dir: PathBuf::from("/tmp/test/workspace"),
             config_path: PathBuf::from("/tmp/test/config.toml"),
+            secret_refs: SecretReferences::default(),
             api_key: Some("sk-test-key".into()),
             default_provider: Some("openrouter".into()),
             default_model: Some("gpt-4o".into()),
@@ -1943,6 +2374,7 @@ mod tests {
                 require_approval_for_medium_risk: false,
                 block_high_risk_commands: true,
             },
+            security: SecurityConfig::default(),
             runtime: RuntimeConfig {
                 kind: "docker".into(),
                 ..RuntimeConfig::default()
@@ -1950,6 +2382,7 @@ mod tests {
             reliability: ReliabilityConfig::default(),
             scheduler: SchedulerConfig::default(),
             model_routes: Vec::new(),
+            routing: RoutingConfig::default(),
             heartbeat: HeartbeatConfig {
                 enabled: true,
                 interval_minutes: 15,
@@ -1970,6 +2403,8 @@ mod tests {
                 irc: None,
                 lark: None,
                 dingtalk: None,
+                signal: None,
+                teams: None,
             },
             orchestrator: OrchestratorConfig::default(),
             memory: MemoryConfig::default(),
@@ -1979,6 +2414,8 @@ mod tests {
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             http_request: HttpRequestConfig::default(),
+            home_assistant: HomeAssistantConfig::default(),
+            mcp: McpConfig::default(),
             agent: AgentConfig::default(),
             identity: IdentityConfig::default(),
             cost: CostConfig::default(),
@@ -2036,6 +2473,8 @@ default_temperature = 0.7
         assert_eq!(cfg.max_history_messages, 50);
         assert!(!cfg.parallel_tools);
         assert_eq!(cfg.tool_dispatcher, "auto");
+        assert_eq!(cfg.context_window, None);
+        assert!(!cfg.summarize_tool_output);
     }
 
     #[test]
@@ -2048,6 +2487,8 @@ max_tool_iterations = 20
 max_history_messages = 80
 parallel_tools = true
 tool_dispatcher = "xml"
+context_window = 65536
+summarize_tool_output = true
 "#;
         let parsed: Config = toml::from_str(raw).unwrap();
         assert!(parsed.agent.compact_context);
@@ -2055,6 +2496,8 @@ tool_dispatcher = "xml"
         assert_eq!(parsed.agent.max_history_messages, 80);
         assert!(parsed.agent.parallel_tools);
         assert_eq!(parsed.agent.tool_dispatcher, "xml");
+        assert_eq!(parsed.agent.context_window, Some(65_536));
+        assert!(parsed.agent.summarize_tool_output);
     }
 
     #[test]
@@ -2067,16 +2510,19 @@ tool_dispatcher = "xml"
         let config = Config {
             workspace_dir: dir.join("workspace"),
             config_path: config_path.clone(),
+            secret_refs: SecretReferences::default(),
             api_key: Some("sk-roundtrip".into()),
             default_provider: Some("openrouter".into()),
             default_model: Some("test-model".into()),
             default_temperature: 0.9,
             observability: ObservabilityConfig::default(),
             autonomy: AutonomyConfig::default(),
+            security: SecurityConfig::default(),
             runtime: RuntimeConfig::default(),
             reliability: ReliabilityConfig::default(),
             scheduler: SchedulerConfig::default(),
             model_routes: Vec::new(),
+            routing: RoutingConfig::default(),
             heartbeat: HeartbeatConfig::default(),
             channels_config: ChannelsConfig::default(),
             orchestrator: OrchestratorConfig::default(),
@@ -2087,6 +2533,8 @@ tool_dispatcher = "xml"
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             http_request: HttpRequestConfig::default(),
+            home_assistant: HomeAssistantConfig::default(),
+            mcp: McpConfig::default(),
             agent: AgentConfig::default(),
             identity: IdentityConfig::default(),
             cost: CostConfig::default(),
@@ -2107,6 +2555,51 @@ tool_dispatcher = "xml"
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn config_secret_references_resolve_on_load_and_survive_save() {
+        let dir = TempDir::new().unwrap();
+        std::env::set_var("ZEROCLAW_TEST_CONFIG_SECRET_REF", "sk-from-env");
+        let store = crate::security::SecretStore::new(dir.path(), true);
+        let encrypted = store.encrypt("sk-agent").unwrap();
+
+        let mut config = Config::default();
+        config.config_path = dir.path().join("config.toml");
+        let mut contents = toml::to_string_pretty(&config).unwrap();
+        contents = format!(
+            "api_key = \"env:ZEROCLAW_TEST_CONFIG_SECRET_REF\"\n{contents}\n\
+             [agents.researcher]\nprovider = \"openrouter\"\nmodel = \"m\"\napi_key = \"{encrypted}\"\n"
+        );
+
+        let mut loaded = Config::from_toml_str(&contents, dir.path()).unwrap();
+        assert_eq!(loaded.api_key.as_deref(), Some("sk-from-env"));
+        assert_eq!(
+            loaded.agents["researcher"].api_key.as_deref(),
+            Some("sk-agent")
+        );
+
+        loaded.config_path = dir.path().join("config.toml");
+        loaded.default_model = Some("changed".into());
+        loaded.save().unwrap();
+
+        let saved = fs::read_to_string(&loaded.config_path).unwrap();
+        assert!(saved.contains("env:ZEROCLAW_TEST_CONFIG_SECRET_REF"));
+        assert!(saved.contains(&encrypted));
+        assert!(!saved.contains("sk-from-env"));
+        assert!(!saved.contains("sk-agent"));
+        assert!(saved.contains("changed"));
+    }
+
+    #[test]
+    fn config_unresolvable_secret_reference_names_key() {
+        let dir = TempDir::new().unwrap();
+        let contents = format!(
+            "api_key = \"env:ZEROCLAW_TEST_CONFIG_SECRET_UNSET\"\n{}",
+            toml::to_string_pretty(&Config::default()).unwrap()
+        );
+        let err = Config::from_toml_str(&contents, dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("`api_key`"));
+    }
+
     #[test]
     fn config_save_atomic_cleanup() {
         let dir =
@@ -2219,6 +2712,11 @@ tool_dispatcher = "xml"
             access_token: "syt_token_abc".into(),
             room_id: "!room123:matrix.org".into(),
             allowed_users: vec!["@user:matrix.org".into()],
+            rooms: vec![],
+            auto_join: true,
+            thread_replies: true,
+            e2ee: false,
+            store_dir: None,
         };
         let json = serde_json::to_string(&mc).unwrap();
         let parsed: MatrixConfig = serde_json::from_str(&json).unwrap();
@@ -2235,6 +2733,11 @@ tool_dispatcher = "xml"
             access_token: "tok".into(),
             room_id: "!abc:synapse.local".into(),
             allowed_users: vec!["@admin:synapse.local".into(), "*".into()],
+            rooms: vec![],
+            auto_join: true,
+            thread_replies: true,
+            e2ee: false,
+            store_dir: None,
         };
         let toml_str = toml::to_string(&mc).unwrap();
         let parsed: MatrixConfig = toml::from_str(&toml_str).unwrap();
@@ -2258,12 +2761,19 @@ tool_dispatcher = "xml"
                 access_token: "tok".into(),
                 room_id: "!r:m".into(),
                 allowed_users: vec!["@u:m".into()],
+                rooms: vec![],
+                auto_join: true,
+                thread_replies: true,
+                e2ee: false,
+                store_dir: None,
             }),
             whatsapp: None,
             email: None,
             irc: None,
             lark: None,
             dingtalk: None,
+            signal: None,
+            teams: None,
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -2424,6 +2934,8 @@ channel_id = "C123"
             irc: None,
             lark: None,
             dingtalk: None,
+            signal: None,
+            teams: None,
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -2496,6 +3008,7 @@ channel_id = "C123"
             pair_rate_limit_per_minute: 12,
             webhook_rate_limit_per_minute: 80,
             idempotency_ttl_secs: 600,
+            webchat_enabled: false,
         };
         let toml_str = toml::to_string(&g).unwrap();
         let parsed: GatewayConfig = toml::from_str(&toml_str).unwrap();
@@ -2505,6 +3018,7 @@ channel_id = "C123"
         assert_eq!(parsed.pair_rate_limit_per_minute, 12);
         assert_eq!(parsed.webhook_rate_limit_per_minute, 80);
         assert_eq!(parsed.idempotency_ttl_secs, 600);
+        assert!(!parsed.webchat_enabled);
     }
 
     #[test]
@@ -2942,6 +3456,8 @@ default_temperature = 0.7
                 transport: "serial".into(),
                 path: Some("/dev/ttyACM0".into()),
                 baud: 115200,
+                script: None,
+                secret: None,
             }],
             datasheet_dir: None,
         };
@@ -0,0 +1,530 @@
+//! Semantic config checks that the TOML schema cannot express.
+//!
+//! Parsing already rejects wrong types and unknown enum values; this module
+//! catches configs that parse but cannot work (a route to an unknown
+//! provider, two listeners on one port). Every issue names the exact TOML
+//! key so `zeroclaw config validate` output can be acted on directly.
+
+use super::{Config, RoutingMode};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// How serious a [`ConfigIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The daemon refuses to start (and hot reload keeps the old config).
+    Error,
+    /// Works, but probably not as intended.
+    Warning,
+}
+
+/// A single validation finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    /// Dotted TOML key, with array indices as `.N` (e.g. `model_routes.0.provider`)
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{level}: {}: {}", self.key, self.message)
+    }
+}
+
+/// Allowlist fields where an empty list means "deny everyone".
+const ALLOWLIST_KEYS: &[&str] = &[
+    "allowed_users",
+    "allowed_contacts",
+    "allowed_numbers",
+    "allowed_senders",
+];
+
+/// Run every check. Issues are ordered by section, errors and warnings mixed.
+pub fn validate(config: &Config) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    check_providers(config, &mut issues);
+    check_model_routes(config, &mut issues);
+    check_agents(config, &mut issues);
+    check_allowlists(config, &mut issues);
+    check_gateway(config, &mut issues);
+    check_home_assistant(config, &mut issues);
+    check_mcp(config, &mut issues);
+    check_matrix(config, &mut issues);
+    issues
+}
+
+/// Fail with every hard error listed, one per line; otherwise return the
+/// warnings for the caller to log.
+pub fn ensure_valid(config: &Config) -> anyhow::Result<Vec<ConfigIssue>> {
+    let (errors, warnings): (Vec<_>, Vec<_>) = validate(config)
+        .into_iter()
+        .partition(ConfigIssue::is_error);
+    if errors.is_empty() {
+        return Ok(warnings);
+    }
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    anyhow::bail!(
+        "Invalid config ({}):\n  {}",
+        config.config_path.display(),
+        errors.join("\n  ")
+    )
+}
+
+fn check_temperature(key: String, temperature: f64, issues: &mut Vec<ConfigIssue>) {
+    if !(0.0..=2.0).contains(&temperature) {
+        issues.push(ConfigIssue::error(
+            key,
+            format!("temperature {temperature} is outside 0.0–2.0"),
+        ));
+    }
+}
+
+fn unknown_provider(name: &str) -> String {
+    format!(
+        "unknown provider `{name}` (use a supported name, custom:https://… or anthropic-custom:https://…)"
+    )
+}
+
+fn check_providers(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    if let Some(provider) = &config.default_provider {
+        if !crate::providers::is_known_provider(provider) {
+            issues.push(ConfigIssue::error(
+                "default_provider",
+                unknown_provider(provider),
+            ));
+        }
+    }
+    check_temperature(
+        "default_temperature".into(),
+        config.default_temperature,
+        issues,
+    );
+
+    for (i, fallback) in config.reliability.fallback_providers.iter().enumerate() {
+        if !crate::providers::is_known_provider(fallback) {
+            issues.push(ConfigIssue::warning(
+                format!("reliability.fallback_providers.{i}"),
+                format!("{}; it will be skipped", unknown_provider(fallback)),
+            ));
+        }
+    }
+}
+
+/// Providers that run without an API key or bring their own auth in the URL.
+fn needs_api_key(provider: &str) -> bool {
+    !(provider == "ollama"
+        || provider.starts_with("custom:")
+        || provider.starts_with("anthropic-custom:"))
+}
+
+fn check_model_routes(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let default_provider = config.default_provider.as_deref().unwrap_or("openrouter");
+    let mut hints = HashSet::new();
+
+    for (i, route) in config.model_routes.iter().enumerate() {
+        if route.hint.trim().is_empty() {
+            issues.push(ConfigIssue::error(
+                format!("model_routes.{i}.hint"),
+                "hint must not be empty",
+            ));
+        } else if !hints.insert(route.hint.as_str()) {
+            issues.push(ConfigIssue::error(
+                format!("model_routes.{i}.hint"),
+                format!("duplicate hint `{}`", route.hint),
+            ));
+        }
+
+        if !crate::providers::is_known_provider(&route.provider) {
+            issues.push(ConfigIssue::error(
+                format!("model_routes.{i}.provider"),
+                unknown_provider(&route.provider),
+            ));
+        } else if route.provider != default_provider
+            && needs_api_key(&route.provider)
+            && !crate::providers::has_api_key(&route.provider, route.api_key.as_deref())
+        {
+            issues.push(ConfigIssue::warning(
+                format!("model_routes.{i}.api_key"),
+                format!(
+                    "provider `{}` is not configured: no route api_key or provider env var, \
+                     so the top-level api_key for `{default_provider}` would be sent to it",
+                    route.provider
+                ),
+            ));
+        }
+
+        if route.model.trim().is_empty() {
+            issues.push(ConfigIssue::error(
+                format!("model_routes.{i}.model"),
+                "model must not be empty",
+            ));
+        }
+
+        if route
+            .daily_budget_usd
+            .is_some_and(|usd| usd.is_nan() || usd < 0.0)
+        {
+            issues.push(ConfigIssue::error(
+                format!("model_routes.{i}.daily_budget_usd"),
+                "budget must be zero or more",
+            ));
+        } else if route.daily_budget_usd.is_some() && !config.cost.enabled {
+            issues.push(ConfigIssue::warning(
+                format!("model_routes.{i}.daily_budget_usd"),
+                "budgets are read from the cost log; set [cost] enabled = true to enforce them",
+            ));
+        }
+    }
+
+    if config.routing.mode == RoutingMode::Auto && config.model_routes.is_empty() {
+        issues.push(ConfigIssue::warning(
+            "routing.mode",
+            "auto routing has no model_routes to choose from",
+        ));
+    }
+    if let Some(hint) = &config.routing.classifier_hint {
+        if !hints.contains(hint.as_str()) {
+            issues.push(ConfigIssue::error(
+                "routing.classifier_hint",
+                format!("no model_routes entry has hint `{hint}`"),
+            ));
+        }
+    }
+}
+
+fn check_agents(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let agents: BTreeMap<_, _> = config.agents.iter().collect();
+    for (name, agent) in agents {
+        if !crate::providers::is_known_provider(&agent.provider) {
+            issues.push(ConfigIssue::error(
+                format!("agents.{name}.provider"),
+                unknown_provider(&agent.provider),
+            ));
+        }
+        if let Some(temperature) = agent.temperature {
+            check_temperature(format!("agents.{name}.temperature"), temperature, issues);
+        }
+        if agent.max_depth == 0 {
+            issues.push(ConfigIssue::warning(
+                format!("agents.{name}.max_depth"),
+                "max_depth = 0 means this agent can never be delegated to",
+            ));
+        }
+    }
+}
+
+fn check_allowlists(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let Ok(channels) = toml::Table::try_from(&config.channels_config) else {
+        return;
+    };
+    for (channel, value) in &channels {
+        let Some(table) = value.as_table() else {
+            continue;
+        };
+        for key in ALLOWLIST_KEYS {
+            if table
+                .get(*key)
+                .and_then(toml::Value::as_array)
+                .is_some_and(Vec::is_empty)
+            {
+                issues.push(ConfigIssue::warning(
+                    format!("channels_config.{channel}.{key}"),
+                    "empty allowlist denies everyone; add identities or \"*\" to allow all",
+                ));
+            }
+        }
+    }
+}
+
+fn check_gateway(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let mut listeners: Vec<(&str, u16)> = vec![
+        ("gateway.port", config.gateway.port),
+        ("mcp.serve_port", config.mcp.serve_port),
+    ];
+    if config.channels_config.lark.is_some() {
+        listeners.push(("channels_config.lark", crate::channels::lark::CALLBACK_PORT));
+    }
+    for (i, (key, port)) in listeners.iter().enumerate() {
+        if *port == 0 {
+            continue;
+        }
+        if let Some((other, _)) = listeners[..i].iter().find(|(_, p)| p == port) {
+            issues.push(ConfigIssue::error(
+                *key,
+                format!("port {port} is already used by {other}"),
+            ));
+        }
+    }
+
+    if crate::security::pairing::is_public_bind(&config.gateway.host)
+        && config.tunnel.provider == "none"
+        && !config.gateway.allow_public_bind
+    {
+        issues.push(ConfigIssue::error(
+            "gateway.host",
+            format!(
+                "{} is a public address; use 127.0.0.1, configure a tunnel or set gateway.allow_public_bind = true",
+                config.gateway.host
+            ),
+        ));
+    }
+}
+
+fn check_home_assistant(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let ha = &config.home_assistant;
+    if ha.enabled && ha.token.as_deref().is_none_or(|t| t.trim().is_empty()) {
+        issues.push(ConfigIssue::error(
+            "home_assistant.token",
+            "home_assistant is enabled but has no access token",
+        ));
+    }
+}
+
+fn check_mcp(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let mut names = HashSet::new();
+    for (i, server) in config.mcp.servers.iter().enumerate() {
+        let name = server.name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            issues.push(ConfigIssue::error(
+                format!("mcp.servers.{i}.name"),
+                format!("`{name}` is not a valid server name (letters, digits, `-` and `_`)"),
+            ));
+        } else if !names.insert(name) {
+            issues.push(ConfigIssue::error(
+                format!("mcp.servers.{i}.name"),
+                format!("duplicate server name `{name}`"),
+            ));
+        }
+
+        match server.transport.as_str() {
+            "stdio" => {
+                if server
+                    .command
+                    .as_deref()
+                    .is_none_or(|c| c.trim().is_empty())
+                {
+                    issues.push(ConfigIssue::error(
+                        format!("mcp.servers.{i}.command"),
+                        "stdio transport needs a command to spawn",
+                    ));
+                }
+            }
+            "http" => {
+                let url = server.url.as_deref().unwrap_or_default();
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    issues.push(ConfigIssue::error(
+                        format!("mcp.servers.{i}.url"),
+                        "http transport needs an http:// or https:// url",
+                    ));
+                }
+            }
+            other => issues.push(ConfigIssue::error(
+                format!("mcp.servers.{i}.transport"),
+                format!("unknown transport `{other}` (use \"stdio\" or \"http\")"),
+            )),
+        }
+
+        if server.timeout_secs == 0 {
+            issues.push(ConfigIssue::warning(
+                format!("mcp.servers.{i}.timeout_secs"),
+                "timeout of 0 makes every call fail",
+            ));
+        }
+    }
+}
+
+fn check_matrix(config: &Config, issues: &mut Vec<ConfigIssue>) {
+    let Some(matrix) = config.channels_config.matrix.as_ref() else {
+        return;
+    };
+    if matrix.e2ee && !cfg!(feature = "matrix-e2ee") {
+        issues.push(ConfigIssue::error(
+            "channels_config.matrix.e2ee",
+            "this build has no Matrix encryption support; rebuild with --features matrix-e2ee",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DelegateAgentConfig, LarkConfig, ModelRouteConfig, TelegramConfig};
+
+    fn keys(issues: &[ConfigIssue]) -> Vec<(&str, Severity)> {
+        issues
+            .iter()
+            .map(|issue| (issue.key.as_str(), issue.severity))
+            .collect()
+    }
+
+    #[test]
+    fn default_config_has_no_errors() {
+        let issues = validate(&Config::default());
+        assert!(!issues.iter().any(ConfigIssue::is_error), "{issues:?}");
+        assert!(ensure_valid(&Config::default()).is_ok());
+    }
+
+    #[test]
+    fn unknown_providers_point_at_exact_keys() {
+        let mut config = Config::default();
+        config.model_routes = vec![
+            ModelRouteConfig {
+                hint: "fast".into(),
+                provider: "ollama".into(),
+                model: "llama3".into(),
+                api_key: None,
+                capabilities: vec![],
+                daily_budget_usd: None,
+            },
+            ModelRouteConfig {
+                hint: "fast".into(),
+                provider: "nonexistent".into(),
+                model: "m".into(),
+                api_key: None,
+                capabilities: vec![],
+                daily_budget_usd: None,
+            },
+        ];
+        config.routing.classifier_hint = Some("classifier".into());
+        config.agents.insert(
+            "researcher".into(),
+            DelegateAgentConfig {
+                provider: "bogus".into(),
+                model: "m".into(),
+                system_prompt: None,
+                api_key: None,
+                temperature: Some(3.0),
+                max_depth: 3,
+            },
+        );
+
+        let issues = validate(&config);
+        assert_eq!(
+            keys(&issues),
+            vec![
+                ("model_routes.1.hint", Severity::Error),
+                ("model_routes.1.provider", Severity::Error),
+                ("routing.classifier_hint", Severity::Error),
+                ("agents.researcher.provider", Severity::Error),
+                ("agents.researcher.temperature", Severity::Error),
+            ]
+        );
+
+        let err = ensure_valid(&config).unwrap_err().to_string();
+        assert!(err.contains("error: model_routes.1.provider: unknown provider `nonexistent`"));
+    }
+
+    #[test]
+    fn empty_allowlists_and_port_clashes_are_reported() {
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(TelegramConfig {
+            bot_token: "token".into(),
+            allowed_users: vec![],
+        });
+        config.channels_config.lark = Some(LarkConfig {
+            app_id: "id".into(),
+            app_secret: "secret".into(),
+            encrypt_key: None,
+            verification_token: None,
+            allowed_users: vec!["*".into()],
+            use_feishu: false,
+        });
+        config.gateway.port = crate::channels::lark::CALLBACK_PORT;
+        config.mcp.serve_port = crate::channels::lark::CALLBACK_PORT;
+
+        let issues = validate(&config);
+        assert_eq!(
+            keys(&issues),
+            vec![
+                ("channels_config.telegram.allowed_users", Severity::Warning),
+                ("mcp.serve_port", Severity::Error),
+                ("channels_config.lark", Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn public_bind_and_home_assistant_token_are_errors() {
+        let mut config = Config::default();
+        config.gateway.host = "0.0.0.0".into();
+        config.home_assistant.enabled = true;
+
+        let issues = validate(&config);
+        assert_eq!(
+            keys(&issues),
+            vec![
+                ("gateway.host", Severity::Error),
+                ("home_assistant.token", Severity::Error),
+            ]
+        );
+
+        config.gateway.allow_public_bind = true;
+        config.home_assistant.token = Some("token".into());
+        assert!(validate(&config).is_empty());
+    }
+
+    #[test]
+    fn mcp_servers_need_unique_names_and_a_transport_target() {
+        let mut config = Config::default();
+        config.mcp = toml::from_str(
+            r#"
+            [[servers]]
+            name = "github"
+            command = "npx"
+
+            [[servers]]
+            name = "github"
+            transport = "http"
+            url = "ftp://example.com"
+
+            [[servers]]
+            name = "bad name"
+            transport = "sse"
+            "#,
+        )
+        .unwrap();
+
+        let issues = validate(&config);
+        assert_eq!(
+            keys(&issues),
+            vec![
+                ("mcp.servers.1.name", Severity::Error),
+                ("mcp.servers.1.url", Severity::Error),
+                ("mcp.servers.2.name", Severity::Error),
+                ("mcp.servers.2.transport", Severity::Error),
+            ]
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use super::types::{BudgetCheck, CostRecord, CostSummary, ModelStats, TokenUsage, UsagePeriod};
-use crate::config::schema::CostConfig;
+use crate::config::schema::{CostConfig, ModelPricing};
+use crate::config::Config;
+use crate::providers::routing::price_for;
+use crate::providers::Usage;
 use anyhow::{anyhow, Context, Result};
 use chrono::{Datelike, NaiveDate, Utc};
 use std::collections::HashMap;
@@ -33,6 +36,21 @@ impl CostTracker {
         })
     }
 
+    /// The tracker the agent runtimes share for `config`. `None` when cost
+    /// tracking is disabled or its storage can't be opened.
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        if !config.cost.enabled {
+            return None;
+        }
+        match Self::new(config.cost.clone(), &config.workspace_dir) {
+            Ok(tracker) => Some(Arc::new(tracker)),
+            Err(e) => {
+                tracing::warn!("Cost tracking disabled: {e:#}");
+                None
+            }
+        }
+    }
+
     /// Get the session ID.
     pub fn session_id(&self) -> &str {
         &self.session_id
@@ -136,6 +154,19 @@ impl CostTracker {
         Ok(())
     }
 
+    /// Record the usage a provider reported for one response, priced from
+    /// `[cost.prices]` including its cache read/write rates. Models without
+    /// a price entry are recorded at zero cost.
+    pub fn record_response(&self, model: &str, usage: &Usage) -> Result<()> {
+        let pricing = price_for(&self.config.prices, model).unwrap_or(ModelPricing {
+            input: 0.0,
+            output: 0.0,
+            cache_read: None,
+            cache_write: None,
+        });
+        self.record_usage(TokenUsage::from_usage(model, usage, &pricing))
+    }
+
     /// Get the current cost summary.
     pub fn get_summary(&self) -> Result<CostSummary> {
         let (daily_cost, monthly_cost) = {
@@ -171,6 +202,14 @@ impl CostTracker {
         storage.get_cost_for_date(date)
     }
 
+    /// Today's cost for `model`, which auto routing checks against route
+    /// budgets.
+    pub fn get_daily_model_cost(&self, model: &str) -> Result<f64> {
+        let mut storage = self.lock_storage()?;
+        storage.ensure_period_cache_current()?;
+        Ok(storage.daily_by_model.get(model).copied().unwrap_or(0.0))
+    }
+
     /// Get the monthly cost for a specific month.
     pub fn get_monthly_cost(&self, year: i32, month: u32) -> Result<f64> {
         let storage = self.lock_storage()?;
@@ -233,6 +272,7 @@ struct CostStorage {
     path: PathBuf,
     daily_cost_usd: f64,
     monthly_cost_usd: f64,
+    daily_by_model: HashMap<String, f64>,
     cached_day: NaiveDate,
     cached_year: i32,
     cached_month: u32,
@@ -251,6 +291,7 @@ impl CostStorage {
             path: path.to_path_buf(),
             daily_cost_usd: 0.0,
             monthly_cost_usd: 0.0,
+            daily_by_model: HashMap::new(),
             cached_day: now.date_naive(),
             cached_year: now.year(),
             cached_month: now.month(),
@@ -309,12 +350,14 @@ impl CostStorage {
     fn rebuild_aggregates(&mut self, day: NaiveDate, year: i32, month: u32) -> Result<()> {
         let mut daily_cost = 0.0;
         let mut monthly_cost = 0.0;
+        let mut daily_by_model = HashMap::new();
 
         self.for_each_record(|record| {
             let timestamp = record.usage.timestamp.naive_utc();
 
             if timestamp.date() == day {
                 daily_cost += record.usage.cost_usd;
+                *daily_by_model.entry(record.usage.model).or_insert(0.0) += record.usage.cost_usd;
             }
 
             if timestamp.year() == year && timestamp.month() == month {
@@ -324,6 +367,7 @@ impl CostStorage {
 
         self.daily_cost_usd = daily_cost;
         self.monthly_cost_usd = monthly_cost;
+        self.daily_by_model = daily_by_model;
         self.cached_day = day;
         self.cached_year = year;
         self.cached_month = month;
@@ -362,6 +406,10 @@ impl CostStorage {
         let timestamp = record.usage.timestamp.naive_utc();
         if timestamp.date() == self.cached_day {
             self.daily_cost_usd += record.usage.cost_usd;
+            *self
+                .daily_by_model
+                .entry(record.usage.model.clone())
+                .or_insert(0.0) += record.usage.cost_usd;
         }
         if timestamp.year() == self.cached_year && timestamp.month() == self.cached_month {
             self.monthly_cost_usd += record.usage.cost_usd;
@@ -436,6 +484,23 @@ mod tests {
         assert!(matches!(check, BudgetCheck::Allowed));
     }
 
+    #[test]
+    fn daily_model_cost_survives_restart() {
+        let tmp = TempDir::new().unwrap();
+        let tracker = CostTracker::new(enabled_config(), tmp.path()).unwrap();
+        tracker
+            .record_usage(TokenUsage::new("gpt-4o", 1_000_000, 0, 2.0, 8.0))
+            .unwrap();
+        tracker
+            .record_usage(TokenUsage::new("llama3", 1_000_000, 0, 1.0, 1.0))
+            .unwrap();
+        assert!((tracker.get_daily_model_cost("gpt-4o").unwrap() - 2.0).abs() < 1e-9);
+
+        let reopened = CostTracker::new(enabled_config(), tmp.path()).unwrap();
+        assert!((reopened.get_daily_model_cost("gpt-4o").unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(reopened.get_daily_model_cost("claude").unwrap(), 0.0);
+    }
+
     #[test]
     fn record_usage_and_get_summary() {
         let tmp = TempDir::new().unwrap();
@@ -450,6 +515,34 @@ mod tests {
         assert_eq!(summary.by_model.len(), 1);
     }
 
+    #[test]
+    fn record_response_prices_cache_tokens() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = enabled_config();
+        config.prices.insert(
+            "anthropic/claude-sonnet-4".into(),
+            ModelPricing {
+                input: 3.0,
+                output: 15.0,
+                cache_read: None,
+                cache_write: None,
+            },
+        );
+        let tracker = CostTracker::new(config, tmp.path()).unwrap();
+
+        let usage = Usage {
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_read_tokens: 1_000_000,
+            cache_write_tokens: 0,
+        };
+        tracker.record_response("claude-sonnet-4", &usage).unwrap();
+
+        let summary = tracker.get_summary().unwrap();
+        assert!((summary.session_cost_usd - 0.3).abs() < 1e-9);
+        assert_eq!(summary.total_tokens, 1_000_000);
+    }
+
     #[test]
     fn budget_exceeded_daily_limit() {
         let tmp = TempDir::new().unwrap();
@@ -1,3 +1,5 @@
+use crate::config::schema::ModelPricing;
+use crate::providers::Usage;
 use serde::{Deserialize, Serialize};
 
 /// Token usage information from a single API call.
@@ -9,6 +11,12 @@ pub struct TokenUsage {
     pub input_tokens: u64,
     /// Output/completion tokens
     pub output_tokens: u64,
+    /// Prompt tokens read from the provider's prompt cache
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache
+    #[serde(default)]
+    pub cache_write_tokens: u64,
     /// Total tokens
     pub total_tokens: u64,
     /// Calculated cost in USD
@@ -48,12 +56,38 @@ impl TokenUsage {
             model,
             input_tokens,
             output_tokens,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
             total_tokens,
             cost_usd,
             timestamp: chrono::Utc::now(),
         }
     }
 
+    /// Create a usage record from what a provider reported, pricing cache
+    /// reads and writes at their own rates.
+    pub fn from_usage(model: impl Into<String>, usage: &Usage, pricing: &ModelPricing) -> Self {
+        let model: String = model.into();
+        let cache_read_price = Self::sanitize_price(pricing.cache_read_price(&model));
+        let cache_write_price = Self::sanitize_price(pricing.cache_write_price(&model));
+        let mut record = Self::new(
+            model,
+            usage.input_tokens,
+            usage.output_tokens,
+            pricing.input,
+            pricing.output,
+        );
+        record.cache_read_tokens = usage.cache_read_tokens;
+        record.cache_write_tokens = usage.cache_write_tokens;
+        record.total_tokens = record
+            .total_tokens
+            .saturating_add(usage.cache_read_tokens)
+            .saturating_add(usage.cache_write_tokens);
+        record.cost_usd += (usage.cache_read_tokens as f64 / 1_000_000.0) * cache_read_price
+            + (usage.cache_write_tokens as f64 / 1_000_000.0) * cache_write_price;
+        record
+    }
+
     /// Get the total cost.
     pub fn cost(&self) -> f64 {
         self.cost_usd
@@ -181,6 +215,58 @@ mod tests {
         assert_eq!(usage.total_tokens, 2000);
     }
 
+    #[test]
+    fn cache_tokens_are_priced_at_cache_rates() {
+        let pricing = ModelPricing {
+            input: 3.0,
+            output: 15.0,
+            cache_read: None,
+            cache_write: None,
+        };
+        let usage = Usage {
+            input_tokens: 1_000,
+            output_tokens: 500,
+            cache_read_tokens: 100_000,
+            cache_write_tokens: 10_000,
+        };
+        let record = TokenUsage::from_usage("anthropic/claude-sonnet-4", &usage, &pricing);
+
+        // 0.003 input + 0.0075 output + 100k * $0.30/M read + 10k * $3.75/M write
+        assert!((record.cost_usd - 0.078).abs() < 1e-9);
+        assert_eq!(record.cache_read_tokens, 100_000);
+        assert_eq!(record.cache_write_tokens, 10_000);
+        assert_eq!(record.total_tokens, 111_500);
+
+        let flat = ModelPricing {
+            cache_read: Some(1.5),
+            ..pricing
+        };
+        let record = TokenUsage::from_usage("anthropic/claude-sonnet-4", &usage, &flat);
+        assert!((record.cost_usd - (0.0105 + 0.15 + 0.0375)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cache_defaults_follow_the_model_vendor() {
+        let pricing = ModelPricing {
+            input: 2.0,
+            output: 8.0,
+            cache_read: None,
+            cache_write: None,
+        };
+        let usage = Usage {
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_read_tokens: 1_000_000,
+            cache_write_tokens: 0,
+        };
+        let cost = |model: &str| TokenUsage::from_usage(model, &usage, &pricing).cost_usd;
+
+        assert!((cost("openai/gpt-4o") - 1.0).abs() < 1e-9);
+        assert!((cost("gemini-2.5-pro") - 0.5).abs() < 1e-9);
+        assert!((cost("deepseek-chat") - 0.2).abs() < 1e-9);
+        assert!((cost("mistral-large") - 2.0).abs() < 1e-9);
+    }
+
     #[test]
     fn cost_record_creation() {
         let usage = TokenUsage::new("test/model", 100, 50, 1.0, 2.0);
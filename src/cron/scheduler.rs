@@ -5,16 +5,18 @@ use anyhow::Result;
 use chrono::Utc;
 use tokio::process::Command;
 use tokio::time::{self, Duration};
+use tokio_util::sync::CancellationToken;
 
 const MIN_POLL_SECONDS: u64 = 5;
 
-pub async fn run(config: Config) -> Result<()> {
+/// Poll for due jobs until `shutdown` is cancelled. A job that is already
+/// running finishes (retries included); no new job starts afterwards.
+pub async fn run(config: Config, shutdown: CancellationToken) -> Result<()> {
     if !config.scheduler.enabled {
         tracing::info!("Scheduler disabled by config");
         crate::health::mark_component_ok("scheduler");
-        loop {
-            time::sleep(Duration::from_secs(3600)).await;
-        }
+        shutdown.cancelled().await;
+        return Ok(());
     }
 
     let poll_secs = config.reliability.scheduler_poll_secs.max(MIN_POLL_SECONDS);
@@ -25,7 +27,10 @@ pub async fn run(config: Config) -> Result<()> {
     crate::health::mark_component_ok("scheduler");
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.cancelled() => return Ok(()),
+        }
 
         let jobs = match due_jobs(&config, Utc::now()) {
             Ok(jobs) => jobs,
@@ -37,6 +42,9 @@ pub async fn run(config: Config) -> Result<()> {
         };
 
         for job in jobs.into_iter().take(max_concurrent) {
+            if shutdown.is_cancelled() {
+                return Ok(());
+            }
             crate::health::mark_component_ok("scheduler");
             let (success, output) = execute_job_with_retry(&config, &security, &job).await;
 
@@ -1,10 +1,17 @@
+pub mod reload;
+pub mod shutdown;
+
+use crate::config::reload::LiveConfig;
 use crate::config::Config;
 use anyhow::Result;
 use chrono::Utc;
 use std::future::Future;
 use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{watch, Notify};
 use tokio::task::JoinHandle;
 use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 const STATUS_FLUSH_SECONDS: u64 = 5;
 
@@ -15,6 +22,10 @@ pub async fn run(
     job_queue: Option<PathBuf>,
     results_dir: Option<PathBuf>,
 ) -> Result<()> {
+    for warning in crate::config::validate::ensure_valid(&config)? {
+        tracing::warn!("Config {warning}");
+    }
+
     if let Some(queue_dir) = job_queue {
         let worker_results_dir = results_dir.unwrap_or_else(|| {
             let agent = queue_dir
@@ -42,34 +53,56 @@ pub async fn run(
                 .await;
     }
 
-    let mut handles: Vec<JoinHandle<()>> = vec![spawn_state_writer(config.clone())];
+    let audit = open_audit_logger(&config);
+    let state_writer = spawn_state_writer(config.clone());
+    let shutdown = CancellationToken::new();
+    let mut components: Vec<(&'static str, JoinHandle<()>)> = Vec::new();
+
+    // Components read the latest validated config each time they (re)start;
+    // the reload watcher publishes new configs and signals selective restarts.
+    let (config_tx, live) = watch::channel(Arc::new(config.clone()));
+    let mut restarts = Vec::new();
 
     {
-        let gateway_cfg = config.clone();
+        let restart = Arc::new(Notify::new());
+        restarts.push(("gateway", Arc::clone(&restart)));
+        let live = live.clone();
         let gateway_host = host.clone();
-        handles.push(spawn_component_supervisor(
+        let stop = shutdown.clone();
+        components.push((
             "gateway",
-            initial_backoff,
-            max_backoff,
-            move || {
-                let cfg = gateway_cfg.clone();
-                let host = gateway_host.clone();
-                async move { crate::gateway::run_gateway(&host, port, cfg).await }
-            },
+            spawn_component_supervisor(
+                "gateway",
+                initial_backoff,
+                max_backoff,
+                restart,
+                shutdown.clone(),
+                move || {
+                    let cfg = live.borrow().as_ref().clone();
+                    let host = gateway_host.clone();
+                    let stop = stop.clone();
+                    async move { crate::gateway::run_gateway_until(&host, port, cfg, stop).await }
+                },
+            ),
         ));
     }
 
     {
         if has_supervised_channels(&config) {
-            let channels_cfg = config.clone();
-            handles.push(spawn_component_supervisor(
+            let restart = Arc::new(Notify::new());
+            restarts.push(("channels", Arc::clone(&restart)));
+            let live = live.clone();
+            let stop = shutdown.clone();
+            components.push((
                 "channels",
-                initial_backoff,
-                max_backoff,
-                move || {
-                    let cfg = channels_cfg.clone();
-                    async move { crate::channels::start_channels(cfg).await }
-                },
+                spawn_component_supervisor(
+                    "channels",
+                    initial_backoff,
+                    max_backoff,
+                    restart,
+                    shutdown.clone(),
+                    move || crate::channels::start_channels_live(live.clone(), stop.clone()),
+                ),
             ));
         } else {
             crate::health::mark_component_ok("channels");
@@ -78,47 +111,193 @@ pub async fn run(
     }
 
     if config.heartbeat.enabled {
-        let heartbeat_cfg = config.clone();
-        handles.push(spawn_component_supervisor(
+        let restart = Arc::new(Notify::new());
+        restarts.push(("heartbeat", Arc::clone(&restart)));
+        let live = live.clone();
+        let stop = shutdown.clone();
+        components.push((
             "heartbeat",
-            initial_backoff,
-            max_backoff,
-            move || {
-                let cfg = heartbeat_cfg.clone();
-                async move { run_heartbeat_worker(cfg).await }
-            },
+            spawn_component_supervisor(
+                "heartbeat",
+                initial_backoff,
+                max_backoff,
+                restart,
+                shutdown.clone(),
+                move || run_heartbeat_worker(live.clone(), stop.clone()),
+            ),
+        ));
+    }
+
+    if config.mcp.has_enabled_servers() {
+        let restart = Arc::new(Notify::new());
+        restarts.push(("mcp", Arc::clone(&restart)));
+        let live = live.clone();
+        let stop = shutdown.clone();
+        components.push((
+            "mcp",
+            spawn_component_supervisor(
+                "mcp",
+                initial_backoff,
+                max_backoff,
+                restart,
+                shutdown.clone(),
+                move || crate::mcp::run_supervisor(live.clone(), stop.clone()),
+            ),
         ));
     }
 
     {
-        let scheduler_cfg = config.clone();
-        handles.push(spawn_component_supervisor(
+        let restart = Arc::new(Notify::new());
+        restarts.push(("scheduler", Arc::clone(&restart)));
+        let live = live.clone();
+        let stop = shutdown.clone();
+        components.push((
             "scheduler",
-            initial_backoff,
-            max_backoff,
-            move || {
-                let cfg = scheduler_cfg.clone();
-                async move { crate::cron::scheduler::run(cfg).await }
-            },
+            spawn_component_supervisor(
+                "scheduler",
+                initial_backoff,
+                max_backoff,
+                restart,
+                shutdown.clone(),
+                move || {
+                    let cfg = live.borrow().as_ref().clone();
+                    let stop = stop.clone();
+                    async move { crate::cron::scheduler::run(cfg, stop).await }
+                },
+            ),
         ));
     }
 
+    if config.reliability.health_probe_interval_secs > 0 {
+        let restart = Arc::new(Notify::new());
+        restarts.push(("providers", Arc::clone(&restart)));
+        let live = live.clone();
+        let stop = shutdown.clone();
+        components.push((
+            "providers",
+            spawn_component_supervisor(
+                "providers",
+                initial_backoff,
+                max_backoff,
+                restart,
+                shutdown.clone(),
+                move || run_provider_probes(live.clone(), stop.clone()),
+            ),
+        ));
+    }
+
+    let config_watcher = reload::spawn_config_watcher(config_tx, restarts);
+
     println!("🧠 ZeroClaw daemon started");
     println!("   Gateway:  http://{host}:{port}");
-    println!("   Components: gateway, channels, heartbeat, scheduler");
-    println!("   Ctrl+C to stop");
-
-    tokio::signal::ctrl_c().await?;
-    crate::health::mark_component_error("daemon", "shutdown requested");
+    println!(
+        "   Components: {}",
+        components
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!(
+        "   Config:   {} (reloads on change or SIGHUP)",
+        config.config_path.display()
+    );
+    println!("   Ctrl+C or SIGTERM to stop gracefully");
+
+    let reason = shutdown::wait_for_signal().await?;
+    let started = std::time::Instant::now();
+    let started_at = Utc::now().to_rfc3339();
+    println!(
+        "🛑 {reason} received; finishing in-flight work (up to {}s)",
+        shutdown::DRAIN_DEADLINE.as_secs()
+    );
+    tracing::info!("{reason} received; draining daemon components");
+
+    // No reloads or restarts from here on; components see the cancellation,
+    // stop taking new work and return once what they are running is done.
+    config_watcher.abort();
+    shutdown.cancel();
+    let (drained, aborted) = shutdown::drain(components, shutdown::DRAIN_DEADLINE).await;
+
+    let observers_flushed = crate::observability::flush_all();
+    let audit_flushed = audit
+        .as_ref()
+        .is_some_and(|audit| record_shutdown_audit(audit, reason, &aborted, started));
+
+    state_writer.abort();
+    let _ = state_writer.await;
+    crate::health::mark_component_stopped("daemon");
+    let report = shutdown::ShutdownReport {
+        reason: reason.to_string(),
+        started_at,
+        drained,
+        aborted,
+        observers_flushed,
+        audit_flushed,
+    };
+    write_state_file(&state_file_path(&config), Some(&report)).await;
+
+    if report.aborted.is_empty() {
+        println!("✅ Daemon stopped cleanly");
+    } else {
+        println!(
+            "⚠️  Daemon stopped; aborted after deadline: {}",
+            report.aborted.join(", ")
+        );
+    }
+    Ok(())
+}
 
-    for handle in &handles {
-        handle.abort();
+/// The daemon's audit logger, from `[security.audit]`. `None` when audit
+/// logging is disabled.
+fn open_audit_logger(config: &Config) -> Option<crate::security::AuditLogger> {
+    if !config.security.audit.enabled {
+        return None;
     }
-    for handle in handles {
-        let _ = handle.await;
+    let config_dir = config.config_path.parent()?.to_path_buf();
+    match crate::security::AuditLogger::new(config.security.audit.clone(), config_dir) {
+        Ok(audit) => Some(audit),
+        Err(e) => {
+            tracing::warn!("Audit logging disabled: {e}");
+            None
+        }
     }
+}
 
-    Ok(())
+/// Record the shutdown in the audit log and flush anything still buffered.
+fn record_shutdown_audit(
+    audit: &crate::security::AuditLogger,
+    reason: &str,
+    aborted: &[&'static str],
+    started: std::time::Instant,
+) -> bool {
+    use crate::security::{AuditEvent, AuditEventType};
+
+    let error = (!aborted.is_empty()).then(|| format!("aborted: {}", aborted.join(", ")));
+    let event = AuditEvent::new(AuditEventType::SecurityEvent)
+        .with_actor("daemon".into(), None, None)
+        .with_action(
+            format!("daemon shutdown ({reason})"),
+            "low".into(),
+            true,
+            true,
+        )
+        .with_result(
+            aborted.is_empty(),
+            None,
+            u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            error,
+        );
+    if let Err(e) = audit.log(&event) {
+        tracing::warn!("Failed to write shutdown audit event: {e}");
+    }
+    match audit.flush() {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Failed to flush audit log: {e}");
+            false
+        }
+    }
 }
 
 pub fn state_file_path(config: &Config) -> PathBuf {
@@ -139,23 +318,34 @@ fn spawn_state_writer(config: Config) -> JoinHandle<()> {
         let mut interval = tokio::time::interval(Duration::from_secs(STATUS_FLUSH_SECONDS));
         loop {
             interval.tick().await;
-            let mut json = crate::health::snapshot_json();
-            if let Some(obj) = json.as_object_mut() {
-                obj.insert(
-                    "written_at".into(),
-                    serde_json::json!(Utc::now().to_rfc3339()),
-                );
-            }
-            let data = serde_json::to_vec_pretty(&json).unwrap_or_else(|_| b"{}".to_vec());
-            let _ = tokio::fs::write(&path, data).await;
+            write_state_file(&path, None).await;
         }
     })
 }
 
+/// Write the health snapshot to `daemon_state.json`; the final write on
+/// shutdown also carries the [`shutdown::ShutdownReport`].
+async fn write_state_file(path: &std::path::Path, shutdown: Option<&shutdown::ShutdownReport>) {
+    let mut json = crate::health::snapshot_json();
+    if let Some(obj) = json.as_object_mut() {
+        obj.insert(
+            "written_at".into(),
+            serde_json::json!(Utc::now().to_rfc3339()),
+        );
+        if let Some(report) = shutdown {
+            obj.insert("shutdown".into(), serde_json::json!(report));
+        }
+    }
+    let data = serde_json::to_vec_pretty(&json).unwrap_or_else(|_| b"{}".to_vec());
+    let _ = tokio::fs::write(path, data).await;
+}
+
 fn spawn_component_supervisor<F, Fut>(
     name: &'static str,
     initial_backoff_secs: u64,
     max_backoff_secs: u64,
+    restart: Arc<Notify>,
+    shutdown: CancellationToken,
     mut run_component: F,
 ) -> JoinHandle<()>
 where
@@ -168,7 +358,23 @@ where
 
         loop {
             crate::health::mark_component_ok(name);
-            match run_component().await {
+            let result = tokio::select! {
+                result = run_component() => result,
+                () = restart.notified() => {
+                    // Config reload: start again immediately with the new config
+                    tracing::info!("Restarting daemon component '{name}' to apply config changes");
+                    backoff = initial_backoff_secs.max(1);
+                    continue;
+                }
+            };
+            if shutdown.is_cancelled() {
+                // Returned because of shutdown: done, not a failure.
+                if let Err(e) = result {
+                    tracing::warn!("Daemon component '{name}' failed while stopping: {e}");
+                }
+                return;
+            }
+            match result {
                 Ok(()) => {
                     crate::health::mark_component_error(name, "component exited unexpectedly");
                     tracing::warn!("Daemon component '{name}' exited unexpectedly");
@@ -182,16 +388,20 @@ where
             }
 
             crate::health::bump_component_restart(name);
-            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(backoff)) => {}
+                () = shutdown.cancelled() => return,
+            }
             // Double backoff AFTER sleeping so first error uses initial_backoff
             backoff = backoff.saturating_mul(2).min(max_backoff);
         }
     })
 }
 
-async fn run_heartbeat_worker(config: Config) -> Result<()> {
+async fn run_heartbeat_worker(live: LiveConfig, shutdown: CancellationToken) -> Result<()> {
+    let config = live.borrow().as_ref().clone();
     let observer: std::sync::Arc<dyn crate::observability::Observer> =
-        std::sync::Arc::from(crate::observability::create_observer(&config.observability));
+        crate::observability::create_shared_observer(&config.observability);
     let engine = crate::heartbeat::engine::HeartbeatEngine::new(
         config.heartbeat.clone(),
         config.workspace_dir.clone(),
@@ -202,7 +412,10 @@ async fn run_heartbeat_worker(config: Config) -> Result<()> {
     let mut interval = tokio::time::interval(Duration::from_secs(u64::from(interval_mins) * 60));
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.cancelled() => return Ok(()),
+        }
 
         let tasks = engine.collect_tasks().await?;
         if tasks.is_empty() {
@@ -210,10 +423,16 @@ async fn run_heartbeat_worker(config: Config) -> Result<()> {
         }
 
         for task in tasks {
+            if shutdown.is_cancelled() {
+                return Ok(());
+            }
+            // Model, provider and autonomy changes apply from the next task on.
+            let config = live.borrow().as_ref().clone();
             let prompt = format!("[Heartbeat Task] {task}");
             let temp = config.default_temperature;
             if let Err(e) =
-                crate::agent::run(config.clone(), Some(prompt), None, None, temp, vec![]).await
+                crate::agent::run(config.clone(), Some(prompt), None, None, temp, vec![], None)
+                    .await
             {
                 crate::health::mark_component_error("heartbeat", e.to_string());
                 tracing::warn!("Heartbeat task failed: {e}");
@@ -224,6 +443,69 @@ async fn run_heartbeat_worker(config: Config) -> Result<()> {
     }
 }
 
+/// Periodically warm up every configured provider so open circuit breakers
+/// learn about recoveries (and stay open through outages) without waiting
+/// for user traffic. The providers are rebuilt when the live config changes,
+/// so probes follow the current keys and routes.
+async fn run_provider_probes(mut live: LiveConfig, shutdown: CancellationToken) -> Result<()> {
+    let config = live.borrow_and_update().clone();
+    let mut provider = probe_provider(&config)?;
+    let mut interval = probe_interval(&config);
+    // Probing pauses while `health_probe_interval_secs` is reloaded to 0.
+    let mut probing = config.reliability.health_probe_interval_secs > 0;
+    let mut watching = true;
+    loop {
+        tokio::select! {
+            _ = interval.tick(), if probing => {}
+            changed = live.changed(), if watching => {
+                if changed.is_err() {
+                    watching = false;
+                    continue;
+                }
+                let config = live.borrow_and_update().clone();
+                match probe_provider(&config) {
+                    Ok(next) => {
+                        provider = next;
+                        interval = probe_interval(&config);
+                        probing = config.reliability.health_probe_interval_secs > 0;
+                    }
+                    Err(e) => tracing::warn!(
+                        "Provider probes keep the previous providers after reload: {e:#}"
+                    ),
+                }
+                continue;
+            }
+            () = shutdown.cancelled() => return Ok(()),
+        }
+        // Results are recorded per provider in the breaker registry.
+        if let Ok(false) = provider.warmup().await {
+            tracing::debug!("No provider has a health check; open circuits wait for cool-down");
+        }
+        crate::health::mark_component_ok("providers");
+    }
+}
+
+fn probe_interval(config: &Config) -> tokio::time::Interval {
+    tokio::time::interval(Duration::from_secs(
+        config.reliability.health_probe_interval_secs.max(1),
+    ))
+}
+
+fn probe_provider(config: &Config) -> Result<Box<dyn crate::providers::Provider>> {
+    let provider_name = config.default_provider.as_deref().unwrap_or("openrouter");
+    let model = config
+        .default_model
+        .as_deref()
+        .unwrap_or("anthropic/claude-sonnet-4");
+    crate::providers::create_routed_provider(
+        provider_name,
+        config.api_key.as_deref(),
+        &config.reliability,
+        &config.model_routes,
+        model,
+    )
+}
+
 fn has_supervised_channels(config: &Config) -> bool {
     config.channels_config.telegram.is_some()
         || config.channels_config.discord.is_some()
@@ -232,6 +514,9 @@ fn has_supervised_channels(config: &Config) -> bool {
         || config.channels_config.matrix.is_some()
         || config.channels_config.whatsapp.is_some()
         || config.channels_config.email.is_some()
+        || config.channels_config.signal.is_some()
+        || config.channels_config.teams.is_some()
+        || config.home_assistant.triggers_enabled()
 }
 
 #[cfg(test)]
@@ -260,9 +545,14 @@ mod tests {
 
     #[tokio::test]
     async fn supervisor_marks_error_and_restart_on_failure() {
-        let handle = spawn_component_supervisor("daemon-test-fail", 1, 1, || async {
-            anyhow::bail!("boom")
-        });
+        let handle = spawn_component_supervisor(
+            "daemon-test-fail",
+            1,
+            1,
+            Arc::new(Notify::new()),
+            CancellationToken::new(),
+            || async { anyhow::bail!("boom") },
+        );
 
         tokio::time::sleep(Duration::from_millis(50)).await;
         handle.abort();
@@ -280,7 +570,14 @@ mod tests {
 
     #[tokio::test]
     async fn supervisor_marks_unexpected_exit_as_error() {
-        let handle = spawn_component_supervisor("daemon-test-exit", 1, 1, || async { Ok(()) });
+        let handle = spawn_component_supervisor(
+            "daemon-test-exit",
+            1,
+            1,
+            Arc::new(Notify::new()),
+            CancellationToken::new(),
+            || async { Ok(()) },
+        );
 
         tokio::time::sleep(Duration::from_millis(50)).await;
         handle.abort();
@@ -296,6 +593,132 @@ mod tests {
             .contains("component exited unexpectedly"));
     }
 
+    #[tokio::test]
+    async fn supervisor_restarts_component_on_reload_signal() {
+        let starts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let restart = Arc::new(Notify::new());
+        let counter = Arc::clone(&starts);
+        let handle = spawn_component_supervisor(
+            "daemon-test-reload",
+            60,
+            60,
+            Arc::clone(&restart),
+            CancellationToken::new(),
+            move || {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                std::future::pending::<Result<()>>()
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        restart.notify_one();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        handle.abort();
+        let _ = handle.await;
+
+        // Restarted right away (no backoff) and not counted as a failure.
+        assert_eq!(starts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        let snapshot = crate::health::snapshot_json();
+        let component = &snapshot["components"]["daemon-test-reload"];
+        assert_eq!(component["status"], "ok");
+        assert_eq!(component["restart_count"], 0);
+    }
+
+    #[tokio::test]
+    async fn supervisor_returns_without_restart_after_shutdown() {
+        let starts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let shutdown = CancellationToken::new();
+        let counter = Arc::clone(&starts);
+        let stop = shutdown.clone();
+        let handle = spawn_component_supervisor(
+            "daemon-test-shutdown",
+            1,
+            1,
+            Arc::new(Notify::new()),
+            shutdown.clone(),
+            move || {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let stop = stop.clone();
+                async move {
+                    // Finish "in-flight work" after cancellation, then return.
+                    stop.cancelled().await;
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok(())
+                }
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("supervisor returns after shutdown")
+            .unwrap();
+
+        assert_eq!(starts.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let snapshot = crate::health::snapshot_json();
+        assert_eq!(
+            snapshot["components"]["daemon-test-shutdown"]["restart_count"],
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn final_state_file_includes_shutdown_report() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("daemon_state.json");
+        let report = shutdown::ShutdownReport {
+            reason: "SIGTERM".into(),
+            started_at: Utc::now().to_rfc3339(),
+            drained: vec!["gateway", "scheduler"],
+            aborted: vec!["channels"],
+            observers_flushed: 2,
+            audit_flushed: true,
+        };
+        write_state_file(&path, Some(&report)).await;
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["shutdown"]["reason"], "SIGTERM");
+        assert_eq!(json["shutdown"]["aborted"][0], "channels");
+        assert!(json["components"].is_object());
+    }
+
+    #[test]
+    fn shutdown_audit_uses_configured_log_path() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.security.audit.log_path = "logs-audit.jsonl".into();
+
+        let audit = open_audit_logger(&config).expect("audit enabled");
+        assert!(record_shutdown_audit(
+            &audit,
+            "SIGTERM",
+            &["channels"],
+            std::time::Instant::now()
+        ));
+
+        let written = std::fs::read_to_string(tmp.path().join("logs-audit.jsonl")).unwrap();
+        assert!(written.contains("daemon shutdown (SIGTERM)"));
+        assert!(written.contains("aborted: channels"));
+        assert!(!tmp.path().join("audit.log").exists());
+    }
+
+    #[test]
+    fn shutdown_audit_skipped_when_disabled() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.security.audit.enabled = false;
+
+        assert!(open_audit_logger(&config).is_none());
+    }
+
     #[test]
     fn detects_no_supervised_channels() {
         let config = Config::default();
@@ -0,0 +1,316 @@
+//! Config hot reload for the daemon.
+//!
+//! Watches `config.toml` (and SIGHUP), validates the new file exactly like
+//! startup does, publishes it to live components and restarts only the
+//! supervised components that read the changed sections once at startup.
+//! Every outcome — applied, restarted, rejected — lands in `health` under
+//! the `config` component.
+
+use crate::config::reload::{changed_sections, load_candidate};
+use crate::config::Config;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+/// Health component that reports reload results.
+pub const CONFIG_COMPONENT: &str = "config";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A supervised component and the signal that makes its supervisor restart it.
+pub type RestartHandle = (&'static str, Arc<Notify>);
+
+/// What a reload did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadOutcome {
+    pub changed: BTreeSet<String>,
+    pub restarted: Vec<&'static str>,
+    /// Components that are not running and only start with the daemon.
+    pub needs_daemon_restart: Vec<&'static str>,
+}
+
+impl ReloadOutcome {
+    fn summary(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut summary = format!(
+            "applied [{}]",
+            self.changed.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+        if !self.restarted.is_empty() {
+            let _ = write!(summary, "; restarted {}", self.restarted.join(", "));
+        }
+        if !self.needs_daemon_restart.is_empty() {
+            let _ = write!(
+                summary,
+                "; restart the daemon to start {}",
+                self.needs_daemon_restart.join(", ")
+            );
+        }
+        summary
+    }
+}
+
+/// Whether a change to `section` requires restarting `component`. Sections
+/// not listed reach the component live through its `LiveConfig`.
+fn restart_required(component: &str, section: &str) -> bool {
+    match component {
+        // Provider, model, memory, pairing and the webhook-driven channels are
+        // baked into the gateway's router state.
+        "gateway" => match section.strip_prefix("channels_config.") {
+            Some(channel) => matches!(channel, "webhook" | "whatsapp" | "teams"),
+            None => !matches!(
+                section,
+                "heartbeat" | "scheduler" | "orchestrator" | "cost" | "mcp"
+            ),
+        },
+        "heartbeat" => section == "heartbeat",
+        "scheduler" => matches!(section, "scheduler" | "reliability" | "autonomy"),
+        "providers" => matches!(
+            section,
+            "reliability" | "default_provider" | "default_model" | "api_key" | "model_routes"
+        ),
+        // Server sessions are keyed by their config entry.
+        "mcp" => section == "mcp",
+        // Channels swap agent settings per message and restart individual
+        // listeners themselves.
+        _ => false,
+    }
+}
+
+/// Poll the config file and listen for SIGHUP, reloading on either.
+pub fn spawn_config_watcher(
+    config_tx: watch::Sender<Arc<Config>>,
+    restarts: Vec<RestartHandle>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let path = config_tx.borrow().config_path.clone();
+        let mut last_seen = file_stamp(&path);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        #[cfg(unix)]
+        let mut hangup =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).ok();
+
+        crate::health::mark_component_ok(CONFIG_COMPONENT);
+
+        loop {
+            #[cfg(unix)]
+            let forced = tokio::select! {
+                _ = interval.tick() => false,
+                Some(()) = async {
+                    match hangup.as_mut() {
+                        Some(signal) => signal.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => true,
+            };
+            #[cfg(not(unix))]
+            let forced = {
+                interval.tick().await;
+                false
+            };
+
+            let stamp = file_stamp(&path);
+            if !forced && stamp == last_seen {
+                continue;
+            }
+            last_seen = stamp;
+            if forced {
+                tracing::info!("SIGHUP received; reloading {}", path.display());
+            }
+
+            match apply_reload(&config_tx, &restarts).await {
+                Ok(Some(outcome)) => {
+                    tracing::info!("Config reloaded: {}", outcome.summary());
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Config reload rejected, keeping previous config: {e:#}");
+                }
+            }
+        }
+    })
+}
+
+/// Validate the config on disk and, if anything changed, publish it and
+/// restart the components that need it. Returns `Ok(None)` when the file
+/// parses but nothing effective changed.
+///
+/// Loading reads files and may run secret commands, so it happens on the
+/// blocking pool rather than the runtime thread.
+pub async fn apply_reload(
+    config_tx: &watch::Sender<Arc<Config>>,
+    restarts: &[RestartHandle],
+) -> anyhow::Result<Option<ReloadOutcome>> {
+    let current = Arc::clone(&config_tx.borrow());
+    let loading = Arc::clone(&current);
+    let loaded = tokio::task::spawn_blocking(move || load_candidate(&loading))
+        .await
+        .map_err(|e| anyhow::anyhow!("config reload task failed: {e}"))
+        .and_then(|result| result);
+    let candidate = match loaded {
+        Ok(candidate) => candidate,
+        Err(e) => {
+            crate::health::mark_component_error(
+                CONFIG_COMPONENT,
+                format!("reload rejected, keeping previous config: {e:#}"),
+            );
+            return Err(e);
+        }
+    };
+
+    let changed = changed_sections(&current, &candidate);
+    crate::health::mark_component_ok(CONFIG_COMPONENT);
+    if changed.is_empty() {
+        return Ok(None);
+    }
+
+    let running: Vec<&str> = restarts.iter().map(|(name, _)| *name).collect();
+    let mut needs_daemon_restart = Vec::new();
+    if !running.contains(&"channels") && super::has_supervised_channels(&candidate) {
+        needs_daemon_restart.push("channels");
+    }
+    if !running.contains(&"heartbeat") && candidate.heartbeat.enabled {
+        needs_daemon_restart.push("heartbeat");
+    }
+    if !running.contains(&"mcp") && candidate.mcp.has_enabled_servers() {
+        needs_daemon_restart.push("mcp");
+    }
+
+    let restarted: Vec<_> = restarts
+        .iter()
+        .filter(|(name, _)| changed.iter().any(|s| restart_required(name, s)))
+        .collect();
+
+    // Publish first so restarted components start from the new config.
+    config_tx.send_replace(Arc::new(candidate));
+
+    for (name, signal) in &restarted {
+        let sections: Vec<&str> = changed
+            .iter()
+            .map(String::as_str)
+            .filter(|s| restart_required(name, s))
+            .collect();
+        crate::health::record_component_reload(
+            name,
+            format!("restarted to apply [{}]", sections.join(", ")),
+        );
+        signal.notify_one();
+    }
+
+    let outcome = ReloadOutcome {
+        changed,
+        restarted: restarted.iter().map(|(name, _)| *name).collect(),
+        needs_daemon_restart,
+    };
+    crate::health::record_component_reload(CONFIG_COMPONENT, outcome.summary());
+    Ok(Some(outcome))
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup(tmp: &TempDir) -> (watch::Sender<Arc<Config>>, Vec<RestartHandle>) {
+        let mut config = Config::default();
+        config.config_path = tmp.path().join("config.toml");
+        config.workspace_dir = tmp.path().join("workspace");
+        config.save().unwrap();
+        let config = crate::config::reload::load_candidate(&config).unwrap();
+
+        let restarts = ["gateway", "channels", "scheduler"]
+            .into_iter()
+            .map(|name| (name, Arc::new(Notify::new())))
+            .collect();
+        (watch::channel(Arc::new(config)).0, restarts)
+    }
+
+    fn edit(config_tx: &watch::Sender<Arc<Config>>, f: impl FnOnce(&mut Config)) {
+        let mut config = config_tx.borrow().as_ref().clone();
+        f(&mut config);
+        config.save().unwrap();
+    }
+
+    #[test]
+    fn restart_rules_keep_channel_edits_away_from_gateway() {
+        assert!(restart_required("gateway", "default_model"));
+        assert!(restart_required("gateway", "channels_config.whatsapp"));
+        assert!(!restart_required("gateway", "channels_config.telegram"));
+        assert!(!restart_required("gateway", "heartbeat"));
+        assert!(restart_required("scheduler", "autonomy"));
+        assert!(!restart_required("scheduler", "default_model"));
+        assert!(restart_required("providers", "model_routes"));
+        assert!(!restart_required("providers", "memory"));
+        assert!(!restart_required("channels", "default_model"));
+        assert!(restart_required("mcp", "mcp"));
+        assert!(!restart_required("gateway", "mcp"));
+        assert!(!restart_required("mcp", "default_model"));
+    }
+
+    #[tokio::test]
+    async fn unchanged_file_is_a_no_op() {
+        let tmp = TempDir::new().unwrap();
+        let (config_tx, restarts) = setup(&tmp);
+        assert_eq!(apply_reload(&config_tx, &restarts).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn reload_publishes_config_and_restarts_selected_components() {
+        let tmp = TempDir::new().unwrap();
+        let (config_tx, restarts) = setup(&tmp);
+        let mut live = config_tx.subscribe();
+
+        edit(&config_tx, |c| c.autonomy.max_actions_per_hour = 99);
+        let outcome = apply_reload(&config_tx, &restarts).await.unwrap().unwrap();
+
+        assert_eq!(
+            outcome.changed.into_iter().collect::<Vec<_>>(),
+            vec!["autonomy"]
+        );
+        assert_eq!(outcome.restarted, vec!["gateway", "scheduler"]);
+        assert!(live.has_changed().unwrap());
+        assert_eq!(live.borrow_and_update().autonomy.max_actions_per_hour, 99);
+
+        // The scheduler's supervisor would be woken; channels would not.
+        tokio::time::timeout(Duration::from_millis(100), restarts[2].1.notified())
+            .await
+            .expect("scheduler restart signalled");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), restarts[1].1.notified())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_file_is_rejected_and_previous_config_kept() {
+        let tmp = TempDir::new().unwrap();
+        let (config_tx, restarts) = setup(&tmp);
+        let before = config_tx.borrow().default_temperature;
+
+        std::fs::write(tmp.path().join("config.toml"), "default_temperature = [").unwrap();
+        assert!(apply_reload(&config_tx, &restarts).await.is_err());
+        assert!((config_tx.borrow().default_temperature - before).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn enabling_heartbeat_without_supervisor_asks_for_daemon_restart() {
+        let tmp = TempDir::new().unwrap();
+        let (config_tx, restarts) = setup(&tmp);
+
+        edit(&config_tx, |c| c.heartbeat.enabled = true);
+        let outcome = apply_reload(&config_tx, &restarts).await.unwrap().unwrap();
+        assert_eq!(outcome.needs_daemon_restart, vec!["heartbeat"]);
+        assert!(outcome.restarted.is_empty());
+    }
+}
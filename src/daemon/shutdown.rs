@@ -0,0 +1,97 @@
+//! Graceful daemon shutdown.
+//!
+//! Ctrl+C, or the SIGTERM that `systemctl stop` and `launchctl stop` send,
+//! cancels the daemon's [`CancellationToken`]. Components stop taking new
+//! work and return once what they are running is done; anything still
+//! running at the deadline is aborted. The daemon then flushes observers and
+//! the audit log and writes a final state snapshot.
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+/// How long in-flight turns, requests and jobs get to finish. Keep below the
+/// stop timeouts in the service units (`TimeoutStopSec`, `ExitTimeOut`).
+pub const DRAIN_DEADLINE: Duration = Duration::from_secs(25);
+
+/// Wait for Ctrl+C or SIGTERM and name the one that arrived.
+pub async fn wait_for_signal() -> Result<&'static str> {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                Ok("SIGINT")
+            }
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("Ctrl+C")
+    }
+}
+
+/// What shutdown did; stored under `shutdown` in the final state snapshot.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ShutdownReport {
+    pub reason: String,
+    pub started_at: String,
+    /// Components that finished their in-flight work and returned
+    pub drained: Vec<&'static str>,
+    /// Components still busy at the deadline
+    pub aborted: Vec<&'static str>,
+    pub observers_flushed: usize,
+    /// `false` when audit logging is disabled or the final flush failed
+    pub audit_flushed: bool,
+}
+
+/// Wait for cancelled components to return, aborting any still running once
+/// `deadline` has passed. Returns `(drained, aborted)` component names.
+pub async fn drain(
+    components: Vec<(&'static str, JoinHandle<()>)>,
+    deadline: Duration,
+) -> (Vec<&'static str>, Vec<&'static str>) {
+    let until = Instant::now() + deadline;
+    let mut drained = Vec::new();
+    let mut aborted = Vec::new();
+
+    for (name, mut handle) in components {
+        if tokio::time::timeout_at(until, &mut handle).await.is_ok() {
+            crate::health::mark_component_stopped(name);
+            drained.push(name);
+        } else {
+            handle.abort();
+            let _ = handle.await;
+            crate::health::mark_component_error(name, "aborted at shutdown deadline");
+            tracing::warn!("Component '{name}' did not finish within {deadline:?}; aborted");
+            aborted.push(name);
+        }
+    }
+    (drained, aborted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drain_waits_for_finishers_and_aborts_stragglers() {
+        let quick = tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        });
+        let stuck = tokio::spawn(std::future::pending::<()>());
+
+        let (drained, aborted) = drain(
+            vec![("quick-drain-test", quick), ("stuck-drain-test", stuck)],
+            Duration::from_millis(100),
+        )
+        .await;
+        assert_eq!(drained, vec!["quick-drain-test"]);
+        assert_eq!(aborted, vec!["stuck-drain-test"]);
+    }
+}
@@ -28,7 +28,29 @@ pub fn run(config: &Config) -> Result<()> {
         .and_then(serde_json::Value::as_str)
         .unwrap_or("");
 
-    if let Ok(ts) = DateTime::parse_from_rfc3339(updated_at) {
+    if let Some(shutdown) = snapshot.get("shutdown") {
+        let reason = shutdown
+            .get("reason")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unknown");
+        let at = shutdown
+            .get("started_at")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("");
+        let aborted: Vec<&str> = shutdown
+            .get("aborted")
+            .and_then(serde_json::Value::as_array)
+            .map(|a| a.iter().filter_map(serde_json::Value::as_str).collect())
+            .unwrap_or_default();
+        if aborted.is_empty() {
+            println!("  ℹ️  daemon stopped cleanly at {at} ({reason})");
+        } else {
+            println!(
+                "  ⚠️  daemon stopped at {at} ({reason}); aborted: {}",
+                aborted.join(", ")
+            );
+        }
+    } else if let Ok(ts) = DateTime::parse_from_rfc3339(updated_at) {
         let age = Utc::now()
             .signed_duration_since(ts.with_timezone(&Utc))
             .num_seconds();
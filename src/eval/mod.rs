@@ -0,0 +1,73 @@
+//! `zeroclaw eval` — scenario harness for agent behavior.
+//!
+//! Each scenario file (TOML or YAML) sends a sequence of user messages
+//! through `Agent::turn` against a real, scripted or replayed provider, then
+//! checks which tools were called, the final reply, memory writes and tool
+//! iterations. Results print to the console and optionally to JUnit XML or
+//! JSON for CI.
+
+pub mod report;
+pub mod runner;
+pub mod scenario;
+
+pub use runner::run_scenario;
+pub use scenario::Scenario;
+
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Options for `zeroclaw eval`.
+#[derive(Debug, Clone, Default)]
+pub struct EvalOptions {
+    /// Only run scenarios whose name contains this text
+    pub filter: Option<String>,
+    pub junit: Option<PathBuf>,
+    pub json: Option<PathBuf>,
+    /// Provider for `judge` assertions (defaults to `default_provider`)
+    pub judge_provider: Option<String>,
+    pub judge_model: Option<String>,
+}
+
+/// Run every scenario under `path` and fail if any does not pass.
+pub async fn run(config: &Config, path: &Path, options: &EvalOptions) -> Result<()> {
+    let files = scenario::discover(path)?;
+    let mut scenarios = Vec::with_capacity(files.len());
+    for file in &files {
+        let scenario = Scenario::load(file)?;
+        if options
+            .filter
+            .as_deref()
+            .is_none_or(|f| scenario.name.contains(f))
+        {
+            scenarios.push(scenario);
+        }
+    }
+    if scenarios.is_empty() {
+        bail!("No scenarios found in {}", path.display());
+    }
+
+    let mut results = Vec::with_capacity(scenarios.len());
+    for scenario in &scenarios {
+        results.push(run_scenario(config, scenario, options).await);
+    }
+
+    report::print(&results);
+    if let Some(junit) = &options.junit {
+        std::fs::write(junit, report::to_junit(&results))
+            .with_context(|| format!("Failed to write {}", junit.display()))?;
+    }
+    if let Some(json) = &options.json {
+        std::fs::write(
+            json,
+            serde_json::to_string_pretty(&report::to_json(&results))?,
+        )
+        .with_context(|| format!("Failed to write {}", json.display()))?;
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    if failed > 0 {
+        bail!("{failed} of {} scenarios failed", results.len());
+    }
+    Ok(())
+}
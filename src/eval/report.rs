@@ -0,0 +1,145 @@
+//! Console, JSON and JUnit output for eval runs.
+
+use super::runner::ScenarioResult;
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+/// Print one line per scenario, with failures underneath.
+pub fn print(results: &[ScenarioResult]) {
+    for result in results {
+        let icon = if result.passed { "✅" } else { "❌" };
+        println!(
+            "{icon} {} ({:.1}s)",
+            result.name,
+            result.duration.as_secs_f64()
+        );
+        if let Some(error) = &result.error {
+            println!("   error: {error}");
+        }
+        for failure in &result.failures {
+            println!("   - {failure}");
+        }
+    }
+    let passed = results.iter().filter(|r| r.passed).count();
+    println!();
+    println!("{passed}/{} scenarios passed", results.len());
+}
+
+pub fn to_json(results: &[ScenarioResult]) -> Value {
+    let passed = results.iter().filter(|r| r.passed).count();
+    json!({
+        "summary": {
+            "total": results.len(),
+            "passed": passed,
+            "failed": results.len() - passed,
+        },
+        "scenarios": results,
+    })
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if c.is_control() && !matches!(c, '\n' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn to_junit(results: &[ScenarioResult]) -> String {
+    let failures = results
+        .iter()
+        .filter(|r| r.error.is_none() && !r.failures.is_empty())
+        .count();
+    let errors = results.iter().filter(|r| r.error.is_some()).count();
+    let total: f64 = results.iter().map(|r| r.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{total:.3}\">",
+        results.len()
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"zeroclaw-eval\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{total:.3}\">",
+        results.len()
+    );
+    for result in results {
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape_xml(&result.name),
+            escape_xml(&result.path.display().to_string()),
+            result.duration.as_secs_f64()
+        );
+        if let Some(error) = &result.error {
+            let _ = writeln!(
+                xml,
+                ">\n      <error message=\"{}\"/>\n    </testcase>",
+                escape_xml(error)
+            );
+        } else if result.failures.is_empty() {
+            xml.push_str("/>\n");
+        } else {
+            let _ = writeln!(
+                xml,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape_xml(&result.failures[0]),
+                escape_xml(&result.failures.join("\n"))
+            );
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::scenario::Observed;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn result(name: &str, failures: &[&str], error: Option<&str>) -> ScenarioResult {
+        ScenarioResult {
+            name: name.into(),
+            path: PathBuf::from(format!("evals/{name}.toml")),
+            passed: failures.is_empty() && error.is_none(),
+            failures: failures.iter().map(|f| (*f).to_string()).collect(),
+            error: error.map(str::to_string),
+            observed: Observed::default(),
+            duration: Duration::from_millis(1500),
+        }
+    }
+
+    #[test]
+    fn reports_count_passes_failures_and_errors() {
+        let results = vec![
+            result("ok", &[], None),
+            result("bad <reply>", &["final reply does not contain \"x\""], None),
+            result("broken", &[], Some("Replay drift & more")),
+        ];
+
+        let report = to_json(&results);
+        assert_eq!(
+            report["summary"],
+            json!({"total": 3, "passed": 1, "failed": 2})
+        );
+        assert_eq!(report["scenarios"][0]["duration_ms"], 1500);
+
+        let xml = to_junit(&results);
+        assert!(xml.contains("tests=\"3\" failures=\"1\" errors=\"1\""));
+        assert!(xml.contains("<testcase name=\"ok\" classname=\"evals/ok.toml\" time=\"1.500\"/>"));
+        assert!(xml.contains("name=\"bad &lt;reply&gt;\""));
+        assert!(xml.contains("<failure message=\"final reply does not contain &quot;x&quot;\">"));
+        assert!(xml.contains("<error message=\"Replay drift &amp; more\"/>"));
+    }
+}
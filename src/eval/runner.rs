@@ -0,0 +1,691 @@
+//! Runs a scenario through `Agent::turn` in a scratch workspace and records
+//! tool calls, memory writes and tool iterations.
+
+use super::scenario::{
+    Observed, ObservedMemoryWrite, ObservedToolCall, Scenario, ScenarioProvider, ScriptedResponse,
+};
+use super::EvalOptions;
+use crate::agent::dispatcher::{NativeToolDispatcher, ToolDispatcher, XmlToolDispatcher};
+use crate::agent::Agent;
+use crate::config::Config;
+use crate::memory::{self, Memory, MemoryCategory, MemoryEntry};
+use crate::observability::traits::ObserverMetric;
+use crate::observability::{Observer, ObserverEvent};
+use crate::providers::replay::ReplayProvider;
+use crate::providers::{self, ChatMessage, ChatRequest, ChatResponse, Provider, ToolCall};
+use crate::runtime;
+use crate::security::SecurityPolicy;
+use crate::tools::{self, Tool, ToolResult};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const JUDGE_SYSTEM_PROMPT: &str = "You grade an AI assistant's conversation against a rubric. \
+Set `pass` to whether it meets the rubric and explain why in one sentence in `reason`.";
+
+/// The judge's structured answer.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+struct Verdict {
+    pass: bool,
+    reason: String,
+}
+
+/// Outcome of one scenario.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub path: PathBuf,
+    pub passed: bool,
+    /// Failed assertions
+    pub failures: Vec<String>,
+    /// Set when the run itself failed (provider error, replay drift, …)
+    pub error: Option<String>,
+    pub observed: Observed,
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn as_millis<S: serde::Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// Counts provider rounds.
+#[derive(Default)]
+struct EvalObserver {
+    llm_requests: Mutex<usize>,
+}
+
+impl Observer for EvalObserver {
+    fn record_event(&self, event: &ObserverEvent) {
+        if let ObserverEvent::LlmRequest { .. } = event {
+            *self
+                .llm_requests
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) += 1;
+        }
+    }
+
+    fn record_metric(&self, _metric: &ObserverMetric) {}
+
+    fn name(&self) -> &str {
+        "eval"
+    }
+}
+
+impl EvalObserver {
+    fn llm_requests(&self) -> usize {
+        *self
+            .llm_requests
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Captures each call's arguments and outcome, which observer events do not
+/// tie to one call.
+struct RecordingTool {
+    inner: Box<dyn Tool>,
+    calls: Arc<Mutex<Vec<ObservedToolCall>>>,
+}
+
+#[async_trait]
+impl Tool for RecordingTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.inner.parameters_schema()
+    }
+
+    fn read_only(&self) -> bool {
+        self.inner.read_only()
+    }
+
+    async fn execute(&self, args: Value) -> Result<ToolResult> {
+        let index = {
+            let mut calls = self
+                .calls
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            calls.push(ObservedToolCall {
+                name: self.inner.name().to_string(),
+                arguments: args.clone(),
+                success: None,
+            });
+            calls.len() - 1
+        };
+        let result = self.inner.execute(args).await;
+        self.calls
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)[index]
+            .success = Some(matches!(&result, Ok(r) if r.success));
+        result
+    }
+}
+
+/// Captures memory writes from tools and auto-save.
+struct RecordingMemory {
+    inner: Box<dyn Memory>,
+    writes: Mutex<Vec<ObservedMemoryWrite>>,
+}
+
+#[async_trait]
+impl Memory for RecordingMemory {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn store(&self, key: &str, content: &str, category: MemoryCategory) -> Result<()> {
+        self.writes
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(ObservedMemoryWrite {
+                key: key.to_string(),
+                content: content.to_string(),
+                category: category.to_string(),
+            });
+        self.inner.store(key, content, category).await
+    }
+
+    async fn recall(&self, query: &str, limit: usize) -> Result<Vec<MemoryEntry>> {
+        self.inner.recall(query, limit).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        self.inner.get(key).await
+    }
+
+    async fn list(&self, category: Option<&MemoryCategory>) -> Result<Vec<MemoryEntry>> {
+        self.inner.list(category).await
+    }
+
+    async fn forget(&self, key: &str) -> Result<bool> {
+        self.inner.forget(key).await
+    }
+
+    async fn count(&self) -> Result<usize> {
+        self.inner.count().await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+}
+
+/// Returns canned responses in order.
+struct ScriptedProvider {
+    responses: Mutex<VecDeque<ScriptedResponse>>,
+    next_id: Mutex<usize>,
+}
+
+impl ScriptedProvider {
+    fn new(responses: Vec<ScriptedResponse>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into()),
+            next_id: Mutex::new(0),
+        }
+    }
+
+    fn next(&self) -> Result<ChatResponse> {
+        let Some(response) = self
+            .responses
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .pop_front()
+        else {
+            bail!("Scripted provider ran out of responses");
+        };
+        let mut next_id = self
+            .next_id
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let tool_calls = response
+            .tool_calls
+            .into_iter()
+            .map(|call| {
+                *next_id += 1;
+                ToolCall {
+                    id: call.id.unwrap_or_else(|| format!("call_{next_id}")),
+                    name: call.name,
+                    arguments: call.arguments.to_string(),
+                }
+            })
+            .collect();
+        Ok(ChatResponse {
+            text: response.text,
+            tool_calls,
+            usage: None,
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for ScriptedProvider {
+    async fn chat_with_system(
+        &self,
+        _system_prompt: Option<&str>,
+        _message: &str,
+        _model: &str,
+        _temperature: f64,
+    ) -> Result<String> {
+        Ok(self.next()?.text.unwrap_or_default())
+    }
+
+    async fn chat(
+        &self,
+        _request: ChatRequest<'_>,
+        _model: &str,
+        _temperature: f64,
+    ) -> Result<ChatResponse> {
+        self.next()
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        true
+    }
+}
+
+/// A temp workspace removed on drop.
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run one scenario and check its assertions.
+pub async fn run_scenario(
+    config: &Config,
+    scenario: &Scenario,
+    options: &EvalOptions,
+) -> ScenarioResult {
+    let started = Instant::now();
+    let mut result = ScenarioResult {
+        name: scenario.name.clone(),
+        path: scenario.path.clone(),
+        passed: false,
+        failures: Vec::new(),
+        error: None,
+        observed: Observed::default(),
+        duration: Duration::ZERO,
+    };
+
+    match Box::pin(execute(config, scenario, &mut result.observed)).await {
+        Ok(()) => {
+            result.failures = scenario.check(&result.observed);
+            if let Some(rubric) = &scenario.expect.judge {
+                match judge(config, options, rubric, scenario, &result.observed).await {
+                    Ok(None) => {}
+                    Ok(Some(reason)) => result.failures.push(format!("judge: {reason}")),
+                    Err(e) => result.error = Some(format!("judge failed: {e}")),
+                }
+            }
+        }
+        Err(e) => result.error = Some(format!("{e:#}")),
+    }
+
+    result.passed = result.error.is_none() && result.failures.is_empty();
+    result.duration = started.elapsed();
+    result
+}
+
+async fn execute(config: &Config, scenario: &Scenario, observed: &mut Observed) -> Result<()> {
+    let scratch =
+        Scratch(std::env::temp_dir().join(format!("zeroclaw-eval-{}", uuid::Uuid::new_v4())));
+    std::fs::create_dir_all(&scratch.0)?;
+    let workspace = scratch.0.clone();
+
+    let mut config = config.clone();
+    config.workspace_dir.clone_from(&workspace);
+    // Keep memory local and deterministic.
+    config.memory.embedding_provider = "none".into();
+
+    let observer = Arc::new(EvalObserver::default());
+    let memory = Arc::new(RecordingMemory {
+        inner: memory::create_memory(&config.memory, &workspace, config.api_key.as_deref())?,
+        writes: Mutex::new(Vec::new()),
+    });
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let tools = scenario_tools(&config, scenario, memory.clone(), &calls)?;
+
+    let model_name = scenario
+        .model
+        .clone()
+        .or_else(|| config.default_model.clone())
+        .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
+    let mut replay = None;
+    let (provider_name, provider): (String, Box<dyn Provider>) = match &scenario.provider {
+        ScenarioProvider::Real { name } => {
+            let name = name
+                .clone()
+                .or_else(|| config.default_provider.clone())
+                .unwrap_or_else(|| "openrouter".into());
+            let provider = providers::create_routed_provider(
+                &name,
+                config.api_key.as_deref(),
+                &config.reliability,
+                &config.model_routes,
+                &model_name,
+            )?;
+            (name, provider)
+        }
+        ScenarioProvider::Scripted { responses } => (
+            "scripted".into(),
+            Box::new(ScriptedProvider::new(responses.clone())),
+        ),
+        ScenarioProvider::Replay { cassette } => {
+            let base = scenario.path.parent().unwrap_or(Path::new("."));
+            let provider = Arc::new(
+                ReplayProvider::load(&base.join(cassette))?
+                    .with_alias(&workspace.display().to_string(), "{workspace}"),
+            );
+            replay = Some(provider.clone());
+            (format!("replay:{cassette}"), Box::new(provider))
+        }
+    };
+
+    let tool_dispatcher: Box<dyn ToolDispatcher> = match config.agent.tool_dispatcher.as_str() {
+        "native" => Box::new(NativeToolDispatcher),
+        "xml" => Box::new(XmlToolDispatcher),
+        _ if provider.supports_native_tools() => Box::new(NativeToolDispatcher),
+        _ => Box::new(XmlToolDispatcher),
+    };
+
+    let memory_handle: Arc<dyn Memory> = memory.clone();
+    let observer_handle: Arc<dyn Observer> = observer.clone();
+    let mut agent = Agent::builder()
+        .provider(provider)
+        .tools(tools)
+        .memory(memory_handle)
+        .observer(observer_handle)
+        .tool_dispatcher(tool_dispatcher)
+        .config(config.agent.clone())
+        .provider_name(provider_name)
+        .model_name(model_name)
+        .temperature(config.default_temperature)
+        .workspace_dir(workspace)
+        .identity_config(config.identity.clone())
+        .auto_save(config.memory.auto_save)
+        .build()?;
+
+    let mut outcome = Ok(());
+    for message in &scenario.messages {
+        let before = observer.llm_requests();
+        let reply = agent.turn(message).await;
+        observed
+            .tool_iterations
+            .push(observer.llm_requests().saturating_sub(before + 1));
+        match reply {
+            Ok(reply) => observed.replies.push(reply),
+            Err(e) => {
+                outcome = Err(e);
+                break;
+            }
+        }
+    }
+
+    observed.tool_calls = std::mem::take(
+        &mut *calls
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner),
+    );
+    observed.memory_writes = std::mem::take(
+        &mut *memory
+            .writes
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner),
+    );
+
+    outcome?;
+    if let Some(replay) = replay {
+        replay.assert_exhausted()?;
+    }
+    Ok(())
+}
+
+fn scenario_tools(
+    config: &Config,
+    scenario: &Scenario,
+    memory: Arc<RecordingMemory>,
+    calls: &Arc<Mutex<Vec<ObservedToolCall>>>,
+) -> Result<Vec<Box<dyn Tool>>> {
+    let security = Arc::new(SecurityPolicy::from_config(
+        &config.autonomy,
+        &config.workspace_dir,
+    ));
+    let runtime: Arc<dyn runtime::RuntimeAdapter> =
+        Arc::from(runtime::create_runtime(&config.runtime)?);
+    let (composio_key, composio_entity_id) = if config.composio.enabled {
+        (
+            config.composio.api_key.as_deref(),
+            Some(config.composio.entity_id.as_str()),
+        )
+    } else {
+        (None, None)
+    };
+    let mut all = tools::all_tools_with_runtime(
+        &security,
+        runtime,
+        memory,
+        composio_key,
+        composio_entity_id,
+        &config.browser,
+        &config.http_request,
+        &config.workspace_dir,
+        &config.agents,
+        config.api_key.as_deref(),
+        config,
+    );
+
+    if let Some(wanted) = &scenario.tools {
+        if let Some(unknown) = wanted
+            .iter()
+            .find(|name| !all.iter().any(|t| t.name() == name.as_str()))
+        {
+            bail!("Scenario lists unknown tool `{unknown}`");
+        }
+        all.retain(|t| wanted.iter().any(|name| name == t.name()));
+    }
+
+    Ok(all
+        .into_iter()
+        .map(|inner| {
+            Box::new(RecordingTool {
+                inner,
+                calls: calls.clone(),
+            }) as Box<dyn Tool>
+        })
+        .collect())
+}
+
+/// Ask an LLM whether the conversation meets `rubric`. Returns the reason
+/// when it does not.
+async fn judge(
+    config: &Config,
+    options: &EvalOptions,
+    rubric: &str,
+    scenario: &Scenario,
+    observed: &Observed,
+) -> Result<Option<String>> {
+    let provider_name = options
+        .judge_provider
+        .clone()
+        .or_else(|| config.default_provider.clone())
+        .unwrap_or_else(|| "openrouter".into());
+    let model = options
+        .judge_model
+        .clone()
+        .or_else(|| config.default_model.clone())
+        .unwrap_or_else(|| "anthropic/claude-sonnet-4-20250514".into());
+    let provider = providers::create_resilient_provider(
+        &provider_name,
+        config.api_key.as_deref(),
+        &config.reliability,
+    )?;
+
+    let verdict: Verdict = providers::structured::chat_as(
+        provider.as_ref(),
+        &[
+            ChatMessage::system(JUDGE_SYSTEM_PROMPT),
+            ChatMessage::user(judge_prompt(rubric, scenario, observed)),
+        ],
+        &model,
+        0.0,
+    )
+    .await?;
+    Ok(failure_reason(verdict))
+}
+
+fn judge_prompt(rubric: &str, scenario: &Scenario, observed: &Observed) -> String {
+    let mut transcript = String::new();
+    for (message, reply) in scenario.messages.iter().zip(&observed.replies) {
+        let _ = write!(transcript, "User: {message}\nAssistant: {reply}\n\n");
+    }
+    let tools: Vec<String> = observed
+        .tool_calls
+        .iter()
+        .map(|c| format!("{}({})", c.name, c.arguments))
+        .collect();
+    format!(
+        "Rubric:\n{rubric}\n\nConversation:\n{transcript}Tools called: {}",
+        if tools.is_empty() {
+            "none".to_string()
+        } else {
+            tools.join(", ")
+        }
+    )
+}
+
+/// `None` for a pass, otherwise the judge's explanation.
+fn failure_reason(verdict: Verdict) -> Option<String> {
+    if verdict.pass {
+        return None;
+    }
+    let reason = verdict.reason.trim();
+    Some(if reason.is_empty() {
+        "judge did not pass the conversation".into()
+    } else {
+        reason.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(dir: &Path) -> Config {
+        let mut config = Config::default();
+        config.workspace_dir = dir.to_path_buf();
+        config.memory.backend = "sqlite".into();
+        config.memory.auto_save = false;
+        config
+    }
+
+    fn scenario(dir: &Path, name: &str, body: &str) -> Scenario {
+        let path = dir.join(name);
+        std::fs::write(&path, body).unwrap();
+        Scenario::load(&path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn scripted_scenario_records_tools_memory_and_iterations() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let scenario = scenario(
+            dir.path(),
+            "remember.toml",
+            r#"
+messages = ["Remember that I like Rust", "What do I like?"]
+tools = ["memory_store", "memory_recall"]
+
+[provider]
+kind = "scripted"
+
+[[provider.responses]]
+tool_calls = [{ name = "memory_store", arguments = { key = "lang", content = "User likes Rust", category = "core" } }]
+
+[[provider.responses]]
+text = "Saved."
+
+[[provider.responses]]
+tool_calls = [{ name = "memory_recall", arguments = { query = "Rust" } }]
+
+[[provider.responses]]
+text = "You like Rust."
+
+[expect]
+tools_called = [{ name = "memory_store", args = { key = "lang" } }, { name = "memory_recall" }]
+tools_not_called = ["shell"]
+final_contains = ["Rust"]
+final_regex = "^You like"
+memory_writes = [{ key = "lang", contains = "Rust", category = "core" }]
+max_tool_iterations = 1
+"#,
+        );
+
+        let result =
+            run_scenario(&test_config(dir.path()), &scenario, &EvalOptions::default()).await;
+        assert!(result.passed, "{result:#?}");
+        assert_eq!(result.observed.replies, ["Saved.", "You like Rust."]);
+        assert_eq!(result.observed.tool_iterations, [1, 1]);
+        assert_eq!(result.observed.tool_calls[0].success, Some(true));
+        assert_eq!(result.observed.memory_writes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_calls_do_not_shift_tool_outcomes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let scenario = scenario(
+            dir.path(),
+            "rejected.toml",
+            r#"
+messages = ["Remember that I like Rust"]
+tools = ["memory_store"]
+
+[provider]
+kind = "scripted"
+
+[[provider.responses]]
+tool_calls = [
+    { name = "memory_store", arguments = { key = "lang" } },
+    { name = "memory_store", arguments = { key = "lang", content = "User likes Rust" } },
+]
+
+[[provider.responses]]
+text = "Saved."
+"#,
+        );
+
+        let result =
+            run_scenario(&test_config(dir.path()), &scenario, &EvalOptions::default()).await;
+        assert!(result.passed, "{result:#?}");
+        assert_eq!(result.observed.tool_calls.len(), 1);
+        assert_eq!(
+            result.observed.tool_calls[0].arguments["content"],
+            "User likes Rust"
+        );
+        assert_eq!(result.observed.tool_calls[0].success, Some(true));
+    }
+
+    #[tokio::test]
+    async fn failures_and_run_errors_are_reported() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wrong = scenario(
+            dir.path(),
+            "wrong.yaml",
+            "messages: [hi]\ntools: [memory_store]\nprovider:\n  kind: scripted\n  responses:\n    - text: hello\nexpect:\n  final_contains: [bye]\n  tools_called:\n    - name: memory_store\n",
+        );
+        let result = run_scenario(&test_config(dir.path()), &wrong, &EvalOptions::default()).await;
+        assert!(!result.passed);
+        assert!(result.error.is_none());
+        assert_eq!(result.failures.len(), 2, "{:?}", result.failures);
+
+        let exhausted = scenario(
+            dir.path(),
+            "exhausted.yaml",
+            "messages: [hi, again]\ntools: [memory_store]\nprovider:\n  kind: scripted\n  responses:\n    - text: hello\n",
+        );
+        let result = run_scenario(
+            &test_config(dir.path()),
+            &exhausted,
+            &EvalOptions::default(),
+        )
+        .await;
+        assert!(!result.passed);
+        assert!(result
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("ran out of responses"));
+        assert_eq!(result.observed.replies, ["hello"]);
+    }
+
+    #[test]
+    fn verdicts_are_read() {
+        let verdict = |pass, reason: &str| Verdict {
+            pass,
+            reason: reason.into(),
+        };
+        assert_eq!(failure_reason(verdict(true, "Looks right.")), None);
+        assert_eq!(
+            failure_reason(verdict(false, "It never mentions Rust.")),
+            Some("It never mentions Rust.".into())
+        );
+        assert_eq!(
+            failure_reason(verdict(false, " ")),
+            Some("judge did not pass the conversation".into())
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Scenario files: what to send, which provider answers, what to expect.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// File extensions treated as scenarios. JSON is left out so cassettes can
+/// live next to the scenarios that replay them.
+const SCENARIO_EXTENSIONS: &[&str] = &["toml", "yaml", "yml"];
+
+/// One eval scenario.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Display name (defaults to the file stem)
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// User messages, sent in order as separate turns
+    pub messages: Vec<String>,
+    #[serde(default)]
+    pub provider: ScenarioProvider,
+    /// Model override (defaults to `default_model` from config)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Only expose these tools (defaults to the full tool set)
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub expect: Expectations,
+    /// Source file, set on load
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// Where responses come from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScenarioProvider {
+    /// A live provider (defaults to `default_provider` from config)
+    Real {
+        #[serde(default)]
+        name: Option<String>,
+    },
+    /// Canned responses, returned in order
+    Scripted { responses: Vec<ScriptedResponse> },
+    /// A cassette recorded with `zeroclaw agent --record`, relative to the
+    /// scenario file
+    Replay { cassette: String },
+}
+
+impl Default for ScenarioProvider {
+    fn default() -> Self {
+        Self::Real { name: None }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptedResponse {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ScriptedToolCall>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptedToolCall {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default = "empty_object")]
+    pub arguments: Value,
+}
+
+fn empty_object() -> Value {
+    Value::Object(serde_json::Map::new())
+}
+
+/// Assertions checked after the last message.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    /// Tools that must have been called, optionally with matching arguments
+    #[serde(default)]
+    pub tools_called: Vec<ToolExpectation>,
+    #[serde(default)]
+    pub tools_not_called: Vec<String>,
+    /// Substrings the final reply must contain
+    #[serde(default)]
+    pub final_contains: Vec<String>,
+    #[serde(default)]
+    pub final_not_contains: Vec<String>,
+    /// Regex the final reply must match
+    #[serde(default)]
+    pub final_regex: Option<String>,
+    /// Rubric for an LLM judge, which must return a passing verdict
+    #[serde(default)]
+    pub judge: Option<String>,
+    /// Memory entries that must have been stored
+    #[serde(default)]
+    pub memory_writes: Vec<MemoryExpectation>,
+    /// Upper bound on tool-call rounds in any single turn
+    #[serde(default)]
+    pub max_tool_iterations: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolExpectation {
+    pub name: String,
+    /// Argument subset: every key given must match (objects recursively)
+    #[serde(default)]
+    pub args: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryExpectation {
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub contains: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// A tool call observed during a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct ObservedToolCall {
+    pub name: String,
+    pub arguments: Value,
+    pub success: Option<bool>,
+}
+
+/// A memory write observed during a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct ObservedMemoryWrite {
+    pub key: String,
+    pub content: String,
+    pub category: String,
+}
+
+/// What happened while running a scenario.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Observed {
+    /// Reply to each message
+    pub replies: Vec<String>,
+    pub tool_calls: Vec<ObservedToolCall>,
+    pub memory_writes: Vec<ObservedMemoryWrite>,
+    /// Tool-call rounds per turn
+    pub tool_iterations: Vec<usize>,
+}
+
+impl Observed {
+    pub fn final_reply(&self) -> &str {
+        self.replies.last().map_or("", String::as_str)
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let mut scenario: Self = match extension {
+            "toml" => toml::from_str(&raw).map_err(anyhow::Error::from),
+            "yaml" | "yml" => serde_yaml::from_str(&raw).map_err(anyhow::Error::from),
+            _ => bail!("Unsupported scenario format: {}", path.display()),
+        }
+        .with_context(|| format!("Invalid scenario {}", path.display()))?;
+
+        if scenario.messages.is_empty() {
+            bail!("Scenario {} has no messages", path.display());
+        }
+        if scenario.name.trim().is_empty() {
+            scenario.name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("scenario")
+                .to_string();
+        }
+        if let Some(pattern) = &scenario.expect.final_regex {
+            regex::Regex::new(pattern)
+                .with_context(|| format!("Invalid final_regex in {}: {pattern}", path.display()))?;
+        }
+        scenario.path = path.to_path_buf();
+        Ok(scenario)
+    }
+
+    /// Check everything except the LLM judge. Returns one message per
+    /// failed assertion.
+    pub fn check(&self, observed: &Observed) -> Vec<String> {
+        let expect = &self.expect;
+        let reply = observed.final_reply();
+        let mut failures = Vec::new();
+
+        for wanted in &expect.tools_called {
+            let hit = observed.tool_calls.iter().any(|call| {
+                call.name == wanted.name
+                    && wanted
+                        .args
+                        .as_ref()
+                        .is_none_or(|args| value_contains(&call.arguments, args))
+            });
+            if !hit {
+                let seen: Vec<String> = observed
+                    .tool_calls
+                    .iter()
+                    .filter(|c| c.name == wanted.name)
+                    .map(|c| c.arguments.to_string())
+                    .collect();
+                failures.push(match (&wanted.args, seen.is_empty()) {
+                    (_, true) => format!("expected tool `{}` to be called", wanted.name),
+                    (Some(args), false) => format!(
+                        "expected tool `{}` with args {args}, saw {}",
+                        wanted.name,
+                        seen.join(", ")
+                    ),
+                    (None, false) => unreachable!("a call without an args filter always matches"),
+                });
+            }
+        }
+        for name in &expect.tools_not_called {
+            if observed.tool_calls.iter().any(|c| &c.name == name) {
+                failures.push(format!("expected tool `{name}` not to be called"));
+            }
+        }
+
+        for needle in &expect.final_contains {
+            if !reply.contains(needle.as_str()) {
+                failures.push(format!("final reply does not contain {needle:?}"));
+            }
+        }
+        for needle in &expect.final_not_contains {
+            if reply.contains(needle.as_str()) {
+                failures.push(format!("final reply contains {needle:?}"));
+            }
+        }
+        if let Some(pattern) = &expect.final_regex {
+            // Validated in `load`.
+            if let Ok(re) = regex::Regex::new(pattern) {
+                if !re.is_match(reply) {
+                    failures.push(format!("final reply does not match /{pattern}/"));
+                }
+            }
+        }
+
+        for wanted in &expect.memory_writes {
+            let hit = observed.memory_writes.iter().any(|write| {
+                wanted.key.as_ref().is_none_or(|k| &write.key == k)
+                    && wanted
+                        .contains
+                        .as_ref()
+                        .is_none_or(|c| write.content.contains(c.as_str()))
+                    && wanted
+                        .category
+                        .as_ref()
+                        .is_none_or(|c| &write.category == c)
+            });
+            if !hit {
+                failures.push(format!(
+                    "expected a memory write matching key={:?} contains={:?} category={:?}",
+                    wanted.key, wanted.contains, wanted.category
+                ));
+            }
+        }
+
+        if let Some(max) = expect.max_tool_iterations {
+            let worst = observed.tool_iterations.iter().copied().max().unwrap_or(0);
+            if worst > max {
+                failures.push(format!(
+                    "used {worst} tool iterations in one turn (max {max})"
+                ));
+            }
+        }
+        failures
+    }
+}
+
+/// Whether `actual` contains everything in `expected`: objects match on the
+/// given keys, everything else must be equal.
+fn value_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(k, v)| actual.get(k).is_some_and(|a| value_contains(a, v))),
+        _ => actual == expected,
+    }
+}
+
+/// Scenario files under `path` (or `path` itself), sorted.
+pub fn discover(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut found = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                pending.push(entry_path);
+            } else if entry_path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| SCENARIO_EXTENSIONS.contains(&e))
+            {
+                found.push(entry_path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[test]
+    fn bundled_example_scenario_loads() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/evals/remember-language.yaml");
+        let scenario = Scenario::load(&path).unwrap();
+        assert_eq!(scenario.name, "remembers favourite language");
+        assert_eq!(scenario.messages.len(), 2);
+        assert_eq!(scenario.expect.tools_called.len(), 2);
+    }
+
+    #[test]
+    fn toml_and_yaml_scenarios_load_the_same() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let toml = write(
+            dir.path(),
+            "store.toml",
+            r#"
+messages = ["Remember I like Rust"]
+
+[provider]
+kind = "scripted"
+
+[[provider.responses]]
+tool_calls = [{ name = "memory_store", arguments = { key = "lang", content = "Rust" } }]
+
+[[provider.responses]]
+text = "Noted."
+
+[expect]
+tools_called = [{ name = "memory_store", args = { key = "lang" } }]
+final_regex = "(?i)noted"
+max_tool_iterations = 1
+"#,
+        );
+        let yaml = write(
+            dir.path(),
+            "store.yaml",
+            r#"
+messages:
+  - Remember I like Rust
+provider:
+  kind: scripted
+  responses:
+    - tool_calls:
+        - name: memory_store
+          arguments: {key: lang, content: Rust}
+    - text: Noted.
+expect:
+  tools_called:
+    - name: memory_store
+      args: {key: lang}
+  final_regex: "(?i)noted"
+  max_tool_iterations: 1
+"#,
+        );
+        write(dir.path(), "cassette.json", "{}");
+
+        let found = discover(dir.path()).unwrap();
+        assert_eq!(found, vec![toml.clone(), yaml.clone()]);
+
+        for path in found {
+            let scenario = Scenario::load(&path).unwrap();
+            assert_eq!(scenario.name, "store");
+            let ScenarioProvider::Scripted { responses } = &scenario.provider else {
+                panic!("expected scripted provider");
+            };
+            assert_eq!(responses.len(), 2);
+            assert_eq!(
+                responses[0].tool_calls[0].arguments,
+                json!({"key": "lang", "content": "Rust"})
+            );
+            assert_eq!(scenario.expect.tools_called[0].name, "memory_store");
+        }
+    }
+
+    #[test]
+    fn invalid_scenarios_are_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let empty = write(dir.path(), "empty.toml", "messages = []");
+        assert!(Scenario::load(&empty).is_err());
+        let unknown = write(
+            dir.path(),
+            "typo.toml",
+            "messages = [\"hi\"]\n[expect]\nfinal_contain = [\"x\"]",
+        );
+        assert!(Scenario::load(&unknown).is_err());
+        let regex = write(
+            dir.path(),
+            "regex.toml",
+            "messages = [\"hi\"]\n[expect]\nfinal_regex = \"(\"",
+        );
+        assert!(Scenario::load(&regex).is_err());
+    }
+
+    #[test]
+    fn check_reports_each_failed_assertion() {
+        let scenario = Scenario {
+            name: "s".into(),
+            description: String::new(),
+            messages: vec!["hi".into()],
+            provider: ScenarioProvider::default(),
+            model: None,
+            tools: None,
+            expect: Expectations {
+                tools_called: vec![ToolExpectation {
+                    name: "shell".into(),
+                    args: Some(json!({"command": "ls"})),
+                }],
+                tools_not_called: vec!["file_write".into()],
+                final_contains: vec!["done".into()],
+                final_not_contains: vec!["error".into()],
+                final_regex: Some("^done".into()),
+                judge: None,
+                memory_writes: vec![MemoryExpectation {
+                    key: Some("lang".into()),
+                    ..MemoryExpectation::default()
+                }],
+                max_tool_iterations: Some(1),
+            },
+            path: PathBuf::new(),
+        };
+        let passing = Observed {
+            replies: vec!["done, no problems".into()],
+            tool_calls: vec![ObservedToolCall {
+                name: "shell".into(),
+                arguments: json!({"command": "ls", "approved": false}),
+                success: Some(true),
+            }],
+            memory_writes: vec![ObservedMemoryWrite {
+                key: "lang".into(),
+                content: "Rust".into(),
+                category: "core".into(),
+            }],
+            tool_iterations: vec![1],
+        };
+        assert!(scenario.check(&passing).is_empty());
+
+        let failing = Observed {
+            replies: vec!["error: not done".into()],
+            tool_calls: vec![
+                ObservedToolCall {
+                    name: "shell".into(),
+                    arguments: json!({"command": "pwd"}),
+                    success: Some(true),
+                },
+                ObservedToolCall {
+                    name: "file_write".into(),
+                    arguments: json!({}),
+                    success: Some(true),
+                },
+            ],
+            memory_writes: vec![],
+            tool_iterations: vec![2],
+        };
+        let failures = scenario.check(&failing);
+        assert_eq!(failures.len(), 6, "{failures:#?}");
+        assert!(failures[0].contains(r#"saw {"command":"pwd"}"#));
+    }
+}
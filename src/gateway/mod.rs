@@ -7,10 +7,11 @@
 //! - Request timeouts (30s) to prevent slow-loris attacks
 //! - Header sanitization (handled by axum/hyper)
 
-use crate::channels::{Channel, WhatsAppChannel};
+use crate::channels::{Channel, TeamsChannel, WhatsAppChannel};
 use crate::config::Config;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::providers::{self, Provider};
+use crate::security::gateway_tokens::{TokenError, TokenScope, TokenStore};
 use crate::security::pairing::{constant_time_eq, is_public_bind, PairingGuard};
 use crate::util::truncate_with_ellipsis;
 use anyhow::Result;
@@ -26,10 +27,16 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::timeout::TimeoutLayer;
 use uuid::Uuid;
 
+pub mod tokens;
+mod webchat;
+
+pub use webchat::WebChatRuntime;
+
 /// Maximum request body size (64KB) — prevents memory exhaustion
 pub const MAX_BODY_SIZE: usize = 65_536;
 /// Request timeout (30s) — prevents slow-loris attacks
@@ -45,6 +52,10 @@ fn whatsapp_memory_key(msg: &crate::channels::traits::ChannelMessage) -> String
     format!("whatsapp_{}_{}", msg.sender, msg.id)
 }
 
+fn teams_memory_key(msg: &crate::channels::traits::ChannelMessage) -> String {
+    format!("teams_{}_{}", msg.sender, msg.id)
+}
+
 /// How often the rate limiter sweeps stale IP entries from its map.
 const RATE_LIMITER_SWEEP_INTERVAL_SECS: u64 = 300; // 5 minutes
 
@@ -156,6 +167,40 @@ impl IdempotencyStore {
     }
 }
 
+/// Bearer token from the `Authorization` header (empty when absent).
+fn bearer_token(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|auth| auth.strip_prefix("Bearer "))
+        .unwrap_or("")
+        .trim()
+}
+
+/// Check the request's bearer token for `scope`: 401 for unknown or expired
+/// tokens, 403 when the token is valid but lacks the scope.
+fn require_scope(
+    pairing: &PairingGuard,
+    headers: &HeaderMap,
+    scope: TokenScope,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    match pairing.authorize(bearer_token(headers), scope) {
+        Ok(()) => Ok(()),
+        Err(e @ TokenError::MissingScope(_)) => {
+            tracing::warn!("Gateway: rejected {scope} request — {e}");
+            let err = serde_json::json!({"error": format!("Forbidden — {e}")});
+            Err((StatusCode::FORBIDDEN, Json(err)))
+        }
+        Err(e) => {
+            tracing::warn!("Gateway: rejected {scope} request — {e}");
+            let err = serde_json::json!({
+                "error": format!("Unauthorized ({e}) — pair first via POST /pair, then send Authorization: Bearer <token>")
+            });
+            Err((StatusCode::UNAUTHORIZED, Json(err)))
+        }
+    }
+}
+
 fn client_key_from_headers(headers: &HeaderMap) -> String {
     for header_name in ["X-Forwarded-For", "X-Real-IP"] {
         if let Some(value) = headers.get(header_name).and_then(|v| v.to_str().ok()) {
@@ -183,11 +228,25 @@ pub struct AppState {
     pub whatsapp: Option<Arc<WhatsAppChannel>>,
     /// `WhatsApp` app secret for webhook signature verification (`X-Hub-Signature-256`)
     pub whatsapp_app_secret: Option<Arc<str>>,
+    pub teams: Option<Arc<TeamsChannel>>,
+    /// Agent runtime behind `/chat` (`None` when `gateway.webchat_enabled = false`)
+    pub webchat: Option<Arc<WebChatRuntime>>,
 }
 
 /// Run the HTTP gateway using axum with proper HTTP/1.1 compliance.
-#[allow(clippy::too_many_lines)]
 pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
+    run_gateway_until(host, port, config, CancellationToken::new()).await
+}
+
+/// Run the gateway until `shutdown` is cancelled, then stop accepting
+/// connections and let in-flight requests finish.
+#[allow(clippy::too_many_lines)]
+pub async fn run_gateway_until(
+    host: &str,
+    port: u16,
+    config: Config,
+    shutdown: CancellationToken,
+) -> Result<()> {
     // ── Security: refuse public bind without tunnel or explicit opt-in ──
     if is_public_bind(host) && config.tunnel.provider == "none" && !config.gateway.allow_public_bind
     {
@@ -257,10 +316,30 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         })
         .map(Arc::from);
 
+    // Microsoft Teams channel (if configured)
+    let teams_channel: Option<Arc<TeamsChannel>> = config
+        .channels_config
+        .teams
+        .as_ref()
+        .map(|tm| Arc::new(TeamsChannel::from_config(tm, &config.workspace_dir)));
+
+    // Built-in WebChat (agent with tools, per-socket history)
+    let webchat = if config.gateway.webchat_enabled {
+        Some(Arc::new(WebChatRuntime::from_config(
+            &config,
+            Arc::clone(&provider),
+            Arc::clone(&mem),
+            &model,
+            shutdown.clone(),
+        )?))
+    } else {
+        None
+    };
+
     // ── Pairing guard ──────────────────────────────────────
-    let pairing = Arc::new(PairingGuard::new(
+    let pairing = Arc::new(PairingGuard::with_store(
         config.gateway.require_pairing,
-        &config.gateway.paired_tokens,
+        TokenStore::for_config(&config)?,
     ));
     let rate_limiter = Arc::new(GatewayRateLimiter::new(
         config.gateway.pair_rate_limit_per_minute,
@@ -298,7 +377,15 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         println!("  GET  /whatsapp  — Meta webhook verification");
         println!("  POST /whatsapp  — WhatsApp message webhook");
     }
+    if teams_channel.is_some() {
+        println!("  POST /teams/messages — Microsoft Teams (Bot Framework) activities");
+    }
+    if webchat.is_some() {
+        println!("  GET  /chat      — WebChat UI (WebSocket: /chat/ws)");
+    }
     println!("  GET  /health    — health check");
+    println!("  GET  /metrics   — Prometheus metrics (metrics scope)");
+    println!("  GET  /admin/tokens — list gateway tokens (admin scope)");
     if let Some(code) = pairing.pairing_code() {
         println!();
         println!("  🔐 PAIRING REQUIRED — use this one-time code:");
@@ -309,7 +396,7 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
     } else if pairing.require_pairing() {
         println!("  🔒 Pairing: ACTIVE (bearer token required)");
     } else {
-        println!("  ⚠️  Pairing: DISABLED (all requests accepted)");
+        println!("  ⚠️  Pairing: DISABLED (/metrics and /admin still need tokens)");
     }
     if webhook_secret.is_some() {
         println!("  🔒 Webhook secret: ENABLED");
@@ -331,15 +418,22 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         idempotency_store,
         whatsapp: whatsapp_channel,
         whatsapp_app_secret,
+        teams: teams_channel,
+        webchat,
     };
 
     // Build router with middleware
     let app = Router::new()
         .route("/health", get(handle_health))
+        .route("/metrics", get(handle_metrics))
+        .route("/admin/tokens", get(handle_admin_tokens))
         .route("/pair", post(handle_pair))
         .route("/webhook", post(handle_webhook))
         .route("/whatsapp", get(handle_whatsapp_verify))
         .route("/whatsapp", post(handle_whatsapp_message))
+        .route("/teams/messages", post(handle_teams_activity))
+        .route("/chat", get(webchat::handle_webchat_page))
+        .route("/chat/ws", get(webchat::handle_webchat_ws))
         .with_state(state)
         .layer(RequestBodyLimitLayer::new(MAX_BODY_SIZE))
         .layer(TimeoutLayer::with_status_code(
@@ -348,7 +442,15 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         ));
 
     // Run the server
-    axum::serve(listener, app).await?;
+    let served = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await;
+    if let Some(ref tun) = tunnel {
+        if let Err(e) = tun.stop().await {
+            tracing::warn!("Failed to stop {} tunnel: {e}", tun.name());
+        }
+    }
+    served?;
 
     Ok(())
 }
@@ -367,6 +469,95 @@ async fn handle_health(State(state): State<AppState>) -> impl IntoResponse {
     Json(body)
 }
 
+/// GET /metrics — Prometheus text exposition of the health registry
+async fn handle_metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Err(rejection) = require_scope(&state.pairing, &headers, TokenScope::Metrics) {
+        return rejection.into_response();
+    }
+
+    use std::fmt::Write as _;
+
+    let snapshot = crate::health::snapshot();
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "# HELP zeroclaw_uptime_seconds Seconds since the process started."
+    );
+    let _ = writeln!(body, "# TYPE zeroclaw_uptime_seconds gauge");
+    let _ = writeln!(body, "zeroclaw_uptime_seconds {}", snapshot.uptime_seconds);
+    let _ = writeln!(
+        body,
+        "# HELP zeroclaw_component_up Whether a component last reported ok."
+    );
+    let _ = writeln!(body, "# TYPE zeroclaw_component_up gauge");
+    for (name, component) in &snapshot.components {
+        let up = u8::from(component.status == "ok");
+        let _ = writeln!(body, "zeroclaw_component_up{{component=\"{name}\"}} {up}");
+    }
+    let _ = writeln!(
+        body,
+        "# HELP zeroclaw_component_restarts_total Restarts per supervised component."
+    );
+    let _ = writeln!(body, "# TYPE zeroclaw_component_restarts_total counter");
+    for (name, component) in &snapshot.components {
+        let _ = writeln!(
+            body,
+            "zeroclaw_component_restarts_total{{component=\"{name}\"}} {}",
+            component.restart_count
+        );
+    }
+    let _ = writeln!(
+        body,
+        "# HELP zeroclaw_provider_circuit_open Whether a provider/model circuit breaker is open."
+    );
+    let _ = writeln!(body, "# TYPE zeroclaw_provider_circuit_open gauge");
+    for (key, health) in &snapshot.providers {
+        let open = u8::from(health.state == crate::providers::circuit::CircuitState::Open);
+        let _ = writeln!(
+            body,
+            "zeroclaw_provider_circuit_open{{breaker=\"{key}\"}} {open}"
+        );
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+/// GET /admin/tokens — token metadata (never the secrets or hashes)
+async fn handle_admin_tokens(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(rejection) = require_scope(&state.pairing, &headers, TokenScope::Admin) {
+        return rejection;
+    }
+
+    let now = chrono::Utc::now();
+    let tokens: Vec<serde_json::Value> = state
+        .pairing
+        .token_store()
+        .list()
+        .into_iter()
+        .map(|t| {
+            serde_json::json!({
+                "id": t.id,
+                "label": t.label,
+                "scopes": t.scopes,
+                "created_at": t.created_at,
+                "expires_at": t.expires_at,
+                "last_used_at": t.last_used_at,
+                "expired": t.is_expired(now),
+            })
+        })
+        .collect();
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "tokens": tokens })),
+    )
+}
+
 /// POST /pair — exchange one-time code for bearer token
 async fn handle_pair(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
     let client_key = client_key_from_headers(&headers);
@@ -434,20 +625,9 @@ async fn handle_webhook(
         return (StatusCode::TOO_MANY_REQUESTS, Json(err));
     }
 
-    // ── Bearer token auth (pairing) ──
-    if state.pairing.require_pairing() {
-        let auth = headers
-            .get(header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
-        let token = auth.strip_prefix("Bearer ").unwrap_or("");
-        if !state.pairing.is_authenticated(token) {
-            tracing::warn!("Webhook: rejected — not paired / invalid bearer token");
-            let err = serde_json::json!({
-                "error": "Unauthorized — pair first via POST /pair, then send Authorization: Bearer <token>"
-            });
-            return (StatusCode::UNAUTHORIZED, Json(err));
-        }
+    // ── Bearer token auth (pairing / scoped tokens) ──
+    if let Err(rejection) = require_scope(&state.pairing, &headers, TokenScope::Webhook) {
+        return rejection;
     }
 
     // ── Webhook secret auth (optional, additional layer) ──
@@ -686,6 +866,79 @@ async fn handle_whatsapp_message(
     (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
 }
 
+/// POST /teams/messages — Bot Framework activity endpoint
+async fn handle_teams_activity(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(teams) = state.teams.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Teams not configured"})),
+        );
+    };
+
+    let Ok(activity) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid JSON payload"})),
+        );
+    };
+
+    // ── Security: Bot Connector JWT (signature, issuer, audience, serviceUrl) ──
+    let auth = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    if let Err(e) = teams.validate_auth_header(auth, &activity).await {
+        tracing::warn!("Teams activity rejected: {e:#}");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": "Unauthorized"})),
+        );
+    }
+
+    let Some(msg) = teams.handle_activity(&activity) else {
+        // conversationUpdate, reactions, unauthorized senders, ...
+        return (StatusCode::OK, Json(serde_json::json!({"status": "ok"})));
+    };
+
+    tracing::info!(
+        "Teams message from {}: {}",
+        msg.sender,
+        truncate_with_ellipsis(&msg.content, 50)
+    );
+
+    // The connector expects a fast acknowledgement; reply out of band.
+    tokio::spawn(async move {
+        if state.auto_save {
+            let key = teams_memory_key(&msg);
+            let _ = state
+                .mem
+                .store(&key, &msg.content, MemoryCategory::Conversation)
+                .await;
+        }
+
+        let _ = teams.start_typing(&msg.sender).await;
+        let reply = match state
+            .provider
+            .simple_chat(&msg.content, &state.model, state.temperature)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!("LLM error for Teams message: {e:#}");
+                "Sorry, I couldn't process your message right now.".to_string()
+            }
+        };
+        if let Err(e) = teams.send(&reply, &msg.sender).await {
+            tracing::error!("Failed to send Teams reply: {e}");
+        }
+    });
+
+    (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -966,6 +1219,8 @@ mod tests {
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            teams: None,
+            webchat: None,
         };
 
         let mut headers = HeaderMap::new();
@@ -1014,6 +1269,8 @@ mod tests {
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            teams: None,
+            webchat: None,
         };
 
         let headers = HeaderMap::new();
@@ -1046,6 +1303,180 @@ mod tests {
         assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 2);
     }
 
+    fn teams_test_state(teams: Option<Arc<TeamsChannel>>) -> (AppState, Arc<MockProvider>) {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let state = AppState {
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: Arc::new(MockMemory),
+            auto_save: false,
+            webhook_secret: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
+            whatsapp: None,
+            whatsapp_app_secret: None,
+            teams,
+            webchat: None,
+        };
+        (state, provider_impl)
+    }
+
+    fn scoped_state(scopes: &[TokenScope]) -> (AppState, String, Arc<MockProvider>) {
+        let store = TokenStore::in_memory(&[]);
+        let (_, secret) = store.create("test", scopes, None).unwrap();
+        let (mut state, provider_impl) = teams_test_state(None);
+        state.pairing = Arc::new(PairingGuard::with_store(true, store));
+        (state, secret, provider_impl)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        headers
+    }
+
+    fn webhook_body() -> Result<Json<WebhookBody>, axum::extract::rejection::JsonRejection> {
+        Ok(Json(WebhookBody {
+            message: "hello".into(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn webhook_enforces_token_scope() {
+        let (state, secret, provider_impl) = scoped_state(&[TokenScope::Metrics]);
+        let response = handle_webhook(State(state.clone()), bearer(&secret), webhook_body())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = handle_webhook(State(state), bearer("zc_unknown"), webhook_body())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+
+        let (state, secret, provider_impl) = scoped_state(&[TokenScope::Webhook]);
+        let response = handle_webhook(State(state), bearer(&secret), webhook_body())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn webhook_rejects_expired_token() {
+        let store = TokenStore::in_memory(&[]);
+        let past = chrono::Utc::now() - chrono::Duration::hours(1);
+        let (_, secret) = store
+            .create("old", &[TokenScope::Webhook], Some(past))
+            .unwrap();
+        let (mut state, _) = teams_test_state(None);
+        state.pairing = Arc::new(PairingGuard::with_store(true, store));
+
+        let response = handle_webhook(State(state), bearer(&secret), webhook_body())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn metrics_requires_metrics_scope() {
+        let (state, secret, _) = scoped_state(&[TokenScope::Webhook]);
+        let response = handle_metrics(State(state), bearer(&secret)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        crate::health::mark_component_ok("gateway");
+        let (state, secret, _) = scoped_state(&[TokenScope::Metrics]);
+        let response = handle_metrics(State(state), bearer(&secret)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("zeroclaw_uptime_seconds"));
+        assert!(text.contains("zeroclaw_component_up{component=\"gateway\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn admin_tokens_lists_metadata_without_hashes() {
+        let (state, secret, _) = scoped_state(&[TokenScope::Chat]);
+        let response = handle_admin_tokens(State(state), bearer(&secret))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let (state, secret, _) = scoped_state(&[TokenScope::Admin]);
+        let response = handle_admin_tokens(State(state), bearer(&secret))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = &json["tokens"][0];
+        assert_eq!(token["label"], "test");
+        assert_eq!(token["scopes"][0], "admin");
+        assert!(token.get("hash").is_none());
+        assert!(!body.windows(3).any(|w| w == b"zc_"));
+    }
+
+    #[tokio::test]
+    async fn admin_and_metrics_need_tokens_when_pairing_disabled() {
+        let (mut state, _) = teams_test_state(None);
+        state.pairing = Arc::new(PairingGuard::new(false, &[]));
+
+        let response = handle_metrics(State(state.clone()), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = handle_admin_tokens(State(state), HeaderMap::new())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn teams_activity_not_configured_returns_404() {
+        let (state, _) = teams_test_state(None);
+        let response = handle_teams_activity(State(state), HeaderMap::new(), Bytes::from("{}"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn teams_activity_without_bearer_is_rejected() {
+        let teams = Arc::new(TeamsChannel::new(
+            "app-id".into(),
+            "secret".into(),
+            None,
+            vec!["*".into()],
+        ));
+        let (state, provider_impl) = teams_test_state(Some(teams));
+        let body = serde_json::json!({
+            "type": "message",
+            "serviceUrl": "https://smba.example/",
+            "channelId": "msteams",
+            "from": {"id": "29:user", "aadObjectId": "user-1"},
+            "conversation": {"id": "a:conv-1"},
+            "text": "hello",
+        });
+        let response = handle_teams_activity(
+            State(state),
+            HeaderMap::new(),
+            Bytes::from(body.to_string()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+    }
+
     // ══════════════════════════════════════════════════════════
     // WhatsApp Signature Verification Tests (CWE-345 Prevention)
     // ══════════════════════════════════════════════════════════
@@ -0,0 +1,102 @@
+//! `zeroclaw gateway tokens` — manage scoped gateway bearer tokens.
+//!
+//! Operates on the same `gateway_tokens.json` the running gateway reads, so
+//! revocations and rotations take effect on the next request without a restart.
+
+use crate::config::Config;
+use crate::security::gateway_tokens::{parse_ttl, GatewayToken, TokenScope, TokenStore};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+pub fn handle_command(cmd: crate::GatewayCommands, config: &Config) -> Result<()> {
+    let crate::GatewayCommands::Tokens { tokens_command } = cmd;
+    let store = TokenStore::for_config(config)?;
+
+    match tokens_command {
+        crate::GatewayTokenCommands::List => {
+            let tokens = store.list();
+            if tokens.is_empty() {
+                println!("No gateway tokens.");
+                println!();
+                println!("Create one with: zeroclaw gateway tokens create <label> --scope webhook");
+                return Ok(());
+            }
+            let now = Utc::now();
+            println!(
+                "{:<16} {:<24} {:<22} {:<17} {:<17} LAST USED",
+                "ID", "LABEL", "SCOPES", "CREATED", "EXPIRES"
+            );
+            for token in &tokens {
+                print_row(token, now);
+            }
+        }
+        crate::GatewayTokenCommands::Create {
+            label,
+            scopes,
+            expires_in,
+        } => {
+            let scopes = scopes
+                .iter()
+                .map(|s| s.parse::<TokenScope>())
+                .collect::<Result<Vec<_>>>()?;
+            let expires_at = expires_in
+                .as_deref()
+                .map(parse_ttl)
+                .transpose()?
+                .map(|ttl| Utc::now() + ttl);
+            let (token, secret) = store.create(&label, &scopes, expires_at)?;
+            println!("✅ Created gateway token {} ({})", token.id, token.label);
+            println!("   Scopes:  {}", format_scopes(&token.scopes));
+            println!("   Expires: {}", format_time(token.expires_at, "never"));
+            println!();
+            println!("   {secret}");
+            println!();
+            println!("   Save it now — it will not be shown again.");
+            println!("   Use it as: Authorization: Bearer <token>");
+        }
+        crate::GatewayTokenCommands::Revoke { id } => {
+            let token = store.revoke(&id)?;
+            println!("🗑️  Revoked gateway token {} ({})", token.id, token.label);
+        }
+        crate::GatewayTokenCommands::Rotate { id } => {
+            let (token, secret) = store.rotate(&id)?;
+            println!("🔄 Rotated gateway token {} ({})", token.id, token.label);
+            println!("   The previous secret no longer works.");
+            println!();
+            println!("   {secret}");
+        }
+    }
+    Ok(())
+}
+
+fn print_row(token: &GatewayToken, now: DateTime<Utc>) {
+    let expires = if token.is_expired(now) {
+        "expired".to_string()
+    } else {
+        format_time(token.expires_at, "never")
+    };
+    println!(
+        "{:<16} {:<24} {:<22} {:<17} {:<17} {}",
+        token.id,
+        crate::util::truncate_with_ellipsis(&token.label, 24),
+        format_scopes(&token.scopes),
+        format_time(Some(token.created_at), "-"),
+        expires,
+        format_time(token.last_used_at, "never"),
+    );
+}
+
+fn format_scopes(scopes: &[TokenScope]) -> String {
+    scopes
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn format_time(time: Option<DateTime<Utc>>, missing: &str) -> String {
+    time.map_or_else(
+        || missing.to_string(),
+        |t| t.format("%Y-%m-%d %H:%M").to_string(),
+    )
+}
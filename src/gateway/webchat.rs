@@ -0,0 +1,741 @@
+//! Built-in browser chat served by the gateway.
+//!
+//! - `GET /chat` — self-contained HTML page (no external assets)
+//! - `GET /chat/ws` — WebSocket running the full tool-call loop per message
+//!
+//! Clients authenticate with a pairing bearer token, either through the
+//! `Authorization` header on the upgrade request or as the first frame
+//! (`{"type":"auth","token":"..."}`), since browsers cannot set headers on
+//! WebSocket handshakes. Tool calls are streamed to the page as they run.
+
+use super::AppState;
+use crate::agent::context::ContextBudget;
+use crate::agent::loop_::run_tool_call_loop;
+use crate::config::Config;
+use crate::cost::CostTracker;
+use crate::memory::{Memory, MemoryCategory};
+use crate::observability::traits::ObserverMetric;
+use crate::observability::{self, Observer, ObserverEvent};
+use crate::providers::{ChatMessage, Provider};
+use crate::security::gateway_tokens::TokenScope;
+use crate::security::pairing::PairingGuard;
+use crate::security::SecurityPolicy;
+use crate::tools::{self, Tool};
+use crate::util::truncate_with_ellipsis;
+use anyhow::Result;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+const WEBCHAT_PAGE: &str = include_str!("webchat.html");
+/// Upper bound for one agent turn (LLM + tools), matching the channel runtime.
+const WEBCHAT_TURN_TIMEOUT_SECS: u64 = 300;
+/// Inline script/style only; the socket and `/pair` are same-origin.
+const WEBCHAT_CSP: &str = "default-src 'none'; script-src 'unsafe-inline'; \
+    style-src 'unsafe-inline'; connect-src 'self'; frame-ancestors 'none'";
+
+/// Shared agent runtime for WebChat sessions. Each socket keeps its own
+/// conversation history; provider, tools and memory are shared.
+pub struct WebChatRuntime {
+    provider: Arc<dyn Provider>,
+    memory: Arc<dyn Memory>,
+    tools_registry: Arc<Vec<Box<dyn Tool>>>,
+    observer: Arc<dyn Observer>,
+    system_prompt: String,
+    model: String,
+    temperature: f64,
+    auto_save: bool,
+    max_history_messages: usize,
+    context_budget: ContextBudget,
+    cost: Option<Arc<CostTracker>>,
+    /// Cancelled on gateway shutdown; idle sessions close, running turns finish.
+    shutdown: CancellationToken,
+}
+
+impl WebChatRuntime {
+    pub fn from_config(
+        config: &Config,
+        provider: Arc<dyn Provider>,
+        memory: Arc<dyn Memory>,
+        model: &str,
+        shutdown: CancellationToken,
+    ) -> Result<Self> {
+        let observer: Arc<dyn Observer> =
+            observability::create_shared_observer(&config.observability);
+        let runtime: Arc<dyn crate::runtime::RuntimeAdapter> =
+            Arc::from(crate::runtime::create_runtime(&config.runtime)?);
+        let security = Arc::new(SecurityPolicy::from_config(
+            &config.autonomy,
+            &config.workspace_dir,
+        ));
+        let (composio_key, composio_entity_id) = if config.composio.enabled {
+            (
+                config.composio.api_key.as_deref(),
+                Some(config.composio.entity_id.as_str()),
+            )
+        } else {
+            (None, None)
+        };
+        let tools_registry = tools::all_tools_with_runtime(
+            &security,
+            runtime,
+            Arc::clone(&memory),
+            composio_key,
+            composio_entity_id,
+            &config.browser,
+            &config.http_request,
+            &config.workspace_dir,
+            &config.agents,
+            config.api_key.as_deref(),
+            config,
+        );
+        let skills = crate::skills::load_skills_for_run(&config.workspace_dir);
+        let system_prompt =
+            crate::channels::build_runtime_system_prompt(config, model, &tools_registry, &skills);
+
+        Ok(Self {
+            provider,
+            memory,
+            tools_registry: Arc::new(tools_registry),
+            observer,
+            system_prompt,
+            model: model.to_string(),
+            temperature: config.default_temperature,
+            auto_save: config.memory.auto_save,
+            max_history_messages: config.agent.max_history_messages,
+            context_budget: ContextBudget::resolve(
+                &config.agent,
+                &config.workspace_dir,
+                config.default_provider.as_deref().unwrap_or("openrouter"),
+                model,
+            ),
+            cost: CostTracker::from_config(config),
+            shutdown,
+        })
+    }
+
+    /// Run one user message through the tool-call loop, streaming reply text
+    /// and progress frames to `events`, and return the final answer.
+    async fn turn(
+        &self,
+        history: &mut Vec<ChatMessage>,
+        content: &str,
+        events: &mpsc::UnboundedSender<serde_json::Value>,
+    ) -> Result<String> {
+        let memory_context = self.context_budget.fit_memory_context(
+            crate::channels::build_memory_context(self.memory.as_ref(), content).await,
+        );
+        if self.auto_save {
+            let key = format!("webchat_{}", Uuid::new_v4());
+            let _ = self
+                .memory
+                .store(&key, content, MemoryCategory::Conversation)
+                .await;
+        }
+        history.push(ChatMessage::user(format!("{memory_context}{content}")));
+
+        let observer = WebChatObserver {
+            inner: Arc::clone(&self.observer),
+            events: events.clone(),
+        };
+        let on_delta = |text: &str| {
+            let _ = events.send(serde_json::json!({"type": "delta", "content": text}));
+        };
+        let result = tokio::time::timeout(
+            Duration::from_secs(WEBCHAT_TURN_TIMEOUT_SECS),
+            run_tool_call_loop(
+                self.provider.as_ref(),
+                history,
+                self.tools_registry.as_ref(),
+                &observer,
+                "webchat",
+                &self.model,
+                self.temperature,
+                true,
+                &self.context_budget,
+                self.cost.as_deref(),
+                Some(&on_delta),
+            ),
+        )
+        .await;
+        trim_history(history, self.max_history_messages);
+
+        match result {
+            Ok(result) => result,
+            Err(_) => anyhow::bail!(
+                "Request timed out after {WEBCHAT_TURN_TIMEOUT_SECS}s — try a simpler request"
+            ),
+        }
+    }
+}
+
+/// Keep the system prompt plus the most recent `max` messages.
+fn trim_history(history: &mut Vec<ChatMessage>, max: usize) {
+    if history.len() <= max + 1 {
+        return;
+    }
+    let excess = history.len() - 1 - max;
+    history.drain(1..=excess);
+}
+
+/// Forwards every event to the configured observer and mirrors LLM/tool
+/// progress to the socket. Tool arguments and outputs are not sent.
+struct WebChatObserver {
+    inner: Arc<dyn Observer>,
+    events: mpsc::UnboundedSender<serde_json::Value>,
+}
+
+impl Observer for WebChatObserver {
+    fn record_event(&self, event: &ObserverEvent) {
+        self.inner.record_event(event);
+        let frame = match event {
+            ObserverEvent::LlmRequest { .. } => serde_json::json!({"type": "thinking"}),
+            ObserverEvent::ToolCallStart { tool } => {
+                serde_json::json!({"type": "tool_start", "tool": tool})
+            }
+            ObserverEvent::ToolCall {
+                tool,
+                duration,
+                success,
+            } => serde_json::json!({
+                "type": "tool_end",
+                "tool": tool,
+                "success": success,
+                "duration_ms": u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            }),
+            _ => return,
+        };
+        let _ = self.events.send(frame);
+    }
+
+    fn record_metric(&self, metric: &ObserverMetric) {
+        self.inner.record_metric(metric);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+
+    fn name(&self) -> &str {
+        "webchat"
+    }
+}
+
+/// Frames sent by the browser.
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientFrame {
+    Auth { token: String },
+    Message { content: String },
+    Reset,
+}
+
+fn error_frame(code: &str, message: &str) -> serde_json::Value {
+    serde_json::json!({"type": "error", "code": code, "message": message})
+}
+
+/// GET /chat — the WebChat page (public; it carries no secrets)
+pub(super) async fn handle_webchat_page(State(state): State<AppState>) -> Response {
+    if state.webchat.is_none() {
+        return (StatusCode::NOT_FOUND, "WebChat is disabled").into_response();
+    }
+    (
+        [
+            (header::CONTENT_SECURITY_POLICY, WEBCHAT_CSP),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        Html(WEBCHAT_PAGE),
+    )
+        .into_response()
+}
+
+/// Browsers send `Origin` on every WebSocket upgrade but do not apply CORS
+/// to it, so a page on another site could open a session with the user's
+/// network position. Only upgrades from the gateway's own origin (or from
+/// non-browser clients, which send no `Origin`) are accepted.
+fn same_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    let Some(host) = headers.get(header::HOST).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    origin
+        .split_once("://")
+        .map_or(origin, |(_, rest)| rest)
+        .trim_end_matches('/')
+        .eq_ignore_ascii_case(host)
+}
+
+/// GET /chat/ws — WebSocket upgrade for a chat session
+pub(super) async fn handle_webchat_ws(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let Some(runtime) = state.webchat.clone() else {
+        return (StatusCode::NOT_FOUND, "WebChat is disabled").into_response();
+    };
+    if !same_origin(&headers) {
+        tracing::warn!("WebChat: rejected cross-origin upgrade");
+        return (StatusCode::FORBIDDEN, "Cross-origin WebSocket rejected").into_response();
+    }
+
+    let header_token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|auth| auth.strip_prefix("Bearer "));
+    let authenticated = match header_token {
+        Some(token) if state.pairing.authorize(token, TokenScope::Chat).is_err() => {
+            tracing::warn!("WebChat: rejected upgrade — invalid bearer token");
+            return (StatusCode::UNAUTHORIZED, "Invalid bearer token").into_response();
+        }
+        Some(_) => true,
+        None => !state.pairing.require_pairing(),
+    };
+
+    let pairing = Arc::clone(&state.pairing);
+    ws.on_upgrade(move |socket| run_session(socket, runtime, pairing, authenticated))
+}
+
+async fn run_session(
+    socket: WebSocket,
+    runtime: Arc<WebChatRuntime>,
+    pairing: Arc<PairingGuard>,
+    mut authenticated: bool,
+) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<serde_json::Value>();
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = rx.recv().await {
+            if sink
+                .send(Message::Text(frame.to_string().into()))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let ready = |authenticated: bool| serde_json::json!({"type": "ready", "authenticated": authenticated, "model": runtime.model});
+    let _ = tx.send(ready(authenticated));
+    let mut history = vec![ChatMessage::system(runtime.system_prompt.as_str())];
+
+    loop {
+        let message = tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(message)) => message,
+                _ => break,
+            },
+            () = runtime.shutdown.cancelled() => {
+                let _ = tx.send(error_frame("shutting_down", "Server is shutting down"));
+                break;
+            }
+        };
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let frame = match serde_json::from_str::<ClientFrame>(text.as_str()) {
+            Ok(frame) => frame,
+            Err(e) => {
+                let _ = tx.send(error_frame("bad_frame", &format!("Invalid frame: {e}")));
+                continue;
+            }
+        };
+
+        match frame {
+            ClientFrame::Auth { token } => {
+                if let Err(e) = pairing.authorize(token.trim(), TokenScope::Chat) {
+                    tracing::warn!("WebChat: rejected session — {e}");
+                    let _ = tx.send(error_frame("unauthorized", &format!("Unauthorized — {e}")));
+                    break;
+                }
+                authenticated = true;
+                let _ = tx.send(ready(true));
+            }
+            _ if !authenticated => {
+                let _ = tx.send(error_frame(
+                    "unauthorized",
+                    "Unauthorized — pair via POST /pair, then send {\"type\":\"auth\",\"token\":\"...\"}",
+                ));
+                break;
+            }
+            ClientFrame::Message { content } => {
+                let content = content.trim();
+                if content.is_empty() {
+                    continue;
+                }
+                tracing::info!("WebChat message: {}", truncate_with_ellipsis(content, 50));
+                match runtime.turn(&mut history, content, &tx).await {
+                    Ok(response) => {
+                        let _ =
+                            tx.send(serde_json::json!({"type": "response", "content": response}));
+                    }
+                    Err(e) => {
+                        let error = crate::providers::sanitize_api_error(&e.to_string());
+                        tracing::error!("WebChat turn failed: {error}");
+                        let _ = tx.send(error_frame("agent_error", &format!("⚠️ {error}")));
+                    }
+                }
+            }
+            ClientFrame::Reset => {
+                history.truncate(1);
+                let _ = tx.send(serde_json::json!({"type": "reset"}));
+            }
+        }
+    }
+
+    drop(tx);
+    let _ = writer.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::{GatewayRateLimiter, IdempotencyStore};
+    use crate::memory::MemoryEntry;
+    use crate::tools::ToolResult;
+    use async_trait::async_trait;
+    use axum::routing::get;
+    use axum::Router;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    /// First reply calls the `echo` tool, second reply is the final answer.
+    struct ScriptedProvider;
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok("ok".into())
+        }
+
+        async fn chat_with_history(
+            &self,
+            messages: &[ChatMessage],
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            let last = messages.last().map(|m| m.content.as_str()).unwrap_or("");
+            if last.starts_with("[Tool results]") {
+                Ok(format!("done after {} messages", messages.len()))
+            } else {
+                Ok("<tool_call>\n{\"name\": \"echo\", \"arguments\": {}}\n</tool_call>".into())
+            }
+        }
+    }
+
+    struct EchoTool;
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "echo"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(&self, _args: serde_json::Value) -> anyhow::Result<ToolResult> {
+            Ok(ToolResult {
+                success: true,
+                output: "echoed".into(),
+                error: None,
+            })
+        }
+    }
+
+    struct NoMemory;
+
+    #[async_trait]
+    impl Memory for NoMemory {
+        fn name(&self) -> &str {
+            "none"
+        }
+
+        async fn store(
+            &self,
+            _key: &str,
+            _content: &str,
+            _category: MemoryCategory,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn recall(&self, _query: &str, _limit: usize) -> anyhow::Result<Vec<MemoryEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn get(&self, _key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+            Ok(None)
+        }
+
+        async fn list(
+            &self,
+            _category: Option<&MemoryCategory>,
+        ) -> anyhow::Result<Vec<MemoryEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn forget(&self, _key: &str) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        async fn count(&self) -> anyhow::Result<usize> {
+            Ok(0)
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+    }
+
+    fn runtime() -> WebChatRuntime {
+        WebChatRuntime {
+            provider: Arc::new(ScriptedProvider),
+            memory: Arc::new(NoMemory),
+            tools_registry: Arc::new(vec![Box::new(EchoTool) as Box<dyn Tool>]),
+            observer: Arc::new(crate::observability::NoopObserver),
+            system_prompt: "system".into(),
+            model: "test-model".into(),
+            temperature: 0.0,
+            auto_save: false,
+            max_history_messages: 50,
+            context_budget: ContextBudget::for_window(32_768),
+            cost: None,
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    async fn spawn_gateway(pairing: PairingGuard) -> String {
+        let state = AppState {
+            provider: Arc::new(ScriptedProvider),
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: Arc::new(NoMemory),
+            auto_save: false,
+            webhook_secret: None,
+            pairing: Arc::new(pairing),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
+            whatsapp: None,
+            whatsapp_app_secret: None,
+            teams: None,
+            webchat: Some(Arc::new(runtime())),
+        };
+        let app = Router::new()
+            .route("/chat", get(handle_webchat_page))
+            .route("/chat/ws", get(handle_webchat_ws))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("{addr}")
+    }
+
+    async fn next_frame<S>(stream: &mut S) -> serde_json::Value
+    where
+        S: futures_util::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>>
+            + Unpin,
+    {
+        loop {
+            match stream.next().await.unwrap().unwrap() {
+                WsMessage::Text(text) => return serde_json::from_str(&text).unwrap(),
+                WsMessage::Close(_) => return serde_json::json!({"type": "closed"}),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn trim_history_keeps_system_prompt() {
+        let mut history = vec![ChatMessage::system("sys")];
+        for i in 0..10 {
+            history.push(ChatMessage::user(format!("m{i}")));
+        }
+        trim_history(&mut history, 4);
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[0].role, "system");
+        assert_eq!(history[1].content, "m6");
+    }
+
+    #[test]
+    fn client_frames_parse() {
+        let auth: ClientFrame = serde_json::from_str(r#"{"type":"auth","token":"t"}"#).unwrap();
+        assert!(matches!(auth, ClientFrame::Auth { token } if token == "t"));
+        let msg: ClientFrame =
+            serde_json::from_str(r#"{"type":"message","content":"hi"}"#).unwrap();
+        assert!(matches!(msg, ClientFrame::Message { content } if content == "hi"));
+        assert!(serde_json::from_str::<ClientFrame>(r#"{"type":"nope"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn turn_streams_tool_events_and_returns_answer() {
+        let rt = runtime();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut history = vec![ChatMessage::system("system")];
+        let answer = rt.turn(&mut history, "hello", &tx).await.unwrap();
+        assert!(answer.starts_with("done after"));
+
+        let mut frames = Vec::new();
+        while let Ok(frame) = rx.try_recv() {
+            frames.push(frame);
+        }
+        let kinds: Vec<_> = frames.iter().map(|f| f["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            kinds,
+            ["thinking", "tool_start", "tool_end", "thinking", "delta"]
+        );
+        // Tool-call markup is not streamed; the answer text is
+        assert_eq!(frames[4]["content"], answer.as_str());
+        // Conversation persists for the next turn
+        assert!(history.len() > 2);
+    }
+
+    #[tokio::test]
+    async fn page_is_served_with_csp() {
+        let addr = spawn_gateway(PairingGuard::new(false, &[])).await;
+        let resp = reqwest::get(format!("http://{addr}/chat")).await.unwrap();
+        assert!(resp.status().is_success());
+        let csp = resp.headers()[header::CONTENT_SECURITY_POLICY]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(csp.contains("frame-ancestors 'none'"));
+        assert!(resp.text().await.unwrap().contains("/chat/ws"));
+    }
+
+    #[tokio::test]
+    async fn socket_requires_auth_frame_when_pairing_enabled() {
+        let addr = spawn_gateway(PairingGuard::new(true, &["zc_valid".into()])).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/chat/ws"))
+            .await
+            .unwrap();
+
+        let ready = next_frame(&mut socket).await;
+        assert_eq!(ready["authenticated"], false);
+
+        socket
+            .send(WsMessage::Text(
+                r#"{"type":"message","content":"hi"}"#.into(),
+            ))
+            .await
+            .unwrap();
+        let err = next_frame(&mut socket).await;
+        assert_eq!(err["type"], "error");
+        assert_eq!(err["code"], "unauthorized");
+    }
+
+    #[tokio::test]
+    async fn socket_rejects_cross_origin_upgrade() {
+        let addr = spawn_gateway(PairingGuard::new(false, &[])).await;
+        let request = |origin: String| {
+            let mut request =
+                tokio_tungstenite::tungstenite::client::IntoClientRequest::into_client_request(
+                    format!("ws://{addr}/chat/ws"),
+                )
+                .unwrap();
+            request
+                .headers_mut()
+                .insert(header::ORIGIN, origin.parse().unwrap());
+            request
+        };
+        assert!(
+            tokio_tungstenite::connect_async(request("https://evil.example".into()))
+                .await
+                .is_err()
+        );
+        assert!(
+            tokio_tungstenite::connect_async(request(format!("http://{addr}")))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn socket_rejects_invalid_header_token() {
+        let addr = spawn_gateway(PairingGuard::new(true, &["zc_valid".into()])).await;
+        let mut request =
+            tokio_tungstenite::tungstenite::client::IntoClientRequest::into_client_request(
+                format!("ws://{addr}/chat/ws"),
+            )
+            .unwrap();
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert!(tokio_tungstenite::connect_async(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn socket_chat_round_trip_after_auth() {
+        let addr = spawn_gateway(PairingGuard::new(true, &["zc_valid".into()])).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/chat/ws"))
+            .await
+            .unwrap();
+        assert_eq!(next_frame(&mut socket).await["authenticated"], false);
+
+        socket
+            .send(WsMessage::Text(
+                r#"{"type":"auth","token":"zc_valid"}"#.into(),
+            ))
+            .await
+            .unwrap();
+        let ready = next_frame(&mut socket).await;
+        assert_eq!(ready["authenticated"], true);
+        assert_eq!(ready["model"], "test-model");
+
+        socket
+            .send(WsMessage::Text(
+                r#"{"type":"message","content":"hello"}"#.into(),
+            ))
+            .await
+            .unwrap();
+        let mut frames = Vec::new();
+        loop {
+            let frame = next_frame(&mut socket).await;
+            let done = frame["type"] == "response";
+            frames.push(frame);
+            if done {
+                break;
+            }
+        }
+        assert!(frames
+            .iter()
+            .any(|f| f["type"] == "tool_start" && f["tool"] == "echo"));
+        assert!(frames
+            .iter()
+            .any(|f| f["type"] == "tool_end" && f["success"] == true));
+        let response = frames.last().unwrap();
+        assert!(response["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("done after"));
+    }
+}
@@ -53,7 +53,7 @@ pub fn discover_hardware() -> Vec<DiscoveredDevice> {
 }
 
 /// Return the recommended default wizard choice index based on discovered devices.
-/// 0 = Native, 1 = Tethered/Serial, 2 = Debug Probe, 3 = Software Only
+/// 0 = Native, 1 = Tethered/Serial, 2 = Debug Probe, 3 = Software Only, 4 = Networked
 pub fn recommended_wizard_default(devices: &[DiscoveredDevice]) -> usize {
     if devices.is_empty() {
         3 // software only
@@ -62,7 +62,7 @@ pub fn recommended_wizard_default(devices: &[DiscoveredDevice]) -> usize {
     }
 }
 
-/// Build a `HardwareConfig` from the wizard menu choice (0–3) and discovered devices.
+/// Build a `HardwareConfig` from the wizard menu choice (0–4) and discovered devices.
 pub fn config_from_wizard_choice(choice: usize, devices: &[DiscoveredDevice]) -> HardwareConfig {
     match choice {
         0 => HardwareConfig {
@@ -87,6 +87,11 @@ pub fn config_from_wizard_choice(choice: usize, devices: &[DiscoveredDevice]) ->
             transport: HardwareTransport::Probe,
             ..HardwareConfig::default()
         },
+        4 => HardwareConfig {
+            enabled: true,
+            transport: HardwareTransport::Network,
+            ..HardwareConfig::default()
+        },
         _ => HardwareConfig::default(), // software only
     }
 }
@@ -1,6 +1,8 @@
+use crate::providers::circuit::ProviderHealth;
 use chrono::Utc;
 use serde::Serialize;
 use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::{Mutex, OnceLock};
 use std::time::Instant;
 
@@ -11,6 +13,9 @@ pub struct ComponentHealth {
     pub last_ok: Option<String>,
     pub last_error: Option<String>,
     pub restart_count: u64,
+    /// Outcome of the most recent config reload that touched this component
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_reload: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -19,6 +24,8 @@ pub struct HealthSnapshot {
     pub updated_at: String,
     pub uptime_seconds: u64,
     pub components: BTreeMap<String, ComponentHealth>,
+    /// Circuit breaker state by `provider/model`.
+    pub providers: BTreeMap<String, ProviderHealth>,
 }
 
 struct HealthRegistry {
@@ -53,6 +60,7 @@ where
                 last_ok: None,
                 last_error: None,
                 restart_count: 0,
+                last_reload: None,
             });
         update(entry);
         entry.updated_at = now;
@@ -76,12 +84,27 @@ pub fn mark_component_error(component: &str, error: impl ToString) {
     });
 }
 
+/// Mark a component as deliberately stopped (daemon shutdown), not failed.
+pub fn mark_component_stopped(component: &str) {
+    upsert_component(component, |entry| {
+        entry.status = "stopped".into();
+    });
+}
+
 pub fn bump_component_restart(component: &str) {
     upsert_component(component, |entry| {
         entry.restart_count = entry.restart_count.saturating_add(1);
     });
 }
 
+/// Record what a config reload did to a component (applied live, restarted, rejected).
+pub fn record_component_reload(component: &str, outcome: impl Into<String>) {
+    let outcome = format!("{} {}", now_rfc3339(), outcome.into());
+    upsert_component(component, move |entry| {
+        entry.last_reload = Some(outcome);
+    });
+}
+
 pub fn snapshot() -> HealthSnapshot {
     let components = registry()
         .components
@@ -93,6 +116,7 @@ pub fn snapshot() -> HealthSnapshot {
         updated_at: now_rfc3339(),
         uptime_seconds: registry().started_at.elapsed().as_secs(),
         components,
+        providers: crate::providers::circuit::shared().snapshot(),
     }
 }
 
@@ -105,6 +129,49 @@ pub fn snapshot_json() -> serde_json::Value {
     })
 }
 
+/// Provider health lines for `zeroclaw status`, read from the daemon's
+/// state file at `state_file`.
+pub fn provider_status_lines(state_file: &Path) -> Vec<String> {
+    let Some(snapshot) = std::fs::read_to_string(state_file)
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+    else {
+        return vec!["daemon not running (no state file)".into()];
+    };
+    let providers: BTreeMap<String, ProviderHealth> = snapshot
+        .get("providers")
+        .cloned()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    if providers.is_empty() {
+        return vec!["no provider calls recorded yet".into()];
+    }
+
+    providers
+        .iter()
+        .map(|(key, health)| {
+            use std::fmt::Write as _;
+
+            let mut line = format!("{key}: {} (score {:.2}", health.state, health.score);
+            if let Some(latency) = health.latency_ms {
+                let _ = write!(line, ", {latency}ms");
+            }
+            if health.consecutive_failures > 0 {
+                let _ = write!(line, ", {} failures", health.consecutive_failures);
+            }
+            line.push(')');
+            if let Some(error) = health
+                .last_error
+                .as_deref()
+                .filter(|_| health.consecutive_failures > 0)
+            {
+                let _ = write!(line, " — {error}");
+            }
+            line
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,6 +237,54 @@ mod tests {
         assert_eq!(entry.restart_count, 2);
     }
 
+    #[test]
+    fn record_component_reload_keeps_status_and_sets_outcome() {
+        let component = unique_component("health-reload");
+
+        mark_component_ok(&component);
+        record_component_reload(&component, "restarted to apply [gateway]");
+
+        let snapshot = snapshot();
+        let entry = snapshot
+            .components
+            .get(&component)
+            .expect("component should exist after reload");
+        assert_eq!(entry.status, "ok");
+        assert!(entry
+            .last_reload
+            .as_deref()
+            .unwrap()
+            .ends_with("restarted to apply [gateway]"));
+    }
+
+    #[test]
+    fn provider_status_lines_read_breakers_from_state_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("daemon_state.json");
+        assert_eq!(
+            provider_status_lines(&path),
+            vec!["daemon not running (no state file)"]
+        );
+
+        let registry = crate::providers::circuit::CircuitRegistry::new();
+        let settings = crate::providers::circuit::BreakerSettings {
+            failure_threshold: 1,
+            open_duration: std::time::Duration::from_secs(30),
+        };
+        registry.record_success("openai", "gpt-4o", std::time::Duration::from_millis(800));
+        registry.record_failure("anthropic", "claude", "503 overloaded", &settings);
+        let state = serde_json::json!({ "providers": registry.snapshot() });
+        std::fs::write(&path, state.to_string()).unwrap();
+
+        let lines = provider_status_lines(&path);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "anthropic/claude: open (score 0.80, 1 failures) — 503 overloaded"
+        );
+        assert_eq!(lines[1], "openai/gpt-4o: closed (score 1.00, 800ms)");
+    }
+
     #[test]
     fn snapshot_json_contains_registered_component_fields() {
         let component = unique_component("health-json");
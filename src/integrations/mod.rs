@@ -69,10 +69,64 @@ pub struct IntegrationEntry {
 /// Handle the `integrations` CLI command
 pub fn handle_command(command: crate::IntegrationCommands, config: &Config) -> Result<()> {
     match command {
+        crate::IntegrationCommands::List => {
+            list_integrations(config);
+            Ok(())
+        }
         crate::IntegrationCommands::Info { name } => show_integration_info(config, &name),
     }
 }
 
+fn status_icon(status: IntegrationStatus) -> (&'static str, &'static str) {
+    match status {
+        IntegrationStatus::Active => ("✅", "Active"),
+        IntegrationStatus::Available => ("⚪", "Available"),
+        IntegrationStatus::ComingSoon => ("🔜", "Coming Soon"),
+    }
+}
+
+fn list_integrations(config: &Config) {
+    let entries = registry::all_integrations();
+    for category in IntegrationCategory::all() {
+        println!();
+        println!("  {}", console::style(category.label()).white().bold());
+        for entry in entries.iter().filter(|e| e.category == *category) {
+            let (icon, _) = status_icon((entry.status_fn)(config));
+            println!("    {icon} {:<16} {}", entry.name, entry.description);
+        }
+    }
+    println!();
+    print_mcp_servers(config);
+}
+
+/// One line per `[[mcp.servers]]` entry.
+fn print_mcp_servers(config: &Config) {
+    if config.mcp.servers.is_empty() {
+        println!("  No MCP servers configured. Add one under [[mcp.servers]] in config.toml.");
+        println!();
+        return;
+    }
+    println!("  {}", console::style("MCP Servers").white().bold());
+    for server in &config.mcp.servers {
+        let icon = if server.enabled { "✅" } else { "⏸️ " };
+        let target = match server.transport.as_str() {
+            "http" => server.url.clone().unwrap_or_default(),
+            _ => std::iter::once(server.command.clone().unwrap_or_default())
+                .chain(server.args.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        println!(
+            "    {icon} {:<16} {} {target}",
+            server.name, server.transport
+        );
+        if !server.allowed_tools.is_empty() {
+            println!("       tools: {}", server.allowed_tools.join(", "));
+        }
+    }
+    println!();
+}
+
 fn show_integration_info(config: &Config, name: &str) -> Result<()> {
     let entries = registry::all_integrations();
     let name_lower = name.to_lowercase();
@@ -84,11 +138,7 @@ fn show_integration_info(config: &Config, name: &str) -> Result<()> {
     };
 
     let status = (entry.status_fn)(config);
-    let (icon, label) = match status {
-        IntegrationStatus::Active => ("✅", "Active"),
-        IntegrationStatus::Available => ("⚪", "Available"),
-        IntegrationStatus::ComingSoon => ("🔜", "Coming Soon"),
-    };
+    let (icon, label) = status_icon(status);
 
     println!();
     println!(
@@ -155,6 +205,18 @@ fn show_integration_info(config: &Config, name: &str) -> Result<()> {
             println!("    Schedule tasks in ~/.zeroclaw/workspace/cron/");
             println!("    Run: zeroclaw cron list");
         }
+        "MCP" => {
+            println!("  Setup:");
+            println!("    Add servers to config.toml; their tools appear as mcp_<server>_<tool>:");
+            println!("      [[mcp.servers]]");
+            println!("      name = \"github\"");
+            println!("      command = \"npx\"");
+            println!("      args = [\"-y\", \"@modelcontextprotocol/server-github\"]");
+            println!("      env = {{ GITHUB_PERSONAL_ACCESS_TOKEN = \"env:GITHUB_TOKEN\" }}");
+            println!("    HTTP servers use transport = \"http\" and url = \"https://…/mcp\".");
+            println!();
+            print_mcp_servers(config);
+        }
         "Webhooks" => {
             println!("  Built-in:");
             println!("    HTTP endpoint for external triggers.");
@@ -210,6 +272,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn mcp_integration_is_active_once_a_server_is_enabled() {
+        let mut config = Config::default();
+        let entries = registry::all_integrations();
+        let mcp = entries.iter().find(|e| e.name == "MCP").unwrap();
+        assert_eq!((mcp.status_fn)(&config), IntegrationStatus::Available);
+
+        config.mcp = toml::from_str("[[servers]]\nname = \"fs\"\ncommand = \"mcp-fs\"").unwrap();
+        assert_eq!((mcp.status_fn)(&config), IntegrationStatus::Active);
+        config.mcp.servers[0].enabled = false;
+        assert_eq!((mcp.status_fn)(&config), IntegrationStatus::Available);
+
+        assert!(handle_command(crate::IntegrationCommands::List, &config).is_ok());
+    }
+
     #[test]
     fn handle_command_info_returns_error_for_unknown_integration() {
         let config = Config::default();
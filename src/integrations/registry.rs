@@ -69,7 +69,13 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
             name: "Signal",
             description: "Privacy-focused via signal-cli",
             category: IntegrationCategory::Chat,
-            status_fn: |_| IntegrationStatus::ComingSoon,
+            status_fn: |c| {
+                if c.channels_config.signal.is_some() {
+                    IntegrationStatus::Active
+                } else {
+                    IntegrationStatus::Available
+                }
+            },
         },
         IntegrationEntry {
             name: "iMessage",
@@ -85,9 +91,15 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
         },
         IntegrationEntry {
             name: "Microsoft Teams",
-            description: "Enterprise chat support",
+            description: "Enterprise chat via Bot Framework",
             category: IntegrationCategory::Chat,
-            status_fn: |_| IntegrationStatus::ComingSoon,
+            status_fn: |c| {
+                if c.channels_config.teams.is_some() {
+                    IntegrationStatus::Active
+                } else {
+                    IntegrationStatus::Available
+                }
+            },
         },
         IntegrationEntry {
             name: "Matrix",
@@ -109,9 +121,15 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
         },
         IntegrationEntry {
             name: "WebChat",
-            description: "Browser-based chat UI",
+            description: "Browser chat UI served by the gateway",
             category: IntegrationCategory::Chat,
-            status_fn: |_| IntegrationStatus::ComingSoon,
+            status_fn: |c| {
+                if c.gateway.webchat_enabled {
+                    IntegrationStatus::Active
+                } else {
+                    IntegrationStatus::Available
+                }
+            },
         },
         IntegrationEntry {
             name: "Nextcloud Talk",
@@ -529,7 +547,13 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
             name: "Home Assistant",
             description: "Home automation hub",
             category: IntegrationCategory::SmartHome,
-            status_fn: |_| IntegrationStatus::ComingSoon,
+            status_fn: |c| {
+                if c.home_assistant.enabled {
+                    IntegrationStatus::Active
+                } else {
+                    IntegrationStatus::Available
+                }
+            },
         },
         IntegrationEntry {
             name: "Philips Hue",
@@ -568,6 +592,18 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
             category: IntegrationCategory::ToolsAutomation,
             status_fn: |_| IntegrationStatus::Available,
         },
+        IntegrationEntry {
+            name: "MCP",
+            description: "Model Context Protocol servers",
+            category: IntegrationCategory::ToolsAutomation,
+            status_fn: |c| {
+                if c.mcp.has_enabled_servers() {
+                    IntegrationStatus::Active
+                } else {
+                    IntegrationStatus::Available
+                }
+            },
+        },
         IntegrationEntry {
             name: "Voice",
             description: "Voice wake + talk mode",
@@ -691,7 +727,9 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::schema::{IMessageConfig, MatrixConfig, TelegramConfig};
+    use crate::config::schema::{
+        IMessageConfig, MatrixConfig, SignalConfig, TeamsConfig, TelegramConfig,
+    };
     use crate::config::Config;
 
     #[test]
@@ -801,6 +839,11 @@ mod tests {
             access_token: "tok".into(),
             room_id: "!r:m".into(),
             allowed_users: vec![],
+            rooms: vec![],
+            auto_join: true,
+            thread_replies: true,
+            e2ee: false,
+            store_dir: None,
         });
         let entries = all_integrations();
         let mx = entries.iter().find(|e| e.name == "Matrix").unwrap();
@@ -822,7 +865,7 @@ mod tests {
     fn coming_soon_integrations_stay_coming_soon() {
         let config = Config::default();
         let entries = all_integrations();
-        for name in ["Signal", "Nostr", "Spotify", "Home Assistant"] {
+        for name in ["Nostr", "Spotify"] {
             let entry = entries.iter().find(|e| e.name == name).unwrap();
             assert!(
                 matches!((entry.status_fn)(&config), IntegrationStatus::ComingSoon),
@@ -831,6 +874,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn home_assistant_active_when_enabled() {
+        let mut config = Config::default();
+        let entries = all_integrations();
+        let ha = entries.iter().find(|e| e.name == "Home Assistant").unwrap();
+        assert!(matches!(
+            (ha.status_fn)(&config),
+            IntegrationStatus::Available
+        ));
+        config.home_assistant.enabled = true;
+        assert!(matches!((ha.status_fn)(&config), IntegrationStatus::Active));
+    }
+
+    #[test]
+    fn signal_active_when_configured() {
+        let mut config = Config::default();
+        let entries = all_integrations();
+        let sg = entries.iter().find(|e| e.name == "Signal").unwrap();
+        assert!(matches!(
+            (sg.status_fn)(&config),
+            IntegrationStatus::Available
+        ));
+
+        config.channels_config.signal = Some(SignalConfig {
+            endpoint: "http://127.0.0.1:8080".into(),
+            account: None,
+            allowed_users: vec!["+15551234567".into()],
+            allowed_groups: vec![],
+            attachments_dir: None,
+        });
+        assert!(matches!((sg.status_fn)(&config), IntegrationStatus::Active));
+    }
+
+    #[test]
+    fn teams_active_when_configured() {
+        let mut config = Config::default();
+        let entries = all_integrations();
+        let teams = entries
+            .iter()
+            .find(|e| e.name == "Microsoft Teams")
+            .unwrap();
+        assert!(matches!(
+            (teams.status_fn)(&config),
+            IntegrationStatus::Available
+        ));
+
+        config.channels_config.teams = Some(TeamsConfig {
+            app_id: "app-id".into(),
+            app_password: "secret".into(),
+            tenant_id: None,
+            allowed_users: vec!["*".into()],
+        });
+        assert!(matches!(
+            (teams.status_fn)(&config),
+            IntegrationStatus::Active
+        ));
+    }
+
+    #[test]
+    fn webchat_follows_gateway_toggle() {
+        let mut config = Config::default();
+        let entries = all_integrations();
+        let webchat = entries.iter().find(|e| e.name == "WebChat").unwrap();
+        assert!(matches!(
+            (webchat.status_fn)(&config),
+            IntegrationStatus::Active
+        ));
+
+        config.gateway.webchat_enabled = false;
+        assert!(matches!(
+            (webchat.status_fn)(&config),
+            IntegrationStatus::Available
+        ));
+    }
+
     #[test]
     fn whatsapp_available_when_not_configured() {
         let config = Config::default();
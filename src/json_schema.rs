@@ -0,0 +1,629 @@
+//! Validation against the JSON Schema subset that tool specs and structured
+//! responses use.
+//!
+//! Covers `type`, `enum`, `const`, string/number/array/object bounds,
+//! `pattern`, `allOf`/`anyOf`/`oneOf`/`not`, OpenAPI `nullable` and local
+//! `$ref`s (`#/$defs/..`, `#/definitions/..`) as emitted by schemars.
+//! Unknown keywords are ignored rather than rejected.
+//!
+//! [`coerce`] repairs the type slips models commonly make in tool arguments
+//! before they are validated.
+
+use serde_json::Value;
+use std::fmt;
+
+/// Nesting limit for `$ref` chains and deeply nested values.
+const MAX_DEPTH: usize = 64;
+
+/// One way a value fails its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value (`""` is the root).
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Check `value` against `schema`, returning every violation found.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), Vec<SchemaError>> {
+    let mut validator = Validator {
+        root: schema,
+        errors: Vec::new(),
+    };
+    validator.check(schema, value, "", 0);
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+/// Resolve a local `$ref` against `root`.
+pub fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        Some(root)
+    } else {
+        root.pointer(pointer)
+    }
+}
+
+/// The JSON Schema type name of `value`.
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Whether `value` is an instance of the JSON Schema type `ty`.
+pub fn is_type(ty: &str, value: &Value) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "number" => value.is_number(),
+        "integer" => match value {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        _ => true,
+    }
+}
+
+/// The `type` keyword as a list (empty when absent).
+pub fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Fix obvious type mismatches in place: numeric and boolean strings,
+/// stringified JSON objects and arrays, numbers and booleans where a string
+/// is expected, whole floats for integers, a lone value where an array is
+/// expected, `null` for an object, and `null` optional properties (dropped).
+/// Anything else is left for [`validate`] to report.
+pub fn coerce(schema: &Value, value: &mut Value) {
+    coerce_at(schema, schema, value, 0);
+}
+
+fn coerce_at(root: &Value, schema: &Value, value: &mut Value, depth: usize) {
+    if depth > MAX_DEPTH || !schema.is_object() {
+        return;
+    }
+    if let Some(target) = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| resolve_ref(root, r))
+    {
+        coerce_at(root, target, value, depth + 1);
+    }
+
+    let types = schema_types(schema);
+    if !types.is_empty() && !types.iter().any(|ty| is_type(ty, value)) {
+        if let Some(converted) = types.iter().find_map(|ty| convert(ty, value)) {
+            *value = converted;
+        }
+    }
+    if types.contains(&"integer") && !types.contains(&"number") {
+        if let Some(f) = value.as_f64().filter(|_| value.is_f64()) {
+            if f.fract() == 0.0 && f.abs() < 9.0e15 {
+                #[allow(clippy::cast_possible_truncation)]
+                let whole = f as i64;
+                *value = Value::from(whole);
+            }
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                return;
+            };
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            object.retain(|key, child| {
+                !child.is_null()
+                    || required.contains(&key.as_str())
+                    || properties
+                        .get(key)
+                        .is_none_or(|property| accepts_null(root, property, depth))
+            });
+            for (key, child) in object.iter_mut() {
+                if let Some(property) = properties.get(key) {
+                    coerce_at(root, property, child, depth + 1);
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    coerce_at(root, item_schema, item, depth + 1);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `value` as an instance of `ty`, when the intent is unambiguous.
+fn convert(ty: &str, value: &Value) -> Option<Value> {
+    match (ty, value) {
+        ("integer", Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+        ("number", Value::String(s)) => {
+            let s = s.trim();
+            s.parse::<i64>().map(Value::from).ok().or_else(|| {
+                s.parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+            })
+        }
+        ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        ("object", Value::Null) => Some(Value::Object(serde_json::Map::new())),
+        ("object", Value::String(s)) => {
+            serde_json::from_str(s.trim()).ok().filter(Value::is_object)
+        }
+        ("array", Value::String(s)) => serde_json::from_str(s.trim())
+            .ok()
+            .filter(Value::is_array)
+            .or_else(|| Some(Value::Array(vec![value.clone()]))),
+        ("array", Value::Null) => None,
+        ("array", other) => Some(Value::Array(vec![other.clone()])),
+        _ => None,
+    }
+}
+
+/// Whether `schema` allows `null`. Untyped schemas allow anything.
+fn accepts_null(root: &Value, schema: &Value, depth: usize) -> bool {
+    if depth > MAX_DEPTH {
+        return true;
+    }
+    if let Some(target) = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| resolve_ref(root, r))
+    {
+        return accepts_null(root, target, depth + 1);
+    }
+    if schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+        return true;
+    }
+    if let Some(branches) = schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array)
+    {
+        return branches.iter().any(|b| accepts_null(root, b, depth + 1));
+    }
+    let types = schema_types(schema);
+    types.is_empty() || types.contains(&"null")
+}
+
+fn child_path(path: &str, segment: &str) -> String {
+    format!("{path}/{}", segment.replace('~', "~0").replace('/', "~1"))
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    errors: Vec<SchemaError>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.errors.push(SchemaError {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Whether `value` matches `schema`, without recording errors.
+    fn passes(&self, schema: &Value, value: &Value, path: &str, depth: usize) -> bool {
+        let mut probe = Validator {
+            root: self.root,
+            errors: Vec::new(),
+        };
+        probe.check(schema, value, path, depth);
+        probe.errors.is_empty()
+    }
+
+    fn check(&mut self, schema: &Value, value: &Value, path: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.error(path, "schema nesting too deep");
+            return;
+        }
+        // `true` and non-object schemas accept anything.
+        let schema = match schema {
+            Value::Object(_) => schema,
+            Value::Bool(false) => {
+                self.error(path, "no value is allowed here");
+                return;
+            }
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            if let Some(target) = resolve_ref(self.root, reference) {
+                self.check(target, value, path, depth + 1);
+            }
+        }
+
+        if value.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+            return;
+        }
+
+        let types = schema_types(schema);
+        if !types.is_empty() && !types.iter().any(|ty| is_type(ty, value)) {
+            self.error(
+                path,
+                format!("expected {}, got {}", types.join(" or "), type_name(value)),
+            );
+            return;
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                let options: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+                self.error(path, format!("must be one of {}", options.join(", ")));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != value {
+                self.error(path, format!("must be {expected}"));
+            }
+        }
+
+        match value {
+            Value::String(s) => self.check_string(schema, s, path),
+            Value::Number(_) => self.check_number(schema, value, path),
+            Value::Array(items) => self.check_array(schema, items, path, depth),
+            Value::Object(object) => self.check_object(schema, object, path, depth),
+            Value::Null | Value::Bool(_) => {}
+        }
+
+        self.check_combinators(schema, value, path, depth);
+    }
+
+    fn check_string(&mut self, schema: &Value, s: &str, path: &str) {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                self.error(path, format!("must be at least {min} characters"));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                self.error(path, format!("must be at most {max} characters"));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            if let Ok(re) = regex::Regex::new(pattern) {
+                if !re.is_match(s) {
+                    self.error(path, format!("must match /{pattern}/"));
+                }
+            }
+        }
+    }
+
+    fn check_number(&mut self, schema: &Value, value: &Value, path: &str) {
+        let Some(n) = value.as_f64() else {
+            return;
+        };
+        let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum") {
+            if n < min {
+                self.error(path, format!("must be >= {min}"));
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if n > max {
+                self.error(path, format!("must be <= {max}"));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if n <= min {
+                self.error(path, format!("must be > {min}"));
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if n >= max {
+                self.error(path, format!("must be < {max}"));
+            }
+        }
+    }
+
+    fn check_array(&mut self, schema: &Value, items: &[Value], path: &str, depth: usize) {
+        let len = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if len < min {
+                self.error(path, format!("must have at least {min} items"));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if len > max {
+                self.error(path, format!("must have at most {max} items"));
+            }
+        }
+        if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+            let duplicate = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].contains(item));
+            if duplicate {
+                self.error(path, "items must be unique");
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                self.check(
+                    item_schema,
+                    item,
+                    &child_path(path, &i.to_string()),
+                    depth + 1,
+                );
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &Value,
+        object: &serde_json::Map<String, Value>,
+        path: &str,
+        depth: usize,
+    ) {
+        for key in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !object.contains_key(key) {
+                self.error(path, format!("missing required property `{key}`"));
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+        for (key, child) in object {
+            let child_path = child_path(path, key);
+            match properties.and_then(|p| p.get(key)) {
+                Some(property) => self.check(property, child, &child_path, depth + 1),
+                None => match additional {
+                    Some(Value::Bool(false)) => {
+                        self.error(path, format!("unexpected property `{key}`"));
+                    }
+                    Some(extra @ Value::Object(_)) => {
+                        self.check(extra, child, &child_path, depth + 1);
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn check_combinators(&mut self, schema: &Value, value: &Value, path: &str, depth: usize) {
+        let branches = |key: &str| -> Vec<&Value> {
+            schema
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|b| b.iter().collect())
+                .unwrap_or_default()
+        };
+
+        for branch in branches("allOf") {
+            self.check(branch, value, path, depth + 1);
+        }
+
+        let any_of = branches("anyOf");
+        if !any_of.is_empty()
+            && !any_of
+                .iter()
+                .any(|branch| self.passes(branch, value, path, depth + 1))
+        {
+            // One alternative usually fits best; its errors are the useful ones.
+            if any_of.len() == 1 {
+                self.check(any_of[0], value, path, depth + 1);
+            } else {
+                self.error(path, "does not match any of the allowed schemas");
+            }
+        }
+
+        let one_of = branches("oneOf");
+        if !one_of.is_empty() {
+            let matching = one_of
+                .iter()
+                .filter(|branch| self.passes(branch, value, path, depth + 1))
+                .count();
+            if matching != 1 {
+                self.error(
+                    path,
+                    format!("must match exactly one allowed schema (matched {matching})"),
+                );
+            }
+        }
+
+        if let Some(not) = schema.get("not") {
+            if self.passes(not, value, path, depth + 1) {
+                self.error(path, "matches a schema it must not match");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn messages(schema: &Value, value: &Value) -> Vec<String> {
+        validate(schema, value)
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn objects_check_types_required_and_extra_properties() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "minLength": 1},
+                "limit": {"type": "integer", "minimum": 1, "maximum": 100},
+                "mode": {"enum": ["fast", "slow"]}
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        });
+        assert!(validate(&schema, &json!({"path": "a.txt", "limit": 5})).is_ok());
+        assert_eq!(
+            messages(
+                &schema,
+                &json!({"limit": "5", "mode": "medium", "extra": true})
+            ),
+            vec![
+                "missing required property `path`",
+                "unexpected property `extra`",
+                "/limit: expected integer, got string",
+                "/mode: must be one of \"fast\", \"slow\"",
+            ]
+        );
+        assert_eq!(
+            messages(&schema, &json!({"path": "", "limit": 0})),
+            vec![
+                "/limit: must be >= 1",
+                "/path: must be at least 1 characters",
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_refs_and_combinators() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "steps": {"type": "array", "items": {"$ref": "#/$defs/Step"}, "minItems": 1},
+                "note": {"anyOf": [{"type": "string"}, {"type": "null"}]},
+                "id": {"type": ["integer", "string"], "pattern": "^[a-z]+$"}
+            },
+            "$defs": {
+                "Step": {"type": "object", "properties": {"done": {"type": "boolean"}}, "required": ["done"]}
+            }
+        });
+        assert!(validate(
+            &schema,
+            &json!({"steps": [{"done": true}], "note": null, "id": 3})
+        )
+        .is_ok());
+        assert_eq!(
+            messages(
+                &schema,
+                &json!({"steps": [{"done": true}, {}], "note": 1, "id": "ABC"})
+            ),
+            vec![
+                "/id: must match /^[a-z]+$/",
+                "/note: does not match any of the allowed schemas",
+                "/steps/1: missing required property `done`",
+            ]
+        );
+        assert_eq!(
+            messages(&schema, &json!({"steps": []})),
+            vec!["/steps: must have at least 1 items"]
+        );
+    }
+
+    #[test]
+    fn coerce_fixes_obvious_slips_only() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "limit": {"type": "integer"},
+                "ratio": {"type": "number"},
+                "force": {"type": "boolean"},
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "options": {"type": "object", "properties": {"depth": {"type": "integer"}}},
+                "note": {"type": "string"},
+                "label": {"type": ["string", "null"]},
+                "count": {"type": "integer"}
+            },
+            "required": ["count"]
+        });
+        let mut args = json!({
+            "limit": " 5 ",
+            "ratio": "0.5",
+            "force": "TRUE",
+            "name": 42,
+            "tags": "rust",
+            "options": "{\"depth\": 2.0}",
+            "note": null,
+            "label": null,
+            "count": "many"
+        });
+        coerce(&schema, &mut args);
+        assert_eq!(
+            args,
+            json!({
+                "limit": 5,
+                "ratio": 0.5,
+                "force": true,
+                "name": "42",
+                "tags": ["rust"],
+                "options": {"depth": 2},
+                "label": null,
+                "count": "many"
+            })
+        );
+        assert_eq!(
+            messages(&schema, &args),
+            vec!["/count: expected integer, got string"]
+        );
+
+        let mut empty = Value::Null;
+        coerce(&schema, &mut empty);
+        assert_eq!(empty, json!({}));
+    }
+
+    #[test]
+    fn integers_accept_whole_floats_and_nullable_accepts_null() {
+        assert!(validate(&json!({"type": "integer"}), &json!(3.0)).is_ok());
+        assert!(validate(&json!({"type": "integer"}), &json!(3.5)).is_err());
+        assert!(validate(&json!({"type": "string", "nullable": true}), &Value::Null).is_ok());
+        assert!(validate(&json!(false), &json!(1)).is_err());
+        assert!(validate(&json!({}), &json!({"anything": [1, 2]})).is_ok());
+    }
+}
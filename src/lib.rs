@@ -45,12 +45,15 @@ pub mod cost;
 pub mod cron;
 pub mod daemon;
 pub mod doctor;
+pub mod eval;
 pub mod gateway;
 pub mod hardware;
 pub mod health;
 pub mod heartbeat;
 pub mod identity;
 pub mod integrations;
+pub mod json_schema;
+pub mod mcp;
 pub mod memory;
 pub mod migration;
 pub mod observability;
@@ -191,6 +194,8 @@ pub enum CronCommands {
 /// Integration subcommands
 #[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum IntegrationCommands {
+    /// List integrations by category, with configured MCP servers
+    List,
     /// Show details about a specific integration
     Info {
         /// Integration name
@@ -198,6 +203,92 @@ pub enum IntegrationCommands {
     },
 }
 
+/// Gateway management subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GatewayCommands {
+    /// Manage gateway bearer tokens
+    Tokens {
+        #[command(subcommand)]
+        tokens_command: GatewayTokenCommands,
+    },
+}
+
+/// Gateway bearer-token subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GatewayTokenCommands {
+    /// List tokens with scopes, expiry and last use
+    List,
+    /// Create a token (the secret is printed once)
+    Create {
+        /// Human-readable label (e.g. "home-assistant", "grafana")
+        label: String,
+        /// Scopes: webhook, chat, metrics, mcp, admin (repeat or comma-separate)
+        #[arg(long = "scope", value_delimiter = ',', default_value = "webhook")]
+        scopes: Vec<String>,
+        /// Lifetime such as 12h, 30d or 2w (default: never expires)
+        #[arg(long)]
+        expires_in: Option<String>,
+    },
+    /// Revoke a token by id or label
+    Revoke {
+        /// Token id (or unique label)
+        id: String,
+    },
+    /// Issue a new secret for a token, keeping its label and scopes
+    Rotate {
+        /// Token id (or unique label)
+        id: String,
+    },
+}
+
+/// Config inspection and editing subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConfigCommands {
+    /// Check config.toml and report errors and warnings by TOML key
+    Validate,
+    /// Print the effective config with secrets redacted
+    Show,
+    /// Print one value by dotted key (e.g. gateway.port)
+    Get {
+        /// Dotted config key
+        key: String,
+    },
+    /// Set one value by dotted key; the result is validated before writing
+    Set {
+        /// Dotted config key (e.g. autonomy.level)
+        key: String,
+        /// New value: a TOML literal (`8080`, `true`, `["a", "b"]`) or a plain string
+        value: String,
+    },
+    /// Print the JSON Schema for config.toml
+    Schema,
+}
+
+/// Config secret management subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SecretsCommands {
+    /// Store a secret at a config key (encrypted, or an env:/file:/cmd: reference)
+    Set {
+        /// Dotted config key (e.g. api_key, channels_config.telegram.bot_token)
+        key: String,
+        /// Value or reference; prompted for (or read from stdin) when omitted
+        value: Option<String>,
+    },
+    /// Print the resolved value of a config secret
+    Get {
+        /// Dotted config key
+        key: String,
+    },
+    /// Generate a new encryption key and re-encrypt every secret in config
+    RotateKey,
+    /// Upgrade legacy enc: (XOR) values to enc2: and report plaintext secrets
+    Migrate {
+        /// Also encrypt secrets currently stored in plaintext
+        #[arg(long)]
+        encrypt_plaintext: bool,
+    },
+}
+
 /// Hardware discovery subcommands
 #[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum HardwareCommands {
@@ -242,4 +333,38 @@ pub enum PeripheralCommands {
     },
     /// Flash ZeroClaw firmware to Nucleo-F401RE (builds + probe-rs run)
     FlashNucleo,
+    /// Run a simulated board on a pty (gpio_read/gpio_write/capabilities, no hardware)
+    Simulate {
+        /// Board to emulate (arduino-uno, nucleo-f401re, esp32, ...)
+        board: String,
+        /// TOML script with pin levels and sensor values
+        #[arg(long)]
+        script: Option<String>,
+        /// Serve over the network instead of a pty (tcp://, ws://, mqtt:// or mqtts:// URL)
+        #[arg(long)]
+        listen: Option<String>,
+        /// Shared secret for the network handshake (supports env:/file:/cmd: refs)
+        #[arg(long, requires = "listen")]
+        secret: Option<String>,
+    },
+}
+
+/// Model Context Protocol subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum McpCommands {
+    /// Serve the tool registry and memory to MCP clients (Claude Desktop, IDEs, ...)
+    Serve {
+        /// Transport: stdio (spawned by the client) or http (streamable HTTP at /mcp)
+        #[arg(long, default_value = "stdio")]
+        transport: String,
+        /// Bind address for --transport http
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port for --transport http (default: `[mcp] serve_port`)
+        #[arg(long)]
+        port: Option<u16>,
+        /// Only expose these tools (repeatable); defaults to all
+        #[arg(long = "tool")]
+        tools: Vec<String>,
+    },
 }
@@ -35,6 +35,7 @@
 use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 use tracing::{info, Level};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::FmtSubscriber;
 
 mod agent;
@@ -43,15 +44,19 @@ mod rag {
     pub use zeroclaw::rag::*;
 }
 mod config;
+mod cost;
 mod cron;
 mod daemon;
 mod doctor;
+mod eval;
 mod gateway;
 mod hardware;
 mod health;
 mod heartbeat;
 mod identity;
 mod integrations;
+mod json_schema;
+mod mcp;
 mod memory;
 mod migration;
 mod observability;
@@ -71,7 +76,10 @@ mod util;
 use config::Config;
 
 // Re-export so binary's hardware/peripherals modules can use crate::HardwareCommands etc.
-pub use zeroclaw::{HardwareCommands, PeripheralCommands};
+pub use zeroclaw::{
+    ConfigCommands, GatewayCommands, GatewayTokenCommands, HardwareCommands, McpCommands,
+    PeripheralCommands, SecretsCommands,
+};
 
 /// `ZeroClaw` - Zero overhead. Zero compromise. 100% Rust.
 #[derive(Parser, Debug)]
@@ -144,10 +152,43 @@ enum Commands {
         /// Attach a peripheral (board:path, e.g. nucleo-f401re:/dev/ttyACM0)
         #[arg(long)]
         peripheral: Vec<String>,
+
+        /// Record provider requests/responses to a cassette for `replay:<cassette>`
+        #[arg(long, value_name = "CASSETTE")]
+        record: Option<String>,
+    },
+
+    /// Run agent behavior scenarios (TOML/YAML) and report results
+    Eval {
+        /// Scenario file or directory
+        path: std::path::PathBuf,
+
+        /// Only run scenarios whose name contains this text
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Write a JUnit XML report to this path
+        #[arg(long)]
+        junit: Option<std::path::PathBuf>,
+
+        /// Write a JSON report to this path
+        #[arg(long)]
+        json: Option<std::path::PathBuf>,
+
+        /// Provider for `judge` assertions (defaults to the configured provider)
+        #[arg(long)]
+        judge_provider: Option<String>,
+
+        /// Model for `judge` assertions (defaults to the configured model)
+        #[arg(long)]
+        judge_model: Option<String>,
     },
 
     /// Start the gateway server (webhooks, websockets)
     Gateway {
+        #[command(subcommand)]
+        gateway_command: Option<zeroclaw::GatewayCommands>,
+
         /// Port to listen on (use 0 for random available port)
         #[arg(short, long, default_value = "8080")]
         port: u16,
@@ -224,6 +265,18 @@ enum Commands {
         migrate_command: MigrateCommands,
     },
 
+    /// Validate, inspect and edit config.toml
+    Config {
+        #[command(subcommand)]
+        config_command: zeroclaw::ConfigCommands,
+    },
+
+    /// Manage config secrets (encrypted values and env:/file:/cmd: references)
+    Secrets {
+        #[command(subcommand)]
+        secrets_command: zeroclaw::SecretsCommands,
+    },
+
     /// Discover and introspect USB hardware
     Hardware {
         #[command(subcommand)]
@@ -235,6 +288,12 @@ enum Commands {
         #[command(subcommand)]
         peripheral_command: zeroclaw::PeripheralCommands,
     },
+
+    /// Serve tools and memory over the Model Context Protocol
+    Mcp {
+        #[command(subcommand)]
+        mcp_command: zeroclaw::McpCommands,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -353,6 +412,8 @@ enum SkillCommands {
 
 #[derive(Subcommand, Debug)]
 enum IntegrationCommands {
+    /// List integrations by category, with configured MCP servers
+    List,
     /// Show details about a specific integration
     Info {
         /// Integration name
@@ -372,9 +433,14 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Initialize logging
+    // Initialize logging. `mcp serve` speaks JSON-RPC on stdout, so logs go to stderr.
     let subscriber = FmtSubscriber::builder()
         .with_max_level(Level::INFO)
+        .with_writer(if matches!(cli.command, Commands::Mcp { .. }) {
+            BoxMakeWriter::new(std::io::stderr)
+        } else {
+            BoxMakeWriter::new(std::io::stdout)
+        })
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
@@ -409,12 +475,24 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Secrets work on the raw config file, so a broken reference can be fixed
+    if let Commands::Secrets { secrets_command } = &cli.command {
+        return security::secrets_cli::handle_command(secrets_command.clone());
+    }
+
+    // Likewise for `config`, so a config the daemon rejects can be inspected
+    if let Commands::Config { config_command } = &cli.command {
+        return config::cli::handle_command(config_command.clone());
+    }
+
     // All other commands need config loaded first
     let mut config = Config::load_or_init()?;
     config.apply_env_overrides();
 
     match cli.command {
-        Commands::Onboard { .. } => unreachable!(),
+        Commands::Onboard { .. } | Commands::Config { .. } | Commands::Secrets { .. } => {
+            unreachable!()
+        }
 
         Commands::Agent {
             message,
@@ -422,9 +500,48 @@ async fn main() -> Result<()> {
             model,
             temperature,
             peripheral,
-        } => agent::run(config, message, provider, model, temperature, peripheral).await,
+            record,
+        } => {
+            agent::run(
+                config,
+                message,
+                provider,
+                model,
+                temperature,
+                peripheral,
+                record,
+            )
+            .await
+        }
 
-        Commands::Gateway { port, host } => {
+        Commands::Eval {
+            path,
+            filter,
+            junit,
+            json,
+            judge_provider,
+            judge_model,
+        } => {
+            let options = eval::EvalOptions {
+                filter,
+                junit,
+                json,
+                judge_provider,
+                judge_model,
+            };
+            Box::pin(eval::run(&config, &path, &options)).await
+        }
+
+        Commands::Gateway {
+            gateway_command: Some(gateway_command),
+            ..
+        } => gateway::tokens::handle_command(gateway_command, &config),
+
+        Commands::Gateway {
+            gateway_command: None,
+            port,
+            host,
+        } => {
             if port == 0 {
                 info!("🚀 Starting ZeroClaw Gateway on {host} (random port)");
             } else {
@@ -523,6 +640,11 @@ async fn main() -> Result<()> {
                 }
             );
             println!("  Boards:    {}", config.peripherals.boards.len());
+            println!();
+            println!("Provider health:");
+            for line in health::provider_status_lines(&daemon::state_file_path(&config)) {
+                println!("  {line}");
+            }
 
             Ok(())
         }
@@ -562,8 +684,10 @@ async fn main() -> Result<()> {
         }
 
         Commands::Peripheral { peripheral_command } => {
-            peripherals::handle_command(peripheral_command.clone(), &config)
+            peripherals::handle_command(peripheral_command.clone(), &config).await
         }
+
+        Commands::Mcp { mcp_command } => Box::pin(mcp::handle_command(mcp_command, &config)).await,
     }
 }
 
@@ -0,0 +1,278 @@
+//! MCP client session: handshake, tool discovery and tool calls.
+
+use super::protocol::{PROTOCOL_VERSION, SUPPORTED_VERSIONS};
+use super::transport::{HttpTransport, StdioTransport, Transport};
+use crate::config::McpServerConfig;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A tool advertised by a server in `tools/list`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpToolInfo {
+    pub name: String,
+    pub description: String,
+    pub input_schema: Value,
+    /// The server's `readOnlyHint` annotation. Advisory only: it is not
+    /// used to authorize calls.
+    pub read_only: bool,
+}
+
+impl McpToolInfo {
+    fn from_value(value: &Value) -> Option<Self> {
+        let name = value.get("name")?.as_str()?.to_string();
+        let description = value
+            .get("description")
+            .and_then(Value::as_str)
+            .or_else(|| value.get("title").and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string();
+        let input_schema = value
+            .get("inputSchema")
+            .filter(|schema| schema.is_object())
+            .cloned()
+            .unwrap_or_else(|| json!({"type": "object", "properties": {}}));
+        let read_only = value
+            .pointer("/annotations/readOnlyHint")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        Some(Self {
+            name,
+            description,
+            input_schema,
+            read_only,
+        })
+    }
+}
+
+/// The text of a `tools/call` result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallOutcome {
+    pub output: String,
+    /// The server's `isError` flag: the tool ran and failed.
+    pub is_error: bool,
+}
+
+/// An initialized session with one server.
+pub struct McpClient {
+    transport: Box<dyn Transport>,
+    server_name: String,
+    server_version: String,
+    tools: tokio::sync::Mutex<Option<Vec<McpToolInfo>>>,
+}
+
+impl McpClient {
+    /// Open the configured transport and run the `initialize` handshake.
+    pub async fn connect(config: &McpServerConfig, workspace_dir: &Path) -> Result<Self> {
+        let transport: Box<dyn Transport> = match config.transport.as_str() {
+            "stdio" => Box::new(StdioTransport::spawn(config, workspace_dir)?),
+            "http" => Box::new(HttpTransport::new(config, workspace_dir)?),
+            other => bail!("unknown MCP transport `{other}`"),
+        };
+        Self::initialize(transport).await
+    }
+
+    pub async fn initialize(transport: Box<dyn Transport>) -> Result<Self> {
+        let result = transport
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "zeroclaw",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+            .await
+            .context("MCP initialize failed")?;
+
+        let version = result
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .unwrap_or(PROTOCOL_VERSION);
+        if !SUPPORTED_VERSIONS.contains(&version) {
+            bail!("MCP server speaks unsupported protocol version {version}");
+        }
+        transport.set_protocol_version(version);
+        transport
+            .notify("notifications/initialized", json!({}))
+            .await?;
+
+        let info = result.get("serverInfo");
+        let field = |key: &str| {
+            info.and_then(|i| i.get(key))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        Ok(Self {
+            server_name: field("name"),
+            server_version: field("version"),
+            transport,
+            tools: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    /// `name version` as reported by the server.
+    pub fn server_label(&self) -> String {
+        format!("{} {}", self.server_name, self.server_version)
+            .trim()
+            .to_string()
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.transport.is_alive()
+    }
+
+    /// Advertised tools, re-listed when the server says they changed.
+    pub async fn tools(&self) -> Result<Vec<McpToolInfo>> {
+        let mut cached = self.tools.lock().await;
+        if self.transport.take_tools_changed() {
+            *cached = None;
+        }
+        if let Some(tools) = cached.as_ref() {
+            return Ok(tools.clone());
+        }
+
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({"cursor": cursor}),
+                None => json!({}),
+            };
+            let page = self.transport.request("tools/list", params).await?;
+            if let Some(listed) = page.get("tools").and_then(Value::as_array) {
+                tools.extend(listed.iter().filter_map(McpToolInfo::from_value));
+            }
+            cursor = page
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        *cached = Some(tools.clone());
+        Ok(tools)
+    }
+
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallOutcome> {
+        let result = self
+            .transport
+            .request("tools/call", json!({"name": name, "arguments": arguments}))
+            .await?;
+        Ok(CallOutcome {
+            output: render_content(&result),
+            is_error: result
+                .get("isError")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        })
+    }
+
+    pub async fn ping(&self) -> Result<()> {
+        self.transport.request("ping", json!({})).await?;
+        Ok(())
+    }
+
+    pub async fn close(&self) {
+        self.transport.close().await;
+    }
+}
+
+/// Flatten a `tools/call` result into text for the model. Binary content is
+/// summarized; `structuredContent` is used when there is no content list.
+fn render_content(result: &Value) -> String {
+    let mut out = String::new();
+    for item in result
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let kind = item.get("type").and_then(Value::as_str).unwrap_or_default();
+        let mime = item
+            .get("mimeType")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown type");
+        match kind {
+            "text" => out.push_str(item.get("text").and_then(Value::as_str).unwrap_or_default()),
+            "image" | "audio" => {
+                let _ = write!(out, "[{kind}: {mime}]");
+            }
+            "resource" => {
+                let resource = item.get("resource").unwrap_or(&Value::Null);
+                match resource.get("text").and_then(Value::as_str) {
+                    Some(text) => out.push_str(text),
+                    None => {
+                        let uri = resource.get("uri").and_then(Value::as_str).unwrap_or("?");
+                        let _ = write!(out, "[resource: {uri}]");
+                    }
+                }
+            }
+            "resource_link" => {
+                let uri = item.get("uri").and_then(Value::as_str).unwrap_or("?");
+                let _ = write!(out, "[resource: {uri}]");
+            }
+            _ => out.push_str(&item.to_string()),
+        }
+    }
+
+    if out.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            return structured.to_string();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_info_reads_schema_and_read_only_hint() {
+        let info = McpToolInfo::from_value(&json!({
+            "name": "search",
+            "description": "Search issues",
+            "inputSchema": {"type": "object", "properties": {"q": {"type": "string"}}},
+            "annotations": {"readOnlyHint": true},
+        }))
+        .unwrap();
+        assert_eq!(info.name, "search");
+        assert!(info.read_only);
+        assert_eq!(info.input_schema["properties"]["q"]["type"], "string");
+
+        let bare = McpToolInfo::from_value(&json!({"name": "poke"})).unwrap();
+        assert!(!bare.read_only);
+        assert_eq!(bare.input_schema["type"], "object");
+        assert!(McpToolInfo::from_value(&json!({"description": "no name"})).is_none());
+    }
+
+    #[test]
+    fn render_content_flattens_text_and_summarizes_binary() {
+        let result = json!({
+            "content": [
+                {"type": "text", "text": "first"},
+                {"type": "image", "data": "AAAA", "mimeType": "image/png"},
+                {"type": "resource", "resource": {"uri": "file:///a.txt", "text": "inline"}},
+                {"type": "resource_link", "uri": "file:///b.txt", "name": "b"},
+            ]
+        });
+        assert_eq!(
+            render_content(&result),
+            "first\n[image: image/png]\ninline\n[resource: file:///b.txt]"
+        );
+        assert_eq!(
+            render_content(&json!({"content": [], "structuredContent": {"n": 1}})),
+            "{\"n\":1}"
+        );
+    }
+}
@@ -0,0 +1,500 @@
+//! Model Context Protocol client and server.
+//!
+//! Each `[[mcp.servers]]` entry is a stdio subprocess or a streamable HTTP
+//! endpoint. The tools it advertises become [`McpTool`]s named
+//! `mcp_<server>_<tool>` and are appended to the agent's tool registry,
+//! gated by `SecurityPolicy` like the built-ins.
+//!
+//! Connections are shared process-wide and rebuilt lazily, so a crashed
+//! server comes back on the next call. Under `zeroclaw daemon` the `mcp`
+//! component keeps them warm, pings them and reports each one in `health`
+//! as `mcp:<name>`.
+//!
+//! In the other direction, `zeroclaw mcp serve` (see [`server`]) exposes
+//! ZeroClaw's own tools and memory to MCP clients over stdio or HTTP.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+pub mod tool;
+pub mod transport;
+
+use crate::config::reload::LiveConfig;
+use crate::config::{Config, McpConfig, McpServerConfig};
+use crate::security::SecurityPolicy;
+use crate::tools::Tool;
+use anyhow::{bail, Context, Result};
+use client::{CallOutcome, McpClient};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+pub use tool::McpTool;
+
+/// How often the daemon pings each server.
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// One configured server and its current session, if any.
+pub struct McpServer {
+    config: McpServerConfig,
+    workspace_dir: PathBuf,
+    client: tokio::sync::Mutex<Option<Arc<McpClient>>>,
+}
+
+impl McpServer {
+    fn new(config: McpServerConfig, workspace_dir: PathBuf) -> Self {
+        Self {
+            config,
+            workspace_dir,
+            client: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn component(&self) -> String {
+        format!("mcp:{}", self.config.name)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout_secs.max(1))
+    }
+
+    /// The live session, reconnecting if the previous one died.
+    async fn client(&self) -> Result<Arc<McpClient>> {
+        let mut slot = self.client.lock().await;
+        if let Some(client) = slot.as_ref().filter(|c| c.is_alive()) {
+            return Ok(Arc::clone(client));
+        }
+        *slot = None;
+
+        let connect = McpClient::connect(&self.config, &self.workspace_dir);
+        let connected = match tokio::time::timeout(self.timeout(), connect).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!(
+                "connect timed out after {}s",
+                self.timeout().as_secs()
+            )),
+        };
+        match connected {
+            Ok(client) => {
+                tracing::info!(
+                    server = %self.config.name,
+                    "Connected to MCP server {}",
+                    client.server_label()
+                );
+                crate::health::mark_component_ok(&self.component());
+                let client = Arc::new(client);
+                *slot = Some(Arc::clone(&client));
+                Ok(client)
+            }
+            Err(e) => {
+                crate::health::mark_component_error(&self.component(), format!("{e:#}"));
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn call_tool(&self, tool: &str, arguments: serde_json::Value) -> Result<CallOutcome> {
+        let client = self.client().await?;
+        let call = client.call_tool(tool, arguments);
+        let result = match tokio::time::timeout(self.timeout(), call).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!(
+                "{tool} timed out after {}s",
+                self.timeout().as_secs()
+            )),
+        };
+        if let Err(e) = &result {
+            if !client.is_alive() {
+                crate::health::mark_component_error(&self.component(), format!("{e:#}"));
+            }
+        }
+        result
+    }
+
+    /// Connect if needed and ping; a session that does not answer is
+    /// dropped so the next check starts a fresh one.
+    async fn check(&self) {
+        let client = match self.client().await {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::warn!(server = %self.config.name, "MCP server unavailable: {e:#}");
+                return;
+            }
+        };
+        match tokio::time::timeout(self.timeout(), client.ping()).await {
+            Ok(Ok(())) => crate::health::mark_component_ok(&self.component()),
+            Ok(Err(e)) => self.reset(format!("ping failed: {e:#}")).await,
+            Err(_) => self.reset("ping timed out".to_string()).await,
+        }
+    }
+
+    async fn reset(&self, reason: String) {
+        tracing::warn!(server = %self.config.name, "MCP server {reason}; reconnecting");
+        crate::health::mark_component_error(&self.component(), reason);
+        self.close().await;
+    }
+
+    async fn close(&self) {
+        if let Some(client) = self.client.lock().await.take() {
+            client.close().await;
+        }
+    }
+}
+
+fn servers() -> &'static parking_lot::Mutex<HashMap<String, Arc<McpServer>>> {
+    static SERVERS: OnceLock<parking_lot::Mutex<HashMap<String, Arc<McpServer>>>> = OnceLock::new();
+    SERVERS.get_or_init(Default::default)
+}
+
+/// The shared handle for `config`; a changed entry replaces the old handle.
+fn server_handle(config: &McpServerConfig, workspace_dir: &Path) -> Arc<McpServer> {
+    let mut servers = servers().lock();
+    if let Some(existing) = servers.get(&config.name) {
+        if existing.config == *config && existing.workspace_dir == workspace_dir {
+            return Arc::clone(existing);
+        }
+    }
+    let handle = Arc::new(McpServer::new(config.clone(), workspace_dir.to_path_buf()));
+    servers.insert(config.name.clone(), Arc::clone(&handle));
+    handle
+}
+
+/// Connect to every enabled server and wrap its tools. Servers that fail
+/// to connect are logged and skipped so one bad entry cannot take the
+/// agent down.
+pub async fn create_mcp_tools(
+    config: &McpConfig,
+    security: &Arc<SecurityPolicy>,
+    workspace_dir: &Path,
+) -> Vec<Box<dyn Tool>> {
+    let mut tools: Vec<Box<dyn Tool>> = Vec::new();
+    for server_config in config.enabled_servers() {
+        let handle = server_handle(server_config, workspace_dir);
+        match server_tools(&handle, security).await {
+            Ok(server_tools) => {
+                tracing::info!(
+                    server = %server_config.name,
+                    count = server_tools.len(),
+                    "MCP tools added"
+                );
+                tools.extend(server_tools);
+            }
+            Err(e) => {
+                tracing::warn!("Skipping MCP server `{}`: {e:#}", server_config.name);
+            }
+        }
+    }
+
+    // Truncated names can collide; the first one wins.
+    let mut seen = HashSet::new();
+    tools.retain(|tool| {
+        let unique = seen.insert(tool.name().to_string());
+        if !unique {
+            tracing::warn!("Duplicate MCP tool name {}; skipping", tool.name());
+        }
+        unique
+    });
+    tools
+}
+
+async fn server_tools(
+    server: &Arc<McpServer>,
+    security: &Arc<SecurityPolicy>,
+) -> Result<Vec<Box<dyn Tool>>> {
+    let client = server.client().await?;
+    let listed = tokio::time::timeout(server.timeout(), client.tools())
+        .await
+        .context("tools/list timed out")??;
+    let allowed = &server.config.allowed_tools;
+    Ok(listed
+        .into_iter()
+        .filter(|info| allowed.is_empty() || allowed.contains(&info.name))
+        .map(|info| {
+            Box::new(McpTool::new(Arc::clone(server), info, Arc::clone(security))) as Box<dyn Tool>
+        })
+        .collect())
+}
+
+/// Daemon component: keep configured servers connected and healthy until
+/// shutdown, then end their sessions.
+pub async fn run_supervisor(live: LiveConfig, shutdown: CancellationToken) -> Result<()> {
+    let config: Arc<Config> = Arc::clone(&live.borrow());
+    let handles: Vec<Arc<McpServer>> = config
+        .mcp
+        .enabled_servers()
+        .map(|s| server_handle(s, &config.workspace_dir))
+        .collect();
+
+    // Sessions for servers that were removed or disabled end here.
+    let removed: Vec<Arc<McpServer>> = {
+        let mut servers = servers().lock();
+        let keep: HashSet<&str> = handles.iter().map(|h| h.name()).collect();
+        let stale: Vec<String> = servers
+            .keys()
+            .filter(|name| !keep.contains(name.as_str()))
+            .cloned()
+            .collect();
+        stale.iter().filter_map(|n| servers.remove(n)).collect()
+    };
+    for handle in removed {
+        handle.close().await;
+        crate::health::mark_component_stopped(&handle.component());
+    }
+
+    let mut interval = tokio::time::interval(HEALTH_INTERVAL);
+    loop {
+        tokio::select! {
+            () = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        for handle in &handles {
+            handle.check().await;
+        }
+    }
+
+    for handle in &handles {
+        handle.close().await;
+        crate::health::mark_component_stopped(&handle.component());
+    }
+    Ok(())
+}
+
+pub async fn handle_command(cmd: crate::McpCommands, config: &Config) -> Result<()> {
+    match cmd {
+        crate::McpCommands::Serve {
+            transport,
+            host,
+            port,
+            tools,
+        } => {
+            let server = server::ToolServer::from_config(config, &tools).await?;
+            match transport.as_str() {
+                "stdio" => server::serve_stdio(&server).await,
+                "http" => {
+                    let port = port.unwrap_or(config.mcp.serve_port);
+                    server::serve_http(server, config, &host, port).await
+                }
+                other => bail!("Unknown MCP transport `{other}` (expected stdio or http)"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::AutonomyLevel;
+    use protocol::Message;
+    use serde_json::json;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use transport::StdioTransport;
+
+    /// Minimal MCP server: a tool hinted read-only, one that echoes its
+    /// arguments (or fails when asked to), and one with its own `approved`
+    /// argument.
+    async fn fake_server(io: tokio::io::DuplexStream) {
+        let (read, mut write) = tokio::io::split(io);
+        let mut lines = BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Some(Message::Request { id, method, params }) =
+                Message::parse(serde_json::from_str(&line).unwrap())
+            else {
+                continue;
+            };
+            let result = match method.as_str() {
+                "initialize" => json!({
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {"tools": {}},
+                    "serverInfo": {"name": "fake", "version": "1.0"},
+                }),
+                "tools/list" => json!({"tools": [
+                    {
+                        "name": "lookup",
+                        "description": "Look something up",
+                        "inputSchema": {"type": "object", "properties": {"q": {"type": "string"}}},
+                        "annotations": {"readOnlyHint": true},
+                    },
+                    {"name": "echo", "inputSchema": {"type": "object"}},
+                    {
+                        "name": "sign",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {"approved": {"type": "string"}},
+                        },
+                    },
+                ]}),
+                "tools/call" if params["arguments"]["fail"] == true => json!({
+                    "content": [{"type": "text", "text": "it broke"}],
+                    "isError": true,
+                }),
+                "tools/call" => json!({
+                    "content": [{"type": "text", "text": params["arguments"].to_string()}],
+                }),
+                _ => json!({}),
+            };
+            let reply = protocol::response(id, result);
+            write
+                .write_all(format!("{reply}\n").as_bytes())
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn connected_server(name: &str) -> Arc<McpServer> {
+        let (client_io, server_io) = tokio::io::duplex(16 * 1024);
+        tokio::spawn(fake_server(server_io));
+        let (read, write) = tokio::io::split(client_io);
+        let transport = StdioTransport::from_streams(read, write, Duration::from_secs(5));
+        let client = McpClient::initialize(Box::new(transport)).await.unwrap();
+        assert_eq!(client.server_label(), "fake 1.0");
+
+        let config: McpServerConfig =
+            toml::from_str(&format!("name = \"{name}\"\ncommand = \"unused\"")).unwrap();
+        let server = McpServer::new(config, std::env::temp_dir());
+        *server.client.lock().await = Some(Arc::new(client));
+        Arc::new(server)
+    }
+
+    fn policy(autonomy: AutonomyLevel) -> Arc<SecurityPolicy> {
+        Arc::new(SecurityPolicy {
+            autonomy,
+            require_approval_for_medium_risk: true,
+            ..SecurityPolicy::default()
+        })
+    }
+
+    async fn tools_by_name(
+        server: &Arc<McpServer>,
+        security: &Arc<SecurityPolicy>,
+    ) -> HashMap<String, Box<dyn Tool>> {
+        server_tools(server, security)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tool| (tool.name().to_string(), tool))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn advertised_tools_are_wrapped_with_their_schemas() {
+        let server = connected_server("docs").await;
+        let tools = tools_by_name(&server, &policy(AutonomyLevel::Full)).await;
+
+        let lookup = &tools["mcp_docs_lookup"];
+        assert_eq!(
+            lookup.description(),
+            "Look something up (MCP server `docs`)"
+        );
+        assert_eq!(
+            lookup.parameters_schema()["properties"]["q"]["type"],
+            "string"
+        );
+        // Every tool gets the approval flag; hints are not trusted.
+        assert_eq!(
+            lookup.parameters_schema()["properties"]["approved"]["type"],
+            "boolean"
+        );
+        // A tool's own `approved` argument is kept, ours is renamed.
+        let sign = tools["mcp_docs_sign"].parameters_schema();
+        assert_eq!(sign["properties"]["approved"]["type"], "string");
+        assert_eq!(sign["properties"]["zeroclaw_approved"]["type"], "boolean");
+
+        let result = lookup.execute(json!({"q": "rust"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, r#"{"q":"rust"}"#);
+
+        let result = tools["mcp_docs_echo"]
+            .execute(json!({"fail": true}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("it broke"));
+    }
+
+    #[tokio::test]
+    async fn calls_follow_autonomy_and_approval() {
+        let server = connected_server("ops").await;
+
+        // `readOnlyHint` does not get a tool past read-only autonomy.
+        let read_only = tools_by_name(&server, &policy(AutonomyLevel::ReadOnly)).await;
+        for name in ["mcp_ops_lookup", "mcp_ops_echo"] {
+            let blocked = read_only[name].execute(json!({})).await.unwrap();
+            assert!(blocked.error.unwrap().contains("read-only"), "{name}");
+        }
+
+        let supervised = tools_by_name(&server, &policy(AutonomyLevel::Supervised)).await;
+        let echo = &supervised["mcp_ops_echo"];
+        let pending = echo.execute(json!({"x": 1})).await.unwrap();
+        assert!(pending.error.unwrap().contains("approved=true"));
+        // `approved` is ours and is not forwarded to the server.
+        let approved = echo
+            .execute(json!({"x": 1, "approved": true}))
+            .await
+            .unwrap();
+        assert_eq!(approved.output, r#"{"x":1}"#);
+
+        // A tool with its own `approved` argument is approved via ours.
+        let sign = &supervised["mcp_ops_sign"];
+        let pending = sign.execute(json!({"approved": "yes"})).await.unwrap();
+        assert!(pending.error.unwrap().contains("zeroclaw_approved=true"));
+        let approved = sign
+            .execute(json!({"approved": "yes", "zeroclaw_approved": true}))
+            .await
+            .unwrap();
+        assert_eq!(approved.output, r#"{"approved":"yes"}"#);
+
+        let limited = Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Full,
+            max_actions_per_hour: 0,
+            ..SecurityPolicy::default()
+        });
+        let tools = tools_by_name(&server, &limited).await;
+        let result = tools["mcp_ops_lookup"].execute(json!({})).await.unwrap();
+        assert!(result.error.unwrap().contains("rate limit"));
+    }
+
+    #[tokio::test]
+    async fn allowed_tools_filters_what_is_imported() {
+        let server = connected_server("gh").await;
+        let mut config = server.config.clone();
+        config.allowed_tools = vec!["lookup".into()];
+        let filtered = Arc::new(McpServer::new(config, std::env::temp_dir()));
+        *filtered.client.lock().await = server.client.lock().await.clone();
+
+        let tools = tools_by_name(&filtered, &policy(AutonomyLevel::Full)).await;
+        assert_eq!(tools.keys().collect::<Vec<_>>(), vec!["mcp_gh_lookup"]);
+    }
+
+    #[test]
+    fn tool_names_are_sanitized_and_capped() {
+        assert_eq!(
+            tool::tool_name("git hub", "list.issues"),
+            "mcp_git_hub_list_issues"
+        );
+        assert_eq!(tool::tool_name("s", &"x".repeat(100)).len(), 64);
+    }
+
+    #[tokio::test]
+    async fn create_mcp_tools_skips_servers_that_fail_to_start() {
+        let config: McpConfig = toml::from_str(
+            r#"
+            [[servers]]
+            name = "missing"
+            command = "/nonexistent/zeroclaw-mcp-server"
+            timeout_secs = 5
+            "#,
+        )
+        .unwrap();
+        let tools =
+            create_mcp_tools(&config, &policy(AutonomyLevel::Full), &std::env::temp_dir()).await;
+        assert!(tools.is_empty());
+        let health = crate::health::snapshot();
+        assert_eq!(health.components["mcp:missing"].status, "error");
+    }
+}
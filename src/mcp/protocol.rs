@@ -0,0 +1,163 @@
+//! JSON-RPC 2.0 framing for MCP.
+//!
+//! Messages are plain `serde_json::Value`s; this module only builds the
+//! envelopes and sorts incoming messages into requests, notifications and
+//! responses.
+
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Revision sent in `initialize`.
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Revisions whose tool calls look the same as [`PROTOCOL_VERSION`].
+pub const SUPPORTED_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// An `error` object from a JSON-RPC response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn from_value(value: &Value) -> Self {
+        Self {
+            code: value
+                .get("code")
+                .and_then(Value::as_i64)
+                .unwrap_or(INTERNAL_ERROR),
+            message: value
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+                .to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MCP error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// An incoming JSON-RPC message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+    Response {
+        id: Value,
+        result: Result<Value, RpcError>,
+    },
+}
+
+impl Message {
+    /// Classify a decoded message; `None` if it is not valid JSON-RPC.
+    pub fn parse(value: Value) -> Option<Self> {
+        let Value::Object(mut object) = value else {
+            return None;
+        };
+        let id = object.remove("id").filter(|id| !id.is_null());
+        if let Some(method) = object.get("method").and_then(Value::as_str) {
+            let method = method.to_string();
+            let params = object.remove("params").unwrap_or(Value::Null);
+            return Some(match id {
+                Some(id) => Self::Request { id, method, params },
+                None => Self::Notification { method, params },
+            });
+        }
+        let id = id?;
+        let result = match object.remove("error") {
+            Some(error) => Err(RpcError::from_value(&error)),
+            None => Ok(object.remove("result")?),
+        };
+        Some(Self::Response { id, result })
+    }
+}
+
+pub fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "method": method, "params": params})
+}
+
+pub fn response(id: Value, result: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+pub fn error_response(id: Value, error: &RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sorts_requests_notifications_and_responses() {
+        assert_eq!(
+            Message::parse(request(7, "tools/list", json!({}))),
+            Some(Message::Request {
+                id: json!(7),
+                method: "tools/list".into(),
+                params: json!({}),
+            })
+        );
+        assert_eq!(
+            Message::parse(notification("notifications/initialized", Value::Null)),
+            Some(Message::Notification {
+                method: "notifications/initialized".into(),
+                params: Value::Null,
+            })
+        );
+        assert_eq!(
+            Message::parse(response(json!(1), json!({"ok": true}))),
+            Some(Message::Response {
+                id: json!(1),
+                result: Ok(json!({"ok": true})),
+            })
+        );
+        assert_eq!(
+            Message::parse(error_response(
+                json!("a"),
+                &RpcError::new(METHOD_NOT_FOUND, "nope")
+            )),
+            Some(Message::Response {
+                id: json!("a"),
+                result: Err(RpcError::new(METHOD_NOT_FOUND, "nope")),
+            })
+        );
+        assert_eq!(Message::parse(json!({"jsonrpc": "2.0", "id": 1})), None);
+        assert_eq!(Message::parse(json!([1, 2])), None);
+    }
+}
@@ -0,0 +1,607 @@
+//! `zeroclaw mcp serve`: the tool registry and memory as an MCP server.
+//!
+//! Tools are advertised from their [`ToolSpec`](crate::tools::ToolSpec)s and
+//! memory entries are resources at `memory://<key>`. In read-only autonomy
+//! only tools that cannot change anything are listed or callable; every
+//! tool still applies its own `SecurityPolicy` checks on top.
+
+use super::protocol::{self, Message, RpcError, PROTOCOL_VERSION, SUPPORTED_VERSIONS};
+use crate::config::Config;
+use crate::memory::{self, Memory};
+use crate::observability::{self, Observer, ObserverEvent};
+use crate::security::gateway_tokens::{TokenError, TokenScope, TokenStore};
+use crate::security::pairing::is_public_bind;
+use crate::security::{AutonomyLevel, PairingGuard, SecurityPolicy};
+use crate::tools::{self, Tool};
+use anyhow::{bail, Context, Result};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Tools that can write but refuse to in read-only mode themselves.
+const SELF_GATED_TOOLS: &[&str] = &["git_operations"];
+
+/// Resource URI prefix for memory entries.
+const MEMORY_SCHEME: &str = "memory://";
+
+/// JSON-RPC error for `resources/read` on an unknown URI.
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+pub struct ToolServer {
+    tools: Vec<Box<dyn Tool>>,
+    memory: Arc<dyn Memory>,
+    security: Arc<SecurityPolicy>,
+    observer: Arc<dyn Observer>,
+}
+
+impl ToolServer {
+    pub fn new(
+        tools: Vec<Box<dyn Tool>>,
+        memory: Arc<dyn Memory>,
+        security: Arc<SecurityPolicy>,
+        observer: Arc<dyn Observer>,
+    ) -> Self {
+        Self {
+            tools,
+            memory,
+            security,
+            observer,
+        }
+    }
+
+    /// Build the same registry the agent gets (built-ins, Composio,
+    /// delegate, peripherals), optionally limited to `only`. Tools imported
+    /// from other MCP servers are not re-exported.
+    pub async fn from_config(config: &Config, only: &[String]) -> Result<Self> {
+        let security = Arc::new(SecurityPolicy::from_config(
+            &config.autonomy,
+            &config.workspace_dir,
+        ));
+        let runtime: Arc<dyn crate::runtime::RuntimeAdapter> =
+            Arc::from(crate::runtime::create_runtime(&config.runtime)?);
+        let memory: Arc<dyn Memory> = Arc::from(memory::create_memory(
+            &config.memory,
+            &config.workspace_dir,
+            config.api_key.as_deref(),
+        )?);
+        let (composio_key, composio_entity_id) = if config.composio.enabled {
+            (
+                config.composio.api_key.as_deref(),
+                Some(config.composio.entity_id.as_str()),
+            )
+        } else {
+            (None, None)
+        };
+        let mut registry = tools::all_tools_with_runtime(
+            &security,
+            runtime,
+            Arc::clone(&memory),
+            composio_key,
+            composio_entity_id,
+            &config.browser,
+            &config.http_request,
+            &config.workspace_dir,
+            &config.agents,
+            config.api_key.as_deref(),
+            config,
+        );
+        registry.extend(crate::peripherals::create_peripheral_tools(&config.peripherals).await?);
+
+        if !only.is_empty() {
+            if let Some(unknown) = only
+                .iter()
+                .find(|name| !registry.iter().any(|t| t.name() == name.as_str()))
+            {
+                bail!("Unknown tool `{unknown}`");
+            }
+            registry.retain(|t| only.iter().any(|name| name == t.name()));
+        }
+
+        let observer = observability::create_shared_observer(&config.observability);
+        Ok(Self::new(registry, memory, security, observer))
+    }
+
+    fn visible(&self, tool: &dyn Tool) -> bool {
+        self.security.autonomy != AutonomyLevel::ReadOnly
+            || tool.read_only()
+            || SELF_GATED_TOOLS.contains(&tool.name())
+    }
+
+    /// Handle one decoded message (or batch). Notifications get no reply.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        if let Value::Array(batch) = message {
+            let mut replies = Vec::new();
+            for message in batch {
+                replies.extend(Box::pin(self.handle(message)).await);
+            }
+            return (!replies.is_empty()).then_some(Value::Array(replies));
+        }
+        match Message::parse(message) {
+            Some(Message::Request { id, method, params }) => {
+                Some(match self.dispatch(&method, params).await {
+                    Ok(result) => protocol::response(id, result),
+                    Err(error) => protocol::error_response(id, &error),
+                })
+            }
+            Some(Message::Notification { .. } | Message::Response { .. }) => None,
+            None => Some(protocol::error_response(
+                Value::Null,
+                &RpcError::new(protocol::INVALID_REQUEST, "not a JSON-RPC 2.0 message"),
+            )),
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool(params).await,
+            "resources/list" => self.list_resources().await,
+            "resources/templates/list" => Ok(json!({"resourceTemplates": []})),
+            "resources/read" => self.read_resource(&params).await,
+            other => Err(RpcError::new(
+                protocol::METHOD_NOT_FOUND,
+                format!("unknown method {other}"),
+            )),
+        }
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .unwrap_or(PROTOCOL_VERSION);
+        let version = if SUPPORTED_VERSIONS.contains(&requested) {
+            requested
+        } else {
+            PROTOCOL_VERSION
+        };
+        let autonomy = match self.security.autonomy {
+            AutonomyLevel::ReadOnly => "read-only: only tools that observe are available",
+            AutonomyLevel::Supervised => {
+                "supervised: risky tool calls need approved=true, after asking the user"
+            }
+            AutonomyLevel::Full => "full",
+        };
+        json!({
+            "protocolVersion": version,
+            "capabilities": {"tools": {}, "resources": {}},
+            "serverInfo": {"name": "zeroclaw", "version": env!("CARGO_PKG_VERSION")},
+            "instructions": format!(
+                "ZeroClaw tools run inside its workspace sandbox and security policy (autonomy {autonomy}). \
+                 Long-term memory entries are resources under {MEMORY_SCHEME}<key>."
+            ),
+        })
+    }
+
+    fn list_tools(&self) -> Value {
+        let tools: Vec<Value> = self
+            .tools
+            .iter()
+            .filter(|tool| self.visible(tool.as_ref()))
+            .map(|tool| {
+                let spec = tool.spec();
+                json!({
+                    "name": spec.name,
+                    "description": spec.description,
+                    "inputSchema": spec.parameters,
+                    "annotations": {"readOnlyHint": tool.read_only()},
+                })
+            })
+            .collect();
+        json!({"tools": tools})
+    }
+
+    async fn call_tool(&self, params: Value) -> Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(protocol::INVALID_PARAMS, "missing tool name"))?;
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name() == name)
+            .ok_or_else(|| {
+                RpcError::new(protocol::INVALID_PARAMS, format!("unknown tool {name}"))
+            })?;
+        if !self.visible(tool.as_ref()) {
+            return Ok(tool_result("Action blocked: autonomy is read-only", true));
+        }
+        let arguments = match params.get("arguments") {
+            None | Some(Value::Null) => json!({}),
+            Some(arguments) => arguments.clone(),
+        };
+
+        self.observer.record_event(&ObserverEvent::ToolCallStart {
+            tool: name.to_string(),
+        });
+        let started = Instant::now();
+        let (text, is_error) = match tools::execute_validated(tool.as_ref(), arguments).await {
+            Ok(result) if result.success => (result.output, false),
+            Ok(result) => (
+                result
+                    .error
+                    .filter(|e| !e.is_empty())
+                    .unwrap_or(result.output),
+                true,
+            ),
+            Err(e) => (format!("{e:#}"), true),
+        };
+        self.observer.record_event(&ObserverEvent::ToolCall {
+            tool: name.to_string(),
+            duration: started.elapsed(),
+            success: !is_error,
+        });
+        Ok(tool_result(&text, is_error))
+    }
+
+    async fn list_resources(&self) -> Result<Value, RpcError> {
+        let entries = self
+            .memory
+            .list(None)
+            .await
+            .map_err(|e| RpcError::new(protocol::INTERNAL_ERROR, format!("{e:#}")))?;
+        let resources: Vec<Value> = entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "uri": format!("{MEMORY_SCHEME}{}", entry.key),
+                    "name": entry.key,
+                    "description": format!("{} memory, {}", entry.category, entry.timestamp),
+                    "mimeType": "text/plain",
+                })
+            })
+            .collect();
+        Ok(json!({"resources": resources}))
+    }
+
+    async fn read_resource(&self, params: &Value) -> Result<Value, RpcError> {
+        let uri = params
+            .get("uri")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(protocol::INVALID_PARAMS, "missing uri"))?;
+        let not_found = || RpcError::new(RESOURCE_NOT_FOUND, format!("Resource not found: {uri}"));
+        let key = uri.strip_prefix(MEMORY_SCHEME).ok_or_else(not_found)?;
+        let entry = self
+            .memory
+            .get(key)
+            .await
+            .map_err(|e| RpcError::new(protocol::INTERNAL_ERROR, format!("{e:#}")))?
+            .ok_or_else(not_found)?;
+        Ok(json!({"contents": [{
+            "uri": uri,
+            "mimeType": "text/plain",
+            "text": entry.content,
+        }]}))
+    }
+}
+
+fn tool_result(text: &str, is_error: bool) -> Value {
+    json!({
+        "content": [{"type": "text", "text": text}],
+        "isError": is_error,
+    })
+}
+
+/// Serve newline-delimited JSON-RPC until `reader` closes.
+pub async fn serve_streams(
+    server: &ToolServer,
+    reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+) -> Result<()> {
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str(line) {
+            Ok(message) => server.handle(message).await,
+            Err(e) => Some(protocol::error_response(
+                Value::Null,
+                &RpcError::new(protocol::PARSE_ERROR, e.to_string()),
+            )),
+        };
+        if let Some(reply) = reply {
+            let mut out = serde_json::to_vec(&reply)?;
+            out.push(b'\n');
+            writer.write_all(&out).await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Serve on stdin/stdout for clients that spawn `zeroclaw mcp serve`.
+pub async fn serve_stdio(server: &ToolServer) -> Result<()> {
+    serve_streams(server, tokio::io::stdin(), tokio::io::stdout()).await
+}
+
+#[derive(Clone)]
+struct HttpState {
+    server: Arc<ToolServer>,
+    auth: Arc<PairingGuard>,
+}
+
+/// Serve streamable HTTP at `POST /mcp`. Requests need a gateway token
+/// with the `mcp` scope unless `gateway.require_pairing` is off.
+pub async fn serve_http(server: ToolServer, config: &Config, host: &str, port: u16) -> Result<()> {
+    if is_public_bind(host) && !config.gateway.allow_public_bind {
+        bail!(
+            "Refusing to expose MCP tools on {host}; use 127.0.0.1 or set \
+             [gateway] allow_public_bind = true"
+        );
+    }
+    let tokens = TokenStore::for_config(config)?;
+    if config.gateway.require_pairing && !tokens.has_active() {
+        bail!(
+            "No gateway tokens exist; create one first: \
+             zeroclaw gateway tokens create <label> --scope mcp"
+        );
+    }
+    let state = HttpState {
+        server: Arc::new(server),
+        auth: Arc::new(PairingGuard::with_store(
+            config.gateway.require_pairing,
+            tokens,
+        )),
+    };
+    let app = axum::Router::new()
+        .route(
+            "/mcp",
+            axum::routing::post(handle_post)
+                .get(method_not_allowed)
+                .delete(method_not_allowed),
+        )
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind((host, port))
+        .await
+        .with_context(|| format!("Failed to bind {host}:{port}"))?;
+    eprintln!(
+        "🔌 ZeroClaw MCP server on http://{}/mcp",
+        listener.local_addr()?
+    );
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+async fn method_not_allowed() -> StatusCode {
+    StatusCode::METHOD_NOT_ALLOWED
+}
+
+/// Browsers send `Origin`; only local pages may call a local server.
+fn origin_allowed(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    let host = origin
+        .split_once("://")
+        .map_or(origin, |(_, rest)| rest)
+        .trim_end_matches('/');
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.split_once(']'))
+        .map_or_else(|| host.split(':').next().unwrap_or(host), |(h, _)| h);
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+async fn handle_post(
+    axum::extract::State(state): axum::extract::State<HttpState>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    if !origin_allowed(&headers) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|auth| auth.strip_prefix("Bearer "))
+        .unwrap_or("")
+        .trim();
+    match state.auth.authorize(token, TokenScope::Mcp) {
+        Ok(()) => {}
+        Err(TokenError::MissingScope(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Err(_) => return StatusCode::UNAUTHORIZED.into_response(),
+    }
+
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let error = RpcError::new(protocol::PARSE_ERROR, e.to_string());
+            return (
+                StatusCode::BAD_REQUEST,
+                axum::Json(protocol::error_response(Value::Null, &error)),
+            )
+                .into_response();
+        }
+    };
+    match state.server.handle(message).await {
+        Some(reply) => axum::Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::client::McpClient;
+    use crate::mcp::transport::StdioTransport;
+    use crate::memory::{MemoryCategory, SqliteMemory};
+    use crate::observability::NoopObserver;
+    use crate::tools::{FileReadTool, MemoryRecallTool, MemoryStoreTool};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    async fn server(tmp: &TempDir, autonomy: AutonomyLevel) -> ToolServer {
+        let security = Arc::new(SecurityPolicy {
+            autonomy,
+            workspace_dir: tmp.path().to_path_buf(),
+            ..SecurityPolicy::default()
+        });
+        let memory: Arc<dyn Memory> = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        memory
+            .store("favorite_language", "Rust", MemoryCategory::Core)
+            .await
+            .unwrap();
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(FileReadTool::new(Arc::clone(&security))),
+            Box::new(MemoryStoreTool::new(Arc::clone(&memory))),
+            Box::new(MemoryRecallTool::new(Arc::clone(&memory))),
+        ];
+        ToolServer::new(tools, memory, security, Arc::new(NoopObserver))
+    }
+
+    #[tokio::test]
+    async fn our_client_can_list_and_call_served_tools() {
+        let tmp = TempDir::new().unwrap();
+        let server = server(&tmp, AutonomyLevel::Full).await;
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_io);
+        tokio::spawn(async move { serve_streams(&server, server_read, server_write).await });
+
+        let (read, write) = tokio::io::split(client_io);
+        let transport = StdioTransport::from_streams(read, write, Duration::from_secs(5));
+        let client = McpClient::initialize(Box::new(transport)).await.unwrap();
+        assert!(client.server_label().starts_with("zeroclaw "));
+
+        let tools = client.tools().await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["file_read", "memory_store", "memory_recall"]);
+        assert!(tools[0].read_only);
+        assert!(!tools[1].read_only);
+        assert_eq!(
+            tools[1].input_schema,
+            MemoryStoreTool::new(Arc::new(SqliteMemory::new(tmp.path()).unwrap()))
+                .spec()
+                .parameters
+        );
+
+        let stored = client
+            .call_tool(
+                "memory_store",
+                json!({"key": "editor", "content": "helix", "category": "core"}),
+            )
+            .await
+            .unwrap();
+        assert!(!stored.is_error, "{}", stored.output);
+        let recalled = client
+            .call_tool("memory_recall", json!({"query": "helix"}))
+            .await
+            .unwrap();
+        assert!(recalled.output.contains("helix"));
+    }
+
+    #[tokio::test]
+    async fn read_only_autonomy_hides_and_blocks_writing_tools() {
+        let tmp = TempDir::new().unwrap();
+        let server = server(&tmp, AutonomyLevel::ReadOnly).await;
+
+        let listed = server.list_tools();
+        let names: Vec<&str> = listed["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["file_read", "memory_recall"]);
+        assert_eq!(listed["tools"][0]["annotations"]["readOnlyHint"], true);
+
+        let reply = server
+            .handle(protocol::request(
+                1,
+                "tools/call",
+                json!({"name": "memory_store", "arguments": {"key": "k", "content": "v"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reply["result"]["isError"], true);
+        assert!(reply["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("read-only"));
+
+        let unknown = server
+            .handle(protocol::request(2, "tools/call", json!({"name": "nope"})))
+            .await
+            .unwrap();
+        assert_eq!(unknown["error"]["code"], protocol::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn memory_entries_are_resources() {
+        let tmp = TempDir::new().unwrap();
+        let server = server(&tmp, AutonomyLevel::ReadOnly).await;
+
+        let listed = server
+            .handle(protocol::request(1, "resources/list", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(
+            listed["result"]["resources"][0]["uri"],
+            "memory://favorite_language"
+        );
+
+        let read = server
+            .handle(protocol::request(
+                2,
+                "resources/read",
+                json!({"uri": "memory://favorite_language"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(read["result"]["contents"][0]["text"], "Rust");
+
+        let missing = server
+            .handle(protocol::request(
+                3,
+                "resources/read",
+                json!({"uri": "memory://nope"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(missing["error"]["code"], RESOURCE_NOT_FOUND);
+
+        // Notifications get no reply; batches get one per request.
+        assert!(server
+            .handle(protocol::notification(
+                "notifications/initialized",
+                json!({})
+            ))
+            .await
+            .is_none());
+        let batch = server
+            .handle(json!([
+                protocol::request(4, "ping", json!({})),
+                protocol::notification("notifications/cancelled", json!({})),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(batch.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn only_local_origins_are_allowed() {
+        let mut headers = HeaderMap::new();
+        assert!(origin_allowed(&headers));
+        for (origin, allowed) in [
+            ("http://localhost:5173", true),
+            ("http://127.0.0.1", true),
+            ("http://[::1]:8080", true),
+            ("https://evil.example", false),
+            ("http://localhost.evil.example", false),
+        ] {
+            headers.insert(header::ORIGIN, origin.parse().unwrap());
+            assert_eq!(origin_allowed(&headers), allowed, "{origin}");
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! A tool advertised by an MCP server, exposed as a regular [`Tool`].
+
+use super::client::McpToolInfo;
+use super::McpServer;
+use crate::security::SecurityPolicy;
+use crate::tools::traits::{Tool, ToolResult};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// OpenAI-compatible function names are limited to 64 characters.
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// Approval flag used when the tool already takes an `approved` argument.
+const FALLBACK_APPROVAL_ARG: &str = "zeroclaw_approved";
+
+/// `mcp_<server>_<tool>`, restricted to `[A-Za-z0-9_-]`.
+pub fn tool_name(server: &str, tool: &str) -> String {
+    format!("mcp_{server}_{tool}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_TOOL_NAME_LEN)
+        .collect()
+}
+
+pub struct McpTool {
+    server: Arc<McpServer>,
+    info: McpToolInfo,
+    name: String,
+    description: String,
+    security: Arc<SecurityPolicy>,
+}
+
+impl McpTool {
+    pub fn new(server: Arc<McpServer>, info: McpToolInfo, security: Arc<SecurityPolicy>) -> Self {
+        let name = tool_name(server.name(), &info.name);
+        let description = if info.description.is_empty() {
+            format!("{} (MCP server `{}`)", info.name, server.name())
+        } else {
+            format!("{} (MCP server `{}`)", info.description, server.name())
+        };
+        Self {
+            server,
+            info,
+            name,
+            description,
+            security,
+        }
+    }
+
+    /// Our approval flag: `approved`, unless the tool has an argument of
+    /// that name, which is then passed through untouched.
+    fn approval_arg(&self) -> &'static str {
+        if self
+            .info
+            .input_schema
+            .pointer("/properties/approved")
+            .is_some()
+        {
+            FALLBACK_APPROVAL_ARG
+        } else {
+            "approved"
+        }
+    }
+
+    /// The server's `readOnlyHint` is not trusted: every call is a device
+    /// action under the security policy, then counts against the rate limit.
+    fn authorize(&self, approved: bool) -> Result<(), String> {
+        self.security
+            .validate_device_action(approved)
+            .map_err(|reason| reason.replace("approved=", &format!("{}=", self.approval_arg())))?;
+        if !self.security.record_action() {
+            return Err("Action blocked: rate limit exceeded".into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        let mut schema = self.info.input_schema.clone();
+        if let Some(properties) = schema
+            .as_object_mut()
+            .map(|s| s.entry("properties").or_insert_with(|| json!({})))
+            .and_then(Value::as_object_mut)
+        {
+            properties.insert(
+                self.approval_arg().to_string(),
+                json!({
+                    "type": "boolean",
+                    "description": "Set true to explicitly approve the call in supervised mode",
+                    "default": false
+                }),
+            );
+        }
+        schema
+    }
+
+    async fn execute(&self, mut args: Value) -> anyhow::Result<ToolResult> {
+        let approved = args
+            .as_object_mut()
+            .and_then(|a| a.remove(self.approval_arg()))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if let Err(reason) = self.authorize(approved) {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(reason),
+            });
+        }
+
+        if args.is_null() {
+            args = json!({});
+        }
+        match self.server.call_tool(&self.info.name, args).await {
+            Ok(outcome) if outcome.is_error => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(outcome.output),
+            }),
+            Ok(outcome) => Ok(ToolResult {
+                success: true,
+                output: outcome.output,
+                error: None,
+            }),
+            Err(e) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("MCP server `{}`: {e:#}", self.server.name())),
+            }),
+        }
+    }
+}
@@ -0,0 +1,579 @@
+//! MCP transports: newline-delimited JSON over a subprocess's stdio, and
+//! streamable HTTP (JSON or server-sent-event responses to POSTs).
+
+use super::protocol::{self, Message, RpcError};
+use crate::config::McpServerConfig;
+use crate::security::secret_sources::SecretSource;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// A connection to one MCP server.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Send a request and wait for its result.
+    async fn request(&self, method: &str, params: Value) -> Result<Value>;
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()>;
+
+    /// Record the protocol revision agreed in `initialize`.
+    fn set_protocol_version(&self, _version: &str) {}
+
+    /// `false` once the server went away; the connection must be rebuilt.
+    fn is_alive(&self) -> bool {
+        true
+    }
+
+    /// Whether the server announced `notifications/tools/list_changed`
+    /// since the last call.
+    fn take_tools_changed(&self) -> bool {
+        false
+    }
+
+    /// End the session.
+    async fn close(&self) {}
+}
+
+/// Resolve `env:`/`file:`/`cmd:` references; other values pass through.
+fn resolve_value(value: &str, base_dir: &Path) -> Result<String> {
+    match SecretSource::parse(value) {
+        Some(source) => source.resolve(base_dir),
+        None => Ok(value.to_string()),
+    }
+}
+
+fn connection_closed() -> RpcError {
+    RpcError::new(protocol::INTERNAL_ERROR, "connection closed")
+}
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, RpcError>>>>>;
+type SharedWriter = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+
+async fn write_message(writer: &SharedWriter, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    let mut writer = writer.lock().await;
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// JSON-RPC over a byte stream, one message per line. Usually a child
+/// process's stdin/stdout, but any reader/writer pair works.
+pub struct StdioTransport {
+    writer: SharedWriter,
+    pending: Pending,
+    next_id: AtomicU64,
+    closed: Arc<AtomicBool>,
+    tools_changed: Arc<AtomicBool>,
+    reader: JoinHandle<()>,
+    child: Option<Mutex<tokio::process::Child>>,
+    timeout: Duration,
+}
+
+impl StdioTransport {
+    /// Spawn the server's `command` with `args` and `env`, working in the
+    /// workspace. Its stderr is forwarded to the debug log.
+    pub fn spawn(config: &McpServerConfig, workspace_dir: &Path) -> Result<Self> {
+        let command = config
+            .command
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+            .with_context(|| format!("MCP server `{}` has no command", config.name))?;
+        let mut cmd = tokio::process::Command::new(shellexpand::tilde(command).as_ref());
+        cmd.args(&config.args)
+            .current_dir(workspace_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        for (key, value) in &config.env {
+            let value = resolve_value(value, workspace_dir)
+                .with_context(|| format!("mcp server `{}` env {key}", config.name))?;
+            cmd.env(key, value);
+        }
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to start MCP server `{}` ({command})", config.name))?;
+        let stdin = child.stdin.take().context("child stdin unavailable")?;
+        let stdout = child.stdout.take().context("child stdout unavailable")?;
+        if let Some(stderr) = child.stderr.take() {
+            let name = config.name.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!(server = %name, "mcp stderr: {line}");
+                }
+            });
+        }
+
+        let mut transport = Self::from_streams(
+            stdout,
+            stdin,
+            Duration::from_secs(config.timeout_secs.max(1)),
+        );
+        transport.child = Some(Mutex::new(child));
+        Ok(transport)
+    }
+
+    /// Speak JSON-RPC over an existing stream pair.
+    pub fn from_streams(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+        timeout: Duration,
+    ) -> Self {
+        let writer: SharedWriter = Arc::new(tokio::sync::Mutex::new(Box::new(writer)));
+        let pending: Pending = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let tools_changed = Arc::new(AtomicBool::new(false));
+        let reader = tokio::spawn(read_loop(
+            reader,
+            Arc::clone(&writer),
+            Arc::clone(&pending),
+            Arc::clone(&closed),
+            Arc::clone(&tools_changed),
+        ));
+        Self {
+            writer,
+            pending,
+            next_id: AtomicU64::new(1),
+            closed,
+            tools_changed,
+            reader,
+            child: None,
+            timeout,
+        }
+    }
+}
+
+async fn read_loop(
+    reader: impl AsyncRead + Unpin,
+    writer: SharedWriter,
+    pending: Pending,
+    closed: Arc<AtomicBool>,
+    tools_changed: Arc<AtomicBool>,
+) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some(message) = serde_json::from_str(line).ok().and_then(Message::parse) else {
+            tracing::debug!("Ignoring malformed MCP message: {line}");
+            continue;
+        };
+        match message {
+            Message::Response { id, result } => {
+                if let Some(sender) = id.as_u64().and_then(|id| pending.lock().remove(&id)) {
+                    let _ = sender.send(result);
+                }
+            }
+            Message::Request { id, method, .. } => {
+                // Servers may ping us; we offer no sampling or roots.
+                let reply = if method == "ping" {
+                    protocol::response(id, json!({}))
+                } else {
+                    protocol::error_response(
+                        id,
+                        &RpcError::new(
+                            protocol::METHOD_NOT_FOUND,
+                            format!("unsupported method {method}"),
+                        ),
+                    )
+                };
+                if write_message(&writer, &reply).await.is_err() {
+                    break;
+                }
+            }
+            Message::Notification { method, .. } => {
+                if method == "notifications/tools/list_changed" {
+                    tools_changed.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    closed.store(true, Ordering::SeqCst);
+    for (_, sender) in pending.lock().drain() {
+        let _ = sender.send(Err(connection_closed()));
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(id, tx);
+        // Checked after registering so the reader cannot close in between
+        // and leave this request waiting for the timeout.
+        if self.closed.load(Ordering::SeqCst) {
+            self.pending.lock().remove(&id);
+            return Err(connection_closed().into());
+        }
+
+        if let Err(e) = write_message(&self.writer, &protocol::request(id, method, params)).await {
+            self.pending.lock().remove(&id);
+            self.closed.store(true, Ordering::SeqCst);
+            return Err(e.context("MCP server stdin closed"));
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(result)) => result.map_err(Into::into),
+            Ok(Err(_)) => Err(connection_closed().into()),
+            Err(_) => {
+                self.pending.lock().remove(&id);
+                let _ = self
+                    .notify(
+                        "notifications/cancelled",
+                        json!({"requestId": id, "reason": "timeout"}),
+                    )
+                    .await;
+                bail!("{method} timed out after {}s", self.timeout.as_secs())
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        write_message(&self.writer, &protocol::notification(method, params)).await
+    }
+
+    fn is_alive(&self) -> bool {
+        if self.closed.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.child
+            .as_ref()
+            .is_none_or(|child| matches!(child.lock().try_wait(), Ok(None)))
+    }
+
+    fn take_tools_changed(&self) -> bool {
+        self.tools_changed.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+        if let Some(child) = &self.child {
+            let _ = child.lock().start_kill();
+        }
+    }
+}
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const PROTOCOL_HEADER: &str = "mcp-protocol-version";
+
+/// Streamable HTTP: every message is a POST to one endpoint, answered with
+/// either a JSON body or an SSE stream carrying the response.
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: reqwest::header::HeaderMap,
+    session_id: Mutex<Option<String>>,
+    protocol_version: Mutex<Option<String>>,
+    next_id: AtomicU64,
+    expired: AtomicBool,
+    tools_changed: AtomicBool,
+}
+
+impl HttpTransport {
+    pub fn new(config: &McpServerConfig, workspace_dir: &Path) -> Result<Self> {
+        use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+
+        let url = config
+            .url
+            .clone()
+            .with_context(|| format!("MCP server `{}` has no url", config.name))?;
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &config.headers {
+            let value = resolve_value(value, workspace_dir)
+                .with_context(|| format!("mcp server `{}` header {name}", config.name))?;
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("invalid header name `{name}`"))?,
+                HeaderValue::from_str(&value)
+                    .with_context(|| format!("invalid value for header `{name}`"))?,
+            );
+        }
+        if let Some(token) = config.bearer_token.as_deref().filter(|t| !t.is_empty()) {
+            let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .context("invalid bearer_token")?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()?;
+        Ok(Self {
+            client,
+            url,
+            headers,
+            session_id: Mutex::new(None),
+            protocol_version: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+            expired: AtomicBool::new(false),
+            tools_changed: AtomicBool::new(false),
+        })
+    }
+
+    async fn post(&self, message: &Value) -> Result<reqwest::Response> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
+            .json(message);
+        if let Some(session) = self.session_id.lock().clone() {
+            request = request.header(SESSION_HEADER, session);
+        }
+        if let Some(version) = self.protocol_version.lock().clone() {
+            request = request.header(PROTOCOL_HEADER, version);
+        }
+
+        let response = request.send().await?;
+        if let Some(session) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock() = Some(session.to_string());
+        }
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND && self.session_id.lock().is_some() {
+            self.expired.store(true, Ordering::SeqCst);
+            bail!("MCP session expired");
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!(
+                "MCP server returned {status}: {}",
+                crate::providers::sanitize_api_error(&body)
+            );
+        }
+        Ok(response)
+    }
+
+    /// Pick our response out of the messages in a reply, noting
+    /// notifications on the way.
+    fn take_response(&self, id: u64, message: Value) -> Option<Result<Value, RpcError>> {
+        match Message::parse(message)? {
+            Message::Response { id: got, result } if got.as_u64() == Some(id) => Some(result),
+            Message::Notification { method, .. }
+                if method == "notifications/tools/list_changed" =>
+            {
+                self.tools_changed.store(true, Ordering::SeqCst);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Split the next complete event off an SSE buffer and return its data.
+fn next_sse_event(buffer: &mut String) -> Option<String> {
+    let end = buffer.find("\n\n")?;
+    let event: String = buffer.drain(..end + 2).collect();
+    let data: Vec<&str> = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    Some(data.join("\n"))
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let response = self.post(&protocol::request(id, method, params)).await?;
+        let is_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+        if !is_stream {
+            let body: Value = response.json().await.context("invalid MCP response")?;
+            let messages = match body {
+                Value::Array(batch) => batch,
+                single => vec![single],
+            };
+            for message in messages {
+                if let Some(result) = self.take_response(id, message) {
+                    return result.map_err(Into::into);
+                }
+            }
+            bail!("MCP server sent no response to {method}");
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+        while let Some(chunk) = stream.next().await {
+            buffer.push_str(&String::from_utf8_lossy(&chunk?).replace("\r\n", "\n"));
+            while let Some(data) = next_sse_event(&mut buffer) {
+                let Ok(message) = serde_json::from_str(&data) else {
+                    continue;
+                };
+                if let Some(result) = self.take_response(id, message) {
+                    return result.map_err(Into::into);
+                }
+            }
+        }
+        bail!("MCP event stream ended without a response to {method}")
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        self.post(&protocol::notification(method, params)).await?;
+        Ok(())
+    }
+
+    fn set_protocol_version(&self, version: &str) {
+        *self.protocol_version.lock() = Some(version.to_string());
+    }
+
+    fn is_alive(&self) -> bool {
+        !self.expired.load(Ordering::SeqCst)
+    }
+
+    fn take_tools_changed(&self) -> bool {
+        self.tools_changed.swap(false, Ordering::SeqCst)
+    }
+
+    async fn close(&self) {
+        let Some(session) = self.session_id.lock().take() else {
+            return;
+        };
+        let _ = self
+            .client
+            .delete(&self.url)
+            .headers(self.headers.clone())
+            .header(SESSION_HEADER, session)
+            .send()
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_events_are_split_and_data_lines_joined() {
+        let mut buffer = String::from("event: message\ndata: {\"a\":\ndata: 1}\n\nid: 2\ndata: x");
+        assert_eq!(next_sse_event(&mut buffer).as_deref(), Some("{\"a\":\n1}"));
+        assert_eq!(next_sse_event(&mut buffer), None);
+        buffer.push_str("\n\n");
+        assert_eq!(next_sse_event(&mut buffer).as_deref(), Some("x"));
+        assert!(buffer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stdio_transport_matches_responses_and_answers_pings() {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        let (client_read, client_write) = tokio::io::split(client_io);
+        let transport =
+            StdioTransport::from_streams(client_read, client_write, Duration::from_secs(5));
+
+        let (server_read, mut server_write) = tokio::io::split(server_io);
+        let server = tokio::spawn(async move {
+            let mut lines = BufReader::new(server_read).lines();
+            let request: Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            let ping = protocol::request(99, "ping", json!({}));
+            let changed = protocol::notification("notifications/tools/list_changed", json!({}));
+            let reply =
+                protocol::response(request["id"].clone(), json!({"echo": request["params"]}));
+            for message in [ping, changed, reply] {
+                server_write
+                    .write_all(format!("{message}\n").as_bytes())
+                    .await
+                    .unwrap();
+            }
+            let pong: Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            assert_eq!(pong["id"], 99);
+            assert_eq!(pong["result"], json!({}));
+        });
+
+        let result = transport.request("echo", json!({"x": 1})).await.unwrap();
+        assert_eq!(result, json!({"echo": {"x": 1}}));
+        server.await.unwrap();
+        assert!(transport.take_tools_changed());
+        assert!(!transport.take_tools_changed());
+
+        // The server side is gone, so the connection reports itself dead.
+        assert!(transport.request("echo", json!({})).await.is_err());
+        assert!(!transport.is_alive());
+    }
+
+    #[tokio::test]
+    async fn http_transport_keeps_the_session_and_reads_event_streams() {
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::response::IntoResponse;
+
+        async fn handle(
+            headers: HeaderMap,
+            axum::Json(body): axum::Json<Value>,
+        ) -> axum::response::Response {
+            let Some(Message::Request { id, method, .. }) = Message::parse(body) else {
+                return StatusCode::ACCEPTED.into_response();
+            };
+            if method == "initialize" {
+                let reply = protocol::response(id, json!({"protocolVersion": "2025-06-18"}));
+                return ([(SESSION_HEADER, "s-1")], axum::Json(reply)).into_response();
+            }
+            let session = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok());
+            let version = headers.get(PROTOCOL_HEADER).and_then(|v| v.to_str().ok());
+            if session != Some("s-1") || version != Some("2025-06-18") {
+                return StatusCode::BAD_REQUEST.into_response();
+            }
+            let changed = protocol::notification("notifications/tools/list_changed", json!({}));
+            let reply = protocol::response(id, json!({"method": method}));
+            (
+                [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+                format!("event: message\r\ndata: {changed}\r\n\r\ndata: {reply}\r\n\r\n"),
+            )
+                .into_response()
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new().route("/mcp", axum::routing::post(handle));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config: McpServerConfig = toml::from_str(&format!(
+            "name = \"remote\"\ntransport = \"http\"\nurl = \"http://{addr}/mcp\""
+        ))
+        .unwrap();
+        let transport = HttpTransport::new(&config, Path::new(".")).unwrap();
+
+        transport.request("initialize", json!({})).await.unwrap();
+        transport.set_protocol_version("2025-06-18");
+        transport
+            .notify("notifications/initialized", json!({}))
+            .await
+            .unwrap();
+        let result = transport.request("tools/list", json!({})).await.unwrap();
+        assert_eq!(result, json!({"method": "tools/list"}));
+        assert!(transport.take_tools_changed());
+        assert!(transport.is_alive());
+    }
+}
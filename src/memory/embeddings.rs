@@ -9,6 +9,12 @@ pub trait EmbeddingProvider: Send + Sync {
     /// Embedding dimensions
     fn dimensions(&self) -> usize;
 
+    /// Model name; part of the embedding cache key, so vectors from one
+    /// model are never served for another
+    fn model(&self) -> &str {
+        ""
+    }
+
     /// Embed a batch of texts into vectors
     async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>>;
 
@@ -101,6 +107,10 @@ impl EmbeddingProvider for OpenAiEmbedding {
         self.dims
     }
 
+    fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
     async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
         if texts.is_empty() {
             return Ok(Vec::new());
@@ -152,8 +162,278 @@ impl EmbeddingProvider for OpenAiEmbedding {
     }
 }
 
+// ── Ollama embedding provider (local HTTP) ───────────────────
+
+pub struct OllamaEmbedding {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    dims: usize,
+}
+
+impl OllamaEmbedding {
+    pub fn new(base_url: &str, model: &str, dims: usize) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            dims,
+        }
+    }
+
+    fn embed_url(&self) -> String {
+        format!("{}/api/embed", self.base_url)
+    }
+
+    fn legacy_embeddings_url(&self) -> String {
+        format!("{}/api/embeddings", self.base_url)
+    }
+
+    /// Older Ollama releases only expose `/api/embeddings`, which takes a
+    /// single `prompt` per request.
+    async fn embed_legacy(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            let body = serde_json::json!({
+                "model": self.model,
+                "prompt": text,
+            });
+
+            let resp = self
+                .client
+                .post(self.legacy_embeddings_url())
+                .json(&body)
+                .send()
+                .await?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let text = resp.text().await.unwrap_or_default();
+                anyhow::bail!("Ollama embedding error {status}: {text}");
+            }
+
+            let json: serde_json::Value = resp.json().await?;
+            let embedding = json
+                .get("embedding")
+                .and_then(|e| e.as_array())
+                .ok_or_else(|| anyhow::anyhow!("Invalid Ollama response: missing 'embedding'"))?;
+            embeddings.push(json_to_vec(embedding));
+        }
+        Ok(embeddings)
+    }
+}
+
+fn json_to_vec(values: &[serde_json::Value]) -> Vec<f32> {
+    #[allow(clippy::cast_possible_truncation)]
+    values
+        .iter()
+        .filter_map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbedding {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dims
+    }
+
+    fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
+    async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = serde_json::json!({
+            "model": self.model,
+            "input": texts,
+        });
+
+        let resp = self
+            .client
+            .post(self.embed_url())
+            .json(&body)
+            .send()
+            .await?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return self.embed_legacy(texts).await;
+        }
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Ollama embedding error {status}: {text}");
+        }
+
+        let json: serde_json::Value = resp.json().await?;
+        let data = json
+            .get("embeddings")
+            .and_then(|d| d.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Invalid Ollama response: missing 'embeddings'"))?;
+
+        data.iter()
+            .map(|item| {
+                item.as_array()
+                    .map(|v| json_to_vec(v))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid Ollama embedding item"))
+            })
+            .collect()
+    }
+}
+
+// ── In-process CPU embedding provider (offline) ──────────────
+
+/// Sentence-embedding models supported by the in-process provider:
+/// (config name, output dimensions).
+const LOCAL_MODELS: &[(&str, usize)] = &[
+    ("all-minilm-l6-v2", 384),
+    ("bge-small-en-v1.5", 384),
+    ("bge-base-en-v1.5", 768),
+    ("nomic-embed-text-v1.5", 768),
+    ("multilingual-e5-small", 384),
+];
+
+/// Default model for `embedding_provider = "local"` when the configured
+/// model is not one of [`LOCAL_MODELS`] (e.g. the OpenAI default).
+pub const DEFAULT_LOCAL_MODEL: &str = "all-minilm-l6-v2";
+
+/// Resolve a configured model name to a supported local model and its dimensions.
+pub fn local_model_spec(model: &str) -> Option<(&'static str, usize)> {
+    let wanted = model
+        .trim()
+        .trim_start_matches("sentence-transformers/")
+        .trim_start_matches("BAAI/")
+        .trim_start_matches("nomic-ai/")
+        .trim_start_matches("intfloat/")
+        .to_ascii_lowercase();
+    LOCAL_MODELS
+        .iter()
+        .find(|(name, _)| *name == wanted)
+        .copied()
+}
+
+/// Directory where local embedding model weights are cached.
+///
+/// Honors `FASTEMBED_CACHE_DIR` so air-gapped hosts can point at a
+/// pre-seeded directory; otherwise `~/.zeroclaw/models`.
+pub fn local_model_cache_dir() -> std::path::PathBuf {
+    if let Ok(dir) = std::env::var("FASTEMBED_CACHE_DIR") {
+        if !dir.trim().is_empty() {
+            return std::path::PathBuf::from(dir);
+        }
+    }
+    directories::UserDirs::new().map_or_else(
+        || std::path::PathBuf::from(".zeroclaw").join("models"),
+        |u| u.home_dir().join(".zeroclaw").join("models"),
+    )
+}
+
+/// Runs a small sentence-embedding model on CPU inside the process.
+/// The model is loaded lazily on first use and never leaves the host.
+#[cfg(feature = "embeddings-local")]
+pub struct LocalEmbedding {
+    model_name: &'static str,
+    dims: usize,
+    cache_dir: std::path::PathBuf,
+    model: std::sync::Arc<parking_lot::Mutex<Option<fastembed::TextEmbedding>>>,
+}
+
+#[cfg(feature = "embeddings-local")]
+impl LocalEmbedding {
+    pub fn new(model: &str) -> Self {
+        let (model_name, dims) = local_model_spec(model).unwrap_or_else(|| {
+            tracing::warn!("Unknown local embedding model '{model}', using {DEFAULT_LOCAL_MODEL}");
+            local_model_spec(DEFAULT_LOCAL_MODEL).expect("default local model is listed")
+        });
+        Self {
+            model_name,
+            dims,
+            cache_dir: local_model_cache_dir(),
+            model: std::sync::Arc::new(parking_lot::Mutex::new(None)),
+        }
+    }
+
+    fn fastembed_model(name: &str) -> fastembed::EmbeddingModel {
+        use fastembed::EmbeddingModel;
+        match name {
+            "bge-small-en-v1.5" => EmbeddingModel::BGESmallENV15,
+            "bge-base-en-v1.5" => EmbeddingModel::BGEBaseENV15,
+            "nomic-embed-text-v1.5" => EmbeddingModel::NomicEmbedTextV15,
+            "multilingual-e5-small" => EmbeddingModel::MultilingualE5Small,
+            _ => EmbeddingModel::AllMiniLML6V2,
+        }
+    }
+}
+
+#[cfg(feature = "embeddings-local")]
+#[async_trait]
+impl EmbeddingProvider for LocalEmbedding {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dims
+    }
+
+    fn model(&self) -> &str {
+        self.model_name
+    }
+
+    async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let owned: Vec<String> = texts.iter().map(|t| (*t).to_string()).collect();
+        let slot = self.model.clone();
+        let model_name = self.model_name;
+        let cache_dir = self.cache_dir.clone();
+
+        // Inference is CPU-bound — keep it off the async workers.
+        tokio::task::spawn_blocking(move || {
+            let mut guard = slot.lock();
+            if guard.is_none() {
+                std::fs::create_dir_all(&cache_dir)?;
+                let options = fastembed::InitOptions::new(Self::fastembed_model(model_name))
+                    .with_cache_dir(cache_dir)
+                    .with_show_download_progress(false);
+                *guard = Some(fastembed::TextEmbedding::try_new(options)?);
+            }
+            let model = guard
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("Local embedding model failed to load"))?;
+            model.embed(owned, None)
+        })
+        .await?
+    }
+}
+
 // ── Factory ──────────────────────────────────────────────────
 
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+#[cfg(feature = "embeddings-local")]
+fn create_local_embedding(model: &str) -> Box<dyn EmbeddingProvider> {
+    Box::new(LocalEmbedding::new(model))
+}
+
+#[cfg(not(feature = "embeddings-local"))]
+fn create_local_embedding(_model: &str) -> Box<dyn EmbeddingProvider> {
+    tracing::warn!(
+        "embedding_provider = \"local\" requires building with --features embeddings-local; \
+         falling back to keyword-only search"
+    );
+    Box::new(NoopEmbedding)
+}
+
 pub fn create_embedding_provider(
     provider: &str,
     api_key: Option<&str>,
@@ -175,6 +455,17 @@ pub fn create_embedding_provider(
             let key = api_key.unwrap_or("");
             Box::new(OpenAiEmbedding::new(base_url, key, model, dims))
         }
+        "ollama" => Box::new(OllamaEmbedding::new(DEFAULT_OLLAMA_URL, model, dims)),
+        name if name.starts_with("ollama:") => {
+            let base_url = name.strip_prefix("ollama:").unwrap_or("");
+            let base_url = if base_url.is_empty() {
+                DEFAULT_OLLAMA_URL
+            } else {
+                base_url
+            };
+            Box::new(OllamaEmbedding::new(base_url, model, dims))
+        }
+        "local" => create_local_embedding(model),
         _ => Box::new(NoopEmbedding),
     }
 }
@@ -267,6 +558,118 @@ mod tests {
         assert_eq!(p.dimensions(), 1536);
     }
 
+    #[test]
+    fn factory_ollama_default_url() {
+        let p = create_embedding_provider("ollama", None, "nomic-embed-text", 768);
+        assert_eq!(p.name(), "ollama");
+        assert_eq!(p.dimensions(), 768);
+    }
+
+    #[test]
+    fn factory_ollama_custom_url() {
+        let p = create_embedding_provider("ollama:http://gpu-box:11434", None, "m", 1024);
+        assert_eq!(p.name(), "ollama");
+        assert_eq!(p.dimensions(), 1024);
+    }
+
+    async fn spawn_ollama(app: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn ollama_embeds_a_batch_against_a_fake_server() {
+        use axum::{routing::post, Json, Router};
+        use serde_json::{json, Value};
+
+        let app = Router::new().route(
+            "/api/embed",
+            post(|Json(body): Json<Value>| async move {
+                assert_eq!(body["model"], "nomic-embed-text");
+                let embeddings: Vec<Value> = body["input"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|text| json!([text.as_str().unwrap().len(), 0.5]))
+                    .collect();
+                Json(json!({ "embeddings": embeddings }))
+            }),
+        );
+        let base = spawn_ollama(app).await;
+        let p = OllamaEmbedding::new(&base, "nomic-embed-text", 2);
+
+        let vectors = p.embed(&["a", "bbb"]).await.unwrap();
+        assert_eq!(vectors, [vec![1.0, 0.5], vec![3.0, 0.5]]);
+    }
+
+    #[tokio::test]
+    async fn ollama_falls_back_to_legacy_endpoint_on_404() {
+        use axum::{routing::post, Json, Router};
+        use serde_json::{json, Value};
+
+        let app = Router::new().route(
+            "/api/embeddings",
+            post(|Json(body): Json<Value>| async move {
+                let len = body["prompt"].as_str().unwrap().len();
+                Json(json!({ "embedding": [len, 1.0] }))
+            }),
+        );
+        let base = spawn_ollama(app).await;
+        let p = OllamaEmbedding::new(&base, "m", 2);
+
+        let vectors = p.embed(&["ab", "cdef"]).await.unwrap();
+        assert_eq!(vectors, [vec![2.0, 1.0], vec![4.0, 1.0]]);
+    }
+
+    #[test]
+    fn ollama_urls() {
+        let p = OllamaEmbedding::new("http://localhost:11434/", "m", 768);
+        assert_eq!(p.embed_url(), "http://localhost:11434/api/embed");
+        assert_eq!(
+            p.legacy_embeddings_url(),
+            "http://localhost:11434/api/embeddings"
+        );
+    }
+
+    #[tokio::test]
+    async fn ollama_embed_empty_batch_skips_request() {
+        // Unroutable URL: an empty batch must not touch the network.
+        let p = OllamaEmbedding::new("http://127.0.0.1:9", "m", 768);
+        assert!(p.embed(&[]).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn local_model_spec_resolves_aliases() {
+        assert_eq!(
+            local_model_spec("sentence-transformers/all-MiniLM-L6-v2"),
+            Some(("all-minilm-l6-v2", 384))
+        );
+        assert_eq!(
+            local_model_spec("BAAI/bge-base-en-v1.5"),
+            Some(("bge-base-en-v1.5", 768))
+        );
+        assert_eq!(local_model_spec("text-embedding-3-small"), None);
+    }
+
+    #[cfg(not(feature = "embeddings-local"))]
+    #[test]
+    fn factory_local_without_feature_returns_noop() {
+        let p = create_embedding_provider("local", None, "all-minilm-l6-v2", 384);
+        assert_eq!(p.name(), "none");
+    }
+
+    #[cfg(feature = "embeddings-local")]
+    #[test]
+    fn factory_local_uses_model_dimensions() {
+        let p = create_embedding_provider("local", None, "text-embedding-3-small", 1536);
+        assert_eq!(p.name(), "local");
+        assert_eq!(p.dimensions(), 384);
+    }
+
     #[test]
     fn openai_trailing_slash_stripped() {
         let p = OpenAiEmbedding::new("https://api.openai.com/", "key", "model", 1536);
@@ -8,6 +8,9 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+/// Memories embedded per provider call during reindex.
+const REINDEX_BATCH_SIZE: usize = 32;
+
 /// SQLite-backed persistent memory — the brain
 ///
 /// Full-stack search engine:
@@ -144,6 +147,18 @@ impl SqliteMemory {
         }
     }
 
+    /// Embedding cache key: the text plus the provider, model and
+    /// dimensions that embedded it, so switching embedders misses the cache
+    /// instead of mixing vectors from different models.
+    fn cache_key(&self, text: &str) -> String {
+        Self::content_hash(&format!(
+            "{}\n{}\n{}\n{text}",
+            self.embedder.name(),
+            self.embedder.model(),
+            self.embedder.dimensions()
+        ))
+    }
+
     /// Deterministic content hash for embedding cache.
     /// Uses SHA-256 (truncated) instead of DefaultHasher, which is
     /// explicitly documented as unstable across Rust versions.
@@ -163,12 +178,24 @@ impl SqliteMemory {
 
     /// Get embedding from cache, or compute + cache it
     async fn get_or_compute_embedding(&self, text: &str) -> anyhow::Result<Option<Vec<f32>>> {
+        let mut results = self.get_or_compute_embeddings(&[text]).await?;
+        Ok(results.pop().flatten())
+    }
+
+    /// Batched variant of [`Self::get_or_compute_embedding`]: cache hits are
+    /// served from `embedding_cache`, all misses go to the embedder in a
+    /// single `embed` call and are written back to the cache.
+    async fn get_or_compute_embeddings(
+        &self,
+        texts: &[&str],
+    ) -> anyhow::Result<Vec<Option<Vec<f32>>>> {
         if self.embedder.dimensions() == 0 {
-            return Ok(None); // Noop embedder
+            return Ok(vec![None; texts.len()]); // Noop embedder
         }
 
-        let hash = Self::content_hash(text);
+        let hashes: Vec<String> = texts.iter().map(|t| self.cache_key(t)).collect();
         let now = Local::now().to_rfc3339();
+        let mut results: Vec<Option<Vec<f32>>> = vec![None; texts.len()];
 
         // Check cache
         {
@@ -179,21 +206,36 @@ impl SqliteMemory {
 
             let mut stmt =
                 conn.prepare("SELECT embedding FROM embedding_cache WHERE content_hash = ?1")?;
-            let cached: Option<Vec<u8>> = stmt.query_row(params![hash], |row| row.get(0)).ok();
-
-            if let Some(bytes) = cached {
-                // Update accessed_at for LRU
-                conn.execute(
-                    "UPDATE embedding_cache SET accessed_at = ?1 WHERE content_hash = ?2",
-                    params![now, hash],
-                )?;
-                return Ok(Some(vector::bytes_to_vec(&bytes)));
+            for (slot, hash) in results.iter_mut().zip(&hashes) {
+                let cached: Option<Vec<u8>> = stmt.query_row(params![hash], |row| row.get(0)).ok();
+
+                if let Some(bytes) = cached {
+                    // Update accessed_at for LRU
+                    conn.execute(
+                        "UPDATE embedding_cache SET accessed_at = ?1 WHERE content_hash = ?2",
+                        params![now, hash],
+                    )?;
+                    *slot = Some(vector::bytes_to_vec(&bytes));
+                }
             }
         }
 
-        // Compute embedding
-        let embedding = self.embedder.embed_one(text).await?;
-        let bytes = vector::vec_to_bytes(&embedding);
+        let missing: Vec<usize> = (0..texts.len()).filter(|&i| results[i].is_none()).collect();
+        if missing.is_empty() {
+            return Ok(results);
+        }
+
+        // Compute embeddings for all cache misses in one batch
+        let batch: Vec<&str> = missing.iter().map(|&i| texts[i]).collect();
+        let embeddings = self.embedder.embed(&batch).await?;
+        if embeddings.len() != batch.len() {
+            anyhow::bail!(
+                "Embedding provider '{}' returned {} vectors for {} inputs",
+                self.embedder.name(),
+                embeddings.len(),
+                batch.len()
+            );
+        }
 
         // Store in cache + LRU eviction
         {
@@ -202,11 +244,15 @@ impl SqliteMemory {
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
 
-            conn.execute(
-                "INSERT OR REPLACE INTO embedding_cache (content_hash, embedding, created_at, accessed_at)
-                 VALUES (?1, ?2, ?3, ?4)",
-                params![hash, bytes, now, now],
-            )?;
+            for (&i, embedding) in missing.iter().zip(embeddings) {
+                let bytes = vector::vec_to_bytes(&embedding);
+                conn.execute(
+                    "INSERT OR REPLACE INTO embedding_cache (content_hash, embedding, created_at, accessed_at)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![hashes[i], bytes, now, now],
+                )?;
+                results[i] = Some(embedding);
+            }
 
             // LRU eviction: keep only cache_max entries
             #[allow(clippy::cast_possible_wrap)]
@@ -221,7 +267,7 @@ impl SqliteMemory {
             )?;
         }
 
-        Ok(Some(embedding))
+        Ok(results)
     }
 
     /// FTS5 BM25 keyword search
@@ -330,18 +376,25 @@ impl SqliteMemory {
         };
 
         let mut count = 0;
-        for (id, content) in &entries {
-            if let Ok(Some(emb)) = self.get_or_compute_embedding(content).await {
-                let bytes = vector::vec_to_bytes(&emb);
-                let conn = self
-                    .conn
-                    .lock()
-                    .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
-                conn.execute(
-                    "UPDATE memories SET embedding = ?1 WHERE id = ?2",
-                    params![bytes, id],
-                )?;
-                count += 1;
+        for chunk in entries.chunks(REINDEX_BATCH_SIZE) {
+            let texts: Vec<&str> = chunk.iter().map(|(_, content)| content.as_str()).collect();
+            let Ok(embeddings) = self.get_or_compute_embeddings(&texts).await else {
+                continue;
+            };
+
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+            for ((id, _), emb) in chunk.iter().zip(embeddings) {
+                if let Some(emb) = emb {
+                    let bytes = vector::vec_to_bytes(&emb);
+                    conn.execute(
+                        "UPDATE memories SET embedding = ?1 WHERE id = ?2",
+                        params![bytes, id],
+                    )?;
+                    count += 1;
+                }
             }
         }
 
@@ -1034,6 +1087,94 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    /// Deterministic embedder that counts provider calls and batch sizes.
+    struct CountingEmbedding {
+        model: &'static str,
+        calls: std::sync::atomic::AtomicUsize,
+        last_batch: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CountingEmbedding {
+        fn new(model: &'static str) -> Self {
+            Self {
+                model,
+                calls: std::sync::atomic::AtomicUsize::new(0),
+                last_batch: std::sync::atomic::AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingEmbedding {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn dimensions(&self) -> usize {
+            3
+        }
+
+        fn model(&self) -> &str {
+            self.model
+        }
+
+        async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+            use std::sync::atomic::Ordering;
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.last_batch.store(texts.len(), Ordering::SeqCst);
+            #[allow(clippy::cast_precision_loss)]
+            Ok(texts
+                .iter()
+                .map(|t| vec![t.len() as f32, 1.0, 0.0])
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn batched_embeddings_use_cache_and_single_call_for_misses() {
+        use std::sync::atomic::Ordering;
+        let tmp = TempDir::new().unwrap();
+        let embedder = Arc::new(CountingEmbedding::new("m1"));
+        let mem =
+            SqliteMemory::with_embedder(tmp.path(), embedder.clone(), 0.7, 0.3, 1000).unwrap();
+
+        let first = mem.get_or_compute_embeddings(&["a", "bb"]).await.unwrap();
+        assert!(first.iter().all(Option::is_some));
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(embedder.last_batch.load(Ordering::SeqCst), 2);
+
+        // "a" and "bb" are cached; only "ccc" should reach the provider.
+        let second = mem
+            .get_or_compute_embeddings(&["a", "bb", "ccc"])
+            .await
+            .unwrap();
+        assert_eq!(second[2].as_deref(), Some(&[3.0, 1.0, 0.0][..]));
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(embedder.last_batch.load(Ordering::SeqCst), 1);
+
+        mem.get_or_compute_embeddings(&["a", "ccc"]).await.unwrap();
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn embedding_cache_is_not_shared_across_models() {
+        use std::sync::atomic::Ordering;
+        let tmp = TempDir::new().unwrap();
+        let first = Arc::new(CountingEmbedding::new("m1"));
+        let mem = SqliteMemory::with_embedder(tmp.path(), first.clone(), 0.7, 0.3, 1000).unwrap();
+        mem.get_or_compute_embeddings(&["a"]).await.unwrap();
+        drop(mem);
+
+        let second = Arc::new(CountingEmbedding::new("m2"));
+        let mem = SqliteMemory::with_embedder(tmp.path(), second.clone(), 0.7, 0.3, 1000).unwrap();
+        mem.get_or_compute_embeddings(&["a"]).await.unwrap();
+        assert_eq!(second.calls.load(Ordering::SeqCst), 1);
+
+        let again = SqliteMemory::with_embedder(tmp.path(), first.clone(), 0.7, 0.3, 1000).unwrap();
+        again.get_or_compute_embeddings(&["a"]).await.unwrap();
+        assert_eq!(first.calls.load(Ordering::SeqCst), 1);
+    }
+
     // ── Recall limit test ────────────────────────────────────────
 
     #[tokio::test]
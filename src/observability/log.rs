@@ -52,6 +52,20 @@ impl Observer for LogObserver {
                 let ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
                 info!(duration_ms = ms, tokens = ?tokens_used, "agent.end");
             }
+            ObserverEvent::RouteSelected {
+                route,
+                provider,
+                model,
+                reason,
+            } => {
+                info!(
+                    route = %route,
+                    provider = %provider,
+                    model = %model,
+                    reason = %reason,
+                    "route.selected"
+                );
+            }
             ObserverEvent::ToolCallStart { tool } => {
                 info!(tool = %tool, "tool.start");
             }
@@ -138,6 +152,12 @@ mod tests {
             duration: Duration::ZERO,
             tokens_used: None,
         });
+        obs.record_event(&ObserverEvent::RouteSelected {
+            route: "code".into(),
+            provider: "openrouter".into(),
+            model: "qwen-coder".into(),
+            reason: "category=code".into(),
+        });
         obs.record_event(&ObserverEvent::ToolCallStart {
             tool: "shell".into(),
         });
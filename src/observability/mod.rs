@@ -13,6 +13,12 @@ pub use traits::{Observer, ObserverEvent};
 pub use verbose::VerboseObserver;
 
 use crate::config::ObservabilityConfig;
+use parking_lot::Mutex;
+use std::sync::{Arc, Weak};
+
+/// Observers handed out by [`create_shared_observer`], so shutdown can flush
+/// them all. Weak, so registering never keeps an observer alive.
+static SHARED_OBSERVERS: Mutex<Vec<Weak<dyn Observer>>> = Mutex::new(Vec::new());
 
 /// Factory: create the right observer from config
 pub fn create_observer(config: &ObservabilityConfig) -> Box<dyn Observer> {
@@ -50,6 +56,29 @@ pub fn create_observer(config: &ObservabilityConfig) -> Box<dyn Observer> {
     }
 }
 
+/// Create a shared observer and register it for [`flush_all`].
+pub fn create_shared_observer(config: &ObservabilityConfig) -> Arc<dyn Observer> {
+    let observer: Arc<dyn Observer> = Arc::from(create_observer(config));
+    let mut shared = SHARED_OBSERVERS.lock();
+    shared.retain(|weak| weak.strong_count() > 0);
+    shared.push(Arc::downgrade(&observer));
+    observer
+}
+
+/// Flush every shared observer that is still alive (OTel batches, etc.).
+/// Returns how many were flushed.
+pub fn flush_all() -> usize {
+    let live: Vec<Arc<dyn Observer>> = {
+        let mut shared = SHARED_OBSERVERS.lock();
+        shared.retain(|weak| weak.strong_count() > 0);
+        shared.iter().filter_map(Weak::upgrade).collect()
+    };
+    for observer in &live {
+        observer.flush();
+    }
+    live.len()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,6 +92,23 @@ mod tests {
         assert_eq!(create_observer(&cfg).name(), "noop");
     }
 
+    #[test]
+    fn flush_all_reaches_live_shared_observers_only() {
+        let cfg = ObservabilityConfig {
+            backend: "log".into(),
+            ..ObservabilityConfig::default()
+        };
+        let kept = create_shared_observer(&cfg);
+        drop(create_shared_observer(&cfg));
+
+        assert!(flush_all() >= 1);
+        assert!(SHARED_OBSERVERS
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .any(|observer| Arc::ptr_eq(&observer, &kept)));
+    }
+
     #[test]
     fn factory_noop_returns_noop() {
         let cfg = ObservabilityConfig {
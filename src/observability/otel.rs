@@ -17,6 +17,7 @@ pub struct OtelObserver {
     agent_duration: Histogram<f64>,
     llm_calls: Counter<u64>,
     llm_duration: Histogram<f64>,
+    route_selections: Counter<u64>,
     tool_calls: Counter<u64>,
     tool_duration: Histogram<f64>,
     channel_messages: Counter<u64>,
@@ -96,6 +97,11 @@ impl OtelObserver {
             .with_description("Total LLM provider calls")
             .build();
 
+        let route_selections = meter
+            .u64_counter("zeroclaw.route.selections")
+            .with_description("Automatic routing decisions by route")
+            .build();
+
         let llm_duration = meter
             .f64_histogram("zeroclaw.llm.duration")
             .with_description("LLM provider call duration in seconds")
@@ -156,6 +162,7 @@ impl OtelObserver {
             agent_duration,
             llm_calls,
             llm_duration,
+            route_selections,
             tool_calls,
             tool_duration,
             channel_messages,
@@ -295,6 +302,21 @@ impl Observer for OtelObserver {
                     ],
                 );
             }
+            ObserverEvent::RouteSelected {
+                route,
+                provider,
+                model,
+                reason: _,
+            } => {
+                self.route_selections.add(
+                    1,
+                    &[
+                        KeyValue::new("route", route.clone()),
+                        KeyValue::new("provider", provider.clone()),
+                        KeyValue::new("model", model.clone()),
+                    ],
+                );
+            }
             ObserverEvent::HeartbeatTick => {
                 self.heartbeat_ticks.add(1, &[]);
             }
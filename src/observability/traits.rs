@@ -28,6 +28,13 @@ pub enum ObserverEvent {
         duration: Duration,
         tokens_used: Option<u64>,
     },
+    /// The model router picked a route for a request, and why.
+    RouteSelected {
+        route: String,
+        provider: String,
+        model: String,
+        reason: String,
+    },
     /// A tool call is about to be executed.
     ToolCallStart {
         tool: String,
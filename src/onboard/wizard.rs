@@ -1,8 +1,9 @@
-use crate::config::schema::{DingTalkConfig, IrcConfig, WhatsAppConfig};
+use crate::config::schema::{DingTalkConfig, IrcConfig, SignalConfig, TeamsConfig, WhatsAppConfig};
 use crate::config::{
     AutonomyConfig, BrowserConfig, ChannelsConfig, ComposioConfig, Config, DiscordConfig,
     HeartbeatConfig, IMessageConfig, MatrixConfig, MemoryConfig, ObservabilityConfig,
-    OrchestratorConfig, RuntimeConfig, SecretsConfig, SlackConfig, TelegramConfig, WebhookConfig,
+    OrchestratorConfig, RuntimeConfig, SecretsConfig, SecurityConfig, SlackConfig, TelegramConfig,
+    WebhookConfig,
 };
 use crate::hardware::{self, HardwareConfig};
 use crate::memory::{
@@ -13,7 +14,7 @@ use console::style;
 use dialoguer::{Confirm, Input, Select};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
@@ -101,6 +102,7 @@ pub fn run_wizard() -> Result<Config> {
     let config = Config {
         workspace_dir: workspace_dir.clone(),
         config_path: config_path.clone(),
+        secret_refs: crate::security::secret_sources::SecretReferences::default(),
         api_key: if api_key.is_empty() {
             None
         } else {
@@ -111,11 +113,13 @@ pub fn run_wizard() -> Result<Config> {
         default_temperature: 0.7,
         observability: ObservabilityConfig::default(),
         autonomy: AutonomyConfig::default(),
+        security: SecurityConfig::default(),
         runtime: RuntimeConfig::default(),
         reliability: crate::config::ReliabilityConfig::default(),
         scheduler: crate::config::schema::SchedulerConfig::default(),
         agent: crate::config::schema::AgentConfig::default(),
         model_routes: Vec::new(),
+        routing: crate::config::RoutingConfig::default(),
         heartbeat: HeartbeatConfig::default(),
         channels_config,
         orchestrator: OrchestratorConfig::default(),
@@ -126,6 +130,8 @@ pub fn run_wizard() -> Result<Config> {
         secrets: secrets_config,
         browser: BrowserConfig::default(),
         http_request: crate::config::HttpRequestConfig::default(),
+        home_assistant: crate::config::HomeAssistantConfig::default(),
+        mcp: crate::config::McpConfig::default(),
         identity: crate::config::IdentityConfig::default(),
         cost: crate::config::CostConfig::default(),
         peripherals: crate::config::PeripheralsConfig::default(),
@@ -157,7 +163,8 @@ pub fn run_wizard() -> Result<Config> {
         || config.channels_config.imessage.is_some()
         || config.channels_config.matrix.is_some()
         || config.channels_config.email.is_some()
-        || config.channels_config.dingtalk.is_some();
+        || config.channels_config.dingtalk.is_some()
+        || config.channels_config.signal.is_some();
 
     if has_channels && config.api_key.is_some() {
         let launch: bool = Confirm::new()
@@ -214,7 +221,8 @@ pub fn run_channels_repair_wizard() -> Result<Config> {
         || config.channels_config.imessage.is_some()
         || config.channels_config.matrix.is_some()
         || config.channels_config.email.is_some()
-        || config.channels_config.dingtalk.is_some();
+        || config.channels_config.dingtalk.is_some()
+        || config.channels_config.signal.is_some();
 
     if has_channels && config.api_key.is_some() {
         let launch: bool = Confirm::new()
@@ -318,17 +326,20 @@ pub fn run_quick_setup(
     let config = Config {
         workspace_dir: workspace_dir.clone(),
         config_path: config_path.clone(),
+        secret_refs: crate::security::secret_sources::SecretReferences::default(),
         api_key: api_key.map(String::from),
         default_provider: Some(provider_name.clone()),
         default_model: Some(model.clone()),
         default_temperature: 0.7,
         observability: ObservabilityConfig::default(),
         autonomy: AutonomyConfig::default(),
+        security: SecurityConfig::default(),
         runtime: RuntimeConfig::default(),
         reliability: crate::config::ReliabilityConfig::default(),
         scheduler: crate::config::schema::SchedulerConfig::default(),
         agent: crate::config::schema::AgentConfig::default(),
         model_routes: Vec::new(),
+        routing: crate::config::RoutingConfig::default(),
         heartbeat: HeartbeatConfig::default(),
         channels_config: ChannelsConfig::default(),
         orchestrator: OrchestratorConfig::default(),
@@ -339,6 +350,8 @@ pub fn run_quick_setup(
         secrets: SecretsConfig::default(),
         browser: BrowserConfig::default(),
         http_request: crate::config::HttpRequestConfig::default(),
+        home_assistant: crate::config::HomeAssistantConfig::default(),
+        mcp: crate::config::McpConfig::default(),
         identity: crate::config::IdentityConfig::default(),
         cost: crate::config::CostConfig::default(),
         peripherals: crate::config::PeripheralsConfig::default(),
@@ -829,9 +842,64 @@ fn parse_ollama_model_ids(payload: &Value) -> Vec<String> {
     normalize_model_ids(ids)
 }
 
-fn fetch_openai_compatible_models(endpoint: &str, api_key: Option<&str>) -> Result<Vec<String>> {
+/// Context-window sizes (in tokens) advertised by a model list response:
+/// `context_length` (OpenRouter, Together), `context_window` (Groq),
+/// `max_context_length` (Mistral) or Gemini's `inputTokenLimit`.
+fn parse_context_windows(payload: &Value) -> BTreeMap<String, usize> {
+    let entries = payload
+        .get("data")
+        .or_else(|| payload.get("models"))
+        .unwrap_or(payload)
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
+
+    let mut windows = BTreeMap::new();
+    for model in entries {
+        let Some(id) = model
+            .get("id")
+            .or_else(|| model.get("name"))
+            .and_then(Value::as_str)
+        else {
+            continue;
+        };
+        let window = [
+            "context_length",
+            "context_window",
+            "max_context_length",
+            "inputTokenLimit",
+        ]
+        .iter()
+        .find_map(|key| model.get(*key).and_then(Value::as_u64))
+        .and_then(|tokens| usize::try_from(tokens).ok())
+        .filter(|tokens| *tokens > 0);
+        if let Some(window) = window {
+            windows.insert(id.trim().trim_start_matches("models/").to_string(), window);
+        }
+    }
+    windows
+}
+
+/// Model ids from a live fetch, plus any context windows the provider reported.
+#[derive(Debug, Clone, Default)]
+struct LiveModels {
+    ids: Vec<String>,
+    context_windows: BTreeMap<String, usize>,
+}
+
+impl LiveModels {
+    fn from_payload(ids: Vec<String>, payload: &Value) -> Self {
+        let mut context_windows = parse_context_windows(payload);
+        context_windows.retain(|id, _| ids.binary_search(id).is_ok());
+        Self {
+            ids,
+            context_windows,
+        }
+    }
+}
+
+fn fetch_openai_compatible_models(endpoint: &str, api_key: Option<&str>) -> Result<LiveModels> {
     let Some(api_key) = api_key else {
-        return Ok(Vec::new());
+        return Ok(LiveModels::default());
     };
 
     let client = build_model_fetch_client()?;
@@ -844,10 +912,13 @@ fn fetch_openai_compatible_models(endpoint: &str, api_key: Option<&str>) -> Resu
         .json()
         .context("failed to parse model list response")?;
 
-    Ok(parse_openai_compatible_model_ids(&payload))
+    Ok(LiveModels::from_payload(
+        parse_openai_compatible_model_ids(&payload),
+        &payload,
+    ))
 }
 
-fn fetch_openrouter_models(api_key: Option<&str>) -> Result<Vec<String>> {
+fn fetch_openrouter_models(api_key: Option<&str>) -> Result<LiveModels> {
     let client = build_model_fetch_client()?;
     let mut request = client.get("https://openrouter.ai/api/v1/models");
     if let Some(api_key) = api_key {
@@ -861,12 +932,15 @@ fn fetch_openrouter_models(api_key: Option<&str>) -> Result<Vec<String>> {
         .json()
         .context("failed to parse OpenRouter model list response")?;
 
-    Ok(parse_openai_compatible_model_ids(&payload))
+    Ok(LiveModels::from_payload(
+        parse_openai_compatible_model_ids(&payload),
+        &payload,
+    ))
 }
 
-fn fetch_anthropic_models(api_key: Option<&str>) -> Result<Vec<String>> {
+fn fetch_anthropic_models(api_key: Option<&str>) -> Result<LiveModels> {
     let Some(api_key) = api_key else {
-        return Ok(Vec::new());
+        return Ok(LiveModels::default());
     };
 
     let client = build_model_fetch_client()?;
@@ -880,12 +954,15 @@ fn fetch_anthropic_models(api_key: Option<&str>) -> Result<Vec<String>> {
         .json()
         .context("failed to parse Anthropic model list response")?;
 
-    Ok(parse_openai_compatible_model_ids(&payload))
+    Ok(LiveModels::from_payload(
+        parse_openai_compatible_model_ids(&payload),
+        &payload,
+    ))
 }
 
-fn fetch_gemini_models(api_key: Option<&str>) -> Result<Vec<String>> {
+fn fetch_gemini_models(api_key: Option<&str>) -> Result<LiveModels> {
     let Some(api_key) = api_key else {
-        return Ok(Vec::new());
+        return Ok(LiveModels::default());
     };
 
     let client = build_model_fetch_client()?;
@@ -898,10 +975,13 @@ fn fetch_gemini_models(api_key: Option<&str>) -> Result<Vec<String>> {
         .json()
         .context("failed to parse Gemini model list response")?;
 
-    Ok(parse_gemini_model_ids(&payload))
+    Ok(LiveModels::from_payload(
+        parse_gemini_model_ids(&payload),
+        &payload,
+    ))
 }
 
-fn fetch_ollama_models() -> Result<Vec<String>> {
+fn fetch_ollama_models() -> Result<LiveModels> {
     let client = build_model_fetch_client()?;
     let payload: Value = client
         .get("http://localhost:11434/api/tags")
@@ -911,10 +991,13 @@ fn fetch_ollama_models() -> Result<Vec<String>> {
         .json()
         .context("failed to parse Ollama model list response")?;
 
-    Ok(parse_ollama_model_ids(&payload))
+    Ok(LiveModels {
+        ids: parse_ollama_model_ids(&payload),
+        context_windows: BTreeMap::new(),
+    })
 }
 
-fn fetch_live_models_for_provider(provider_name: &str, api_key: &str) -> Result<Vec<String>> {
+fn fetch_live_models_for_provider(provider_name: &str, api_key: &str) -> Result<LiveModels> {
     let provider_name = canonical_provider_name(provider_name);
     let api_key = if api_key.trim().is_empty() {
         std::env::var(provider_env_var(provider_name))
@@ -949,7 +1032,7 @@ fn fetch_live_models_for_provider(provider_name: &str, api_key: &str) -> Result<
         "anthropic" => fetch_anthropic_models(api_key.as_deref())?,
         "gemini" => fetch_gemini_models(api_key.as_deref())?,
         "ollama" => fetch_ollama_models()?,
-        _ => Vec::new(),
+        _ => LiveModels::default(),
     };
 
     Ok(models)
@@ -960,6 +1043,8 @@ struct ModelCacheEntry {
     provider: String,
     fetched_at_unix: u64,
     models: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    context_windows: BTreeMap<String, usize>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -1020,6 +1105,7 @@ fn cache_live_models_for_provider(
     workspace_dir: &Path,
     provider_name: &str,
     models: &[String],
+    context_windows: &BTreeMap<String, usize>,
 ) -> Result<()> {
     let normalized_models = normalize_model_ids(models.to_vec());
     if normalized_models.is_empty() {
@@ -1036,11 +1122,13 @@ fn cache_live_models_for_provider(
     {
         entry.fetched_at_unix = now;
         entry.models = normalized_models;
+        entry.context_windows = context_windows.clone();
     } else {
         state.entries.push(ModelCacheEntry {
             provider: provider_name.to_string(),
             fetched_at_unix: now,
             models: normalized_models,
+            context_windows: context_windows.clone(),
         });
     }
 
@@ -1093,6 +1181,27 @@ fn load_any_cached_models_for_provider(
     load_cached_models_for_provider_internal(workspace_dir, provider_name, None)
 }
 
+/// Context window of `model` as last reported by `provider_name`'s model
+/// list, regardless of cache age. Matches the full id first, then the id
+/// without a `vendor/` prefix.
+pub fn cached_context_window(
+    workspace_dir: &Path,
+    provider_name: &str,
+    model: &str,
+) -> Option<usize> {
+    let state = load_model_cache_state(workspace_dir).ok()?;
+    let entry = state
+        .entries
+        .into_iter()
+        .find(|entry| entry.provider == provider_name)?;
+    let bare = model.rsplit_once('/').map_or(model, |(_, name)| name);
+    entry
+        .context_windows
+        .get(model)
+        .or_else(|| entry.context_windows.get(bare))
+        .copied()
+}
+
 fn humanize_age(age_secs: u64) -> String {
     if age_secs < 60 {
         format!("{age_secs}s")
@@ -1170,8 +1279,14 @@ pub fn run_models_refresh(
     let api_key = config.api_key.clone().unwrap_or_default();
 
     match fetch_live_models_for_provider(&provider_name, &api_key) {
-        Ok(models) if !models.is_empty() => {
-            cache_live_models_for_provider(&config.workspace_dir, &provider_name, &models)?;
+        Ok(live) if !live.ids.is_empty() => {
+            cache_live_models_for_provider(
+                &config.workspace_dir,
+                &provider_name,
+                &live.ids,
+                &live.context_windows,
+            )?;
+            let models = live.ids;
             println!(
                 "Refreshed '{}' model cache with {} models.",
                 provider_name,
@@ -1651,12 +1766,14 @@ fn setup_provider(workspace_dir: &Path) -> Result<(String, String, String)> {
 
             if should_fetch_now {
                 match fetch_live_models_for_provider(provider_name, &api_key) {
-                    Ok(live_model_ids) if !live_model_ids.is_empty() => {
+                    Ok(live) if !live.ids.is_empty() => {
                         cache_live_models_for_provider(
                             workspace_dir,
                             provider_name,
-                            &live_model_ids,
+                            &live.ids,
+                            &live.context_windows,
                         )?;
+                        let live_model_ids = live.ids;
 
                         let fetched_count = live_model_ids.len();
                         let shown_count = fetched_count.min(LIVE_MODEL_MAX_OPTIONS);
@@ -1947,6 +2064,7 @@ fn setup_hardware() -> Result<HardwareConfig> {
         "🔌 Tethered — control an Arduino/ESP32/Nucleo plugged into USB",
         "🔬 Debug Probe — flash/read MCUs via SWD/JTAG (probe-rs)",
         "☁️  Software Only — no hardware access (default)",
+        "📡 Networked — board on the LAN over TCP, WebSocket or MQTT",
     ];
 
     let recommended = hardware::recommended_wizard_default(&devices);
@@ -2034,6 +2152,17 @@ fn setup_hardware() -> Result<HardwareConfig> {
         hw_config.probe_target = Some(target);
     }
 
+    // ── Network: ask for the board URL ──
+    if hw_config.transport_mode() == hardware::HardwareTransport::Network {
+        let url: String = Input::new()
+            .with_prompt(
+                "  Board URL (tcp://host:port, ws://host:port/path, mqtt(s)://broker:port/topic)",
+            )
+            .default("tcp://192.168.1.50:7777".into())
+            .interact_text()?;
+        hw_config.network_url = Some(url);
+    }
+
     // ── Datasheet RAG ──
     if hw_config.enabled {
         let datasheets = Confirm::new()
@@ -2056,6 +2185,10 @@ fn setup_hardware() -> Result<HardwareConfig> {
                 "Probe (SWD/JTAG) → {}",
                 hw_config.probe_target.as_deref().unwrap_or("?")
             ),
+            hardware::HardwareTransport::Network => format!(
+                "Network → {}",
+                hw_config.network_url.as_deref().unwrap_or("?")
+            ),
             hardware::HardwareTransport::None => "Software Only".to_string(),
         };
 
@@ -2241,6 +2374,8 @@ fn setup_channels() -> Result<ChannelsConfig> {
         irc: None,
         lark: None,
         dingtalk: None,
+        signal: None,
+        teams: None,
     };
 
     loop {
@@ -2317,13 +2452,29 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     "— 钉钉 Stream Mode"
                 }
             ),
+            format!(
+                "Signal     {}",
+                if config.signal.is_some() {
+                    "✅ connected"
+                } else {
+                    "— via local signal-cli daemon"
+                }
+            ),
+            format!(
+                "Teams      {}",
+                if config.teams.is_some() {
+                    "✅ configured"
+                } else {
+                    "— Azure Bot Framework"
+                }
+            ),
             "Done — finish setup".to_string(),
         ];
 
         let choice = Select::new()
             .with_prompt("  Connect a channel (or Done to continue)")
             .items(&options)
-            .default(9)
+            .default(11)
             .interact()?;
 
         match choice {
@@ -2774,6 +2925,11 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     access_token,
                     room_id,
                     allowed_users,
+                    rooms: vec![],
+                    auto_join: true,
+                    thread_replies: true,
+                    e2ee: false,
+                    store_dir: None,
                 });
             }
             5 => {
@@ -3112,6 +3268,162 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     allowed_users,
                 });
             }
+            9 => {
+                // ── Signal ──
+                println!();
+                println!(
+                    "  {} {}",
+                    style("Signal Setup").white().bold(),
+                    style("— private messaging via signal-cli").dim()
+                );
+                print_bullet("1. Install signal-cli and register or link your bot number");
+                print_bullet("2. Run: signal-cli -a +1234567890 daemon --http 127.0.0.1:8080");
+                print_bullet("   (or --tcp 127.0.0.1:7583 and use a tcp:// endpoint)");
+                println!();
+
+                let endpoint: String = Input::new()
+                    .with_prompt("  signal-cli endpoint")
+                    .default("http://127.0.0.1:8080".into())
+                    .interact_text()?;
+
+                if endpoint.trim().is_empty() {
+                    println!("  {} Skipped", style("→").dim());
+                    continue;
+                }
+
+                if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+                    print!("  {} Testing connection... ", style("⏳").dim());
+                    let check_url = format!("{}/api/v1/check", endpoint.trim_end_matches('/'));
+                    let thread_result = std::thread::spawn(move || {
+                        let client = reqwest::blocking::Client::builder()
+                            .timeout(std::time::Duration::from_secs(5))
+                            .build()?;
+                        let resp = client.get(&check_url).send()?;
+                        Ok::<_, reqwest::Error>(resp.status().is_success())
+                    })
+                    .join();
+                    if let Ok(Ok(true)) = thread_result {
+                        println!(
+                            "\r  {} signal-cli daemon reachable        ",
+                            style("✅").green().bold()
+                        );
+                    } else {
+                        println!(
+                            "\r  {} signal-cli daemon not reachable — saving anyway; start it before launching channels",
+                            style("⚠").yellow().bold()
+                        );
+                    }
+                }
+
+                let account: String = Input::new()
+                    .with_prompt(
+                        "  Bot account number (E.164, blank if daemon runs a single account)",
+                    )
+                    .allow_empty(true)
+                    .interact_text()?;
+
+                print_bullet(
+                    "Allowlist your own number first. Use '*' only for temporary open testing.",
+                );
+                let users_str: String = Input::new()
+                    .with_prompt("  Allowed sender numbers/UUIDs (comma-separated, '*' for all)")
+                    .allow_empty(true)
+                    .interact_text()?;
+
+                let allowed_users: Vec<String> = users_str
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+
+                if allowed_users.is_empty() {
+                    println!(
+                        "  {} No senders allowlisted — Signal inbound messages will be denied until you add a number or '*'.",
+                        style("⚠").yellow().bold()
+                    );
+                }
+
+                let groups_str: String = Input::new()
+                    .with_prompt("  Group IDs to respond in (comma-separated, '*' for all, blank for 1:1 only)")
+                    .allow_empty(true)
+                    .interact_text()?;
+
+                let allowed_groups: Vec<String> = groups_str
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+
+                config.signal = Some(SignalConfig {
+                    endpoint: endpoint.trim().to_string(),
+                    account: Some(account.trim().to_string()).filter(|a| !a.is_empty()),
+                    allowed_users,
+                    allowed_groups,
+                    attachments_dir: None,
+                });
+            }
+            10 => {
+                // ── Microsoft Teams ──
+                println!();
+                println!(
+                    "  {} {}",
+                    style("Microsoft Teams Setup").white().bold(),
+                    style("— Azure Bot via the Bot Framework").dim()
+                );
+                print_bullet("1. Create an Azure Bot resource and note its Microsoft App ID");
+                print_bullet("2. Create a client secret for the app registration");
+                print_bullet(
+                    "3. Set the messaging endpoint to: https://your-domain/teams/messages",
+                );
+                print_bullet("4. Enable the Microsoft Teams channel on the bot");
+                println!();
+
+                let app_id: String = Input::new()
+                    .with_prompt("  Microsoft App ID")
+                    .interact_text()?;
+
+                if app_id.trim().is_empty() {
+                    println!("  {} Skipped", style("→").dim());
+                    continue;
+                }
+
+                let app_password: String = Input::new()
+                    .with_prompt("  Client secret")
+                    .interact_text()?;
+
+                let tenant_id: String = Input::new()
+                    .with_prompt("  Tenant ID (single-tenant bots only, blank for multi-tenant)")
+                    .allow_empty(true)
+                    .interact_text()?;
+
+                print_bullet(
+                    "Users are matched by Entra ID (AAD) object ID. Use '*' only for temporary open testing.",
+                );
+                let users_str: String = Input::new()
+                    .with_prompt("  Allowed AAD object IDs (comma-separated, '*' for all)")
+                    .allow_empty(true)
+                    .interact_text()?;
+
+                let allowed_users: Vec<String> = users_str
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+
+                if allowed_users.is_empty() {
+                    println!(
+                        "  {} No users allowlisted — Teams messages will be denied until you add an object ID or '*'.",
+                        style("⚠").yellow().bold()
+                    );
+                }
+
+                config.teams = Some(TeamsConfig {
+                    app_id: app_id.trim().to_string(),
+                    app_password: app_password.trim().to_string(),
+                    tenant_id: Some(tenant_id.trim().to_string()).filter(|t| !t.is_empty()),
+                    allowed_users,
+                });
+            }
             _ => break, // Done
         }
         println!();
@@ -3149,6 +3461,12 @@ fn setup_channels() -> Result<ChannelsConfig> {
     if config.dingtalk.is_some() {
         active.push("DingTalk");
     }
+    if config.signal.is_some() {
+        active.push("Signal");
+    }
+    if config.teams.is_some() {
+        active.push("Teams");
+    }
 
     println!(
         "  {} Channels: {}",
@@ -3600,7 +3918,8 @@ fn print_summary(config: &Config) {
         || config.channels_config.imessage.is_some()
         || config.channels_config.matrix.is_some()
         || config.channels_config.email.is_some()
-        || config.channels_config.dingtalk.is_some();
+        || config.channels_config.dingtalk.is_some()
+        || config.channels_config.signal.is_some();
 
     println!();
     println!(
@@ -3757,6 +4076,14 @@ fn print_summary(config: &Config) {
                     ))
                     .green()
                 ),
+                hardware::HardwareTransport::Network => format!(
+                    "{}",
+                    style(format!(
+                        "Network → {}",
+                        config.hardware.network_url.as_deref().unwrap_or("?")
+                    ))
+                    .green()
+                ),
                 hardware::HardwareTransport::None => "disabled (software only)".to_string(),
             }
         } else {
@@ -4384,7 +4711,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let models = vec!["gpt-5.1".to_string(), "gpt-5-mini".to_string()];
 
-        cache_live_models_for_provider(tmp.path(), "openai", &models).unwrap();
+        cache_live_models_for_provider(tmp.path(), "openai", &models, &BTreeMap::new()).unwrap();
 
         let cached =
             load_cached_models_for_provider(tmp.path(), "openai", MODEL_CACHE_TTL_SECS).unwrap();
@@ -4403,6 +4730,7 @@ mod tests {
                 provider: "openai".to_string(),
                 fetched_at_unix: now_unix_secs().saturating_sub(MODEL_CACHE_TTL_SECS + 120),
                 models: vec!["gpt-5.1".to_string()],
+                context_windows: BTreeMap::new(),
             }],
         };
 
@@ -4416,11 +4744,53 @@ mod tests {
         assert!(stale_any.is_some());
     }
 
+    #[test]
+    fn context_windows_are_parsed_and_cached() {
+        let openrouter = serde_json::json!({
+            "data": [
+                {"id": "anthropic/claude-sonnet-4", "context_length": 200_000},
+                {"id": "mystery/model"}
+            ]
+        });
+        let gemini = serde_json::json!({
+            "models": [{"name": "models/gemini-2.5-pro", "inputTokenLimit": 1_048_576}]
+        });
+        assert_eq!(
+            parse_context_windows(&openrouter),
+            BTreeMap::from([("anthropic/claude-sonnet-4".to_string(), 200_000)])
+        );
+        assert_eq!(
+            parse_context_windows(&gemini),
+            BTreeMap::from([("gemini-2.5-pro".to_string(), 1_048_576)])
+        );
+
+        let tmp = TempDir::new().unwrap();
+        let live =
+            LiveModels::from_payload(parse_openai_compatible_model_ids(&openrouter), &openrouter);
+        cache_live_models_for_provider(tmp.path(), "openrouter", &live.ids, &live.context_windows)
+            .unwrap();
+        assert_eq!(
+            cached_context_window(tmp.path(), "openrouter", "anthropic/claude-sonnet-4"),
+            Some(200_000)
+        );
+        assert_eq!(
+            cached_context_window(tmp.path(), "openrouter", "mystery/model"),
+            None
+        );
+        assert_eq!(cached_context_window(tmp.path(), "openai", "gpt-5.1"), None);
+    }
+
     #[test]
     fn run_models_refresh_uses_fresh_cache_without_network() {
         let tmp = TempDir::new().unwrap();
 
-        cache_live_models_for_provider(tmp.path(), "openai", &["gpt-5.1".to_string()]).unwrap();
+        cache_live_models_for_provider(
+            tmp.path(),
+            "openai",
+            &["gpt-5.1".to_string()],
+            &BTreeMap::new(),
+        )
+        .unwrap();
 
         let config = Config {
             workspace_dir: tmp.path().to_path_buf(),
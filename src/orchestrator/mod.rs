@@ -214,14 +214,15 @@ pub async fn run_queue_worker(
                 .with_context(|| format!("invalid job payload: {}", path.display()))?;
 
             let started = Utc::now().to_rfc3339();
-            let result = crate::agent::run(
+            let result = Box::pin(crate::agent::run(
                 config.clone(),
                 Some(job.text.clone()),
                 None,
                 None,
                 config.default_temperature,
                 vec![],
-            )
+                None,
+            ))
             .await;
 
             let (status, summary) = match result {
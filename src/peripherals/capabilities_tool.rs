@@ -25,7 +25,11 @@ impl Tool for HardwareCapabilitiesTool {
     }
 
     fn description(&self) -> &str {
-        "Query connected hardware for reported GPIO pins and LED pin. Use when: user asks what pins are available."
+        "Query connected hardware for reported GPIO pins, LED pin and board commands. Use when: user asks what pins or features are available."
+    }
+
+    fn read_only(&self) -> bool {
+        true
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -56,11 +60,24 @@ impl Tool for HardwareCapabilitiesTool {
                         if let Ok(parsed) =
                             serde_json::from_str::<serde_json::Value>(&result.output)
                         {
+                            let commands: Vec<&str> = parsed
+                                .get("commands")
+                                .and_then(|c| c.as_array())
+                                .into_iter()
+                                .flatten()
+                                .filter_map(|c| c.get("name").and_then(|n| n.as_str()))
+                                .collect();
+                            let commands = if commands.is_empty() {
+                                String::new()
+                            } else {
+                                format!(", commands {commands:?}")
+                            };
                             format!(
-                                "{}: gpio {:?}, led_pin {:?}",
+                                "{}: gpio {:?}, led_pin {:?}{}",
                                 board_name,
                                 parsed.get("gpio").unwrap_or(&json!([])),
-                                parsed.get("led_pin").unwrap_or(&json!(null))
+                                parsed.get("led_pin").unwrap_or(&json!(null)),
+                                commands
                             )
                         } else {
                             format!("{}: {}", board_name, result.output)
@@ -0,0 +1,280 @@
+//! Tools generated from the commands a board advertises in `capabilities`.
+//!
+//! Firmware may add a `commands` list to its capabilities JSON:
+//!
+//! ```json
+//! {"gpio":[2,13],"led_pin":13,"commands":[
+//!   {"name":"sensor_read","description":"Read a sensor",
+//!    "parameters":{"type":"object","properties":{"name":{"type":"string","enum":["temperature"]}},"required":["name"]}}
+//! ]}
+//! ```
+//!
+//! Each entry becomes a tool named `<board>_<command>` (so firmware cannot
+//! shadow `shell` or other built-in tools) whose arguments are sent
+//! unchanged as the request `args`, so new firmware commands need no host
+//! changes. Advertised `gpio_read`/`gpio_write` keep their names and replace
+//! the built-in GPIO tools; boards without `commands` keep the built-ins.
+
+use super::serial::SerialTransport;
+use crate::tools::traits::{Tool, ToolResult};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Protocol commands that are never exposed as tools.
+const RESERVED_COMMANDS: &[&str] = &["ping", "capabilities", "hello", "auth"];
+
+/// Upper bound on tools taken from one board.
+const MAX_COMMANDS: usize = 32;
+
+/// Board commands that stand in for the built-in peripheral tool of the
+/// same name.
+pub(crate) const GPIO_COMMANDS: &[&str] = &["gpio_read", "gpio_write"];
+
+/// Tool name for a board command: `<board>_<command>`, limited to
+/// `[a-z0-9_]` and 64 characters. GPIO commands keep their own name.
+pub(crate) fn tool_name(board: &str, command: &str) -> String {
+    if GPIO_COMMANDS.contains(&command) {
+        return command.to_string();
+    }
+    format!("{board}_{command}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+/// A command advertised by a board.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct BoardCommand {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema (type "object") for the request args
+    #[serde(default)]
+    pub parameters: Option<Value>,
+    /// The command only observes the board (e.g. reads a pin or sensor)
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    name.len() <= 64
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Extract the advertised commands from a capabilities result. Invalid,
+/// reserved and duplicate entries are skipped with a warning.
+pub(crate) fn parse_commands(board: &str, capabilities: &str) -> Vec<BoardCommand> {
+    let Ok(parsed) = serde_json::from_str::<Value>(capabilities) else {
+        return Vec::new();
+    };
+    let Some(entries) = parsed.get("commands").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    let mut commands: Vec<BoardCommand> = Vec::new();
+    for entry in entries {
+        let mut command = match serde_json::from_value::<BoardCommand>(entry.clone()) {
+            Ok(command) => command,
+            Err(e) => {
+                tracing::warn!(board, "Ignoring malformed board command {entry}: {e}");
+                continue;
+            }
+        };
+        if !is_valid_name(&command.name)
+            || RESERVED_COMMANDS.contains(&command.name.as_str())
+            || commands.iter().any(|c| c.name == command.name)
+        {
+            tracing::warn!(board, "Ignoring board command {:?}", command.name);
+            continue;
+        }
+        if command
+            .parameters
+            .as_ref()
+            .is_some_and(|p| p.get("type").and_then(Value::as_str) != Some("object"))
+        {
+            tracing::warn!(
+                board,
+                "Board command {} has a non-object schema; ignoring it",
+                command.name
+            );
+            command.parameters = None;
+        }
+        if commands.len() == MAX_COMMANDS {
+            tracing::warn!(board, "Board advertises more than {MAX_COMMANDS} commands");
+            break;
+        }
+        commands.push(command);
+    }
+    commands
+}
+
+/// Tool: run a board-advertised command.
+pub(crate) struct BoardCommandTool {
+    name: String,
+    command: BoardCommand,
+    description: String,
+    transport: Arc<SerialTransport>,
+}
+
+impl BoardCommandTool {
+    pub(crate) fn new(board: &str, command: BoardCommand, transport: Arc<SerialTransport>) -> Self {
+        let description = if command.description.trim().is_empty() {
+            format!(
+                "Run the {} command on the connected {board} board",
+                command.name
+            )
+        } else {
+            format!("{} (board: {board})", command.description.trim())
+        };
+        Self {
+            name: tool_name(board, &command.name),
+            command,
+            description,
+            transport,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for BoardCommandTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.command
+            .parameters
+            .clone()
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} }))
+    }
+
+    fn read_only(&self) -> bool {
+        self.command.read_only
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+        let args = match args {
+            Value::Null => json!({}),
+            Value::Object(_) => args,
+            _ => anyhow::bail!("Arguments must be an object"),
+        };
+        let required = self
+            .command
+            .parameters
+            .as_ref()
+            .and_then(|p| p.get("required"))
+            .and_then(Value::as_array);
+        if let Some(missing) = required
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .find(|key| args.get(key).is_none())
+        {
+            anyhow::bail!("Missing '{missing}' parameter");
+        }
+        self.transport.request(&self.command.name, args).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed_and_filtered() {
+        let caps = json!({
+            "gpio": [2],
+            "commands": [
+                {"name": "sensor_read", "description": "Read a sensor",
+                 "parameters": {"type": "object", "properties": {"name": {"type": "string"}}, "required": ["name"]}},
+                {"name": "servo_set"},
+                {"name": "ping"},
+                {"name": "Bad Name"},
+                {"name": "sensor_read"},
+                {"name": "odd_schema", "parameters": {"type": "string"}},
+                {"description": "no name"}
+            ]
+        })
+        .to_string();
+
+        let commands = parse_commands("esp32", &caps);
+        let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["sensor_read", "servo_set", "odd_schema"]);
+        assert_eq!(commands[0].description, "Read a sensor");
+        assert!(commands[1].parameters.is_none());
+        assert!(commands[2].parameters.is_none());
+
+        assert!(parse_commands("uno", r#"{"gpio":[13],"led_pin":13}"#).is_empty());
+        assert!(parse_commands("uno", "not json").is_empty());
+    }
+
+    #[test]
+    fn tool_names_are_namespaced_by_board() {
+        assert_eq!(tool_name("esp32", "shell"), "esp32_shell");
+        assert_eq!(
+            tool_name("nucleo-f401re", "sensor_read"),
+            "nucleo_f401re_sensor_read"
+        );
+        assert_eq!(tool_name("esp32", "gpio_read"), "gpio_read");
+        assert_eq!(tool_name(&"b".repeat(80), "x").len(), 64);
+    }
+
+    #[tokio::test]
+    async fn advertised_commands_become_tools_on_connect() {
+        use crate::config::PeripheralBoardConfig;
+        use crate::peripherals::serial::SerialPeripheral;
+        use crate::peripherals::traits::Peripheral;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("sim.toml");
+        std::fs::write(&script, "[sensors]\ntemperature = 21.5\nhumidity = 40.0").unwrap();
+        let config = PeripheralBoardConfig {
+            board: "esp32".into(),
+            transport: "simulator".into(),
+            script: Some(script.display().to_string()),
+            ..PeripheralBoardConfig::default()
+        };
+        let mut peripheral = SerialPeripheral::connect(&config).await.unwrap();
+        peripheral.connect().await.unwrap();
+
+        let tools = peripheral.tools();
+        let names: Vec<&str> = tools.iter().map(|t| t.name()).collect();
+        assert_eq!(names, ["gpio_read", "gpio_write", "esp32_sensor_read"]);
+        let read_only: Vec<bool> = tools.iter().map(|t| t.read_only()).collect();
+        assert_eq!(read_only, [true, false, true]);
+
+        let sensor = &tools[2];
+        assert!(sensor.description().contains("(board: esp32)"));
+        assert_eq!(
+            sensor.parameters_schema()["properties"]["name"]["enum"],
+            json!(["humidity", "temperature"])
+        );
+        let reading = sensor
+            .execute(json!({ "name": "temperature" }))
+            .await
+            .unwrap();
+        assert!(reading.success);
+        assert_eq!(reading.output, "21.5");
+
+        let missing = sensor.execute(json!({})).await.unwrap_err();
+        assert_eq!(missing.to_string(), "Missing 'name' parameter");
+        let read = tools[0].execute(json!({ "pin": 2 })).await.unwrap();
+        assert_eq!(read.output, "0");
+    }
+}
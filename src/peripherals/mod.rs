@@ -15,8 +15,14 @@ pub mod arduino_upload;
 #[cfg(feature = "hardware")]
 pub mod capabilities_tool;
 #[cfg(feature = "hardware")]
+pub mod command_tool;
+#[cfg(feature = "hardware")]
+pub mod network;
+#[cfg(feature = "hardware")]
 pub mod nucleo_flash;
 #[cfg(feature = "hardware")]
+pub mod simulator;
+#[cfg(feature = "hardware")]
 pub mod uno_q_bridge;
 #[cfg(feature = "hardware")]
 pub mod uno_q_setup;
@@ -40,7 +46,7 @@ pub fn list_configured_boards(config: &PeripheralsConfig) -> Vec<&PeripheralBoar
 
 /// Handle `zeroclaw peripheral` subcommands.
 #[allow(clippy::module_name_repetitions)]
-pub fn handle_command(cmd: crate::PeripheralCommands, config: &Config) -> Result<()> {
+pub async fn handle_command(cmd: crate::PeripheralCommands, config: &Config) -> Result<()> {
     match cmd {
         crate::PeripheralCommands::List => {
             let boards = list_configured_boards(&config.peripherals);
@@ -92,6 +98,8 @@ pub fn handle_command(cmd: crate::PeripheralCommands, config: &Config) -> Result
                 transport: transport.to_string(),
                 path: path_opt,
                 baud: 115200,
+                script: None,
+                secret: None,
             });
             cfg.save()?;
             println!("Added {} at {}. Restart daemon to apply.", board, path);
@@ -128,6 +136,31 @@ pub fn handle_command(cmd: crate::PeripheralCommands, config: &Config) -> Result
             println!("Nucleo flash requires the 'hardware' feature.");
             println!("Build with: cargo build --features hardware");
         }
+        #[cfg(feature = "hardware")]
+        crate::PeripheralCommands::Simulate {
+            board,
+            script,
+            listen,
+            secret,
+        } => match listen {
+            Some(listen) => {
+                let secret = match secret
+                    .as_deref()
+                    .and_then(crate::security::secret_sources::SecretSource::parse)
+                {
+                    Some(source) => Some(source.resolve(&config.workspace_dir)?),
+                    None => secret,
+                };
+                simulator::run_network(&board, script.as_deref(), &listen, secret.as_deref())
+                    .await?;
+            }
+            None => simulator::run_pty(&board, script.as_deref()).await?,
+        },
+        #[cfg(not(feature = "hardware"))]
+        crate::PeripheralCommands::Simulate { .. } => {
+            println!("Board simulation requires the 'hardware' feature.");
+            println!("Build with: cargo build --features hardware");
+        }
     }
     Ok(())
 }
@@ -170,11 +203,13 @@ pub async fn create_peripheral_tools(config: &PeripheralsConfig) -> Result<Vec<B
             continue;
         }
 
-        // Serial transport (STM32, ESP32, Arduino, etc.)
-        if board.transport != "serial" {
+        // Serial transport (STM32, ESP32, Arduino, etc.), or a simulated or
+        // networked board speaking the same protocol
+        let simulated = board.transport == "simulator";
+        if !matches!(board.transport.as_str(), "serial" | "network") && !simulated {
             continue;
         }
-        if board.path.is_none() {
+        if board.path.is_none() && !simulated {
             tracing::warn!("Skipping serial board {}: no path", board.board);
             continue;
         }
@@ -186,8 +221,14 @@ pub async fn create_peripheral_tools(config: &PeripheralsConfig) -> Result<Vec<B
                     tracing::warn!("Peripheral {} connect warning (continuing)", p.name());
                 }
                 serial_transports.push((board.board.clone(), p.transport()));
-                tools.extend(p.tools());
-                if board.board == "arduino-uno" {
+                for tool in p.tools() {
+                    if tools.iter().any(|t| t.name() == tool.name()) {
+                        tracing::warn!("Skipping duplicate tool {} from {}", tool.name(), p.name());
+                        continue;
+                    }
+                    tools.push(tool);
+                }
+                if board.board == "arduino-uno" && board.transport == "serial" {
                     if let Some(ref path) = board.path {
                         tools.push(Box::new(arduino_upload::ArduinoUploadTool::new(
                             path.clone(),
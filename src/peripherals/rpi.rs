@@ -73,6 +73,10 @@ impl Tool for RpiGpioReadTool {
         "Read the value (0 or 1) of a GPIO pin on Raspberry Pi. Uses BCM pin numbers (e.g. 17, 27)."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -4,6 +4,7 @@
 //! Request:  {"id":"1","cmd":"gpio_write","args":{"pin":13,"value":1}}
 //! Response: {"id":"1","ok":true,"result":"done"}
 
+use super::command_tool::{parse_commands, BoardCommand, BoardCommandTool};
 use super::traits::Peripheral;
 use crate::config::PeripheralBoardConfig;
 use crate::tools::traits::{Tool, ToolResult};
@@ -11,9 +12,9 @@ use async_trait::async_trait;
 use serde_json::{json, Value};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::sync::Mutex;
-use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tokio_serial::SerialPortBuilderExt;
 
 /// Allowed serial path patterns (security: deny arbitrary paths).
 const ALLOWED_PATH_PREFIXES: &[&str] = &[
@@ -30,8 +31,17 @@ fn is_path_allowed(path: &str) -> bool {
     ALLOWED_PATH_PREFIXES.iter().any(|p| path.starts_with(p))
 }
 
+/// Byte stream a board is reached over: serial port, pty or in-process pipe.
+pub(crate) trait PeripheralIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> PeripheralIo for T {}
+
 /// JSON request/response over serial.
-async fn send_request(port: &mut SerialStream, cmd: &str, args: Value) -> anyhow::Result<Value> {
+async fn send_request(
+    port: &mut dyn PeripheralIo,
+    cmd: &str,
+    args: Value,
+) -> anyhow::Result<Value> {
     static ID: AtomicU64 = AtomicU64::new(0);
     let id = ID.fetch_add(1, Ordering::Relaxed);
     let id_str = id.to_string();
@@ -46,6 +56,16 @@ async fn send_request(port: &mut SerialStream, cmd: &str, args: Value) -> anyhow
     port.write_all(line.as_bytes()).await?;
     port.flush().await?;
 
+    let resp = read_response(port).await?;
+    let resp_id = resp["id"].as_str().unwrap_or("");
+    if resp_id != id_str {
+        anyhow::bail!("Response id mismatch: expected {}, got {}", id_str, resp_id);
+    }
+    Ok(resp)
+}
+
+/// Read one newline-terminated JSON response.
+pub(crate) async fn read_response(port: &mut dyn PeripheralIo) -> anyhow::Result<Value> {
     let mut buf = Vec::new();
     let mut b = [0u8; 1];
     while port.read_exact(&mut b).await.is_ok() {
@@ -55,28 +75,29 @@ async fn send_request(port: &mut SerialStream, cmd: &str, args: Value) -> anyhow
         buf.push(b[0]);
     }
     let line_str = String::from_utf8_lossy(&buf);
-    let resp: Value = serde_json::from_str(line_str.trim())?;
-    let resp_id = resp["id"].as_str().unwrap_or("");
-    if resp_id != id_str {
-        anyhow::bail!("Response id mismatch: expected {}, got {}", id_str, resp_id);
-    }
-    Ok(resp)
+    Ok(serde_json::from_str(line_str.trim())?)
 }
 
 /// Shared serial transport for tools. Pub(crate) for capabilities tool.
 pub(crate) struct SerialTransport {
-    port: Mutex<SerialStream>,
+    port: Mutex<Box<dyn PeripheralIo>>,
 }
 
 /// Timeout for serial request/response (seconds).
 const SERIAL_TIMEOUT_SECS: u64 = 5;
 
 impl SerialTransport {
-    async fn request(&self, cmd: &str, args: Value) -> anyhow::Result<ToolResult> {
+    pub(crate) fn new(port: impl PeripheralIo + 'static) -> Self {
+        Self {
+            port: Mutex::new(Box::new(port)),
+        }
+    }
+
+    pub(crate) async fn request(&self, cmd: &str, args: Value) -> anyhow::Result<ToolResult> {
         let mut port = self.port.lock().await;
         let resp = tokio::time::timeout(
             std::time::Duration::from_secs(SERIAL_TIMEOUT_SECS),
-            send_request(&mut *port, cmd, args),
+            send_request(port.as_mut(), cmd, args),
         )
         .await
         .map_err(|_| {
@@ -108,11 +129,29 @@ pub struct SerialPeripheral {
     name: String,
     board_type: String,
     transport: Arc<SerialTransport>,
+    /// Commands advertised in `capabilities`, fetched on connect
+    commands: Vec<BoardCommand>,
 }
 
 impl SerialPeripheral {
-    /// Create and connect to a serial peripheral.
+    /// Create and connect to a serial peripheral, to a simulated board when
+    /// `transport = "simulator"`, or to a board on the LAN when
+    /// `transport = "network"`.
     pub async fn connect(config: &PeripheralBoardConfig) -> anyhow::Result<Self> {
+        let opened = match config.transport.as_str() {
+            "simulator" => Some(super::simulator::open(config)?),
+            "network" => Some(Box::pin(super::network::open(config)).await?),
+            _ => None,
+        };
+        if let Some((name, transport)) = opened {
+            return Ok(Self {
+                name,
+                board_type: config.board.clone(),
+                transport: Arc::new(transport),
+                commands: Vec::new(),
+            });
+        }
+
         let path = config
             .path
             .as_deref()
@@ -130,14 +169,13 @@ impl SerialPeripheral {
             .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path, e))?;
 
         let name = format!("{}-{}", config.board, path.replace('/', "_"));
-        let transport = Arc::new(SerialTransport {
-            port: Mutex::new(port),
-        });
+        let transport = Arc::new(SerialTransport::new(port));
 
         Ok(Self {
             name: name.clone(),
             board_type: config.board.clone(),
             transport,
+            commands: Vec::new(),
         })
     }
 }
@@ -152,7 +190,12 @@ impl Peripheral for SerialPeripheral {
         &self.board_type
     }
 
+    /// Fetch the commands the board advertises, so `tools()` can expose them.
     async fn connect(&mut self) -> anyhow::Result<()> {
+        let caps = self.transport.capabilities().await?;
+        if caps.success {
+            self.commands = parse_commands(&self.board_type, &caps.output);
+        }
         Ok(())
     }
 
@@ -168,15 +211,29 @@ impl Peripheral for SerialPeripheral {
             .unwrap_or(false)
     }
 
+    /// Built-in GPIO tools (unless the board advertises its own) followed by
+    /// one tool per advertised command.
     fn tools(&self) -> Vec<Box<dyn Tool>> {
-        vec![
-            Box::new(GpioReadTool {
+        let advertised = |name: &str| self.commands.iter().any(|c| c.name == name);
+        let mut tools: Vec<Box<dyn Tool>> = Vec::new();
+        if !advertised("gpio_read") {
+            tools.push(Box::new(GpioReadTool {
                 transport: self.transport.clone(),
-            }),
-            Box::new(GpioWriteTool {
+            }));
+        }
+        if !advertised("gpio_write") {
+            tools.push(Box::new(GpioWriteTool {
                 transport: self.transport.clone(),
-            }),
-        ]
+            }));
+        }
+        for command in &self.commands {
+            tools.push(Box::new(BoardCommandTool::new(
+                &self.board_type,
+                command.clone(),
+                self.transport.clone(),
+            )));
+        }
+        tools
     }
 }
 
@@ -202,6 +259,10 @@ impl Tool for GpioReadTool {
         "Read the value (0 or 1) of a GPIO pin on a connected peripheral (e.g. STM32 Nucleo)"
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -0,0 +1,556 @@
+//! Simulated board — speaks the serial JSON protocol without hardware.
+//!
+//! Emulates `ping`, `capabilities`, `gpio_read`, `gpio_write` and
+//! `sensor_read`, with pin levels and sensor values taken from an optional
+//! TOML script:
+//!
+//! ```toml
+//! gpio = [2, 3, 13]          # pins reported by `capabilities` (default: board preset)
+//! led_pin = 13
+//!
+//! [pins]
+//! 2 = 1                      # fixed level
+//! 3 = [0, 1, 1, 0]           # successive reads, cycling
+//!
+//! [sensors]
+//! temperature = 21.5
+//! humidity = [40.0, 41.5]
+//! ```
+//!
+//! Boards are reachable in-process (`transport = "simulator"` with no path),
+//! over a pty created by `zeroclaw peripheral simulate <board>`, or over the
+//! network with `zeroclaw peripheral simulate <board> --listen <url>`.
+
+use super::serial::SerialTransport;
+use crate::config::PeripheralBoardConfig;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Pty paths a `simulator` board may be opened from (Linux, macOS).
+const ALLOWED_PTY_PREFIXES: &[&str] = &["/dev/pts/", "/dev/ttys"];
+
+/// Buffer size of the in-process pipe between transport and board.
+const IN_PROCESS_BUFFER: usize = 4096;
+
+/// A single value or a sequence replayed in order (cycling).
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ScriptValue<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+/// Pin levels and sensor values for a simulated board.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SimulatorScript {
+    /// Pins reported by `capabilities` (default: board preset)
+    #[serde(default)]
+    pub gpio: Option<Vec<u32>>,
+    /// LED pin reported by `capabilities` (default: board preset)
+    #[serde(default)]
+    pub led_pin: Option<u32>,
+    /// Pin number → level (0/1) or sequence of levels
+    #[serde(default)]
+    pub pins: HashMap<String, ScriptValue<u8>>,
+    /// Sensor name → value or sequence of values
+    #[serde(default)]
+    pub sensors: BTreeMap<String, ScriptValue<f64>>,
+}
+
+impl SimulatorScript {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read simulator script {}", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("Invalid simulator script {}", path.display()))
+    }
+}
+
+/// Replays a script value; a write replaces it with a fixed value.
+#[derive(Debug, Clone)]
+struct Sequence<T> {
+    values: Vec<T>,
+    next: usize,
+}
+
+impl<T: Copy> Sequence<T> {
+    fn new(value: ScriptValue<T>) -> Option<Self> {
+        let values = match value {
+            ScriptValue::One(v) => vec![v],
+            ScriptValue::Many(vs) => vs,
+        };
+        (!values.is_empty()).then_some(Self { values, next: 0 })
+    }
+
+    fn fixed(value: T) -> Self {
+        Self {
+            values: vec![value],
+            next: 0,
+        }
+    }
+
+    fn next(&mut self) -> T {
+        let value = self.values[self.next % self.values.len()];
+        self.next = (self.next + 1) % self.values.len();
+        value
+    }
+}
+
+/// GPIO pins and LED pin reported by the firmware for each board.
+fn board_preset(board: &str) -> (Vec<u32>, u32) {
+    match board {
+        "esp32" => (vec![0, 1, 2, 3, 4, 5, 12, 13, 14, 15, 16, 17, 18, 19], 2),
+        // arduino-uno, nucleo-f401re, nucleo-f411re and unknown boards
+        _ => ((0..=13).collect(), 13),
+    }
+}
+
+/// An emulated board answering the newline-delimited JSON protocol.
+#[derive(Debug, Clone)]
+pub struct SimulatedBoard {
+    board: String,
+    gpio: Vec<u32>,
+    led_pin: u32,
+    pins: HashMap<u32, Sequence<u8>>,
+    sensors: BTreeMap<String, Sequence<f64>>,
+}
+
+impl SimulatedBoard {
+    pub fn new(board: &str, script: SimulatorScript) -> Result<Self> {
+        let (preset_gpio, preset_led) = board_preset(board);
+        let gpio = script.gpio.unwrap_or(preset_gpio);
+        let led_pin = script.led_pin.unwrap_or(preset_led);
+
+        let mut pins = HashMap::new();
+        for (pin, value) in script.pins {
+            let pin: u32 = pin
+                .trim()
+                .parse()
+                .with_context(|| format!("Simulator script: invalid pin number {pin:?}"))?;
+            if !gpio.contains(&pin) {
+                anyhow::bail!("Simulator script: pin {pin} is not in gpio {gpio:?}");
+            }
+            if let Some(sequence) = Sequence::new(value) {
+                pins.insert(pin, sequence);
+            }
+        }
+        let sensors = script
+            .sensors
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, Sequence::new(value)?)))
+            .collect();
+
+        Ok(Self {
+            board: board.to_string(),
+            gpio,
+            led_pin,
+            pins,
+            sensors,
+        })
+    }
+
+    pub fn board(&self) -> &str {
+        &self.board
+    }
+
+    /// Answer one request line. Malformed requests get an error with id "0",
+    /// like the firmware.
+    pub fn handle_line(&mut self, line: &str) -> Value {
+        let request: Value = serde_json::from_str(line.trim()).unwrap_or(Value::Null);
+        let id = request
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or("0")
+            .to_string();
+        let cmd = request.get("cmd").and_then(Value::as_str).unwrap_or("");
+        let args = request.get("args").cloned().unwrap_or_else(|| json!({}));
+
+        match self.execute(cmd, &args) {
+            Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+            Err(error) => json!({ "id": id, "ok": false, "result": "", "error": error }),
+        }
+    }
+
+    fn execute(&mut self, cmd: &str, args: &Value) -> Result<String, String> {
+        match cmd {
+            "ping" => Ok("pong".into()),
+            "capabilities" => Ok(json!({
+                "gpio": self.gpio,
+                "led_pin": self.led_pin,
+                "sensors": self.sensors.keys().collect::<Vec<_>>(),
+                "commands": self.commands(),
+            })
+            .to_string()),
+            "gpio_read" => {
+                let pin = self.pin_arg(args)?;
+                let level = self.pins.get_mut(&pin).map_or(0, Sequence::next);
+                Ok(level.to_string())
+            }
+            "gpio_write" => {
+                let pin = self.pin_arg(args)?;
+                let value = args
+                    .get("value")
+                    .and_then(Value::as_u64)
+                    .ok_or("Missing value")?;
+                self.pins.insert(pin, Sequence::fixed(u8::from(value != 0)));
+                Ok("done".into())
+            }
+            "sensor_read" => {
+                let name = args
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or("Missing sensor name")?;
+                self.sensors
+                    .get_mut(name)
+                    .map(|sensor| sensor.next().to_string())
+                    .ok_or_else(|| format!("Unknown sensor {name}"))
+            }
+            "" => Err("Invalid request".into()),
+            other => Err(format!("Unknown command: {other}")),
+        }
+    }
+
+    /// Commands advertised in `capabilities`, as self-describing firmware does.
+    fn commands(&self) -> Value {
+        let mut commands = vec![
+            json!({
+                "name": "gpio_read",
+                "description": "Read the value (0 or 1) of a GPIO pin",
+                "read_only": true,
+                "parameters": {
+                    "type": "object",
+                    "properties": { "pin": { "type": "integer", "enum": self.gpio } },
+                    "required": ["pin"]
+                }
+            }),
+            json!({
+                "name": "gpio_write",
+                "description": "Set a GPIO pin high (1) or low (0)",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "pin": { "type": "integer", "enum": self.gpio },
+                        "value": { "type": "integer", "enum": [0, 1] }
+                    },
+                    "required": ["pin", "value"]
+                }
+            }),
+        ];
+        if !self.sensors.is_empty() {
+            commands.push(json!({
+                "name": "sensor_read",
+                "description": "Read the current value of a sensor",
+                "read_only": true,
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "enum": self.sensors.keys().collect::<Vec<_>>() }
+                    },
+                    "required": ["name"]
+                }
+            }));
+        }
+        Value::Array(commands)
+    }
+
+    fn pin_arg(&self, args: &Value) -> Result<u32, String> {
+        let pin = args.get("pin").and_then(Value::as_i64).unwrap_or(-1);
+        u32::try_from(pin)
+            .ok()
+            .filter(|p| self.gpio.contains(p))
+            .ok_or_else(|| format!("Invalid pin {pin}"))
+    }
+
+    /// Serve requests on `io` until it closes.
+    pub async fn serve(mut self, io: impl AsyncRead + AsyncWrite + Unpin) -> Result<()> {
+        let (reader, mut writer) = tokio::io::split(io);
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = self.handle_line(&line);
+            writer.write_all(format!("{response}\n").as_bytes()).await?;
+            writer.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+fn is_pty_path_allowed(path: &str) -> bool {
+    ALLOWED_PTY_PREFIXES.iter().any(|p| path.starts_with(p))
+}
+
+/// Open a `transport = "simulator"` board: over the configured pty, or as an
+/// in-process board when no path is set. Returns the peripheral name and
+/// its transport.
+pub(crate) fn open(config: &PeripheralBoardConfig) -> Result<(String, SerialTransport)> {
+    use tokio_serial::SerialPortBuilderExt;
+
+    if let Some(path) = config.path.as_deref() {
+        if !is_pty_path_allowed(path) {
+            anyhow::bail!(
+                "Simulator path not allowed: {path}. Use the pty printed by `zeroclaw peripheral simulate` (/dev/pts/*, /dev/ttys*)"
+            );
+        }
+        let port = tokio_serial::new(path, config.baud)
+            .open_native_async()
+            .map_err(|e| anyhow::anyhow!("Failed to open {path}: {e}"))?;
+        let name = format!("{}-{}", config.board, path.replace('/', "_"));
+        return Ok((name, SerialTransport::new(port)));
+    }
+
+    let board = load_board(&config.board, config.script.as_deref())?;
+    Ok((format!("{}-sim", config.board), spawn_in_process(board)))
+}
+
+/// Build a simulated board from an optional script path.
+fn load_board(board: &str, script: Option<&str>) -> Result<SimulatedBoard> {
+    let script = script
+        .map(|p| SimulatorScript::load(Path::new(&*shellexpand::tilde(p))))
+        .transpose()?
+        .unwrap_or_default();
+    SimulatedBoard::new(board, script)
+}
+
+/// Run `board` on a background task behind an in-process pipe.
+pub(crate) fn spawn_in_process(board: SimulatedBoard) -> SerialTransport {
+    let (client, server) = tokio::io::duplex(IN_PROCESS_BUFFER);
+    tokio::spawn(async move {
+        if let Err(e) = board.serve(server).await {
+            tracing::warn!("Simulated board stopped: {e}");
+        }
+    });
+    SerialTransport::new(client)
+}
+
+/// `zeroclaw peripheral simulate <board>`: serve a simulated board on a pty
+/// until Ctrl+C.
+#[cfg(unix)]
+pub async fn run_pty(board: &str, script: Option<&str>) -> Result<()> {
+    use tokio_serial::{SerialPort, SerialStream};
+
+    let board = load_board(board, script)?;
+
+    // Keep the slave end open so the master survives clients reconnecting.
+    let (master, slave) = SerialStream::pair().context("Failed to create pty")?;
+    let path = slave
+        .name()
+        .ok_or_else(|| anyhow::anyhow!("pty has no device path"))?;
+
+    println!("🔌 Simulated {} on {path}", board.board());
+    println!();
+    println!("Add it to config.toml:");
+    println!("  [[peripherals.boards]]");
+    println!("  board = \"{}\"", board.board());
+    println!("  transport = \"simulator\"");
+    println!("  path = \"{path}\"");
+    println!();
+    println!("Press Ctrl+C to stop.");
+
+    tokio::select! {
+        result = board.serve(master) => result,
+        _ = tokio::signal::ctrl_c() => {
+            drop(slave);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn run_pty(_board: &str, _script: Option<&str>) -> Result<()> {
+    anyhow::bail!(
+        "pty simulation needs a Unix host; use transport = \"simulator\" without a path for an in-process board"
+    )
+}
+
+/// `zeroclaw peripheral simulate <board> --listen <url>`: serve a simulated
+/// board over TCP, WebSocket or MQTT until Ctrl+C.
+pub async fn run_network(
+    board: &str,
+    script: Option<&str>,
+    listen: &str,
+    secret: Option<&str>,
+) -> Result<()> {
+    let board = load_board(board, script)?;
+    let endpoint = super::network::Endpoint::parse(listen)?;
+
+    println!("📡 Simulated {} on {listen}", board.board());
+    println!();
+    println!("Add it to config.toml:");
+    println!("  [[peripherals.boards]]");
+    println!("  board = \"{}\"", board.board());
+    println!("  transport = \"network\"");
+    println!("  path = \"{listen}\"");
+    if secret.is_some() {
+        println!("  secret = \"env:ZEROCLAW_BOARD_SECRET\"   # same secret as --secret");
+    }
+    println!();
+    println!("Press Ctrl+C to stop.");
+
+    let secret = secret.map(str::to_string);
+    tokio::select! {
+        result = super::network::serve(&endpoint, board, secret) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::serial::SerialPeripheral;
+    use crate::peripherals::traits::Peripheral;
+
+    fn board(script: &str) -> SimulatedBoard {
+        SimulatedBoard::new("arduino-uno", toml::from_str(script).unwrap()).unwrap()
+    }
+
+    fn request(board: &mut SimulatedBoard, cmd: &str, args: Value) -> Value {
+        board.handle_line(&json!({ "id": "7", "cmd": cmd, "args": args }).to_string())
+    }
+
+    #[test]
+    fn capabilities_follow_board_preset_and_script() {
+        let mut uno = board("");
+        let caps = request(&mut uno, "capabilities", json!({}));
+        assert_eq!(caps["id"], "7");
+        let parsed: Value = serde_json::from_str(caps["result"].as_str().unwrap()).unwrap();
+        assert_eq!(parsed["led_pin"], 13);
+        assert_eq!(parsed["gpio"].as_array().unwrap().len(), 14);
+
+        let esp = SimulatedBoard::new("esp32", SimulatorScript::default()).unwrap();
+        assert_eq!(esp.led_pin, 2);
+
+        let mut custom = board("gpio = [4, 5]\nled_pin = 5\n[sensors]\ntemperature = 21.5");
+        let caps = request(&mut custom, "capabilities", json!({}));
+        let parsed: Value = serde_json::from_str(caps["result"].as_str().unwrap()).unwrap();
+        assert_eq!(parsed["gpio"], json!([4, 5]));
+        assert_eq!(parsed["led_pin"], 5);
+        assert_eq!(parsed["sensors"], json!(["temperature"]));
+        let commands: Vec<&str> = parsed["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(commands, ["gpio_read", "gpio_write", "sensor_read"]);
+    }
+
+    #[test]
+    fn scripted_pins_and_sensors_replay_in_order() {
+        let mut sim = board("[pins]\n2 = 1\n3 = [0, 1]\n[sensors]\nhumidity = [40.0, 41.5]");
+        let read = |sim: &mut SimulatedBoard, pin: u32| {
+            request(sim, "gpio_read", json!({ "pin": pin }))["result"].clone()
+        };
+        assert_eq!(read(&mut sim, 2), "1");
+        assert_eq!(read(&mut sim, 3), "0");
+        assert_eq!(read(&mut sim, 3), "1");
+        assert_eq!(read(&mut sim, 3), "0");
+        assert_eq!(read(&mut sim, 4), "0");
+
+        let sensor = |sim: &mut SimulatedBoard| {
+            request(sim, "sensor_read", json!({ "name": "humidity" }))["result"].clone()
+        };
+        assert_eq!(sensor(&mut sim), "40");
+        assert_eq!(sensor(&mut sim), "41.5");
+        assert_eq!(sensor(&mut sim), "40");
+    }
+
+    #[test]
+    fn writes_are_read_back_and_bad_requests_fail() {
+        let mut sim = board("[pins]\n13 = [0, 1]");
+        let write = request(&mut sim, "gpio_write", json!({ "pin": 13, "value": 1 }));
+        assert_eq!(write, json!({"id": "7", "ok": true, "result": "done"}));
+        for _ in 0..2 {
+            assert_eq!(
+                request(&mut sim, "gpio_read", json!({ "pin": 13 }))["result"],
+                "1"
+            );
+        }
+
+        let bad_pin = request(&mut sim, "gpio_read", json!({ "pin": 99 }));
+        assert_eq!(bad_pin["ok"], false);
+        assert_eq!(bad_pin["error"], "Invalid pin 99");
+        let unknown = request(&mut sim, "reboot", json!({}));
+        assert_eq!(unknown["error"], "Unknown command: reboot");
+        let garbage = sim.handle_line("not json");
+        assert_eq!(garbage["id"], "0");
+        assert_eq!(garbage["ok"], false);
+    }
+
+    #[test]
+    fn scripts_are_validated() {
+        let err = SimulatedBoard::new("arduino-uno", toml::from_str("[pins]\n42 = 1").unwrap())
+            .unwrap_err();
+        assert!(err.to_string().contains("pin 42"));
+        assert!(toml::from_str::<SimulatorScript>("typo = 1").is_err());
+        assert!(is_pty_path_allowed("/dev/pts/3"));
+        assert!(!is_pty_path_allowed("/dev/sda"));
+    }
+
+    #[tokio::test]
+    async fn in_process_board_drives_serial_tools() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("sim.toml");
+        std::fs::write(&script, "[pins]\n2 = [1, 0]").unwrap();
+        let config = PeripheralBoardConfig {
+            board: "nucleo-f401re".into(),
+            transport: "simulator".into(),
+            script: Some(script.display().to_string()),
+            ..PeripheralBoardConfig::default()
+        };
+
+        let peripheral = SerialPeripheral::connect(&config).await.unwrap();
+        assert_eq!(peripheral.name(), "nucleo-f401re-sim");
+        assert!(peripheral.health_check().await);
+
+        let tools = peripheral.tools();
+        let tool = |name: &str| tools.iter().find(|t| t.name() == name).unwrap();
+        let first = tool("gpio_read")
+            .execute(json!({ "pin": 2 }))
+            .await
+            .unwrap();
+        assert!(first.success);
+        assert_eq!(first.output, "1");
+        let written = tool("gpio_write")
+            .execute(json!({ "pin": 13, "value": 1 }))
+            .await
+            .unwrap();
+        assert_eq!(written.output, "done");
+        let led = tool("gpio_read")
+            .execute(json!({ "pin": 13 }))
+            .await
+            .unwrap();
+        assert_eq!(led.output, "1");
+
+        let caps = peripheral.transport().capabilities().await.unwrap();
+        assert!(caps.output.contains("\"led_pin\":13"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn pty_board_is_reachable_through_its_device_path() {
+        use tokio_serial::{SerialPort, SerialStream};
+
+        let (master, slave) = SerialStream::pair().unwrap();
+        let path = slave.name().unwrap();
+        tokio::spawn(board("[pins]\n7 = 1").serve(master));
+
+        let config = PeripheralBoardConfig {
+            board: "arduino-uno".into(),
+            transport: "simulator".into(),
+            path: Some(path),
+            ..PeripheralBoardConfig::default()
+        };
+        let peripheral = SerialPeripheral::connect(&config).await.unwrap();
+        let tools = peripheral.tools();
+        let read = tools.iter().find(|t| t.name() == "gpio_read").unwrap();
+        let result = read.execute(json!({ "pin": 7 })).await.unwrap();
+        assert_eq!(result.output, "1");
+        drop(slave);
+    }
+}
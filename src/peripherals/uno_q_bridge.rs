@@ -43,6 +43,10 @@ impl Tool for UnoQGpioReadTool {
         "Read GPIO pin value (0 or 1) on Arduino Uno Q. Requires zeroclaw-uno-q-bridge app running."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
@@ -1,6 +1,7 @@
+use crate::providers::streaming::DeltaSink;
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
-    Provider, ToolCall as ProviderToolCall,
+    Provider, ResponseSchema, ToolCall as ProviderToolCall, Usage,
 };
 use crate::tools::ToolSpec;
 use async_trait::async_trait;
@@ -13,33 +14,26 @@ pub struct AnthropicProvider {
     client: Client,
 }
 
-#[derive(Debug, Serialize)]
-struct ChatRequest {
-    model: String,
-    max_tokens: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
-    messages: Vec<Message>,
-    temperature: f64,
-}
-
-#[derive(Debug, Serialize)]
-struct Message {
-    role: String,
-    content: String,
+/// Marks the end of a prompt prefix Anthropic should cache.
+#[derive(Debug, Clone, Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    kind: &'static str,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatResponse {
-    content: Vec<ContentBlock>,
+impl CacheControl {
+    fn ephemeral() -> Option<Self> {
+        Some(Self { kind: "ephemeral" })
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct ContentBlock {
+#[derive(Debug, Serialize)]
+struct SystemBlock {
     #[serde(rename = "type")]
-    kind: String,
-    #[serde(default)]
-    text: Option<String>,
+    kind: &'static str,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
 }
 
 #[derive(Debug, Serialize)]
@@ -47,11 +41,15 @@ struct NativeChatRequest {
     model: String,
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<Vec<SystemBlock>>,
     messages: Vec<NativeMessage>,
     temperature: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<NativeToolSpec>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -64,7 +62,11 @@ struct NativeMessage {
 #[serde(tag = "type")]
 enum NativeContentOut {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
     #[serde(rename = "tool_use")]
     ToolUse {
         id: String,
@@ -75,6 +77,8 @@ enum NativeContentOut {
     ToolResult {
         tool_use_id: String,
         content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
     },
 }
 
@@ -83,12 +87,40 @@ struct NativeToolSpec {
     name: String,
     description: String,
     input_schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
 }
 
 #[derive(Debug, Deserialize)]
 struct NativeChatResponse {
     #[serde(default)]
     content: Vec<NativeContentIn>,
+    #[serde(default)]
+    usage: Option<NativeUsage>,
+}
+
+/// Anthropic's `input_tokens` already excludes cache reads and writes.
+#[derive(Debug, Default, Deserialize)]
+struct NativeUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+impl From<NativeUsage> for Usage {
+    fn from(usage: NativeUsage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            cache_write_tokens: usage.cache_creation_input_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -105,6 +137,78 @@ struct NativeContentIn {
     input: Option<serde_json::Value>,
 }
 
+/// Rebuilds a [`NativeChatResponse`] from Messages API stream events. Tool
+/// inputs arrive as partial JSON and are parsed once the stream ends.
+#[derive(Debug, Default)]
+struct NativeStream {
+    blocks: Vec<(NativeContentIn, String)>,
+    usage: NativeUsage,
+}
+
+impl NativeStream {
+    fn push(&mut self, event: &str, data: &str, on_delta: &DeltaSink<'_>) -> anyhow::Result<()> {
+        let value: serde_json::Value = serde_json::from_str(data)?;
+        match event {
+            "message_start" => {
+                if let Some(usage) = value.pointer("/message/usage") {
+                    self.usage = serde_json::from_value(usage.clone())?;
+                }
+            }
+            "content_block_start" => {
+                let block = serde_json::from_value(value["content_block"].clone())?;
+                self.blocks.push((block, String::new()));
+            }
+            "content_block_delta" => {
+                let Some((block, partial_json)) = self.blocks.last_mut() else {
+                    return Ok(());
+                };
+                let delta = &value["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => {
+                        let text = delta["text"].as_str().unwrap_or_default();
+                        if !text.is_empty() {
+                            on_delta(text);
+                            block.text.get_or_insert_with(String::new).push_str(text);
+                        }
+                    }
+                    Some("input_json_delta") => {
+                        partial_json.push_str(delta["partial_json"].as_str().unwrap_or_default());
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                if let Some(output) = value
+                    .pointer("/usage/output_tokens")
+                    .and_then(|v| v.as_u64())
+                {
+                    self.usage.output_tokens = output;
+                }
+            }
+            "error" => anyhow::bail!("Anthropic stream error: {}", value["error"]),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> anyhow::Result<NativeChatResponse> {
+        let content = self
+            .blocks
+            .into_iter()
+            .map(|(mut block, partial_json)| {
+                if !partial_json.is_empty() {
+                    block.input = Some(serde_json::from_str(&partial_json)?);
+                }
+                Ok(block)
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(NativeChatResponse {
+            content,
+            usage: Some(self.usage),
+        })
+    }
+}
+
 impl AnthropicProvider {
     pub fn new(api_key: Option<&str>) -> Self {
         Self::with_base_url(api_key, None)
@@ -157,6 +261,7 @@ impl AnthropicProvider {
                     name: tool.name.clone(),
                     description: tool.description.clone(),
                     input_schema: tool.parameters.clone(),
+                    cache_control: None,
                 })
                 .collect(),
         )
@@ -177,6 +282,7 @@ impl AnthropicProvider {
         {
             blocks.push(NativeContentOut::Text {
                 text: text.to_string(),
+                cache_control: None,
             });
         }
         for call in tool_calls {
@@ -207,6 +313,7 @@ impl AnthropicProvider {
             content: vec![NativeContentOut::ToolResult {
                 tool_use_id,
                 content: result,
+                cache_control: None,
             }],
         })
     }
@@ -233,6 +340,7 @@ impl AnthropicProvider {
                             role: "assistant".to_string(),
                             content: vec![NativeContentOut::Text {
                                 text: msg.content.clone(),
+                                cache_control: None,
                             }],
                         });
                     }
@@ -245,6 +353,7 @@ impl AnthropicProvider {
                             role: "user".to_string(),
                             content: vec![NativeContentOut::Text {
                                 text: msg.content.clone(),
+                                cache_control: None,
                             }],
                         });
                     }
@@ -254,6 +363,7 @@ impl AnthropicProvider {
                         role: "user".to_string(),
                         content: vec![NativeContentOut::Text {
                             text: msg.content.clone(),
+                            cache_control: None,
                         }],
                     });
                 }
@@ -263,13 +373,148 @@ impl AnthropicProvider {
         (system_prompt, native_messages)
     }
 
-    fn parse_text_response(response: ChatResponse) -> anyhow::Result<String> {
-        response
-            .content
-            .into_iter()
-            .find(|c| c.kind == "text")
-            .and_then(|c| c.text)
-            .ok_or_else(|| anyhow::anyhow!("No response from Anthropic"))
+    /// Mark the stable prefix for prompt caching. Anthropic renders tools,
+    /// then the system prompt, then messages; a breakpoint after the last
+    /// tool and after the system prompt caches identity, workspace files,
+    /// skills and tool descriptions, and one on the newest message lets the
+    /// next turn reuse the whole conversation so far. That is 3 of the 4
+    /// breakpoints a request may carry. Prefixes shorter than the model's
+    /// minimum (1024 tokens on most models) are simply not cached.
+    fn add_cache_breakpoints(request: &mut NativeChatRequest) {
+        if let Some(tool) = request.tools.as_mut().and_then(|tools| tools.last_mut()) {
+            tool.cache_control = CacheControl::ephemeral();
+        }
+        if let Some(block) = request.system.as_mut().and_then(|blocks| blocks.last_mut()) {
+            block.cache_control = CacheControl::ephemeral();
+        }
+        if let Some(block) = request
+            .messages
+            .last_mut()
+            .and_then(|message| message.content.last_mut())
+        {
+            match block {
+                NativeContentOut::Text { cache_control, .. }
+                | NativeContentOut::ToolResult { cache_control, .. } => {
+                    *cache_control = CacheControl::ephemeral();
+                }
+                NativeContentOut::ToolUse { .. } => {}
+            }
+        }
+    }
+
+    /// Messages API request for `messages` with cache breakpoints on the
+    /// stable prefix. Every chat path goes through here, so one-shot and
+    /// history callers (channels, webchat) get caching as well.
+    fn native_request(
+        messages: &[ChatMessage],
+        tools: Option<Vec<NativeToolSpec>>,
+        model: &str,
+        temperature: f64,
+    ) -> NativeChatRequest {
+        let (system_prompt, messages) = Self::convert_messages(messages);
+        let mut request = NativeChatRequest {
+            model: model.to_string(),
+            max_tokens: 4096,
+            system: Self::system_blocks(system_prompt),
+            messages,
+            temperature,
+            tools,
+            tool_choice: None,
+            stream: false,
+        };
+        Self::add_cache_breakpoints(&mut request);
+        request
+    }
+
+    async fn send_native(&self, request: &NativeChatRequest) -> anyhow::Result<NativeChatResponse> {
+        Ok(self.post_native(request).await?.json().await?)
+    }
+
+    async fn post_native(&self, request: &NativeChatRequest) -> anyhow::Result<reqwest::Response> {
+        let credential = self.credential.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Anthropic credentials not set. Set ANTHROPIC_API_KEY or ANTHROPIC_OAUTH_TOKEN (setup-token)."
+            )
+        })?;
+
+        let req = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(request);
+
+        let response = self.apply_auth(req, credential).send().await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("Anthropic", response).await);
+        }
+
+        Ok(response)
+    }
+
+    fn system_blocks(system_prompt: Option<String>) -> Option<Vec<SystemBlock>> {
+        system_prompt.map(|text| {
+            vec![SystemBlock {
+                kind: "text",
+                text,
+                cache_control: None,
+            }]
+        })
+    }
+
+    /// Anthropic has no response-format option, so structured replies are
+    /// forced through a single tool whose input is the answer. Tool inputs
+    /// must be objects; other schemas are wrapped in `{"value": ...}`.
+    fn forced_tool(schema: &ResponseSchema) -> (NativeToolSpec, bool) {
+        let is_object = schema
+            .schema
+            .get("type")
+            .and_then(serde_json::Value::as_str)
+            == Some("object");
+        let input_schema = if is_object {
+            schema.schema.clone()
+        } else {
+            serde_json::json!({
+                "type": "object",
+                "properties": {"value": schema.schema},
+                "required": ["value"],
+            })
+        };
+        let tool = NativeToolSpec {
+            name: schema.name.clone(),
+            description: "Give your answer by calling this tool; its input is your response."
+                .to_string(),
+            input_schema,
+            cache_control: None,
+        };
+        (tool, !is_object)
+    }
+
+    fn parse_forced_tool_response(
+        response: NativeChatResponse,
+        tool_name: &str,
+        wrapped: bool,
+    ) -> anyhow::Result<String> {
+        let mut text = Vec::new();
+        for block in response.content {
+            match block.kind.as_str() {
+                "tool_use" if block.name.as_deref() == Some(tool_name) => {
+                    let input = block.input.unwrap_or(serde_json::Value::Null);
+                    let answer = if wrapped {
+                        input.get("value").cloned().unwrap_or(input)
+                    } else {
+                        input
+                    };
+                    return Ok(answer.to_string());
+                }
+                "text" => text.extend(block.text),
+                _ => {}
+            }
+        }
+        if text.is_empty() {
+            anyhow::bail!("No response from Anthropic");
+        }
+        Ok(text.join("\n"))
     }
 
     fn parse_native_response(response: NativeChatResponse) -> ProviderChatResponse {
@@ -310,6 +555,7 @@ impl AnthropicProvider {
                 Some(text_parts.join("\n"))
             },
             tool_calls,
+            usage: response.usage.map(Into::into),
         }
     }
 }
@@ -323,40 +569,25 @@ impl Provider for AnthropicProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let credential = self.credential.as_ref().ok_or_else(|| {
-            anyhow::anyhow!(
-                "Anthropic credentials not set. Set ANTHROPIC_API_KEY or ANTHROPIC_OAUTH_TOKEN (setup-token)."
-            )
-        })?;
-
-        let request = ChatRequest {
-            model: model.to_string(),
-            max_tokens: 4096,
-            system: system_prompt.map(ToString::to_string),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: message.to_string(),
-            }],
-            temperature,
-        };
-
-        let mut request = self
-            .client
-            .post(format!("{}/v1/messages", self.base_url))
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&request);
-
-        request = self.apply_auth(request, credential);
-
-        let response = request.send().await?;
-
-        if !response.status().is_success() {
-            return Err(super::api_error("Anthropic", response).await);
+        let mut messages = Vec::with_capacity(2);
+        if let Some(system_prompt) = system_prompt {
+            messages.push(ChatMessage::system(system_prompt));
         }
+        messages.push(ChatMessage::user(message));
+        self.chat_with_history(&messages, model, temperature).await
+    }
 
-        let chat_response: ChatResponse = response.json().await?;
-        Self::parse_text_response(chat_response)
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let native_request = Self::native_request(messages, None, model, temperature);
+        let native_response = self.send_native(&native_request).await?;
+        Self::parse_native_response(native_response)
+            .text
+            .ok_or_else(|| anyhow::anyhow!("No response from Anthropic"))
     }
 
     async fn chat(
@@ -365,36 +596,52 @@ impl Provider for AnthropicProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<ProviderChatResponse> {
-        let credential = self.credential.as_ref().ok_or_else(|| {
-            anyhow::anyhow!(
-                "Anthropic credentials not set. Set ANTHROPIC_API_KEY or ANTHROPIC_OAUTH_TOKEN (setup-token)."
-            )
-        })?;
-
-        let (system_prompt, messages) = Self::convert_messages(request.messages);
-        let native_request = NativeChatRequest {
-            model: model.to_string(),
-            max_tokens: 4096,
-            system: system_prompt,
-            messages,
+        let native_request = Self::native_request(
+            request.messages,
+            Self::convert_tools(request.tools),
+            model,
             temperature,
-            tools: Self::convert_tools(request.tools),
-        };
-
-        let req = self
-            .client
-            .post(format!("{}/v1/messages", self.base_url))
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&native_request);
+        );
+        let native_response = self.send_native(&native_request).await?;
+        Ok(Self::parse_native_response(native_response))
+    }
 
-        let response = self.apply_auth(req, credential).send().await?;
-        if !response.status().is_success() {
-            return Err(super::api_error("Anthropic", response).await);
-        }
+    async fn chat_streaming(
+        &self,
+        request: ProviderChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ProviderChatResponse> {
+        let mut native_request = Self::native_request(
+            request.messages,
+            Self::convert_tools(request.tools),
+            model,
+            temperature,
+        );
+        native_request.stream = true;
+        let response = self.post_native(&native_request).await?;
+        let mut stream = NativeStream::default();
+        super::streaming::for_each_event(response, |event, data| {
+            stream.push(event, data, on_delta)
+        })
+        .await?;
+        Ok(Self::parse_native_response(stream.finish()?))
+    }
 
-        let native_response: NativeChatResponse = response.json().await?;
-        Ok(Self::parse_native_response(native_response))
+    async fn chat_with_schema(
+        &self,
+        request: ProviderChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let (tool, wrapped) = Self::forced_tool(schema);
+        let mut native_request =
+            Self::native_request(request.messages, Some(vec![tool]), model, temperature);
+        native_request.tool_choice = Some(serde_json::json!({"type": "tool", "name": schema.name}));
+        let native_response = self.send_native(&native_request).await?;
+        Self::parse_forced_tool_response(native_response, &schema.name, wrapped)
     }
 
     fn supports_native_tools(&self) -> bool {
@@ -485,16 +732,12 @@ mod tests {
 
     #[test]
     fn chat_request_serializes_without_system() {
-        let req = ChatRequest {
-            model: "claude-3-opus".to_string(),
-            max_tokens: 4096,
-            system: None,
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: "hello".to_string(),
-            }],
-            temperature: 0.7,
-        };
+        let req = AnthropicProvider::native_request(
+            &[ChatMessage::user("hello")],
+            None,
+            "claude-3-opus",
+            0.7,
+        );
         let json = serde_json::to_string(&req).unwrap();
         assert!(
             !json.contains("system"),
@@ -506,24 +749,23 @@ mod tests {
 
     #[test]
     fn chat_request_serializes_with_system() {
-        let req = ChatRequest {
-            model: "claude-3-opus".to_string(),
-            max_tokens: 4096,
-            system: Some("You are ZeroClaw".to_string()),
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: "hello".to_string(),
-            }],
-            temperature: 0.7,
-        };
-        let json = serde_json::to_string(&req).unwrap();
-        assert!(json.contains("\"system\":\"You are ZeroClaw\""));
+        let req = AnthropicProvider::native_request(
+            &[
+                ChatMessage::system("You are ZeroClaw"),
+                ChatMessage::user("hello"),
+            ],
+            None,
+            "claude-3-opus",
+            0.7,
+        );
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["system"][0]["text"], "You are ZeroClaw");
     }
 
     #[test]
     fn chat_response_deserializes() {
         let json = r#"{"content":[{"type":"text","text":"Hello there!"}]}"#;
-        let resp: ChatResponse = serde_json::from_str(json).unwrap();
+        let resp: NativeChatResponse = serde_json::from_str(json).unwrap();
         assert_eq!(resp.content.len(), 1);
         assert_eq!(resp.content[0].kind, "text");
         assert_eq!(resp.content[0].text.as_deref(), Some("Hello there!"));
@@ -532,7 +774,7 @@ mod tests {
     #[test]
     fn chat_response_empty_content() {
         let json = r#"{"content":[]}"#;
-        let resp: ChatResponse = serde_json::from_str(json).unwrap();
+        let resp: NativeChatResponse = serde_json::from_str(json).unwrap();
         assert!(resp.content.is_empty());
     }
 
@@ -540,22 +782,230 @@ mod tests {
     fn chat_response_multiple_blocks() {
         let json =
             r#"{"content":[{"type":"text","text":"First"},{"type":"text","text":"Second"}]}"#;
-        let resp: ChatResponse = serde_json::from_str(json).unwrap();
+        let resp: NativeChatResponse = serde_json::from_str(json).unwrap();
         assert_eq!(resp.content.len(), 2);
         assert_eq!(resp.content[0].text.as_deref(), Some("First"));
         assert_eq!(resp.content[1].text.as_deref(), Some("Second"));
     }
 
+    #[test]
+    fn structured_replies_are_forced_through_a_tool() {
+        let list = ResponseSchema::new(
+            "tags",
+            serde_json::json!({"type": "array", "items": {"type": "string"}}),
+        );
+        let (tool, wrapped) = AnthropicProvider::forced_tool(&list);
+        assert!(wrapped);
+        assert_eq!(tool.name, "tags");
+        assert_eq!(tool.input_schema["properties"]["value"]["type"], "array");
+
+        let response: NativeChatResponse = serde_json::from_str(
+            r#"{"content":[{"type":"tool_use","id":"t1","name":"tags","input":{"value":["a","b"]}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            AnthropicProvider::parse_forced_tool_response(response, "tags", true).unwrap(),
+            r#"["a","b"]"#
+        );
+
+        let verdict = ResponseSchema::new("verdict", serde_json::json!({"type": "object"}));
+        assert!(!AnthropicProvider::forced_tool(&verdict).1);
+        let response: NativeChatResponse =
+            serde_json::from_str(r#"{"content":[{"type":"text","text":"{\"pass\":true}"}]}"#)
+                .unwrap();
+        assert_eq!(
+            AnthropicProvider::parse_forced_tool_response(response, "verdict", false).unwrap(),
+            r#"{"pass":true}"#
+        );
+    }
+
+    #[test]
+    fn stable_prefix_carries_cache_breakpoints() {
+        let history = [
+            ChatMessage::system("You are ZeroClaw"),
+            ChatMessage::user("hello"),
+            ChatMessage::assistant("hi"),
+            ChatMessage::user("list files"),
+        ];
+        let tools = [
+            ToolSpec {
+                name: "shell".into(),
+                description: "Run a command".into(),
+                parameters: serde_json::json!({"type": "object"}),
+            },
+            ToolSpec {
+                name: "file_read".into(),
+                description: "Read a file".into(),
+                parameters: serde_json::json!({"type": "object"}),
+            },
+        ];
+        let request = AnthropicProvider::native_request(
+            &history,
+            AnthropicProvider::convert_tools(Some(&tools)),
+            "claude-sonnet-4",
+            0.7,
+        );
+
+        let json = serde_json::to_value(&request).unwrap();
+        let ephemeral = serde_json::json!({"type": "ephemeral"});
+        assert!(json["tools"][0].get("cache_control").is_none());
+        assert_eq!(json["tools"][1]["cache_control"], ephemeral);
+        assert_eq!(json["system"][0]["text"], "You are ZeroClaw");
+        assert_eq!(json["system"][0]["cache_control"], ephemeral);
+        assert!(json["messages"][0]["content"][0]
+            .get("cache_control")
+            .is_none());
+        assert_eq!(
+            json["messages"][2]["content"][0]["cache_control"],
+            ephemeral
+        );
+        assert_eq!(json.to_string().matches("ephemeral").count(), 3);
+    }
+
+    #[test]
+    fn cache_token_usage_is_reported() {
+        let response: NativeChatResponse = serde_json::from_str(
+            r#"{"content":[{"type":"text","text":"ok"}],"usage":{"input_tokens":12,"output_tokens":30,"cache_creation_input_tokens":0,"cache_read_input_tokens":4100}}"#,
+        )
+        .unwrap();
+        let parsed = AnthropicProvider::parse_native_response(response);
+        assert_eq!(
+            parsed.usage,
+            Some(Usage {
+                input_tokens: 12,
+                output_tokens: 30,
+                cache_read_tokens: 4100,
+                cache_write_tokens: 0,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn history_chat_sends_cache_breakpoints() {
+        use axum::{routing::post, Json, Router};
+        use std::sync::{Arc, Mutex};
+
+        let seen: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let app = Router::new().route(
+            "/v1/messages",
+            post({
+                let seen = Arc::clone(&seen);
+                move |Json(body): Json<serde_json::Value>| async move {
+                    seen.lock().unwrap().push(body);
+                    Json(serde_json::json!({
+                        "content": [{"type": "text", "text": "done"}],
+                        "usage": {"input_tokens": 3, "output_tokens": 1}
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let provider =
+            AnthropicProvider::with_base_url(Some("sk-ant-test"), Some(&format!("http://{addr}")));
+        let history = [
+            ChatMessage::system("You are ZeroClaw"),
+            ChatMessage::user("hello"),
+            ChatMessage::assistant("hi"),
+            ChatMessage::user("list files"),
+        ];
+        let reply = provider
+            .chat_with_history(&history, "claude-sonnet-4", 0.7)
+            .await
+            .unwrap();
+        assert_eq!(reply, "done");
+
+        let body = seen.lock().unwrap().pop().unwrap();
+        let ephemeral = serde_json::json!({"type": "ephemeral"});
+        assert_eq!(body["system"][0]["cache_control"], ephemeral);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            body["messages"][2]["content"][0]["cache_control"],
+            ephemeral
+        );
+        assert!(body["messages"][0]["content"][0]
+            .get("cache_control")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn streaming_chat_forwards_text_and_joins_tool_input() {
+        use axum::{routing::post, Json, Router};
+        use std::sync::{Arc, Mutex};
+
+        let seen: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let app = Router::new().route(
+            "/v1/messages",
+            post({
+                let seen = Arc::clone(&seen);
+                move |Json(body): Json<serde_json::Value>| async move {
+                    seen.lock().unwrap().push(body);
+                    [
+                        r#"{"message":{"usage":{"input_tokens":9,"cache_read_input_tokens":100}}}"#,
+                        r#"{"index":0,"content_block":{"type":"text","text":""}}"#,
+                        r#"{"index":0,"delta":{"type":"text_delta","text":"Let me "}}"#,
+                        r#"{"index":0,"delta":{"type":"text_delta","text":"check."}}"#,
+                        r#"{"index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"shell","input":{}}}"#,
+                        r#"{"index":1,"delta":{"type":"input_json_delta","partial_json":"{\"command\":"}}"#,
+                        r#"{"index":1,"delta":{"type":"input_json_delta","partial_json":"\"ls\"}"}}"#,
+                        r#"{"usage":{"output_tokens":21}}"#,
+                    ]
+                    .iter()
+                    .zip([
+                        "message_start",
+                        "content_block_start",
+                        "content_block_delta",
+                        "content_block_delta",
+                        "content_block_start",
+                        "content_block_delta",
+                        "content_block_delta",
+                        "message_delta",
+                    ])
+                    .map(|(data, event)| format!("event: {event}\ndata: {data}\n\n"))
+                    .collect::<String>()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let provider =
+            AnthropicProvider::with_base_url(Some("sk-ant-test"), Some(&format!("http://{addr}")));
+        let deltas = Mutex::new(Vec::new());
+        let messages = [ChatMessage::user("list files")];
+        let response = provider
+            .chat_streaming(
+                ProviderChatRequest {
+                    messages: &messages,
+                    tools: None,
+                },
+                "claude-sonnet-4",
+                0.7,
+                &|text| deltas.lock().unwrap().push(text.to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(seen.lock().unwrap()[0]["stream"], true);
+        assert_eq!(*deltas.lock().unwrap(), ["Let me ", "check."]);
+        assert_eq!(response.text.as_deref(), Some("Let me check."));
+        assert_eq!(response.tool_calls[0].id, "toolu_1");
+        assert_eq!(response.tool_calls[0].arguments, r#"{"command":"ls"}"#);
+        let usage = response.usage.unwrap();
+        assert_eq!((usage.output_tokens, usage.cache_read_tokens), (21, 100));
+    }
+
     #[test]
     fn temperature_range_serializes() {
         for temp in [0.0, 0.5, 1.0, 2.0] {
-            let req = ChatRequest {
-                model: "claude-3-opus".to_string(),
-                max_tokens: 4096,
-                system: None,
-                messages: vec![],
-                temperature: temp,
-            };
+            let req = AnthropicProvider::native_request(&[], None, "claude-3-opus", temp);
             let json = serde_json::to_string(&req).unwrap();
             assert!(json.contains(&format!("{temp}")));
         }
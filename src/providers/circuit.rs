@@ -0,0 +1,350 @@
+//! Per provider/model circuit breakers.
+//!
+//! Every [`ReliableProvider`](super::reliable::ReliableProvider) built by the
+//! provider factories shares one [`CircuitRegistry`], so when the gateway,
+//! channels, heartbeat and scheduler talk to the same provider, an outage
+//! trips one breaker and all of them stop waiting on it. A breaker is
+//! closed while calls succeed, opens after enough consecutive failures, and
+//! turns half-open after a cool-down (or a successful background probe) to
+//! let a single trial call decide whether it closes again.
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Weight of the newest call in the health score and latency averages.
+const SCORE_ALPHA: f64 = 0.2;
+
+/// Closed circuits scoring below this are tried after healthier ones.
+pub const DEGRADED_SCORE: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls flow normally.
+    Closed,
+    /// Calls are skipped until the cool-down ends.
+    Open,
+    /// One trial call decides whether the circuit closes or re-opens.
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half-open",
+        })
+    }
+}
+
+/// When a breaker opens and how long it stays open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerSettings {
+    /// Consecutive failures that open the circuit.
+    pub failure_threshold: u32,
+    /// Cool-down before an open circuit lets a trial call through.
+    pub open_duration: Duration,
+}
+
+impl BreakerSettings {
+    /// Settings from `[reliability]`, or `None` when breakers are disabled.
+    pub fn from_config(reliability: &crate::config::ReliabilityConfig) -> Option<Self> {
+        (reliability.circuit_failure_threshold > 0).then(|| Self {
+            failure_threshold: reliability.circuit_failure_threshold,
+            open_duration: Duration::from_secs(reliability.circuit_open_secs.max(1)),
+        })
+    }
+}
+
+/// Health of one provider/model pair, as reported by `health::snapshot`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Recent success rate from 0.0 to 1.0, weighted towards the latest calls.
+    pub score: f64,
+    /// Weighted average latency of successful calls.
+    pub latency_ms: Option<u64>,
+    pub last_ok: Option<String>,
+    pub last_error: Option<String>,
+    /// When the circuit last opened.
+    pub opened_at: Option<String>,
+    /// Outcome of the most recent background probe.
+    pub last_probe: Option<String>,
+}
+
+struct Breaker {
+    provider: String,
+    health: ProviderHealth,
+    opened: Option<Instant>,
+    trial_started: Option<Instant>,
+}
+
+impl Breaker {
+    fn new(provider: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            health: ProviderHealth {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                score: 1.0,
+                latency_ms: None,
+                last_ok: None,
+                last_error: None,
+                opened_at: None,
+                last_probe: None,
+            },
+            opened: None,
+            trial_started: None,
+        }
+    }
+}
+
+/// Breaker state for every provider/model pair seen so far.
+#[derive(Default)]
+pub struct CircuitRegistry {
+    breakers: Mutex<BTreeMap<String, Breaker>>,
+}
+
+static SHARED: OnceLock<Arc<CircuitRegistry>> = OnceLock::new();
+
+/// The process-wide registry used by the provider factories.
+pub fn shared() -> Arc<CircuitRegistry> {
+    Arc::clone(SHARED.get_or_init(Arc::default))
+}
+
+/// Registry key for `model` served by `provider`.
+pub fn breaker_key(provider: &str, model: &str) -> String {
+    format!("{provider}/{model}")
+}
+
+fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339()
+}
+
+impl CircuitRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self, provider: &str, model: &str) -> CircuitState {
+        self.breakers
+            .lock()
+            .get(&breaker_key(provider, model))
+            .map_or(CircuitState::Closed, |b| b.health.state)
+    }
+
+    pub fn score(&self, provider: &str, model: &str) -> f64 {
+        self.breakers
+            .lock()
+            .get(&breaker_key(provider, model))
+            .map_or(1.0, |b| b.health.score)
+    }
+
+    /// Whether a call may go to `model` on `provider` now. An open circuit
+    /// whose cool-down has passed turns half-open and admits one trial call.
+    pub fn allow(&self, provider: &str, model: &str, settings: &BreakerSettings) -> bool {
+        let key = breaker_key(provider, model);
+        let mut breakers = self.breakers.lock();
+        let Some(breaker) = breakers.get_mut(&key) else {
+            return true;
+        };
+        let now = Instant::now();
+        let cooling = |since: Option<Instant>| {
+            since.is_some_and(|at| now.duration_since(at) < settings.open_duration)
+        };
+        match breaker.health.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => {
+                if cooling(breaker.opened) {
+                    return false;
+                }
+                breaker.health.state = CircuitState::HalfOpen;
+                breaker.trial_started = Some(now);
+                tracing::info!(breaker = key, "Circuit half-open, sending a trial call");
+                true
+            }
+            // A trial that never reported back (its request was cancelled)
+            // stops blocking others after one cool-down.
+            CircuitState::HalfOpen => {
+                if cooling(breaker.trial_started) {
+                    return false;
+                }
+                breaker.trial_started = Some(now);
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self, provider: &str, model: &str, latency: Duration) {
+        let key = breaker_key(provider, model);
+        let mut breakers = self.breakers.lock();
+        let breaker = breakers
+            .entry(key.clone())
+            .or_insert_with(|| Breaker::new(provider));
+        if breaker.health.state != CircuitState::Closed {
+            tracing::info!(breaker = key, "Circuit closed, provider recovered");
+        }
+        let latency_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        let health = &mut breaker.health;
+        health.state = CircuitState::Closed;
+        health.consecutive_failures = 0;
+        health.score = health.score * (1.0 - SCORE_ALPHA) + SCORE_ALPHA;
+        health.latency_ms = Some(health.latency_ms.map_or(latency_ms, |avg| {
+            avg.saturating_mul(4).saturating_add(latency_ms) / 5
+        }));
+        health.last_ok = Some(now_rfc3339());
+        breaker.opened = None;
+        breaker.trial_started = None;
+    }
+
+    /// Count a failed call. Returns `true` when the circuit is now open.
+    pub fn record_failure(
+        &self,
+        provider: &str,
+        model: &str,
+        error: &str,
+        settings: &BreakerSettings,
+    ) -> bool {
+        let key = breaker_key(provider, model);
+        let mut breakers = self.breakers.lock();
+        let breaker = breakers
+            .entry(key.clone())
+            .or_insert_with(|| Breaker::new(provider));
+        let health = &mut breaker.health;
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        health.score *= 1.0 - SCORE_ALPHA;
+        health.last_error = Some(error.to_string());
+
+        let trips = health.state == CircuitState::HalfOpen
+            || health.consecutive_failures >= settings.failure_threshold;
+        if trips {
+            if health.state != CircuitState::Open {
+                tracing::warn!(
+                    breaker = key,
+                    failures = health.consecutive_failures,
+                    cooldown_secs = settings.open_duration.as_secs(),
+                    "Circuit opened"
+                );
+            }
+            health.state = CircuitState::Open;
+            health.opened_at = Some(now_rfc3339());
+            breaker.opened = Some(Instant::now());
+            breaker.trial_started = None;
+        }
+        trips
+    }
+
+    /// Apply a background probe of `provider`: success lets its open
+    /// circuits send a trial call right away, failure restarts their cool-down.
+    pub fn record_probe(&self, provider: &str, result: Result<(), String>) {
+        let now = now_rfc3339();
+        let mut breakers = self.breakers.lock();
+        for breaker in breakers.values_mut().filter(|b| b.provider == provider) {
+            match &result {
+                Ok(()) => {
+                    breaker.health.last_probe = Some(format!("{now} ok"));
+                    if breaker.health.state == CircuitState::Open {
+                        breaker.health.state = CircuitState::HalfOpen;
+                        breaker.trial_started = None;
+                    }
+                }
+                Err(e) => {
+                    breaker.health.last_probe = Some(format!("{now} failed: {e}"));
+                    if breaker.health.state == CircuitState::Open {
+                        breaker.opened = Some(Instant::now());
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, ProviderHealth> {
+        self.breakers
+            .lock()
+            .iter()
+            .map(|(key, breaker)| (key.clone(), breaker.health.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: BreakerSettings = BreakerSettings {
+        failure_threshold: 3,
+        open_duration: Duration::from_millis(40),
+    };
+
+    #[test]
+    fn breaker_opens_after_threshold_and_recovers_through_half_open() {
+        let registry = CircuitRegistry::new();
+        assert!(registry.allow("p", "m", &SETTINGS));
+
+        assert!(!registry.record_failure("p", "m", "500", &SETTINGS));
+        assert!(!registry.record_failure("p", "m", "500", &SETTINGS));
+        assert!(registry.record_failure("p", "m", "500", &SETTINGS));
+        assert_eq!(registry.state("p", "m"), CircuitState::Open);
+        assert!(!registry.allow("p", "m", &SETTINGS));
+        // Other models on the same provider are unaffected.
+        assert!(registry.allow("p", "other", &SETTINGS));
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(registry.allow("p", "m", &SETTINGS));
+        assert_eq!(registry.state("p", "m"), CircuitState::HalfOpen);
+        // Only one trial call at a time.
+        assert!(!registry.allow("p", "m", &SETTINGS));
+
+        registry.record_success("p", "m", Duration::from_millis(120));
+        assert_eq!(registry.state("p", "m"), CircuitState::Closed);
+        let health = &registry.snapshot()["p/m"];
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(health.latency_ms, Some(120));
+        assert!(health.score < 1.0);
+    }
+
+    #[test]
+    fn failed_trial_reopens_the_circuit() {
+        let registry = CircuitRegistry::new();
+        for _ in 0..3 {
+            registry.record_failure("p", "m", "timeout", &SETTINGS);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(registry.allow("p", "m", &SETTINGS));
+        assert!(registry.record_failure("p", "m", "timeout", &SETTINGS));
+        assert!(!registry.allow("p", "m", &SETTINGS));
+    }
+
+    #[test]
+    fn probes_update_every_model_of_the_provider() {
+        let registry = CircuitRegistry::new();
+        for _ in 0..3 {
+            registry.record_failure("p", "a", "down", &SETTINGS);
+            registry.record_failure("p", "b", "down", &SETTINGS);
+        }
+        registry.record_success("q", "a", Duration::from_millis(5));
+
+        registry.record_probe("p", Err("connection refused".into()));
+        assert_eq!(registry.state("p", "a"), CircuitState::Open);
+
+        registry.record_probe("p", Ok(()));
+        assert_eq!(registry.state("p", "a"), CircuitState::HalfOpen);
+        assert_eq!(registry.state("p", "b"), CircuitState::HalfOpen);
+        assert!(registry.allow("p", "a", &SETTINGS));
+
+        let snapshot = registry.snapshot();
+        assert!(snapshot["p/a"]
+            .last_probe
+            .as_deref()
+            .unwrap()
+            .ends_with(" ok"));
+        assert!(snapshot["q/a"].last_probe.is_none());
+    }
+}
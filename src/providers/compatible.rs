@@ -2,9 +2,11 @@
 //! Most LLM APIs follow the same `/v1/chat/completions` format.
 //! This module provides a single implementation that works for all of them.
 
+use crate::providers::prompt_cache::OpenAiUsage;
+use crate::providers::streaming::{DeltaSink, OpenAiStream};
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
-    Provider, ToolCall as ProviderToolCall,
+    Provider, ToolCall as ProviderToolCall, Usage,
 };
 use async_trait::async_trait;
 use reqwest::Client;
@@ -143,6 +145,9 @@ struct ChatRequest {
     temperature: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    /// Asks for a final chunk carrying `usage` when streaming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -154,6 +159,8 @@ struct Message {
 #[derive(Debug, Deserialize)]
 struct ApiChatResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -303,17 +310,15 @@ impl OpenAiCompatibleProvider {
         extract_responses_text(responses)
             .ok_or_else(|| anyhow::anyhow!("No response from {} Responses API", self.name))
     }
-}
 
-#[async_trait]
-impl Provider for OpenAiCompatibleProvider {
-    async fn chat_with_system(
+    /// Chat completion over `messages`, with the usage the server reported.
+    /// Tool calls come back serialized as the JSON message.
+    async fn chat_completion(
         &self,
-        system_prompt: Option<&str>,
-        message: &str,
+        messages: &[ChatMessage],
         model: &str,
         temperature: f64,
-    ) -> anyhow::Result<String> {
+    ) -> anyhow::Result<(String, Option<Usage>)> {
         let api_key = self.api_key.as_ref().ok_or_else(|| {
             anyhow::anyhow!(
                 "{} API key not set. Run `zeroclaw onboard` or set the appropriate env var.",
@@ -321,29 +326,23 @@ impl Provider for OpenAiCompatibleProvider {
             )
         })?;
 
-        let mut messages = Vec::new();
-
-        if let Some(sys) = system_prompt {
-            messages.push(Message {
-                role: "system".to_string(),
-                content: sys.to_string(),
-            });
-        }
-
-        messages.push(Message {
-            role: "user".to_string(),
-            content: message.to_string(),
-        });
+        let api_messages: Vec<Message> = messages
+            .iter()
+            .map(|m| Message {
+                role: m.role.clone(),
+                content: m.content.clone(),
+            })
+            .collect();
 
         let request = ChatRequest {
             model: model.to_string(),
-            messages,
+            messages: api_messages,
             temperature,
             stream: Some(false),
+            stream_options: None,
         };
 
         let url = self.chat_completions_url();
-
         let response = self
             .apply_auth_header(self.client.post(&url).json(&request), api_key)
             .send()
@@ -351,25 +350,36 @@ impl Provider for OpenAiCompatibleProvider {
 
         if !response.status().is_success() {
             let status = response.status();
-            let error = response.text().await?;
-            let sanitized = super::sanitize_api_error(&error);
 
+            // Mirror chat_with_system: 404 may mean this provider uses the Responses API
             if status == reqwest::StatusCode::NOT_FOUND && self.supports_responses_fallback {
-                return self
-                    .chat_via_responses(api_key, system_prompt, message, model)
-                    .await
-                    .map_err(|responses_err| {
-                        anyhow::anyhow!(
-                            "{} API error ({status}): {sanitized} (chat completions unavailable; responses fallback failed: {responses_err})",
-                            self.name
+                // Extract system prompt and last user message for responses fallback
+                let system = messages.iter().find(|m| m.role == "system");
+                let last_user = messages.iter().rfind(|m| m.role == "user");
+                if let Some(user_msg) = last_user {
+                    return self
+                        .chat_via_responses(
+                            api_key,
+                            system.map(|m| m.content.as_str()),
+                            &user_msg.content,
+                            model,
                         )
-                    });
+                        .await
+                        .map(|text| (text, None))
+                        .map_err(|responses_err| {
+                            anyhow::anyhow!(
+                                "{} API error (chat completions unavailable; responses fallback failed: {responses_err})",
+                                self.name
+                            )
+                        });
+                }
             }
 
-            anyhow::bail!("{} API error ({status}): {sanitized}", self.name);
+            return Err(super::api_error(&self.name, response).await);
         }
 
         let chat_response: ApiChatResponse = response.json().await?;
+        let usage = chat_response.usage.map(Into::into);
 
         chat_response
             .choices
@@ -391,12 +401,17 @@ impl Provider for OpenAiCompatibleProvider {
                     c.message.content.unwrap_or_default()
                 }
             })
+            .map(|text| (text, usage))
             .ok_or_else(|| anyhow::anyhow!("No response from {}", self.name))
     }
+}
 
-    async fn chat_with_history(
+#[async_trait]
+impl Provider for OpenAiCompatibleProvider {
+    async fn chat_with_system(
         &self,
-        messages: &[ChatMessage],
+        system_prompt: Option<&str>,
+        message: &str,
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
@@ -407,22 +422,30 @@ impl Provider for OpenAiCompatibleProvider {
             )
         })?;
 
-        let api_messages: Vec<Message> = messages
-            .iter()
-            .map(|m| Message {
-                role: m.role.clone(),
-                content: m.content.clone(),
-            })
-            .collect();
+        let mut messages = Vec::new();
+
+        if let Some(sys) = system_prompt {
+            messages.push(Message {
+                role: "system".to_string(),
+                content: sys.to_string(),
+            });
+        }
+
+        messages.push(Message {
+            role: "user".to_string(),
+            content: message.to_string(),
+        });
 
         let request = ChatRequest {
             model: model.to_string(),
-            messages: api_messages,
+            messages,
             temperature,
             stream: Some(false),
+            stream_options: None,
         };
 
         let url = self.chat_completions_url();
+
         let response = self
             .apply_auth_header(self.client.post(&url).json(&request), api_key)
             .send()
@@ -430,31 +453,22 @@ impl Provider for OpenAiCompatibleProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            let error = response.text().await?;
+            let sanitized = super::sanitize_api_error(&error);
 
-            // Mirror chat_with_system: 404 may mean this provider uses the Responses API
             if status == reqwest::StatusCode::NOT_FOUND && self.supports_responses_fallback {
-                // Extract system prompt and last user message for responses fallback
-                let system = messages.iter().find(|m| m.role == "system");
-                let last_user = messages.iter().rfind(|m| m.role == "user");
-                if let Some(user_msg) = last_user {
-                    return self
-                        .chat_via_responses(
-                            api_key,
-                            system.map(|m| m.content.as_str()),
-                            &user_msg.content,
-                            model,
+                return self
+                    .chat_via_responses(api_key, system_prompt, message, model)
+                    .await
+                    .map_err(|responses_err| {
+                        anyhow::anyhow!(
+                            "{} API error ({status}): {sanitized} (chat completions unavailable; responses fallback failed: {responses_err})",
+                            self.name
                         )
-                        .await
-                        .map_err(|responses_err| {
-                            anyhow::anyhow!(
-                                "{} API error (chat completions unavailable; responses fallback failed: {responses_err})",
-                                self.name
-                            )
-                        });
-                }
+                    });
             }
 
-            return Err(super::api_error(&self.name, response).await);
+            anyhow::bail!("{} API error ({status}): {sanitized}", self.name);
         }
 
         let chat_response: ApiChatResponse = response.json().await?;
@@ -482,18 +496,33 @@ impl Provider for OpenAiCompatibleProvider {
             .ok_or_else(|| anyhow::anyhow!("No response from {}", self.name))
     }
 
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.chat_completion(messages, model, temperature)
+            .await
+            .map(|(text, _)| text)
+    }
+
     async fn chat(
         &self,
         request: ProviderChatRequest<'_>,
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<ProviderChatResponse> {
-        let text = self
-            .chat_with_history(request.messages, model, temperature)
+        let (text, usage) = self
+            .chat_completion(request.messages, model, temperature)
             .await?;
 
         // Backward compatible path: chat_with_history may serialize tool_calls JSON into content.
-        if let Ok(message) = serde_json::from_str::<ResponseMessage>(&text) {
+        // A reply that merely is JSON has no tool calls and stays text.
+        if let Some(message) = serde_json::from_str::<ResponseMessage>(&text)
+            .ok()
+            .filter(|message| message.tool_calls.as_ref().is_some_and(|t| !t.is_empty()))
+        {
             let tool_calls = message
                 .tool_calls
                 .unwrap_or_default()
@@ -513,15 +542,68 @@ impl Provider for OpenAiCompatibleProvider {
             return Ok(ProviderChatResponse {
                 text: message.content,
                 tool_calls,
+                usage,
             });
         }
 
         Ok(ProviderChatResponse {
             text: Some(text),
             tool_calls: vec![],
+            usage,
         })
     }
 
+    async fn chat_streaming(
+        &self,
+        request: ProviderChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ProviderChatResponse> {
+        let Some(api_key) = self.api_key.as_ref() else {
+            return self.chat(request, model, temperature).await;
+        };
+        let body = ChatRequest {
+            model: model.to_string(),
+            messages: request
+                .messages
+                .iter()
+                .map(|m| Message {
+                    role: m.role.clone(),
+                    content: m.content.clone(),
+                })
+                .collect(),
+            temperature,
+            stream: Some(true),
+            stream_options: Some(serde_json::json!({"include_usage": true})),
+        };
+        let response = self
+            .apply_auth_header(
+                self.client.post(self.chat_completions_url()).json(&body),
+                api_key,
+            )
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            // Servers that reject streaming (or only speak the Responses API)
+            // still answer the plain request; the reply becomes one delta.
+            if matches!(status.as_u16(), 400 | 404 | 405 | 422) {
+                let reply = self.chat(request, model, temperature).await?;
+                if let Some(text) = reply.text.as_deref().filter(|text| !text.is_empty()) {
+                    on_delta(text);
+                }
+                return Ok(reply);
+            }
+            return Err(super::api_error(&self.name, response).await);
+        }
+
+        let mut stream = OpenAiStream::default();
+        super::streaming::for_each_event(response, |_, data| stream.push(data, on_delta)).await?;
+        Ok(stream.finish())
+    }
+
     fn supports_native_tools(&self) -> bool {
         true
     }
@@ -584,6 +666,7 @@ mod tests {
             ],
             temperature: 0.4,
             stream: Some(false),
+            stream_options: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("llama-3.3-70b"));
@@ -653,6 +736,55 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn chat_keeps_json_replies_as_text_and_reports_usage() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(|| async {
+                Json(serde_json::json!({
+                    "choices": [{"message": {"content": "{\"answer\": 42}"}}],
+                    "usage": {
+                        "prompt_tokens": 1200,
+                        "completion_tokens": 8,
+                        "prompt_tokens_details": {"cached_tokens": 1024}
+                    }
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let p = make_provider("custom", &format!("http://{addr}/v1"), Some("key"));
+        let messages = [ChatMessage::user("answer as JSON")];
+        let response = p
+            .chat(
+                ProviderChatRequest {
+                    messages: &messages,
+                    tools: None,
+                },
+                "model",
+                0.0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.text.as_deref(), Some("{\"answer\": 42}"));
+        assert!(response.tool_calls.is_empty());
+        assert_eq!(
+            response.usage,
+            Some(Usage {
+                input_tokens: 176,
+                output_tokens: 8,
+                cache_read_tokens: 1024,
+                cache_write_tokens: 0,
+            })
+        );
+    }
+
     #[test]
     fn responses_extracts_top_level_output_text() {
         let json = r#"{"output_text":"Hello from top-level","output":[]}"#;
@@ -3,7 +3,7 @@
 //! - Gemini CLI OAuth tokens (reuse existing ~/.gemini/ authentication)
 //! - Google Cloud ADC (`GOOGLE_APPLICATION_CREDENTIALS`)
 
-use crate::providers::traits::Provider;
+use crate::providers::traits::{ChatMessage, ChatRequest, Provider, ResponseSchema};
 use async_trait::async_trait;
 use directories::UserDirs;
 use reqwest::Client;
@@ -80,6 +80,10 @@ struct GenerationConfig {
     temperature: f64,
     #[serde(rename = "maxOutputTokens")]
     max_output_tokens: u32,
+    #[serde(rename = "responseMimeType", skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
+    #[serde(rename = "responseSchema", skip_serializing_if = "Option::is_none")]
+    response_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -238,6 +242,87 @@ impl GeminiProvider {
         }
     }
 
+    fn require_auth(&self) -> anyhow::Result<&GeminiAuth> {
+        self.auth.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Gemini API key not found. Options:\n\
+                 1. Set GEMINI_API_KEY env var\n\
+                 2. Run `gemini` CLI to authenticate (tokens will be reused)\n\
+                 3. Get an API key from https://aistudio.google.com/app/apikey\n\
+                 4. Run `zeroclaw onboard` to configure"
+            )
+        })
+    }
+
+    /// System messages become the system instruction; assistant turns use
+    /// Gemini's `model` role and everything else is sent as `user`.
+    fn convert_messages(messages: &[ChatMessage]) -> (Option<Content>, Vec<Content>) {
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == "system")
+            .map(|m| m.content.as_str())
+            .collect();
+        let system_instruction = (!system.is_empty()).then(|| Content {
+            role: None,
+            parts: vec![Part {
+                text: system.join("\n\n"),
+            }],
+        });
+        let contents = messages
+            .iter()
+            .filter(|m| m.role != "system")
+            .map(|m| Content {
+                role: Some(
+                    if m.role == "assistant" {
+                        "model"
+                    } else {
+                        "user"
+                    }
+                    .to_string(),
+                ),
+                parts: vec![Part {
+                    text: m.content.clone(),
+                }],
+            })
+            .collect();
+        (system_instruction, contents)
+    }
+
+    async fn generate_content(
+        &self,
+        auth: &GeminiAuth,
+        model: &str,
+        request: &GenerateContentRequest,
+    ) -> anyhow::Result<String> {
+        let url = Self::build_generate_content_url(model, auth);
+
+        let response = self
+            .build_generate_content_request(auth, &url, request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Gemini API error ({status}): {error_text}");
+        }
+
+        let result: GenerateContentResponse = response.json().await?;
+
+        // Check for API error in response body
+        if let Some(err) = result.error {
+            anyhow::bail!("Gemini API error: {}", err.message);
+        }
+
+        // Extract text from response
+        result
+            .candidates
+            .and_then(|c| c.into_iter().next())
+            .and_then(|c| c.content.parts.into_iter().next())
+            .and_then(|p| p.text)
+            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))
+    }
+
     fn build_generate_content_request(
         &self,
         auth: &GeminiAuth,
@@ -261,15 +346,7 @@ impl Provider for GeminiProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let auth = self.auth.as_ref().ok_or_else(|| {
-            anyhow::anyhow!(
-                "Gemini API key not found. Options:\n\
-                 1. Set GEMINI_API_KEY env var\n\
-                 2. Run `gemini` CLI to authenticate (tokens will be reused)\n\
-                 3. Get an API key from https://aistudio.google.com/app/apikey\n\
-                 4. Run `zeroclaw onboard` to configure"
-            )
-        })?;
+        let auth = self.require_auth()?;
 
         // Build request
         let system_instruction = system_prompt.map(|sys| Content {
@@ -290,36 +367,35 @@ impl Provider for GeminiProvider {
             generation_config: GenerationConfig {
                 temperature,
                 max_output_tokens: 8192,
+                response_mime_type: None,
+                response_schema: None,
             },
         };
 
-        let url = Self::build_generate_content_url(model, auth);
-
-        let response = self
-            .build_generate_content_request(auth, &url, &request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Gemini API error ({status}): {error_text}");
-        }
-
-        let result: GenerateContentResponse = response.json().await?;
+        self.generate_content(auth, model, &request).await
+    }
 
-        // Check for API error in response body
-        if let Some(err) = result.error {
-            anyhow::bail!("Gemini API error: {}", err.message);
-        }
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let auth = self.require_auth()?;
 
-        // Extract text from response
-        result
-            .candidates
-            .and_then(|c| c.into_iter().next())
-            .and_then(|c| c.content.parts.into_iter().next())
-            .and_then(|p| p.text)
-            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))
+        let (system_instruction, contents) = Self::convert_messages(request.messages);
+        let request = GenerateContentRequest {
+            contents,
+            system_instruction,
+            generation_config: GenerationConfig {
+                temperature,
+                max_output_tokens: 8192,
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: Some(super::structured::to_gemini_schema(&schema.schema)),
+            },
+        };
+        self.generate_content(auth, model, &request).await
     }
 }
 
@@ -443,6 +519,8 @@ mod tests {
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
+                response_mime_type: None,
+                response_schema: None,
             },
         };
 
@@ -479,6 +557,8 @@ mod tests {
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
+                response_mime_type: None,
+                response_schema: None,
             },
         };
 
@@ -508,6 +588,8 @@ mod tests {
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
+                response_mime_type: None,
+                response_schema: None,
             },
         };
 
@@ -516,6 +598,36 @@ mod tests {
         assert!(json.contains("\"text\":\"Hello\""));
         assert!(json.contains("\"temperature\":0.7"));
         assert!(json.contains("\"maxOutputTokens\":8192"));
+        assert!(!json.contains("responseSchema"));
+    }
+
+    #[test]
+    fn structured_request_maps_roles_and_schema() {
+        let (system, contents) = GeminiProvider::convert_messages(&[
+            ChatMessage::system("Be terse"),
+            ChatMessage::user("Grade this"),
+            ChatMessage::assistant("{}"),
+            ChatMessage::user("Fix it"),
+        ]);
+        let request = GenerateContentRequest {
+            contents,
+            system_instruction: system,
+            generation_config: GenerationConfig {
+                temperature: 0.0,
+                max_output_tokens: 8192,
+                response_mime_type: Some("application/json".into()),
+                response_schema: Some(serde_json::json!({"type": "OBJECT"})),
+            },
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["system_instruction"]["parts"][0]["text"], "Be terse");
+        assert_eq!(json["contents"][1]["role"], "model");
+        assert_eq!(json["contents"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            json["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert_eq!(json["generationConfig"]["responseSchema"]["type"], "OBJECT");
     }
 
     #[test]
@@ -1,17 +1,24 @@
 pub mod anthropic;
+pub mod circuit;
 pub mod compatible;
 pub mod gemini;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod prompt_cache;
 pub mod reliable;
+pub mod replay;
 pub mod router;
+pub mod routing;
+pub mod streaming;
+pub mod structured;
 pub mod traits;
 
+pub use streaming::DeltaSink;
 #[allow(unused_imports)]
 pub use traits::{
-    ChatMessage, ChatRequest, ChatResponse, ConversationMessage, Provider, ToolCall,
-    ToolResultMessage,
+    ChatMessage, ChatRequest, ChatResponse, ConversationMessage, Provider, ResponseSchema,
+    ToolCall, ToolResultMessage, Usage,
 };
 
 use compatible::{AuthStyle, OpenAiCompatibleProvider};
@@ -158,6 +165,26 @@ fn resolve_api_key(name: &str, api_key: Option<&str>) -> Option<String> {
     None
 }
 
+/// Whether `name` is a provider name `create_provider` accepts. Only the name
+/// is checked: a `replay:` cassette is not loaded.
+pub fn is_known_provider(name: &str) -> bool {
+    if let Some(path) = name.strip_prefix("replay:") {
+        return !path.trim().is_empty();
+    }
+    for prefix in ["custom:", "anthropic-custom:"] {
+        if let Some(url) = name.strip_prefix(prefix) {
+            return parse_custom_provider_url(url, prefix, "").is_ok();
+        }
+    }
+    // Built-in names only construct an HTTP client; nothing is read or sent.
+    create_provider(name, None).is_ok()
+}
+
+/// Whether an API key for `name` is available from `api_key` or the environment.
+pub fn has_api_key(name: &str, api_key: Option<&str>) -> bool {
+    resolve_api_key(name, api_key).is_some()
+}
+
 fn parse_custom_provider_url(
     raw_url: &str,
     provider_label: &str,
@@ -310,6 +337,18 @@ pub fn create_provider(name: &str, api_key: Option<&str>) -> anyhow::Result<Box<
             )))
         }
 
+        // ── Recorded cassette (offline tests) ───────────────
+        // Format: "replay:path/to/cassette.json"
+        name if name.starts_with("replay:") => {
+            let path = name.strip_prefix("replay:").unwrap_or("").trim();
+            if path.is_empty() {
+                anyhow::bail!("Replay provider requires a cassette path, e.g. replay:tests/cassettes/chat.json");
+            }
+            Ok(Box::new(replay::ReplayProvider::load(
+                std::path::Path::new(&*shellexpand::tilde(path)),
+            )?))
+        }
+
         _ => anyhow::bail!(
             "Unknown provider: {name}. Check README for supported providers or run `zeroclaw onboard --interactive` to reconfigure.\n\
              Tip: Use \"custom:https://your-api.com\" for OpenAI-compatible endpoints.\n\
@@ -356,13 +395,16 @@ pub fn create_resilient_provider(
         }
     }
 
-    let reliable = ReliableProvider::new(
+    let mut reliable = ReliableProvider::new(
         providers,
         reliability.provider_retries,
         reliability.provider_backoff_ms,
     )
     .with_api_keys(reliability.api_keys.clone())
     .with_model_fallbacks(reliability.model_fallbacks.clone());
+    if let Some(settings) = circuit::BreakerSettings::from_config(reliability) {
+        reliable = reliable.with_circuit_breaker(circuit::shared(), settings);
+    }
 
     Ok(Box::new(reliable))
 }
@@ -381,6 +423,76 @@ pub fn create_routed_provider(
         return create_resilient_provider(primary_name, api_key, reliability);
     }
 
+    Ok(Box::new(build_router(
+        primary_name,
+        api_key,
+        reliability,
+        model_routes,
+        default_model,
+    )?))
+}
+
+/// Like [`create_routed_provider`], honouring `[routing] mode = "auto"`:
+/// requests for `default_model` are classified and sent to the best-suited
+/// route, and every decision is reported to `observer`.
+pub fn create_auto_routed_provider(
+    config: &crate::config::Config,
+    primary_name: &str,
+    default_model: &str,
+    observer: std::sync::Arc<dyn crate::observability::Observer>,
+) -> anyhow::Result<Box<dyn Provider>> {
+    if config.routing.mode != crate::config::RoutingMode::Auto || config.model_routes.is_empty() {
+        return create_routed_provider(
+            primary_name,
+            config.api_key.as_deref(),
+            &config.reliability,
+            &config.model_routes,
+            default_model,
+        );
+    }
+
+    let window = |provider: &str, model: &str| {
+        let window = crate::agent::context::context_window(
+            &config.agent,
+            &config.workspace_dir,
+            provider,
+            model,
+        );
+        (model.to_string(), window)
+    };
+    let context_windows = std::iter::once(window(primary_name, default_model))
+        .chain(
+            config
+                .model_routes
+                .iter()
+                .map(|route| window(&route.provider, &route.model)),
+        )
+        .collect();
+
+    let router = build_router(
+        primary_name,
+        config.api_key.as_deref(),
+        &config.reliability,
+        &config.model_routes,
+        default_model,
+    )?
+    .with_auto_routing(router::AutoRouting {
+        classifier_hint: config.routing.classifier_hint.clone(),
+        prices: config.cost.prices.clone(),
+        context_windows,
+        observer,
+        cost: crate::cost::CostTracker::from_config(config),
+    });
+    Ok(Box::new(router))
+}
+
+fn build_router(
+    primary_name: &str,
+    api_key: Option<&str>,
+    reliability: &crate::config::ReliabilityConfig,
+    model_routes: &[crate::config::ModelRouteConfig],
+    default_model: &str,
+) -> anyhow::Result<router::RouterProvider> {
     // Collect unique provider names needed
     let mut needed: Vec<String> = vec![primary_name.to_string()];
     for route in model_routes {
@@ -420,16 +532,18 @@ pub fn create_routed_provider(
                 router::Route {
                     provider_name: r.provider.clone(),
                     model: r.model.clone(),
+                    capabilities: r.capabilities.clone(),
+                    daily_budget_usd: r.daily_budget_usd,
                 },
             )
         })
         .collect();
 
-    Ok(Box::new(router::RouterProvider::new(
+    Ok(router::RouterProvider::new(
         providers,
         routes,
         default_model.to_string(),
-    )))
+    ))
 }
 
 #[cfg(test)]
@@ -603,6 +717,18 @@ mod tests {
         assert!(create_provider("github-copilot", Some("key")).is_ok());
     }
 
+    // ── Replay provider ────────────────────────────────────
+
+    #[test]
+    fn factory_replay_loads_cassette() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chat.json");
+        std::fs::write(&path, r#"{"version":1,"interactions":[]}"#).unwrap();
+        assert!(create_provider(&format!("replay:{}", path.display()), None).is_ok());
+        assert!(create_provider("replay:", None).is_err());
+        assert!(create_provider("replay:/nonexistent/cassette.json", None).is_err());
+    }
+
     // ── Custom / BYOP provider ─────────────────────────────
 
     #[test]
@@ -731,6 +857,16 @@ mod tests {
         assert!(create_provider("", None).is_err());
     }
 
+    #[test]
+    fn known_provider_checks_the_name_without_loading_cassettes() {
+        assert!(is_known_provider("anthropic"));
+        assert!(is_known_provider("custom:http://localhost:1234"));
+        assert!(is_known_provider("replay:/nonexistent/cassette.json"));
+        assert!(!is_known_provider("replay: "));
+        assert!(!is_known_provider("anthropic-custom:ftp://example.com"));
+        assert!(!is_known_provider("nonexistent"));
+    }
+
     #[test]
     fn resilient_provider_ignores_duplicate_and_invalid_fallbacks() {
         let reliability = crate::config::ReliabilityConfig {
@@ -744,6 +880,9 @@ mod tests {
             ],
             api_keys: Vec::new(),
             model_fallbacks: std::collections::HashMap::new(),
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+            health_probe_interval_secs: 60,
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
             scheduler_poll_secs: 15,
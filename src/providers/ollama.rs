@@ -1,4 +1,4 @@
-use crate::providers::traits::Provider;
+use crate::providers::traits::{ChatRequest as ProviderChatRequest, Provider, ResponseSchema};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,9 @@ struct ChatRequest {
     messages: Vec<Message>,
     stream: bool,
     options: Options,
+    /// JSON Schema the reply must follow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -53,6 +56,22 @@ impl OllamaProvider {
     }
 }
 
+impl OllamaProvider {
+    async fn send(&self, request: &ChatRequest) -> anyhow::Result<String> {
+        let url = format!("{}/api/chat", self.base_url);
+
+        let response = self.client.post(&url).json(request).send().await?;
+
+        if !response.status().is_success() {
+            let err = super::api_error("Ollama", response).await;
+            anyhow::bail!("{err}. Is Ollama running? (brew install ollama && ollama serve)");
+        }
+
+        let chat_response: ApiChatResponse = response.json().await?;
+        Ok(chat_response.message.content)
+    }
+}
+
 #[async_trait]
 impl Provider for OllamaProvider {
     async fn chat_with_system(
@@ -81,19 +100,35 @@ impl Provider for OllamaProvider {
             messages,
             stream: false,
             options: Options { temperature },
+            format: None,
         };
 
-        let url = format!("{}/api/chat", self.base_url);
-
-        let response = self.client.post(&url).json(&request).send().await?;
+        self.send(&request).await
+    }
 
-        if !response.status().is_success() {
-            let err = super::api_error("Ollama", response).await;
-            anyhow::bail!("{err}. Is Ollama running? (brew install ollama && ollama serve)");
-        }
+    async fn chat_with_schema(
+        &self,
+        request: ProviderChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let request = ChatRequest {
+            model: model.to_string(),
+            messages: request
+                .messages
+                .iter()
+                .map(|m| Message {
+                    role: m.role.clone(),
+                    content: m.content.clone(),
+                })
+                .collect(),
+            stream: false,
+            options: Options { temperature },
+            format: Some(schema.schema.clone()),
+        };
 
-        let chat_response: ApiChatResponse = response.json().await?;
-        Ok(chat_response.message.content)
+        self.send(&request).await
     }
 }
 
@@ -141,6 +176,7 @@ mod tests {
             ],
             stream: false,
             options: Options { temperature: 0.7 },
+            format: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("\"stream\":false"));
@@ -159,10 +195,25 @@ mod tests {
             }],
             stream: false,
             options: Options { temperature: 0.0 },
+            format: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(!json.contains("\"role\":\"system\""));
         assert!(json.contains("mistral"));
+        assert!(!json.contains("format"));
+    }
+
+    #[test]
+    fn request_serializes_schema_as_format() {
+        let req = ChatRequest {
+            model: "llama3".to_string(),
+            messages: vec![],
+            stream: false,
+            options: Options { temperature: 0.0 },
+            format: Some(serde_json::json!({"type": "object"})),
+        };
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["format"]["type"], "object");
     }
 
     #[test]
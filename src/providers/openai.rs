@@ -1,6 +1,8 @@
+use crate::providers::prompt_cache::{prompt_cache_key, stable_tool_order, OpenAiUsage};
+use crate::providers::streaming::{DeltaSink, OpenAiStream};
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
-    Provider, ToolCall as ProviderToolCall,
+    Provider, ResponseSchema, ToolCall as ProviderToolCall,
 };
 use crate::tools::ToolSpec;
 use async_trait::async_trait;
@@ -49,6 +51,14 @@ struct NativeChatRequest {
     tools: Option<Vec<NativeToolSpec>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt_cache_key: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -94,6 +104,8 @@ struct NativeFunctionCall {
 #[derive(Debug, Deserialize)]
 struct NativeChatResponse {
     choices: Vec<NativeChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -123,8 +135,8 @@ impl OpenAiProvider {
 
     fn convert_tools(tools: Option<&[ToolSpec]>) -> Option<Vec<NativeToolSpec>> {
         tools.map(|items| {
-            items
-                .iter()
+            stable_tool_order(items)
+                .into_iter()
                 .map(|tool| NativeToolSpec {
                     kind: "function".to_string(),
                     function: NativeToolFunctionSpec {
@@ -204,6 +216,17 @@ impl OpenAiProvider {
             .collect()
     }
 
+    fn response_format(schema: &ResponseSchema) -> serde_json::Value {
+        serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": schema.name,
+                "schema": schema.schema,
+                "strict": schema.strict,
+            }
+        })
+    }
+
     fn parse_native_response(message: NativeResponseMessage) -> ProviderChatResponse {
         let tool_calls = message
             .tool_calls
@@ -219,8 +242,46 @@ impl OpenAiProvider {
         ProviderChatResponse {
             text: message.content,
             tool_calls,
+            usage: None,
         }
     }
+
+    /// Chat-completions request for `request`, with native tools.
+    fn native_request(
+        request: ProviderChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> NativeChatRequest {
+        let tools = Self::convert_tools(request.tools);
+        NativeChatRequest {
+            model: model.to_string(),
+            messages: Self::convert_messages(request.messages),
+            temperature,
+            tool_choice: tools.as_ref().map(|_| "auto".to_string()),
+            tools,
+            response_format: None,
+            prompt_cache_key: prompt_cache_key(request.messages, request.tools),
+            stream: false,
+            stream_options: None,
+        }
+    }
+
+    async fn send_native(&self, request: &NativeChatRequest) -> anyhow::Result<reqwest::Response> {
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("OpenAI API key not set. Set OPENAI_API_KEY or edit config.toml.")
+        })?;
+        let response = self
+            .client
+            .post("https://api.openai.com/v1/chat/completions")
+            .header("Authorization", format!("Bearer {api_key}"))
+            .json(request)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("OpenAI", response).await);
+        }
+        Ok(response)
+    }
 }
 
 #[async_trait]
@@ -284,39 +345,62 @@ impl Provider for OpenAiProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<ProviderChatResponse> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| {
-            anyhow::anyhow!("OpenAI API key not set. Set OPENAI_API_KEY or edit config.toml.")
-        })?;
+        let native_request = Self::native_request(request, model, temperature);
+        let response = self.send_native(&native_request).await?;
+        let native_response: NativeChatResponse = response.json().await?;
+        let usage = native_response.usage.map(Into::into);
+        let message = native_response
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| c.message)
+            .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
+        Ok(ProviderChatResponse {
+            usage,
+            ..Self::parse_native_response(message)
+        })
+    }
 
-        let tools = Self::convert_tools(request.tools);
+    async fn chat_streaming(
+        &self,
+        request: ProviderChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ProviderChatResponse> {
         let native_request = NativeChatRequest {
-            model: model.to_string(),
-            messages: Self::convert_messages(request.messages),
-            temperature,
-            tool_choice: tools.as_ref().map(|_| "auto".to_string()),
-            tools,
+            stream: true,
+            stream_options: Some(serde_json::json!({"include_usage": true})),
+            ..Self::native_request(request, model, temperature)
         };
+        let response = self.send_native(&native_request).await?;
+        let mut stream = OpenAiStream::default();
+        super::streaming::for_each_event(response, |_, data| stream.push(data, on_delta)).await?;
+        Ok(stream.finish())
+    }
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {api_key}"))
-            .json(&native_request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(super::api_error("OpenAI", response).await);
-        }
-
+    async fn chat_with_schema(
+        &self,
+        request: ProviderChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let native_request = NativeChatRequest {
+            tools: None,
+            tool_choice: None,
+            response_format: Some(Self::response_format(schema)),
+            prompt_cache_key: prompt_cache_key(request.messages, None),
+            ..Self::native_request(request, model, temperature)
+        };
+        let response = self.send_native(&native_request).await?;
         let native_response: NativeChatResponse = response.json().await?;
-        let message = native_response
+        native_response
             .choices
             .into_iter()
             .next()
-            .map(|c| c.message)
-            .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
-        Ok(Self::parse_native_response(message))
+            .and_then(|c| c.message.content)
+            .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))
     }
 
     fn supports_native_tools(&self) -> bool {
@@ -400,6 +484,23 @@ mod tests {
         assert!(json.contains("\"temperature\":0.0"));
     }
 
+    #[test]
+    fn response_format_carries_the_schema() {
+        let mut schema = ResponseSchema::new(
+            "verdict",
+            serde_json::json!({"type": "object", "properties": {"pass": {"type": "boolean"}}}),
+        );
+        schema.strict = true;
+        let format = OpenAiProvider::response_format(&schema);
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["name"], "verdict");
+        assert_eq!(format["json_schema"]["strict"], true);
+        assert_eq!(
+            format["json_schema"]["schema"]["properties"]["pass"]["type"],
+            "boolean"
+        );
+    }
+
     #[test]
     fn response_deserializes_single_choice() {
         let json = r#"{"choices":[{"message":{"content":"Hi!"}}]}"#;
@@ -1,3 +1,5 @@
+use crate::providers::prompt_cache::{stable_tool_order, OpenAiUsage};
+use crate::providers::streaming::{DeltaSink, OpenAiStream};
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
     Provider, ToolCall as ProviderToolCall,
@@ -49,6 +51,10 @@ struct NativeChatRequest {
     tools: Option<Vec<NativeToolSpec>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -94,6 +100,8 @@ struct NativeFunctionCall {
 #[derive(Debug, Deserialize)]
 struct NativeChatResponse {
     choices: Vec<NativeChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -127,8 +135,8 @@ impl OpenRouterProvider {
             return None;
         }
         Some(
-            items
-                .iter()
+            stable_tool_order(items)
+                .into_iter()
                 .map(|tool| NativeToolSpec {
                     kind: "function".to_string(),
                     function: NativeToolFunctionSpec {
@@ -223,24 +231,68 @@ impl OpenRouterProvider {
         ProviderChatResponse {
             text: message.content,
             tool_calls,
+            usage: None,
+        }
+    }
+
+    /// Chat-completions request for `request`, with native tools.
+    fn native_request(
+        request: ProviderChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> NativeChatRequest {
+        let tools = Self::convert_tools(request.tools);
+        NativeChatRequest {
+            model: model.to_string(),
+            messages: Self::convert_messages(request.messages),
+            temperature,
+            tool_choice: tools.as_ref().map(|_| "auto".to_string()),
+            tools,
+            stream: false,
+            stream_options: None,
+        }
+    }
+
+    async fn send_native(&self, request: &NativeChatRequest) -> anyhow::Result<reqwest::Response> {
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "OpenRouter API key not set. Run `zeroclaw onboard` or set OPENROUTER_API_KEY env var."
+            )
+        })?;
+        let response = self
+            .client
+            .post("https://openrouter.ai/api/v1/chat/completions")
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header(
+                "HTTP-Referer",
+                "https://github.com/theonlyhennygod/zeroclaw",
+            )
+            .header("X-Title", "ZeroClaw")
+            .json(request)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("OpenRouter", response).await);
         }
+        Ok(response)
     }
 }
 
 #[async_trait]
 impl Provider for OpenRouterProvider {
-    async fn warmup(&self) -> anyhow::Result<()> {
+    async fn warmup(&self) -> anyhow::Result<bool> {
         // Hit a lightweight endpoint to establish TLS + HTTP/2 connection pool.
         // This prevents the first real chat request from timing out on cold start.
-        if let Some(api_key) = self.api_key.as_ref() {
-            self.client
-                .get("https://openrouter.ai/api/v1/auth/key")
-                .header("Authorization", format!("Bearer {api_key}"))
-                .send()
-                .await?
-                .error_for_status()?;
-        }
-        Ok(())
+        let Some(api_key) = self.api_key.as_ref() else {
+            return Ok(false);
+        };
+        self.client
+            .get("https://openrouter.ai/api/v1/auth/key")
+            .header("Authorization", format!("Bearer {api_key}"))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(true)
     }
 
     async fn chat_with_system(
@@ -356,46 +408,39 @@ impl Provider for OpenRouterProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<ProviderChatResponse> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| {
-            anyhow::anyhow!(
-            "OpenRouter API key not set. Run `zeroclaw onboard` or set OPENROUTER_API_KEY env var."
-        )
-        })?;
-
-        let tools = Self::convert_tools(request.tools);
-        let native_request = NativeChatRequest {
-            model: model.to_string(),
-            messages: Self::convert_messages(request.messages),
-            temperature,
-            tool_choice: tools.as_ref().map(|_| "auto".to_string()),
-            tools,
-        };
-
         let response = self
-            .client
-            .post("https://openrouter.ai/api/v1/chat/completions")
-            .header("Authorization", format!("Bearer {api_key}"))
-            .header(
-                "HTTP-Referer",
-                "https://github.com/theonlyhennygod/zeroclaw",
-            )
-            .header("X-Title", "ZeroClaw")
-            .json(&native_request)
-            .send()
+            .send_native(&Self::native_request(request, model, temperature))
             .await?;
-
-        if !response.status().is_success() {
-            return Err(super::api_error("OpenRouter", response).await);
-        }
-
         let native_response: NativeChatResponse = response.json().await?;
+        let usage = native_response.usage.map(Into::into);
         let message = native_response
             .choices
             .into_iter()
             .next()
             .map(|c| c.message)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenRouter"))?;
-        Ok(Self::parse_native_response(message))
+        Ok(ProviderChatResponse {
+            usage,
+            ..Self::parse_native_response(message)
+        })
+    }
+
+    async fn chat_streaming(
+        &self,
+        request: ProviderChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ProviderChatResponse> {
+        let native_request = NativeChatRequest {
+            stream: true,
+            stream_options: Some(serde_json::json!({"include_usage": true})),
+            ..Self::native_request(request, model, temperature)
+        };
+        let response = self.send_native(&native_request).await?;
+        let mut stream = OpenAiStream::default();
+        super::streaming::for_each_event(response, |_, data| stream.push(data, on_delta)).await?;
+        Ok(stream.finish())
     }
 
     fn supports_native_tools(&self) -> bool {
@@ -424,7 +469,7 @@ mod tests {
     async fn warmup_without_key_is_noop() {
         let provider = OpenRouterProvider::new(None);
         let result = provider.warmup().await;
-        assert!(result.is_ok());
+        assert!(!result.unwrap());
     }
 
     #[tokio::test]
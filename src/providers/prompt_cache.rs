@@ -0,0 +1,141 @@
+//! Prompt-caching helpers for the OpenAI-format providers.
+//!
+//! OpenAI, OpenRouter and most OpenAI-compatible servers cache the longest
+//! prompt prefix they have seen recently without being asked. The cached
+//! prefix is tools first, then messages in order. Two things keep the
+//! system prompt and tool list inside it from turn to turn: a stable tool
+//! order, and a `prompt_cache_key` that sends requests with the same prefix
+//! to the same cache.
+
+use super::traits::{ChatMessage, Usage};
+use crate::tools::ToolSpec;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// `usage` object of an OpenAI-format chat completion.
+#[derive(Debug, Default, Deserialize)]
+pub struct OpenAiUsage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    /// DeepSeek reports cache hits here instead of `prompt_tokens_details`.
+    #[serde(default)]
+    pub prompt_cache_hit_tokens: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PromptTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: u64,
+}
+
+impl From<OpenAiUsage> for Usage {
+    /// `prompt_tokens` includes cached tokens; [`Usage::input_tokens`] does not.
+    fn from(usage: OpenAiUsage) -> Self {
+        let cached = usage
+            .prompt_cache_hit_tokens
+            .or_else(|| usage.prompt_tokens_details.map(|d| d.cached_tokens))
+            .unwrap_or(0)
+            .min(usage.prompt_tokens);
+        Self {
+            input_tokens: usage.prompt_tokens - cached,
+            output_tokens: usage.completion_tokens,
+            cache_read_tokens: cached,
+            cache_write_tokens: 0,
+        }
+    }
+}
+
+/// `tools` sorted by name, so a tool list assembled in a different order
+/// (MCP servers reconnecting, skills reloading) still hits the cache.
+pub fn stable_tool_order(tools: &[ToolSpec]) -> Vec<&ToolSpec> {
+    let mut ordered: Vec<&ToolSpec> = tools.iter().collect();
+    ordered.sort_by(|a, b| a.name.cmp(&b.name));
+    ordered
+}
+
+/// Cache routing key derived from the stable prefix: the leading system
+/// messages and the tool names. Conversations sharing a system prompt
+/// share a key; `None` when there is no system prompt to cache.
+pub fn prompt_cache_key(messages: &[ChatMessage], tools: Option<&[ToolSpec]>) -> Option<String> {
+    let mut hasher = Sha256::new();
+    let mut has_system = false;
+    for message in messages.iter().take_while(|m| m.role == "system") {
+        hasher.update(message.content.as_bytes());
+        hasher.update([0]);
+        has_system = true;
+    }
+    if !has_system {
+        return None;
+    }
+    for tool in stable_tool_order(tools.unwrap_or_default()) {
+        hasher.update(tool.name.as_bytes());
+        hasher.update([0]);
+    }
+    let digest = hex::encode(hasher.finalize());
+    Some(format!("zeroclaw-{}", &digest[..16]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str) -> ToolSpec {
+        ToolSpec {
+            name: name.into(),
+            description: String::new(),
+            parameters: serde_json::json!({"type": "object"}),
+        }
+    }
+
+    #[test]
+    fn cached_prompt_tokens_are_split_out() {
+        let usage: OpenAiUsage = serde_json::from_str(
+            r#"{"prompt_tokens":2000,"completion_tokens":50,"prompt_tokens_details":{"cached_tokens":1536}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Usage::from(usage),
+            Usage {
+                input_tokens: 464,
+                output_tokens: 50,
+                cache_read_tokens: 1536,
+                cache_write_tokens: 0,
+            }
+        );
+
+        let deepseek: OpenAiUsage = serde_json::from_str(
+            r#"{"prompt_tokens":100,"completion_tokens":5,"prompt_cache_hit_tokens":64}"#,
+        )
+        .unwrap();
+        assert_eq!(Usage::from(deepseek).cache_read_tokens, 64);
+    }
+
+    #[test]
+    fn cache_key_follows_the_stable_prefix_only() {
+        let tools = [tool("shell"), tool("file_read")];
+        let reordered = [tool("file_read"), tool("shell")];
+        let turn_one = [ChatMessage::system("identity"), ChatMessage::user("hi")];
+        let turn_two = [
+            ChatMessage::system("identity"),
+            ChatMessage::user("hi"),
+            ChatMessage::assistant("hello"),
+            ChatMessage::user("more"),
+        ];
+
+        let key = prompt_cache_key(&turn_one, Some(&tools)).unwrap();
+        assert!(key.starts_with("zeroclaw-"));
+        assert_eq!(
+            prompt_cache_key(&turn_two, Some(&reordered)),
+            Some(key.clone())
+        );
+        assert_ne!(
+            prompt_cache_key(&[ChatMessage::system("other")], Some(&tools)),
+            Some(key)
+        );
+        assert_eq!(prompt_cache_key(&[ChatMessage::user("hi")], None), None);
+    }
+}
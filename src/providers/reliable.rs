@@ -1,12 +1,18 @@
-use super::traits::ChatMessage;
-use super::Provider;
+use super::circuit::{BreakerSettings, CircuitRegistry, CircuitState, DEGRADED_SCORE};
+use super::traits::{ChatMessage, ChatRequest, ChatResponse, ResponseSchema};
+use super::{DeltaSink, Provider};
 use async_trait::async_trait;
+use futures_util::future::BoxFuture;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Check if an error is non-retryable (client errors that won't resolve with retries).
 fn is_non_retryable(err: &anyhow::Error) -> bool {
+    if err.downcast_ref::<super::replay::ReplayDrift>().is_some() {
+        return true;
+    }
     if let Some(reqwest_err) = err.downcast_ref::<reqwest::Error>() {
         if let Some(status) = reqwest_err.status() {
             let code = status.as_u16();
@@ -24,6 +30,33 @@ fn is_non_retryable(err: &anyhow::Error) -> bool {
     false
 }
 
+/// Check if an error says the provider itself is unhealthy: server errors,
+/// timeouts, failed connections and rejected credentials. Requests the
+/// provider turned down for their own shape (400, 404, 422, …) or rate
+/// limits say nothing about its health and stay out of the breaker.
+fn is_health_failure(err: &anyhow::Error) -> bool {
+    if err.downcast_ref::<super::replay::ReplayDrift>().is_some() {
+        return false;
+    }
+    let status = match err.downcast_ref::<reqwest::Error>() {
+        Some(reqwest_err) => match reqwest_err.status() {
+            Some(status) => Some(status.as_u16()),
+            None => {
+                return reqwest_err.is_timeout()
+                    || reqwest_err.is_connect()
+                    || reqwest_err.is_request()
+                    || reqwest_err.is_body();
+            }
+        },
+        None => err
+            .to_string()
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|word| word.parse::<u16>().ok())
+            .find(|code| (400..600).contains(code)),
+    };
+    status.is_none_or(|code| code >= 500 || matches!(code, 401 | 403 | 408))
+}
+
 /// Check if an error is a rate-limit (429) error.
 fn is_rate_limited(err: &anyhow::Error) -> bool {
     if let Some(reqwest_err) = err.downcast_ref::<reqwest::Error>() {
@@ -69,7 +102,8 @@ fn parse_retry_after_ms(err: &anyhow::Error) -> Option<u64> {
     None
 }
 
-/// Provider wrapper with retry, fallback, auth rotation, and model failover.
+/// Provider wrapper with retry, fallback, auth rotation, model failover and
+/// (optionally) circuit breakers.
 pub struct ReliableProvider {
     providers: Vec<(String, Box<dyn Provider>)>,
     max_retries: u32,
@@ -79,6 +113,8 @@ pub struct ReliableProvider {
     key_index: AtomicUsize,
     /// Per-model fallback chains: model_name → [fallback_model_1, fallback_model_2, ...]
     model_fallbacks: HashMap<String, Vec<String>>,
+    /// Shared breaker state; `None` disables circuit breaking.
+    circuit: Option<(Arc<CircuitRegistry>, BreakerSettings)>,
 }
 
 impl ReliableProvider {
@@ -94,6 +130,7 @@ impl ReliableProvider {
             api_keys: Vec::new(),
             key_index: AtomicUsize::new(0),
             model_fallbacks: HashMap::new(),
+            circuit: None,
         }
     }
 
@@ -109,6 +146,32 @@ impl ReliableProvider {
         self
     }
 
+    /// Track provider/model health in `registry` and skip pairs whose
+    /// circuit is open instead of retrying them on every request.
+    pub fn with_circuit_breaker(
+        mut self,
+        registry: Arc<CircuitRegistry>,
+        settings: BreakerSettings,
+    ) -> Self {
+        self.circuit = Some((registry, settings));
+        self
+    }
+
+    /// Providers in the order to try them for `model`: healthy circuits in
+    /// configured order, then degraded, half-open and open ones.
+    fn providers_by_health(&self, model: &str) -> Vec<&(String, Box<dyn Provider>)> {
+        let mut order: Vec<_> = self.providers.iter().collect();
+        if let Some((registry, _)) = &self.circuit {
+            order.sort_by_key(|(name, _)| match registry.state(name, model) {
+                CircuitState::Closed if registry.score(name, model) >= DEGRADED_SCORE => 0,
+                CircuitState::Closed => 1,
+                CircuitState::HalfOpen => 2,
+                CircuitState::Open => 3,
+            });
+        }
+        order
+    }
+
     /// Build the list of models to try: [original, fallback1, fallback2, ...]
     fn model_chain<'a>(&'a self, model: &'a str) -> Vec<&'a str> {
         let mut chain = vec![model];
@@ -136,44 +199,47 @@ impl ReliableProvider {
             base
         }
     }
-}
-
-#[async_trait]
-impl Provider for ReliableProvider {
-    async fn warmup(&self) -> anyhow::Result<()> {
-        for (name, provider) in &self.providers {
-            tracing::info!(provider = name, "Warming up provider connection pool");
-            if let Err(e) = provider.warmup().await {
-                tracing::warn!(provider = name, "Warmup failed (non-fatal): {e}");
-            }
-        }
-        Ok(())
-    }
 
-    async fn chat_with_system(
-        &self,
-        system_prompt: Option<&str>,
-        message: &str,
-        model: &str,
-        temperature: f64,
-    ) -> anyhow::Result<String> {
+    /// Call each model in the fallback chain on each provider in turn,
+    /// retrying with backoff, until one succeeds.
+    async fn call_with_failover<'a, T>(
+        &'a self,
+        model: &'a str,
+        call: impl Fn(&'a dyn Provider, &'a str) -> BoxFuture<'a, anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
         let models = self.model_chain(model);
         let mut failures = Vec::new();
 
-        for current_model in &models {
-            for (provider_name, provider) in &self.providers {
+        for current_model in models {
+            for (provider_name, provider) in self.providers_by_health(current_model) {
+                if let Some((registry, settings)) = &self.circuit {
+                    if !registry.allow(provider_name, current_model, settings) {
+                        tracing::debug!(
+                            provider = provider_name,
+                            model = current_model,
+                            "Circuit open, skipping"
+                        );
+                        failures.push(format!("{provider_name}/{current_model}: circuit open"));
+                        continue;
+                    }
+                }
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
-                    match provider
-                        .chat_with_system(system_prompt, message, current_model, temperature)
-                        .await
-                    {
+                    let started = Instant::now();
+                    match call(provider.as_ref(), current_model).await {
                         Ok(resp) => {
-                            if attempt > 0 || *current_model != model {
+                            if let Some((registry, _)) = &self.circuit {
+                                registry.record_success(
+                                    provider_name,
+                                    current_model,
+                                    started.elapsed(),
+                                );
+                            }
+                            if attempt > 0 || current_model != model {
                                 tracing::info!(
                                     provider = provider_name,
-                                    model = *current_model,
+                                    model = current_model,
                                     attempt,
                                     original_model = model,
                                     "Provider recovered (failover/retry)"
@@ -202,20 +268,35 @@ impl Provider for ReliableProvider {
                                 }
                             }
 
+                            let circuit_opened = is_health_failure(&e)
+                                && self.circuit.as_ref().is_some_and(|(registry, settings)| {
+                                    let error =
+                                        crate::util::truncate_with_ellipsis(&e.to_string(), 200);
+                                    registry.record_failure(
+                                        provider_name,
+                                        current_model,
+                                        &error,
+                                        settings,
+                                    )
+                                });
+
                             if non_retryable {
                                 tracing::warn!(
                                     provider = provider_name,
-                                    model = *current_model,
+                                    model = current_model,
                                     "Non-retryable error, moving on"
                                 );
                                 break;
                             }
+                            if circuit_opened {
+                                break;
+                            }
 
                             if attempt < self.max_retries {
                                 let wait = self.compute_backoff(backoff_ms, &e);
                                 tracing::warn!(
                                     provider = provider_name,
-                                    model = *current_model,
+                                    model = current_model,
                                     attempt = attempt + 1,
                                     backoff_ms = wait,
                                     "Provider call failed, retrying"
@@ -229,15 +310,15 @@ impl Provider for ReliableProvider {
 
                 tracing::warn!(
                     provider = provider_name,
-                    model = *current_model,
+                    model = current_model,
                     "Exhausted retries, trying next provider/model"
                 );
             }
 
-            if *current_model != model {
+            if current_model != model {
                 tracing::warn!(
                     original_model = model,
-                    fallback_model = *current_model,
+                    fallback_model = current_model,
                     "Model fallback exhausted all providers, trying next fallback model"
                 );
             }
@@ -248,6 +329,44 @@ impl Provider for ReliableProvider {
             failures.join("\n")
         )
     }
+}
+
+#[async_trait]
+impl Provider for ReliableProvider {
+    async fn warmup(&self) -> anyhow::Result<bool> {
+        let mut probed = false;
+        for (name, provider) in &self.providers {
+            tracing::info!(provider = name, "Warming up provider connection pool");
+            // A provider with nothing to contact says nothing about its
+            // health, so only real checks reach the breakers.
+            let result = match provider.warmup().await {
+                Ok(false) => continue,
+                Ok(true) => Ok(()),
+                Err(e) => {
+                    tracing::warn!(provider = name, "Warmup failed (non-fatal): {e}");
+                    Err(e.to_string())
+                }
+            };
+            probed = true;
+            if let Some((registry, _)) = &self.circuit {
+                registry.record_probe(name, result);
+            }
+        }
+        Ok(probed)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.call_with_failover(model, |provider, current_model| {
+            provider.chat_with_system(system_prompt, message, current_model, temperature)
+        })
+        .await
+    }
 
     async fn chat_with_history(
         &self,
@@ -255,94 +374,54 @@ impl Provider for ReliableProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let models = self.model_chain(model);
-        let mut failures = Vec::new();
-
-        for current_model in &models {
-            for (provider_name, provider) in &self.providers {
-                let mut backoff_ms = self.base_backoff_ms;
-
-                for attempt in 0..=self.max_retries {
-                    match provider
-                        .chat_with_history(messages, current_model, temperature)
-                        .await
-                    {
-                        Ok(resp) => {
-                            if attempt > 0 || *current_model != model {
-                                tracing::info!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt,
-                                    original_model = model,
-                                    "Provider recovered (failover/retry)"
-                                );
-                            }
-                            return Ok(resp);
-                        }
-                        Err(e) => {
-                            let non_retryable = is_non_retryable(&e);
-                            let rate_limited = is_rate_limited(&e);
-
-                            failures.push(format!(
-                                "{provider_name}/{current_model} attempt {}/{}: {e}",
-                                attempt + 1,
-                                self.max_retries + 1
-                            ));
-
-                            if rate_limited {
-                                if let Some(new_key) = self.rotate_key() {
-                                    tracing::info!(
-                                        provider = provider_name,
-                                        "Rate limited, rotated API key (key ending ...{})",
-                                        &new_key[new_key.len().saturating_sub(4)..]
-                                    );
-                                }
-                            }
-
-                            if non_retryable {
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    "Non-retryable error, moving on"
-                                );
-                                break;
-                            }
+        self.call_with_failover(model, |provider, current_model| {
+            provider.chat_with_history(messages, current_model, temperature)
+        })
+        .await
+    }
 
-                            if attempt < self.max_retries {
-                                let wait = self.compute_backoff(backoff_ms, &e);
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt = attempt + 1,
-                                    backoff_ms = wait,
-                                    "Provider call failed, retrying"
-                                );
-                                tokio::time::sleep(Duration::from_millis(wait)).await;
-                                backoff_ms = (backoff_ms.saturating_mul(2)).min(10_000);
-                            }
-                        }
-                    }
-                }
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.call_with_failover(model, |provider, current_model| {
+            provider.chat(request, current_model, temperature)
+        })
+        .await
+    }
 
-                tracing::warn!(
-                    provider = provider_name,
-                    model = *current_model,
-                    "Exhausted retries, trying next provider/model"
-                );
-            }
-        }
+    async fn chat_streaming(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ChatResponse> {
+        self.call_with_failover(model, |provider, current_model| {
+            provider.chat_streaming(request, current_model, temperature, on_delta)
+        })
+        .await
+    }
 
-        anyhow::bail!(
-            "All providers/models failed. Attempts:\n{}",
-            failures.join("\n")
-        )
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.call_with_failover(model, |provider, current_model| {
+            provider.chat_with_schema(request, schema, current_model, temperature)
+        })
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
 
     struct MockProvider {
         calls: Arc<AtomicUsize>,
@@ -542,6 +621,28 @@ mod tests {
         assert!(!is_non_retryable(&anyhow::anyhow!("connection reset")));
     }
 
+    #[test]
+    fn health_failures_exclude_request_shape_errors() {
+        for health in [
+            "OpenAI API error (500 Internal Server Error): oops",
+            "503 unavailable",
+            "401 Unauthorized",
+            "403 Forbidden",
+            "408 Request Timeout",
+            "connection reset",
+        ] {
+            assert!(is_health_failure(&anyhow::anyhow!(health)), "{health}");
+        }
+        for shape in [
+            "OpenAI API error (400 Bad Request): context too long",
+            "404 Not Found",
+            "422 Unprocessable Entity",
+            "429 Too Many Requests",
+        ] {
+            assert!(!is_health_failure(&anyhow::anyhow!(shape)), "{shape}");
+        }
+    }
+
     #[tokio::test]
     async fn skips_retries_on_non_retryable_error() {
         let primary_calls = Arc::new(AtomicUsize::new(0));
@@ -767,6 +868,165 @@ mod tests {
         assert!(provider.rotate_key().is_none());
     }
 
+    // ── Circuit breaker ──
+
+    #[tokio::test]
+    async fn open_circuit_skips_failing_provider() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let registry = Arc::new(CircuitRegistry::new());
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: usize::MAX,
+                        response: "never",
+                        error: "503 unavailable",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&fallback_calls),
+                        fail_until_attempt: 0,
+                        response: "from fallback",
+                        error: "boom",
+                    }),
+                ),
+            ],
+            2,
+            1,
+        )
+        .with_circuit_breaker(
+            Arc::clone(&registry),
+            BreakerSettings {
+                failure_threshold: 2,
+                open_duration: Duration::from_secs(60),
+            },
+        );
+
+        let result = provider.simple_chat("hello", "m", 0.0).await.unwrap();
+        assert_eq!(result, "from fallback");
+        // The circuit opened on the second failure, cutting retries short.
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(registry.state("primary", "m"), CircuitState::Open);
+
+        let result = provider.simple_chat("again", "m", 0.0).await.unwrap();
+        assert_eq!(result, "from fallback");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(registry.state("fallback", "m"), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn only_health_failures_count_against_the_circuit() {
+        let breaker = |error: &'static str| {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let registry = Arc::new(CircuitRegistry::new());
+            let provider = ReliableProvider::new(
+                vec![(
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&calls),
+                        fail_until_attempt: usize::MAX,
+                        response: "never",
+                        error,
+                    }) as Box<dyn Provider>,
+                )],
+                3,
+                1,
+            )
+            .with_circuit_breaker(
+                Arc::clone(&registry),
+                BreakerSettings {
+                    failure_threshold: 2,
+                    open_duration: Duration::from_secs(60),
+                },
+            );
+            (provider, calls, registry)
+        };
+
+        let (provider, calls, registry) = breaker("401 Unauthorized");
+        assert!(provider.simple_chat("hello", "m", 0.0).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(registry.snapshot()["primary/m"].consecutive_failures, 1);
+        assert!(provider.simple_chat("again", "m", 0.0).await.is_err());
+        assert_eq!(registry.state("primary", "m"), CircuitState::Open);
+
+        // A request the provider rejects for its shape says nothing about
+        // the provider, however often it is sent.
+        let (provider, calls, registry) = breaker("400 Bad Request: context too long");
+        for _ in 0..3 {
+            assert!(provider.simple_chat("hello", "m", 0.0).await.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(registry.state("primary", "m"), CircuitState::Closed);
+    }
+
+    /// Mock whose `warmup` reports a fixed probe outcome.
+    struct WarmupMock(Option<Result<(), &'static str>>);
+
+    #[async_trait]
+    impl Provider for WarmupMock {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            anyhow::bail!("503 unavailable")
+        }
+
+        async fn warmup(&self) -> anyhow::Result<bool> {
+            match self.0 {
+                None => Ok(false),
+                Some(Ok(())) => Ok(true),
+                Some(Err(e)) => anyhow::bail!(e),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn warmup_records_only_real_probes() {
+        let registry = Arc::new(CircuitRegistry::new());
+        let settings = BreakerSettings {
+            failure_threshold: 1,
+            open_duration: Duration::from_secs(60),
+        };
+        for name in ["noop", "healthy", "down"] {
+            registry.record_failure(name, "m", "503", &settings);
+        }
+        let provider = ReliableProvider::new(
+            vec![
+                ("noop".into(), Box::new(WarmupMock(None))),
+                ("healthy".into(), Box::new(WarmupMock(Some(Ok(()))))),
+                ("down".into(), Box::new(WarmupMock(Some(Err("refused"))))),
+            ],
+            0,
+            1,
+        )
+        .with_circuit_breaker(Arc::clone(&registry), settings);
+
+        assert!(provider.warmup().await.unwrap());
+        let snapshot = registry.snapshot();
+        assert_eq!(registry.state("noop", "m"), CircuitState::Open);
+        assert!(snapshot["noop/m"].last_probe.is_none());
+        assert_eq!(registry.state("healthy", "m"), CircuitState::HalfOpen);
+        assert_eq!(registry.state("down", "m"), CircuitState::Open);
+        assert!(snapshot["down/m"]
+            .last_probe
+            .as_deref()
+            .unwrap()
+            .ends_with("failed: refused"));
+
+        let noop_only =
+            ReliableProvider::new(vec![("noop".into(), Box::new(WarmupMock(None)))], 0, 1);
+        assert!(!noop_only.warmup().await.unwrap());
+    }
+
     // ── New tests: Retry-After parsing ──
 
     #[test]
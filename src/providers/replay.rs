@@ -0,0 +1,1141 @@
+//! Record/replay providers for deterministic, offline agent tests.
+//!
+//! [`RecordingProvider`] wraps a real provider and writes every request and
+//! response to a JSON cassette, with secrets scrubbed by
+//! [`scrub_secret_patterns`]. [`ReplayProvider`] (`replay:<cassette>`) answers
+//! from that cassette, matching requests by fingerprint and failing with
+//! [`ReplayDrift`] when a request was never recorded.
+//!
+//! The fingerprint covers the model, the messages and the tool specs.
+//! Host name, OS and timezone lines in system prompts are stored as
+//! placeholders so cassettes replay on other machines. A cassette's
+//! [`MatchOptions`] can leave system prompts or tool descriptions out of
+//! matching. Temperature is never matched.
+
+use super::scrub_secret_patterns;
+use super::traits::{ChatMessage, ChatRequest, ChatResponse, ResponseSchema, ToolCall};
+use super::{DeltaSink, Provider};
+use crate::tools::ToolSpec;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const CASSETTE_VERSION: u32 = 1;
+
+/// Longest message excerpt shown in drift errors.
+const DRIFT_EXCERPT_CHARS: usize = 160;
+
+/// A recorded conversation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    /// Whether the recorded provider used native tool calls
+    #[serde(default)]
+    pub native_tools: bool,
+    #[serde(default)]
+    pub matching: MatchOptions,
+    #[serde(default)]
+    pub interactions: Vec<Interaction>,
+}
+
+/// Request parts left out when matching replayed requests. Set in the
+/// cassette's `matching` object, or with [`ReplayProvider::with_matching`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchOptions {
+    #[serde(default)]
+    pub ignore_system_prompt: bool,
+    #[serde(default)]
+    pub ignore_tool_descriptions: bool,
+}
+
+/// One request/response pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub fingerprint: String,
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub model: String,
+    pub temperature: f64,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<RecordedTool>,
+    /// Schema of a [`Provider::chat_with_schema`] request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<RecordedSchema>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub parameters: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedSchema {
+    pub name: String,
+    pub schema: Value,
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl RecordedRequest {
+    fn with_schema(mut self, schema: &ResponseSchema) -> Self {
+        self.response_schema = Some(RecordedSchema {
+            name: schema.name.clone(),
+            schema: schema.schema.clone(),
+            strict: schema.strict,
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Provider error, replayed as an error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cassette {}", path.display()))?;
+        let cassette: Self = serde_json::from_str(&raw)
+            .with_context(|| format!("Invalid cassette {}", path.display()))?;
+        if cassette.version != CASSETTE_VERSION {
+            anyhow::bail!(
+                "Cassette {} has version {}, expected {CASSETTE_VERSION}",
+                path.display(),
+                cassette.version
+            );
+        }
+        Ok(cassette)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to write cassette {}", path.display()))
+    }
+}
+
+/// A request that matches no unused recorded interaction.
+#[derive(Debug)]
+pub struct ReplayDrift(String);
+
+impl std::fmt::Display for ReplayDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ReplayDrift {}
+
+/// Literal substitutions applied before scrubbing and fingerprinting, e.g.
+/// a temp workspace path → `{workspace}`. Replayed responses get the
+/// reverse substitution.
+#[derive(Debug, Clone, Default)]
+struct Aliases(Vec<(String, String)>);
+
+impl Aliases {
+    fn push(&mut self, value: &str, placeholder: &str) {
+        if !value.is_empty() {
+            self.0.push((value.to_string(), placeholder.to_string()));
+            // Longest values first so nested paths substitute correctly.
+            self.0.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        }
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (value, placeholder) in &self.0 {
+            out = out.replace(value, placeholder);
+        }
+        scrub_secret_patterns(&out)
+    }
+
+    fn expand(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (value, placeholder) in &self.0 {
+            out = out.replace(placeholder, value);
+        }
+        out
+    }
+}
+
+fn recorded_request(
+    aliases: &Aliases,
+    messages: &[ChatMessage],
+    tools: Option<&[ToolSpec]>,
+    model: &str,
+    temperature: f64,
+) -> RecordedRequest {
+    RecordedRequest {
+        model: model.to_string(),
+        temperature,
+        messages: messages
+            .iter()
+            .map(|m| {
+                let content = aliases.normalize(&m.content);
+                ChatMessage {
+                    role: m.role.clone(),
+                    content: if m.role == "system" {
+                        normalize_system_prompt(&content)
+                    } else {
+                        content
+                    },
+                }
+            })
+            .collect(),
+        tools: tools
+            .unwrap_or_default()
+            .iter()
+            .map(|t| RecordedTool {
+                name: t.name.clone(),
+                description: aliases.normalize(&t.description),
+                parameters: t.parameters.clone(),
+            })
+            .collect(),
+        response_schema: None,
+    }
+}
+
+/// Replace the machine-specific runtime lines of a system prompt (host,
+/// OS, timezone) with placeholders.
+fn normalize_system_prompt(prompt: &str) -> String {
+    prompt
+        .split('\n')
+        .map(|line| {
+            if line.starts_with("Timezone: ") {
+                return "Timezone: {timezone}".to_string();
+            }
+            if !line.starts_with("Host: ") {
+                return line.to_string();
+            }
+            line.split(" | ")
+                .map(|part| match part.split_once(": ") {
+                    Some(("Host", _)) => "Host: {host}",
+                    Some(("OS", _)) => "OS: {os}",
+                    _ => part,
+                })
+                .collect::<Vec<_>>()
+                .join(" | ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// JSON with object keys sorted, so fingerprints do not depend on map order.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|k| (k.clone(), canonical(&map[k])))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Messages that take part in matching.
+fn matched_messages(request: &RecordedRequest, options: MatchOptions) -> Vec<ChatMessage> {
+    request
+        .messages
+        .iter()
+        .filter(|m| !(options.ignore_system_prompt && m.role == "system"))
+        .cloned()
+        .collect()
+}
+
+/// Stable hash of the parts of a request that decide the response.
+pub fn fingerprint(request: &RecordedRequest, options: MatchOptions) -> String {
+    let messages: Vec<Value> = matched_messages(request, options)
+        .into_iter()
+        .map(|m| json!([m.role, m.content]))
+        .collect();
+    let tools: Vec<Value> = request
+        .tools
+        .iter()
+        .map(|t| {
+            let description = if options.ignore_tool_descriptions {
+                ""
+            } else {
+                t.description.as_str()
+            };
+            json!([t.name, description, canonical(&t.parameters)])
+        })
+        .collect();
+    let schema = request
+        .response_schema
+        .as_ref()
+        .map(|s| json!([s.name, canonical(&s.schema), s.strict]));
+    let material = json!({
+        "model": request.model,
+        "messages": messages,
+        "tools": tools,
+        "schema": schema,
+    });
+    hex::encode(Sha256::digest(material.to_string().as_bytes()))
+}
+
+fn excerpt(text: &str) -> String {
+    let mut short: String = text.chars().take(DRIFT_EXCERPT_CHARS).collect();
+    if text.chars().count() > DRIFT_EXCERPT_CHARS {
+        short.push('…');
+    }
+    format!("{short:?}")
+}
+
+/// Describe the first difference between a recorded and an actual request.
+fn describe_drift(
+    expected: &RecordedRequest,
+    actual: &RecordedRequest,
+    options: MatchOptions,
+) -> String {
+    if expected.model != actual.model {
+        return format!(
+            "model differs: recorded {:?}, got {:?}",
+            expected.model, actual.model
+        );
+    }
+    let names = |r: &RecordedRequest| r.tools.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+    if names(expected) != names(actual) {
+        return format!(
+            "tools differ: recorded {:?}, got {:?}",
+            names(expected),
+            names(actual)
+        );
+    }
+    let schema = |r: &RecordedRequest| {
+        r.response_schema
+            .as_ref()
+            .map(|s| (s.name.clone(), canonical(&s.schema), s.strict))
+    };
+    if schema(expected) != schema(actual) {
+        return "response schema differs".into();
+    }
+    let (expected_msgs, actual_msgs) = (
+        matched_messages(expected, options),
+        matched_messages(actual, options),
+    );
+    for (i, (e, a)) in expected_msgs.iter().zip(&actual_msgs).enumerate() {
+        if e.role != a.role || e.content != a.content {
+            return format!(
+                "message {i} differs:\n  recorded {}: {}\n  got      {}: {}",
+                e.role,
+                excerpt(&e.content),
+                a.role,
+                excerpt(&a.content)
+            );
+        }
+    }
+    if expected_msgs.len() != actual_msgs.len() {
+        return format!(
+            "message count differs: recorded {}, got {}",
+            expected_msgs.len(),
+            actual_msgs.len()
+        );
+    }
+    let descriptions = expected
+        .tools
+        .iter()
+        .zip(&actual.tools)
+        .find(|(e, a)| e.description != a.description);
+    match descriptions {
+        Some((tool, _)) if !options.ignore_tool_descriptions => {
+            format!("description of tool {:?} differs", tool.name)
+        }
+        _ => "tool schemas differ".into(),
+    }
+}
+
+/// Answers from a cassette; selectable as `replay:<cassette>`.
+pub struct ReplayProvider {
+    source: String,
+    native_tools: bool,
+    matching: MatchOptions,
+    interactions: Vec<Interaction>,
+    used: Mutex<Vec<bool>>,
+    aliases: Aliases,
+}
+
+impl ReplayProvider {
+    pub fn new(cassette: Cassette, source: impl Into<String>) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        Self {
+            source: source.into(),
+            native_tools: cassette.native_tools,
+            matching: cassette.matching,
+            interactions: cassette.interactions,
+            used: Mutex::new(used),
+            aliases: Aliases::default(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::new(Cassette::load(path)?, path.display().to_string()))
+    }
+
+    /// Treat `placeholder` in the cassette as `value` (e.g. a temp dir).
+    #[must_use]
+    pub fn with_alias(mut self, value: &str, placeholder: &str) -> Self {
+        self.aliases.push(value, placeholder);
+        self
+    }
+
+    /// Match requests with `matching` instead of the cassette's options.
+    #[must_use]
+    pub fn with_matching(mut self, matching: MatchOptions) -> Self {
+        self.matching = matching;
+        self
+    }
+
+    /// Number of recorded interactions not yet replayed.
+    pub fn remaining(&self) -> usize {
+        self.used
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .iter()
+            .filter(|u| !**u)
+            .count()
+    }
+
+    /// Fail unless every recorded interaction was replayed.
+    pub fn assert_exhausted(&self) -> Result<()> {
+        match self.remaining() {
+            0 => Ok(()),
+            n => Err(ReplayDrift(format!(
+                "Replay drift in {}: {n} recorded interaction(s) were never requested",
+                self.source
+            ))
+            .into()),
+        }
+    }
+
+    fn replay(&self, request: RecordedRequest) -> Result<ChatResponse> {
+        let fingerprint = fingerprint(&request, self.matching);
+
+        let mut used = self
+            .used
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let hit = self
+            .interactions
+            .iter()
+            .enumerate()
+            .find(|(i, interaction)| {
+                !used[*i] && self::fingerprint(&interaction.request, self.matching) == fingerprint
+            })
+            .map(|(i, _)| i);
+
+        let Some(index) = hit else {
+            let detail = match used.iter().position(|u| !u) {
+                Some(next) => format!(
+                    "next recorded interaction is #{next}; {}",
+                    describe_drift(&self.interactions[next].request, &request, self.matching)
+                ),
+                None => "all recorded interactions were already replayed".into(),
+            };
+            return Err(ReplayDrift(format!(
+                "Replay drift in {}: no recorded interaction matches this request \
+                 (fingerprint {}); {detail}. Re-record the cassette if the change is intended.",
+                self.source,
+                &fingerprint[..12]
+            ))
+            .into());
+        };
+        used[index] = true;
+
+        let response = &self.interactions[index].response;
+        if let Some(error) = &response.error {
+            anyhow::bail!("{error}");
+        }
+        Ok(ChatResponse {
+            text: response.text.as_deref().map(|t| self.aliases.expand(t)),
+            tool_calls: response
+                .tool_calls
+                .iter()
+                .map(|call| ToolCall {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    arguments: self.aliases.expand(&call.arguments),
+                })
+                .collect(),
+            usage: None,
+        })
+    }
+}
+
+fn one_shot(system_prompt: Option<&str>, message: &str) -> Vec<ChatMessage> {
+    system_prompt
+        .map(ChatMessage::system)
+        .into_iter()
+        .chain(std::iter::once(ChatMessage::user(message)))
+        .collect()
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let messages = one_shot(system_prompt, message);
+        let request = recorded_request(&self.aliases, &messages, None, model, temperature);
+        Ok(self.replay(request)?.text.unwrap_or_default())
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let request = recorded_request(&self.aliases, messages, None, model, temperature);
+        Ok(self.replay(request)?.text.unwrap_or_default())
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        self.replay(recorded_request(
+            &self.aliases,
+            request.messages,
+            request.tools,
+            model,
+            temperature,
+        ))
+    }
+
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let request = recorded_request(&self.aliases, request.messages, None, model, temperature)
+            .with_schema(schema);
+        Ok(self.replay(request)?.text.unwrap_or_default())
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.native_tools
+    }
+}
+
+/// Shared handle, so callers can check [`ReplayProvider::assert_exhausted`]
+/// after handing the provider to an agent.
+#[async_trait]
+impl Provider for Arc<ReplayProvider> {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        self.as_ref()
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        self.as_ref()
+            .chat_with_history(messages, model, temperature)
+            .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        self.as_ref().chat(request, model, temperature).await
+    }
+
+    async fn chat_streaming(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> Result<ChatResponse> {
+        self.as_ref()
+            .chat_streaming(request, model, temperature, on_delta)
+            .await
+    }
+
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        self.as_ref()
+            .chat_with_schema(request, schema, model, temperature)
+            .await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.as_ref().supports_native_tools()
+    }
+
+    async fn warmup(&self) -> Result<bool> {
+        self.as_ref().warmup().await
+    }
+}
+
+/// Wraps a provider and appends every call to a cassette file.
+pub struct RecordingProvider {
+    inner: Box<dyn Provider>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+    aliases: Aliases,
+}
+
+impl RecordingProvider {
+    /// Start a new cassette at `path` (overwritten on the first call).
+    pub fn new(inner: Box<dyn Provider>, path: impl Into<PathBuf>) -> Self {
+        let cassette = Cassette {
+            version: CASSETTE_VERSION,
+            native_tools: inner.supports_native_tools(),
+            matching: MatchOptions::default(),
+            interactions: Vec::new(),
+        };
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(cassette),
+            aliases: Aliases::default(),
+        }
+    }
+
+    /// Store `value` as `placeholder` (e.g. a temp dir → `{workspace}`).
+    #[must_use]
+    pub fn with_alias(mut self, value: &str, placeholder: &str) -> Self {
+        self.aliases.push(value, placeholder);
+        self
+    }
+
+    /// Store `matching` in the cassette so replays use it.
+    #[must_use]
+    pub fn with_matching(mut self, matching: MatchOptions) -> Self {
+        self.cassette
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .matching = matching;
+        self
+    }
+
+    fn record(&self, request: RecordedRequest, result: &Result<ChatResponse>) -> Result<()> {
+        let response = match result {
+            Ok(response) => RecordedResponse {
+                text: response.text.as_deref().map(|t| self.aliases.normalize(t)),
+                tool_calls: response
+                    .tool_calls
+                    .iter()
+                    .map(|call| ToolCall {
+                        id: call.id.clone(),
+                        name: call.name.clone(),
+                        arguments: self.aliases.normalize(&call.arguments),
+                    })
+                    .collect(),
+                error: None,
+            },
+            Err(e) => RecordedResponse {
+                error: Some(self.aliases.normalize(&e.to_string())),
+                ..RecordedResponse::default()
+            },
+        };
+        let mut cassette = self
+            .cassette
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let fingerprint = fingerprint(&request, cassette.matching);
+        cassette.interactions.push(Interaction {
+            fingerprint,
+            request,
+            response,
+        });
+        cassette.save(&self.path)
+    }
+
+    fn recorded(
+        &self,
+        request: RecordedRequest,
+        result: Result<ChatResponse>,
+    ) -> Result<ChatResponse> {
+        if let Err(e) = self.record(request, &result) {
+            tracing::warn!("Failed to record cassette {}: {e}", self.path.display());
+        }
+        result
+    }
+}
+
+fn text_response(result: Result<String>) -> Result<ChatResponse> {
+    result.map(|text| ChatResponse {
+        text: Some(text),
+        tool_calls: Vec::new(),
+        usage: None,
+    })
+}
+
+#[async_trait]
+impl Provider for RecordingProvider {
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let result = self
+            .inner
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await;
+        let messages = one_shot(system_prompt, message);
+        let request = recorded_request(&self.aliases, &messages, None, model, temperature);
+        let response = self.recorded(request, text_response(result))?;
+        Ok(response.text.unwrap_or_default())
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let result = self
+            .inner
+            .chat_with_history(messages, model, temperature)
+            .await;
+        let request = recorded_request(&self.aliases, messages, None, model, temperature);
+        let response = self.recorded(request, text_response(result))?;
+        Ok(response.text.unwrap_or_default())
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        let result = self.inner.chat(request, model, temperature).await;
+        let recorded = recorded_request(
+            &self.aliases,
+            request.messages,
+            request.tools,
+            model,
+            temperature,
+        );
+        self.recorded(recorded, result)
+    }
+
+    async fn chat_streaming(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> Result<ChatResponse> {
+        let result = self
+            .inner
+            .chat_streaming(request, model, temperature, on_delta)
+            .await;
+        let recorded = recorded_request(
+            &self.aliases,
+            request.messages,
+            request.tools,
+            model,
+            temperature,
+        );
+        self.recorded(recorded, result)
+    }
+
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let result = self
+            .inner
+            .chat_with_schema(request, schema, model, temperature)
+            .await;
+        let recorded = recorded_request(&self.aliases, request.messages, None, model, temperature)
+            .with_schema(schema);
+        let response = self.recorded(recorded, text_response(result))?;
+        Ok(response.text.unwrap_or_default())
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    async fn warmup(&self) -> Result<bool> {
+        self.inner.warmup().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::dispatcher::NativeToolDispatcher;
+    use crate::agent::Agent;
+    use crate::memory::Memory;
+    use crate::observability::{NoopObserver, Observer};
+    use crate::tools::{Tool, ToolResult};
+
+    /// Scripted stand-in for a real API: asks for `echo` once, then answers.
+    struct ScriptedApi {
+        calls: Mutex<usize>,
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedApi {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            Ok(format!("reply to {message} with sk-live1234567890"))
+        }
+
+        async fn chat(
+            &self,
+            request: ChatRequest<'_>,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<ChatResponse> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            if *calls == 1 {
+                return Ok(ChatResponse {
+                    text: Some(String::new()),
+                    tool_calls: vec![ToolCall {
+                        id: "call_1".into(),
+                        name: "echo".into(),
+                        arguments: r#"{"text":"hi"}"#.into(),
+                    }],
+                    usage: None,
+                });
+            }
+            let last = request.messages.last().map_or("", |m| m.content.as_str());
+            Ok(ChatResponse {
+                text: Some(format!("done after {last}")),
+                tool_calls: vec![],
+                usage: None,
+            })
+        }
+
+        fn supports_native_tools(&self) -> bool {
+            true
+        }
+    }
+
+    struct EchoTool;
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echo text back"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        }
+
+        async fn execute(&self, args: Value) -> Result<ToolResult> {
+            Ok(ToolResult {
+                success: true,
+                output: format!("echo: {}", args["text"].as_str().unwrap_or("")),
+                error: None,
+            })
+        }
+    }
+
+    fn agent(provider: Box<dyn Provider>) -> Agent {
+        let memory_cfg = crate::config::MemoryConfig {
+            backend: "none".into(),
+            ..crate::config::MemoryConfig::default()
+        };
+        let mem: Arc<dyn Memory> =
+            Arc::from(crate::memory::create_memory(&memory_cfg, Path::new("/tmp"), None).unwrap());
+        let observer: Arc<dyn Observer> = Arc::new(NoopObserver {});
+        Agent::builder()
+            .provider(provider)
+            .tools(vec![Box::new(EchoTool)])
+            .memory(mem)
+            .observer(observer)
+            .tool_dispatcher(Box::new(NativeToolDispatcher))
+            .workspace_dir(PathBuf::from("/tmp"))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn recorded_tool_conversation_replays_offline() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cassettes/echo.json");
+
+        let recorder = RecordingProvider::new(
+            Box::new(ScriptedApi {
+                calls: Mutex::new(0),
+            }),
+            &path,
+        );
+        let recorded = agent(Box::new(recorder)).turn("say hi").await.unwrap();
+        assert!(recorded.contains("echo: hi"), "{recorded}");
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert!(cassette.native_tools);
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[0].response.tool_calls[0].name, "echo");
+        assert_eq!(cassette.interactions[1].request.tools[0].name, "echo");
+
+        let replay = Arc::new(ReplayProvider::load(&path).unwrap());
+        let replayed = agent(Box::new(replay.clone()))
+            .turn("say hi")
+            .await
+            .unwrap();
+        assert_eq!(replayed, recorded);
+        replay.assert_exhausted().unwrap();
+    }
+
+    #[tokio::test]
+    async fn drift_fails_loudly_with_the_first_difference() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("echo.json");
+        let recorder = RecordingProvider::new(
+            Box::new(ScriptedApi {
+                calls: Mutex::new(0),
+            }),
+            &path,
+        );
+        agent(Box::new(recorder)).turn("say hi").await.unwrap();
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        let err = agent(Box::new(replay)).turn("say bye").await.unwrap_err();
+        let drift = err.downcast_ref::<ReplayDrift>().expect("drift error");
+        let message = drift.to_string();
+        assert!(
+            message.contains("no recorded interaction matches"),
+            "{message}"
+        );
+        assert!(message.contains("\"say hi\""), "{message}");
+        assert!(message.contains("\"say bye\""), "{message}");
+
+        let unused = ReplayProvider::load(&path).unwrap();
+        assert!(unused
+            .assert_exhausted()
+            .unwrap_err()
+            .to_string()
+            .contains("2 recorded interaction(s)"));
+    }
+
+    #[tokio::test]
+    async fn secrets_are_scrubbed_and_aliases_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("one-shot.json");
+        let recorder = RecordingProvider::new(
+            Box::new(ScriptedApi {
+                calls: Mutex::new(0),
+            }),
+            &path,
+        )
+        .with_alias("/tmp/run-123", "{workspace}");
+
+        let live = recorder
+            .chat_with_system(Some("sys at /tmp/run-123"), "list /tmp/run-123", "m", 0.2)
+            .await
+            .unwrap();
+        assert!(live.contains("sk-live1234567890"));
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("sk-live1234567890"));
+        assert!(raw.contains("[REDACTED]"));
+        assert!(raw.contains("list {workspace}"));
+
+        let replay = ReplayProvider::load(&path)
+            .unwrap()
+            .with_alias("/tmp/run-456", "{workspace}");
+        let replayed = replay
+            .chat_with_system(Some("sys at /tmp/run-456"), "list /tmp/run-456", "m", 0.9)
+            .await
+            .unwrap();
+        assert_eq!(replayed, "reply to list /tmp/run-456 with [REDACTED]");
+    }
+
+    #[test]
+    fn fingerprint_covers_system_prompt_and_tool_specs() {
+        let request = |system: &str, description: &str, schema: Value| {
+            let tools = [ToolSpec {
+                name: "echo".into(),
+                description: description.into(),
+                parameters: schema,
+            }];
+            let messages = [ChatMessage::system(system), ChatMessage::user("hi")];
+            recorded_request(&Aliases::default(), &messages, Some(&tools), "m", 0.7)
+        };
+        let schema = json!({"type": "object", "properties": {}});
+        let strict = MatchOptions::default();
+        let a = request(
+            "Be brief.\nHost: ci-1 | OS: linux | Model: m\nTimezone: UTC",
+            "Echo",
+            schema.clone(),
+        );
+        let b = request(
+            "Be brief.\nHost: laptop | OS: macos | Model: m\nTimezone: CEST",
+            "Echo",
+            json!({"properties": {}, "type": "object"}),
+        );
+        assert_eq!(fingerprint(&a, strict), fingerprint(&b, strict));
+        assert_eq!(
+            a.messages[0].content,
+            "Be brief.\nHost: {host} | OS: {os} | Model: m\nTimezone: {timezone}"
+        );
+
+        let required = request(
+            "Be brief.",
+            "Echo",
+            json!({"type": "object", "required": ["x"]}),
+        );
+        let prompt = request("Be verbose.", "Echo", schema.clone());
+        let described = request("Be brief.", "Echo text", schema.clone());
+        let brief = request("Be brief.", "Echo", schema);
+        assert_ne!(fingerprint(&brief, strict), fingerprint(&required, strict));
+        assert_ne!(fingerprint(&brief, strict), fingerprint(&prompt, strict));
+        assert_ne!(fingerprint(&brief, strict), fingerprint(&described, strict));
+        assert!(describe_drift(&brief, &described, strict).contains("description of tool"));
+
+        let lenient = MatchOptions {
+            ignore_system_prompt: true,
+            ignore_tool_descriptions: true,
+        };
+        assert_eq!(fingerprint(&brief, lenient), fingerprint(&prompt, lenient));
+        assert_eq!(
+            fingerprint(&brief, lenient),
+            fingerprint(&described, lenient)
+        );
+    }
+
+    #[tokio::test]
+    async fn schema_requests_are_recorded_and_replayed() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("schema.json");
+        let recorder = RecordingProvider::new(
+            Box::new(ScriptedApi {
+                calls: Mutex::new(0),
+            }),
+            &path,
+        );
+        let messages = [ChatMessage::user("classify")];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+        let verdict = ResponseSchema::new("verdict", json!({"type": "object"}));
+        recorder
+            .chat_with_schema(request, &verdict, "m", 0.0)
+            .await
+            .unwrap();
+
+        let cassette = Cassette::load(&path).unwrap();
+        let recorded = cassette.interactions[0].request.response_schema.as_ref();
+        assert_eq!(recorded.unwrap().name, "verdict");
+
+        let replay = Arc::new(ReplayProvider::load(&path).unwrap());
+        let other = ResponseSchema::new("other", json!({"type": "object"}));
+        let err = replay
+            .chat_with_schema(request, &other, "m", 0.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("response schema differs"), "{err}");
+        let replayed = replay
+            .chat_with_schema(request, &verdict, "m", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(replayed, "reply to classify with [REDACTED]");
+        replay.assert_exhausted().unwrap();
+    }
+
+    #[tokio::test]
+    async fn cassette_matching_options_opt_out_of_system_prompts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("lenient.json");
+        let recorder = RecordingProvider::new(
+            Box::new(ScriptedApi {
+                calls: Mutex::new(0),
+            }),
+            &path,
+        )
+        .with_matching(MatchOptions {
+            ignore_system_prompt: true,
+            ..MatchOptions::default()
+        });
+        recorder
+            .chat_with_system(Some("prompt v1"), "hi", "m", 0.2)
+            .await
+            .unwrap();
+
+        let strict = ReplayProvider::load(&path)
+            .unwrap()
+            .with_matching(MatchOptions::default());
+        let err = strict
+            .chat_with_system(Some("prompt v2"), "hi", "m", 0.2)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("message 0 differs"), "{err}");
+
+        let lenient = ReplayProvider::load(&path).unwrap();
+        let replayed = lenient
+            .chat_with_system(Some("prompt v2"), "hi", "m", 0.2)
+            .await
+            .unwrap();
+        assert!(replayed.starts_with("reply to hi"));
+    }
+}
@@ -1,13 +1,56 @@
-use super::traits::{ChatMessage, ChatRequest, ChatResponse};
-use super::Provider;
+use super::routing::{
+    classifier_prompt, is_user_turn, last_user_text, parse_classification, price_for, window_for,
+    AutoRouter, RequestProfile, RouteCandidate, DEFAULT_ROUTE,
+};
+use super::traits::{ChatMessage, ChatRequest, ChatResponse, ResponseSchema, Usage};
+use super::{DeltaSink, Provider};
+use crate::agent::context::{estimate_messages, estimate_tokens};
+use crate::config::ModelPricing;
+use crate::cost::CostTracker;
+use crate::observability::Observer;
 use async_trait::async_trait;
-use std::collections::HashMap;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Auto-routing decisions kept for reuse by later tool-loop iterations.
+const CACHED_DECISIONS: usize = 64;
 
 /// A single route: maps a task hint to a provider + model combo.
 #[derive(Debug, Clone)]
 pub struct Route {
     pub provider_name: String,
     pub model: String,
+    /// What the route handles in auto mode besides its hint.
+    pub capabilities: Vec<String>,
+    /// Auto mode skips the route once its spend today reaches this.
+    pub daily_budget_usd: Option<f64>,
+}
+
+/// Settings for [`RouterProvider::with_auto_routing`].
+pub struct AutoRouting {
+    /// Route whose model classifies requests keywords cannot place.
+    pub classifier_hint: Option<String>,
+    /// `[cost.prices]`, USD per 1M tokens by model.
+    pub prices: HashMap<String, ModelPricing>,
+    /// Context window by model, as [`crate::agent::context::context_window`]
+    /// resolves it. Models not listed use the built-in table.
+    pub context_windows: HashMap<String, usize>,
+    /// Receives a `RouteSelected` event for every decision.
+    pub observer: Arc<dyn Observer>,
+    /// Cost log that routed usage is recorded in and route budgets are
+    /// checked against.
+    pub cost: Option<Arc<CostTracker>>,
+}
+
+/// Where one request goes.
+struct Target {
+    provider_index: usize,
+    model: String,
+    /// Candidate index when auto routing picked the target.
+    auto: Option<usize>,
 }
 
 /// Multi-model router — routes requests to different provider+model combos
@@ -17,12 +60,20 @@ pub struct Route {
 /// - A regular model name (e.g. "anthropic/claude-sonnet-4") → uses default provider
 /// - A hint-prefixed string (e.g. "hint:reasoning") → resolves via route table
 ///
+/// With [`with_auto_routing`](Self::with_auto_routing), requests for the
+/// default model (or `hint:auto`) are classified and routed automatically.
+///
 /// This wraps multiple pre-created providers and selects the right one per request.
 pub struct RouterProvider {
-    routes: HashMap<String, (usize, String)>, // hint → (provider_index, model)
+    routes: HashMap<String, (usize, Route)>, // hint → (provider_index, route)
     providers: Vec<(String, Box<dyn Provider>)>,
     default_index: usize,
     default_model: String,
+    auto: Option<AutoRouter>,
+    /// Recent auto-routing decisions by conversation fingerprint, so the
+    /// tool-loop iterations that answer one user message stay on one route
+    /// and are classified once.
+    decisions: Mutex<VecDeque<(u64, usize)>>,
 }
 
 impl RouterProvider {
@@ -43,12 +94,12 @@ impl RouterProvider {
             .collect();
 
         // Resolve routes to provider indices
-        let resolved_routes: HashMap<String, (usize, String)> = routes
+        let resolved_routes: HashMap<String, (usize, Route)> = routes
             .into_iter()
             .filter_map(|(hint, route)| {
                 let index = name_to_index.get(route.provider_name.as_str()).copied();
                 match index {
-                    Some(i) => Some((hint, (i, route.model))),
+                    Some(i) => Some((hint, (i, route))),
                     None => {
                         tracing::warn!(
                             hint = hint,
@@ -66,9 +117,49 @@ impl RouterProvider {
             providers,
             default_index: 0,
             default_model,
+            auto: None,
+            decisions: Mutex::new(VecDeque::new()),
         }
     }
 
+    /// Classify requests for the default model and send each to the best
+    /// route by capability, estimated price, latency and error rate.
+    pub fn with_auto_routing(mut self, settings: AutoRouting) -> Self {
+        let mut candidates = vec![RouteCandidate {
+            name: DEFAULT_ROUTE.into(),
+            provider_index: self.default_index,
+            provider_name: self.providers[self.default_index].0.clone(),
+            model: self.default_model.clone(),
+            capabilities: Vec::new(),
+            native_tools: self.providers[self.default_index].1.supports_native_tools(),
+            pricing: price_for(&settings.prices, &self.default_model),
+            context_window: window_for(&settings.context_windows, &self.default_model),
+            daily_budget_usd: None,
+        }];
+        let mut hints: Vec<_> = self.routes.iter().collect();
+        hints.sort_by(|a, b| a.0.cmp(b.0));
+        for (hint, (index, route)) in hints {
+            candidates.push(RouteCandidate {
+                name: hint.clone(),
+                provider_index: *index,
+                provider_name: self.providers[*index].0.clone(),
+                model: route.model.clone(),
+                capabilities: route.capabilities.clone(),
+                native_tools: self.providers[*index].1.supports_native_tools(),
+                pricing: price_for(&settings.prices, &route.model),
+                context_window: window_for(&settings.context_windows, &route.model),
+                daily_budget_usd: route.daily_budget_usd,
+            });
+        }
+        self.auto = Some(AutoRouter::new(
+            candidates,
+            settings.classifier_hint.as_deref(),
+            settings.observer,
+            settings.cost,
+        ));
+        self
+    }
+
     /// Resolve a model parameter to a (provider, actual_model) pair.
     ///
     /// If the model starts with "hint:", look up the hint in the route table.
@@ -76,8 +167,8 @@ impl RouterProvider {
     /// Resolve a model parameter to a (provider_index, actual_model) pair.
     fn resolve(&self, model: &str) -> (usize, String) {
         if let Some(hint) = model.strip_prefix("hint:") {
-            if let Some((idx, resolved_model)) = self.routes.get(hint) {
-                return (*idx, resolved_model.clone());
+            if let Some((idx, route)) = self.routes.get(hint) {
+                return (*idx, route.model.clone());
             }
             tracing::warn!(
                 hint = hint,
@@ -88,6 +179,158 @@ impl RouterProvider {
         // Not a hint or hint not found — use default provider with the model as-is
         (self.default_index, model.to_string())
     }
+
+    /// Pick the provider and model for a request: automatically for the
+    /// default model when auto routing is on, otherwise via [`Self::resolve`].
+    /// Auto routing fails when the budgets rule out every route.
+    async fn target(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        needs_tools: bool,
+    ) -> anyhow::Result<Target> {
+        let Some(auto) = self
+            .auto
+            .as_ref()
+            .filter(|_| model == "hint:auto" || model == self.default_model)
+        else {
+            let (provider_index, model) = self.resolve(model);
+            return Ok(Target {
+                provider_index,
+                model,
+                auto: None,
+            });
+        };
+
+        let key = conversation_key(messages, needs_tools);
+        let cached = self
+            .decisions
+            .lock()
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, candidate)| *candidate);
+        let chosen = match cached {
+            Some(candidate) => candidate,
+            None => {
+                let mut profile = RequestProfile::from_messages(messages, needs_tools);
+                if profile.category.is_none() {
+                    profile.category = self.classify_with_model(auto, messages).await;
+                }
+                let candidate = auto.choose(&profile)?.candidate;
+                let mut decisions = self.decisions.lock();
+                if decisions.len() == CACHED_DECISIONS {
+                    decisions.pop_front();
+                }
+                decisions.push_back((key, candidate));
+                candidate
+            }
+        };
+        let candidate = auto.candidate(chosen);
+        Ok(Target {
+            provider_index: candidate.provider_index,
+            model: candidate.model.clone(),
+            auto: Some(chosen),
+        })
+    }
+
+    /// Ask the classifier route's model for a category, if one is configured.
+    async fn classify_with_model(
+        &self,
+        auto: &AutoRouter,
+        messages: &[ChatMessage],
+    ) -> Option<String> {
+        let classifier = auto.classifier()?;
+        let categories = auto.categories();
+        let text = last_user_text(messages);
+        if categories.is_empty() || text.trim().is_empty() {
+            return None;
+        }
+        let (_, provider) = &self.providers[classifier.provider_index];
+        match provider
+            .chat_with_system(
+                None,
+                &classifier_prompt(&categories, text),
+                &classifier.model,
+                0.0,
+            )
+            .await
+        {
+            Ok(reply) => parse_classification(&reply, &categories),
+            Err(e) => {
+                tracing::warn!("Routing classifier failed, keeping the default route: {e}");
+                None
+            }
+        }
+    }
+
+    /// Feed the outcome of an auto-routed call back into its route's stats
+    /// and the cost log. `usage` is what the provider reported; without it,
+    /// tokens are estimated from `messages` and `output`.
+    fn record(
+        &self,
+        target: &Target,
+        started: Instant,
+        messages: &[ChatMessage],
+        output: Option<&str>,
+        usage: Option<Usage>,
+    ) {
+        if let (Some(auto), Some(candidate)) = (&self.auto, target.auto) {
+            let usage = output.map(|output| {
+                usage.unwrap_or_else(|| Usage {
+                    input_tokens: estimate_messages(messages) as u64,
+                    output_tokens: estimate_tokens(output) as u64,
+                    ..Usage::default()
+                })
+            });
+            auto.record(
+                candidate,
+                started.elapsed(),
+                output.is_some(),
+                usage.as_ref(),
+            );
+        }
+    }
+
+    /// [`Self::record`] for calls that return a [`ChatResponse`]. Auto-routed
+    /// usage is recorded here under the model that served it, so it is taken
+    /// off the response to keep callers from counting it again.
+    fn record_response(
+        &self,
+        target: &Target,
+        started: Instant,
+        messages: &[ChatMessage],
+        mut result: anyhow::Result<ChatResponse>,
+    ) -> anyhow::Result<ChatResponse> {
+        let records_cost =
+            target.auto.is_some() && self.auto.as_ref().is_some_and(AutoRouter::records_cost);
+        let usage = match &mut result {
+            Ok(response) if records_cost => response.usage.take(),
+            Ok(response) => response.usage,
+            Err(_) => None,
+        };
+        let output = result
+            .as_ref()
+            .ok()
+            .map(|response| response.text.as_deref().unwrap_or_default());
+        self.record(target, started, messages, output, usage);
+        result
+    }
+}
+
+/// Identifies a conversation up to its latest user message, so requests
+/// that only add replies and tool results to it share a routing decision.
+fn conversation_key(messages: &[ChatMessage], needs_tools: bool) -> u64 {
+    let end = messages
+        .iter()
+        .rposition(is_user_turn)
+        .map_or(messages.len(), |i| i + 1);
+    let mut hasher = DefaultHasher::new();
+    needs_tools.hash(&mut hasher);
+    for message in &messages[..end] {
+        message.role.hash(&mut hasher);
+        message.content.hash(&mut hasher);
+    }
+    hasher.finish()
 }
 
 #[async_trait]
@@ -99,18 +342,26 @@ impl Provider for RouterProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let (provider_idx, resolved_model) = self.resolve(model);
+        let mut messages = Vec::with_capacity(2);
+        if let Some(system_prompt) = system_prompt {
+            messages.push(ChatMessage::system(system_prompt));
+        }
+        messages.push(ChatMessage::user(message));
+        let target = self.target(model, &messages, false).await?;
 
-        let (provider_name, provider) = &self.providers[provider_idx];
+        let (provider_name, provider) = &self.providers[target.provider_index];
         tracing::info!(
             provider = provider_name.as_str(),
-            model = resolved_model.as_str(),
+            model = target.model.as_str(),
             "Router dispatching request"
         );
 
-        provider
-            .chat_with_system(system_prompt, message, &resolved_model, temperature)
-            .await
+        let started = Instant::now();
+        let result = provider
+            .chat_with_system(system_prompt, message, &target.model, temperature)
+            .await;
+        self.record(&target, started, &messages, result.as_deref().ok(), None);
+        result
     }
 
     async fn chat_with_history(
@@ -119,11 +370,14 @@ impl Provider for RouterProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let (provider_idx, resolved_model) = self.resolve(model);
-        let (_, provider) = &self.providers[provider_idx];
-        provider
-            .chat_with_history(messages, &resolved_model, temperature)
-            .await
+        let target = self.target(model, messages, false).await?;
+        let (_, provider) = &self.providers[target.provider_index];
+        let started = Instant::now();
+        let result = provider
+            .chat_with_history(messages, &target.model, temperature)
+            .await;
+        self.record(&target, started, messages, result.as_deref().ok(), None);
+        result
     }
 
     async fn chat(
@@ -132,9 +386,49 @@ impl Provider for RouterProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<ChatResponse> {
-        let (provider_idx, resolved_model) = self.resolve(model);
-        let (_, provider) = &self.providers[provider_idx];
-        provider.chat(request, &resolved_model, temperature).await
+        let needs_tools = request.tools.is_some_and(|tools| !tools.is_empty());
+        let target = self.target(model, request.messages, needs_tools).await?;
+        let (_, provider) = &self.providers[target.provider_index];
+        let messages = request.messages;
+        let started = Instant::now();
+        let result = provider.chat(request, &target.model, temperature).await;
+        self.record_response(&target, started, messages, result)
+    }
+
+    async fn chat_streaming(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ChatResponse> {
+        let needs_tools = request.tools.is_some_and(|tools| !tools.is_empty());
+        let target = self.target(model, request.messages, needs_tools).await?;
+        let (_, provider) = &self.providers[target.provider_index];
+        let messages = request.messages;
+        let started = Instant::now();
+        let result = provider
+            .chat_streaming(request, &target.model, temperature, on_delta)
+            .await;
+        self.record_response(&target, started, messages, result)
+    }
+
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let target = self.target(model, request.messages, false).await?;
+        let (_, provider) = &self.providers[target.provider_index];
+        let messages = request.messages;
+        let started = Instant::now();
+        let result = provider
+            .chat_with_schema(request, schema, &target.model, temperature)
+            .await;
+        self.record(&target, started, messages, result.as_deref().ok(), None);
+        result
     }
 
     fn supports_native_tools(&self) -> bool {
@@ -144,14 +438,19 @@ impl Provider for RouterProvider {
             .unwrap_or(false)
     }
 
-    async fn warmup(&self) -> anyhow::Result<()> {
+    async fn warmup(&self) -> anyhow::Result<bool> {
+        let mut probed = false;
         for (name, provider) in &self.providers {
             tracing::info!(provider = name, "Warming up routed provider");
-            if let Err(e) = provider.warmup().await {
-                tracing::warn!(provider = name, "Warmup failed (non-fatal): {e}");
+            match provider.warmup().await {
+                Ok(contacted) => probed |= contacted,
+                Err(e) => {
+                    probed = true;
+                    tracing::warn!(provider = name, "Warmup failed (non-fatal): {e}");
+                }
             }
         }
-        Ok(())
+        Ok(probed)
     }
 }
 
@@ -228,6 +527,8 @@ mod tests {
                     Route {
                         provider_name: provider_name.to_string(),
                         model: model.to_string(),
+                        capabilities: Vec::new(),
+                        daily_budget_usd: None,
                     },
                 )
             })
@@ -359,8 +660,8 @@ mod tests {
     async fn warmup_calls_all_providers() {
         let (router, _) = make_router(vec![("a", "ok"), ("b", "ok")], vec![]);
 
-        // Warmup should not error
-        assert!(router.warmup().await.is_ok());
+        // Warmup should not error; the mocks have nothing to contact.
+        assert!(!router.warmup().await.unwrap());
     }
 
     #[tokio::test]
@@ -382,4 +683,78 @@ mod tests {
         assert_eq!(result, "response");
         assert_eq!(mock.call_count(), 1);
     }
+
+    #[tokio::test]
+    async fn auto_routing_sends_requests_to_matching_route() {
+        let (router, mocks) = make_router(
+            vec![("default", "default-response"), ("smart", "smart-response")],
+            vec![("code", "smart", "claude-opus")],
+        );
+        let router = router.with_auto_routing(AutoRouting {
+            classifier_hint: None,
+            prices: HashMap::new(),
+            context_windows: HashMap::new(),
+            observer: Arc::new(crate::observability::NoopObserver),
+            cost: None,
+        });
+
+        let result = router
+            .simple_chat("Please refactor this function", "default-model", 0.5)
+            .await
+            .unwrap();
+        assert_eq!(result, "smart-response");
+        assert_eq!(mocks[1].last_model(), "claude-opus");
+
+        let result = router
+            .simple_chat("hi", "default-model", 0.5)
+            .await
+            .unwrap();
+        assert_eq!(result, "default-response");
+        assert_eq!(mocks[0].last_model(), "default-model");
+
+        // Explicit models bypass auto routing.
+        router.simple_chat("refactor", "gpt-4o", 0.5).await.unwrap();
+        assert_eq!(mocks[0].last_model(), "gpt-4o");
+    }
+
+    #[tokio::test]
+    async fn auto_routing_classifies_once_per_user_message() {
+        let (router, mocks) = make_router(
+            vec![("default", "default-response"), ("cheap", "general")],
+            vec![("classify", "cheap", "llama3")],
+        );
+        let router = router.with_auto_routing(AutoRouting {
+            classifier_hint: Some("classify".into()),
+            prices: HashMap::new(),
+            context_windows: HashMap::new(),
+            observer: Arc::new(crate::observability::NoopObserver),
+            cost: None,
+        });
+
+        let mut history = vec![
+            ChatMessage::system("sys"),
+            ChatMessage::user("Tell me a story. ".repeat(20)),
+        ];
+        for _ in 0..3 {
+            router
+                .chat_with_history(&history, "default-model", 0.5)
+                .await
+                .unwrap();
+            history.push(ChatMessage::assistant("<tool_call>...</tool_call>"));
+            history.push(ChatMessage::user("[Tool results]\nok"));
+        }
+        assert_eq!(
+            mocks[1].call_count(),
+            1,
+            "tool-loop turns reuse the decision"
+        );
+        assert_eq!(mocks[0].call_count(), 3);
+
+        history.push(ChatMessage::user("And another one. ".repeat(20)));
+        router
+            .chat_with_history(&history, "default-model", 0.5)
+            .await
+            .unwrap();
+        assert_eq!(mocks[1].call_count(), 2, "a new user message is classified");
+    }
 }
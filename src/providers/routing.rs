@@ -0,0 +1,724 @@
+//! Automatic route selection for [`RouterProvider`](super::router::RouterProvider).
+//!
+//! A request is profiled (category, tools, images, size), routes that cannot
+//! serve it are dropped (missing capability, too small a context window,
+//! daily budget spent), and the rest are scored by estimated price plus
+//! penalties for observed latency and error rate. The lowest score wins.
+//!
+//! Spend is read from the persisted [`CostTracker`] log, so budgets hold
+//! across restarts and config reloads.
+
+use super::traits::{ChatMessage, Usage};
+use crate::agent::context::{default_context_window, estimate_messages};
+use crate::config::ModelPricing;
+use crate::cost::{BudgetCheck, CostTracker, UsagePeriod};
+use crate::observability::{Observer, ObserverEvent};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Name of the implicit route for the default provider and model.
+pub const DEFAULT_ROUTE: &str = "default";
+
+/// Reply length assumed when estimating the price of a request.
+const EXPECTED_OUTPUT_TOKENS: usize = 500;
+
+/// Score penalty per second of observed latency, in USD.
+const LATENCY_USD_PER_SEC: f64 = 0.001;
+
+/// Score penalty for a route that always fails, in USD.
+const ERROR_PENALTY_USD: f64 = 0.05;
+
+/// Weight of the newest sample in latency and error-rate averages.
+const EWMA_ALPHA: f64 = 0.2;
+
+/// Requests shorter than this (and without tools) count as "fast".
+const FAST_MAX_CHARS: usize = 200;
+
+/// Longest excerpt of the request sent to the classifier model.
+const CLASSIFIER_INPUT_CHARS: usize = 2_000;
+
+/// Categories recognised by keyword, checked in order.
+const KEYWORDS: &[(&str, &[&str])] = &[
+    (
+        "code",
+        &[
+            "```",
+            "compile",
+            "stack trace",
+            "traceback",
+            "refactor",
+            "function",
+            "regex",
+            "unit test",
+            "segfault",
+            "sql",
+        ],
+    ),
+    (
+        "summarize",
+        &["summarize", "summarise", "summary", "tl;dr", "tldr"],
+    ),
+    (
+        "reasoning",
+        &[
+            "step by step",
+            "prove",
+            "analyze",
+            "analyse",
+            "trade-off",
+            "tradeoff",
+            "compare",
+            "explain why",
+            "design a",
+        ],
+    ),
+];
+
+/// What a request needs, as far as routing is concerned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestProfile {
+    /// Task category ("code", "reasoning", "summarize", "fast", or one the
+    /// classifier model returned). `None` keeps the default route.
+    pub category: Option<String>,
+    pub needs_tools: bool,
+    pub has_images: bool,
+    /// Estimated prompt size in tokens.
+    pub tokens: usize,
+}
+
+impl RequestProfile {
+    /// Profile `messages`, classifying by the latest user message.
+    pub fn from_messages(messages: &[ChatMessage], needs_tools: bool) -> Self {
+        let text = last_user_text(messages);
+        Self {
+            category: classify(text, needs_tools).map(str::to_string),
+            needs_tools,
+            has_images: messages
+                .iter()
+                .any(|m| m.content.contains("data:image/") || m.content.contains("[IMAGE:")),
+            tokens: estimate_messages(messages),
+        }
+    }
+}
+
+/// Whether `message` was written by the user, not tool results the agent
+/// loop sends back under the user role.
+pub fn is_user_turn(message: &ChatMessage) -> bool {
+    message.role == "user" && !message.content.starts_with("[Tool results]")
+}
+
+/// The latest user message, which is what the request is about.
+pub fn last_user_text(messages: &[ChatMessage]) -> &str {
+    messages
+        .iter()
+        .rev()
+        .find(|m| is_user_turn(m))
+        .map_or("", |m| m.content.as_str())
+}
+
+/// Whether `needle` occurs in `text` as a whole word: not preceded or
+/// followed by a letter or digit ("prove" does not match "improve").
+fn contains_word(text: &str, needle: &str) -> bool {
+    text.match_indices(needle).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Keyword classification. Short tool-free requests are "fast".
+pub fn classify(text: &str, needs_tools: bool) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    KEYWORDS
+        .iter()
+        .find(|(_, words)| words.iter().any(|w| contains_word(&lower, w)))
+        .map(|(category, _)| *category)
+        .or_else(|| (!needs_tools && text.trim().len() < FAST_MAX_CHARS).then_some("fast"))
+}
+
+/// Prompt asking a cheap model to pick one of `categories` for `text`.
+pub fn classifier_prompt(categories: &[&str], text: &str) -> String {
+    format!(
+        "Classify the request below into exactly one of these categories: {}, general. \
+         Reply with the category name only.\n\nRequest:\n{}",
+        categories.join(", "),
+        crate::util::truncate_with_ellipsis(text, CLASSIFIER_INPUT_CHARS)
+    )
+}
+
+/// The category in a classifier reply, if it names one of `categories`.
+pub fn parse_classification(reply: &str, categories: &[&str]) -> Option<String> {
+    let word = reply
+        .trim()
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    categories
+        .iter()
+        .find(|c| c.eq_ignore_ascii_case(&word))
+        .map(|c| (*c).to_string())
+}
+
+/// Context window for `model` from `windows`, else the built-in table.
+#[allow(clippy::implicit_hasher)]
+pub fn window_for(windows: &HashMap<String, usize>, model: &str) -> usize {
+    windows
+        .get(model)
+        .copied()
+        .unwrap_or_else(|| default_context_window(model))
+}
+
+/// Price for `model` in the `[cost.prices]` table, matching with or without
+/// a `vendor/` prefix on either side.
+#[allow(clippy::implicit_hasher)]
+pub fn price_for(prices: &HashMap<String, ModelPricing>, model: &str) -> Option<ModelPricing> {
+    let bare = |name: &str| {
+        name.rsplit_once('/')
+            .map_or(name, |(_, rest)| rest)
+            .to_string()
+    };
+    prices
+        .get(model)
+        .or_else(|| {
+            let model = bare(model);
+            prices
+                .iter()
+                .filter(|(key, _)| bare(key) == model)
+                .min_by(|a, b| a.0.cmp(b.0))
+                .map(|(_, price)| price)
+        })
+        .cloned()
+}
+
+/// A route auto mode can choose.
+#[derive(Debug, Clone)]
+pub struct RouteCandidate {
+    /// Route hint, or [`DEFAULT_ROUTE`].
+    pub name: String,
+    pub provider_index: usize,
+    pub provider_name: String,
+    pub model: String,
+    pub capabilities: Vec<String>,
+    /// Whether the route's provider accepts tool specs natively.
+    pub native_tools: bool,
+    /// `None` when the model is not priced (local models are usually free).
+    pub pricing: Option<ModelPricing>,
+    /// Context window in tokens; longer requests skip the route.
+    pub context_window: usize,
+    pub daily_budget_usd: Option<f64>,
+}
+
+impl RouteCandidate {
+    /// Whether the route is meant for `category`, by hint or capability.
+    fn serves(&self, category: &str) -> bool {
+        self.name == category || self.capabilities.iter().any(|c| c == category)
+    }
+
+    /// Whether the route can take images. General-purpose routes declare no
+    /// capabilities and are assumed to.
+    fn supports_vision(&self) -> bool {
+        self.capabilities.is_empty() || self.capabilities.iter().any(|c| c == "vision")
+    }
+
+    /// Whether the route can take tool specs: its provider must support
+    /// native tool calling, and a route that lists capabilities must list
+    /// "tools".
+    fn supports_tools(&self) -> bool {
+        self.native_tools
+            && (self.capabilities.is_empty() || self.capabilities.iter().any(|c| c == "tools"))
+    }
+
+    /// Estimated USD for a call with the given token counts.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cost_usd(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        self.pricing.as_ref().map_or(0.0, |price| {
+            (input_tokens as f64 * price.input.max(0.0)
+                + output_tokens as f64 * price.output.max(0.0))
+                / 1_000_000.0
+        })
+    }
+}
+
+/// Observed behaviour of one route.
+#[derive(Debug, Clone, Default)]
+struct RouteStats {
+    latency_secs: Option<f64>,
+    error_rate: f64,
+}
+
+/// A routing decision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteChoice {
+    /// Index into the router's candidates.
+    pub candidate: usize,
+    pub reason: String,
+}
+
+/// Chooses among routes and learns from the outcome of each call.
+pub struct AutoRouter {
+    candidates: Vec<RouteCandidate>,
+    stats: Mutex<Vec<RouteStats>>,
+    classifier: Option<usize>,
+    observer: Arc<dyn Observer>,
+    /// Where routed spend is recorded and budgets are read from. Without
+    /// it, route budgets are not enforced.
+    cost: Option<Arc<CostTracker>>,
+}
+
+impl AutoRouter {
+    /// `candidates[0]` must be the default route.
+    pub fn new(
+        candidates: Vec<RouteCandidate>,
+        classifier_hint: Option<&str>,
+        observer: Arc<dyn Observer>,
+        cost: Option<Arc<CostTracker>>,
+    ) -> Self {
+        let classifier =
+            classifier_hint.and_then(|hint| candidates.iter().position(|c| c.name == hint));
+        let stats = Mutex::new(vec![RouteStats::default(); candidates.len()]);
+        if cost.is_none() && candidates.iter().any(|c| c.daily_budget_usd.is_some()) {
+            tracing::warn!("Route budgets need [cost] enabled = true; they are not enforced");
+        }
+        Self {
+            candidates,
+            stats,
+            classifier,
+            observer,
+            cost,
+        }
+    }
+
+    /// Whether [`Self::record`] writes usage to the cost log.
+    pub fn records_cost(&self) -> bool {
+        self.cost.is_some()
+    }
+
+    pub fn candidate(&self, index: usize) -> &RouteCandidate {
+        &self.candidates[index]
+    }
+
+    /// The route used to classify requests keywords could not place.
+    pub fn classifier(&self) -> Option<&RouteCandidate> {
+        self.classifier.map(|i| &self.candidates[i])
+    }
+
+    /// Categories a classifier may answer with: every route hint plus the
+    /// capabilities routes declare, excluding tool/vision support.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = self
+            .candidates
+            .iter()
+            .skip(1)
+            .flat_map(|c| std::iter::once(&c.name).chain(&c.capabilities))
+            .map(String::as_str)
+            .filter(|c| !matches!(*c, "tools" | "vision"))
+            .collect();
+        categories.sort_unstable();
+        categories.dedup();
+        categories
+    }
+
+    /// Today's spend on `candidate`'s model, from the cost log.
+    fn spent_today(&self, candidate: &RouteCandidate) -> f64 {
+        let Some(cost) = &self.cost else {
+            return 0.0;
+        };
+        cost.get_daily_model_cost(&candidate.model)
+            .unwrap_or_else(|e| {
+                tracing::warn!("Could not read route spend: {e:#}");
+                0.0
+            })
+    }
+
+    /// Pick a route for `profile` and report the decision to the observer.
+    /// Fails when the `[cost]` limit is reached, or when every route that
+    /// could serve the request has spent its daily budget.
+    pub fn choose(&self, profile: &RequestProfile) -> anyhow::Result<RouteChoice> {
+        if let Some(cost) = &self.cost {
+            if let BudgetCheck::Exceeded {
+                current_usd,
+                limit_usd,
+                period,
+            } = cost.check_budget(0.0)?
+            {
+                let period = match period {
+                    UsagePeriod::Session => "Session",
+                    UsagePeriod::Day => "Daily",
+                    UsagePeriod::Month => "Monthly",
+                };
+                anyhow::bail!("{period} cost limit reached (${current_usd:.2} of ${limit_usd:.2})");
+            }
+        }
+        let stats = self.stats.lock().clone();
+
+        let capable: Vec<usize> = (0..self.candidates.len())
+            .filter(|&i| {
+                let candidate = &self.candidates[i];
+                (!profile.needs_tools || candidate.supports_tools())
+                    && (!profile.has_images || candidate.supports_vision())
+                    && profile.tokens + EXPECTED_OUTPUT_TOKENS <= candidate.context_window
+            })
+            .collect();
+        let eligible: Vec<usize> = capable
+            .iter()
+            .copied()
+            .filter(|&i| {
+                let candidate = &self.candidates[i];
+                candidate
+                    .daily_budget_usd
+                    .is_none_or(|cap| self.spent_today(candidate) < cap)
+            })
+            .collect();
+        if eligible.is_empty() && !capable.is_empty() {
+            anyhow::bail!("Every route that can serve this request has spent its daily budget");
+        }
+
+        let category = profile.category.as_deref();
+        let matching: Vec<usize> = category
+            .map(|c| {
+                eligible
+                    .iter()
+                    .copied()
+                    .filter(|&i| self.candidates[i].serves(c))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let pool = if !matching.is_empty() {
+            matching
+        } else if eligible.contains(&0) {
+            vec![0]
+        } else {
+            eligible.clone()
+        };
+
+        let score = |i: usize| {
+            let candidate = &self.candidates[i];
+            candidate.cost_usd(profile.tokens, EXPECTED_OUTPUT_TOKENS)
+                + stats[i].latency_secs.unwrap_or(0.0) * LATENCY_USD_PER_SEC
+                + stats[i].error_rate * ERROR_PENALTY_USD
+        };
+        let chosen = pool
+            .iter()
+            .copied()
+            .min_by(|&a, &b| score(a).total_cmp(&score(b)))
+            .unwrap_or(0);
+
+        let mut reason = format!(
+            "category={} tools={} images={} tokens~{}",
+            category.unwrap_or("general"),
+            profile.needs_tools,
+            profile.has_images,
+            profile.tokens
+        );
+        if eligible.is_empty() {
+            reason.push_str("; no route can serve it, using default");
+        } else {
+            let _ = write!(
+                reason,
+                "; {} of {} routes eligible, {} considered; est ${:.4}",
+                eligible.len(),
+                self.candidates.len(),
+                pool.len(),
+                self.candidates[chosen].cost_usd(profile.tokens, EXPECTED_OUTPUT_TOKENS)
+            );
+            if let Some(latency) = stats[chosen].latency_secs {
+                let _ = write!(reason, ", latency {:.0}ms", latency * 1000.0);
+            }
+            let _ = write!(reason, ", errors {:.0}%", stats[chosen].error_rate * 100.0);
+        }
+
+        let candidate = &self.candidates[chosen];
+        tracing::info!(
+            route = candidate.name.as_str(),
+            provider = candidate.provider_name.as_str(),
+            model = candidate.model.as_str(),
+            reason = reason.as_str(),
+            "Auto-routing request"
+        );
+        self.observer.record_event(&ObserverEvent::RouteSelected {
+            route: candidate.name.clone(),
+            provider: candidate.provider_name.clone(),
+            model: candidate.model.clone(),
+            reason: reason.clone(),
+        });
+
+        Ok(RouteChoice {
+            candidate: chosen,
+            reason,
+        })
+    }
+
+    /// Fold the outcome of a routed call into the route's statistics, and
+    /// record its `usage` in the cost log under the route's model.
+    pub fn record(
+        &self,
+        candidate: usize,
+        latency: Duration,
+        success: bool,
+        usage: Option<&Usage>,
+    ) {
+        if let (Some(cost), Some(usage)) = (&self.cost, usage) {
+            let model = &self.candidates[candidate].model;
+            if let Err(e) = cost.record_response(model, usage) {
+                tracing::warn!("Failed to record routed token usage: {e:#}");
+            }
+        }
+        let mut stats = self.stats.lock();
+        let stats = &mut stats[candidate];
+        let secs = latency.as_secs_f64();
+        stats.latency_secs = Some(
+            stats
+                .latency_secs
+                .map_or(secs, |avg| avg + EWMA_ALPHA * (secs - avg)),
+        );
+        let failure = if success { 0.0 } else { 1.0 };
+        stats.error_rate += EWMA_ALPHA * (failure - stats.error_rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::traits::ObserverMetric;
+
+    #[derive(Default)]
+    struct Recording(Mutex<Vec<String>>);
+
+    impl Observer for Recording {
+        fn record_event(&self, event: &ObserverEvent) {
+            if let ObserverEvent::RouteSelected { route, reason, .. } = event {
+                self.0.lock().push(format!("{route}: {reason}"));
+            }
+        }
+
+        fn record_metric(&self, _metric: &ObserverMetric) {}
+
+        fn name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    fn candidate(name: &str, model: &str, input: f64, capabilities: &[&str]) -> RouteCandidate {
+        RouteCandidate {
+            name: name.into(),
+            provider_index: 0,
+            provider_name: "openrouter".into(),
+            model: model.into(),
+            capabilities: capabilities.iter().map(|c| (*c).to_string()).collect(),
+            native_tools: true,
+            pricing: Some(ModelPricing {
+                input,
+                output: input * 5.0,
+                cache_read: None,
+                cache_write: None,
+            }),
+            context_window: default_context_window(model),
+            daily_budget_usd: None,
+        }
+    }
+
+    fn profile(category: Option<&str>, needs_tools: bool) -> RequestProfile {
+        RequestProfile {
+            category: category.map(str::to_string),
+            needs_tools,
+            has_images: false,
+            tokens: 1_000,
+        }
+    }
+
+    #[test]
+    fn requests_are_classified_by_keywords_and_size() {
+        assert_eq!(
+            classify("Why does this fail to compile?", false),
+            Some("code")
+        );
+        assert_eq!(
+            classify("Summarize the meeting notes", true),
+            Some("summarize")
+        );
+        assert_eq!(
+            classify("How can I improve my functions' naming?", true),
+            None,
+            "keywords match whole words only"
+        );
+        assert_eq!(classify("hi!", false), Some("fast"));
+        assert_eq!(classify("hi!", true), None);
+        assert_eq!(classify(&"Tell me a story. ".repeat(20), false), None);
+
+        let messages = [
+            ChatMessage::system("You write code all day"),
+            ChatMessage::user("compare these designs step by step, please, in detail"),
+        ];
+        let profile = RequestProfile::from_messages(&messages, false);
+        assert_eq!(profile.category.as_deref(), Some("reasoning"));
+        assert!(!profile.has_images);
+
+        assert_eq!(
+            parse_classification(" Code.\n", &["code", "reasoning"]),
+            Some("code".into())
+        );
+        assert_eq!(parse_classification("general", &["code"]), None);
+    }
+
+    fn chosen(router: &AutoRouter, profile: &RequestProfile) -> usize {
+        router.choose(profile).unwrap().candidate
+    }
+
+    fn tracker(dir: &std::path::Path, daily_limit_usd: f64) -> Arc<CostTracker> {
+        let config = crate::config::schema::CostConfig {
+            enabled: true,
+            daily_limit_usd,
+            prices: HashMap::from([(
+                "gpt-4o".to_string(),
+                ModelPricing {
+                    input: 1.0,
+                    output: 5.0,
+                    cache_read: None,
+                    cache_write: None,
+                },
+            )]),
+            ..Default::default()
+        };
+        Arc::new(CostTracker::new(config, dir).unwrap())
+    }
+
+    #[test]
+    fn cheapest_capable_route_wins_and_decision_is_logged() {
+        let observer = Arc::new(Recording::default());
+        let mut no_native_tools = candidate("cheap-code", "phi3", 0.01, &["code"]);
+        no_native_tools.native_tools = false;
+        let router = AutoRouter::new(
+            vec![
+                candidate(DEFAULT_ROUTE, "claude-sonnet-4", 3.0, &[]),
+                candidate("code", "qwen-coder", 0.5, &["tools"]),
+                candidate("code-premium", "gpt-4o", 5.0, &["code"]),
+                candidate("fast", "llama3", 0.1, &["vision"]),
+                no_native_tools,
+            ],
+            None,
+            observer.clone(),
+            None,
+        );
+
+        // The cheapest code route has no native tool calling.
+        assert_eq!(chosen(&router, &profile(Some("code"), true)), 1);
+        assert_eq!(chosen(&router, &profile(Some("code"), false)), 4);
+        // The cheap route is not eligible without tool support.
+        assert_eq!(chosen(&router, &profile(Some("fast"), true)), 0);
+        // Unknown and missing categories stay on the default route.
+        assert_eq!(chosen(&router, &profile(Some("poetry"), false)), 0);
+        assert_eq!(chosen(&router, &profile(None, false)), 0);
+
+        let log = observer.0.lock();
+        assert_eq!(log.len(), 5);
+        assert!(log[0].starts_with("code: category=code tools=true"));
+        assert!(log[0].contains("2 of 5 routes eligible, 1 considered; est $0.00"));
+    }
+
+    #[test]
+    fn routes_use_the_resolved_context_window() {
+        let windows = HashMap::from([("qwen-coder".to_string(), 1_200)]);
+        let mut small = candidate("code", "qwen-coder", 0.5, &["code"]);
+        small.context_window = window_for(&windows, "qwen-coder");
+        assert_eq!(window_for(&windows, "llama3.1"), 128_000);
+
+        let router = AutoRouter::new(
+            vec![candidate(DEFAULT_ROUTE, "claude-sonnet-4", 3.0, &[]), small],
+            None,
+            Arc::new(crate::observability::NoopObserver),
+            None,
+        );
+        let mut code = profile(Some("code"), false);
+        code.tokens = 100;
+        assert_eq!(chosen(&router, &code), 1);
+        code.tokens = 1_000;
+        assert_eq!(chosen(&router, &code), 0);
+    }
+
+    #[test]
+    fn errors_latency_and_budgets_steer_away() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut budgeted = candidate("code-b", "gpt-4o", 1.0, &["code"]);
+        budgeted.daily_budget_usd = Some(0.001);
+        let router = AutoRouter::new(
+            vec![
+                candidate(DEFAULT_ROUTE, "claude-sonnet-4", 3.0, &[]),
+                candidate("code", "qwen-coder", 0.5, &[]),
+                budgeted.clone(),
+            ],
+            None,
+            Arc::new(crate::observability::NoopObserver),
+            Some(tracker(tmp.path(), 100.0)),
+        );
+        let code = profile(Some("code"), false);
+        assert_eq!(chosen(&router, &code), 1);
+
+        for _ in 0..5 {
+            router.record(1, Duration::from_secs(2), false, None);
+        }
+        assert_eq!(chosen(&router, &code), 2);
+
+        let usage = Usage {
+            input_tokens: 1_000,
+            output_tokens: 500,
+            ..Usage::default()
+        };
+        router.record(2, Duration::from_millis(300), true, Some(&usage));
+        assert_eq!(chosen(&router, &code), 1, "budget spent");
+
+        // Spend is persisted, so a rebuilt router still sees it.
+        let reloaded = AutoRouter::new(
+            vec![
+                candidate(DEFAULT_ROUTE, "claude-sonnet-4", 3.0, &[]),
+                budgeted,
+            ],
+            None,
+            Arc::new(crate::observability::NoopObserver),
+            Some(tracker(tmp.path(), 100.0)),
+        );
+        assert_eq!(chosen(&reloaded, &code), 0);
+
+        let huge = RequestProfile {
+            tokens: 150_000,
+            ..profile(Some("code"), false)
+        };
+        assert_eq!(chosen(&router, &huge), 0, "only the default fits");
+    }
+
+    #[test]
+    fn spent_budgets_refuse_instead_of_falling_back() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let cost = tracker(tmp.path(), 0.0015);
+        let mut default = candidate(DEFAULT_ROUTE, "llama3-8b", 0.0, &[]);
+        default.native_tools = false;
+        let mut budgeted = candidate("tools", "gpt-4o", 1.0, &["tools"]);
+        budgeted.daily_budget_usd = Some(0.001);
+        let router = AutoRouter::new(
+            vec![default, budgeted],
+            None,
+            Arc::new(crate::observability::NoopObserver),
+            Some(Arc::clone(&cost)),
+        );
+        let tools = profile(None, true);
+        assert_eq!(chosen(&router, &tools), 1);
+
+        let usage = Usage {
+            input_tokens: 1_000,
+            ..Usage::default()
+        };
+        router.record(1, Duration::from_millis(300), true, Some(&usage));
+        let err = router.choose(&tools).unwrap_err();
+        assert!(err.to_string().contains("spent its daily budget"), "{err}");
+        // Requests the default can serve still go through.
+        assert_eq!(chosen(&router, &profile(None, false)), 0);
+
+        router.record(1, Duration::from_millis(300), true, Some(&usage));
+        let err = router.choose(&profile(None, false)).unwrap_err();
+        assert!(
+            err.to_string().contains("Daily cost limit reached"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Streamed chat replies: server-sent event parsing and the OpenAI-style
+//! `chat.completion.chunk` accumulator shared by the providers that speak it.
+
+use super::prompt_cache::OpenAiUsage;
+use super::traits::{ChatResponse, ToolCall, Usage};
+use futures_util::StreamExt;
+use serde::Deserialize;
+
+/// Receives reply text as it arrives.
+pub type DeltaSink<'a> = dyn Fn(&str) + Send + Sync + 'a;
+
+/// Call `on_event(event, data)` for every server-sent event in `response`.
+/// `event` is empty when the server names none.
+pub(crate) async fn for_each_event(
+    response: reqwest::Response,
+    mut on_event: impl FnMut(&str, &str) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut body = response.bytes_stream();
+    let mut buffer = Vec::new();
+    let mut event = String::new();
+    let mut data = String::new();
+    while let Some(chunk) = body.next().await {
+        buffer.extend_from_slice(&chunk?);
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !data.is_empty() {
+                    on_event(&event, &data)?;
+                }
+                event.clear();
+                data.clear();
+            } else if let Some(value) = line.strip_prefix("event:") {
+                event = value.trim().to_string();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                push_data(&mut data, value);
+            }
+        }
+    }
+    let tail = String::from_utf8_lossy(&buffer);
+    if let Some(value) = tail.trim_end_matches('\r').strip_prefix("data:") {
+        push_data(&mut data, value);
+    }
+    if !data.is_empty() {
+        on_event(&event, &data)?;
+    }
+    Ok(())
+}
+
+fn push_data(data: &mut String, value: &str) {
+    if !data.is_empty() {
+        data.push('\n');
+    }
+    data.push_str(value.strip_prefix(' ').unwrap_or(value));
+}
+
+#[derive(Debug, Deserialize)]
+struct Chunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ChunkToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkToolCall {
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<ChunkFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+/// Builds a [`ChatResponse`] from OpenAI-style streamed chunks. Tool call
+/// fragments are joined by their `index`.
+#[derive(Debug, Default)]
+pub(crate) struct OpenAiStream {
+    text: String,
+    tool_calls: Vec<ToolCall>,
+    usage: Option<Usage>,
+}
+
+impl OpenAiStream {
+    /// Apply one `data:` payload, passing new text to `on_delta`.
+    pub(crate) fn push(&mut self, data: &str, on_delta: &DeltaSink<'_>) -> anyhow::Result<()> {
+        if data.trim() == "[DONE]" {
+            return Ok(());
+        }
+        let value: serde_json::Value = serde_json::from_str(data)?;
+        if let Some(error) = value.get("error") {
+            anyhow::bail!("stream error: {error}");
+        }
+        let chunk: Chunk = serde_json::from_value(value)?;
+        if let Some(usage) = chunk.usage {
+            self.usage = Some(usage.into());
+        }
+        let Some(choice) = chunk.choices.into_iter().next() else {
+            return Ok(());
+        };
+        if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+            on_delta(&text);
+            self.text.push_str(&text);
+        }
+        for fragment in choice.delta.tool_calls {
+            if self.tool_calls.len() <= fragment.index {
+                self.tool_calls
+                    .resize_with(fragment.index + 1, || ToolCall {
+                        id: String::new(),
+                        name: String::new(),
+                        arguments: String::new(),
+                    });
+            }
+            let call = &mut self.tool_calls[fragment.index];
+            if let Some(id) = fragment.id {
+                call.id = id;
+            }
+            if let Some(function) = fragment.function {
+                call.name
+                    .push_str(function.name.as_deref().unwrap_or_default());
+                call.arguments
+                    .push_str(function.arguments.as_deref().unwrap_or_default());
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> ChatResponse {
+        let tool_calls = self
+            .tool_calls
+            .into_iter()
+            .filter(|call| !call.name.is_empty())
+            .map(|call| ToolCall {
+                id: if call.id.is_empty() {
+                    uuid::Uuid::new_v4().to_string()
+                } else {
+                    call.id
+                },
+                name: call.name,
+                arguments: if call.arguments.is_empty() {
+                    "{}".to_string()
+                } else {
+                    call.arguments
+                },
+            })
+            .collect();
+        ChatResponse {
+            text: (!self.text.is_empty()).then_some(self.text),
+            tool_calls,
+            usage: self.usage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[tokio::test]
+    async fn events_are_split_across_chunk_boundaries() {
+        use axum::{body::Body, routing::post, Router};
+
+        let app = Router::new().route(
+            "/",
+            post(|| async {
+                let parts: Vec<Result<&'static str, std::io::Error>> = vec![
+                    Ok("event: message_start\ndata: {\"a\""),
+                    Ok(":1}\n\ndata: one\r\ndata: two\n\n"),
+                    Ok("data: tail"),
+                ];
+                Body::from_stream(futures_util::stream::iter(parts))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}/"))
+            .send()
+            .await
+            .unwrap();
+        let mut events = Vec::new();
+        for_each_event(response, |event, data| {
+            events.push((event.to_string(), data.to_string()));
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            events,
+            [
+                ("message_start".into(), "{\"a\":1}".into()),
+                (String::new(), "one\ntwo".into()),
+                (String::new(), "tail".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn openai_chunks_join_text_tool_calls_and_usage() {
+        let deltas = Mutex::new(Vec::new());
+        let on_delta = |text: &str| deltas.lock().push(text.to_string());
+        let mut stream = OpenAiStream::default();
+        for data in [
+            r#"{"choices":[{"delta":{"content":"Hel"}}]}"#,
+            r#"{"choices":[{"delta":{"content":"lo"}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"c1","function":{"name":"shell","arguments":"{\"comm"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"and\":\"ls\"}"}}]}}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":10,"completion_tokens":4}}"#,
+            "[DONE]",
+        ] {
+            stream.push(data, &on_delta).unwrap();
+        }
+
+        let response = stream.finish();
+        assert_eq!(*deltas.lock(), ["Hel", "lo"]);
+        assert_eq!(response.text.as_deref(), Some("Hello"));
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].id, "c1");
+        assert_eq!(response.tool_calls[0].arguments, r#"{"command":"ls"}"#);
+        assert_eq!(response.usage.unwrap().output_tokens, 4);
+    }
+
+    #[test]
+    fn openai_stream_errors_are_reported() {
+        let mut stream = OpenAiStream::default();
+        let error = stream
+            .push(r#"{"error":{"message":"overloaded"}}"#, &|_| {})
+            .unwrap_err();
+        assert!(error.to_string().contains("overloaded"));
+    }
+}
@@ -0,0 +1,360 @@
+//! Schema-constrained responses: the prompt fallback, JSON extraction and
+//! the validate-and-repair loop behind [`Provider::chat_structured`].
+
+use super::traits::{ChatMessage, ChatRequest, Provider, ResponseSchema};
+use crate::json_schema;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Extra attempts after a reply fails validation.
+pub const MAX_REPAIR_ATTEMPTS: usize = 2;
+
+/// `messages` with the schema and a JSON-only instruction added to the
+/// system prompt, for providers without native structured output.
+pub fn with_schema_prompt(messages: &[ChatMessage], schema: &ResponseSchema) -> Vec<ChatMessage> {
+    let instruction = format!(
+        "Respond with a single JSON value that matches this JSON Schema, and nothing else \
+         (no prose, no code fences):\n{}",
+        schema.schema
+    );
+    let mut out = messages.to_vec();
+    match out.iter_mut().find(|m| m.role == "system") {
+        Some(system) => {
+            system.content.push_str("\n\n");
+            system.content.push_str(&instruction);
+        }
+        None => out.insert(0, ChatMessage::system(instruction)),
+    }
+    out
+}
+
+/// The JSON document in a reply: the whole text, a fenced block, or the
+/// outermost `{...}` / `[...]` span.
+pub fn extract_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    if let Some(start) = text.find("```") {
+        let body = &text[start + 3..];
+        let body = body.split_once('\n').map_or(body, |(_, rest)| rest);
+        if let Some(end) = body.find("```") {
+            if let Ok(value) = serde_json::from_str(body[..end].trim()) {
+                return Some(value);
+            }
+        }
+    }
+    for (open, close) in [('{', '}'), ('[', ']')] {
+        if let (Some(start), Some(end)) = (text.find(open), text.rfind(close)) {
+            if start < end {
+                if let Ok(value) = serde_json::from_str(&text[start..=end]) {
+                    return Some(value);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Why a reply was rejected, phrased for the model.
+fn problems(raw: &str, schema: &ResponseSchema) -> std::result::Result<Value, String> {
+    let value = extract_json(raw).ok_or_else(|| "the reply is not valid JSON".to_string())?;
+    json_schema::validate(&schema.schema, &value).map_err(|errors| {
+        errors
+            .iter()
+            .map(|e| format!("- {e}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    Ok(value)
+}
+
+pub async fn chat_structured<P: Provider + ?Sized>(
+    provider: &P,
+    request: ChatRequest<'_>,
+    schema: &ResponseSchema,
+    model: &str,
+    temperature: f64,
+) -> Result<Value> {
+    let mut messages = request.messages.to_vec();
+    let mut attempt = 0;
+    loop {
+        let raw = provider
+            .chat_with_schema(
+                ChatRequest {
+                    messages: &messages,
+                    tools: None,
+                },
+                schema,
+                model,
+                temperature,
+            )
+            .await?;
+        let problems = match problems(&raw, schema) {
+            Ok(value) => return Ok(value),
+            Err(problems) => problems,
+        };
+        if attempt == MAX_REPAIR_ATTEMPTS {
+            anyhow::bail!(
+                "Response did not match the `{}` schema after {} attempts:\n{problems}",
+                schema.name,
+                attempt + 1
+            );
+        }
+        attempt += 1;
+        tracing::debug!(
+            schema = %schema.name,
+            attempt,
+            "Structured response failed validation, asking for a repair"
+        );
+        messages.push(ChatMessage::assistant(raw));
+        messages.push(ChatMessage::user(format!(
+            "That response does not match the required JSON Schema:\n{problems}\n\
+             Reply again with only the corrected JSON."
+        )));
+    }
+}
+
+/// [`Provider::chat_structured`] for a schemars type, deserialized.
+pub async fn chat_as<T: DeserializeOwned + schemars::JsonSchema>(
+    provider: &dyn Provider,
+    messages: &[ChatMessage],
+    model: &str,
+    temperature: f64,
+) -> Result<T> {
+    let schema = ResponseSchema::for_type::<T>();
+    let value = provider
+        .chat_structured(
+            ChatRequest {
+                messages,
+                tools: None,
+            },
+            &schema,
+            model,
+            temperature,
+        )
+        .await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Convert a JSON Schema to Gemini's OpenAPI subset: `$ref`s inlined,
+/// `["T", "null"]` turned into `nullable`, upper-case type names and
+/// unsupported keywords dropped.
+pub fn to_gemini_schema(schema: &Value) -> Value {
+    convert_gemini(schema, schema, 0)
+}
+
+fn convert_gemini(node: &Value, root: &Value, depth: usize) -> Value {
+    const KEEP: &[&str] = &[
+        "description",
+        "format",
+        "enum",
+        "minimum",
+        "maximum",
+        "minItems",
+        "maxItems",
+        "required",
+        "title",
+    ];
+    let Value::Object(object) = node else {
+        return serde_json::json!({});
+    };
+    if depth > 32 {
+        return serde_json::json!({});
+    }
+    if let Some(target) = object
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| json_schema::resolve_ref(root, r))
+    {
+        return convert_gemini(target, root, depth + 1);
+    }
+
+    let mut out = serde_json::Map::new();
+    let types = json_schema::schema_types(node);
+    if types.contains(&"null") {
+        out.insert("nullable".into(), Value::Bool(true));
+    }
+    if let Some(ty) = types.iter().find(|t| **t != "null") {
+        out.insert("type".into(), Value::String(ty.to_ascii_uppercase()));
+    }
+    for key in KEEP {
+        if let Some(value) = object.get(*key) {
+            out.insert((*key).to_string(), value.clone());
+        }
+    }
+    if let Some(properties) = object.get("properties").and_then(Value::as_object) {
+        let converted = properties
+            .iter()
+            .map(|(k, v)| (k.clone(), convert_gemini(v, root, depth + 1)))
+            .collect();
+        out.insert("properties".into(), Value::Object(converted));
+    }
+    if let Some(items) = object.get("items") {
+        out.insert("items".into(), convert_gemini(items, root, depth + 1));
+    }
+    if let Some(branches) = object
+        .get("anyOf")
+        .or_else(|| object.get("oneOf"))
+        .and_then(Value::as_array)
+    {
+        let non_null: Vec<&Value> = branches
+            .iter()
+            .filter(|b| b.get("type").and_then(Value::as_str) != Some("null"))
+            .collect();
+        if non_null.len() < branches.len() {
+            out.insert("nullable".into(), Value::Bool(true));
+        }
+        if let [only] = non_null.as_slice() {
+            if let Value::Object(inner) = convert_gemini(only, root, depth + 1) {
+                for (k, v) in inner {
+                    out.entry(k).or_insert(v);
+                }
+            }
+        } else {
+            out.insert(
+                "anyOf".into(),
+                non_null
+                    .iter()
+                    .map(|b| convert_gemini(b, root, depth + 1))
+                    .collect(),
+            );
+        }
+    }
+    Value::Object(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use serde_json::json;
+
+    /// Replies from a script and records what it was sent.
+    struct Scripted {
+        replies: Mutex<Vec<&'static str>>,
+        seen: Mutex<Vec<Vec<ChatMessage>>>,
+    }
+
+    #[async_trait]
+    impl Provider for Scripted {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            unreachable!()
+        }
+
+        async fn chat_with_history(
+            &self,
+            messages: &[ChatMessage],
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            self.seen.lock().push(messages.to_vec());
+            Ok(self.replies.lock().remove(0).to_string())
+        }
+    }
+
+    fn scripted(replies: Vec<&'static str>) -> Scripted {
+        Scripted {
+            replies: Mutex::new(replies),
+            seen: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn verdict_schema() -> ResponseSchema {
+        ResponseSchema::new(
+            "verdict",
+            json!({
+                "type": "object",
+                "properties": {"pass": {"type": "boolean"}, "reason": {"type": "string"}},
+                "required": ["pass", "reason"]
+            }),
+        )
+    }
+
+    fn request(messages: &[ChatMessage]) -> ChatRequest<'_> {
+        ChatRequest {
+            messages,
+            tools: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_replies_are_repaired_with_the_errors() {
+        let provider = scripted(vec![
+            "Sure! {\"pass\": \"yes\"}",
+            "```json\n{\"pass\": true, \"reason\": \"fine\"}\n```",
+        ]);
+        let messages = [ChatMessage::user("Grade this")];
+        let value = provider
+            .chat_structured(request(&messages), &verdict_schema(), "m", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(value, json!({"pass": true, "reason": "fine"}));
+
+        let seen = provider.seen.lock();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0][0].role, "system");
+        assert!(seen[0][0]
+            .content
+            .contains("\"required\":[\"pass\",\"reason\"]"));
+        let repair = &seen[1].last().unwrap().content;
+        assert!(repair.contains("missing required property `reason`"));
+        assert!(repair.contains("/pass: expected boolean, got string"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_repair_budget() {
+        let provider = scripted(vec!["nope", "still nope", "no", "unused"]);
+        let messages = [ChatMessage::user("Grade this")];
+        let err = provider
+            .chat_structured(request(&messages), &verdict_schema(), "m", 0.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("after 3 attempts"));
+        assert_eq!(provider.replies.lock().len(), 1);
+    }
+
+    #[test]
+    fn extract_json_finds_the_document() {
+        assert_eq!(extract_json(" [1, 2] "), Some(json!([1, 2])));
+        assert_eq!(
+            extract_json("Here you go: {\"a\": {\"b\": 1}} hope that helps"),
+            Some(json!({"a": {"b": 1}}))
+        );
+        assert_eq!(extract_json("no json here"), None);
+    }
+
+    #[test]
+    fn gemini_schema_inlines_refs_and_nullable() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Step {
+            title: String,
+            note: Option<String>,
+        }
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Plan {
+            steps: Vec<Step>,
+        }
+
+        let schema = ResponseSchema::for_type::<Plan>();
+        assert_eq!(schema.name, "Plan");
+        let gemini = to_gemini_schema(&schema.schema);
+        assert_eq!(gemini["type"], "OBJECT");
+        let step = &gemini["properties"]["steps"]["items"];
+        assert_eq!(step["type"], "OBJECT");
+        assert_eq!(step["properties"]["note"]["type"], "STRING");
+        assert_eq!(step["properties"]["note"]["nullable"], true);
+        assert!(gemini.get("$defs").is_none());
+        assert!(gemini.get("$schema").is_none());
+    }
+}
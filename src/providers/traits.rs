@@ -1,3 +1,4 @@
+use super::streaming::DeltaSink;
 use crate::tools::ToolSpec;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -47,6 +48,19 @@ pub struct ToolCall {
     pub arguments: String,
 }
 
+/// Token counts a provider reported for one call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Prompt tokens billed at the full input price (cache reads and
+    /// writes excluded).
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Prompt tokens served from the provider's prompt cache.
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache.
+    pub cache_write_tokens: u64,
+}
+
 /// An LLM response that may contain text, tool calls, or both.
 #[derive(Debug, Clone)]
 pub struct ChatResponse {
@@ -54,6 +68,8 @@ pub struct ChatResponse {
     pub text: Option<String>,
     /// Tool calls requested by the LLM.
     pub tool_calls: Vec<ToolCall>,
+    /// Token usage, when the provider reports it.
+    pub usage: Option<Usage>,
 }
 
 impl ChatResponse {
@@ -75,6 +91,50 @@ pub struct ChatRequest<'a> {
     pub tools: Option<&'a [ToolSpec]>,
 }
 
+/// JSON Schema a structured response must satisfy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseSchema {
+    /// Identifier sent to the provider (OpenAI schema name, Anthropic tool
+    /// name); limited to `[A-Za-z0-9_-]`.
+    pub name: String,
+    pub schema: serde_json::Value,
+    /// Ask OpenAI for strict mode. The schema must then close every object
+    /// (`additionalProperties: false`) and require every property.
+    pub strict: bool,
+}
+
+impl ResponseSchema {
+    pub fn new(name: &str, schema: serde_json::Value) -> Self {
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(64)
+            .collect();
+        Self {
+            name: if name.is_empty() {
+                "response".into()
+            } else {
+                name
+            },
+            schema,
+            strict: false,
+        }
+    }
+
+    /// The schemars schema for `T`, named after the type.
+    pub fn for_type<T: schemars::JsonSchema>() -> Self {
+        let schema = serde_json::to_value(schemars::schema_for!(T))
+            .unwrap_or_else(|_| serde_json::json!({}));
+        Self::new(&T::schema_name(), schema)
+    }
+}
+
 /// A tool result to feed back to the LLM.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResultMessage {
@@ -157,18 +217,67 @@ pub trait Provider: Send + Sync {
         Ok(ChatResponse {
             text: Some(text),
             tool_calls: Vec::new(),
+            usage: None,
         })
     }
 
+    /// [`Provider::chat`] that hands reply text to `on_delta` as it
+    /// arrives. The default does not stream: the whole reply is one delta.
+    async fn chat_streaming(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+        on_delta: &DeltaSink<'_>,
+    ) -> anyhow::Result<ChatResponse> {
+        let response = self.chat(request, model, temperature).await?;
+        if let Some(text) = response.text.as_deref().filter(|text| !text.is_empty()) {
+            on_delta(text);
+        }
+        Ok(response)
+    }
+
+    /// One attempt at a reply constrained to `schema`, as raw text.
+    ///
+    /// The default describes the schema in the system prompt; providers
+    /// with native support map it onto their API instead. `request.tools`
+    /// is ignored. Callers want [`Provider::chat_structured`].
+    async fn chat_with_schema(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let messages = super::structured::with_schema_prompt(request.messages, schema);
+        self.chat_with_history(&messages, model, temperature).await
+    }
+
+    /// Chat for a JSON value that matches `schema`. Replies are validated
+    /// locally; invalid ones are sent back with the validation errors for
+    /// up to [`MAX_REPAIR_ATTEMPTS`](super::structured::MAX_REPAIR_ATTEMPTS)
+    /// more tries.
+    async fn chat_structured(
+        &self,
+        request: ChatRequest<'_>,
+        schema: &ResponseSchema,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        super::structured::chat_structured(self, request, schema, model, temperature).await
+    }
+
     /// Whether provider supports native tool calls over API.
     fn supports_native_tools(&self) -> bool {
         false
     }
 
     /// Warm up the HTTP connection pool (TLS handshake, DNS, HTTP/2 setup).
-    /// Default implementation is a no-op; providers with HTTP clients should override.
-    async fn warmup(&self) -> anyhow::Result<()> {
-        Ok(())
+    /// Returns `Ok(true)` only when the provider was actually contacted, so
+    /// health probes can tell a real check from a no-op. Default implementation
+    /// is a no-op; providers with HTTP clients should override.
+    async fn warmup(&self) -> anyhow::Result<bool> {
+        Ok(false)
     }
 }
 
@@ -197,6 +306,7 @@ mod tests {
         let empty = ChatResponse {
             text: None,
             tool_calls: vec![],
+            usage: None,
         };
         assert!(!empty.has_tool_calls());
         assert_eq!(empty.text_or_empty(), "");
@@ -208,6 +318,7 @@ mod tests {
                 name: "shell".into(),
                 arguments: "{}".into(),
             }],
+            usage: None,
         };
         assert!(with_tools.has_tool_calls());
         assert_eq!(with_tools.text_or_empty(), "Let me check");
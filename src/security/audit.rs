@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Mutex, PoisonError};
 use uuid::Uuid;
 
 /// Audit event types
@@ -173,23 +173,60 @@ impl AuditLogger {
         })
     }
 
-    /// Log an event
+    /// Log an event. If the write fails the event stays buffered and is
+    /// retried on the next `log` or [`flush`](Self::flush).
     pub fn log(&self, event: &AuditEvent) -> Result<()> {
         if !self.config.enabled {
             return Ok(());
         }
 
+        let mut pending = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        pending.push(event.clone());
+        self.write_pending(&mut pending)
+    }
+
+    /// Write out any buffered events (called on daemon shutdown).
+    pub fn flush(&self) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let mut pending = self.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.write_pending(&mut pending)
+    }
+
+    /// Number of events waiting to be written.
+    pub fn pending(&self) -> usize {
+        self.buffer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn write_pending(&self, pending: &mut Vec<AuditEvent>) -> Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+
         // Check log size and rotate if needed
         self.rotate_if_needed()?;
 
-        // Serialize and write
-        let line = serde_json::to_string(event)?;
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&self.log_path)?;
 
-        writeln!(file, "{}", line)?;
+        // Only drop the events that were written, so a failure part-way
+        // leaves the rest for the next attempt.
+        let mut written = 0;
+        let result = pending.iter().try_for_each(|event| -> Result<()> {
+            let line = serde_json::to_string(event)?;
+            writeln!(file, "{}", line)?;
+            written += 1;
+            Ok(())
+        });
+        pending.drain(..written);
+        result?;
         file.sync_all()?;
 
         Ok(())
@@ -332,4 +369,23 @@ mod tests {
         assert!(!tmp.path().join("audit.log").exists());
         Ok(())
     }
+
+    #[test]
+    fn failed_writes_stay_buffered_until_flush() -> Result<()> {
+        let tmp = TempDir::new()?;
+        let dir = tmp.path().join("not-yet-created");
+        let logger = AuditLogger::new(AuditConfig::default(), dir.clone())?;
+
+        assert!(logger
+            .log(&AuditEvent::new(AuditEventType::SecurityEvent))
+            .is_err());
+        assert_eq!(logger.pending(), 1);
+
+        std::fs::create_dir_all(&dir)?;
+        logger.flush()?;
+        assert_eq!(logger.pending(), 0);
+        let written = std::fs::read_to_string(dir.join("audit.log"))?;
+        assert_eq!(written.lines().count(), 1);
+        Ok(())
+    }
 }
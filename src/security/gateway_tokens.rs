@@ -0,0 +1,631 @@
+// Gateway bearer tokens — scoped, expiring and revocable.
+//
+// Tokens live in `gateway_tokens.json` next to `config.toml`, keyed by the
+// SHA-256 hash of the plaintext (the plaintext is shown once, on creation).
+// Each token carries a label, creation time, optional expiry, last-used time
+// and a set of scopes that gateway routes check individually.
+//
+// Legacy `gateway.paired_tokens` entries are imported once as admin tokens;
+// after that the config list is ignored, so revoking them sticks.
+
+use super::pairing::{constant_time_eq, generate_token, hash_token, is_token_hash};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Instant, SystemTime};
+
+/// File name of the token store inside the config directory.
+pub const TOKENS_FILE: &str = "gateway_tokens.json";
+/// Minimum interval between `last_used_at` writes to disk.
+const LAST_USED_FLUSH_SECS: u64 = 60;
+
+/// What a token is allowed to do. `admin` implies every other scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// `POST /webhook`
+    Webhook,
+    /// WebChat (`/chat/ws`)
+    Chat,
+    /// `GET /metrics`
+    Metrics,
+    /// `zeroclaw mcp serve --transport http`
+    Mcp,
+    /// Everything, including `/admin/*`
+    Admin,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 5] = [
+        Self::Webhook,
+        Self::Chat,
+        Self::Metrics,
+        Self::Mcp,
+        Self::Admin,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Webhook => "webhook",
+            Self::Chat => "chat",
+            Self::Metrics => "metrics",
+            Self::Mcp => "mcp",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown token scope '{s}' (expected webhook, chat, metrics, mcp or admin)"
+                )
+            })
+    }
+}
+
+/// Scopes granted to tokens issued through the one-time pairing code.
+pub const PAIRING_SCOPES: [TokenScope; 2] = [TokenScope::Webhook, TokenScope::Chat];
+
+/// Metadata for one issued token. Only the hash of the secret is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewayToken {
+    pub id: String,
+    pub label: String,
+    pub hash: String,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl GatewayToken {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    pub fn allows(&self, scope: TokenScope) -> bool {
+        self.scopes
+            .iter()
+            .any(|s| *s == scope || *s == TokenScope::Admin)
+    }
+}
+
+/// Why a bearer token was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    Invalid,
+    Expired,
+    MissingScope(TokenScope),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid => f.write_str("invalid bearer token"),
+            Self::Expired => f.write_str("bearer token has expired"),
+            Self::MissingScope(scope) => write!(f, "bearer token lacks the '{scope}' scope"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TokenFile {
+    #[serde(default)]
+    tokens: Vec<GatewayToken>,
+    /// Hashes of `gateway.paired_tokens` entries already imported.
+    #[serde(default)]
+    imported_legacy: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct StoreState {
+    file: TokenFile,
+    /// mtime of the file when last read/written, to pick up CLI edits.
+    loaded_mtime: Option<SystemTime>,
+    last_flush: Option<Instant>,
+    dirty: bool,
+}
+
+/// Token registry shared by the gateway and the `zeroclaw gateway tokens` CLI.
+#[derive(Debug)]
+pub struct TokenStore {
+    path: Option<PathBuf>,
+    state: Mutex<StoreState>,
+}
+
+impl TokenStore {
+    /// In-memory store (nothing persisted), seeded with legacy tokens.
+    pub fn in_memory(legacy_tokens: &[String]) -> Self {
+        let store = Self {
+            path: None,
+            state: Mutex::new(StoreState::default()),
+        };
+        store.import_legacy(&mut store.state.lock(), legacy_tokens);
+        store
+    }
+
+    /// Open (or create) the store at `path`, importing legacy tokens once.
+    pub fn open(path: &Path, legacy_tokens: &[String]) -> Result<Self> {
+        let store = Self {
+            path: Some(path.to_path_buf()),
+            state: Mutex::new(StoreState::default()),
+        };
+        {
+            let mut state = store.state.lock();
+            store.reload(&mut state)?;
+            if store.import_legacy(&mut state, legacy_tokens) {
+                store.persist(&mut state)?;
+            }
+        }
+        Ok(store)
+    }
+
+    /// Store for a loaded config: `<config dir>/gateway_tokens.json`.
+    pub fn for_config(config: &crate::config::Config) -> Result<Self> {
+        let dir = config
+            .config_path
+            .parent()
+            .context("Config path has no parent directory")?;
+        Self::open(&dir.join(TOKENS_FILE), &config.gateway.paired_tokens)
+    }
+
+    fn import_legacy(&self, state: &mut StoreState, legacy_tokens: &[String]) -> bool {
+        let mut changed = false;
+        for raw in legacy_tokens {
+            let hash = if is_token_hash(raw) {
+                raw.to_ascii_lowercase()
+            } else {
+                hash_token(raw)
+            };
+            if state.file.imported_legacy.contains(&hash) {
+                continue;
+            }
+            if !state.file.tokens.iter().any(|t| t.hash == hash) {
+                state.file.tokens.push(GatewayToken {
+                    id: format!("legacy-{}", &hash[..8]),
+                    label: "legacy (gateway.paired_tokens)".into(),
+                    hash: hash.clone(),
+                    scopes: vec![TokenScope::Admin],
+                    created_at: Utc::now(),
+                    expires_at: None,
+                    last_used_at: None,
+                });
+            }
+            state.file.imported_legacy.push(hash);
+            changed = true;
+        }
+        changed
+    }
+
+    fn file_mtime(&self) -> Option<SystemTime> {
+        let path = self.path.as_ref()?;
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    fn reload(&self, state: &mut StoreState) -> Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if !path.exists() {
+            return Ok(());
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        state.file = serde_json::from_str(&raw)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        state.loaded_mtime = self.file_mtime();
+        state.dirty = false;
+        Ok(())
+    }
+
+    /// Re-read the file when another process (the CLI) changed it.
+    fn refresh(&self, state: &mut StoreState) {
+        if self.path.is_none() || self.file_mtime() == state.loaded_mtime {
+            return;
+        }
+        let last_used: Vec<(String, DateTime<Utc>)> = state
+            .file
+            .tokens
+            .iter()
+            .filter_map(|t| Some((t.hash.clone(), t.last_used_at?)))
+            .collect();
+        if let Err(e) = self.reload(state) {
+            tracing::warn!("Gateway tokens: keeping previous state, reload failed: {e}");
+            return;
+        }
+        // Keep in-memory last-used times that were not flushed yet.
+        for (hash, used) in last_used {
+            if let Some(token) = state.file.tokens.iter_mut().find(|t| t.hash == hash) {
+                if token.last_used_at.is_none_or(|at| at < used) {
+                    token.last_used_at = Some(used);
+                }
+            }
+        }
+    }
+
+    fn persist(&self, state: &mut StoreState) -> Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let bytes = serde_json::to_vec_pretty(&state.file)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, bytes)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, path)?;
+        state.loaded_mtime = self.file_mtime();
+        state.last_flush = Some(Instant::now());
+        state.dirty = false;
+        Ok(())
+    }
+
+    /// Issue a new token. Returns its metadata and the plaintext secret.
+    pub fn create(
+        &self,
+        label: &str,
+        scopes: &[TokenScope],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(GatewayToken, String)> {
+        if scopes.is_empty() {
+            bail!("A token needs at least one scope");
+        }
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        let plaintext = generate_token();
+        let token = GatewayToken {
+            id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            label: label.trim().to_string(),
+            hash: hash_token(&plaintext),
+            scopes,
+            created_at: Utc::now(),
+            expires_at,
+            last_used_at: None,
+        };
+        state.file.tokens.push(token.clone());
+        self.persist(&mut state)?;
+        Ok((token, plaintext))
+    }
+
+    fn position(state: &StoreState, id_or_label: &str) -> Result<usize> {
+        let needle = id_or_label.trim();
+        if let Some(pos) = state.file.tokens.iter().position(|t| t.id == needle) {
+            return Ok(pos);
+        }
+        let matches: Vec<usize> = state
+            .file
+            .tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.label == needle)
+            .map(|(i, _)| i)
+            .collect();
+        match matches.as_slice() {
+            [pos] => Ok(*pos),
+            [] => bail!("No gateway token with id or label '{needle}'"),
+            _ => bail!("Label '{needle}' matches several tokens; use the token id"),
+        }
+    }
+
+    /// Revoke a token by id (or unique label).
+    pub fn revoke(&self, id_or_label: &str) -> Result<GatewayToken> {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        let pos = Self::position(&state, id_or_label)?;
+        let token = state.file.tokens.remove(pos);
+        self.persist(&mut state)?;
+        Ok(token)
+    }
+
+    /// Replace a token's secret, keeping its id, label, scopes and lifetime.
+    pub fn rotate(&self, id_or_label: &str) -> Result<(GatewayToken, String)> {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        let pos = Self::position(&state, id_or_label)?;
+        let plaintext = generate_token();
+        let now = Utc::now();
+        let token = &mut state.file.tokens[pos];
+        token.expires_at = token
+            .expires_at
+            .map(|at| now + (at - token.created_at).max(Duration::zero()));
+        token.hash = hash_token(&plaintext);
+        token.created_at = now;
+        token.last_used_at = None;
+        let token = token.clone();
+        self.persist(&mut state)?;
+        Ok((token, plaintext))
+    }
+
+    /// All tokens, oldest first.
+    pub fn list(&self) -> Vec<GatewayToken> {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        state.file.tokens.clone()
+    }
+
+    /// Whether at least one unexpired token exists.
+    pub fn has_active(&self) -> bool {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        let now = Utc::now();
+        state.file.tokens.iter().any(|t| !t.is_expired(now))
+    }
+
+    /// SHA-256 hashes of all tokens.
+    pub fn hashes(&self) -> Vec<String> {
+        self.list().into_iter().map(|t| t.hash).collect()
+    }
+
+    /// Check a presented bearer token, optionally for a specific scope, and
+    /// record its use.
+    pub fn verify(
+        &self,
+        token: &str,
+        scope: Option<TokenScope>,
+    ) -> Result<GatewayToken, TokenError> {
+        let hashed = hash_token(token.trim());
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+
+        // Compare against every entry so timing doesn't reveal the position.
+        let mut found = None;
+        for (i, t) in state.file.tokens.iter().enumerate() {
+            if constant_time_eq(&t.hash, &hashed) {
+                found = Some(i);
+            }
+        }
+        let pos = found.ok_or(TokenError::Invalid)?;
+
+        let now = Utc::now();
+        let entry = &mut state.file.tokens[pos];
+        if entry.is_expired(now) {
+            return Err(TokenError::Expired);
+        }
+        if let Some(scope) = scope {
+            if !entry.allows(scope) {
+                return Err(TokenError::MissingScope(scope));
+            }
+        }
+        let first_use = entry.last_used_at.is_none();
+        entry.last_used_at = Some(now);
+        let entry = entry.clone();
+        state.dirty = true;
+
+        let due = first_use
+            || state
+                .last_flush
+                .is_none_or(|at| at.elapsed().as_secs() >= LAST_USED_FLUSH_SECS);
+        if due {
+            if let Err(e) = self.persist(&mut state) {
+                tracing::warn!("Gateway tokens: failed to record last use: {e}");
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Insert a token issued through pairing.
+    pub(crate) fn add_paired(&self, plaintext: &str) {
+        let mut state = self.state.lock();
+        self.refresh(&mut state);
+        state.file.tokens.push(GatewayToken {
+            id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            label: "paired client".into(),
+            hash: hash_token(plaintext),
+            scopes: PAIRING_SCOPES.to_vec(),
+            created_at: Utc::now(),
+            expires_at: None,
+            last_used_at: None,
+        });
+        if let Err(e) = self.persist(&mut state) {
+            tracing::warn!("Gateway tokens: failed to persist paired token: {e}");
+        }
+    }
+
+    /// Write pending `last_used_at` updates.
+    pub fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.dirty {
+            self.persist(&mut state)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a lifetime such as `90m`, `12h`, `30d` or `2w`.
+pub fn parse_ttl(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (digits, unit) = raw.split_at(split);
+    let amount: i64 = digits
+        .parse()
+        .with_context(|| format!("Invalid duration '{raw}' (examples: 90m, 12h, 30d, 2w)"))?;
+    if amount <= 0 {
+        bail!("Duration must be positive: '{raw}'");
+    }
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" | "" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => bail!("Unknown duration unit in '{raw}' (use s, m, h, d or w)"),
+    };
+    duration.with_context(|| format!("Duration out of range: '{raw}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_in(dir: &Path) -> TokenStore {
+        TokenStore::open(&dir.join(TOKENS_FILE), &[]).unwrap()
+    }
+
+    #[test]
+    fn create_then_verify_with_scopes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = store_in(tmp.path());
+        let (meta, secret) = store.create("ci", &[TokenScope::Webhook], None).unwrap();
+        assert!(secret.starts_with("zc_"));
+        assert_eq!(meta.scopes, vec![TokenScope::Webhook]);
+
+        assert!(store.verify(&secret, Some(TokenScope::Webhook)).is_ok());
+        assert_eq!(
+            store
+                .verify(&secret, Some(TokenScope::Metrics))
+                .unwrap_err(),
+            TokenError::MissingScope(TokenScope::Metrics)
+        );
+        assert_eq!(
+            store.verify("zc_nope", None).unwrap_err(),
+            TokenError::Invalid
+        );
+    }
+
+    #[test]
+    fn admin_scope_implies_everything() {
+        let store = TokenStore::in_memory(&[]);
+        let (_, secret) = store.create("ops", &[TokenScope::Admin], None).unwrap();
+        for scope in TokenScope::ALL {
+            assert!(store.verify(&secret, Some(scope)).is_ok());
+        }
+    }
+
+    #[test]
+    fn expired_tokens_are_rejected() {
+        let store = TokenStore::in_memory(&[]);
+        let past = Utc::now() - Duration::minutes(1);
+        let (_, secret) = store
+            .create("old", &[TokenScope::Webhook], Some(past))
+            .unwrap();
+        assert_eq!(
+            store.verify(&secret, None).unwrap_err(),
+            TokenError::Expired
+        );
+        assert!(!store.has_active());
+    }
+
+    #[test]
+    fn verify_records_last_use() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = store_in(tmp.path());
+        let (_, secret) = store.create("bot", &[TokenScope::Chat], None).unwrap();
+        store.verify(&secret, Some(TokenScope::Chat)).unwrap();
+
+        let reopened = store_in(tmp.path());
+        assert!(reopened.list()[0].last_used_at.is_some());
+    }
+
+    #[test]
+    fn revoke_is_seen_by_running_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let gateway = store_in(tmp.path());
+        let (meta, secret) = gateway
+            .create("leaked", &[TokenScope::Webhook], None)
+            .unwrap();
+        assert!(gateway.verify(&secret, None).is_ok());
+
+        // The CLI works on its own handle to the same file.
+        let cli = store_in(tmp.path());
+        // Make sure the mtime moves even on coarse-grained filesystems.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cli.revoke(&meta.id).unwrap();
+
+        assert_eq!(
+            gateway.verify(&secret, None).unwrap_err(),
+            TokenError::Invalid
+        );
+    }
+
+    #[test]
+    fn rotate_replaces_secret_and_keeps_metadata() {
+        let store = TokenStore::in_memory(&[]);
+        let expiry = Utc::now() + Duration::days(30);
+        let (meta, old) = store
+            .create("grafana", &[TokenScope::Metrics], Some(expiry))
+            .unwrap();
+        let (rotated, new) = store.rotate("grafana").unwrap();
+
+        assert_eq!(rotated.id, meta.id);
+        assert_eq!(rotated.scopes, meta.scopes);
+        assert!(rotated.expires_at.unwrap() >= expiry);
+        assert_eq!(store.verify(&old, None).unwrap_err(), TokenError::Invalid);
+        assert!(store.verify(&new, Some(TokenScope::Metrics)).is_ok());
+    }
+
+    #[test]
+    fn ambiguous_labels_require_id() {
+        let store = TokenStore::in_memory(&[]);
+        store.create("dup", &[TokenScope::Webhook], None).unwrap();
+        store.create("dup", &[TokenScope::Webhook], None).unwrap();
+        let err = store.revoke("dup").unwrap_err();
+        assert!(err.to_string().contains("several tokens"));
+    }
+
+    #[test]
+    fn legacy_tokens_import_once_as_admin() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(TOKENS_FILE);
+        let legacy = vec!["zc_legacy".to_string()];
+
+        let store = TokenStore::open(&path, &legacy).unwrap();
+        let tokens = store.list();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].scopes, vec![TokenScope::Admin]);
+        assert!(store.verify("zc_legacy", Some(TokenScope::Admin)).is_ok());
+
+        store.revoke(&tokens[0].id).unwrap();
+        // Still listed in config, but must not come back.
+        let reopened = TokenStore::open(&path, &legacy).unwrap();
+        assert!(reopened.list().is_empty());
+    }
+
+    #[test]
+    fn scopes_parse_case_insensitively() {
+        assert_eq!(
+            "Webhook".parse::<TokenScope>().unwrap(),
+            TokenScope::Webhook
+        );
+        assert!("root".parse::<TokenScope>().is_err());
+    }
+
+    #[test]
+    fn ttl_parsing() {
+        assert_eq!(parse_ttl("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_ttl("30d").unwrap(), Duration::days(30));
+        assert_eq!(parse_ttl("12").unwrap(), Duration::hours(12));
+        assert!(parse_ttl("0d").is_err());
+        assert!(parse_ttl("5y").is_err());
+        assert!(parse_ttl("soon").is_err());
+    }
+}
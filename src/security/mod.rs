@@ -5,11 +5,14 @@ pub mod detect;
 pub mod docker;
 #[cfg(target_os = "linux")]
 pub mod firejail;
+pub mod gateway_tokens;
 #[cfg(feature = "sandbox-landlock")]
 pub mod landlock;
 pub mod pairing;
 pub mod policy;
+pub mod secret_sources;
 pub mod secrets;
+pub mod secrets_cli;
 pub mod traits;
 
 #[allow(unused_imports)]
@@ -5,12 +5,12 @@
 // header on a `POST /pair` request. The server responds with a bearer token
 // that must be sent on all subsequent requests via `Authorization: Bearer <token>`.
 //
-// Already-paired tokens are persisted in config so restarts don't require
-// re-pairing.
+// Paired tokens are persisted in the gateway token store (see
+// `gateway_tokens`) so restarts don't require re-pairing.
 
+use super::gateway_tokens::{TokenError, TokenScope, TokenStore};
 use sha2::{Digest, Sha256};
 use parking_lot::Mutex;
-use std::collections::HashSet;
 use std::time::Instant;
 
 /// Maximum failed pairing attempts before lockout.
@@ -29,8 +29,8 @@ pub struct PairingGuard {
     require_pairing: bool,
     /// One-time pairing code (generated on startup, consumed on first pair).
     pairing_code: Mutex<Option<String>>,
-    /// Set of SHA-256 hashed bearer tokens (persisted across restarts).
-    paired_tokens: Mutex<HashSet<String>>,
+    /// Scoped bearer tokens, stored as SHA-256 hashes.
+    tokens: TokenStore,
     /// Brute-force protection: failed attempt counter + lockout time.
     failed_attempts: Mutex<(u32, Option<Instant>)>,
 }
@@ -45,17 +45,12 @@ impl PairingGuard {
     /// - Plaintext (`zc_...`): hashed on load for backward compatibility
     /// - Already hashed (64-char hex): stored as-is
     pub fn new(require_pairing: bool, existing_tokens: &[String]) -> Self {
-        let tokens: HashSet<String> = existing_tokens
-            .iter()
-            .map(|t| {
-                if is_token_hash(t) {
-                    t.clone()
-                } else {
-                    hash_token(t)
-                }
-            })
-            .collect();
-        let code = if require_pairing && tokens.is_empty() {
+        Self::with_store(require_pairing, TokenStore::in_memory(existing_tokens))
+    }
+
+    /// Create a pairing guard backed by a (usually persistent) token store.
+    pub fn with_store(require_pairing: bool, tokens: TokenStore) -> Self {
+        let code = if require_pairing && !tokens.has_active() {
             Some(generate_code())
         } else {
             None
@@ -63,7 +58,7 @@ impl PairingGuard {
         Self {
             require_pairing,
             pairing_code: Mutex::new(code),
-            paired_tokens: Mutex::new(tokens),
+            tokens,
             failed_attempts: Mutex::new((0, None)),
         }
     }
@@ -115,11 +110,7 @@ impl PairingGuard {
                         *attempts = (0, None);
                     }
                     let token = generate_token();
-                    let mut tokens = self
-                        .paired_tokens
-                        .lock()
-                        ;
-                    tokens.insert(hash_token(&token));
+                    self.tokens.add_paired(&token);
 
                     // Consume the pairing code so it cannot be reused
                     *pairing_code = None;
@@ -144,35 +135,37 @@ impl PairingGuard {
         Ok(None)
     }
 
-    /// Check if a bearer token is valid (compares against stored hashes).
+    /// Check if a bearer token is valid for any scope.
     pub fn is_authenticated(&self, token: &str) -> bool {
         if !self.require_pairing {
             return true;
         }
-        let hashed = hash_token(token);
-        let tokens = self
-            .paired_tokens
-            .lock()
-            ;
-        tokens.contains(&hashed)
+        self.tokens.verify(token, None).is_ok()
     }
 
-    /// Returns true if the gateway is already paired (has at least one token).
+    /// Check a bearer token for a specific route scope. Turning pairing off
+    /// only opens the agent routes; `admin` and `metrics` always need a token
+    /// carrying that scope (see `zeroclaw gateway tokens create`).
+    pub fn authorize(&self, token: &str, scope: TokenScope) -> Result<(), TokenError> {
+        if !self.require_pairing && !matches!(scope, TokenScope::Admin | TokenScope::Metrics) {
+            return Ok(());
+        }
+        self.tokens.verify(token, Some(scope)).map(|_| ())
+    }
+
+    /// Returns true if the gateway is already paired (has an unexpired token).
     pub fn is_paired(&self) -> bool {
-        let tokens = self
-            .paired_tokens
-            .lock()
-            ;
-        !tokens.is_empty()
+        self.tokens.has_active()
     }
 
-    /// Get all paired token hashes (for persisting to config).
+    /// Get all paired token hashes.
     pub fn tokens(&self) -> Vec<String> {
-        let tokens = self
-            .paired_tokens
-            .lock()
-            ;
-        tokens.iter().cloned().collect()
+        self.tokens.hashes()
+    }
+
+    /// The underlying token store.
+    pub fn token_store(&self) -> &TokenStore {
+        &self.tokens
     }
 }
 
@@ -206,7 +199,7 @@ fn generate_code() -> String {
 /// (/dev/urandom on Linux, BCryptGenRandom on Windows, SecRandomCopyBytes
 /// on macOS). The 32 random bytes (256 bits) are hex-encoded for a
 /// 64-character token, providing 256 bits of entropy.
-fn generate_token() -> String {
+pub(crate) fn generate_token() -> String {
     use rand::RngCore;
     let mut bytes = [0u8; 32];
     rand::thread_rng().fill_bytes(&mut bytes);
@@ -214,13 +207,13 @@ fn generate_token() -> String {
 }
 
 /// SHA-256 hash a bearer token for storage. Returns lowercase hex.
-fn hash_token(token: &str) -> String {
+pub(crate) fn hash_token(token: &str) -> String {
     format!("{:x}", Sha256::digest(token.as_bytes()))
 }
 
 /// Check if a stored value looks like a SHA-256 hash (64 hex chars)
 /// rather than a plaintext token.
-fn is_token_hash(value: &str) -> bool {
+pub(crate) fn is_token_hash(value: &str) -> bool {
     value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
 }
 
@@ -347,6 +340,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn paired_tokens_get_webhook_and_chat_scopes() {
+        let guard = PairingGuard::new(true, &[]);
+        let code = guard.pairing_code().unwrap().to_string();
+        let token = guard.try_pair(&code).unwrap().unwrap();
+        assert!(guard.authorize(&token, TokenScope::Webhook).is_ok());
+        assert!(guard.authorize(&token, TokenScope::Chat).is_ok());
+        assert_eq!(
+            guard.authorize(&token, TokenScope::Admin),
+            Err(TokenError::MissingScope(TokenScope::Admin))
+        );
+    }
+
+    #[test]
+    fn pairing_disabled_still_requires_admin_and_metrics_tokens() {
+        let store = TokenStore::in_memory(&[]);
+        let (_, metrics) = store
+            .create("grafana", &[TokenScope::Metrics], None)
+            .unwrap();
+        let guard = PairingGuard::with_store(false, store);
+
+        assert!(guard.authorize("", TokenScope::Webhook).is_ok());
+        assert!(guard.authorize("", TokenScope::Chat).is_ok());
+        assert!(guard.authorize("", TokenScope::Admin).is_err());
+        assert!(guard.authorize("", TokenScope::Metrics).is_err());
+        assert!(guard.authorize(&metrics, TokenScope::Metrics).is_ok());
+        assert!(matches!(
+            guard.authorize(&metrics, TokenScope::Admin),
+            Err(TokenError::MissingScope(_))
+        ));
+    }
+
     #[test]
     fn pair_then_authenticate() {
         let guard = PairingGuard::new(true, &[]);
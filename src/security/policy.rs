@@ -4,7 +4,9 @@ use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 /// How much autonomy the agent has
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum AutonomyLevel {
     /// Read-only: can observe but not act
@@ -322,6 +324,24 @@ impl SecurityPolicy {
         Ok(risk)
     }
 
+    /// Validate an action that changes physical-world state (smart-home
+    /// services, actuators). These are treated as medium risk: blocked in
+    /// read-only mode and gated on `approved` in supervised mode.
+    pub fn validate_device_action(&self, approved: bool) -> Result<(), String> {
+        if !self.can_act() {
+            return Err("Action blocked: autonomy is read-only".into());
+        }
+
+        if self.autonomy == AutonomyLevel::Supervised
+            && self.require_approval_for_medium_risk
+            && !approved
+        {
+            return Err("Device action requires explicit approval (approved=true)".into());
+        }
+
+        Ok(())
+    }
+
     /// Check if a shell command is allowed.
     ///
     /// Validates the **entire** command string, not just the first word:
@@ -693,6 +713,23 @@ mod tests {
         assert_eq!(allowed.unwrap(), CommandRiskLevel::Medium);
     }
 
+    #[test]
+    fn validate_device_action_follows_autonomy() {
+        let denied = readonly_policy().validate_device_action(true);
+        assert!(denied.unwrap_err().contains("read-only"));
+
+        let p = SecurityPolicy {
+            autonomy: AutonomyLevel::Supervised,
+            require_approval_for_medium_risk: true,
+            ..SecurityPolicy::default()
+        };
+        let denied = p.validate_device_action(false);
+        assert!(denied.unwrap_err().contains("requires explicit approval"));
+        assert!(p.validate_device_action(true).is_ok());
+
+        assert!(full_policy().validate_device_action(false).is_ok());
+    }
+
     #[test]
     fn validate_command_blocks_high_risk_by_default() {
         let p = SecurityPolicy {
@@ -0,0 +1,590 @@
+//! External secret sources for config values.
+//!
+//! Besides inline `enc2:` ciphertext, any secret-bearing config field (a key
+//! named `*key`, `api_keys`, `*token`, `*secret` or `*password`) may reference
+//! an external source that is resolved when the config is loaded:
+//!
+//! - `env:NAME` — read environment variable `NAME`
+//! - `file:PATH` — read a file; relative paths resolve against systemd's
+//!   `$CREDENTIALS_DIRECTORY` when set, otherwise the config directory
+//! - `cmd:COMMAND` — run a shell command (e.g. `cmd:pass show zeroclaw/openai`)
+//!   and use its stdout
+//!
+//! Resolution records the original reference per TOML key path in
+//! [`SecretReferences`], so saving the config writes references and
+//! ciphertext back instead of the resolved plaintext.
+
+use super::SecretStore;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long a `cmd:` source may run before it is killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Environment variable systemd sets for `LoadCredential=` / `SetCredential=`.
+const CREDENTIALS_DIRECTORY_ENV: &str = "CREDENTIALS_DIRECTORY";
+
+/// A parsed external secret reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    Env(String),
+    File(String),
+    Cmd(String),
+}
+
+impl SecretSource {
+    /// Parse an `env:` / `file:` / `cmd:` reference. Returns `None` for
+    /// anything else (plaintext or encrypted values).
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Some(name) = value.strip_prefix("env:") {
+            Some(Self::Env(name.trim().to_string()))
+        } else if let Some(path) = value.strip_prefix("file:") {
+            Some(Self::File(path.trim().to_string()))
+        } else {
+            value
+                .strip_prefix("cmd:")
+                .map(|cmd| Self::Cmd(cmd.trim().to_string()))
+        }
+    }
+
+    /// Short label for status output (`env`, `file`, `cmd`).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Env(_) => "env",
+            Self::File(_) => "file",
+            Self::Cmd(_) => "cmd",
+        }
+    }
+
+    /// Read the secret. `base_dir` anchors relative `file:` paths when no
+    /// systemd credentials directory is available.
+    pub fn resolve(&self, base_dir: &Path) -> Result<String> {
+        let credentials_dir = std::env::var_os(CREDENTIALS_DIRECTORY_ENV).map(PathBuf::from);
+        match self {
+            Self::Env(name) => resolve_env(name),
+            Self::File(path) => resolve_file(path, base_dir, credentials_dir.as_deref()),
+            Self::Cmd(command) => resolve_cmd(command),
+        }
+    }
+}
+
+/// Whether a value references an external source rather than holding the secret.
+pub fn is_reference(value: &str) -> bool {
+    SecretSource::parse(value).is_some()
+}
+
+/// Config key names that hold secrets, matched whole or as a `_`-separated
+/// suffix (`bot_token`, `client_secret`, `sasl_password`).
+const SECRET_KEY_NAMES: &[&str] = &[
+    "api_key",
+    "api_keys",
+    "encrypt_key",
+    "token",
+    "secret",
+    "password",
+];
+
+/// Whether a config key holds a secret and is therefore eligible for
+/// references, encryption and redaction.
+pub fn is_secret_key(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_KEY_NAMES.iter().any(|secret| {
+        name == *secret
+            || name
+                .strip_suffix(secret)
+                .is_some_and(|prefix| prefix.ends_with('_'))
+    })
+}
+
+/// Resolve one config value. Returns `Ok(None)` when the value is plaintext.
+pub fn resolve_value(value: &str, store: &SecretStore, base_dir: &Path) -> Result<Option<String>> {
+    if let Some(source) = SecretSource::parse(value) {
+        return source.resolve(base_dir).map(Some);
+    }
+    if SecretStore::is_encrypted(value) {
+        return store.decrypt(value).map(Some);
+    }
+    Ok(None)
+}
+
+fn resolve_env(name: &str) -> Result<String> {
+    if name.is_empty() {
+        bail!("env: reference is missing a variable name");
+    }
+    match std::env::var(name) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        Ok(_) => bail!("environment variable {name} is empty"),
+        Err(_) => bail!("environment variable {name} is not set"),
+    }
+}
+
+fn resolve_file(path: &str, base_dir: &Path, credentials_dir: Option<&Path>) -> Result<String> {
+    if path.is_empty() {
+        bail!("file: reference is missing a path");
+    }
+    let full_path = secret_file_path(path, base_dir, credentials_dir)?;
+    let contents = std::fs::read_to_string(&full_path)
+        .with_context(|| format!("failed to read {}", full_path.display()))?;
+    let value = contents.trim_end_matches(['\r', '\n']);
+    if value.is_empty() {
+        bail!("{} is empty", full_path.display());
+    }
+    Ok(value.to_string())
+}
+
+fn secret_file_path(
+    path: &str,
+    base_dir: &Path,
+    credentials_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    for prefix in ["$CREDENTIALS_DIRECTORY/", "${CREDENTIALS_DIRECTORY}/"] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            let dir = credentials_dir
+                .context("$CREDENTIALS_DIRECTORY is not set (not running under systemd?)")?;
+            return Ok(dir.join(rest));
+        }
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        let home = directories::UserDirs::new()
+            .map(|u| u.home_dir().to_path_buf())
+            .context("Could not find home directory")?;
+        return Ok(home.join(rest));
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Ok(path.to_path_buf());
+    }
+    Ok(credentials_dir.unwrap_or(base_dir).join(path))
+}
+
+fn resolve_cmd(command: &str) -> Result<String> {
+    if command.is_empty() {
+        bail!("cmd: reference is missing a command");
+    }
+
+    #[cfg(windows)]
+    let mut child = Command::new("cmd");
+    #[cfg(windows)]
+    child.args(["/C", command]);
+    #[cfg(not(windows))]
+    let mut child = Command::new("sh");
+    #[cfg(not(windows))]
+    child.args(["-c", command]);
+
+    let mut child = child
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run `{command}`"))?;
+
+    // Drain both pipes while waiting, or a chatty command blocks on a full
+    // pipe and never exits.
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > COMMAND_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("`{command}` timed out after {}s", COMMAND_TIMEOUT.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let stdout =
+        String::from_utf8(join_output(stdout)).context("command output is not valid UTF-8")?;
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&join_output(stderr)).into_owned();
+        bail!(
+            "`{command}` exited with {status}: {}",
+            crate::util::truncate_with_ellipsis(stderr.trim(), 200)
+        );
+    }
+
+    // `pass show` and friends print the secret on the first line.
+    let value = stdout.lines().next().unwrap_or_default().trim_end();
+    if value.is_empty() {
+        bail!("`{command}` printed nothing");
+    }
+    Ok(value.to_string())
+}
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+fn join_output(reader: Option<JoinHandle<Vec<u8>>>) -> Vec<u8> {
+    reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default()
+}
+
+/// Call `f` with the dotted key path and value of every secret string in a
+/// config table (e.g. `channels_config.telegram.bot_token`,
+/// `reliability.api_keys.0`).
+pub fn for_each_secret(
+    table: &mut toml::Table,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<()>,
+) -> Result<()> {
+    visit_table(table, "", f)
+}
+
+fn visit_table(
+    table: &mut toml::Table,
+    prefix: &str,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<()>,
+) -> Result<()> {
+    for (key, value) in table.iter_mut() {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        visit_value(value, &path, is_secret_key(key), f)?;
+    }
+    Ok(())
+}
+
+fn visit_value(
+    value: &mut toml::Value,
+    path: &str,
+    secret: bool,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<()>,
+) -> Result<()> {
+    match value {
+        toml::Value::String(s) if secret => f(path, s),
+        toml::Value::Table(table) => visit_table(table, path, f),
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                visit_value(item, &format!("{path}.{i}"), secret, f)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Look up a value by dotted key path (`gateway.port`, `reliability.api_keys.0`).
+pub fn get_path<'a>(table: &'a toml::Table, path: &str) -> Option<&'a toml::Value> {
+    let mut segments = path.split('.');
+    let mut current = table.get(segments.next()?)?;
+    for segment in segments {
+        current = match current {
+            toml::Value::Table(t) => t.get(segment)?,
+            toml::Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// Set a value at a dotted key path, creating intermediate tables.
+pub fn set_path(table: &mut toml::Table, path: &str, value: toml::Value) -> Result<()> {
+    let segments: Vec<&str> = path.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        bail!("invalid config key `{path}`");
+    }
+    let (last, parents) = segments.split_last().expect("split yields one segment");
+
+    let mut current = table;
+    for segment in parents {
+        let entry = current
+            .entry(segment.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = match entry {
+            toml::Value::Table(t) => t,
+            toml::Value::Array(_) => bail!("`{path}`: setting array entries is not supported"),
+            _ => bail!("`{path}`: `{segment}` is not a table"),
+        };
+    }
+    current.insert((*last).to_string(), value);
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct SecretReference {
+    raw: String,
+    resolved: String,
+}
+
+/// Original (unresolved) secret values captured at load time, keyed by
+/// dotted TOML key path.
+#[derive(Debug, Clone, Default)]
+pub struct SecretReferences {
+    entries: BTreeMap<String, SecretReference>,
+}
+
+impl SecretReferences {
+    /// Resolve every reference and encrypted value in `table` in place.
+    /// Errors name the offending key so a broken reference is easy to find.
+    pub fn resolve(table: &mut toml::Table, store: &SecretStore, base_dir: &Path) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        for_each_secret(table, &mut |path, value| {
+            let Some(resolved) = resolve_value(value, store, base_dir)
+                .with_context(|| format!("Failed to resolve secret `{path}`"))?
+            else {
+                return Ok(());
+            };
+            if SecretStore::needs_migration(value) {
+                tracing::warn!(
+                    "Config key `{path}` uses the insecure legacy enc: format; \
+                     run `zeroclaw secrets migrate` to upgrade it"
+                );
+            }
+            let raw = std::mem::replace(value, resolved.clone());
+            entries.insert(path.to_string(), SecretReference { raw, resolved });
+            Ok(())
+        })?;
+        Ok(Self { entries })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The original value for `path`, provided the field still holds what it
+    /// resolved to. A field that was changed since load is written as-is.
+    pub fn raw_if_unchanged(&self, path: &str, current: &str) -> Option<&str> {
+        self.entries
+            .get(path)
+            .filter(|r| r.resolved == current)
+            .map(|r| r.raw.as_str())
+    }
+
+    /// Put references and ciphertext back into a serialized config table.
+    pub fn restore(&self, table: &mut toml::Table) {
+        if self.is_empty() {
+            return;
+        }
+        let _ = for_each_secret(table, &mut |path, value| {
+            if let Some(raw) = self.raw_if_unchanged(path, value) {
+                *value = raw.to_string();
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn table(toml_str: &str) -> toml::Table {
+        toml_str.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_recognizes_source_prefixes() {
+        assert_eq!(
+            SecretSource::parse("env:OPENAI_API_KEY"),
+            Some(SecretSource::Env("OPENAI_API_KEY".into()))
+        );
+        assert_eq!(
+            SecretSource::parse("file:telegram"),
+            Some(SecretSource::File("telegram".into()))
+        );
+        assert_eq!(
+            SecretSource::parse("cmd: pass show zeroclaw/openai"),
+            Some(SecretSource::Cmd("pass show zeroclaw/openai".into()))
+        );
+        assert_eq!(SecretSource::parse("sk-plain"), None);
+        assert_eq!(SecretSource::parse("enc2:abcd"), None);
+    }
+
+    #[test]
+    fn secret_keys_are_detected_by_name() {
+        for key in [
+            "api_key",
+            "api_keys",
+            "bot_token",
+            "token",
+            "client_secret",
+            "sasl_password",
+            "encrypt_key",
+        ] {
+            assert!(is_secret_key(key), "{key}");
+        }
+        for key in [
+            "paired_tokens",
+            "model",
+            "chunk_max_tokens",
+            "url",
+            "partition_key",
+            "monkey",
+            "hotkey",
+        ] {
+            assert!(!is_secret_key(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn env_source_reads_variable() {
+        std::env::set_var("ZEROCLAW_TEST_SECRET_SOURCE_ENV", "from-env");
+        let value = SecretSource::Env("ZEROCLAW_TEST_SECRET_SOURCE_ENV".into())
+            .resolve(Path::new("."))
+            .unwrap();
+        assert_eq!(value, "from-env");
+
+        let err = SecretSource::Env("ZEROCLAW_TEST_SECRET_SOURCE_UNSET".into())
+            .resolve(Path::new("."))
+            .unwrap_err();
+        assert!(err.to_string().contains("not set"));
+    }
+
+    #[test]
+    fn file_source_resolves_relative_to_base_or_credentials_dir() {
+        let base = TempDir::new().unwrap();
+        let creds = TempDir::new().unwrap();
+        std::fs::write(base.path().join("token"), "base-secret\n").unwrap();
+        std::fs::write(creds.path().join("token"), "systemd-secret\n").unwrap();
+
+        assert_eq!(
+            resolve_file("token", base.path(), None).unwrap(),
+            "base-secret"
+        );
+        assert_eq!(
+            resolve_file("token", base.path(), Some(creds.path())).unwrap(),
+            "systemd-secret"
+        );
+        assert_eq!(
+            resolve_file(
+                "$CREDENTIALS_DIRECTORY/token",
+                base.path(),
+                Some(creds.path())
+            )
+            .unwrap(),
+            "systemd-secret"
+        );
+        let absolute = base.path().join("token");
+        assert_eq!(
+            resolve_file(
+                absolute.to_str().unwrap(),
+                Path::new("/"),
+                Some(creds.path())
+            )
+            .unwrap(),
+            "base-secret"
+        );
+        assert!(resolve_file("$CREDENTIALS_DIRECTORY/token", base.path(), None).is_err());
+        assert!(resolve_file("missing", base.path(), None).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cmd_source_uses_first_line_of_stdout() {
+        let value = resolve_cmd("printf 'hunter2\\nmetadata: x\\n'").unwrap();
+        assert_eq!(value, "hunter2");
+
+        let err = resolve_cmd("echo nope >&2; exit 3").unwrap_err();
+        assert!(err.to_string().contains("nope"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cmd_source_with_more_output_than_a_pipe_holds_completes() {
+        let value =
+            resolve_cmd("echo hunter2; head -c 1000000 /dev/zero; head -c 1000000 /dev/zero >&2")
+                .unwrap();
+        assert_eq!(value, "hunter2");
+    }
+
+    #[test]
+    fn resolve_replaces_values_and_restore_puts_references_back() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("bot"), "123:abc").unwrap();
+        std::env::set_var("ZEROCLAW_TEST_SECRET_SOURCE_KEY", "sk-env");
+        let store = SecretStore::new(dir.path(), true);
+        let encrypted = store.encrypt("sk-agent").unwrap();
+
+        let mut config = table(&format!(
+            r#"
+api_key = "env:ZEROCLAW_TEST_SECRET_SOURCE_KEY"
+default_model = "env:NOT_A_SECRET_FIELD"
+
+[channels_config.telegram]
+bot_token = "file:bot"
+
+[agents.researcher]
+api_key = "{encrypted}"
+"#
+        ));
+        let refs = SecretReferences::resolve(&mut config, &store, dir.path()).unwrap();
+
+        assert_eq!(config["api_key"].as_str(), Some("sk-env"));
+        assert_eq!(
+            get_path(&config, "channels_config.telegram.bot_token").and_then(|v| v.as_str()),
+            Some("123:abc")
+        );
+        assert_eq!(
+            get_path(&config, "agents.researcher.api_key").and_then(|v| v.as_str()),
+            Some("sk-agent")
+        );
+        // Non-secret fields are never treated as references.
+        assert_eq!(
+            config["default_model"].as_str(),
+            Some("env:NOT_A_SECRET_FIELD")
+        );
+
+        // A field edited after load keeps its new value.
+        set_path(&mut config, "agents.researcher.api_key", "sk-new".into()).unwrap();
+        refs.restore(&mut config);
+        assert_eq!(
+            config["api_key"].as_str(),
+            Some("env:ZEROCLAW_TEST_SECRET_SOURCE_KEY")
+        );
+        assert_eq!(
+            get_path(&config, "channels_config.telegram.bot_token").and_then(|v| v.as_str()),
+            Some("file:bot")
+        );
+        assert_eq!(
+            get_path(&config, "agents.researcher.api_key").and_then(|v| v.as_str()),
+            Some("sk-new")
+        );
+    }
+
+    #[test]
+    fn resolve_error_names_the_config_key() {
+        let dir = TempDir::new().unwrap();
+        let store = SecretStore::new(dir.path(), true);
+        let mut config = table(
+            r#"
+[reliability]
+api_keys = ["sk-one", "env:ZEROCLAW_TEST_SECRET_SOURCE_MISSING"]
+"#,
+        );
+        let err = SecretReferences::resolve(&mut config, &store, dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("`reliability.api_keys.1`"));
+    }
+
+    #[test]
+    fn set_path_creates_tables_and_rejects_bad_keys() {
+        let mut config = toml::Table::new();
+        set_path(&mut config, "channels_config.discord.bot_token", "x".into()).unwrap();
+        assert_eq!(
+            get_path(&config, "channels_config.discord.bot_token").and_then(|v| v.as_str()),
+            Some("x")
+        );
+        assert!(set_path(&mut config, "channels_config..bot_token", "x".into()).is_err());
+        assert!(set_path(
+            &mut config,
+            "channels_config.discord.bot_token.inner",
+            "x".into()
+        )
+        .is_err());
+    }
+}
@@ -50,6 +50,16 @@ impl SecretStore {
         }
     }
 
+    /// Create a secret store backed by an explicit key file (used when rotating keys).
+    pub fn with_key_path(key_path: PathBuf, enabled: bool) -> Self {
+        Self { key_path, enabled }
+    }
+
+    /// Path to the key file this store encrypts with.
+    pub fn key_path(&self) -> &Path {
+        &self.key_path
+    }
+
     /// Encrypt a plaintext secret. Returns hex-encoded ciphertext prefixed with `enc2:`.
     /// Format: `enc2:<hex(nonce ‖ ciphertext ‖ tag)>` (12 + N + 16 bytes).
     /// If encryption is disabled, returns the plaintext as-is.
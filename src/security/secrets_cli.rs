@@ -0,0 +1,383 @@
+//! `zeroclaw secrets` — store, read and re-key config secrets.
+//!
+//! Operates on `config.toml` directly rather than a loaded `Config`, so it
+//! still works when a secret reference no longer resolves and needs fixing.
+
+use super::secret_sources::{
+    for_each_secret, get_path, is_reference, is_secret_key, resolve_value, set_path, SecretSource,
+};
+use super::SecretStore;
+use crate::config::schema::write_config_file;
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::io::{BufRead, IsTerminal};
+use std::path::Path;
+
+pub fn handle_command(cmd: crate::SecretsCommands) -> Result<()> {
+    run(cmd, &Config::default_dir()?)
+}
+
+fn run(cmd: crate::SecretsCommands, config_dir: &Path) -> Result<()> {
+    let config_path = config_dir.join("config.toml");
+    let mut table = read_table(&config_path)?;
+    let encrypt = get_path(&table, "secrets.encrypt")
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(true);
+    let store = SecretStore::new(config_dir, encrypt);
+
+    match cmd {
+        crate::SecretsCommands::Set { key, value } => {
+            let field = key.rsplit('.').next().unwrap_or_default();
+            if !is_secret_key(field) {
+                bail!(
+                    "`{key}` is not a secret field (expected a name ending in key, token, secret or password)"
+                );
+            }
+            let value = match value {
+                Some(value) => value,
+                None => read_secret(&key)?,
+            };
+            if value.is_empty() {
+                bail!("Refusing to store an empty secret");
+            }
+
+            let stored = if let Some(source) = SecretSource::parse(&value) {
+                // Resolve now so a typo fails here instead of at daemon startup.
+                source
+                    .resolve(config_dir)
+                    .with_context(|| format!("`{value}` does not resolve"))?;
+                value
+            } else {
+                store.encrypt(&value)?
+            };
+            let description = describe(&stored);
+            set_path(&mut table, &key, stored.into())?;
+            write_table(&config_path, &table)?;
+            println!("✅ Stored {key} ({description})");
+        }
+        crate::SecretsCommands::Get { key } => {
+            let value = get_path(&table, &key)
+                .and_then(toml::Value::as_str)
+                .with_context(|| format!("`{key}` is not set in {}", config_path.display()))?;
+            let resolved = resolve_value(value, &store, config_dir)
+                .with_context(|| format!("Failed to resolve secret `{key}`"))?
+                .unwrap_or_else(|| value.to_string());
+            println!("{resolved}");
+        }
+        crate::SecretsCommands::RotateKey => {
+            rotate_key(config_dir, &config_path, &mut table, &store)?;
+        }
+        crate::SecretsCommands::Migrate { encrypt_plaintext } => {
+            migrate(&config_path, &mut table, config_dir, encrypt_plaintext)?;
+        }
+    }
+    Ok(())
+}
+
+/// Decrypt every secret with the current key, re-encrypt it under a freshly
+/// generated key, then swap the key file in.
+fn rotate_key(
+    config_dir: &Path,
+    config_path: &Path,
+    table: &mut toml::Table,
+    store: &SecretStore,
+) -> Result<()> {
+    let new_key_path = config_dir.join(".secret_key.new");
+    if new_key_path.exists() {
+        bail!(
+            "{} exists from an interrupted rotation. If config.toml no longer decrypts, \
+             move it over {}; otherwise delete it, then retry.",
+            new_key_path.display(),
+            store.key_path().display()
+        );
+    }
+    let new_store = SecretStore::with_key_path(new_key_path.clone(), true);
+
+    let mut rotated = 0usize;
+    let mut legacy = Vec::new();
+    let result = for_each_secret(table, &mut |path, value| {
+        if !SecretStore::is_encrypted(value) {
+            return Ok(());
+        }
+        if SecretStore::needs_migration(value) {
+            legacy.push(path.to_string());
+        }
+        let plaintext = store
+            .decrypt(value)
+            .with_context(|| format!("Failed to decrypt `{path}` with the current key"))?;
+        *value = new_store.encrypt(&plaintext)?;
+        rotated += 1;
+        Ok(())
+    })
+    .and_then(|()| {
+        if rotated > 0 {
+            write_table(config_path, table)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&new_key_path);
+        return Err(e);
+    }
+
+    if rotated == 0 {
+        println!("No encrypted secrets in config — nothing to rotate.");
+        return Ok(());
+    }
+
+    fs::rename(&new_key_path, store.key_path()).with_context(|| {
+        format!(
+            "Config was re-encrypted but the new key could not be moved into place; \
+             move {} to {} by hand",
+            new_key_path.display(),
+            store.key_path().display()
+        )
+    })?;
+
+    println!("🔑 Rotated secret key; re-encrypted {rotated} secret(s).");
+    report_legacy(&legacy, "upgraded to enc2:");
+    Ok(())
+}
+
+/// Upgrade legacy `enc:` values and optionally encrypt plaintext secrets.
+fn migrate(
+    config_path: &Path,
+    table: &mut toml::Table,
+    config_dir: &Path,
+    encrypt_plaintext: bool,
+) -> Result<()> {
+    // Migration always produces enc2:, even when `secrets.encrypt` is off.
+    let store = SecretStore::new(config_dir, true);
+    let mut legacy = Vec::new();
+    let mut encrypted = Vec::new();
+    let mut plaintext = Vec::new();
+
+    for_each_secret(table, &mut |path, value| {
+        if SecretStore::needs_migration(value) {
+            let (secret, _) = store
+                .decrypt_and_migrate(value)
+                .with_context(|| format!("Failed to decrypt legacy secret `{path}`"))?;
+            *value = store.encrypt(&secret)?;
+            legacy.push(path.to_string());
+        } else if !value.is_empty() && !SecretStore::is_encrypted(value) && !is_reference(value) {
+            if encrypt_plaintext {
+                *value = store.encrypt(value)?;
+                encrypted.push(path.to_string());
+            } else {
+                plaintext.push(path.to_string());
+            }
+        }
+        Ok(())
+    })?;
+
+    if legacy.is_empty() && encrypted.is_empty() {
+        println!("✅ No legacy enc: values found — nothing to migrate.");
+    } else {
+        write_table(config_path, table)?;
+        report_legacy(&legacy, "upgraded to enc2:");
+        if !encrypted.is_empty() {
+            println!("🔒 Encrypted {} plaintext secret(s):", encrypted.len());
+            for path in &encrypted {
+                println!("   {path}");
+            }
+        }
+    }
+
+    if !plaintext.is_empty() {
+        println!();
+        println!(
+            "ℹ️  {} secret(s) are stored in plaintext: {}",
+            plaintext.len(),
+            plaintext.join(", ")
+        );
+        println!("   Encrypt them with: zeroclaw secrets migrate --encrypt-plaintext");
+    }
+    Ok(())
+}
+
+fn report_legacy(paths: &[String], action: &str) {
+    if paths.is_empty() {
+        return;
+    }
+    println!(
+        "⚠️  Found {} legacy enc: (XOR) value(s), {action}:",
+        paths.len()
+    );
+    for path in paths {
+        println!("   {path}");
+    }
+}
+
+fn describe(value: &str) -> String {
+    match SecretSource::parse(value) {
+        Some(source) => format!("{} reference", source.kind()),
+        None if SecretStore::is_encrypted(value) => "encrypted".to_string(),
+        None => "plaintext — secrets.encrypt is off".to_string(),
+    }
+}
+
+/// Prompt without echo on a terminal; otherwise read one line from stdin so
+/// secrets can be piped in (`pass show x | zeroclaw secrets set api_key`).
+fn read_secret(key: &str) -> Result<String> {
+    if std::io::stdin().is_terminal() {
+        return dialoguer::Password::new()
+            .with_prompt(format!("Value for {key}"))
+            .interact()
+            .context("Failed to read secret");
+    }
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context("Failed to read secret from stdin")?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_table(config_path: &Path) -> Result<toml::Table> {
+    if !config_path.exists() {
+        bail!(
+            "No config at {} — run `zeroclaw onboard` first",
+            config_path.display()
+        );
+    }
+    let contents = fs::read_to_string(config_path).context("Failed to read config file")?;
+    contents.parse().context("Failed to parse config file")
+}
+
+fn write_table(config_path: &Path, table: &toml::Table) -> Result<()> {
+    let _: Config = toml::Value::Table(table.clone())
+        .try_into()
+        .context("Refusing to write a config that no longer parses")?;
+    let toml_str = toml::to_string_pretty(table).context("Failed to serialize config")?;
+    write_config_file(config_path, &toml_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SecretsCommands;
+    use tempfile::TempDir;
+
+    fn setup(extra: &str) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let mut config = toml::to_string_pretty(&Config::default()).unwrap();
+        config.push_str(extra);
+        fs::write(dir.path().join("config.toml"), config).unwrap();
+        dir
+    }
+
+    fn raw(dir: &Path, key: &str) -> String {
+        let table = read_table(&dir.join("config.toml")).unwrap();
+        get_path(&table, key).unwrap().as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn set_encrypts_and_keeps_references() {
+        let dir = setup("");
+        run(
+            SecretsCommands::Set {
+                key: "api_key".into(),
+                value: Some("sk-secret".into()),
+            },
+            dir.path(),
+        )
+        .unwrap();
+        let stored = raw(dir.path(), "api_key");
+        assert!(stored.starts_with("enc2:"));
+        let store = SecretStore::new(dir.path(), true);
+        assert_eq!(store.decrypt(&stored).unwrap(), "sk-secret");
+
+        std::env::set_var("ZEROCLAW_TEST_SECRETS_CLI_KEY", "sk-env");
+        run(
+            SecretsCommands::Set {
+                key: "api_key".into(),
+                value: Some("env:ZEROCLAW_TEST_SECRETS_CLI_KEY".into()),
+            },
+            dir.path(),
+        )
+        .unwrap();
+        assert_eq!(
+            raw(dir.path(), "api_key"),
+            "env:ZEROCLAW_TEST_SECRETS_CLI_KEY"
+        );
+    }
+
+    #[test]
+    fn set_rejects_non_secret_fields_and_broken_references() {
+        let dir = setup("");
+        let err = run(
+            SecretsCommands::Set {
+                key: "default_model".into(),
+                value: Some("x".into()),
+            },
+            dir.path(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not a secret field"));
+
+        assert!(run(
+            SecretsCommands::Set {
+                key: "api_key".into(),
+                value: Some("env:ZEROCLAW_TEST_SECRETS_CLI_UNSET".into()),
+            },
+            dir.path(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rotate_key_reencrypts_under_new_key() {
+        let dir = setup("");
+        let old_store = SecretStore::new(dir.path(), true);
+        run(
+            SecretsCommands::Set {
+                key: "api_key".into(),
+                value: Some("sk-rotate".into()),
+            },
+            dir.path(),
+        )
+        .unwrap();
+        let before = raw(dir.path(), "api_key");
+        let old_key = fs::read_to_string(old_store.key_path()).unwrap();
+
+        run(SecretsCommands::RotateKey, dir.path()).unwrap();
+
+        let after = raw(dir.path(), "api_key");
+        assert_ne!(before, after);
+        assert_ne!(fs::read_to_string(old_store.key_path()).unwrap(), old_key);
+        assert!(!dir.path().join(".secret_key.new").exists());
+        assert_eq!(old_store.decrypt(&after).unwrap(), "sk-rotate");
+    }
+
+    #[test]
+    fn migrate_upgrades_legacy_values() {
+        let dir = TempDir::new().unwrap();
+        let store = SecretStore::new(dir.path(), true);
+        // Create the key, then build a legacy XOR value by hand.
+        store.encrypt("warmup").unwrap();
+        let key = hex::decode(fs::read_to_string(store.key_path()).unwrap().trim()).unwrap();
+        let xored: Vec<u8> = b"sk-legacy"
+            .iter()
+            .zip(key.iter().cycle())
+            .map(|(b, k)| b ^ k)
+            .collect();
+        let legacy = format!("enc:{}", hex::encode(xored));
+
+        let mut config = toml::to_string_pretty(&Config::default()).unwrap();
+        config = format!("api_key = \"{legacy}\"\n{config}");
+        fs::write(dir.path().join("config.toml"), config).unwrap();
+
+        run(
+            SecretsCommands::Migrate {
+                encrypt_plaintext: false,
+            },
+            dir.path(),
+        )
+        .unwrap();
+
+        let migrated = raw(dir.path(), "api_key");
+        assert!(migrated.starts_with("enc2:"));
+        assert_eq!(store.decrypt(&migrated).unwrap(), "sk-legacy");
+    }
+}
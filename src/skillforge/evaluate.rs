@@ -1,8 +1,13 @@
 //! Evaluator — scores discovered skill candidates across multiple dimensions.
 
+use std::sync::Arc;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use super::scout::ScoutResult;
+use crate::providers::{structured, ChatMessage, Provider};
 
 // ---------------------------------------------------------------------------
 // Scoring dimensions
@@ -49,6 +54,27 @@ pub struct EvalResult {
     pub scores: Scores,
     pub total_score: f64,
     pub recommendation: Recommendation,
+    /// Concerns raised by the LLM reviewer, if one ran.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub concerns: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// LLM review
+// ---------------------------------------------------------------------------
+
+const REVIEW_SYSTEM_PROMPT: &str = "You review third-party agent skills before they are \
+installed. From the repository metadata, judge how risky installing it is (malware, credential \
+theft, obfuscated code, a description that does not match the name). Score security from 0.0 \
+(unsafe) to 1.0 (no concerns) and list your concerns.";
+
+/// Structured reply of the LLM reviewer.
+#[derive(Debug, Deserialize, JsonSchema)]
+struct Review {
+    /// Security from 0.0 (unsafe) to 1.0 (no concerns)
+    security: f64,
+    /// Reasons for a lower score
+    concerns: Vec<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -58,6 +84,8 @@ pub struct EvalResult {
 pub struct Evaluator {
     /// Minimum total score for auto-integration.
     min_score: f64,
+    /// Provider and model asked to review candidates.
+    reviewer: Option<(Arc<dyn Provider>, String)>,
 }
 
 /// Known-bad patterns in repo names / descriptions (matched as whole words).
@@ -87,7 +115,16 @@ fn contains_word(haystack: &str, word: &str) -> bool {
 
 impl Evaluator {
     pub fn new(min_score: f64) -> Self {
-        Self { min_score }
+        Self {
+            min_score,
+            reviewer: None,
+        }
+    }
+
+    /// Have `model` on `provider` review candidates in [`Evaluator::review`].
+    pub fn with_reviewer(mut self, provider: Arc<dyn Provider>, model: impl Into<String>) -> Self {
+        self.reviewer = Some((provider, model.into()));
+        self
     }
 
     pub fn evaluate(&self, candidate: ScoutResult) -> EvalResult {
@@ -100,6 +137,40 @@ impl Evaluator {
             quality,
             security,
         };
+        self.result(candidate, scores, Vec::new())
+    }
+
+    /// [`Evaluator::evaluate`], then ask the reviewer for a structured
+    /// security verdict. The review can only lower the security score; when
+    /// there is no reviewer or the review fails, the heuristic scores stand.
+    pub async fn review(&self, candidate: ScoutResult) -> EvalResult {
+        let result = self.evaluate(candidate);
+        let Some((provider, model)) = &self.reviewer else {
+            return result;
+        };
+        let metadata = serde_json::to_string_pretty(&result.candidate).unwrap_or_default();
+        let messages = [
+            ChatMessage::system(REVIEW_SYSTEM_PROMPT),
+            ChatMessage::user(metadata),
+        ];
+        match structured::chat_as::<Review>(provider.as_ref(), &messages, model, 0.0).await {
+            Ok(review) => {
+                let mut scores = result.scores;
+                scores.security = scores.security.min(review.security.clamp(0.0, 1.0));
+                self.result(result.candidate, scores, review.concerns)
+            }
+            Err(e) => {
+                warn!(
+                    skill = result.candidate.name.as_str(),
+                    error = %e,
+                    "Skill review failed, keeping heuristic scores"
+                );
+                result
+            }
+        }
+    }
+
+    fn result(&self, candidate: ScoutResult, scores: Scores, concerns: Vec<String>) -> EvalResult {
         let total_score = scores.total();
 
         let recommendation = if total_score >= self.min_score {
@@ -115,6 +186,7 @@ impl Evaluator {
             scores,
             total_score,
             recommendation,
+            concerns,
         }
     }
 
@@ -255,6 +327,46 @@ mod tests {
         );
     }
 
+    struct Reviewer(Option<&'static str>);
+
+    #[async_trait::async_trait]
+    impl Provider for Reviewer {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            assert!(message.contains("test-skill"));
+            self.0
+                .map(String::from)
+                .ok_or_else(|| anyhow::anyhow!("503 unavailable"))
+        }
+    }
+
+    #[tokio::test]
+    async fn review_can_lower_security() {
+        let c = make_candidate(500, Some("Rust"), true);
+        let flagged = r#"{"security": 0.1, "concerns": ["downloads a binary at runtime"]}"#;
+        let eval = Evaluator::new(0.7).with_reviewer(Arc::new(Reviewer(Some(flagged))), "m");
+        let res = eval.review(c.clone()).await;
+        assert!((res.scores.security - 0.1).abs() < f64::EPSILON);
+        assert_eq!(res.recommendation, Recommendation::Manual);
+        assert_eq!(res.concerns, ["downloads a binary at runtime"]);
+
+        let praised = r#"{"security": 1.0, "concerns": []}"#;
+        let eval = Evaluator::new(0.7).with_reviewer(Arc::new(Reviewer(Some(praised))), "m");
+        let heuristic = eval.evaluate(c.clone());
+        let res = eval.review(c.clone()).await;
+        assert!((res.total_score - heuristic.total_score).abs() < f64::EPSILON);
+
+        let eval = Evaluator::new(0.7).with_reviewer(Arc::new(Reviewer(None)), "m");
+        let res = eval.review(c).await;
+        assert_eq!(res.recommendation, Recommendation::Auto);
+        assert!(res.concerns.is_empty());
+    }
+
     #[test]
     fn exact_hack_is_flagged() {
         let eval = Evaluator::new(0.7);
@@ -8,6 +8,8 @@ pub mod evaluate;
 pub mod integrate;
 pub mod scout;
 
+use std::sync::Arc;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
@@ -15,6 +17,7 @@ use tracing::{info, warn};
 use self::evaluate::{EvalResult, Evaluator, Recommendation};
 use self::integrate::Integrator;
 use self::scout::{GitHubScout, Scout, ScoutResult, ScoutSource};
+use crate::providers::Provider;
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -119,6 +122,12 @@ impl SkillForge {
         }
     }
 
+    /// Have `model` on `provider` review each candidate's security.
+    pub fn with_reviewer(mut self, provider: Arc<dyn Provider>, model: impl Into<String>) -> Self {
+        self.evaluator = Evaluator::new(self.config.min_score).with_reviewer(provider, model);
+        self
+    }
+
     /// Run the full pipeline: Scout → Evaluate → Integrate.
     pub async fn forge(&self) -> Result<ForgeReport> {
         if !self.config.enabled {
@@ -166,10 +175,10 @@ impl SkillForge {
         info!(discovered, "Total unique candidates after dedup");
 
         // --- Evaluate -------------------------------------------------------
-        let results: Vec<EvalResult> = candidates
-            .into_iter()
-            .map(|c| self.evaluator.evaluate(c))
-            .collect();
+        let mut results: Vec<EvalResult> = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            results.push(self.evaluator.review(candidate).await);
+        }
         let evaluated = results.len();
 
         // --- Integrate ------------------------------------------------------
@@ -1228,6 +1228,8 @@ async fn run_tool_dispatch_mode(
     let peripheral_tools: Vec<Box<dyn crate::tools::Tool>> =
         crate::peripherals::create_peripheral_tools(&config.peripherals).await?;
     tools_registry.extend(peripheral_tools);
+    tools_registry
+        .extend(crate::mcp::create_mcp_tools(&config.mcp, &security, &config.workspace_dir).await);
 
     observer.record_event(&crate::observability::ObserverEvent::ToolCallStart {
         tool: tool_name.to_string(),
@@ -27,6 +27,10 @@ impl Tool for FileReadTool {
         "Read the contents of a file in the workspace"
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn parameters_schema(&self) -> serde_json::Value {
         json!({
             "type": "object",
@@ -0,0 +1,717 @@
+use super::traits::{Tool, ToolResult};
+use crate::config::HomeAssistantConfig;
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum number of entities returned by `ha_list_entities` in one call.
+const MAX_LISTED_ENTITIES: usize = 200;
+
+/// Thin client for the Home Assistant REST API.
+#[derive(Clone)]
+pub struct HomeAssistantClient {
+    base_url: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl HomeAssistantClient {
+    pub fn new(base_url: &str, token: &str, timeout_secs: u64) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_secs.max(1)))
+            .build()
+            .unwrap_or_default();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            client,
+        }
+    }
+
+    pub fn from_config(config: &HomeAssistantConfig) -> anyhow::Result<Self> {
+        let token = config
+            .token
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("home_assistant.token is not set"))?;
+        Ok(Self::new(&config.url, token, config.timeout_secs))
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// WebSocket endpoint derived from the REST base URL.
+    pub fn websocket_url(&self) -> String {
+        let base = if let Some(rest) = self.base_url.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            self.base_url.clone()
+        };
+        format!("{base}/api/websocket")
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> anyhow::Result<Value> {
+        let mut req = self
+            .client
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(&self.token);
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+        let resp = req.send().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            let text = crate::util::truncate_with_ellipsis(&text, 200);
+            anyhow::bail!("Home Assistant returned {status}: {text}");
+        }
+        Ok(resp.json().await?)
+    }
+
+    /// Lightweight connectivity probe (`GET /api/`).
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        self.request(reqwest::Method::GET, "/api/", None).await?;
+        Ok(())
+    }
+
+    pub async fn states(&self) -> anyhow::Result<Vec<Value>> {
+        let value = self
+            .request(reqwest::Method::GET, "/api/states", None)
+            .await?;
+        match value {
+            Value::Array(states) => Ok(states),
+            _ => anyhow::bail!("Unexpected /api/states response"),
+        }
+    }
+
+    pub async fn state(&self, entity_id: &str) -> anyhow::Result<Value> {
+        self.request(
+            reqwest::Method::GET,
+            &format!("/api/states/{entity_id}"),
+            None,
+        )
+        .await
+    }
+
+    pub async fn call_service(
+        &self,
+        domain: &str,
+        service: &str,
+        data: &Value,
+    ) -> anyhow::Result<Value> {
+        self.request(
+            reqwest::Method::POST,
+            &format!("/api/services/{domain}/{service}"),
+            Some(data),
+        )
+        .await
+    }
+}
+
+/// Check `domain.service` against the configured allowlist. Entries are either
+/// exact (`light.turn_on`) or a whole domain (`light.*`).
+pub fn service_allowed(allowed: &[String], domain: &str, service: &str) -> bool {
+    allowed.iter().any(|entry| {
+        let entry = entry.trim();
+        match entry.split_once('.') {
+            Some((d, "*")) => d == domain,
+            Some((d, s)) => d == domain && s == service,
+            None => false,
+        }
+    })
+}
+
+/// Entity ids and service names are `snake_case` identifiers; reject anything
+/// else so they can be spliced into REST paths safely.
+fn is_valid_identifier(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn is_valid_entity_id(entity_id: &str) -> bool {
+    entity_id
+        .split_once('.')
+        .is_some_and(|(domain, object)| is_valid_identifier(domain) && is_valid_identifier(object))
+}
+
+fn failure(error: impl Into<String>) -> ToolResult {
+    ToolResult {
+        success: false,
+        output: String::new(),
+        error: Some(error.into()),
+    }
+}
+
+fn friendly_name(state: &Value) -> Option<&str> {
+    state
+        .get("attributes")
+        .and_then(|a| a.get("friendly_name"))
+        .and_then(Value::as_str)
+}
+
+/// List Home Assistant entities with their current state.
+pub struct HaListEntitiesTool {
+    client: HomeAssistantClient,
+}
+
+impl HaListEntitiesTool {
+    pub fn new(client: HomeAssistantClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Tool for HaListEntitiesTool {
+    fn name(&self) -> &str {
+        "ha_list_entities"
+    }
+
+    fn description(&self) -> &str {
+        "List Home Assistant entities and their current state, optionally filtered by domain"
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "domain": {
+                    "type": "string",
+                    "description": "Only list entities in this domain (e.g. 'light', 'sensor')"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+        let domain = args.get("domain").and_then(Value::as_str).map(str::trim);
+
+        let states = match self.client.states().await {
+            Ok(states) => states,
+            Err(e) => return Ok(failure(e.to_string())),
+        };
+
+        let mut lines: Vec<String> = states
+            .iter()
+            .filter_map(|state| {
+                let entity_id = state.get("entity_id")?.as_str()?;
+                if let Some(domain) = domain.filter(|d| !d.is_empty()) {
+                    if entity_id.split_once('.').map(|(d, _)| d) != Some(domain) {
+                        return None;
+                    }
+                }
+                let value = state
+                    .get("state")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown");
+                Some(match friendly_name(state) {
+                    Some(name) => format!("{entity_id}: {value} ({name})"),
+                    None => format!("{entity_id}: {value}"),
+                })
+            })
+            .collect();
+        lines.sort();
+
+        let total = lines.len();
+        if total > MAX_LISTED_ENTITIES {
+            lines.truncate(MAX_LISTED_ENTITIES);
+            lines.push(format!(
+                "... {} more entities; filter by domain to narrow the list",
+                total - MAX_LISTED_ENTITIES
+            ));
+        }
+
+        Ok(ToolResult {
+            success: true,
+            output: if lines.is_empty() {
+                "No entities found".into()
+            } else {
+                lines.join("\n")
+            },
+            error: None,
+        })
+    }
+}
+
+/// Read the full state object of one entity.
+pub struct HaGetStateTool {
+    client: HomeAssistantClient,
+}
+
+impl HaGetStateTool {
+    pub fn new(client: HomeAssistantClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Tool for HaGetStateTool {
+    fn name(&self) -> &str {
+        "ha_get_state"
+    }
+
+    fn description(&self) -> &str {
+        "Read the current state and attributes of a Home Assistant entity"
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "entity_id": {
+                    "type": "string",
+                    "description": "Entity id, e.g. 'sensor.living_room_temperature'"
+                }
+            },
+            "required": ["entity_id"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+        let entity_id = args
+            .get("entity_id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing 'entity_id' parameter"))?
+            .trim();
+
+        if !is_valid_entity_id(entity_id) {
+            return Ok(failure(format!("Invalid entity id: {entity_id}")));
+        }
+
+        match self.client.state(entity_id).await {
+            Ok(state) => Ok(ToolResult {
+                success: true,
+                output: serde_json::to_string_pretty(&state)?,
+                error: None,
+            }),
+            Err(e) => Ok(failure(e.to_string())),
+        }
+    }
+}
+
+/// Call a Home Assistant service. Gated by the service allowlist and the
+/// autonomy/approval rules in [`SecurityPolicy`].
+pub struct HaCallServiceTool {
+    client: HomeAssistantClient,
+    security: Arc<SecurityPolicy>,
+    allowed_services: Vec<String>,
+}
+
+impl HaCallServiceTool {
+    pub fn new(
+        client: HomeAssistantClient,
+        security: Arc<SecurityPolicy>,
+        allowed_services: Vec<String>,
+    ) -> Self {
+        Self {
+            client,
+            security,
+            allowed_services,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for HaCallServiceTool {
+    fn name(&self) -> &str {
+        "ha_call_service"
+    }
+
+    fn description(&self) -> &str {
+        "Call a Home Assistant service (e.g. light.turn_on) on an entity"
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "domain": {
+                    "type": "string",
+                    "description": "Service domain, e.g. 'light'"
+                },
+                "service": {
+                    "type": "string",
+                    "description": "Service name, e.g. 'turn_on'"
+                },
+                "entity_id": {
+                    "type": "string",
+                    "description": "Target entity id"
+                },
+                "data": {
+                    "type": "object",
+                    "description": "Additional service data, e.g. {\"brightness_pct\": 40}"
+                },
+                "approved": {
+                    "type": "boolean",
+                    "description": "Set true to explicitly approve the call in supervised mode",
+                    "default": false
+                }
+            },
+            "required": ["domain", "service"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+        let domain = args
+            .get("domain")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing 'domain' parameter"))?
+            .trim();
+        let service = args
+            .get("service")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Missing 'service' parameter"))?
+            .trim();
+        let entity_id = args.get("entity_id").and_then(Value::as_str).map(str::trim);
+        let approved = args
+            .get("approved")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        if !is_valid_identifier(domain) || !is_valid_identifier(service) {
+            return Ok(failure(format!("Invalid service: {domain}.{service}")));
+        }
+
+        if !service_allowed(&self.allowed_services, domain, service) {
+            return Ok(failure(format!(
+                "Service '{domain}.{service}' is not in home_assistant.allowed_services"
+            )));
+        }
+
+        let mut data = match args.get("data") {
+            Some(Value::Object(map)) => map.clone(),
+            Some(Value::Null) | None => serde_json::Map::new(),
+            Some(_) => return Ok(failure("'data' must be an object")),
+        };
+        if let Some(entity_id) = entity_id {
+            if !is_valid_entity_id(entity_id) {
+                return Ok(failure(format!("Invalid entity id: {entity_id}")));
+            }
+            data.insert("entity_id".into(), Value::String(entity_id.to_string()));
+        }
+
+        if self.security.is_rate_limited() {
+            return Ok(failure(
+                "Rate limit exceeded: too many actions in the last hour",
+            ));
+        }
+
+        if let Err(reason) = self.security.validate_device_action(approved) {
+            return Ok(failure(reason));
+        }
+
+        if !self.security.record_action() {
+            return Ok(failure("Rate limit exceeded: action budget exhausted"));
+        }
+
+        match self
+            .client
+            .call_service(domain, service, &Value::Object(data))
+            .await
+        {
+            Ok(changed) => {
+                let changed: Vec<String> = changed
+                    .as_array()
+                    .map(|states| {
+                        states
+                            .iter()
+                            .filter_map(|s| {
+                                let id = s.get("entity_id")?.as_str()?;
+                                let state = s.get("state")?.as_str()?;
+                                Some(format!("{id}: {state}"))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let output = if changed.is_empty() {
+                    format!("Called {domain}.{service}")
+                } else {
+                    format!("Called {domain}.{service}\n{}", changed.join("\n"))
+                };
+                Ok(ToolResult {
+                    success: true,
+                    output,
+                    error: None,
+                })
+            }
+            Err(e) => Ok(failure(e.to_string())),
+        }
+    }
+}
+
+/// Build the Home Assistant tools when `[home_assistant]` is enabled.
+pub fn home_assistant_tools(
+    config: &HomeAssistantConfig,
+    security: &Arc<SecurityPolicy>,
+) -> Vec<Box<dyn Tool>> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    let client = match HomeAssistantClient::from_config(config) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Home Assistant tools disabled: {e}");
+            return Vec::new();
+        }
+    };
+    vec![
+        Box::new(HaListEntitiesTool::new(client.clone())),
+        Box::new(HaGetStateTool::new(client.clone())),
+        Box::new(HaCallServiceTool::new(
+            client,
+            security.clone(),
+            config.allowed_services.clone(),
+        )),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::AutonomyLevel;
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::{get, post},
+        Json, Router,
+    };
+    use parking_lot::Mutex;
+    use tokio::net::TcpListener;
+
+    type Calls = Arc<Mutex<Vec<(String, Value)>>>;
+
+    fn authorized(headers: &HeaderMap) -> bool {
+        headers.get("authorization").and_then(|v| v.to_str().ok()) == Some("Bearer test-token")
+    }
+
+    fn sample_states() -> Value {
+        json!([
+            {"entity_id": "light.kitchen", "state": "off", "attributes": {"friendly_name": "Kitchen"}},
+            {"entity_id": "sensor.outside_temperature", "state": "12.5", "attributes": {"unit_of_measurement": "°C"}},
+            {"entity_id": "lock.front_door", "state": "locked", "attributes": {}}
+        ])
+    }
+
+    async fn states_handler(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+        if !authorized(&headers) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Ok(Json(sample_states()))
+    }
+
+    async fn state_handler(
+        headers: HeaderMap,
+        Path(entity_id): Path<String>,
+    ) -> Result<Json<Value>, StatusCode> {
+        if !authorized(&headers) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        sample_states()
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["entity_id"] == entity_id.as_str())
+            .cloned()
+            .map(Json)
+            .ok_or(StatusCode::NOT_FOUND)
+    }
+
+    async fn service_handler(
+        State(calls): State<Calls>,
+        headers: HeaderMap,
+        Path((domain, service)): Path<(String, String)>,
+        Json(body): Json<Value>,
+    ) -> Result<Json<Value>, StatusCode> {
+        if !authorized(&headers) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        let entity_id = body["entity_id"].as_str().unwrap_or_default().to_string();
+        calls.lock().push((format!("{domain}.{service}"), body));
+        Ok(Json(json!([{"entity_id": entity_id, "state": "on"}])))
+    }
+
+    async fn spawn_fake_home_assistant() -> (HomeAssistantClient, Calls) {
+        let calls: Calls = Arc::default();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/api/states", get(states_handler))
+            .route("/api/states/{entity_id}", get(state_handler))
+            .route("/api/services/{domain}/{service}", post(service_handler))
+            .with_state(Arc::clone(&calls));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (HomeAssistantClient::new(&base, "test-token", 5), calls)
+    }
+
+    fn policy(autonomy: AutonomyLevel) -> Arc<SecurityPolicy> {
+        Arc::new(SecurityPolicy {
+            autonomy,
+            ..SecurityPolicy::default()
+        })
+    }
+
+    fn call_tool(client: HomeAssistantClient, autonomy: AutonomyLevel) -> HaCallServiceTool {
+        HaCallServiceTool::new(client, policy(autonomy), vec!["light.*".into()])
+    }
+
+    #[test]
+    fn service_allowlist_matches_exact_and_domain_wildcards() {
+        let allowed = vec!["light.*".to_string(), "lock.lock".to_string()];
+        assert!(service_allowed(&allowed, "light", "turn_on"));
+        assert!(service_allowed(&allowed, "lock", "lock"));
+        assert!(!service_allowed(&allowed, "lock", "unlock"));
+        assert!(!service_allowed(&allowed, "switch", "turn_on"));
+        assert!(!service_allowed(&[], "light", "turn_on"));
+    }
+
+    #[test]
+    fn entity_ids_are_validated() {
+        assert!(is_valid_entity_id("light.kitchen_2"));
+        assert!(!is_valid_entity_id("light"));
+        assert!(!is_valid_entity_id("light.../config"));
+        assert!(!is_valid_entity_id("Light.Kitchen"));
+    }
+
+    #[test]
+    fn websocket_url_follows_scheme() {
+        let c = HomeAssistantClient::new("https://ha.example.com/", "t", 5);
+        assert_eq!(c.websocket_url(), "wss://ha.example.com/api/websocket");
+        let c = HomeAssistantClient::new("http://10.0.0.5:8123", "t", 5);
+        assert_eq!(c.websocket_url(), "ws://10.0.0.5:8123/api/websocket");
+    }
+
+    #[test]
+    fn tools_require_enabled_config_and_token() {
+        let security = policy(AutonomyLevel::Full);
+        let mut config = HomeAssistantConfig::default();
+        assert!(home_assistant_tools(&config, &security).is_empty());
+
+        config.enabled = true;
+        assert!(home_assistant_tools(&config, &security).is_empty());
+
+        config.token = Some("abc".into());
+        let names: Vec<String> = home_assistant_tools(&config, &security)
+            .iter()
+            .map(|t| t.name().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            ["ha_list_entities", "ha_get_state", "ha_call_service"]
+        );
+    }
+
+    #[tokio::test]
+    async fn list_entities_filters_by_domain() {
+        let (client, _) = spawn_fake_home_assistant().await;
+        let tool = HaListEntitiesTool::new(client);
+
+        let all = tool.execute(json!({})).await.unwrap();
+        assert!(all.success);
+        assert!(all.output.contains("light.kitchen: off (Kitchen)"));
+        assert!(all.output.contains("lock.front_door: locked"));
+
+        let lights = tool.execute(json!({"domain": "light"})).await.unwrap();
+        assert_eq!(lights.output, "light.kitchen: off (Kitchen)");
+    }
+
+    #[tokio::test]
+    async fn get_state_returns_entity_json() {
+        let (client, _) = spawn_fake_home_assistant().await;
+        let tool = HaGetStateTool::new(client);
+
+        let result = tool
+            .execute(json!({"entity_id": "sensor.outside_temperature"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("12.5"));
+
+        let missing = tool
+            .execute(json!({"entity_id": "sensor.nope"}))
+            .await
+            .unwrap();
+        assert!(!missing.success);
+        assert!(missing.error.unwrap().contains("404"));
+    }
+
+    #[tokio::test]
+    async fn call_service_posts_entity_and_data() {
+        let (client, calls) = spawn_fake_home_assistant().await;
+        let tool = call_tool(client, AutonomyLevel::Full);
+
+        let result = tool
+            .execute(json!({
+                "domain": "light",
+                "service": "turn_on",
+                "entity_id": "light.kitchen",
+                "data": {"brightness_pct": 40}
+            }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.output.contains("light.kitchen: on"));
+
+        let calls = calls.lock();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "light.turn_on");
+        assert_eq!(calls[0].1["entity_id"], "light.kitchen");
+        assert_eq!(calls[0].1["brightness_pct"], 40);
+    }
+
+    #[tokio::test]
+    async fn call_service_rejects_services_outside_allowlist() {
+        let (client, calls) = spawn_fake_home_assistant().await;
+        let tool = call_tool(client, AutonomyLevel::Full);
+
+        let result = tool
+            .execute(json!({"domain": "lock", "service": "unlock", "entity_id": "lock.front_door"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("allowed_services"));
+        assert!(calls.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn call_service_respects_autonomy() {
+        let (client, calls) = spawn_fake_home_assistant().await;
+        let args = json!({"domain": "light", "service": "turn_off", "entity_id": "light.kitchen"});
+
+        let readonly = call_tool(client.clone(), AutonomyLevel::ReadOnly);
+        let result = readonly.execute(args.clone()).await.unwrap();
+        assert!(result.error.unwrap().contains("read-only"));
+
+        let supervised = call_tool(client, AutonomyLevel::Supervised);
+        let result = supervised.execute(args.clone()).await.unwrap();
+        assert!(result.error.unwrap().contains("requires explicit approval"));
+        assert!(calls.lock().is_empty());
+
+        let mut approved = args;
+        approved["approved"] = json!(true);
+        let result = supervised.execute(approved).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(calls.lock().len(), 1);
+    }
+}
@@ -128,6 +128,10 @@ impl Tool for ImageInfoTool {
         "Read image file metadata (format, dimensions, size) and optionally return base64-encoded data."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn parameters_schema(&self) -> serde_json::Value {
         json!({
             "type": "object",
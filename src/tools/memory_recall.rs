@@ -26,6 +26,10 @@ impl Tool for MemoryRecallTool {
         "Search long-term memory for relevant facts, preferences, or context. Returns scored results ranked by relevance."
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     fn parameters_schema(&self) -> serde_json::Value {
         json!({
             "type": "object",
@@ -8,6 +8,7 @@ pub mod git_operations;
 pub mod hardware_board_info;
 pub mod hardware_memory_map;
 pub mod hardware_memory_read;
+pub mod home_assistant;
 pub mod http_request;
 pub mod image_info;
 pub mod memory_forget;
@@ -17,6 +18,7 @@ pub mod schedule;
 pub mod screenshot;
 pub mod shell;
 pub mod traits;
+pub mod validation;
 
 pub use browser::{BrowserTool, ComputerUseConfig};
 pub use browser_open::BrowserOpenTool;
@@ -39,6 +41,7 @@ pub use shell::ShellTool;
 pub use traits::Tool;
 #[allow(unused_imports)]
 pub use traits::{ToolResult, ToolSpec};
+pub use validation::execute_validated;
 
 use crate::config::DelegateAgentConfig;
 use crate::memory::Memory;
@@ -158,6 +161,11 @@ pub fn all_tools_with_runtime(
         )));
     }
 
+    tools.extend(home_assistant::home_assistant_tools(
+        &config.home_assistant,
+        security,
+    ));
+
     // Vision tools are always available
     tools.push(Box::new(ScreenshotTool::new(security.clone())));
     tools.push(Box::new(ImageInfoTool::new(security.clone())));
@@ -29,6 +29,12 @@ pub trait Tool: Send + Sync {
     /// JSON schema for parameters
     fn parameters_schema(&self) -> serde_json::Value;
 
+    /// Whether the tool only observes and never changes files, devices or
+    /// other state
+    fn read_only(&self) -> bool {
+        false
+    }
+
     /// Execute the tool with given arguments
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult>;
 
@@ -0,0 +1,145 @@
+//! Central argument checking for tool calls.
+//!
+//! Dispatchers call [`execute_validated`] instead of [`Tool::execute`], so a
+//! malformed call from the model comes back as one consistent, actionable
+//! error in the same turn instead of failing somewhere inside the tool.
+
+use super::traits::{Tool, ToolResult};
+use crate::json_schema::{self, SchemaError};
+use crate::util::truncate_with_ellipsis;
+use serde_json::Value;
+use std::fmt;
+
+/// Longest parameter schema quoted back to the model.
+const MAX_SCHEMA_CHARS: usize = 2_000;
+
+/// Arguments that do not match a tool's `parameters_schema()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgumentError {
+    pub tool: String,
+    pub errors: Vec<SchemaError>,
+    pub schema: Value,
+}
+
+impl fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid arguments for `{}`:", self.tool)?;
+        for error in &self.errors {
+            writeln!(f, "- {error}")?;
+        }
+        write!(
+            f,
+            "Call `{}` again with arguments matching its parameters: {}",
+            self.tool,
+            truncate_with_ellipsis(&self.schema.to_string(), MAX_SCHEMA_CHARS)
+        )
+    }
+}
+
+impl std::error::Error for ArgumentError {}
+
+/// Coerce obvious type slips in `arguments`, then validate them against the
+/// tool's schema. Returns the arguments to execute with.
+pub fn validate_arguments(tool: &dyn Tool, mut arguments: Value) -> Result<Value, ArgumentError> {
+    let schema = tool.parameters_schema();
+    json_schema::coerce(&schema, &mut arguments);
+    match json_schema::validate(&schema, &arguments) {
+        Ok(()) => Ok(arguments),
+        Err(errors) => Err(ArgumentError {
+            tool: tool.name().to_string(),
+            errors,
+            schema,
+        }),
+    }
+}
+
+/// Validate `arguments` and run the tool with the coerced result. Invalid
+/// arguments become a failed [`ToolResult`] carrying the [`ArgumentError`]
+/// text; the tool itself is not called.
+pub async fn execute_validated(tool: &dyn Tool, arguments: Value) -> anyhow::Result<ToolResult> {
+    match validate_arguments(tool, arguments) {
+        Ok(arguments) => tool.execute(arguments).await,
+        Err(e) => Ok(ToolResult {
+            success: false,
+            output: String::new(),
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    struct ReadTool;
+
+    #[async_trait]
+    impl Tool for ReadTool {
+        fn name(&self) -> &str {
+            "read"
+        }
+
+        fn description(&self) -> &str {
+            "Read lines from a file"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string"},
+                    "lines": {"type": "integer", "minimum": 1}
+                },
+                "required": ["path"]
+            })
+        }
+
+        async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+            Ok(ToolResult {
+                success: true,
+                output: args.to_string(),
+                error: None,
+            })
+        }
+    }
+
+    #[test]
+    fn coerced_arguments_pass_through() {
+        let args = validate_arguments(&ReadTool, json!({"path": "a.txt", "lines": "10"})).unwrap();
+        assert_eq!(args, json!({"path": "a.txt", "lines": 10}));
+    }
+
+    #[test]
+    fn invalid_arguments_explain_what_to_fix() {
+        let err = validate_arguments(&ReadTool, json!({"lines": 0})).unwrap_err();
+        assert_eq!(err.tool, "read");
+        assert_eq!(err.errors.len(), 2);
+        let message = err.to_string();
+        assert!(message.starts_with("Invalid arguments for `read`:\n"));
+        assert!(message.contains("- missing required property `path`\n"));
+        assert!(message.contains("- /lines: must be >= 1\n"));
+        assert!(message.contains("\"required\":[\"path\"]"));
+    }
+
+    #[tokio::test]
+    async fn execute_validated_runs_tool_with_coerced_arguments() {
+        let result = execute_validated(&ReadTool, json!({"path": "a.txt", "lines": "3"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, r#"{"lines":3,"path":"a.txt"}"#);
+    }
+
+    #[tokio::test]
+    async fn execute_validated_reports_invalid_arguments_without_running() {
+        let result = execute_validated(&ReadTool, json!({})).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.is_empty());
+        assert!(result
+            .error
+            .unwrap()
+            .starts_with("Invalid arguments for `read`:"));
+    }
+}